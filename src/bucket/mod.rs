@@ -1,8 +1,30 @@
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
 use crate::config::Config;
 
+/// File whose presence in a bucket's folder marks it archived
+const ARCHIVED_MARKER: &str = "archived";
+
+/// Description and icon, kept next to the bucket's database
+const META_FILE: &str = "bucket.toml";
+
+/// Names that mean the default library (no bucket) in listings and totals
+const RESERVED_NAMES: [&str; 2] = ["default", "none"];
+
+/// Icon for buckets that weren't given one
+pub const DEFAULT_ICON: &str = "📕";
+
+/// Optional shelf details for a bucket, e.g. "📘 os-class — CS4414 Fall"
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BucketMeta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+}
+
 /// Represents a knowledge bucket (isolated dataset)
 #[derive(Debug, Clone)]
 pub struct Bucket {
@@ -19,6 +41,15 @@ impl Bucket {
     /// Create a new bucket
     pub fn create(name: &str) -> Result<Self> {
         let name = Self::sanitize_name(name);
+        if name.is_empty() {
+            anyhow::bail!("Bucket names need at least one letter or digit");
+        }
+        if RESERVED_NAMES.contains(&name.as_str()) {
+            anyhow::bail!(
+                "'{}' is reserved for the default library; pick another name",
+                name
+            );
+        }
         let path = Self::buckets_dir()?.join(&name);
 
         if path.exists() {
@@ -68,6 +99,57 @@ impl Bucket {
         Ok(buckets)
     }
 
+    /// Buckets that aren't archived, for the shelf, pickers and operations
+    /// across buckets
+    pub fn list_active() -> Result<Vec<String>> {
+        let mut buckets = Self::list_all()?;
+        buckets.retain(|name| Self::open(name).is_ok_and(|b| !b.is_archived()));
+        Ok(buckets)
+    }
+
+    /// Whether the bucket was archived (e.g. for a finished class)
+    pub fn is_archived(&self) -> bool {
+        self.path.join(ARCHIVED_MARKER).exists()
+    }
+
+    /// Archive the bucket, hiding it without touching its data, or bring it back
+    pub fn set_archived(&self, archived: bool) -> Result<()> {
+        let marker = self.path.join(ARCHIVED_MARKER);
+        if archived {
+            std::fs::write(&marker, chrono::Utc::now().to_rfc3339())
+        } else if marker.exists() {
+            std::fs::remove_file(&marker)
+        } else {
+            Ok(())
+        }
+        .with_context(|| format!("Failed to update bucket '{}'", self.name))
+    }
+
+    /// The bucket's description and icon (empty if never set or unreadable)
+    pub fn meta(&self) -> BucketMeta {
+        std::fs::read_to_string(self.path.join(META_FILE))
+            .ok()
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the bucket's description and icon
+    pub fn set_meta(&self, meta: &BucketMeta) -> Result<()> {
+        let content = toml::to_string_pretty(meta).context("Failed to serialize bucket details")?;
+        std::fs::write(self.path.join(META_FILE), content)
+            .with_context(|| format!("Failed to update bucket '{}'", self.name))
+    }
+
+    /// Icon and name, plus the description when there is one
+    pub fn label(&self) -> String {
+        let meta = self.meta();
+        let icon = meta.icon.as_deref().unwrap_or(DEFAULT_ICON);
+        match meta.description {
+            Some(description) => format!("{} {} — {}", icon, self.name, description),
+            None => format!("{} {}", icon, self.name),
+        }
+    }
+
     /// Delete a bucket
     pub fn delete(name: &str) -> Result<()> {
         let bucket = Self::open(name)?;
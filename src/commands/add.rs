@@ -1,14 +1,82 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use colored::Colorize;
+use futures_util::StreamExt;
 use indicatif::{ProgressBar, ProgressStyle};
 use inquire::{Select, Text};
-use std::path::Path;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
 
+use super::sync::{self, Refresh};
+use crate::config::Config;
 use crate::embeddings;
-use crate::ingest::{self, ChunkConfig, ContentType, chunk_text};
-use crate::storage::{ChunkStore, Database, DocumentStore};
+use crate::ingest::canvas::{CanvasClient, CanvasDocument};
+use crate::ingest::{
+    self, Chunk, ChunkConfig, ContentType, CrawlOptions, ExtractedContent, chunk_text,
+};
+use crate::ingest::{arxiv, vocabulary};
+use crate::llm::whisper::TranscribeOptions;
+use crate::storage::{ChunkStore, Citation, CitationStore, Database, DocumentStore};
+
+/// Default pause between videos when adding a whole channel
+pub const DEFAULT_CHANNEL_DELAY_SECS: u64 = 5;
+
+/// Stop crawling after this many pages
+const MAX_CRAWL_PAGES: usize = 100;
+
+/// Upper bound on directory files extracted and embedded at once
+const MAX_PARALLEL_FILES: usize = 8;
+
+/// Source argument that pulls the configured Canvas course
+const CANVAS_SOURCE: &str = "canvas";
+
+/// Recent documents whose vocabulary is put in transcription prompts
+const VOCABULARY_DOCUMENTS: usize = 20;
+
+/// Most terms a transcription prompt lists
+const VOCABULARY_TERMS: usize = 40;
+
+pub async fn run(
+    path: Option<String>,
+    filter: Option<String>,
+    delay_secs: u64,
+    crawl: Option<CrawlOptions>,
+    hint: Option<String>,
+    tags: Vec<String>,
+) -> Result<()> {
+    // However the source was added, the new documents get the --tag tags and
+    // count towards today's study activity
+    let added = add_source(path, filter, delay_secs, crawl, hint).await?;
+    tag_documents(&added, &tags)?;
+    crate::storage::activity::record(crate::storage::activity::DOCUMENT, added.len() as i64);
+    Ok(())
+}
+
+/// Add `tags` to each of the documents
+fn tag_documents(ids: &[i64], tags: &[String]) -> Result<()> {
+    if tags.is_empty() || ids.is_empty() {
+        return Ok(());
+    }
+    let db = Database::open()?;
+    let doc_store = DocumentStore::new(&db);
+    for &id in ids {
+        doc_store.edit_tags(id, tags, &[])?;
+    }
+    println!(
+        "{} Tagged {} document(s) with {}",
+        "✓".green(),
+        ids.len(),
+        tags.join(", ").cyan()
+    );
+    Ok(())
+}
 
-pub async fn run(path: Option<String>) -> Result<()> {
+async fn add_source(
+    path: Option<String>,
+    filter: Option<String>,
+    delay_secs: u64,
+    crawl: Option<CrawlOptions>,
+    hint: Option<String>,
+) -> Result<Vec<i64>> {
     let source = match path {
         Some(p) => p,
         None => prompt_for_source()?,
@@ -16,9 +84,25 @@ pub async fn run(path: Option<String>) -> Result<()> {
 
     println!("\n{} {}", "Processing:".dimmed(), source);
 
+    // `librarian add canvas` pulls the configured course (unless a file is called that)
+    if source == CANVAS_SOURCE && !Path::new(&source).exists() {
+        return process_canvas().await;
+    }
+
+    if let Some(id) = arxiv::parse_id(&source) {
+        return Ok(process_arxiv(&id).await?.into_iter().collect());
+    }
+
+    if ingest::is_youtube_channel_url(&source) {
+        return process_channel(&source, filter.as_deref(), delay_secs).await;
+    }
+
     // Check if it's a URL
     if source.starts_with("http://") || source.starts_with("https://") {
-        return process_url(&source).await;
+        if let Some(options) = crawl {
+            return process_crawl(&source, options, delay_secs).await;
+        }
+        return Ok(process_url(&source).await?.into_iter().collect());
     }
 
     let path = Path::new(&source);
@@ -35,19 +119,62 @@ pub async fn run(path: Option<String>) -> Result<()> {
     // Initialize chunks table
     chunk_store.init_schema()?;
 
+    if (path.is_dir() || ContentType::from_path(path).is_media())
+        && let Err(e) = set_transcription_prompt(&doc_store, hint.as_deref())
+    {
+        eprintln!(
+            "{} Could not build a vocabulary prompt: {}",
+            "Note:".yellow(),
+            e
+        );
+    }
+
     if path.is_dir() {
-        process_directory(path, &doc_store, &chunk_store).await?;
+        process_directory(path, &doc_store, &chunk_store).await
     } else {
-        process_file(path, &doc_store, &chunk_store).await?;
+        let added = process_file(path, &doc_store, &chunk_store, OnDuplicate::Ask).await?;
+        Ok(added.into_iter().collect())
     }
+}
+
+/// Prompt Whisper with the course (the hint, else the bucket's name) and the
+/// jargon of the bucket's recent documents, so lectures spell it right
+fn set_transcription_prompt(doc_store: &DocumentStore, hint: Option<&str>) -> Result<()> {
+    let course = match hint {
+        Some(hint) => Some(hint.to_string()),
+        None => crate::bucket::get_current_bucket()?.map(|b| b.name),
+    };
 
+    // Earlier transcripts may carry the very misspellings this is meant to fix
+    let documents = doc_store.list()?;
+    let texts: Vec<&str> = documents
+        .iter()
+        .filter(|d| !matches!(d.content_type.as_str(), "audio" | "video" | "youtube"))
+        .take(VOCABULARY_DOCUMENTS)
+        .map(|d| d.content.as_str())
+        .collect();
+    let terms = vocabulary::key_terms(&texts, VOCABULARY_TERMS);
+
+    if let Some(prompt) = vocabulary::transcription_prompt(course.as_deref(), &terms) {
+        TranscribeOptions::set_prompt(prompt);
+    }
     Ok(())
 }
 
 fn prompt_for_source() -> Result<String> {
-    let options = vec!["File", "Directory", "URL/Website", "YouTube Video"];
+    let options = vec![
+        "File",
+        "Directory",
+        "URL/Website",
+        "YouTube Video",
+        "arXiv Paper",
+        "Canvas Course",
+    ];
 
     let source_type = Select::new("What would you like to add?", options).prompt()?;
+    if source_type == "Canvas Course" {
+        return Ok(CANVAS_SOURCE.to_string());
+    }
 
     let (prompt_text, help_text) = match source_type {
         "File" => ("Enter file path:", "You can use tab for path completion"),
@@ -56,7 +183,11 @@ fn prompt_for_source() -> Result<String> {
             "You can use tab for path completion",
         ),
         "URL/Website" => ("Enter URL:", "https://example.com/article"),
-        "YouTube Video" => ("Enter YouTube URL:", "https://youtube.com/watch?v=..."),
+        "YouTube Video" => (
+            "Enter YouTube URL:",
+            "https://youtube.com/watch?v=... or a channel/playlist URL",
+        ),
+        "arXiv Paper" => ("Enter arXiv ID:", "arxiv:2301.12345 or an arxiv.org link"),
         _ => unreachable!(),
     };
 
@@ -75,6 +206,11 @@ fn content_type_str(ct: &ContentType) -> &'static str {
         ContentType::Audio => "audio",
         ContentType::Video => "video",
         ContentType::Image => "image",
+        ContentType::Epub => "epub",
+        ContentType::Docx => "docx",
+        ContentType::Csv => "csv",
+        ContentType::Xlsx => "xlsx",
+        ContentType::Code(language) => language,
         ContentType::Url => "url",
         ContentType::Unknown => "unknown",
     }
@@ -95,7 +231,7 @@ fn create_spinner(message: &str) -> ProgressBar {
 }
 
 /// Create a progress bar for determinate progress
-fn create_progress_bar(total: u64, message: &str) -> ProgressBar {
+pub(crate) fn create_progress_bar(total: u64, message: &str) -> ProgressBar {
     let pb = ProgressBar::new(total);
     pb.set_style(
         ProgressStyle::default_bar()
@@ -107,22 +243,92 @@ fn create_progress_bar(total: u64, message: &str) -> ProgressBar {
     pb
 }
 
-async fn process_file(
+/// What to do when a file's content is already in the bucket under another path
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum OnDuplicate {
+    /// Ask whether to skip, link, or add a separate copy
+    Ask,
+    /// Link the new path to the existing document without re-indexing
+    Link,
+}
+
+pub(crate) async fn process_file(
     path: &Path,
     doc_store: &DocumentStore<'_>,
     chunk_store: &ChunkStore<'_>,
-) -> Result<()> {
+    on_duplicate: OnDuplicate,
+) -> Result<Option<i64>> {
     let abs_path = tokio::fs::canonicalize(path).await?;
     let source_path = abs_path.to_string_lossy().to_string();
+    let hash = ingest::file_hash(path)?;
+
+    if let Some(existing) = doc_store.find_by_path(&source_path)? {
+        // Linked duplicate that is still identical
+        if existing.source_path != source_path && existing.content_hash.as_deref() == Some(&hash) {
+            println!(
+                "{} Already linked to [{}] {}",
+                "⚠".yellow(),
+                existing.id,
+                existing.filename
+            );
+            return Ok(None);
+        }
 
-    // Check if already exists
-    if doc_store.exists_by_path(&source_path)? {
-        println!(
-            "{} Document already exists: {}",
-            "⚠".yellow(),
-            path.display()
-        );
-        return Ok(());
+        // Already added: re-ingest only if the file changed since
+        if existing.source_path == source_path {
+            match sync::refresh_file(path, &existing, doc_store, chunk_store).await? {
+                Refresh::Unchanged => println!(
+                    "{} Document already exists (unchanged): {}",
+                    "⚠".yellow(),
+                    path.display()
+                ),
+                Refresh::Updated(plan) => println!(
+                    "{} Updated {} {}",
+                    "✓".green(),
+                    existing.filename,
+                    sync::describe_plan(&plan).dimmed()
+                ),
+            }
+            return Ok(None);
+        }
+    }
+
+    // Same file under a different path
+    if let Some(original) = doc_store.find_by_hash(&hash)? {
+        let action = match on_duplicate {
+            OnDuplicate::Link => "Link",
+            OnDuplicate::Ask => {
+                println!(
+                    "{} This file is identical to [{}] {} ({})",
+                    "⚠".yellow(),
+                    original.id,
+                    original.filename.bold(),
+                    original.source_path.dimmed()
+                );
+                Select::new(
+                    "What would you like to do?",
+                    vec!["Skip", "Link", "Add a separate copy"],
+                )
+                .with_help_message("Link remembers this path without indexing the file twice")
+                .prompt()?
+            }
+        };
+
+        match action {
+            "Skip" => return Ok(None),
+            "Link" => {
+                doc_store.add_alias(original.id, &source_path)?;
+                println!(
+                    "{} Linked {} to [{}] {}",
+                    "✓".green(),
+                    path.display(),
+                    original.id,
+                    original.filename
+                );
+                return Ok(None);
+            }
+            _ => {}
+        }
     }
 
     // Check if this is a media file that needs transcription
@@ -143,45 +349,29 @@ async fn process_file(
         .map(|f| f.to_string_lossy().to_string())
         .unwrap_or_else(|| "unknown".to_string());
 
-    // Insert document into database
-    let doc_id = doc_store.insert(
-        &source_path,
-        &filename,
-        content_type_str(&content.content_type),
-        &content.text,
-        None,
-    )?;
-
-    // Chunk the document
-    let config = ChunkConfig::default();
-    let chunks = chunk_text(&content.text, &config);
-    let num_chunks = chunks.len();
-
-    // Progress bar for embedding
-    let pb = create_progress_bar(num_chunks as u64, "Embedding chunks");
-
-    // Generate embeddings and store chunks
-    for chunk in &chunks {
-        // Generate embedding
-        let embedding = embeddings::embed_text(&chunk.text).ok();
-
-        chunk_store.insert(
-            doc_id,
-            chunk.index as i64,
-            &chunk.text,
-            embedding.as_deref(),
-        )?;
-
-        pb.inc(1);
-    }
-
+    // Insert the document, then chunk and embed it
+    let document = NewDocument {
+        source: &source_path,
+        title: &filename,
+        content_type: content_type_str(&content.content_type),
+        text: &content.text,
+        tags: None,
+        hash: Some(hash),
+        chunks: content.chunks(&ChunkConfig::current()),
+    };
+    let pb = create_progress_bar(0, "Embedding chunks");
+    let (doc_id, num_chunks) = store_document(doc_store, chunk_store, &document, Some(&pb))?;
     pb.finish_and_clear();
 
     let preview_len = content.text.len().min(200);
     let preview = &content.text[..preview_len];
 
     println!("{}", "─".repeat(50).dimmed());
-    println!("{} {:?}", "Type:".bold(), content.content_type);
+    println!(
+        "{} {}",
+        "Type:".bold(),
+        content_type_str(&content.content_type)
+    );
     println!("{} {} chars", "Length:".bold(), content.text.len());
     println!("{} {}", "Chunks:".bold(), num_chunks);
     println!("{} {}", "ID:".bold(), doc_id);
@@ -201,14 +391,51 @@ async fn process_file(
         num_chunks
     );
 
-    Ok(())
+    Ok(Some(doc_id))
+}
+
+/// Add a file that has already been extracted (e.g. attached in chat) to the
+/// bucket, unless it's there already. Returns the new document's id and chunk count.
+pub(crate) async fn add_extracted(
+    path: &Path,
+    content: &ExtractedContent,
+    doc_store: &DocumentStore<'_>,
+    chunk_store: &ChunkStore<'_>,
+) -> Result<Option<(i64, usize)>> {
+    let abs_path = tokio::fs::canonicalize(path).await?;
+    let source_path = abs_path.to_string_lossy().to_string();
+    let hash = ingest::file_hash(path)?;
+    if doc_store.find_by_path(&source_path)?.is_some() || doc_store.find_by_hash(&hash)?.is_some() {
+        return Ok(None);
+    }
+
+    let filename = path
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let doc_id = doc_store.insert(
+        &source_path,
+        &filename,
+        content_type_str(&content.content_type),
+        &content.text,
+        None,
+    )?;
+    doc_store.set_content_hash(doc_id, &hash)?;
+
+    let chunks = content.chunks(&ChunkConfig::current());
+    let embeddings = embed_chunks(chunk_store, &chunks).await?;
+    for (chunk, embedding) in chunks.iter().zip(&embeddings) {
+        chunk_store.insert_chunk(doc_id, chunk, embedding.as_deref())?;
+    }
+
+    Ok(Some((doc_id, chunks.len())))
 }
 
 async fn process_directory(
     path: &Path,
     doc_store: &DocumentStore<'_>,
     chunk_store: &ChunkStore<'_>,
-) -> Result<()> {
+) -> Result<Vec<i64>> {
     // First, collect all files to get total count
     let mut files = Vec::new();
     let mut entries = tokio::fs::read_dir(path).await?;
@@ -222,7 +449,7 @@ async fn process_directory(
 
     if files.is_empty() {
         println!("{} No files found in directory", "⚠".yellow());
-        return Ok(());
+        return Ok(Vec::new());
     }
 
     let total_files = files.len();
@@ -233,71 +460,144 @@ async fn process_directory(
     let mut count = 0;
     let mut errors = 0;
     let mut skipped = 0;
+    let mut updated = 0;
     let mut total_chunks = 0;
+    let mut added = Vec::new();
+    // Tagged with the file's position so the report keeps directory order
     #[allow(clippy::type_complexity)]
-    let mut results: Vec<(String, Result<(usize, usize), String>)> = Vec::new();
+    let mut results: Vec<(usize, String, Result<(usize, usize), String>)> = Vec::new();
+
+    // Database checks run up front; only genuinely new files go to the pipeline
+    let mut new_files: Vec<NewFile> = Vec::new();
+    let mut pending_hashes: HashSet<String> = HashSet::new();
+    let mut in_batch_duplicates: Vec<NewFile> = Vec::new();
 
-    for file_path in files {
+    for (position, file_path) in files.into_iter().enumerate() {
         let abs_path = tokio::fs::canonicalize(&file_path).await?;
         let source_path = abs_path.to_string_lossy().to_string();
 
-        let filename_display = file_path
+        let filename = file_path
             .file_name()
             .map(|f| f.to_string_lossy().to_string())
             .unwrap_or_else(|| "unknown".to_string());
 
-        pb.set_message(format!("Processing: {}", filename_display));
+        pb.set_message(format!("Checking: {}", filename));
+
+        // Already added: re-ingest only if the file changed since
+        if let Some(existing) = doc_store.find_by_path(&source_path)? {
+            match sync::refresh_file(&file_path, &existing, doc_store, chunk_store).await {
+                Ok(Refresh::Unchanged) => {
+                    results.push((position, filename, Err("already exists".to_string())));
+                    skipped += 1;
+                }
+                Ok(Refresh::Updated(plan)) => {
+                    results.push((
+                        position,
+                        filename,
+                        Err(format!("updated {}", sync::describe_plan(&plan))),
+                    ));
+                    updated += 1;
+                }
+                Err(e) => {
+                    results.push((position, filename, Err(e.to_string())));
+                    errors += 1;
+                }
+            }
+            pb.inc(1);
+            continue;
+        }
 
-        // Check if already exists
-        if doc_store.exists_by_path(&source_path)? {
-            results.push((filename_display, Err("already exists".to_string())));
+        let hash = ingest::file_hash(&file_path).ok();
+
+        // Same file already indexed from another path: link instead of duplicating
+        if let Some(hash) = &hash
+            && let Some(original) = doc_store.find_by_hash(hash)?
+        {
+            doc_store.add_alias(original.id, &source_path)?;
+            results.push((
+                position,
+                filename,
+                Err(format!("duplicate of [{}], linked", original.id)),
+            ));
             skipped += 1;
             pb.inc(1);
             continue;
         }
 
-        match ingest::extract_from_file_async(&file_path).await {
-            Ok(content) => {
-                let filename = file_path
-                    .file_name()
-                    .map(|f| f.to_string_lossy().to_string())
-                    .unwrap_or_else(|| "unknown".to_string());
-
-                match doc_store.insert(
-                    &source_path,
-                    &filename,
-                    content_type_str(&content.content_type),
-                    &content.text,
-                    None,
-                ) {
-                    Ok(doc_id) => {
-                        // Chunk and embed
-                        let config = ChunkConfig::default();
-                        let chunks = chunk_text(&content.text, &config);
-                        let num_chunks = chunks.len();
-
-                        for chunk in &chunks {
-                            let embedding = embeddings::embed_text(&chunk.text).ok();
-                            let _ = chunk_store.insert(
-                                doc_id,
-                                chunk.index as i64,
-                                &chunk.text,
-                                embedding.as_deref(),
-                            );
-                        }
-
-                        results.push((filename, Ok((content.text.len(), num_chunks))));
-                        count += 1;
-                        total_chunks += num_chunks;
-                    }
-                    Err(e) => {
-                        results.push((filename_display, Err(format!("db error: {}", e))));
-                        errors += 1;
-                    }
+        let file = NewFile {
+            position,
+            path: file_path,
+            source_path,
+            filename,
+            hash,
+        };
+
+        // A copy of another file in this batch is linked once that one is stored
+        match &file.hash {
+            Some(hash) if !pending_hashes.insert(hash.clone()) => in_batch_duplicates.push(file),
+            _ => new_files.push(file),
+        }
+    }
+
+    // Extract and chunk several files at once, embedding and storing each as it finishes
+    let config = ChunkConfig::current();
+    let mut prepared = futures_util::stream::iter(new_files)
+        .map(|file| {
+            let config = config.clone();
+            tokio::spawn(async move {
+                let prepared = prepare_file(&file.path, &config).await;
+                (file, prepared)
+            })
+        })
+        .buffer_unordered(parallel_files());
+
+    pb.set_message("Processing files");
+
+    while let Some(joined) = prepared.next().await {
+        let (file, prepared) = joined.context("File processing task failed")?;
+
+        let (content, chunks) = match prepared {
+            Ok(prepared) => prepared,
+            Err(e) => {
+                results.push((file.position, file.filename, Err(e.to_string())));
+                errors += 1;
+                pb.inc(1);
+                continue;
+            }
+        };
+
+        match doc_store.insert(
+            &file.source_path,
+            &file.filename,
+            content_type_str(&content.content_type),
+            &content.text,
+            None,
+        ) {
+            Ok(doc_id) => {
+                if let Some(hash) = &file.hash {
+                    let _ = doc_store.set_content_hash(doc_id, hash);
+                }
+
+                let vectors = embed_chunks(chunk_store, &chunks).await?;
+                for (chunk, embedding) in chunks.iter().zip(&vectors) {
+                    let _ = chunk_store.insert_chunk(doc_id, chunk, embedding.as_deref());
                 }
+
+                results.push((
+                    file.position,
+                    file.filename,
+                    Ok((content.text.len(), chunks.len())),
+                ));
+                added.push(doc_id);
+                count += 1;
+                total_chunks += chunks.len();
             }
             Err(e) => {
-                results.push((filename_display, Err(e.to_string())));
+                results.push((
+                    file.position,
+                    file.filename,
+                    Err(format!("db error: {}", e)),
+                ));
                 errors += 1;
             }
         }
@@ -305,13 +605,42 @@ async fn process_directory(
         pb.inc(1);
     }
 
+    for file in in_batch_duplicates {
+        let original = match &file.hash {
+            Some(hash) => doc_store.find_by_hash(hash)?,
+            None => None,
+        };
+        match original {
+            Some(original) => {
+                doc_store.add_alias(original.id, &file.source_path)?;
+                results.push((
+                    file.position,
+                    file.filename,
+                    Err(format!("duplicate of [{}], linked", original.id)),
+                ));
+                skipped += 1;
+            }
+            None => {
+                // The copy it matched failed to import
+                results.push((
+                    file.position,
+                    file.filename,
+                    Err("duplicate of a file that failed".to_string()),
+                ));
+                errors += 1;
+            }
+        }
+        pb.inc(1);
+    }
+
     pb.finish_and_clear();
+    results.sort_by_key(|(position, _, _)| *position);
 
     // Print results
     println!("\n{}", "Results:".bold());
     println!("{}", "─".repeat(60).dimmed());
 
-    for (filename, result) in results {
+    for (_, filename, result) in results {
         match result {
             Ok((chars, chunks)) => {
                 println!(
@@ -322,9 +651,12 @@ async fn process_directory(
                     chunks
                 );
             }
-            Err(ref e) if e == "already exists" => {
+            Err(ref e) if e == "already exists" || e.starts_with("duplicate") => {
                 println!("  {} {} ({})", "⊘".yellow(), filename, e);
             }
+            Err(ref e) if e.starts_with("updated") => {
+                println!("  {} {} ({})", "↻".cyan(), filename, e);
+            }
             Err(e) => {
                 println!("  {} {} ({})", "✗".red(), filename, e);
             }
@@ -333,18 +665,86 @@ async fn process_directory(
 
     println!("{}", "─".repeat(60).dimmed());
     println!(
-        "\n{} {} added ({} chunks), {} skipped, {} errors",
+        "\n{} {} added ({} chunks), {} updated, {} skipped, {} errors",
         "Summary:".bold(),
         count,
         total_chunks,
+        updated,
         skipped,
         errors
     );
 
-    Ok(())
+    Ok(added)
+}
+
+/// A directory file with nothing stored for it yet
+struct NewFile {
+    position: usize,
+    path: PathBuf,
+    source_path: String,
+    filename: String,
+    hash: Option<String>,
+}
+
+/// How many directory files to extract and embed at the same time
+fn parallel_files() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+        .min(MAX_PARALLEL_FILES)
+}
+
+/// Extract and chunk a file (runs on its own task, alongside other files)
+async fn prepare_file(path: &Path, config: &ChunkConfig) -> Result<(ExtractedContent, Vec<Chunk>)> {
+    let content = ingest::extract_from_file_async(path).await?;
+    let chunks = content.chunks(config);
+    Ok((content, chunks))
+}
+
+/// Embed a file's chunks, taking what the embedding cache has and computing
+/// the rest in one batch on a blocking thread (so other files keep extracting).
+/// Chunks whose embedding fails are stored without one, as before.
+async fn embed_chunks(
+    chunk_store: &ChunkStore<'_>,
+    chunks: &[Chunk],
+) -> Result<Vec<Option<Vec<f32>>>> {
+    let cache = chunk_store.embedding_cache();
+    let texts: Vec<&str> = chunks.iter().map(|c| c.text.as_str()).collect();
+    let mut vectors = cache.lookup(&texts)?;
+
+    let missing: Vec<String> = texts
+        .iter()
+        .zip(&vectors)
+        .filter(|(_, hit)| hit.is_none())
+        .map(|(text, _)| text.to_string())
+        .collect();
+    if missing.is_empty() {
+        return Ok(vectors);
+    }
+
+    let computed = tokio::task::spawn_blocking(move || {
+        let refs: Vec<&str> = missing.iter().map(String::as_str).collect();
+        embeddings::embed_texts(&refs)
+    })
+    .await
+    .context("Embedding task failed")?;
+
+    if let Ok(computed) = computed {
+        let mut computed = computed.into_iter();
+        for (text, slot) in texts.iter().zip(vectors.iter_mut()) {
+            if slot.is_none()
+                && let Some(embedding) = computed.next()
+            {
+                cache.store(text, &embedding)?;
+                *slot = Some(embedding);
+            }
+        }
+    }
+
+    Ok(vectors)
 }
 
-async fn process_url(url: &str) -> Result<()> {
+async fn process_url(url: &str) -> Result<Option<i64>> {
     // Open database
     let db = Database::open()?;
     let doc_store = DocumentStore::new(&db);
@@ -356,13 +756,10 @@ async fn process_url(url: &str) -> Result<()> {
     // Check if already exists
     if doc_store.exists_by_path(url)? {
         println!("{} URL already exists in database: {}", "⚠".yellow(), url);
-        return Ok(());
+        return Ok(None);
     }
 
-    // Determine if it's a YouTube URL for display
-    let is_youtube = url.contains("youtube.com") || url.contains("youtu.be");
-
-    let spinner = if is_youtube {
+    let spinner = if url_content_type(url) == "youtube" {
         create_spinner("Fetching YouTube transcript...")
     } else {
         create_spinner("Fetching and parsing URL...")
@@ -372,28 +769,10 @@ async fn process_url(url: &str) -> Result<()> {
     let content = ingest::fetch_url(url).await?;
     spinner.finish_and_clear();
 
-    // Insert document
-    let content_type = if is_youtube { "youtube" } else { "url" };
-    let doc_id = doc_store.insert(url, &content.title, content_type, &content.text, None)?;
-
-    // Chunk and embed
-    let config = ChunkConfig::default();
-    let chunks = chunk_text(&content.text, &config);
-    let num_chunks = chunks.len();
-
-    let pb = create_progress_bar(num_chunks as u64, "Embedding chunks");
-
-    for chunk in &chunks {
-        let embedding = embeddings::embed_text(&chunk.text).ok();
-        chunk_store.insert(
-            doc_id,
-            chunk.index as i64,
-            &chunk.text,
-            embedding.as_deref(),
-        )?;
-        pb.inc(1);
-    }
-
+    // Insert, chunk and embed
+    let content_type = url_content_type(url);
+    let pb = create_progress_bar(0, "Embedding chunks");
+    let (doc_id, num_chunks) = store_url(&doc_store, &chunk_store, url, &content, Some(&pb))?;
     pb.finish_and_clear();
 
     let preview_len = content.text.len().min(200);
@@ -421,5 +800,706 @@ async fn process_url(url: &str) -> Result<()> {
         num_chunks
     );
 
+    Ok(Some(doc_id))
+}
+
+/// Content type for a document added from a URL
+fn url_content_type(url: &str) -> &'static str {
+    if url.contains("youtube.com") || url.contains("youtu.be") {
+        "youtube"
+    } else {
+        "url"
+    }
+}
+
+/// Insert a fetched page (or YouTube transcript), then chunk and embed it.
+/// Returns the document id and chunk count.
+pub(crate) fn store_url(
+    doc_store: &DocumentStore,
+    chunk_store: &ChunkStore,
+    url: &str,
+    content: &ingest::url::UrlContent,
+    progress: Option<&ProgressBar>,
+) -> Result<(i64, usize)> {
+    let document = NewDocument {
+        source: url,
+        title: &content.title,
+        content_type: url_content_type(url),
+        text: &content.text,
+        tags: None,
+        hash: None,
+        chunks: content.chunks(&ChunkConfig::current()),
+    };
+    store_document(doc_store, chunk_store, &document, progress)
+}
+
+/// Crawl from a starting page, following links up to `options.depth` hops
+/// and adding each page as its own document. Pages already in the bucket are
+/// still followed, so a repeated crawl picks up newly linked pages.
+async fn process_crawl(start: &str, options: CrawlOptions, delay_secs: u64) -> Result<Vec<i64>> {
+    let db = Database::open()?;
+    let doc_store = DocumentStore::new(&db);
+    let chunk_store = ChunkStore::new(&db);
+
+    chunk_store.init_schema()?;
+
+    let mut robots = HashMap::new();
+    let mut queue = VecDeque::from([(start.to_string(), 0)]);
+    let mut seen = HashSet::from([start.to_string()]);
+
+    let (mut fetched, mut existing, mut blocked, mut errors) = (0, 0, 0, 0);
+    let mut added = Vec::new();
+
+    while let Some((url, depth)) = queue.pop_front() {
+        if fetched >= MAX_CRAWL_PAGES {
+            println!(
+                "{} Stopped after {} pages ({} still queued)",
+                "⚠".yellow(),
+                MAX_CRAWL_PAGES,
+                queue.len() + 1
+            );
+            break;
+        }
+
+        let host = url::Url::parse(&url)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_string))
+            .unwrap_or_default();
+        if !robots.contains_key(&host) {
+            robots.insert(host.clone(), ingest::fetch_robots(&url).await);
+        }
+        if !robots[&host].allows(&url) {
+            println!("  {} {} {}", "⊘".dimmed(), url, "(robots.txt)".dimmed());
+            blocked += 1;
+            continue;
+        }
+
+        // Be polite to the server between requests
+        if fetched > 0 && delay_secs > 0 {
+            tokio::time::sleep(std::time::Duration::from_secs(delay_secs)).await;
+        }
+        fetched += 1;
+
+        let spinner = create_spinner(&format!("[depth {}] {}", depth, url));
+        let result = ingest::fetch_page(&url).await;
+        spinner.finish_and_clear();
+
+        let page = match result {
+            Ok(page) => page,
+            Err(e) => {
+                println!("  {} {} {}", "✗".red(), url, format!("({})", e).dimmed());
+                errors += 1;
+                continue;
+            }
+        };
+
+        if depth < options.depth {
+            for link in page.links {
+                if options.same_domain && !ingest::same_host(start, &link) {
+                    continue;
+                }
+                if seen.insert(link.clone()) {
+                    queue.push_back((link, depth + 1));
+                }
+            }
+        }
+
+        if doc_store.exists_by_path(&url)? {
+            existing += 1;
+            continue;
+        }
+
+        // Always a web page here, even on YouTube - crawling reads the HTML
+        let content = page.content;
+        let document = NewDocument {
+            source: &url,
+            title: &content.title,
+            content_type: "url",
+            text: &content.text,
+            tags: None,
+            hash: None,
+            chunks: content.chunks(&ChunkConfig::current()),
+        };
+        let (doc_id, num_chunks) = store_document(&doc_store, &chunk_store, &document, None)?;
+
+        println!(
+            "  {} {} {}",
+            "✓".green(),
+            content.title,
+            format!("(id: {}, {} chunks)", doc_id, num_chunks).dimmed()
+        );
+        added.push(doc_id);
+    }
+
+    println!(
+        "\n{} Added {} pages ({} already added, {} blocked by robots.txt, {} failed)",
+        "✓".green(),
+        added.len(),
+        existing,
+        blocked,
+        errors
+    );
+
+    Ok(added)
+}
+
+/// Add every video in a YouTube channel or playlist, skipping videos that
+/// are already in the bucket (so an interrupted run can simply be repeated)
+async fn process_channel(url: &str, filter: Option<&str>, delay_secs: u64) -> Result<Vec<i64>> {
+    let db = Database::open()?;
+    let doc_store = DocumentStore::new(&db);
+    let chunk_store = ChunkStore::new(&db);
+
+    chunk_store.init_schema()?;
+
+    let spinner = create_spinner("Listing channel videos...");
+    let videos = ingest::list_youtube_channel(url, filter).await?;
+    spinner.finish_and_clear();
+
+    let existing: HashSet<String> = doc_store
+        .list()?
+        .iter()
+        .filter(|d| d.content_type == "youtube")
+        .filter_map(|d| ingest::youtube_video_id(&d.source_path))
+        .collect();
+
+    let pending: Vec<_> = videos
+        .iter()
+        .filter(|v| !existing.contains(&v.id))
+        .collect();
+
+    println!(
+        "Found {} videos{} ({} already added)",
+        videos.len(),
+        filter
+            .map(|f| format!(" matching \"{}\"", f))
+            .unwrap_or_default(),
+        videos.len() - pending.len()
+    );
+
+    if pending.is_empty() {
+        println!("{} Nothing new to add.", "✓".green());
+        return Ok(Vec::new());
+    }
+
+    let mut added = Vec::new();
+    let mut errors = 0;
+
+    for (i, video) in pending.iter().enumerate() {
+        // Be polite to YouTube between requests
+        if i > 0 && delay_secs > 0 {
+            tokio::time::sleep(std::time::Duration::from_secs(delay_secs)).await;
+        }
+
+        let spinner = create_spinner(&format!("[{}/{}] {}", i + 1, pending.len(), video.title));
+
+        let video_url = video.url();
+        let result = ingest::fetch_url(&video_url).await;
+        spinner.finish_and_clear();
+
+        let content = match result {
+            Ok(c) => c,
+            Err(e) => {
+                println!(
+                    "  {} {} {}",
+                    "✗".red(),
+                    video.title,
+                    format!("({})", e).dimmed()
+                );
+                errors += 1;
+                continue;
+            }
+        };
+
+        let (doc_id, num_chunks) = store_url(&doc_store, &chunk_store, &video_url, &content, None)?;
+
+        println!(
+            "  {} {} {}",
+            "✓".green(),
+            content.title,
+            format!("(id: {}, {} chunks)", doc_id, num_chunks).dimmed()
+        );
+        added.push(doc_id);
+    }
+
+    println!(
+        "\n{} Added {} videos ({} failed)",
+        "✓".green(),
+        added.len(),
+        errors
+    );
+
+    Ok(added)
+}
+
+/// Pull announcements, pages and files from the Canvas course in the config.
+/// Anything already in the bucket is skipped, so re-running only fetches new items.
+async fn process_canvas() -> Result<Vec<i64>> {
+    let config = Config::load()?;
+    let Some(canvas) = config
+        .canvas
+        .as_ref()
+        .filter(|c| !c.base_url.is_empty() && !c.token.is_empty() && !c.course_id.is_empty())
+    else {
+        anyhow::bail!(
+            "Canvas isn't set up. Add your Canvas URL, access token and course ID \
+             under `librarian config` → Canvas."
+        );
+    };
+
+    let client = CanvasClient::new(canvas)?;
+    let db = Database::open()?;
+    let doc_store = DocumentStore::new(&db);
+    let chunk_store = ChunkStore::new(&db);
+
+    chunk_store.init_schema()?;
+
+    let (mut skipped, mut errors) = (0, 0);
+    let mut added = Vec::new();
+
+    println!("{}", "Announcements".bold());
+    let spinner = create_spinner("Listing announcements...");
+    let announcements = client.announcements().await;
+    spinner.finish_and_clear();
+    match announcements {
+        Ok(announcements) => {
+            for doc in &announcements {
+                if doc.text.is_empty() || doc_store.exists_by_path(&doc.source)? {
+                    skipped += 1;
+                    continue;
+                }
+                added.push(add_canvas_document(
+                    &doc_store,
+                    &chunk_store,
+                    doc,
+                    "announcement",
+                )?);
+            }
+        }
+        Err(e) => {
+            println!("  {} {}", "✗".red(), e);
+            errors += 1;
+        }
+    }
+
+    println!("{}", "Pages".bold());
+    let spinner = create_spinner("Listing pages...");
+    let pages = client.pages().await;
+    spinner.finish_and_clear();
+    match pages {
+        Ok(pages) => {
+            for page in &pages {
+                if doc_store.exists_by_path(&page.html_url)? {
+                    skipped += 1;
+                    continue;
+                }
+                match client.page(page).await {
+                    Ok(doc) if doc.text.is_empty() => skipped += 1,
+                    Ok(doc) => {
+                        added.push(add_canvas_document(&doc_store, &chunk_store, &doc, "page")?);
+                    }
+                    Err(e) => {
+                        println!(
+                            "  {} {} {}",
+                            "✗".red(),
+                            page.title,
+                            format!("({})", e).dimmed()
+                        );
+                        errors += 1;
+                    }
+                }
+            }
+        }
+        Err(e) => {
+            println!("  {} {}", "✗".red(), e);
+            errors += 1;
+        }
+    }
+
+    println!("{}", "Files".bold());
+    let spinner = create_spinner("Listing files...");
+    let files = client.files().await;
+    spinner.finish_and_clear();
+    match files {
+        Ok(files) => {
+            let dir = Config::data_dir()?.join("canvas").join(&client.course_id);
+            for file in &files {
+                let source = client.file_source(file);
+                let content_type = ContentType::from_path(Path::new(&file.display_name));
+                if file.locked_for_user
+                    || matches!(content_type, ContentType::Unknown)
+                    || doc_store.exists_by_path(&source)?
+                {
+                    skipped += 1;
+                    continue;
+                }
+
+                let spinner = create_spinner(&format!("Downloading {}", file.display_name));
+                let result = async {
+                    let path = client.download(file, &dir).await?;
+                    let content = ingest::extract_from_file_async(&path).await?;
+                    Ok::<_, anyhow::Error>((path, content))
+                }
+                .await;
+                spinner.finish_and_clear();
+
+                let (path, content) = match result {
+                    Ok(r) => r,
+                    Err(e) => {
+                        println!(
+                            "  {} {} {}",
+                            "✗".red(),
+                            file.display_name,
+                            format!("({})", e).dimmed()
+                        );
+                        errors += 1;
+                        continue;
+                    }
+                };
+
+                let document = NewDocument {
+                    source: &source,
+                    title: &file.display_name,
+                    content_type: content_type_str(&content.content_type),
+                    text: &content.text,
+                    tags: None,
+                    hash: ingest::file_hash(&path).ok(),
+                    chunks: content.chunks(&ChunkConfig::current()),
+                };
+                let (doc_id, num_chunks) =
+                    store_document(&doc_store, &chunk_store, &document, None)?;
+
+                println!(
+                    "  {} {} {}",
+                    "✓".green(),
+                    file.display_name,
+                    format!("(id: {}, {} chunks)", doc_id, num_chunks).dimmed()
+                );
+                added.push(doc_id);
+            }
+        }
+        Err(e) => {
+            println!("  {} {}", "✗".red(), e);
+            errors += 1;
+        }
+    }
+
+    println!(
+        "\n{} Added {} items from Canvas course {} ({} already added or skipped, {} failed)",
+        "✓".green(),
+        added.len(),
+        client.course_id,
+        skipped,
+        errors
+    );
+
+    Ok(added)
+}
+
+/// Store an announcement or page and embed its chunks, returning its id
+fn add_canvas_document(
+    doc_store: &DocumentStore,
+    chunk_store: &ChunkStore,
+    doc: &CanvasDocument,
+    content_type: &str,
+) -> Result<i64> {
+    let (doc_id, num_chunks) = store_text(
+        doc_store,
+        chunk_store,
+        &doc.source,
+        &doc.title,
+        content_type,
+        &doc.text,
+        None,
+    )?;
+
+    println!(
+        "  {} {} {}",
+        "✓".green(),
+        doc.title,
+        format!("(id: {}, {} chunks)", doc_id, num_chunks).dimmed()
+    );
+
+    Ok(doc_id)
+}
+
+/// Insert a plain-text document, then chunk and embed it. Returns the
+/// document id and chunk count.
+fn store_text(
+    doc_store: &DocumentStore,
+    chunk_store: &ChunkStore,
+    source: &str,
+    title: &str,
+    content_type: &str,
+    text: &str,
+    progress: Option<&ProgressBar>,
+) -> Result<(i64, usize)> {
+    let document = NewDocument {
+        source,
+        title,
+        content_type,
+        text,
+        tags: None,
+        hash: None,
+        chunks: chunk_text(text, &ChunkConfig::current()),
+    };
+    store_document(doc_store, chunk_store, &document, progress)
+}
+
+/// A document to store along with the chunks its text was split into
+struct NewDocument<'a> {
+    source: &'a str,
+    title: &'a str,
+    content_type: &'a str,
+    text: &'a str,
+    /// Comma-separated tags
+    tags: Option<&'a str>,
+    /// Hash of the source file, so `sync` can tell when it changes
+    hash: Option<String>,
+    chunks: Vec<Chunk>,
+}
+
+/// Insert a document, then embed and insert its chunks one at a time (the
+/// sources added a document at a time share this). Returns the document id
+/// and chunk count.
+fn store_document(
+    doc_store: &DocumentStore,
+    chunk_store: &ChunkStore,
+    document: &NewDocument,
+    progress: Option<&ProgressBar>,
+) -> Result<(i64, usize)> {
+    let doc_id = doc_store.insert(
+        document.source,
+        document.title,
+        document.content_type,
+        document.text,
+        document.tags,
+    )?;
+    if let Some(hash) = &document.hash {
+        doc_store.set_content_hash(doc_id, hash)?;
+    }
+
+    if let Some(pb) = progress {
+        pb.set_length(document.chunks.len() as u64);
+    }
+    for chunk in &document.chunks {
+        let embedding = chunk_store.embed_text(&chunk.text).ok();
+        chunk_store.insert_chunk(doc_id, chunk, embedding.as_deref())?;
+        if let Some(pb) = progress {
+            pb.inc(1);
+        }
+    }
+
+    Ok((doc_id, document.chunks.len()))
+}
+
+/// Add text piped in on stdin as a document, e.g.
+/// `pbpaste | librarian add --stdin --name "lecture 5 notes"`
+pub async fn run_stdin(name: Option<String>, tags: Vec<String>) -> Result<()> {
+    use std::io::{IsTerminal, Read};
+
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        anyhow::bail!(
+            "Nothing piped in. Try: cat notes.txt | librarian add --stdin --name \"notes\""
+        );
+    }
+
+    let mut text = String::new();
+    stdin
+        .read_to_string(&mut text)
+        .map_err(|e| anyhow::anyhow!("Failed to read stdin as UTF-8 text: {}", e))?;
+    let text = text.trim();
+    if text.is_empty() {
+        anyhow::bail!("No text received on stdin");
+    }
+
+    let name = name
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| format!("stdin {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S")));
+    let source = format!("stdin:{}", name);
+
+    let db = Database::open()?;
+    let doc_store = DocumentStore::new(&db);
+    let chunk_store = ChunkStore::new(&db);
+
+    chunk_store.init_schema()?;
+
+    if doc_store.exists_by_path(&source)? {
+        anyhow::bail!(
+            "A document named \"{}\" was already added from stdin; pick another --name",
+            name
+        );
+    }
+
+    let pb = create_progress_bar(0, "Embedding chunks");
+    let (doc_id, num_chunks) = store_text(
+        &doc_store,
+        &chunk_store,
+        &source,
+        &name,
+        "text",
+        text,
+        Some(&pb),
+    )?;
+    pb.finish_and_clear();
+
+    println!(
+        "{} Added \"{}\" (id: {}, {} chars, {} chunks)",
+        "✓".green(),
+        name,
+        doc_id,
+        text.len(),
+        num_chunks
+    );
+    tag_documents(&[doc_id], &tags)?;
+    crate::storage::activity::record(crate::storage::activity::DOCUMENT, 1);
+
     Ok(())
 }
+
+/// Add an arXiv paper: download its PDF (falling back to the abstract), tag it
+/// with its title and authors, and store its citation
+async fn process_arxiv(id: &str) -> Result<Option<i64>> {
+    let db = Database::open()?;
+    let doc_store = DocumentStore::new(&db);
+    let chunk_store = ChunkStore::new(&db);
+
+    chunk_store.init_schema()?;
+
+    let spinner = create_spinner("Looking up paper on arXiv...");
+    let paper = arxiv::fetch_metadata(id).await;
+    spinner.finish_and_clear();
+    let paper = paper?;
+
+    if doc_store.exists_by_path(&paper.abs_url())? {
+        println!(
+            "{} Paper already exists in database: {}",
+            "⚠".yellow(),
+            paper.title
+        );
+        return Ok(None);
+    }
+
+    let spinner = create_spinner("Downloading PDF...");
+    let dir = Config::data_dir()?.join("arxiv");
+    let result = async {
+        let path = arxiv::download_pdf(&paper, &dir).await?;
+        let content = ingest::extract_from_file_async(&path).await?;
+        if content.text.trim().is_empty() {
+            anyhow::bail!("no text in PDF");
+        }
+        Ok::<_, anyhow::Error>((path, content))
+    }
+    .await;
+    spinner.finish_and_clear();
+
+    let (content_type, text, chunks, hash) = match result {
+        Ok((path, content)) => (
+            "pdf",
+            content.text.clone(),
+            content.chunks(&ChunkConfig::current()),
+            ingest::file_hash(&path).ok(),
+        ),
+        Err(e) => {
+            println!(
+                "{} Couldn't read the PDF ({}); adding the abstract instead",
+                "⚠".yellow(),
+                e
+            );
+            let text = paper.abstract_text();
+            let chunks = chunk_text(&text, &ChunkConfig::current());
+            ("arxiv", text, chunks, None)
+        }
+    };
+
+    // Tags are comma-separated, so commas inside names are dropped
+    let tags = std::iter::once(format!("title:{}", paper.title))
+        .chain(paper.authors.iter().map(|a| format!("author:{}", a)))
+        .map(|tag| tag.replace(',', ""))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let document = NewDocument {
+        source: &paper.abs_url(),
+        title: &paper.title,
+        content_type,
+        text: &text,
+        tags: Some(&tags),
+        hash,
+        chunks,
+    };
+    let pb = create_progress_bar(0, "Embedding chunks");
+    let (doc_id, num_chunks) = store_document(&doc_store, &chunk_store, &document, Some(&pb))?;
+    pb.finish_and_clear();
+
+    CitationStore::new(&db).upsert(&Citation {
+        document_id: doc_id,
+        entry_type: if paper.journal_ref.is_some() {
+            "article"
+        } else {
+            "misc"
+        }
+        .to_string(),
+        title: paper.title.clone(),
+        authors: paper.authors.clone(),
+        year: paper.year,
+        venue: paper.journal_ref.clone(),
+        doi: paper.doi.clone(),
+        url: Some(paper.abs_url()),
+        arxiv_id: Some(paper.id.clone()),
+    })?;
+
+    println!("{}", "─".repeat(50).dimmed());
+    println!("{} {}", "Title:".bold(), paper.title);
+    println!("{} {}", "Authors:".bold(), paper.authors.join(", "));
+    println!("{} {}", "Chunks:".bold(), num_chunks);
+    println!("{} {}", "ID:".bold(), doc_id);
+    println!("{}", "─".repeat(50).dimmed());
+
+    println!(
+        "\n{} Added arXiv:{} \"{}\" (id: {}, {} chunks)",
+        "✓".green(),
+        paper.id,
+        paper.title,
+        doc_id,
+        num_chunks
+    );
+
+    Ok(Some(doc_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_document() {
+        embeddings::use_mock_backend();
+        let db = Database::open_in_memory().unwrap();
+        let docs = DocumentStore::new(&db);
+        let chunks = ChunkStore::new(&db);
+        chunks.init_schema().unwrap();
+
+        let text = "Attention replaces recurrence. ".repeat(80);
+        let document = NewDocument {
+            source: "https://arxiv.org/abs/1706.03762",
+            title: "Attention Is All You Need",
+            content_type: "arxiv",
+            text: &text,
+            tags: Some("author:Ashish Vaswani"),
+            hash: Some("abc123".to_string()),
+            chunks: chunk_text(&text, &ChunkConfig::current()),
+        };
+        let (id, num_chunks) = store_document(&docs, &chunks, &document, None).unwrap();
+
+        let stored = docs.get(id).unwrap().unwrap();
+        assert_eq!(stored.tags.as_deref(), Some("author:Ashish Vaswani"));
+        assert_eq!(stored.content_hash.as_deref(), Some("abc123"));
+        let stored_chunks = chunks.get_for_document(id).unwrap();
+        assert!(num_chunks > 1);
+        assert_eq!(stored_chunks.len(), num_chunks);
+        assert!(stored_chunks.iter().all(|c| c.embedding.is_some()));
+    }
+}
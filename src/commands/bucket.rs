@@ -1,9 +1,14 @@
 use anyhow::Result;
+use chrono::{DateTime, Local, Utc};
 use colored::Colorize;
 use inquire::{Select, Text};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
 
-use crate::bucket::{self, Bucket};
-use crate::storage::{Database, DocumentStore};
+use crate::bucket::{self, Bucket, BucketMeta};
+use crate::storage::{ChunkStore, ConversationStore, Database, DocumentStore, StudyStore};
 
 /// Interactive bucket management
 pub async fn run() -> Result<()> {
@@ -36,6 +41,9 @@ pub async fn run() -> Result<()> {
         "📖  Create new book     │ Start a new study collection",
         "🔄  Switch book         │ Change active collection",
         "📋  List all books      │ See your library",
+        "📊  Book stats          │ Documents, study items and activity",
+        "🗄️   Archive book        │ Put away a finished class",
+        "📤  Unarchive book      │ Bring an archived class back",
         "🗑️   Delete book         │ Remove a collection",
         "📭  Use no book         │ Switch to default storage",
         "←   Back",
@@ -73,6 +81,27 @@ pub async fn run() -> Result<()> {
                     eprintln!("{} {}", "Error:".red(), e);
                 }
             }
+            s if s.contains("Book stats") => {
+                if let Err(e) = bucket_stats().await
+                    && !e.to_string().contains("cancelled")
+                {
+                    eprintln!("{} {}", "Error:".red(), e);
+                }
+            }
+            s if s.contains("Unarchive book") => {
+                if let Err(e) = unarchive(None).await
+                    && !e.to_string().contains("cancelled")
+                {
+                    eprintln!("{} {}", "Error:".red(), e);
+                }
+            }
+            s if s.contains("Archive book") => {
+                if let Err(e) = archive(None).await
+                    && !e.to_string().contains("cancelled")
+                {
+                    eprintln!("{} {}", "Error:".red(), e);
+                }
+            }
             s if s.contains("Delete book") => {
                 if let Err(e) = delete_bucket().await
                     && !e.to_string().contains("cancelled")
@@ -97,8 +126,16 @@ pub async fn run() -> Result<()> {
     Ok(())
 }
 
-/// Create a new bucket
-pub async fn create(name: Option<String>) -> Result<()> {
+/// Icons offered when creating a bucket interactively
+const BOOK_ICONS: [&str; 10] = ["📕", "📘", "📗", "📙", "📓", "🧪", "💻", "🧮", "🌍", "🎨"];
+
+/// Create a new bucket, with an optional description and icon for the shelf
+pub async fn create(
+    name: Option<String>,
+    description: Option<String>,
+    icon: Option<String>,
+) -> Result<()> {
+    let interactive = name.is_none();
     let name = match name {
         Some(n) => n,
         None => Text::new("Bucket name:")
@@ -115,6 +152,25 @@ pub async fn create(name: Option<String>) -> Result<()> {
         Ok(bucket) => {
             println!("{} Created bucket '{}'", "✓".green(), bucket.name);
 
+            let mut meta = BucketMeta {
+                description: non_empty(description),
+                icon: non_empty(icon),
+            };
+            if interactive && meta.description.is_none() {
+                meta.description = non_empty(Some(
+                    Text::new("Description (optional):")
+                        .with_help_message("Shown on the shelf, e.g., CS4414 Fall")
+                        .prompt()?,
+                ));
+            }
+            if interactive && meta.icon.is_none() {
+                let icon = Select::new("Icon:", BOOK_ICONS.to_vec()).prompt()?;
+                meta.icon = Some(icon.to_string());
+            }
+            if meta != BucketMeta::default() {
+                bucket.set_meta(&meta)?;
+            }
+
             // Ask if they want to switch to it
             let switch = Select::new(
                 "Switch to this bucket now?",
@@ -135,6 +191,12 @@ pub async fn create(name: Option<String>) -> Result<()> {
     Ok(())
 }
 
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
 /// List all buckets
 pub async fn list() -> Result<()> {
     let buckets = Bucket::list_all()?;
@@ -167,8 +229,33 @@ pub async fn list() -> Result<()> {
         let db = Database::open_for_bucket(&bucket)?;
         let store = DocumentStore::new(&db);
         let count = store.count()?;
-
-        println!("{}{}{}  ({} documents)", marker, name.bold(), suffix, count);
+        let meta = bucket.meta();
+        let icon = meta.icon.as_deref().unwrap_or(bucket::DEFAULT_ICON);
+        let description = meta
+            .description
+            .map(|d| format!("  — {}", d).dimmed().to_string())
+            .unwrap_or_default();
+
+        if bucket.is_archived() {
+            println!(
+                "{}{} {}  {}{}",
+                marker,
+                icon,
+                name.dimmed(),
+                format!("({} documents, archived)", count).dimmed(),
+                description
+            );
+        } else {
+            println!(
+                "{}{} {}{}  ({} documents){}",
+                marker,
+                icon,
+                name.bold(),
+                suffix,
+                count,
+                description
+            );
+        }
     }
 
     Ok(())
@@ -179,7 +266,7 @@ pub async fn switch(name: Option<String>) -> Result<()> {
     let name = match name {
         Some(n) => n,
         None => {
-            let buckets = Bucket::list_all()?;
+            let buckets = Bucket::list_active()?;
 
             if buckets.is_empty() {
                 println!("{}", "No buckets found. Create one first.".dimmed());
@@ -194,6 +281,15 @@ pub async fn switch(name: Option<String>) -> Result<()> {
         println!("{} Bucket '{}' does not exist", "✗".red(), name);
         return Ok(());
     }
+    if Bucket::open(&name)?.is_archived() {
+        println!(
+            "{} Bucket '{}' is archived. Bring it back with {}",
+            "⊘".yellow(),
+            name,
+            format!("librarian bucket unarchive {}", name).cyan()
+        );
+        return Ok(());
+    }
 
     bucket::set_current_bucket(Some(&name))?;
     println!("{} Now using bucket '{}'", "✓".green(), name);
@@ -201,6 +297,289 @@ pub async fn switch(name: Option<String>) -> Result<()> {
     Ok(())
 }
 
+/// Archive a bucket: it keeps its data but leaves the shelf, the bucket
+/// pickers and library-wide operations
+pub async fn archive(name: Option<String>) -> Result<()> {
+    let name = match name {
+        Some(name) => name,
+        None => {
+            let buckets = Bucket::list_active()?;
+            if buckets.is_empty() {
+                println!("{}", "No buckets to archive.".dimmed());
+                return Ok(());
+            }
+            Select::new("Select bucket to archive:", buckets).prompt()?
+        }
+    };
+
+    let bucket = Bucket::open(&name)?;
+    if bucket.is_archived() {
+        println!("{} '{}' is already archived", "ℹ".blue(), bucket.name);
+        return Ok(());
+    }
+    bucket.set_archived(true)?;
+
+    let current = bucket::get_current_bucket()?;
+    if current.is_some_and(|b| b.name == bucket.name) {
+        bucket::set_current_bucket(None)?;
+        println!("{} Now using default (no bucket)", "ℹ".blue());
+    }
+    println!(
+        "{} Archived '{}'. Its documents and study items are kept; {} brings it back",
+        "✓".green(),
+        bucket.name,
+        format!("librarian bucket unarchive {}", bucket.name).cyan()
+    );
+
+    Ok(())
+}
+
+/// Bring an archived bucket back
+pub async fn unarchive(name: Option<String>) -> Result<()> {
+    let name = match name {
+        Some(name) => name,
+        None => {
+            let archived: Vec<String> = Bucket::list_all()?
+                .into_iter()
+                .filter(|name| Bucket::open(name).is_ok_and(|b| b.is_archived()))
+                .collect();
+            if archived.is_empty() {
+                println!("{}", "No archived buckets.".dimmed());
+                return Ok(());
+            }
+            Select::new("Select bucket to unarchive:", archived).prompt()?
+        }
+    };
+
+    let bucket = Bucket::open(&name)?;
+    if !bucket.is_archived() {
+        println!("{} '{}' isn't archived", "ℹ".blue(), bucket.name);
+        return Ok(());
+    }
+    bucket.set_archived(false)?;
+    println!("{} '{}' is back on the shelf", "✓".green(), bucket.name);
+
+    Ok(())
+}
+
+/// Summary numbers for one bucket's database
+pub struct BucketStats {
+    pub documents: i64,
+    pub by_type: Vec<(String, i64)>,
+    pub chunks: i64,
+    pub size_bytes: u64,
+    pub study_items: usize,
+    pub due: i64,
+    pub conversations: usize,
+    pub last_activity: Option<DateTime<Utc>>,
+}
+
+impl BucketStats {
+    pub fn collect(db: &Database) -> Result<Self> {
+        let documents = DocumentStore::new(db);
+        let chunk_store = ChunkStore::new(db);
+        chunk_store.init_schema()?;
+        let study = StudyStore::new(db);
+
+        Ok(Self {
+            documents: documents.count()?,
+            by_type: documents.count_by_type()?,
+            chunks: chunk_store.count()?,
+            size_bytes: db.size_on_disk(),
+            study_items: study.list()?.len(),
+            due: study.count_due(None)?,
+            conversations: ConversationStore::new(db).list()?.len(),
+            last_activity: db.last_activity()?,
+        })
+    }
+}
+
+/// Show what's in a bucket (the current one by default)
+pub async fn stats(name: Option<String>) -> Result<()> {
+    let (label, archived, db) = match name {
+        Some(name) => {
+            if !Bucket::exists(&name)? {
+                println!("{} Bucket '{}' does not exist", "✗".red(), name);
+                return Ok(());
+            }
+            let bucket = Bucket::open(&name)?;
+            let db = Database::open_for_bucket(&bucket)?;
+            (bucket.name.clone(), bucket.is_archived(), db)
+        }
+        None => match bucket::get_current_bucket()? {
+            Some(bucket) => {
+                let db = Database::open_for_bucket(&bucket)?;
+                (bucket.name.clone(), bucket.is_archived(), db)
+            }
+            None => ("default library".to_string(), false, Database::open()?),
+        },
+    };
+    let stats = BucketStats::collect(&db)?;
+
+    println!(
+        "\n{} {}{}\n",
+        "Stats for".bold(),
+        label.cyan(),
+        if archived {
+            " (archived)".dimmed().to_string()
+        } else {
+            String::new()
+        }
+    );
+
+    let types = stats
+        .by_type
+        .iter()
+        .map(|(kind, count)| format!("{} {}", count, kind))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if types.is_empty() {
+        println!("  {:<13} 0", "Documents");
+    } else {
+        println!("  {:<13} {} ({})", "Documents", stats.documents, types);
+    }
+    println!("  {:<13} {}", "Chunks", stats.chunks);
+    println!("  {:<13} {}", "On disk", format_size(stats.size_bytes));
+    println!(
+        "  {:<13} {} ({} due)",
+        "Study items", stats.study_items, stats.due
+    );
+    println!("  {:<13} {}", "Conversations", stats.conversations);
+    match stats.last_activity {
+        Some(at) => println!(
+            "  {:<13} {} ({})",
+            "Last activity",
+            at.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+            days_ago(at)
+        ),
+        None => println!("  {:<13} {}", "Last activity", "never".dimmed()),
+    }
+    println!();
+
+    Ok(())
+}
+
+/// Counts the shelf's roll-up line adds up for one database
+#[derive(Clone, Copy, Default)]
+struct RollupCounts {
+    documents: i64,
+    chunks: i64,
+    due: i64,
+    size_bytes: u64,
+}
+
+/// Roll-up counts already taken, by database file
+struct CachedRollup {
+    counts: RollupCounts,
+    /// The file's modification time and size when counted
+    stamp: (Option<SystemTime>, u64),
+    /// When the due count goes stale as another item comes due
+    next_due: Option<DateTime<Utc>>,
+}
+
+/// The shelf is drawn every time the menu comes back, so counts are kept
+/// until their database changes rather than reopening every bucket each time
+static ROLLUP_CACHE: OnceLock<Mutex<HashMap<PathBuf, CachedRollup>>> = OnceLock::new();
+
+fn file_stamp(path: &Path) -> Option<(Option<SystemTime>, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok(), metadata.len()))
+}
+
+/// Counts for the database at `path`, from the cache while the file and the
+/// due count haven't changed (None when there's no database there)
+fn rollup_counts(path: &Path) -> Option<RollupCounts> {
+    let stamp = file_stamp(path)?;
+    let mut cache = ROLLUP_CACHE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if let Some(cached) = cache.get(path)
+        && cached.stamp == stamp
+        && cached.next_due.is_none_or(|at| at > Utc::now())
+    {
+        return Some(cached.counts);
+    }
+
+    let db = Database::open_at_path(path.to_path_buf()).ok()?;
+    let chunk_store = ChunkStore::new(&db);
+    chunk_store.init_schema().ok()?;
+    let study = StudyStore::new(&db);
+    let counts = RollupCounts {
+        documents: DocumentStore::new(&db).count().ok()?,
+        chunks: chunk_store.count().ok()?,
+        due: study.count_due(None).ok()?,
+        size_bytes: db.size_on_disk(),
+    };
+    let next_due = study.next_due().ok()?;
+    drop(db);
+
+    // Opening can bring an older database's schema up to date, so stamp after
+    cache.insert(
+        path.to_path_buf(),
+        CachedRollup {
+            counts,
+            stamp: file_stamp(path)?,
+            next_due,
+        },
+    );
+    Some(counts)
+}
+
+/// One-line roll-up of every bucket on the shelf plus the default database
+/// (used when no bucket is selected), or None when there's nothing to sum
+pub fn library_rollup(buckets: &[String]) -> Option<String> {
+    if buckets.is_empty() {
+        return None;
+    }
+
+    let paths = buckets
+        .iter()
+        .filter_map(|name| Bucket::open(name).ok().map(|b| b.db_path()))
+        .chain(Database::default_db_path().ok());
+    let mut total = RollupCounts::default();
+    for counts in paths.filter_map(|path| rollup_counts(&path)) {
+        total.documents += counts.documents;
+        total.chunks += counts.chunks;
+        total.due += counts.due;
+        total.size_bytes += counts.size_bytes;
+    }
+
+    Some(format!(
+        "{} book{} · {} documents · {} chunks · {} due · {}",
+        buckets.len(),
+        if buckets.len() == 1 { "" } else { "s" },
+        total.documents,
+        total.chunks,
+        total.due,
+        format_size(total.size_bytes)
+    ))
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+fn days_ago(at: DateTime<Utc>) -> String {
+    let days = (Local::now().date_naive() - at.with_timezone(&Local).date_naive()).num_days();
+    match days {
+        ..=0 => "today".to_string(),
+        1 => "yesterday".to_string(),
+        n => format!("{} days ago", n),
+    }
+}
+
 /// Delete a bucket
 async fn delete_bucket() -> Result<()> {
     let buckets = Bucket::list_all()?;
@@ -247,7 +626,7 @@ async fn delete_bucket() -> Result<()> {
 }
 
 async fn create_bucket() -> Result<()> {
-    create(None).await
+    create(None, None, None).await
 }
 
 async fn switch_bucket() -> Result<()> {
@@ -258,6 +637,15 @@ async fn list_buckets() -> Result<()> {
     list().await
 }
 
+async fn bucket_stats() -> Result<()> {
+    let buckets = Bucket::list_all()?;
+    if buckets.is_empty() {
+        return stats(None).await;
+    }
+    let name = Select::new("Select bucket:", buckets).prompt()?;
+    stats(Some(name)).await
+}
+
 async fn clear_bucket() -> Result<()> {
     bucket::set_current_bucket(None)?;
     println!("{} Now using default (no bucket)", "✓".green());
@@ -288,3 +676,52 @@ pub fn print_bucket_context() {
         Err(_) => {}
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rollup_counts_cached_until_changed() {
+        let dir = std::env::temp_dir().join(format!("librarian_rollup_{}", std::process::id()));
+        let path = dir.join("notes.db");
+        assert!(rollup_counts(&path).is_none());
+
+        let db = Database::open_at_path(path.clone()).unwrap();
+        DocumentStore::new(&db)
+            .insert(
+                "a.txt",
+                "a.txt",
+                "text",
+                "ATP is made in mitochondria",
+                None,
+            )
+            .unwrap();
+        let counts = rollup_counts(&path).unwrap();
+        assert_eq!((counts.documents, counts.chunks, counts.due), (1, 0, 0));
+
+        // Left alone, the database isn't counted again
+        ROLLUP_CACHE
+            .get()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .get_mut(&path)
+            .unwrap()
+            .counts
+            .documents = 7;
+        assert_eq!(rollup_counts(&path).unwrap().documents, 7);
+
+        // A change to the file is
+        let stamp = file_stamp(&path);
+        while file_stamp(&path) == stamp {
+            DocumentStore::new(&db)
+                .insert("b.txt", "b.txt", "text", &"glycolysis ".repeat(500), None)
+                .unwrap();
+        }
+        assert!(rollup_counts(&path).unwrap().documents >= 2);
+
+        drop(db);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,302 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::export::StudyItemExport;
+use crate::storage::study::normalize_tags;
+use crate::storage::{Database, DocumentStore, StudyStore};
+
+/// File format for study item backups
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum CardsFormat {
+    /// One row per item, openable in a spreadsheet
+    Csv,
+    /// A JSON array of items
+    Json,
+}
+
+/// A study item with its review schedule, as written to and read from
+/// backups. Documents are referred to by filename, since IDs differ between
+/// buckets and machines.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CardRecord {
+    item_type: String,
+    front: String,
+    back: String,
+    #[serde(default)]
+    tags: Option<String>,
+    #[serde(default)]
+    document: Option<String>,
+    next_review: DateTime<Utc>,
+    interval_days: f64,
+    ease_factor: f64,
+    review_count: i64,
+}
+
+/// The study items of a `librarian export library` JSON file, bucket by
+/// bucket
+#[derive(Deserialize)]
+struct LibraryFile {
+    buckets: Vec<LibraryBucket>,
+}
+
+#[derive(Deserialize)]
+struct LibraryBucket {
+    documents: Vec<LibraryDocument>,
+    study_items: Vec<StudyItemExport>,
+}
+
+#[derive(Deserialize)]
+struct LibraryDocument {
+    id: i64,
+    title: String,
+}
+
+impl LibraryFile {
+    /// Every bucket's items, with document IDs turned back into filenames
+    fn into_records(self) -> Vec<CardRecord> {
+        let mut records = Vec::new();
+        for bucket in self.buckets {
+            let titles: HashMap<i64, String> = bucket
+                .documents
+                .into_iter()
+                .map(|d| (d.id, d.title))
+                .collect();
+            records.extend(bucket.study_items.into_iter().map(|item| CardRecord {
+                document: item.document_id.and_then(|id| titles.get(&id).cloned()),
+                item_type: item.item_type,
+                front: item.front,
+                back: item.back,
+                tags: (!item.tags.is_empty()).then(|| item.tags.join(",")),
+                next_review: item.next_review,
+                interval_days: item.interval_days,
+                ease_factor: item.ease_factor,
+                review_count: item.review_count,
+            }));
+        }
+        records
+    }
+}
+
+/// Write the current bucket's study items, with their schedules, to a CSV
+/// or JSON file (or stdout with `-o -`)
+pub async fn export(
+    format: CardsFormat,
+    output: Option<PathBuf>,
+    tag: Option<String>,
+) -> Result<()> {
+    let db = Database::open()?;
+    let store = StudyStore::new(&db);
+    let filenames: HashMap<i64, String> = DocumentStore::new(&db)
+        .list()?
+        .into_iter()
+        .map(|d| (d.id, d.filename))
+        .collect();
+
+    let items = match &tag {
+        Some(tag) => store.list_tagged(tag)?,
+        None => store.list()?,
+    };
+    if items.is_empty() {
+        println!("{}", "No study items to export.".dimmed());
+        return Ok(());
+    }
+
+    let records: Vec<CardRecord> = items
+        .into_iter()
+        .map(|item| CardRecord {
+            document: item.document_id.and_then(|id| filenames.get(&id).cloned()),
+            item_type: item.item_type,
+            front: item.front,
+            back: item.back,
+            tags: item.tags,
+            next_review: item.next_review_date,
+            interval_days: item.interval_days,
+            ease_factor: item.ease_factor,
+            review_count: item.review_count,
+        })
+        .collect();
+
+    let (text, extension) = match format {
+        CardsFormat::Csv => (write_csv(&records)?, "csv"),
+        CardsFormat::Json => (serde_json::to_string_pretty(&records)?, "json"),
+    };
+
+    if output.as_deref() == Some(Path::new("-")) {
+        print!("{}", text);
+        return Ok(());
+    }
+
+    let path = output.unwrap_or_else(|| {
+        PathBuf::from(format!(
+            "librarian-cards-{}.{}",
+            Utc::now().format("%Y%m%d-%H%M%S"),
+            extension
+        ))
+    });
+    std::fs::write(&path, text).with_context(|| format!("Failed to write {}", path.display()))?;
+    println!(
+        "{} Exported {} study items to {}",
+        "✓".green(),
+        records.len(),
+        path.display().to_string().cyan()
+    );
+
+    Ok(())
+}
+
+/// Load study items from a `cards export` file (or the JSON of `export
+/// library`) into the current bucket, keeping their schedules and tags.
+/// Items already present are left alone.
+pub async fn import(path: &Path) -> Result<()> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let is_json = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("json"));
+    let records = if is_json {
+        read_json(&text)?
+    } else {
+        read_csv(&text)?
+    };
+
+    let db = Database::open()?;
+    let store = StudyStore::new(&db);
+    let documents: HashMap<String, i64> = DocumentStore::new(&db)
+        .list()?
+        .into_iter()
+        .map(|d| (d.filename, d.id))
+        .collect();
+
+    let (mut imported, mut skipped) = (0, 0);
+    for record in &records {
+        if store.exists(&record.front, &record.back)? {
+            skipped += 1;
+            continue;
+        }
+
+        let document_id = record
+            .document
+            .as_ref()
+            .and_then(|name| documents.get(name).copied());
+        let id = store.insert(document_id, &record.item_type, &record.front, &record.back)?;
+        store.set_schedule(
+            id,
+            record.next_review,
+            record.interval_days,
+            record.ease_factor,
+            record.review_count,
+        )?;
+        if let Some(tags) = record.tags.as_deref().and_then(normalize_tags) {
+            store.set_tags(id, Some(&tags))?;
+        }
+        imported += 1;
+    }
+
+    println!(
+        "{} Imported {} study items from {} ({} already present)",
+        "✓".green(),
+        imported,
+        path.display().to_string().cyan(),
+        skipped
+    );
+
+    Ok(())
+}
+
+fn write_csv(records: &[CardRecord]) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for record in records {
+        writer.serialize(record)?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+fn read_json(text: &str) -> Result<Vec<CardRecord>> {
+    serde_json::from_str::<Vec<CardRecord>>(text)
+        .or_else(|_| serde_json::from_str::<LibraryFile>(text).map(LibraryFile::into_records))
+        .context("Not a `librarian cards export` or `librarian export library` JSON file")
+}
+
+fn read_csv(text: &str) -> Result<Vec<CardRecord>> {
+    let mut reader = csv::Reader::from_reader(text.as_bytes());
+    reader
+        .deserialize()
+        .enumerate()
+        .map(|(i, row)| row.with_context(|| format!("Invalid row {}", i + 1)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_round_trip() {
+        let records = vec![
+            CardRecord {
+                item_type: "flashcard".to_string(),
+                front: "What is a TLB?".to_string(),
+                back: "A cache of page-table entries,\nper core".to_string(),
+                tags: Some("paging,os".to_string()),
+                document: Some("lecture3.pdf".to_string()),
+                next_review: "2026-03-14T09:00:00Z".parse().unwrap(),
+                interval_days: 6.0,
+                ease_factor: 2.6,
+                review_count: 2,
+            },
+            CardRecord {
+                item_type: "cloze".to_string(),
+                front: "The {{kernel}} runs in ring 0".to_string(),
+                back: "kernel".to_string(),
+                tags: None,
+                document: None,
+                next_review: "2026-03-12T09:00:00Z".parse().unwrap(),
+                interval_days: 1.0,
+                ease_factor: 2.5,
+                review_count: 0,
+            },
+        ];
+
+        let csv = write_csv(&records).unwrap();
+        assert!(csv.starts_with("item_type,front,back,tags,document,next_review"));
+        assert_eq!(read_csv(&csv).unwrap(), records);
+    }
+
+    #[test]
+    fn test_read_library_export_keeps_tags() {
+        let item = StudyItemExport {
+            id: 7,
+            document_id: Some(3),
+            item_type: "flashcard".to_string(),
+            front: "What is a TLB?".to_string(),
+            back: "A cache of page-table entries".to_string(),
+            tags: vec!["paging".to_string(), "midterm".to_string()],
+            next_review: "2026-03-14T09:00:00Z".parse().unwrap(),
+            interval_days: 6.0,
+            ease_factor: 2.6,
+            review_count: 2,
+            created_at: "2026-03-01T09:00:00Z".parse().unwrap(),
+        };
+        let library = serde_json::json!({
+            "exported_at": "2026-03-15T09:00:00Z",
+            "buckets": [{
+                "name": "os",
+                "documents": [{ "id": 3, "title": "lecture3.pdf", "content": "..." }],
+                "study_items": [item],
+                "conversations": [],
+            }],
+        });
+
+        let records = read_json(&library.to_string()).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].tags.as_deref(), Some("paging,midterm"));
+        assert_eq!(records[0].document.as_deref(), Some("lecture3.pdf"));
+        assert_eq!(records[0].review_count, 2);
+        assert!(read_json("{}").is_err());
+    }
+}
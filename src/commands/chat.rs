@@ -1,12 +1,20 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use colored::Colorize;
 use inquire::{Select, Text};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
 
 use crate::bucket;
 use crate::config::Config;
 use crate::embeddings;
-use crate::llm::{GroqClient, groq::Message};
-use crate::storage::{ChunkStore, ConversationStore, Database, DocumentStore};
+use crate::ingest;
+use crate::llm::{ChatProvider, tokens};
+use crate::llm::{Message, Usage};
+use crate::storage::chunks::StoredChunk;
+use crate::storage::links::{KIND_CHUNK, KIND_NOTE};
+use crate::storage::{ChunkStore, ConversationStore, Database, Document, DocumentStore, LinkStore};
 
 const GROUNDED_SYSTEM_PROMPT: &str = r#"You are The Librarian, a knowledgeable study assistant helping a student learn from their course materials.
 
@@ -23,7 +31,7 @@ RESPONSE STYLE:
 - Do not assume the student wants code, LaTeX, or any specific output format unless they explicitly ask for it
 - Use plain text with clear formatting. Only use code blocks if the question involves actual code
 
-Format citations like: [Source: filename]"#;
+Format citations like: [Source: filename], adding the page or time when the context gives one: [Source: filename, p. 12] or [Source: lecture.mp4 @ 14:32]. For source code, cite the file and definition ([Source: parser.rs / fn parse_id]) and quote code in a fenced block tagged with its language"#;
 
 const NO_DOCS_SYSTEM_PROMPT: &str = r#"You are The Librarian, a knowledgeable study assistant. The user has no documents loaded in their current library.
 
@@ -32,11 +40,63 @@ Help them by:
 2. Suggesting they add study materials with 'librarian add <file>'
 3. Being clear when you're using general knowledge vs. their specific materials"#;
 
-pub async fn run() -> Result<()> {
+/// Questions for a non-interactive session, when there are any: read from
+/// `file`, or from stdin when it isn't a terminal (or `no_tty` is set)
+pub fn scripted_questions(no_tty: bool, file: Option<&Path>) -> Result<Option<Vec<String>>> {
+    let text = match file {
+        Some(path) => std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?,
+        None if no_tty || !std::io::stdin().is_terminal() => {
+            let mut text = String::new();
+            std::io::stdin()
+                .read_to_string(&mut text)
+                .context("Failed to read stdin as UTF-8 text")?;
+            text
+        }
+        None => return Ok(None),
+    };
+
+    let questions = split_questions(&text);
+    if questions.is_empty() {
+        anyhow::bail!("No questions received");
+    }
+    Ok(Some(questions))
+}
+
+/// One question per paragraph, so a question can span several lines
+fn split_questions(text: &str) -> Vec<String> {
+    let mut questions = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    for line in text.lines().chain(std::iter::once("")) {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                questions.push(current.join("\n"));
+                current.clear();
+            }
+        } else {
+            current.push(line.trim_end());
+        }
+    }
+    questions
+}
+
+/// Chat with the current bucket. With `questions`, answers each in turn in a
+/// new conversation and exits without prompting. `show_context` prints the
+/// retrieved chunks before each answer.
+pub async fn run(
+    questions: Option<Vec<String>>,
+    mut show_context: bool,
+    tag: Option<String>,
+) -> Result<()> {
+    let interactive = questions.is_none();
+    let mut questions = questions.map(Vec::into_iter);
     let config = Config::load()?;
 
-    let api_key = match config.get_api_key() {
-        Some(key) => key,
+    let mut client = match crate::llm::client(&config) {
+        Some(client) => client,
+        None if !interactive => {
+            anyhow::bail!("No API key configured. Run `librarian config` to set up.")
+        }
         None => {
             println!(
                 "{} No API key configured. Run {} to set up.",
@@ -47,8 +107,6 @@ pub async fn run() -> Result<()> {
         }
     };
 
-    let client = GroqClient::new(api_key, config.default_model);
-
     // Check current bucket and document count
     let db = Database::open()?;
     let doc_store = DocumentStore::new(&db);
@@ -58,6 +116,26 @@ pub async fn run() -> Result<()> {
     // Initialize chunks table if needed
     chunk_store.init_schema()?;
 
+    // Documents retrieval is limited to, set with --tag or /topic
+    let mut topic: Option<Topic> = match tag.as_deref() {
+        Some(tag) => {
+            let document_ids: HashSet<i64> = doc_store
+                .list()?
+                .iter()
+                .filter(|d| d.has_tag(tag))
+                .map(|d| d.id)
+                .collect();
+            if document_ids.is_empty() {
+                anyhow::bail!("No documents in this bucket are tagged '{}'", tag);
+            }
+            Some(Topic {
+                label: format!("#{}", tag.trim_start_matches('#')),
+                document_ids,
+            })
+        }
+        None => None,
+    };
+
     let doc_count = doc_store.count()?;
     let chunk_count = chunk_store.count().unwrap_or(0);
 
@@ -65,74 +143,98 @@ pub async fn run() -> Result<()> {
         .map(|b| b.name)
         .unwrap_or_else(|| "(default)".to_string());
 
-    println!();
-    println!(
-        "    {}",
-        "╭──────────────────────────────────────────────────────╮".cyan()
-    );
-    println!(
-        "    {}       {}       {}",
-        "│".cyan(),
-        "🎓 ASK THE LIBRARIAN 🎓".bold().white(),
-        "│".cyan()
-    );
-    println!(
-        "    {}  {}  {}",
-        "│".cyan(),
-        "Your personal study assistant, ready to help!".dimmed(),
-        "│".cyan()
-    );
-    println!(
-        "    {}",
-        "├──────────────────────────────────────────────────────┤".cyan()
-    );
-    println!(
-        "    {}  📖 Book: {:<20} 📄 {} docs, {} chunks  {}",
-        "│".cyan(),
-        bucket_name.cyan(),
-        doc_count.to_string().green(),
-        chunk_count.to_string().green(),
-        "│".cyan()
-    );
-    println!(
-        "    {}  🤖 Model: {:<43} {}",
-        "│".cyan(),
-        client.model.yellow(),
-        "│".cyan()
-    );
-    println!(
-        "    {}",
-        "├──────────────────────────────────────────────────────┤".cyan()
-    );
-    println!(
-        "    {}  💡 {} to exit │ Ask anything about your materials!  {}",
-        "│".cyan(),
-        "quit".yellow().bold(),
-        "│".cyan()
-    );
-    println!(
-        "    {}",
-        "╰──────────────────────────────────────────────────────╯".cyan()
-    );
-    println!();
-
-    if doc_count == 0 {
+    if interactive {
+        println!();
+        println!(
+            "    {}",
+            "╭──────────────────────────────────────────────────────╮".cyan()
+        );
+        println!(
+            "    {}       {}       {}",
+            "│".cyan(),
+            "🎓 ASK THE LIBRARIAN 🎓".bold().white(),
+            "│".cyan()
+        );
+        println!(
+            "    {}  {}  {}",
+            "│".cyan(),
+            "Your personal study assistant, ready to help!".dimmed(),
+            "│".cyan()
+        );
+        println!(
+            "    {}",
+            "├──────────────────────────────────────────────────────┤".cyan()
+        );
         println!(
-            "{} No documents in this bucket. Add some with {}",
-            "Note:".yellow(),
-            "librarian add <file>".cyan()
+            "    {}  📖 Book: {:<20} 📄 {} docs, {} chunks  {}",
+            "│".cyan(),
+            bucket_name.cyan(),
+            doc_count.to_string().green(),
+            chunk_count.to_string().green(),
+            "│".cyan()
         );
-        println!("Chat will use general knowledge only.\n");
-    } else if chunk_count == 0 {
         println!(
-            "{} Documents exist but no chunks/embeddings. Re-add documents to enable semantic search.\n",
-            "Note:".yellow()
+            "    {}  🤖 Model: {:<43} {}",
+            "│".cyan(),
+            client.model().yellow(),
+            "│".cyan()
         );
+        println!(
+            "    {}",
+            "├──────────────────────────────────────────────────────┤".cyan()
+        );
+        println!(
+            "    {}  💡 {} to exit │ {} lists commands          {}",
+            "│".cyan(),
+            "quit".yellow().bold(),
+            "/help".yellow().bold(),
+            "│".cyan()
+        );
+        println!(
+            "    {}",
+            "╰──────────────────────────────────────────────────────╯".cyan()
+        );
+        println!();
+
+        if doc_count == 0 {
+            println!(
+                "{} No documents in this bucket. Add some with {}",
+                "Note:".yellow(),
+                "librarian add <file>".cyan()
+            );
+            println!("Chat will use general knowledge only.\n");
+        } else if chunk_count == 0 {
+            println!(
+                "{} Documents exist but no chunks/embeddings. Re-add documents to enable semantic search.\n",
+                "Note:".yellow()
+            );
+        }
     }
 
     // --- Conversation persistence: choose or create conversation ---
-    let conversation_id = pick_or_create_conversation(&conv_store)?;
+    let mut conversation_id = if interactive {
+        pick_or_create_conversation(&conv_store)?
+    } else {
+        conv_store.create(None)?
+    };
     let mut is_first_message = true;
+    // Chunks used to answer the last question (for linking notes)
+    let mut last_sources: Vec<i64> = Vec::new();
+    if interactive && let Some(topic) = &topic {
+        println!(
+            "{} Answers draw only on {} ({} document(s)). Type {} to use everything.\n",
+            "ℹ".blue(),
+            topic.label.cyan(),
+            topic.document_ids.len(),
+            "/topic off".cyan()
+        );
+    }
+    // Chunks the last answer's [Source: ...] citations point to, for /cite and /open
+    let mut last_citations: Vec<i64> = Vec::new();
+    // What went into the last question's context, for /context
+    let mut last_retrieval: Option<Retrieval> = None;
+    // Files attached with /attach, sent with every question after the system prompt
+    let mut attachments: Vec<(String, Message)> = Vec::new();
 
     // Choose system prompt based on whether we have documents
     let system_prompt = if doc_count > 0 {
@@ -141,32 +243,29 @@ pub async fn run() -> Result<()> {
         NO_DOCS_SYSTEM_PROMPT
     };
 
-    let mut conversation: Vec<Message> = vec![Message {
-        role: "system".to_string(),
-        content: system_prompt.to_string(),
-    }];
-
-    // Load previous messages if resuming a conversation
-    let prev_messages = conv_store.get_messages(conversation_id)?;
-    if !prev_messages.is_empty() {
+    // Load previous messages if resuming a conversation (older turns may
+    // already be folded into a summary)
+    let mut conversation = load_history(&conv_store, conversation_id, system_prompt)?;
+    let prev_messages = conv_store.message_count(conversation_id)?;
+    if prev_messages > 0 {
         is_first_message = false;
         println!(
             "{} Loaded {} previous messages.\n",
             "↻".cyan(),
-            prev_messages.len()
+            prev_messages
         );
-        for msg in &prev_messages {
-            conversation.push(Message {
-                role: msg.role.clone(),
-                content: msg.content.clone(),
-            });
-        }
     }
 
     loop {
-        let input = Text::new("You:")
-            .with_help_message("Ask a question or type 'quit' to exit")
-            .prompt()?;
+        let input = match &mut questions {
+            Some(questions) => match questions.next() {
+                Some(question) => question,
+                None => break,
+            },
+            None => Text::new("You:")
+                .with_help_message("Ask a question or type 'quit' to exit")
+                .prompt()?,
+        };
 
         let input = input.trim();
 
@@ -179,6 +278,125 @@ pub async fn run() -> Result<()> {
             continue;
         }
 
+        if let Some(command) = input.strip_prefix('/') {
+            let (name, arg) = command
+                .split_once(char::is_whitespace)
+                .map(|(name, arg)| (name, arg.trim()))
+                .unwrap_or((command, ""));
+
+            match name {
+                // Save a personal note attached to this conversation
+                "note" => {
+                    if arg.is_empty() {
+                        println!("{} Usage: /note <text>\n", "Note:".yellow());
+                    } else {
+                        let id = crate::commands::note::save_note(
+                            &db,
+                            arg,
+                            None,
+                            Some(conversation_id),
+                        )?;
+                        // Link the note to the passages behind the last answer
+                        let link_store = LinkStore::new(&db);
+                        for chunk_id in &last_sources {
+                            link_store.add(KIND_NOTE, id, KIND_CHUNK, *chunk_id)?;
+                        }
+                        println!("{} Saved note (id: {})\n", "✓".green(), id);
+                    }
+                }
+                "sources" => show_sources(&chunk_store, &doc_store, &last_sources)?,
+                "cite" | "open" => {
+                    let chunk_id = arg
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|n| n.checked_sub(1))
+                        .and_then(|i| last_citations.get(i));
+                    match chunk_id {
+                        Some(id) if name == "cite" => {
+                            show_cited_chunk(&chunk_store, &doc_store, *id)?
+                        }
+                        Some(id) => open_cited_source(&chunk_store, &doc_store, *id)?,
+                        None if last_citations.is_empty() => {
+                            println!("{} The last answer cited no sources\n", "ℹ".blue())
+                        }
+                        None => println!(
+                            "{} Usage: /{} <1-{}>\n",
+                            "Note:".yellow(),
+                            name,
+                            last_citations.len()
+                        ),
+                    }
+                }
+                "attach" => {
+                    if arg.is_empty() {
+                        println!("{} Usage: /attach <path>\n", "Note:".yellow());
+                    } else {
+                        // Let one file take up to a third of the context window
+                        let max_tokens = client.context_window() / 3;
+                        match attach_file(arg, interactive, &doc_store, &chunk_store, max_tokens)
+                            .await
+                        {
+                            Ok(attachment) => attachments.push(attachment),
+                            Err(e) => println!("{} {}\n", "Error:".red().bold(), e),
+                        }
+                    }
+                }
+                "context" => match arg {
+                    "on" | "off" => {
+                        show_context = arg == "on";
+                        println!(
+                            "{} Retrieved context {} before each answer\n",
+                            "✓".green(),
+                            if show_context { "shown" } else { "hidden" }
+                        );
+                    }
+                    _ => match &last_retrieval {
+                        Some(retrieval) => show_retrieval(&chunk_store, &doc_store, retrieval)?,
+                        None => println!("{} Nothing has been asked yet\n", "ℹ".blue()),
+                    },
+                },
+                "model" => switch_model(&mut client, arg),
+                "topic" => {
+                    if arg.is_empty() || arg.eq_ignore_ascii_case("off") {
+                        topic = None;
+                        println!("{} Searching all documents again\n", "✓".green());
+                    } else {
+                        match find_topic(&doc_store, arg)? {
+                            Some(found) => {
+                                println!(
+                                    "{} Answers now draw only on {} ({} document(s))\n",
+                                    "✓".green(),
+                                    found.label.cyan(),
+                                    found.document_ids.len()
+                                );
+                                topic = Some(found);
+                            }
+                            None => {
+                                println!("{} No document or tag matches '{}'\n", "⊘".yellow(), arg)
+                            }
+                        }
+                    }
+                }
+                "clear" => {
+                    conversation.truncate(1);
+                    attachments.clear();
+                    last_sources.clear();
+                    last_citations.clear();
+                    conversation_id = conv_store.create(None)?;
+                    is_first_message = true;
+                    println!("{} Started new conversation.\n", "✦".cyan());
+                }
+                "help" => print_help(),
+                _ => println!(
+                    "{} Unknown command /{} (try {})\n",
+                    "Note:".yellow(),
+                    name,
+                    "/help".cyan()
+                ),
+            }
+            continue;
+        }
+
         // Auto-title from first user message
         if is_first_message {
             let title: String = input.chars().take(60).collect();
@@ -191,52 +409,67 @@ pub async fn run() -> Result<()> {
             is_first_message = false;
         }
 
+        // Keep long conversations inside the model's context window
+        match compact_history(&client, &conv_store, conversation_id).await {
+            Ok(true) => {
+                conversation = load_history(&conv_store, conversation_id, system_prompt)?;
+                eprintln!(
+                    "{} Summarized earlier messages to keep this conversation within the model's context\n",
+                    "↻".cyan()
+                );
+            }
+            Ok(false) => {}
+            Err(e) => eprintln!(
+                "{} Couldn't summarize earlier messages: {}",
+                "Note:".yellow(),
+                e
+            ),
+        }
+
         // --- Query enhancement for better embedding search ---
         let enhanced_query = crate::search::enhance_query(input);
 
         // --- Dynamic context sizing ---
-        let conversation_chars: usize = conversation.iter().map(|m| m.content.len()).sum();
-        let max_context = client
-            .available_context_chars(system_prompt.len(), conversation_chars, 4096)
-            .clamp(2000, 30000);
-
-        // Search for relevant context using semantic search
-        let context = if chunk_count > 0 {
-            build_semantic_context(&chunk_store, &doc_store, &enhanced_query, max_context)?
-        } else if doc_count > 0 {
-            // Fallback to FTS if no chunks
-            build_fts_context(&doc_store, input, max_context)?
-        } else {
-            String::new()
-        };
-
-        // Build the user message with context
-        let user_message = if context.is_empty() {
-            input.to_string()
-        } else {
-            format!(
-                "CONTEXT FROM YOUR STUDY MATERIALS:\n{}\n\n---\n\nQUESTION: {}",
-                context, input
-            )
-        };
+        let mut prompt = conversation.clone();
+        prompt.extend(attachments.iter().map(|(_, m)| m.clone()));
+        let max_context = client.available_context_tokens(&prompt, 4096).max(500);
+
+        let retrieval = retrieve_context(
+            &chunk_store,
+            &doc_store,
+            input,
+            &enhanced_query,
+            max_context,
+            topic.as_ref().map(|t| &t.document_ids),
+        )?;
+        last_sources = retrieval.chunk_ids();
+        if show_context {
+            show_retrieval(&chunk_store, &doc_store, &retrieval)?;
+        }
 
         conversation.push(Message {
             role: "user".to_string(),
-            content: user_message,
+            content: with_context(&retrieval.context, input),
         });
+        last_retrieval = Some(retrieval);
 
-        // Show status briefly then clear for streaming output
-        print!("{}", "Searching context...".dimmed());
-        std::io::Write::flush(&mut std::io::stdout()).ok();
+        if interactive {
+            // Show status briefly then clear for streaming output
+            print!("{}", "Searching context...".dimmed());
+            std::io::Write::flush(&mut std::io::stdout()).ok();
 
-        // Small delay to show the searching message
-        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-        print!("\r{}\r", " ".repeat(25));
+            // Small delay to show the searching message
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            print!("\r{}\r", " ".repeat(25));
 
-        print!("{} ", "Assistant:".green().bold());
-        std::io::Write::flush(&mut std::io::stdout()).ok();
+            print!("{} ", "Assistant:".green().bold());
+            std::io::Write::flush(&mut std::io::stdout()).ok();
+        }
+
+        let mut request = conversation.clone();
+        request.splice(1..1, attachments.iter().map(|(_, m)| m.clone()));
 
-        match client.chat_stream(&conversation).await {
+        match crate::render::stream_answer(&client, &request).await {
             Ok(response) => {
                 println!(); // Extra newline after streaming
 
@@ -252,7 +485,30 @@ pub async fn run() -> Result<()> {
                 // --- Persist messages ---
                 conv_store.add_message(conversation_id, "user", input)?;
                 conv_store.add_message(conversation_id, "assistant", &response)?;
+
+                if interactive && config.show_usage {
+                    print_usage(&client);
+                }
+
+                last_citations = if interactive {
+                    let attached: Vec<&str> =
+                        attachments.iter().map(|(name, _)| name.as_str()).collect();
+                    check_citations(
+                        &chunk_store,
+                        &doc_store,
+                        &response,
+                        &last_sources,
+                        &attached,
+                    )?
+                } else {
+                    verify_citations(&chunk_store, &doc_store, &response, &last_sources)?
+                        .cited
+                        .iter()
+                        .map(|(c, _)| c.id)
+                        .collect()
+                };
             }
+            Err(e) if !interactive => return Err(e),
             Err(e) => {
                 println!("\n{} {}\n", "Error:".red().bold(), e);
                 conversation.pop();
@@ -263,6 +519,597 @@ pub async fn run() -> Result<()> {
     Ok(())
 }
 
+/// Messages always kept verbatim when older turns are summarized
+const KEEP_RECENT_MESSAGES: usize = 6;
+
+const SUMMARY_SYSTEM_PROMPT: &str = "You compress study conversations. Summarize the conversation below so it can replace the original messages: keep the questions asked, the key facts, definitions and conclusions from the answers, any sources cited, and anything the student said about their goals or confusion. Write compact plain prose, at most 300 words.";
+
+/// The messages to send the model: the system prompt, the summary of older
+/// turns (if any), then every message after it
+fn load_history(
+    conv_store: &ConversationStore,
+    conversation_id: i64,
+    system_prompt: &str,
+) -> Result<Vec<Message>> {
+    let mut messages = vec![Message {
+        role: "system".to_string(),
+        content: system_prompt.to_string(),
+    }];
+
+    let summary = conv_store.summary(conversation_id)?;
+    let through = summary.as_ref().map_or(0, |(_, through)| *through);
+    if let Some((summary, _)) = summary {
+        messages.push(Message {
+            role: "system".to_string(),
+            content: format!(
+                "Summary of the earlier part of this conversation:\n{}",
+                summary
+            ),
+        });
+    }
+
+    for msg in conv_store.get_messages(conversation_id)? {
+        if msg.id > through {
+            messages.push(Message {
+                role: msg.role,
+                content: msg.content,
+            });
+        }
+    }
+
+    Ok(messages)
+}
+
+/// How many of the oldest unsummarized messages to fold into the summary, if
+/// together they exceed `budget` tokens (the latest few always stay)
+fn messages_to_summarize(lengths: &[usize], budget: usize) -> Option<usize> {
+    let total: usize = lengths.iter().sum();
+    if total <= budget || lengths.len() <= KEEP_RECENT_MESSAGES {
+        return None;
+    }
+    Some(lengths.len() - KEEP_RECENT_MESSAGES)
+}
+
+/// Once the stored history outgrows a third of the model's context window,
+/// fold all but the latest messages into the conversation's running summary.
+/// Returns whether it did.
+async fn compact_history(
+    client: &impl ChatProvider,
+    conv_store: &ConversationStore<'_>,
+    conversation_id: i64,
+) -> Result<bool> {
+    let summary = conv_store.summary(conversation_id)?;
+    let through = summary.as_ref().map_or(0, |(_, through)| *through);
+    let recent: Vec<_> = conv_store
+        .get_messages(conversation_id)?
+        .into_iter()
+        .filter(|m| m.id > through)
+        .collect();
+
+    // A third leaves room for retrieved context and the reply
+    let budget = client.context_window() / 3;
+    let lengths: Vec<usize> = recent
+        .iter()
+        .map(|m| tokens::estimate(&m.content))
+        .collect();
+    let Some(count) = messages_to_summarize(&lengths, budget) else {
+        return Ok(false);
+    };
+
+    let mut transcript = String::new();
+    if let Some((previous, _)) = &summary {
+        transcript.push_str(&format!("Summary so far:\n{}\n\n", previous));
+    }
+    for msg in &recent[..count] {
+        transcript.push_str(&format!("{}: {}\n\n", msg.role, msg.content));
+    }
+
+    let new_summary = client
+        .query_with_system(SUMMARY_SYSTEM_PROMPT, &transcript)
+        .await?;
+    conv_store.set_summary(conversation_id, new_summary.trim(), recent[count - 1].id)?;
+
+    Ok(true)
+}
+
+/// Documents chat retrieval is limited to
+struct Topic {
+    /// What the user asked for, e.g. a filename or "#exam"
+    label: String,
+    document_ids: HashSet<i64>,
+}
+
+fn print_help() {
+    let commands = [
+        ("/sources", "Show the passages behind the last answer"),
+        (
+            "/cite <n>",
+            "Show the full passage the answer's nth citation points to",
+        ),
+        (
+            "/open <n>",
+            "Open the original file behind the nth citation",
+        ),
+        (
+            "/model <name>",
+            "Switch model for this session (no name: list them)",
+        ),
+        (
+            "/topic <doc>",
+            "Only use one document (ID or filename) or a tag",
+        ),
+        ("/topic off", "Use every document again"),
+        (
+            "/context",
+            "Show the chunks retrieved for the last question",
+        ),
+        (
+            "/context on|off",
+            "Show retrieved chunks before every answer",
+        ),
+        ("/note <text>", "Save a note linked to the last answer"),
+        (
+            "/attach <path>",
+            "Read a file into this conversation (and optionally add it)",
+        ),
+        ("/clear", "Start a new conversation"),
+        ("/help", "Show this list"),
+        ("quit", "Leave chat"),
+    ];
+    println!();
+    for (command, description) in commands {
+        println!("  {:<16} {}", command.yellow(), description.dimmed());
+    }
+    println!();
+}
+
+/// Print the chunks the last answer was built from
+fn show_sources(
+    chunk_store: &ChunkStore,
+    doc_store: &DocumentStore,
+    sources: &[i64],
+) -> Result<()> {
+    if sources.is_empty() {
+        println!("{} No sources yet — ask a question first\n", "ℹ".blue());
+        return Ok(());
+    }
+
+    println!();
+    for (i, chunk_id) in sources.iter().enumerate() {
+        let Some(chunk) = chunk_store.get(*chunk_id)? else {
+            continue;
+        };
+        let filename = doc_store
+            .get(chunk.document_id)?
+            .map(|d| d.filename)
+            .unwrap_or_else(|| "Unknown".to_string());
+        let preview: String = chunk
+            .content
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .chars()
+            .take(160)
+            .collect();
+
+        println!(
+            "  {} {}{} {}",
+            format!("[{}]", i + 1).cyan(),
+            filename.bold(),
+            chunk.location().dimmed(),
+            format!("(doc {}, chunk {})", chunk.document_id, chunk.chunk_index).dimmed()
+        );
+        println!("      {}…", preview.dimmed());
+    }
+    println!();
+
+    Ok(())
+}
+
+/// The text inside each `[Source: ...]` citation in an answer, in order,
+/// without repeats
+fn cited_sources(response: &str) -> Vec<String> {
+    let mut cited: Vec<String> = Vec::new();
+    let mut rest = response;
+
+    while let Some(start) = rest.find("[Source:") {
+        let after = &rest[start + "[Source:".len()..];
+        let Some(end) = after.find(']') else {
+            break;
+        };
+        // One bracket may cite several sources: [Source: a.pdf; Source: b.pdf]
+        for source in after[..end].split(';') {
+            let source = source.trim().trim_start_matches("Source:").trim();
+            if !source.is_empty() && !cited.iter().any(|c| c == source) {
+                cited.push(source.to_string());
+            }
+        }
+        rest = &after[end..];
+    }
+
+    cited
+}
+
+/// The context chunk a citation refers to: the filename must match, and
+/// among that document's chunks the one whose section/page/time the
+/// citation mentions wins
+fn match_citation<'c>(
+    citation: &str,
+    chunks: &'c [(StoredChunk, String)],
+) -> Option<&'c StoredChunk> {
+    let citation_lower = citation.to_lowercase();
+    let name = citation_lower
+        .split([',', '@'])
+        .next()
+        .unwrap_or("")
+        .split(" / ")
+        .next()
+        .unwrap_or("")
+        .trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    chunks
+        .iter()
+        .filter(|(_, filename)| {
+            let filename = filename.to_lowercase();
+            filename == name || filename.contains(name) || name.contains(&filename)
+        })
+        .map(|(chunk, _)| {
+            let location = chunk.location().to_lowercase();
+            let matched = location
+                .split([',', '@', '/'])
+                .map(str::trim)
+                .filter(|part| !part.is_empty() && citation_lower.contains(part))
+                .count();
+            (chunk, matched)
+        })
+        // max_by_key keeps the last of equals; prefer the earliest context chunk
+        .rev()
+        .max_by_key(|(_, matched)| *matched)
+        .map(|(chunk, _)| chunk)
+}
+
+/// An answer's `[Source: ...]` citations, checked against its context
+struct Citations {
+    /// Context chunks the answer cites (with their filenames), in citation order
+    cited: Vec<(StoredChunk, String)>,
+    /// Citations that match nothing the model was given
+    unverified: Vec<String>,
+}
+
+/// Match the answer's citations to the chunks that went into its context
+fn verify_citations(
+    chunk_store: &ChunkStore,
+    doc_store: &DocumentStore,
+    response: &str,
+    sources: &[i64],
+) -> Result<Citations> {
+    let mut citations = Citations {
+        cited: Vec::new(),
+        unverified: Vec::new(),
+    };
+    let cited_text = cited_sources(response);
+    if cited_text.is_empty() {
+        return Ok(citations);
+    }
+
+    let context = source_chunks(chunk_store, doc_store, sources)?;
+    for citation in cited_text {
+        let found = match_citation(&citation, &context)
+            .and_then(|chunk| context.iter().find(|(c, _)| c.id == chunk.id));
+        match found {
+            Some((chunk, _)) if citations.cited.iter().any(|(c, _)| c.id == chunk.id) => {}
+            Some(entry) => citations.cited.push(entry.clone()),
+            None => citations.unverified.push(citation),
+        }
+    }
+
+    Ok(citations)
+}
+
+/// Load context chunks with the filename of the document each came from
+fn source_chunks(
+    chunk_store: &ChunkStore,
+    doc_store: &DocumentStore,
+    sources: &[i64],
+) -> Result<Vec<(StoredChunk, String)>> {
+    let mut chunks = Vec::new();
+    for chunk_id in sources {
+        if let Some(chunk) = chunk_store.get(*chunk_id)? {
+            let filename = doc_store
+                .get(chunk.document_id)?
+                .map(|d| d.filename)
+                .unwrap_or_default();
+            chunks.push((chunk, filename));
+        }
+    }
+    Ok(chunks)
+}
+
+/// Check the answer's citations, list them, and warn about any that cite
+/// something the model wasn't given (citing an `attached` file is fine).
+/// Returns the cited chunk IDs, numbered from 1 for /cite and /open.
+fn check_citations(
+    chunk_store: &ChunkStore,
+    doc_store: &DocumentStore,
+    response: &str,
+    sources: &[i64],
+    attached: &[&str],
+) -> Result<Vec<i64>> {
+    let mut citations = verify_citations(chunk_store, doc_store, response, sources)?;
+    citations.unverified.retain(|citation| {
+        !attached
+            .iter()
+            .any(|name| citation.to_lowercase().starts_with(&name.to_lowercase()))
+    });
+
+    if !citations.cited.is_empty() {
+        let listed: Vec<String> = citations
+            .cited
+            .iter()
+            .enumerate()
+            .map(|(i, (chunk, filename))| format!("[{}] {}{}", i + 1, filename, chunk.location()))
+            .collect();
+        println!(
+            "{} {}  {}",
+            "Cited:".dimmed(),
+            listed.join(" · ").cyan(),
+            "(/cite <n> to read, /open <n> to open)".dimmed()
+        );
+    }
+    for citation in &citations.unverified {
+        println!(
+            "{} [Source: {}] isn't one of the passages this answer was given",
+            "⚠".yellow(),
+            citation
+        );
+    }
+    if !citations.cited.is_empty() || !citations.unverified.is_empty() {
+        println!();
+    }
+
+    Ok(citations.cited.iter().map(|(c, _)| c.id).collect())
+}
+
+/// Print a cited chunk in full, with where it comes from
+fn show_cited_chunk(
+    chunk_store: &ChunkStore,
+    doc_store: &DocumentStore,
+    chunk_id: i64,
+) -> Result<()> {
+    let Some(chunk) = chunk_store.get(chunk_id)? else {
+        println!("{} That passage no longer exists\n", "⊘".yellow());
+        return Ok(());
+    };
+    let filename = doc_store
+        .get(chunk.document_id)?
+        .map(|d| d.filename)
+        .unwrap_or_else(|| "Unknown".to_string());
+
+    println!("\n{}", "─".repeat(50).dimmed());
+    println!(
+        "{}{} {}",
+        filename.bold(),
+        chunk.location(),
+        format!("(doc {}, chunk {})", chunk.document_id, chunk.chunk_index).dimmed()
+    );
+    println!("{}", "─".repeat(50).dimmed());
+    println!("{}\n", chunk.content.trim());
+
+    Ok(())
+}
+
+/// Open the original file (or URL) a cited chunk came from
+fn open_cited_source(
+    chunk_store: &ChunkStore,
+    doc_store: &DocumentStore,
+    chunk_id: i64,
+) -> Result<()> {
+    let doc = match chunk_store.get(chunk_id)? {
+        Some(chunk) => doc_store.get(chunk.document_id)?.map(|doc| (doc, chunk)),
+        None => None,
+    };
+    let Some((doc, chunk)) = doc else {
+        println!("{} That passage no longer exists\n", "⊘".yellow());
+        return Ok(());
+    };
+
+    crate::commands::docs::open_source(&doc)?;
+    let location = chunk.location();
+    if location.is_empty() {
+        println!("{} Opened {}\n", "✓".green(), doc.filename.cyan());
+    } else {
+        println!(
+            "{} Opened {} — the passage is at{}\n",
+            "✓".green(),
+            doc.filename.cyan(),
+            location
+        );
+    }
+
+    Ok(())
+}
+
+/// Extract a file for `/attach`, offer to add it to the bucket, and return
+/// the message that carries its text (cut to `max_tokens`) into the conversation
+async fn attach_file(
+    arg: &str,
+    interactive: bool,
+    doc_store: &DocumentStore<'_>,
+    chunk_store: &ChunkStore<'_>,
+    max_tokens: usize,
+) -> Result<(String, Message)> {
+    // Paths dragged into a terminal arrive quoted or with escaped spaces
+    let arg = arg.trim_matches(['\'', '"']).replace("\\ ", " ");
+    let path = match arg.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+        None => PathBuf::from(&arg),
+    };
+    if !path.is_file() {
+        anyhow::bail!("No file at {}", path.display());
+    }
+
+    if ingest::requires_transcription(&path) {
+        println!("{}", "Transcribing audio/video...".dimmed());
+    }
+    let content = ingest::extract_from_file_async(&path).await?;
+    if content.text.trim().is_empty() {
+        anyhow::bail!("No text could be extracted from {}", path.display());
+    }
+    let filename = path
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_else(|| arg.clone());
+
+    let text = truncate_content(&content.text, tokens::fit(&content.text, max_tokens));
+    println!(
+        "{} Attached {} ({} chars{})",
+        "📎".cyan(),
+        filename.cyan(),
+        content.text.len(),
+        if text.len() < content.text.len() {
+            format!(", first {} sent", text.len())
+        } else {
+            String::new()
+        }
+    );
+
+    if interactive {
+        let add = Select::new("Also add it to this bucket?", vec!["No", "Yes"])
+            .with_help_message("Adding makes it searchable in later chats")
+            .prompt()
+            .unwrap_or("No");
+        if add == "Yes" {
+            match crate::commands::add::add_extracted(&path, &content, doc_store, chunk_store)
+                .await?
+            {
+                Some((id, chunks)) => println!(
+                    "{} Added {} (id: {}, {} chunks)",
+                    "✓".green(),
+                    filename,
+                    id,
+                    chunks
+                ),
+                None => println!("{} {} is already in this bucket", "ℹ".blue(), filename),
+            }
+        }
+    }
+    println!();
+
+    let message = Message {
+        role: "user".to_string(),
+        content: format!(
+            "ATTACHED FILE: {}\n{}\n\n(Use this file when it's relevant to my questions; cite it as [Source: {}].)",
+            filename, text, filename
+        ),
+    };
+    Ok((filename, message))
+}
+
+/// Print the last request's token counts and the model's total for today
+pub(crate) fn print_usage(client: &impl ChatProvider) {
+    let Some(usage) = client.last_usage() else {
+        return;
+    };
+    let today = crate::storage::usage::open().and_then(|db| {
+        crate::storage::UsageStore::new(&db).day(&crate::storage::usage::today(), client.model())
+    });
+    let today = match today {
+        Ok(today) => format!(" · {} today on {}", today.total_tokens(), client.model()),
+        Err(_) => String::new(),
+    };
+    println!(
+        "{}",
+        format!(
+            "↳ {} prompt + {} completion tokens{}",
+            usage.prompt_tokens, usage.completion_tokens, today
+        )
+        .dimmed()
+    );
+}
+
+/// Change the model for the rest of the session
+fn switch_model(client: &mut impl ChatProvider, name: &str) {
+    // Local servers run whatever has been pulled, so take the name as given
+    if client.models().is_empty() && !name.is_empty() {
+        client.set_model(name.to_string());
+        println!(
+            "{} Now using {} on {}\n",
+            "✓".green(),
+            name.yellow(),
+            client.name()
+        );
+        return;
+    }
+
+    let matches: Vec<&str> = client
+        .models()
+        .iter()
+        .map(|(id, _, _)| *id)
+        .filter(|id| !name.is_empty() && id.contains(name))
+        .collect();
+
+    match matches.as_slice() {
+        [model] => {
+            client.set_model(model.to_string());
+            println!("{} Now using {}\n", "✓".green(), model.yellow());
+        }
+        _ => {
+            if !name.is_empty() {
+                println!("{} No single model matches '{}'", "⊘".yellow(), name);
+            }
+            println!("{} {}", "Current model:".dimmed(), client.model().yellow());
+            for (id, description, _) in client.models() {
+                println!("  {} {}", id, format!("- {}", description).dimmed());
+            }
+            println!();
+        }
+    }
+}
+
+/// Resolve /topic's argument: a document ID, a tag, or part of a filename
+fn find_topic(doc_store: &DocumentStore, arg: &str) -> Result<Option<Topic>> {
+    if let Ok(id) = arg.parse::<i64>() {
+        return Ok(doc_store.get(id)?.map(|doc| Topic {
+            label: doc.filename,
+            document_ids: HashSet::from([id]),
+        }));
+    }
+
+    let wanted = arg.trim_start_matches('#').to_lowercase();
+    let docs = doc_store.list()?;
+
+    let tagged: HashSet<i64> = docs
+        .iter()
+        .filter(|d| d.has_tag(&wanted))
+        .map(|d| d.id)
+        .collect();
+    if !tagged.is_empty() {
+        return Ok(Some(Topic {
+            label: format!("#{}", wanted),
+            document_ids: tagged,
+        }));
+    }
+
+    let named: Vec<&Document> = docs
+        .iter()
+        .filter(|d| d.filename.to_lowercase().contains(&wanted))
+        .collect();
+    Ok(match named.as_slice() {
+        [] => None,
+        [doc] => Some(Topic {
+            label: doc.filename.clone(),
+            document_ids: HashSet::from([doc.id]),
+        }),
+        _ => Some(Topic {
+            label: format!("files matching '{}'", arg),
+            document_ids: named.iter().map(|d| d.id).collect(),
+        }),
+    })
+}
+
 /// Let user pick a recent conversation or start a new one
 fn pick_or_create_conversation(store: &ConversationStore) -> Result<i64> {
     let recent = store.list_recent(5)?;
@@ -316,39 +1163,344 @@ fn pick_or_create_conversation(store: &ConversationStore) -> Result<i64> {
     }
 }
 
+/// Find context for a question: hybrid semantic search when documents are
+/// chunked, full-text search otherwise
+fn retrieve_context(
+    chunk_store: &ChunkStore,
+    doc_store: &DocumentStore,
+    question: &str,
+    enhanced_query: &str,
+    max_context_tokens: usize,
+    scope: Option<&HashSet<i64>>,
+) -> Result<Retrieval> {
+    if chunk_store.count().unwrap_or(0) > 0 {
+        let retrieval = build_semantic_context(
+            chunk_store,
+            doc_store,
+            enhanced_query,
+            max_context_tokens,
+            scope,
+        )?;
+        // Counted so `docs stats` can show which documents answers draw on
+        chunk_store.record_retrievals(&retrieval.chunk_ids())?;
+        Ok(retrieval)
+    } else if doc_store.count()? > 0 {
+        // Fallback to FTS if no chunks
+        let context = build_fts_context(doc_store, question, max_context_tokens, scope)?;
+        Ok(Retrieval::documents(question, context))
+    } else {
+        Ok(Retrieval::documents(question, String::new()))
+    }
+}
+
+/// The user message sent to the model: the question with its context
+fn with_context(context: &str, question: &str) -> String {
+    if context.is_empty() {
+        question.to_string()
+    } else {
+        format!(
+            "CONTEXT FROM YOUR STUDY MATERIALS:\n{}\n\n---\n\nQUESTION: {}",
+            context, question
+        )
+    }
+}
+
+#[derive(Serialize)]
+struct AskOutput<'a> {
+    question: &'a str,
+    model: &'a str,
+    answer: &'a str,
+    sources: Vec<AskSource>,
+    /// Citations in the answer that match none of the sources
+    unverified_citations: &'a [String],
+    usage: Option<Usage>,
+}
+
+#[derive(Serialize)]
+struct AskSource {
+    chunk_id: i64,
+    document_id: i64,
+    filename: String,
+    location: String,
+    /// Whether the answer cites this passage
+    cited: bool,
+}
+
+/// Answer one question from the current bucket and exit (`librarian ask`)
+pub async fn ask(question: &str, json: bool, show_context: bool) -> Result<()> {
+    let question = question.trim();
+    if question.is_empty() {
+        anyhow::bail!("No question given");
+    }
+
+    let config = Config::load()?;
+    let client = crate::llm::client(&config)
+        .context("No API key configured. Run `librarian config` to set up.")?;
+
+    let db = Database::open()?;
+    let doc_store = DocumentStore::new(&db);
+    let chunk_store = ChunkStore::new(&db);
+    chunk_store.init_schema()?;
+
+    let system_prompt = if doc_store.count()? > 0 {
+        GROUNDED_SYSTEM_PROMPT
+    } else {
+        NO_DOCS_SYSTEM_PROMPT
+    };
+    let prompt = [
+        Message {
+            role: "system".to_string(),
+            content: system_prompt.to_string(),
+        },
+        Message {
+            role: "user".to_string(),
+            content: question.to_string(),
+        },
+    ];
+    let max_context = client.available_context_tokens(&prompt, 4096).max(500);
+    let enhanced_query = crate::search::enhance_query(question);
+    let retrieval = retrieve_context(
+        &chunk_store,
+        &doc_store,
+        question,
+        &enhanced_query,
+        max_context,
+        None,
+    )?;
+    if show_context {
+        show_retrieval(&chunk_store, &doc_store, &retrieval)?;
+    }
+    let sources = retrieval.chunk_ids();
+
+    let messages = vec![
+        Message {
+            role: "system".to_string(),
+            content: system_prompt.to_string(),
+        },
+        Message {
+            role: "user".to_string(),
+            content: with_context(&retrieval.context, question),
+        },
+    ];
+
+    if json {
+        let answer = client.chat(&messages).await?;
+        let citations = verify_citations(&chunk_store, &doc_store, &answer, &sources)?;
+        let sources = source_chunks(&chunk_store, &doc_store, &sources)?
+            .into_iter()
+            .map(|(chunk, filename)| AskSource {
+                chunk_id: chunk.id,
+                document_id: chunk.document_id,
+                location: chunk
+                    .location()
+                    .trim_start_matches([' ', '/', ','])
+                    .to_string(),
+                cited: citations.cited.iter().any(|(c, _)| c.id == chunk.id),
+                filename,
+            })
+            .collect();
+        let output = AskOutput {
+            question,
+            model: client.model(),
+            answer: answer.trim(),
+            sources,
+            unverified_citations: &citations.unverified,
+            usage: client.last_usage(),
+        };
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    let answer = crate::render::stream_answer(&client, &messages).await?;
+    if config.show_usage {
+        print_usage(&client);
+    }
+    let citations = verify_citations(&chunk_store, &doc_store, &answer, &sources)?;
+    // List what the answer cites, or else everything it was given
+    let listed = if citations.cited.is_empty() {
+        source_chunks(&chunk_store, &doc_store, &sources)?
+    } else {
+        citations.cited
+    };
+    if !listed.is_empty() {
+        println!("\n{}", "Sources:".dimmed());
+        for (i, (chunk, filename)) in listed.iter().enumerate() {
+            println!(
+                "  {} {}{}",
+                format!("[{}]", i + 1).dimmed(),
+                filename.cyan(),
+                chunk.location()
+            );
+        }
+    }
+    for citation in &citations.unverified {
+        eprintln!(
+            "{} [Source: {}] isn't one of the passages this answer was given",
+            "⚠".yellow(),
+            citation
+        );
+    }
+
+    Ok(())
+}
+
+/// Context found for a question
+struct Retrieval {
+    /// The query the search ran with
+    query: String,
+    context: String,
+    /// Chunks in the context, in order (empty when whole documents were
+    /// found by full-text search instead)
+    chunks: Vec<Retrieved>,
+}
+
+/// How one chunk made it into the context
+struct Retrieved {
+    chunk_id: i64,
+    /// Found by keyword search
+    keyword: bool,
+    /// Similarity to the query, if found by semantic search
+    similarity: Option<f32>,
+    /// Cross-encoder score, if reranking is on
+    rerank: Option<f32>,
+}
+
+impl Retrieval {
+    /// Context from full-text search over whole documents
+    fn documents(query: &str, context: String) -> Self {
+        Self {
+            query: query.to_string(),
+            context,
+            chunks: Vec::new(),
+        }
+    }
+
+    fn chunk_ids(&self) -> Vec<i64> {
+        self.chunks.iter().map(|c| c.chunk_id).collect()
+    }
+}
+
+/// Print what went into a question's context: each chunk, where it's from,
+/// and why it was picked
+fn show_retrieval(
+    chunk_store: &ChunkStore,
+    doc_store: &DocumentStore,
+    retrieval: &Retrieval,
+) -> Result<()> {
+    println!(
+        "\n{} {} {}",
+        "Context for:".bold(),
+        retrieval.query.cyan(),
+        format!("({} chars)", retrieval.context.len()).dimmed()
+    );
+    if retrieval.chunks.is_empty() {
+        let note = if retrieval.context.is_empty() {
+            "Nothing matched; answering from general knowledge"
+        } else {
+            "No chunks matched; whole documents from full-text search were used"
+        };
+        println!("  {}\n", note.dimmed());
+        return Ok(());
+    }
+
+    for (i, found) in retrieval.chunks.iter().enumerate() {
+        let Some(chunk) = chunk_store.get(found.chunk_id)? else {
+            continue;
+        };
+        let filename = doc_store
+            .get(chunk.document_id)?
+            .map(|d| d.filename)
+            .unwrap_or_else(|| "Unknown".to_string());
+
+        let mut why = Vec::new();
+        if found.keyword {
+            why.push("keyword".to_string());
+        }
+        if let Some(similarity) = found.similarity {
+            why.push(format!("similarity {:.3}", similarity));
+        }
+        if let Some(rerank) = found.rerank {
+            why.push(format!("rerank {:.3}", rerank));
+        }
+
+        println!(
+            "  {} {}{} {} {}",
+            format!("[{}]", i + 1).dimmed(),
+            filename.cyan(),
+            chunk.location(),
+            format!("(doc {}, chunk {})", chunk.document_id, chunk.chunk_index).dimmed(),
+            why.join(" · ").yellow()
+        );
+        println!("      {}", excerpt(&chunk.content, 160).dimmed());
+    }
+    println!();
+
+    Ok(())
+}
+
+/// The start of a chunk on one line
+fn excerpt(content: &str, max_chars: usize) -> String {
+    let line = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().count() <= max_chars {
+        return line;
+    }
+    let cut: String = line.chars().take(max_chars).collect();
+    format!("{}…", cut.trim_end())
+}
+
 /// Build context using hybrid search: semantic (embeddings) + keyword (LIKE) combined
+/// (optionally only from the documents in `scope`)
 fn build_semantic_context(
     chunk_store: &ChunkStore,
     doc_store: &DocumentStore,
     query: &str,
-    max_context_chars: usize,
-) -> Result<String> {
-    // Get all chunks with embeddings for semantic search
-    let chunks = chunk_store.get_all_with_embeddings()?;
-
-    if chunks.is_empty() {
-        return build_fts_context(doc_store, query, max_context_chars);
+    max_context_tokens: usize,
+    scope: Option<&HashSet<i64>>,
+) -> Result<Retrieval> {
+    if !chunk_store.has_embeddings()? {
+        let context = build_fts_context(doc_store, query, max_context_tokens, scope)?;
+        return Ok(Retrieval::documents(query, context));
     }
 
-    // --- Semantic search: find top 10 similar chunks ---
-    let semantic_ids: Vec<i64> = match embeddings::embed_text(query) {
-        Ok(query_embedding) => {
-            let chunk_embeddings: Vec<(i64, Vec<f32>)> = chunks
-                .iter()
-                .filter_map(|c| c.embedding.as_ref().map(|e| (c.id, e.clone())))
-                .collect();
-            let similar = embeddings::find_similar(&query_embedding, &chunk_embeddings, 10);
-            similar.iter().map(|(id, _)| *id).collect()
+    // --- Semantic search: 10 of the nearest chunks, re-selected with MMR so
+    // near-duplicates from the same page don't crowd out other sources ---
+    let query_embedding = embeddings::embed_text(query).ok();
+    let candidates = match (scope, &query_embedding) {
+        // A topic is usually a handful of documents: rank their chunks directly
+        (Some(scope), _) => {
+            let mut scoped = Vec::new();
+            for document_id in scope {
+                scoped.extend(chunk_store.get_for_document(*document_id)?);
+            }
+            let mut ranked = crate::search::rank_chunks(query, query_embedding.as_deref(), scoped);
+            ranked.truncate(crate::search::MMR_CANDIDATES);
+            ranked
         }
-        Err(_) => Vec::new(),
+        (None, Some(query_embedding)) => chunk_store
+            .nearest(query_embedding, crate::search::MMR_CANDIDATES)
+            .unwrap_or_default(),
+        (None, None) => Vec::new(),
     };
+    let selected = crate::search::mmr_select(candidates, 10, crate::search::MMR_LAMBDA);
+    let similarities: HashMap<i64, f32> = selected.iter().map(|(c, s)| (c.id, *s)).collect();
+    let chunks: Vec<StoredChunk> = selected.into_iter().map(|(chunk, _)| chunk).collect();
+    let semantic_ids: Vec<i64> = chunks.iter().map(|c| c.id).collect();
 
     // --- Keyword search: find chunks containing query terms ---
-    let keyword_chunks = chunk_store.search_content(query, 10).unwrap_or_default();
+    let keyword_chunks: Vec<StoredChunk> = match scope {
+        Some(scope) => chunk_store
+            .search_content(query, 500)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|c| scope.contains(&c.document_id))
+            .take(10)
+            .collect(),
+        None => chunk_store.search_content(query, 10).unwrap_or_default(),
+    };
     let keyword_ids: Vec<i64> = keyword_chunks.iter().map(|c| c.id).collect();
 
     // --- Merge results: keyword hits first (more precise), then semantic ---
-    let mut seen = std::collections::HashSet::new();
+    let mut seen = HashSet::new();
     let mut merged_ids: Vec<i64> = Vec::new();
 
     // Keyword results are more precise for specific references (exercise 0.3, page 26, etc.)
@@ -365,7 +1517,8 @@ fn build_semantic_context(
     }
 
     if merged_ids.is_empty() {
-        return build_fts_context(doc_store, query, max_context_chars);
+        let context = build_fts_context(doc_store, query, max_context_tokens, scope)?;
+        return Ok(Retrieval::documents(query, context));
     }
 
     // Collect matched chunks for dedup — from both the loaded chunks and keyword results
@@ -379,55 +1532,103 @@ fn build_semantic_context(
         }
     }
 
+    // Let the cross-encoder pick the best of the merged candidates, if enabled
+    let mut rerank_scores: HashMap<i64, f32> = HashMap::new();
+    if let Some(reranker) = embeddings::rerank::configured() {
+        match crate::search::rerank_chunks(
+            reranker,
+            query,
+            &matched_chunks,
+            crate::search::RERANK_KEEP,
+        ) {
+            Ok(reranked) => {
+                matched_chunks = reranked
+                    .into_iter()
+                    .map(|(id, content, score)| {
+                        rerank_scores.insert(id, score);
+                        (id, content)
+                    })
+                    .collect();
+            }
+            Err(e) => eprintln!("{} Reranking skipped: {}", "Note:".yellow(), e),
+        }
+    }
+
     // Deduplicate chunks with overlapping content
     let deduped = crate::search::deduplicate_chunks(matched_chunks);
 
     // Build context from deduped chunks
     let mut context = String::new();
-    let mut total_chars = 0;
+    let mut total_tokens = 0;
+    let mut used = Vec::new();
 
     for (chunk_id, content) in &deduped {
-        if total_chars >= max_context_chars {
+        if total_tokens >= max_context_tokens {
             break;
         }
 
         // Find original chunk for metadata — check both sources
         let chunk = chunks.iter().find(|c| c.id == *chunk_id);
         let kw_chunk = keyword_chunks.iter().find(|c| c.id == *chunk_id);
-        let (doc_id, chunk_idx) = chunk
+        let (doc_id, chunk_idx, location) = chunk
             .or(kw_chunk)
-            .map(|c| (c.document_id, c.chunk_index))
-            .unwrap_or((0, 0));
+            .map(|c| (c.document_id, c.chunk_index, c.location()))
+            .unwrap_or((0, 0, String::new()));
 
         let doc = doc_store.get(doc_id)?;
+        // Source code keeps its language so the model can quote it in a fenced block
+        let language = doc
+            .as_ref()
+            .map(|d| d.content_type.clone())
+            .filter(|t| ingest::code::is_code_type(t));
         let filename = doc
             .map(|d| d.filename)
             .unwrap_or_else(|| "Unknown".to_string());
+        let filename = format!("{}{}", filename, location);
+
+        let remaining = max_context_tokens - total_tokens;
+        let truncated = truncate_content(content, tokens::fit(content, remaining.min(500)));
+        let before = context.len();
+
+        match &language {
+            Some(language) => context.push_str(&format!(
+                "--- Document: {} (chunk {}) ---\n```{}\n{}\n```\n\n",
+                filename, chunk_idx, language, truncated
+            )),
+            None => context.push_str(&format!(
+                "--- Document: {} (chunk {}) ---\n{}\n\n",
+                filename, chunk_idx, truncated
+            )),
+        }
 
-        let remaining = max_context_chars - total_chars;
-        let truncated = truncate_content(content, remaining.min(2000));
-
-        context.push_str(&format!(
-            "--- Document: {} (chunk {}) ---\n{}\n\n",
-            filename, chunk_idx, truncated
-        ));
-
-        total_chars += truncated.len() + filename.len() + 50;
+        used.push(Retrieved {
+            chunk_id: *chunk_id,
+            keyword: keyword_ids.contains(chunk_id),
+            similarity: similarities.get(chunk_id).copied(),
+            rerank: rerank_scores.get(chunk_id).copied(),
+        });
+        total_tokens += tokens::estimate(&context[before..]);
     }
 
-    Ok(context)
+    Ok(Retrieval {
+        query: query.to_string(),
+        context,
+        chunks: used,
+    })
 }
 
 /// Build context using full-text search (fallback) with dynamic sizing
 fn build_fts_context(
     store: &DocumentStore,
     query: &str,
-    max_context_chars: usize,
+    max_context_tokens: usize,
+    scope: Option<&HashSet<i64>>,
 ) -> Result<String> {
-    let results = store.search(query)?;
+    let in_scope = |doc: &Document| scope.is_none_or(|s| s.contains(&doc.id));
+    let results: Vec<Document> = store.search(query)?.into_iter().filter(in_scope).collect();
 
     if results.is_empty() {
-        let all_docs = store.list()?;
+        let all_docs: Vec<Document> = store.list()?.into_iter().filter(in_scope).collect();
         if all_docs.is_empty() {
             return Ok(String::new());
         }
@@ -444,22 +1645,19 @@ fn build_fts_context(
     }
 
     let mut context = String::new();
-    let mut total_chars = 0;
+    let mut total_tokens = 0;
 
     for doc in results.iter().take(5) {
-        if total_chars >= max_context_chars {
+        if total_tokens >= max_context_tokens {
             break;
         }
 
-        let remaining = max_context_chars - total_chars;
-        let preview = truncate_content(&doc.content, remaining.min(2000));
-
-        context.push_str(&format!(
-            "--- Document: {} ---\n{}\n\n",
-            doc.filename, preview
-        ));
+        let remaining = max_context_tokens - total_tokens;
+        let preview = truncate_content(&doc.content, tokens::fit(&doc.content, remaining.min(500)));
+        let block = format!("--- Document: {} ---\n{}\n\n", doc.filename, preview);
 
-        total_chars += preview.len() + doc.filename.len() + 30;
+        total_tokens += tokens::estimate(&block);
+        context.push_str(&block);
     }
 
     Ok(context)
@@ -487,3 +1685,98 @@ fn truncate_content(content: &str, max_len: usize) -> String {
 
     format!("{}...", truncated)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: i64, document_id: i64, page: Option<i64>) -> StoredChunk {
+        StoredChunk {
+            id,
+            document_id,
+            chunk_index: id,
+            content: String::new(),
+            embedding: None,
+            section: None,
+            page,
+            start_time: None,
+        }
+    }
+
+    #[test]
+    fn test_split_questions() {
+        assert_eq!(split_questions("what is atp?\n"), vec!["what is atp?"]);
+        assert_eq!(
+            split_questions("first\nstill first\n\n\n  \nsecond\n"),
+            vec!["first\nstill first", "second"]
+        );
+        assert!(split_questions("\n \n").is_empty());
+    }
+
+    #[test]
+    fn test_messages_to_summarize() {
+        // Within budget, or too few messages to fold any: nothing to do
+        assert_eq!(messages_to_summarize(&[100; 10], 5000), None);
+        assert_eq!(messages_to_summarize(&[5000; 6], 1000), None);
+        // Over budget: everything but the latest messages
+        assert_eq!(messages_to_summarize(&[1000; 10], 5000), Some(4));
+    }
+
+    #[tokio::test]
+    async fn test_compact_history_with_mock_provider() {
+        crate::embeddings::use_mock_backend();
+        let db = Database::open_in_memory().unwrap();
+        let conv_store = ConversationStore::new(&db);
+        let conversation_id = conv_store.create(None).unwrap();
+        // Ten ~300-token messages outgrow a third of the mock's 8192 tokens
+        let ids: Vec<i64> = (0..10)
+            .map(|i| {
+                let role = if i % 2 == 0 { "user" } else { "assistant" };
+                conv_store
+                    .add_message(conversation_id, role, &"word ".repeat(240))
+                    .unwrap()
+            })
+            .collect();
+
+        let client = crate::llm::MockProvider::new();
+        assert!(
+            compact_history(&client, &conv_store, conversation_id)
+                .await
+                .unwrap()
+        );
+
+        let (summary, through) = conv_store.summary(conversation_id).unwrap().unwrap();
+        assert!(summary.starts_with("This is a canned reply from the mock provider"));
+        assert_eq!(through, ids[3]);
+        // The summary now stands in for those messages: nothing more to fold
+        assert!(
+            !compact_history(&client, &conv_store, conversation_id)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_cited_sources() {
+        let answer = "Cells make ATP [Source: bio.pdf, p. 12]. Also see \
+                      [Source: bio.pdf, p. 12; Source: notes.md] and [Source: lecture.mp4 @ 14:32].";
+        assert_eq!(
+            cited_sources(answer),
+            vec!["bio.pdf, p. 12", "notes.md", "lecture.mp4 @ 14:32"]
+        );
+    }
+
+    #[test]
+    fn test_match_citation_prefers_cited_page() {
+        let context = vec![
+            (chunk(1, 10, Some(3)), "bio.pdf".to_string()),
+            (chunk(2, 10, Some(12)), "bio.pdf".to_string()),
+            (chunk(3, 11, None), "notes.md".to_string()),
+        ];
+
+        assert_eq!(match_citation("bio.pdf, p. 12", &context).unwrap().id, 2);
+        assert_eq!(match_citation("bio.pdf", &context).unwrap().id, 1);
+        assert_eq!(match_citation("Notes.md", &context).unwrap().id, 3);
+        assert!(match_citation("history.pdf, p. 1", &context).is_none());
+    }
+}
@@ -0,0 +1,393 @@
+use anyhow::Result;
+use chrono::Datelike;
+use clap::ValueEnum;
+use colored::Colorize;
+use inquire::Text;
+use std::collections::HashMap;
+
+use crate::storage::{Citation, CitationStore, Database, Document, DocumentStore};
+
+/// Output format for citations
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum CiteFormat {
+    Bibtex,
+    Apa,
+}
+
+/// Print citations for one document or the whole bucket
+pub async fn run(id: Option<i64>, all: bool, format: CiteFormat) -> Result<()> {
+    let db = Database::open()?;
+    let doc_store = DocumentStore::new(&db);
+    let citation_store = CitationStore::new(&db);
+
+    let documents = if all {
+        doc_store.list()?
+    } else {
+        let id = match id {
+            Some(id) => id,
+            None => {
+                let id_str = Text::new("Document ID to cite:")
+                    .with_help_message("Or run with --all to cite every document")
+                    .prompt()?;
+                id_str
+                    .trim()
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid ID"))?
+            }
+        };
+
+        match doc_store.get(id)? {
+            Some(doc) => vec![doc],
+            None => {
+                println!("{} Document not found: {}", "✗".red(), id);
+                return Ok(());
+            }
+        }
+    };
+
+    if documents.is_empty() {
+        println!("{}", "No documents to cite.".dimmed());
+        return Ok(());
+    }
+
+    let citations: Vec<Citation> = documents
+        .iter()
+        .map(|doc| {
+            Ok(citation_store
+                .get(doc.id)?
+                .unwrap_or_else(|| fallback_citation(doc)))
+        })
+        .collect::<Result<_>>()?;
+    let entries: Vec<String> = match format {
+        CiteFormat::Bibtex => to_bibtex(&citations),
+        CiteFormat::Apa => citations.iter().map(to_apa).collect(),
+    };
+
+    // Plain output so it can be redirected straight into a .bib file
+    println!("{}", entries.join("\n\n"));
+
+    Ok(())
+}
+
+/// Build a best-effort citation for a document with no stored metadata
+fn fallback_citation(doc: &Document) -> Citation {
+    let is_web = doc.source_path.starts_with("http://") || doc.source_path.starts_with("https://");
+
+    let title = if is_web {
+        doc.filename.clone()
+    } else {
+        std::path::Path::new(&doc.filename)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| doc.filename.clone())
+    };
+
+    Citation {
+        document_id: doc.id,
+        entry_type: if is_web { "online" } else { "misc" }.to_string(),
+        title,
+        authors: Vec::new(),
+        year: Some(doc.created_at.year()),
+        venue: None,
+        doi: find_doi(&doc.content),
+        url: is_web.then(|| doc.source_path.clone()),
+        arxiv_id: None,
+    }
+}
+
+/// Find the first DOI mentioned in a document's text
+fn find_doi(text: &str) -> Option<String> {
+    text.split_whitespace().find_map(|word| {
+        let start = word.find("10.")?;
+        let candidate = word[start..].trim_end_matches(['.', ',', ';', ')', ']', '"']);
+        let (prefix, suffix) = candidate.split_once('/')?;
+        let registrant = &prefix[3..];
+        let valid = registrant.len() >= 4
+            && registrant.chars().all(|c| c.is_ascii_digit() || c == '.')
+            && !suffix.is_empty();
+        valid.then(|| candidate.to_string())
+    })
+}
+
+/// Generate a citation key like `vaswani2017attention`
+fn citation_key(citation: &Citation) -> String {
+    let author = citation
+        .authors
+        .first()
+        .map(|a| last_name(a))
+        .unwrap_or_else(|| "doc".to_string());
+    let year = citation.year.map(|y| y.to_string()).unwrap_or_default();
+    let word = citation
+        .title
+        .split_whitespace()
+        .map(|w| {
+            w.chars()
+                .filter(|c| c.is_alphanumeric())
+                .collect::<String>()
+        })
+        .find(|w| w.len() > 3)
+        .unwrap_or_else(|| citation.document_id.to_string());
+
+    format!("{}{}{}", author, year, word)
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect()
+}
+
+/// Citation keys for a whole export, with `a`, `b`, ... appended to keys that
+/// would otherwise repeat (BibTeX tools reject or merge duplicate keys)
+fn unique_keys(citations: &[Citation]) -> Vec<String> {
+    let keys: Vec<String> = citations.iter().map(citation_key).collect();
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for key in &keys {
+        *counts.entry(key).or_insert(0) += 1;
+    }
+
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    keys.iter()
+        .map(|key| {
+            if counts[key.as_str()] == 1 {
+                return key.clone();
+            }
+            let n = seen.entry(key).or_insert(0);
+            *n += 1;
+            format!("{}{}", key, key_suffix(*n - 1))
+        })
+        .collect()
+}
+
+/// `a`..`z`, then `aa`, `ab`, ...
+fn key_suffix(mut n: usize) -> String {
+    let mut suffix = Vec::new();
+    loop {
+        suffix.push(b'a' + (n % 26) as u8);
+        if n < 26 {
+            break;
+        }
+        n = n / 26 - 1;
+    }
+    suffix.reverse();
+    String::from_utf8(suffix).unwrap_or_default()
+}
+
+/// Escape characters that are special in BibTeX/LaTeX field text
+fn escape_bibtex(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '{' | '}' | '%' | '&' | '#' | '_' | '$') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Extract the last name from "First Last" or "Last, First"
+fn last_name(author: &str) -> String {
+    match author.split_once(',') {
+        Some((last, _)) => last.trim().to_string(),
+        None => author
+            .split_whitespace()
+            .last()
+            .unwrap_or(author)
+            .to_string(),
+    }
+}
+
+/// Format citations as BibTeX entries, each under its own key
+fn to_bibtex(citations: &[Citation]) -> Vec<String> {
+    citations
+        .iter()
+        .zip(unique_keys(citations))
+        .map(|(citation, key)| bibtex_entry(citation, &key))
+        .collect()
+}
+
+/// Format a citation as a BibTeX entry under the given key
+fn bibtex_entry(citation: &Citation, key: &str) -> String {
+    let mut fields = vec![("title", format!("{{{}}}", escape_bibtex(&citation.title)))];
+
+    if !citation.authors.is_empty() {
+        let authors: Vec<String> = citation.authors.iter().map(|a| escape_bibtex(a)).collect();
+        fields.push(("author", authors.join(" and ")));
+    }
+    if let Some(year) = citation.year {
+        fields.push(("year", year.to_string()));
+    }
+    if let Some(venue) = &citation.venue {
+        let field = if citation.entry_type == "article" {
+            "journal"
+        } else {
+            "howpublished"
+        };
+        fields.push((field, escape_bibtex(venue)));
+    }
+    if let Some(doi) = &citation.doi {
+        fields.push(("doi", doi.clone()));
+    }
+    if let Some(arxiv_id) = &citation.arxiv_id {
+        fields.push(("eprint", arxiv_id.clone()));
+        fields.push(("archivePrefix", "arXiv".to_string()));
+    }
+    if let Some(url) = &citation.url {
+        // URLs stay verbatim for the url package; only braces would unbalance the field
+        fields.push(("url", url.replace('{', "%7B").replace('}', "%7D")));
+    }
+
+    let body = fields
+        .iter()
+        .map(|(name, value)| format!("  {} = {{{}}}", name, value))
+        .collect::<Vec<_>>()
+        .join(",\n");
+
+    format!("@{}{{{},\n{}\n}}", citation.entry_type, key, body)
+}
+
+/// Format a citation in APA style
+pub fn to_apa(citation: &Citation) -> String {
+    let authors: Vec<String> = citation.authors.iter().map(|a| apa_author(a)).collect();
+    let author_part = match authors.len() {
+        0 => String::new(),
+        1 => authors[0].clone(),
+        n => format!("{}, & {}", authors[..n - 1].join(", "), authors[n - 1]),
+    };
+
+    let year = citation
+        .year
+        .map(|y| y.to_string())
+        .unwrap_or_else(|| "n.d.".to_string());
+
+    let mut out = if author_part.is_empty() {
+        format!("{}. ({}).", citation.title, year)
+    } else {
+        format!("{} ({}). {}.", author_part, year, citation.title)
+    };
+
+    if let Some(venue) = &citation.venue {
+        out.push_str(&format!(" {}.", venue));
+    }
+    if let Some(arxiv_id) = &citation.arxiv_id {
+        out.push_str(&format!(" arXiv:{}.", arxiv_id));
+    }
+    if let Some(doi) = &citation.doi {
+        out.push_str(&format!(" https://doi.org/{}", doi));
+    } else if let Some(url) = &citation.url {
+        out.push_str(&format!(" {}", url));
+    }
+
+    out
+}
+
+/// Format an author as "Last, F. M."
+fn apa_author(author: &str) -> String {
+    let (last, first) = match author.split_once(',') {
+        Some((last, first)) => (last.trim(), first.trim()),
+        None => {
+            let mut parts: Vec<&str> = author.split_whitespace().collect();
+            let last = parts.pop().unwrap_or(author);
+            return apa_author(&format!("{}, {}", last, parts.join(" ")));
+        }
+    };
+
+    let initials: Vec<String> = first
+        .split_whitespace()
+        .filter_map(|name| name.chars().next())
+        .map(|c| format!("{}.", c))
+        .collect();
+
+    if initials.is_empty() {
+        last.to_string()
+    } else {
+        format!("{}, {}", last, initials.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Citation {
+        Citation {
+            document_id: 1,
+            entry_type: "article".to_string(),
+            title: "Attention Is All You Need".to_string(),
+            authors: vec!["Ashish Vaswani".to_string(), "Noam Shazeer".to_string()],
+            year: Some(2017),
+            venue: Some("NeurIPS".to_string()),
+            doi: None,
+            url: None,
+            arxiv_id: Some("1706.03762".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_bibtex_entry() {
+        let bib = &to_bibtex(&[sample()])[0];
+        assert!(bib.starts_with("@article{vaswani2017attention,"));
+        assert!(bib.contains("author = {Ashish Vaswani and Noam Shazeer}"));
+        assert!(bib.contains("eprint = {1706.03762}"));
+    }
+
+    #[test]
+    fn test_duplicate_keys_get_suffixes() {
+        let lecture = Citation {
+            document_id: 2,
+            entry_type: "misc".to_string(),
+            title: "Lecture notes".to_string(),
+            authors: Vec::new(),
+            year: Some(2026),
+            venue: None,
+            doi: None,
+            url: None,
+            arxiv_id: None,
+        };
+        let citations = vec![
+            lecture.clone(),
+            sample(),
+            Citation {
+                document_id: 3,
+                ..lecture
+            },
+        ];
+
+        let keys: Vec<String> = to_bibtex(&citations)
+            .iter()
+            .map(|entry| entry[entry.find('{').unwrap() + 1..entry.find(',').unwrap()].to_string())
+            .collect();
+        assert_eq!(
+            keys,
+            vec!["doc2026lecturea", "vaswani2017attention", "doc2026lectureb"]
+        );
+        assert_eq!(key_suffix(25), "z");
+        assert_eq!(key_suffix(26), "aa");
+    }
+
+    #[test]
+    fn test_bibtex_escapes_special_characters() {
+        let citation = Citation {
+            title: "Q&A on 100% of C# {fast_paths}".to_string(),
+            ..sample()
+        };
+        let bib = &to_bibtex(&[citation])[0];
+        assert!(bib.contains(r"title = {{Q\&A on 100\% of C\# \{fast\_paths\}}}"));
+    }
+
+    #[test]
+    fn test_apa_entry() {
+        let apa = to_apa(&sample());
+        assert_eq!(
+            apa,
+            "Vaswani, A., & Shazeer, N. (2017). Attention Is All You Need. NeurIPS. arXiv:1706.03762."
+        );
+    }
+
+    #[test]
+    fn test_find_doi() {
+        assert_eq!(
+            find_doi("Published as doi:10.1145/3292500.3330701."),
+            Some("10.1145/3292500.3330701".to_string())
+        );
+        assert_eq!(find_doi("version 10.2 of the spec"), None);
+    }
+}
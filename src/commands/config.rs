@@ -1,9 +1,15 @@
 use anyhow::Result;
 use colored::Colorize;
-use inquire::{Password, Select};
+use inquire::{MultiSelect, Password, Select, Text};
 
-use crate::config::Config;
-use crate::llm::GroqClient;
+use crate::config::{
+    CanvasConfig, Config, EmbeddingDevice, ImageReader, Provider, Reranker, TranscriptionBackend,
+};
+use crate::ingest::{ChunkConfig, ChunkStrategy, ocr};
+use crate::llm::local_whisper::LocalWhisper;
+use crate::llm::whisper::{self, TranscribeOptions};
+use crate::llm::{AnthropicClient, ChatProvider, GroqClient, OpenAiClient, models};
+use crate::llm::{anthropic, ollama};
 
 pub async fn run() -> Result<()> {
     println!();
@@ -34,6 +40,17 @@ pub async fn run() -> Result<()> {
     let options = vec![
         "🔑  Set API Key        │ Configure Groq API access",
         "🤖  Select Model       │ Choose default LLM",
+        "🧠  LLM Provider       │ Groq, Anthropic, Ollama or any OpenAI-compatible server",
+        "🌐  OCR Languages      │ Languages for scanned images",
+        "🎙️  Transcription      │ Groq API or local Whisper, language",
+        "🖼️  Image Reading      │ OCR, vision model, or both",
+        "✂️  Chunking           │ Chunk size, overlap and strategy",
+        "⚡  Embedding Device   │ Run local embeddings on CPU or GPU",
+        "🎯  Reranking          │ Re-score retrieved chunks before chat",
+        "📊  Token Usage        │ Tokens used today, shown after answers",
+        "❓  Missed Questions   │ Save quiz mistakes to the review deck automatically",
+        "🔐  Site Logins        │ Cookies/headers for course portals",
+        "🎓  Canvas             │ Course to pull with `add canvas`",
         "📋  View Settings      │ See current configuration",
         "←   Back",
     ];
@@ -63,6 +80,83 @@ pub async fn run() -> Result<()> {
                     eprintln!("{} {}", "Error:".red(), e);
                 }
             }
+            s if s.contains("LLM Provider") => {
+                if let Err(e) = select_provider(&mut config).await
+                    && !e.to_string().contains("cancelled")
+                {
+                    eprintln!("{} {}", "Error:".red(), e);
+                }
+            }
+            s if s.contains("OCR Languages") => {
+                if let Err(e) = select_ocr_languages(&mut config).await
+                    && !e.to_string().contains("cancelled")
+                {
+                    eprintln!("{} {}", "Error:".red(), e);
+                }
+            }
+            s if s.contains("Transcription") => {
+                if let Err(e) = select_transcription(&mut config)
+                    && !e.to_string().contains("cancelled")
+                {
+                    eprintln!("{} {}", "Error:".red(), e);
+                }
+            }
+            s if s.contains("Image Reading") => {
+                if let Err(e) = select_image_reader(&mut config)
+                    && !e.to_string().contains("cancelled")
+                {
+                    eprintln!("{} {}", "Error:".red(), e);
+                }
+            }
+            s if s.contains("Chunking") => {
+                if let Err(e) = set_chunking(&mut config)
+                    && !e.to_string().contains("cancelled")
+                {
+                    eprintln!("{} {}", "Error:".red(), e);
+                }
+            }
+            s if s.contains("Embedding Device") => {
+                if let Err(e) = select_embedding_device(&mut config)
+                    && !e.to_string().contains("cancelled")
+                {
+                    eprintln!("{} {}", "Error:".red(), e);
+                }
+            }
+            s if s.contains("Reranking") => {
+                if let Err(e) = select_reranker(&mut config)
+                    && !e.to_string().contains("cancelled")
+                {
+                    eprintln!("{} {}", "Error:".red(), e);
+                }
+            }
+            s if s.contains("Token Usage") => {
+                if let Err(e) = set_show_usage(&mut config)
+                    && !e.to_string().contains("cancelled")
+                {
+                    eprintln!("{} {}", "Error:".red(), e);
+                }
+            }
+            s if s.contains("Missed Questions") => {
+                if let Err(e) = set_quiz_save_missed(&mut config)
+                    && !e.to_string().contains("cancelled")
+                {
+                    eprintln!("{} {}", "Error:".red(), e);
+                }
+            }
+            s if s.contains("Site Logins") => {
+                if let Err(e) = add_site_login(&mut config)
+                    && !e.to_string().contains("cancelled")
+                {
+                    eprintln!("{} {}", "Error:".red(), e);
+                }
+            }
+            s if s.contains("Canvas") => {
+                if let Err(e) = set_canvas(&mut config)
+                    && !e.to_string().contains("cancelled")
+                {
+                    eprintln!("{} {}", "Error:".red(), e);
+                }
+            }
             s if s.contains("View Settings") => {
                 view_config(&config);
             }
@@ -101,20 +195,616 @@ async fn set_api_key(config: &mut Config) -> Result<()> {
 }
 
 async fn select_model(config: &mut Config) -> Result<()> {
-    let model_options: Vec<String> = GroqClient::MODELS
+    let client = GroqClient::new(config.get_api_key().unwrap_or_default(), None);
+    let model_id = pick_model(&client, "Select default model:").await?;
+
+    config.default_model = Some(model_id.clone());
+    config.save()?;
+
+    println!("{} Default model set to {}", "✓".green(), model_id.yellow());
+
+    Ok(())
+}
+
+async fn select_provider(config: &mut Config) -> Result<()> {
+    let options = vec![
+        "Groq - hosted models, needs an API key",
+        "Anthropic - Claude models, needs an API key",
+        "Ollama - models on this machine, works offline",
+        "OpenAI-compatible - OpenRouter, vLLM, LM Studio, llama.cpp server, ...",
+    ];
+    let choice = Select::new("Send chat and generation requests to:", options).prompt()?;
+
+    if choice.starts_with("Groq") {
+        config.provider = Provider::Groq;
+        config.save()?;
+        println!("{} Using {}", "✓".green(), "Groq".yellow());
+        if !config.has_api_key() {
+            println!(
+                "{} Set an API key with {} next",
+                "Tip:".yellow(),
+                "Set API Key".cyan()
+            );
+        }
+        return Ok(());
+    }
+
+    if choice.starts_with("Anthropic") {
+        return select_anthropic(config).await;
+    }
+    if choice.starts_with("OpenAI") {
+        return select_openai(config).await;
+    }
+
+    let url = Text::new("Ollama address:")
+        .with_default(&config.ollama_url())
+        .prompt()?;
+    let url = url.trim().trim_end_matches('/').to_string();
+
+    let default_model = config
+        .ollama_model
+        .clone()
+        .unwrap_or_else(|| ollama::DEFAULT_MODEL.to_string());
+    let model = match ollama::installed_models(&url).await {
+        Ok(models) if !models.is_empty() => Select::new("Model to chat with:", models).prompt()?,
+        Ok(_) => {
+            println!(
+                "{} No models pulled yet; try {}",
+                "Note:".yellow(),
+                format!("ollama pull {}", default_model).cyan()
+            );
+            Text::new("Model name:")
+                .with_default(&default_model)
+                .prompt()?
+        }
+        Err(e) => {
+            println!("{} {}", "Note:".yellow(), e);
+            Text::new("Model name:")
+                .with_default(&default_model)
+                .prompt()?
+        }
+    };
+    let model = model.trim().to_string();
+
+    config.provider = Provider::Ollama;
+    config.ollama_url = Some(url.clone());
+    config.ollama_model = Some(model.clone());
+    config.save()?;
+
+    println!(
+        "{} Using {} on Ollama at {}",
+        "✓".green(),
+        model.yellow(),
+        url
+    );
+
+    Ok(())
+}
+
+/// Choose from the models the provider lists right now (or the cached or
+/// built-in list when it can't be reached)
+async fn pick_model(client: &impl ChatProvider, prompt: &str) -> Result<String> {
+    let (available, source) = models::list(client).await;
+    match source {
+        models::Source::Live => {}
+        models::Source::Cache => println!(
+            "{} Couldn't reach {}; showing the models it listed last time",
+            "Note:".yellow(),
+            client.name()
+        ),
+        models::Source::BuiltIn => println!(
+            "{} Couldn't reach {}; showing the models The Librarian knows about",
+            "Note:".yellow(),
+            client.name()
+        ),
+    }
+
+    let known = client.models();
+    let model_options: Vec<String> = available
         .iter()
-        .map(|(id, desc, _ctx)| format!("{} - {}", id, desc))
+        .map(|m| {
+            let description = known
+                .iter()
+                .find(|(id, _, _)| *id == m.id)
+                .map(|(_, desc, _)| desc.to_string())
+                .or_else(|| {
+                    m.context_window
+                        .map(|ctx| format!("{}k context", ctx / 1024))
+                });
+            match description {
+                Some(description) => format!("{} - {}", m.id, description),
+                None => m.id.clone(),
+            }
+        })
         .collect();
 
-    let selection = Select::new("Select default model:", model_options).prompt()?;
+    let selection = Select::new(prompt, model_options).prompt()?;
 
     // Extract model ID from selection
-    let model_id = selection.split(" - ").next().unwrap().to_string();
+    Ok(selection.split(" - ").next().unwrap().to_string())
+}
 
-    config.default_model = Some(model_id.clone());
+async fn select_anthropic(config: &mut Config) -> Result<()> {
+    if config.anthropic_api_key().is_none() {
+        println!(
+            "\n{} Get your API key from {}",
+            "Tip:".yellow(),
+            "https://console.anthropic.com/settings/keys".cyan()
+        );
+        let key = Password::new("Enter your Anthropic API key:")
+            .without_confirmation()
+            .prompt()?;
+        if key.is_empty() {
+            println!("{}", "Cancelled.".dimmed());
+            return Ok(());
+        }
+        config.anthropic_api_key = Some(key);
+    }
+
+    let client = AnthropicClient::new(
+        config.anthropic_api_key().unwrap_or_default(),
+        anthropic::DEFAULT_MODEL.to_string(),
+    );
+    let model = pick_model(&client, "Model to chat with:").await?;
+
+    config.provider = Provider::Anthropic;
+    config.anthropic_model = Some(model.clone());
     config.save()?;
 
-    println!("{} Default model set to {}", "✓".green(), model_id.yellow());
+    println!("{} Using {} on Anthropic", "✓".green(), model.yellow());
+
+    Ok(())
+}
+
+async fn select_openai(config: &mut Config) -> Result<()> {
+    let mut url_prompt = Text::new("Base URL:")
+        .with_help_message("e.g. https://openrouter.ai/api/v1 or http://localhost:1234/v1");
+    if let Some(url) = config.openai_base_url.as_deref() {
+        url_prompt = url_prompt.with_default(url);
+    }
+    let url = url_prompt.prompt()?;
+    let url = url.trim().trim_end_matches('/').to_string();
+    if url.is_empty() {
+        println!("{}", "Cancelled.".dimmed());
+        return Ok(());
+    }
+
+    let key = Password::new("API key (leave empty if the server doesn't need one):")
+        .without_confirmation()
+        .prompt()?;
+
+    // Offer the server's own list when it has one
+    let api_key = Some(key.clone())
+        .filter(|k| !k.is_empty())
+        .or_else(|| config.openai_api_key());
+    let client = OpenAiClient::new(&url, api_key, String::new());
+    let model = match models::list(&client).await {
+        (listed, models::Source::Live) => {
+            let ids: Vec<String> = listed.into_iter().map(|m| m.id).collect();
+            Select::new("Model to chat with:", ids).prompt()?
+        }
+        _ => {
+            let mut model_prompt = Text::new("Model name:");
+            if let Some(model) = config.openai_model.as_deref() {
+                model_prompt = model_prompt.with_default(model);
+            }
+            model_prompt.prompt()?
+        }
+    };
+    let model = model.trim().to_string();
+    if model.is_empty() {
+        println!("{}", "Cancelled.".dimmed());
+        return Ok(());
+    }
+
+    config.provider = Provider::OpenAi;
+    config.openai_base_url = Some(url.clone());
+    if !key.is_empty() {
+        config.openai_api_key = Some(key);
+    }
+    config.openai_model = Some(model.clone());
+    config.save()?;
+
+    println!("{} Using {} at {}", "✓".green(), model.yellow(), url);
+
+    Ok(())
+}
+
+async fn select_ocr_languages(config: &mut Config) -> Result<()> {
+    let installed: Vec<String> = ocr::installed_languages()
+        .await?
+        .into_iter()
+        .filter(|lang| lang != "osd")
+        .collect();
+
+    if installed.is_empty() {
+        println!(
+            "{} No Tesseract language packs found. Install tesseract-ocr first.",
+            "⚠".yellow()
+        );
+        return Ok(());
+    }
+
+    let current = config.ocr_languages();
+    let defaults: Vec<usize> = installed
+        .iter()
+        .enumerate()
+        .filter(|(_, lang)| current.split('+').any(|c| c == lang.as_str()))
+        .map(|(i, _)| i)
+        .collect();
+
+    let selected = MultiSelect::new("OCR languages:", installed)
+        .with_default(&defaults)
+        .with_help_message("Pick every language your scans contain")
+        .prompt()?;
+
+    if selected.is_empty() {
+        println!("{}", "Cancelled.".dimmed());
+        return Ok(());
+    }
+
+    let languages = selected.join("+");
+    config.ocr_languages = Some(languages.clone());
+    config.save()?;
+
+    println!(
+        "{} OCR languages set to {}",
+        "✓".green(),
+        languages.yellow()
+    );
+
+    Ok(())
+}
+
+fn select_transcription(config: &mut Config) -> Result<()> {
+    let options = vec![
+        "Groq API - fast, needs an API key",
+        "Local Whisper - offline, audio stays on this machine",
+    ];
+    let selection = Select::new("Transcribe audio with:", options).prompt()?;
+
+    if selection.starts_with("Groq") {
+        config.transcription_backend = TranscriptionBackend::Groq;
+        config.save()?;
+        println!(
+            "{} Transcription set to {}",
+            "✓".green(),
+            "Groq API".yellow()
+        );
+        return set_transcription_language(config);
+    }
+
+    if !LocalWhisper::is_available() {
+        println!(
+            "{} This build doesn't include local transcription. Reinstall with {}",
+            "⚠".yellow(),
+            "cargo install --path . --features local-whisper".cyan()
+        );
+    }
+
+    let default_path = config.whisper_model_path()?.display().to_string();
+    let model_path = Text::new("Path to ggml Whisper model:")
+        .with_default(&default_path)
+        .prompt()?;
+
+    config.transcription_backend = TranscriptionBackend::Local;
+    config.whisper_model_path = Some(model_path.into());
+    config.save()?;
+
+    println!(
+        "{} Transcription set to {}",
+        "✓".green(),
+        "local Whisper".yellow()
+    );
+
+    set_transcription_language(config)
+}
+
+fn set_transcription_language(config: &mut Config) -> Result<()> {
+    let current = config.whisper_language.clone().unwrap_or_default();
+    let language = Text::new("Spoken language (ISO code like \"es\", empty to auto-detect):")
+        .with_default(&current)
+        .prompt()?;
+    let translate = Select::new("Translate recordings to English?", vec!["No", "Yes"]).prompt()?;
+
+    config.whisper_language = whisper::normalize_language(Some(&language));
+    config.whisper_translate = translate == "Yes";
+    config.save()?;
+
+    println!(
+        "{} Recordings: {}",
+        "✓".green(),
+        TranscribeOptions::from_config(config).describe().yellow()
+    );
+
+    Ok(())
+}
+
+fn select_image_reader(config: &mut Config) -> Result<()> {
+    let options = vec![
+        "Auto - Tesseract, then the vision model if it finds no text",
+        "OCR only - Tesseract, images stay on this machine",
+        "Vision model - describes diagrams and reads handwriting",
+    ];
+
+    let choice = Select::new("How should images be read?", options).prompt()?;
+
+    config.image_reader = match choice {
+        c if c.starts_with("Auto") => ImageReader::Auto,
+        c if c.starts_with("OCR") => ImageReader::Ocr,
+        _ => ImageReader::Vision,
+    };
+
+    if config.image_reader != ImageReader::Ocr {
+        let model = Text::new("Vision model:")
+            .with_default(&config.vision_model())
+            .prompt()?;
+        config.vision_model = Some(model.trim().to_string()).filter(|m| !m.is_empty());
+
+        if !config.has_api_key() {
+            println!(
+                "{} The vision model needs a Groq API key (Set API Key)",
+                "Note:".yellow()
+            );
+        }
+    }
+
+    config.save()?;
+    println!("{} Image reading set to {}", "✓".green(), choice.yellow());
+
+    Ok(())
+}
+
+fn set_chunking(config: &mut Config) -> Result<()> {
+    let current = ChunkConfig::from_config(config);
+
+    let chunk_size = inquire::CustomType::<usize>::new("Chunk size (characters):")
+        .with_default(current.chunk_size)
+        .with_help_message("Smaller chunks give more precise search hits; larger ones more context")
+        .prompt()?;
+    let overlap = inquire::CustomType::<usize>::new("Overlap (characters):")
+        .with_default(current.overlap)
+        .with_help_message("Text repeated between neighbouring chunks")
+        .prompt()?;
+
+    let options = vec![
+        "Structured - follow chapters, tables and code definitions",
+        "Fixed - plain windows of the chunk size",
+    ];
+    let strategy = Select::new("Chunking strategy:", options).prompt()?;
+
+    config.chunk_size = Some(chunk_size);
+    config.chunk_overlap = Some(overlap);
+    config.chunk_strategy = if strategy.starts_with("Fixed") {
+        ChunkStrategy::Fixed
+    } else {
+        ChunkStrategy::Structured
+    };
+    config.save()?;
+
+    let saved = ChunkConfig::from_config(config);
+    println!(
+        "{} Chunks: {} chars, {} overlap ({:?}). Applies to newly added documents.",
+        "✓".green(),
+        saved.chunk_size,
+        saved.overlap,
+        saved.strategy
+    );
+
+    Ok(())
+}
+
+fn select_embedding_device(config: &mut Config) -> Result<()> {
+    let options = vec![
+        "CPU - works everywhere, uses every core",
+        "CUDA - NVIDIA GPUs (build with --features cuda)",
+        "CoreML - Apple silicon (build with --features coreml)",
+        "DirectML - GPUs on Windows (build with --features directml)",
+    ];
+
+    let choice = Select::new("Run local embeddings on:", options).prompt()?;
+
+    config.embedding_device = match choice {
+        c if c.starts_with("CUDA") => EmbeddingDevice::Cuda,
+        c if c.starts_with("CoreML") => EmbeddingDevice::CoreMl,
+        c if c.starts_with("DirectML") => EmbeddingDevice::DirectMl,
+        _ => EmbeddingDevice::Cpu,
+    };
+    config.save()?;
+
+    println!(
+        "{} Embeddings will run on {} (falls back to CPU if unavailable)",
+        "✓".green(),
+        config.embedding_device.name().yellow()
+    );
+
+    Ok(())
+}
+
+fn select_reranker(config: &mut Config) -> Result<()> {
+    let options = vec![
+        "Off - use the retrieved chunks as they are",
+        "bge-reranker-base - English, good all-rounder (~1 GB download)",
+        "jina-reranker-v1-turbo-en - English, fastest (~150 MB download)",
+        "bge-reranker-v2-m3 - multilingual, most accurate (~2 GB download)",
+    ];
+
+    let choice = Select::new("Rerank chat context with:", options).prompt()?;
+
+    config.reranker = match choice {
+        c if c.starts_with("bge-reranker-base") => Reranker::Bge,
+        c if c.starts_with("jina") => Reranker::Jina,
+        c if c.starts_with("bge-reranker-v2-m3") => Reranker::BgeM3,
+        _ => Reranker::Off,
+    };
+    config.save()?;
+
+    println!(
+        "{} Reranking: {}",
+        "✓".green(),
+        config.reranker.name().yellow()
+    );
+
+    Ok(())
+}
+
+fn set_quiz_save_missed(config: &mut Config) -> Result<()> {
+    let choice = Select::new(
+        "After a quiz, save the questions you missed for review without asking?",
+        vec!["No", "Yes"],
+    )
+    .prompt()?;
+    config.quiz_save_missed = choice == "Yes";
+    config.save()?;
+
+    println!(
+        "{} Missed questions {}",
+        "✓".green(),
+        if config.quiz_save_missed {
+            "saved automatically"
+        } else {
+            "saved when you confirm"
+        }
+    );
+
+    Ok(())
+}
+
+fn set_show_usage(config: &mut Config) -> Result<()> {
+    print_usage_today();
+
+    let choice = Select::new(
+        "Show token counts after each chat answer?",
+        vec!["No", "Yes"],
+    )
+    .prompt()?;
+    config.show_usage = choice == "Yes";
+    config.save()?;
+
+    println!(
+        "{} Token counts {}",
+        "✓".green(),
+        if config.show_usage {
+            "shown after answers"
+        } else {
+            "hidden"
+        }
+    );
+
+    Ok(())
+}
+
+/// Tokens used today, per model
+fn print_usage_today() {
+    let usage = crate::storage::usage::open().and_then(|db| {
+        crate::storage::UsageStore::new(&db).for_day(&crate::storage::usage::today())
+    });
+    match usage {
+        Ok(models) if models.is_empty() => {
+            println!("\n{}\n", "No tokens used today.".dimmed())
+        }
+        Ok(models) => {
+            println!("\n{}", "Used today:".bold());
+            for day in models {
+                println!(
+                    "  {:<28} {} tokens {}",
+                    day.model,
+                    day.total_tokens(),
+                    format!(
+                        "({} prompt + {} completion, {} requests)",
+                        day.prompt_tokens, day.completion_tokens, day.requests
+                    )
+                    .dimmed()
+                );
+            }
+            println!();
+        }
+        Err(e) => eprintln!("{} Could not read token usage: {}", "Note:".yellow(), e),
+    }
+}
+
+fn add_site_login(config: &mut Config) -> Result<()> {
+    println!(
+        "\n{} Export cookies.txt from a logged-in browser (e.g. a \"Get cookies.txt\" extension)",
+        "Tip:".yellow()
+    );
+
+    let domain = Text::new("Site domain (e.g. canvas.myuni.edu):").prompt()?;
+    let domain = domain.trim().to_lowercase();
+    if domain.is_empty() {
+        println!("{}", "Cancelled.".dimmed());
+        return Ok(());
+    }
+
+    let cookies_file = Text::new("Path to cookies.txt (leave empty to skip):").prompt()?;
+    let header = Text::new("Extra header as \"Name: value\" (leave empty to skip):").prompt()?;
+
+    let mut auth = config.sites.get(&domain).cloned().unwrap_or_default();
+    if !cookies_file.trim().is_empty() {
+        auth.cookies_file = Some(cookies_file.trim().into());
+    }
+    if let Some((name, value)) = header.split_once(':') {
+        auth.headers
+            .insert(name.trim().to_string(), value.trim().to_string());
+    }
+
+    if auth.cookies_file.is_none() && auth.headers.is_empty() {
+        println!("{}", "Nothing to save.".dimmed());
+        return Ok(());
+    }
+
+    config.sites.insert(domain.clone(), auth);
+    config.save()?;
+
+    println!("{} Saved login for {}", "✓".green(), domain.yellow());
+
+    Ok(())
+}
+
+fn set_canvas(config: &mut Config) -> Result<()> {
+    println!(
+        "\n{} Create a token in Canvas under Account → Settings → New Access Token",
+        "Tip:".yellow()
+    );
+
+    let current = config.canvas.clone().unwrap_or_default();
+
+    let base_url = Text::new("Canvas URL:")
+        .with_default(&current.base_url)
+        .with_help_message("e.g. https://canvas.myuni.edu")
+        .prompt()?;
+    let course_id = Text::new("Course ID:")
+        .with_default(&current.course_id)
+        .with_help_message("The number after /courses/ in the course's URL")
+        .prompt()?;
+    let token = Password::new("Access token (leave empty to keep the current one):")
+        .without_confirmation()
+        .prompt()?;
+
+    let token = if token.trim().is_empty() {
+        current.token
+    } else {
+        token.trim().to_string()
+    };
+
+    if base_url.trim().is_empty() || course_id.trim().is_empty() || token.is_empty() {
+        println!("{}", "Cancelled.".dimmed());
+        return Ok(());
+    }
+
+    config.canvas = Some(CanvasConfig {
+        base_url: base_url.trim().trim_end_matches('/').to_string(),
+        token,
+        course_id: course_id.trim().to_string(),
+    });
+    config.save()?;
+
+    println!(
+        "{} Canvas course {} saved. Run {} to pull it in.",
+        "✓".green(),
+        course_id.trim().yellow(),
+        "librarian add canvas".cyan()
+    );
 
     Ok(())
 }
@@ -133,6 +823,42 @@ fn view_config(config: &Config) {
 
     println!("  API Key: {}", api_status);
 
+    match config.provider {
+        Provider::Groq => println!("  LLM Provider: {}", config.provider.name()),
+        Provider::Ollama => println!(
+            "  LLM Provider: {} ({} at {})",
+            config.provider.name(),
+            config
+                .ollama_model
+                .as_deref()
+                .unwrap_or(ollama::DEFAULT_MODEL),
+            config.ollama_url().dimmed()
+        ),
+        Provider::Anthropic => println!(
+            "  LLM Provider: {} ({}, key {})",
+            config.provider.name(),
+            config
+                .anthropic_model
+                .as_deref()
+                .unwrap_or(anthropic::DEFAULT_MODEL),
+            if config.anthropic_api_key().is_some() {
+                "set".green()
+            } else {
+                "not set".red()
+            }
+        ),
+        Provider::OpenAi => println!(
+            "  LLM Provider: {} ({} at {})",
+            config.provider.name(),
+            config.openai_model.as_deref().unwrap_or("no model"),
+            config
+                .openai_base_url
+                .as_deref()
+                .unwrap_or("no URL")
+                .dimmed()
+        ),
+    }
+
     println!(
         "  Default Model: {}",
         config
@@ -141,6 +867,78 @@ fn view_config(config: &Config) {
             .unwrap_or("llama-3.3-70b-versatile (default)")
     );
 
+    println!("  OCR Languages: {}", config.ocr_languages());
+
+    match config.transcription_backend {
+        TranscriptionBackend::Groq => println!("  Transcription: Groq API"),
+        TranscriptionBackend::Local => println!(
+            "  Transcription: local Whisper ({})",
+            config
+                .whisper_model_path()
+                .map(|p| p.display().to_string())
+                .unwrap_or_default()
+                .dimmed()
+        ),
+    }
+    println!(
+        "  Recordings: {}",
+        TranscribeOptions::from_config(config).describe()
+    );
+
+    match config.image_reader {
+        ImageReader::Auto => println!(
+            "  Image Reading: OCR, then {}",
+            config.vision_model().dimmed()
+        ),
+        ImageReader::Ocr => println!("  Image Reading: OCR only"),
+        ImageReader::Vision => println!("  Image Reading: {}", config.vision_model()),
+    }
+
+    println!("  Embedding Device: {}", config.embedding_device.name());
+    println!("  Reranking: {}", config.reranker.name());
+    println!(
+        "  Token Usage: {}",
+        if config.show_usage {
+            "shown after answers"
+        } else {
+            "hidden"
+        }
+    );
+    println!(
+        "  Missed Questions: {}",
+        if config.quiz_save_missed {
+            "saved automatically"
+        } else {
+            "saved when you confirm"
+        }
+    );
+
+    let chunking = ChunkConfig::from_config(config);
+    println!(
+        "  Chunking: {} chars, {} overlap ({:?})",
+        chunking.chunk_size, chunking.overlap, chunking.strategy
+    );
+
+    if !config.sites.is_empty() {
+        let mut domains: Vec<&String> = config.sites.keys().collect();
+        domains.sort();
+        println!(
+            "  Site Logins: {}",
+            domains
+                .iter()
+                .map(|d| d.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    if let Some(canvas) = &config.canvas {
+        println!(
+            "  Canvas: course {} on {}",
+            canvas.course_id, canvas.base_url
+        );
+    }
+
     if let Ok(path) = Config::config_path() {
         println!("  Config file: {}", path.display().to_string().dimmed());
     }
@@ -0,0 +1,164 @@
+use anyhow::{Context, Result};
+use chrono::{Duration, Utc};
+use colored::Colorize;
+use inquire::{Select, Text};
+use std::path::PathBuf;
+
+use super::export::{ConversationExport, ExportFormat};
+use crate::storage::conversations::Conversation;
+use crate::storage::{ConversationStore, Database};
+
+/// List every chat conversation, most recently active first
+pub async fn list() -> Result<()> {
+    let db = Database::open()?;
+    let store = ConversationStore::new(&db);
+
+    let mut conversations = store.list()?;
+    if conversations.is_empty() {
+        println!("{}", "No conversations yet.".dimmed());
+        println!("Start one with {}", "librarian chat".cyan());
+        return Ok(());
+    }
+    conversations.sort_by_key(|c| std::cmp::Reverse(c.updated_at));
+
+    println!(
+        "\n{} ({} conversations)\n",
+        "Conversations".bold(),
+        conversations.len()
+    );
+
+    for conv in &conversations {
+        let messages = store.message_count(conv.id)?;
+        println!(
+            "  {} {} {} {}",
+            format!("[{}]", conv.id).dimmed(),
+            conv.title.as_deref().unwrap_or("(untitled)").bold(),
+            format!("{} messages", messages).dimmed(),
+            conv.updated_at
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+                .dimmed()
+        );
+    }
+
+    Ok(())
+}
+
+/// Give a conversation a new title
+pub async fn rename(id: i64, title: Option<String>) -> Result<()> {
+    let db = Database::open()?;
+    let store = ConversationStore::new(&db);
+    let Some(conv) = store.get(id)? else {
+        println!("{} Conversation not found: {}", "✗".red(), id);
+        return Ok(());
+    };
+
+    let title = match title {
+        Some(title) => title,
+        None => Text::new("New title:")
+            .with_initial_value(conv.title.as_deref().unwrap_or(""))
+            .prompt()?,
+    };
+    let title = title.trim();
+    if title.is_empty() {
+        println!("{}", "Empty title, nothing changed.".dimmed());
+        return Ok(());
+    }
+
+    store.update_title(id, title)?;
+    println!(
+        "{} Renamed conversation {} to {}",
+        "✓".green(),
+        id,
+        title.cyan()
+    );
+
+    Ok(())
+}
+
+/// Delete one conversation, or every conversation idle for `older_than` days
+pub async fn delete(id: Option<i64>, older_than: Option<u32>) -> Result<()> {
+    let db = Database::open()?;
+    let store = ConversationStore::new(&db);
+
+    let doomed: Vec<Conversation> = match (id, older_than) {
+        (Some(id), _) => store.get(id)?.into_iter().collect(),
+        (None, Some(days)) => {
+            let cutoff = Utc::now() - Duration::days(days as i64);
+            store
+                .list()?
+                .into_iter()
+                .filter(|c| c.updated_at < cutoff)
+                .collect()
+        }
+        (None, None) => {
+            let id_str = Text::new("Conversation ID to delete:")
+                .with_help_message("See IDs with `librarian conversations list`")
+                .prompt()?;
+            let id: i64 = id_str
+                .trim()
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Invalid ID"))?;
+            store.get(id)?.into_iter().collect()
+        }
+    };
+
+    let prompt = match doomed.as_slice() {
+        [] => {
+            println!("{} No matching conversations", "⊘".yellow());
+            return Ok(());
+        }
+        [conv] => format!(
+            "Delete conversation {} '{}'?",
+            conv.id,
+            conv.title.as_deref().unwrap_or("(untitled)")
+        ),
+        _ => format!("Delete {} conversations?", doomed.len()),
+    };
+
+    let confirm = Select::new(&prompt, vec!["No", "Yes"]).prompt()?;
+    if confirm != "Yes" {
+        println!("{}", "Cancelled.".dimmed());
+        return Ok(());
+    }
+
+    for conv in &doomed {
+        store.delete(conv.id)?;
+    }
+    println!("{} Deleted {} conversation(s)", "✓".green(), doomed.len());
+
+    Ok(())
+}
+
+/// Write a conversation to a Markdown or JSON file (or stdout with `-o -`)
+pub async fn export(id: i64, format: ExportFormat, output: Option<PathBuf>) -> Result<()> {
+    let db = Database::open()?;
+    let store = ConversationStore::new(&db);
+    let Some(conv) = store.get(id)? else {
+        println!("{} Conversation not found: {}", "✗".red(), id);
+        return Ok(());
+    };
+
+    let export = ConversationExport::load(&store, conv)?;
+    let (text, extension) = match format {
+        ExportFormat::Json => (serde_json::to_string_pretty(&export)?, "json"),
+        ExportFormat::Markdown => (export.to_markdown(), "md"),
+    };
+
+    if output.as_deref() == Some(std::path::Path::new("-")) {
+        print!("{}", text);
+        return Ok(());
+    }
+
+    let path =
+        output.unwrap_or_else(|| PathBuf::from(format!("{}.{}", export.file_stem(), extension)));
+    std::fs::write(&path, text).with_context(|| format!("Failed to write {}", path.display()))?;
+    println!(
+        "{} Exported conversation {} to {}",
+        "✓".green(),
+        id,
+        path.display().to_string().cyan()
+    );
+
+    Ok(())
+}
@@ -1,8 +1,134 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
 use colored::Colorize;
-use inquire::{Select, Text};
+use inquire::{MultiSelect, Select, Text};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::IsTerminal;
+use std::ops::Range;
+use std::path::PathBuf;
 
-use crate::storage::{Database, Document, DocumentStore};
+use crate::storage::links::{KIND_NOTE, KIND_STUDY_ITEM};
+use crate::storage::{
+    ChunkStore, Collection, CollectionStore, Database, Document, DocumentStore, LinkStore,
+    NoteStore, SearchHit, StudyStore,
+};
+
+/// Name shown for the library used when no bucket is selected
+const DEFAULT_LIBRARY: &str = "(default)";
+
+/// How `list` and `search` print their results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable, coloured
+    #[default]
+    Text,
+    /// A JSON array, for scripts and editor plugins
+    Json,
+    /// Tab-separated values with a header row
+    Tsv,
+}
+
+/// Order `list` shows documents in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum SortBy {
+    /// Newest first
+    #[default]
+    Date,
+    /// Alphabetical by name
+    Name,
+    /// Largest first
+    Size,
+}
+
+/// Which documents `list` shows, and in what order
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct ListOptions {
+    /// Sort order
+    #[arg(long, value_enum, default_value = "date")]
+    pub sort: SortBy,
+    /// Only documents of this type, e.g. pdf, video or markdown
+    #[arg(long = "type", value_name = "TYPE")]
+    pub content_type: Option<String>,
+    /// Only documents with this tag
+    #[arg(long)]
+    pub tag: Option<String>,
+    /// Show at most this many documents
+    #[arg(long)]
+    pub limit: Option<usize>,
+}
+
+impl ListOptions {
+    /// Filter, sort and cut down documents (given newest first)
+    fn apply(&self, mut documents: Vec<Document>) -> Vec<Document> {
+        if let Some(content_type) = &self.content_type {
+            documents.retain(|d| d.content_type.eq_ignore_ascii_case(content_type));
+        }
+        if let Some(tag) = &self.tag {
+            documents.retain(|d| d.has_tag(tag));
+        }
+        match self.sort {
+            SortBy::Date => {}
+            SortBy::Name => documents.sort_by_key(|d| d.filename.to_lowercase()),
+            SortBy::Size => documents.sort_by_key(|d| std::cmp::Reverse(d.content.len())),
+        }
+        if let Some(limit) = self.limit {
+            documents.truncate(limit);
+        }
+        documents
+    }
+
+    fn is_filtered(&self) -> bool {
+        self.content_type.is_some() || self.tag.is_some() || self.limit.is_some()
+    }
+}
+
+/// Documents listed before the text view goes through the pager
+const LIST_PAGE_SIZE: usize = 40;
+
+#[derive(Serialize)]
+struct DocumentRow<'a> {
+    id: i64,
+    filename: &'a str,
+    content_type: &'a str,
+    source: &'a str,
+    tags: Vec<&'a str>,
+    chars: usize,
+    created_at: String,
+    updated_at: String,
+    chunks: i64,
+}
+
+impl<'a> DocumentRow<'a> {
+    fn new(doc: &'a Document, chunks: i64) -> Self {
+        Self {
+            id: doc.id,
+            filename: &doc.filename,
+            content_type: &doc.content_type,
+            source: &doc.source_path,
+            tags: doc
+                .tags
+                .as_deref()
+                .unwrap_or("")
+                .split(',')
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .collect(),
+            chars: doc.content.len(),
+            created_at: doc.created_at.to_rfc3339(),
+            updated_at: doc.updated_at.to_rfc3339(),
+            chunks,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SearchRow<'a> {
+    id: i64,
+    filename: &'a str,
+    score: f64,
+    snippet: &'a str,
+}
 
 /// Interactive document management
 pub async fn run() -> Result<()> {
@@ -32,8 +158,12 @@ pub async fn run() -> Result<()> {
     let options = vec![
         "📋  List all documents  │ See everything in this book",
         "🔍  Search documents    │ Find specific content",
+        "⭐  Collections         │ Saved searches, re-run any time",
         "👁️   View document       │ Read document details",
+        "✏️   Rename document     │ Give it a clearer name",
+        "🏷️   Tag document        │ Add or remove tags",
         "🗑️   Delete document     │ Remove from collection",
+        "☑️   Select documents    │ Delete, tag or move several at once",
         "←   Back",
     ];
 
@@ -49,14 +179,21 @@ pub async fn run() -> Result<()> {
 
         match selection {
             s if s.contains("List all documents") => {
-                if let Err(e) = list().await
+                if let Err(e) = list(OutputFormat::Text, &ListOptions::default()).await
                     && !e.to_string().contains("cancelled")
                 {
                     eprintln!("{} {}", "Error:".red(), e);
                 }
             }
             s if s.contains("Search documents") => {
-                if let Err(e) = search(None).await
+                if let Err(e) = search(None, OutputFormat::Text, None).await
+                    && !e.to_string().contains("cancelled")
+                {
+                    eprintln!("{} {}", "Error:".red(), e);
+                }
+            }
+            s if s.contains("Collections") => {
+                if let Err(e) = collections().await
                     && !e.to_string().contains("cancelled")
                 {
                     eprintln!("{} {}", "Error:".red(), e);
@@ -69,6 +206,20 @@ pub async fn run() -> Result<()> {
                     eprintln!("{} {}", "Error:".red(), e);
                 }
             }
+            s if s.contains("Rename document") => {
+                if let Err(e) = rename_document().await
+                    && !e.to_string().contains("cancelled")
+                {
+                    eprintln!("{} {}", "Error:".red(), e);
+                }
+            }
+            s if s.contains("Tag document") => {
+                if let Err(e) = tag_document().await
+                    && !e.to_string().contains("cancelled")
+                {
+                    eprintln!("{} {}", "Error:".red(), e);
+                }
+            }
             s if s.contains("Delete document") => {
                 if let Err(e) = delete_document().await
                     && !e.to_string().contains("cancelled")
@@ -76,6 +227,13 @@ pub async fn run() -> Result<()> {
                     eprintln!("{} {}", "Error:".red(), e);
                 }
             }
+            s if s.contains("Select documents") => {
+                if let Err(e) = bulk().await
+                    && !e.to_string().contains("cancelled")
+                {
+                    eprintln!("{} {}", "Error:".red(), e);
+                }
+            }
             s if s.contains("Back") => break,
             _ => {}
         }
@@ -87,29 +245,85 @@ pub async fn run() -> Result<()> {
 }
 
 /// List all documents
-pub async fn list() -> Result<()> {
+pub async fn list(format: OutputFormat, options: &ListOptions) -> Result<()> {
     let db = Database::open()?;
     let store = DocumentStore::new(&db);
 
-    let documents = store.list()?;
+    let documents = options.apply(store.list()?);
+    let chunk_store = ChunkStore::new(&db);
+    chunk_store.init_schema()?;
+    let chunk_counts = chunk_store.counts_by_document()?;
+    let rows = || {
+        documents
+            .iter()
+            .map(|d| DocumentRow::new(d, chunk_counts.get(&d.id).copied().unwrap_or(0)))
+    };
+
+    match format {
+        OutputFormat::Json => {
+            let rows: Vec<DocumentRow> = rows().collect();
+            println!("{}", serde_json::to_string_pretty(&rows)?);
+            return Ok(());
+        }
+        OutputFormat::Tsv => {
+            println!(
+                "id\tfilename\tcontent_type\tsource\ttags\tchars\tcreated_at\tupdated_at\tchunks"
+            );
+            for row in rows() {
+                println!(
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    row.id,
+                    tsv_field(row.filename),
+                    tsv_field(row.content_type),
+                    tsv_field(row.source),
+                    tsv_field(&row.tags.join(",")),
+                    row.chars,
+                    row.created_at,
+                    row.updated_at,
+                    row.chunks
+                );
+            }
+            return Ok(());
+        }
+        OutputFormat::Text => {}
+    }
 
     if documents.is_empty() {
+        if options.is_filtered() {
+            println!("{}", "No documents match.".dimmed());
+            return Ok(());
+        }
         println!("{}", "No documents found.".dimmed());
         println!("Use {} to add content.", "librarian add".cyan());
         return Ok(());
     }
 
-    println!("\n{} ({} documents)\n", "Documents".bold(), documents.len());
-
+    let mut listing = format!(
+        "\n{} ({} documents)\n\n",
+        "Documents".bold(),
+        documents.len()
+    );
     for doc in &documents {
-        print_document_summary(doc);
+        listing.push_str(&document_summary(doc));
+        listing.push('\n');
+    }
+
+    // Hundreds of documents would scroll off screen
+    if documents.len() > LIST_PAGE_SIZE {
+        crate::render::page(&listing, false)?;
+    } else {
+        print!("{}", listing);
     }
 
     Ok(())
 }
 
 /// Search documents
-pub async fn search(query: Option<String>) -> Result<()> {
+pub async fn search(
+    query: Option<String>,
+    format: OutputFormat,
+    tag: Option<String>,
+) -> Result<()> {
     let query = match query {
         Some(q) => q,
         None => Text::new("Search query:")
@@ -118,16 +332,43 @@ pub async fn search(query: Option<String>) -> Result<()> {
     };
 
     if query.trim().is_empty() {
-        println!("{}", "Empty query.".dimmed());
+        if format == OutputFormat::Text {
+            println!("{}", "Empty query.".dimmed());
+        }
         return Ok(());
     }
 
     let db = Database::open()?;
     let store = DocumentStore::new(&db);
 
-    let documents = store.search(&query)?;
+    let mut hits = store.search_hits(&query)?;
+    if let Some(tag) = &tag {
+        hits.retain(|hit| hit.document.has_tag(tag));
+    }
 
-    if documents.is_empty() {
+    match format {
+        OutputFormat::Json => {
+            let rows: Vec<SearchRow> = hits.iter().map(search_row).collect();
+            println!("{}", serde_json::to_string_pretty(&rows)?);
+            return Ok(());
+        }
+        OutputFormat::Tsv => {
+            println!("id\tfilename\tscore\tsnippet");
+            for row in hits.iter().map(search_row) {
+                println!(
+                    "{}\t{}\t{:.4}\t{}",
+                    row.id,
+                    tsv_field(row.filename),
+                    row.score,
+                    tsv_field(row.snippet)
+                );
+            }
+            return Ok(());
+        }
+        OutputFormat::Text => {}
+    }
+
+    if hits.is_empty() {
         println!("{} No documents found for '{}'", "⊘".yellow(), query);
         return Ok(());
     }
@@ -135,17 +376,968 @@ pub async fn search(query: Option<String>) -> Result<()> {
     println!(
         "\n{} {} results for '{}'\n",
         "Search:".bold(),
-        documents.len(),
+        hits.len(),
         query.cyan()
     );
 
-    for doc in &documents {
-        print_document_summary(doc);
+    for hit in &hits {
+        print_document_summary(&hit.document);
+        println!("      {}", highlight_snippet(hit));
+    }
+
+    Ok(())
+}
+
+fn search_row(hit: &SearchHit) -> SearchRow<'_> {
+    SearchRow {
+        id: hit.document.id,
+        filename: &hit.document.filename,
+        score: hit.score,
+        snippet: &hit.snippet,
+    }
+}
+
+/// The hit's snippet on one line, with the matched terms highlighted
+fn highlight_snippet(hit: &SearchHit) -> String {
+    highlight(&hit.snippet, &hit.matches)
+}
+
+/// Text on one line, dimmed, with the given byte ranges highlighted
+fn highlight(text: &str, matches: &[Range<usize>]) -> String {
+    let snippet = text.trim_end();
+    let mut out = String::new();
+    let mut cursor = 0;
+
+    for range in matches.iter().filter(|r| r.end <= snippet.len()) {
+        out.push_str(&one_line(&snippet[cursor..range.start]).dimmed().to_string());
+        out.push_str(&snippet[range.clone()].yellow().bold().to_string());
+        cursor = range.end;
+    }
+    out.push_str(&one_line(&snippet[cursor..]).dimmed().to_string());
+
+    out
+}
+
+/// Collapse runs of whitespace (including line breaks) into single spaces
+fn one_line(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !in_space {
+                out.push(' ');
+            }
+            in_space = true;
+        } else {
+            out.push(c);
+            in_space = false;
+        }
+    }
+    out
+}
+
+/// A value on one TSV line: tabs and line breaks become spaces
+fn tsv_field(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if matches!(c, '\t' | '\n' | '\r') {
+                ' '
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Rank one document's chunks against a query and show where it answers it
+pub async fn search_in(id: i64, query: &str, limit: usize) -> Result<()> {
+    let db = Database::open()?;
+    let doc = match DocumentStore::new(&db).get(id)? {
+        Some(doc) => doc,
+        None => {
+            println!("{} Document not found: {}", "✗".red(), id);
+            return Ok(());
+        }
+    };
+
+    let chunk_store = ChunkStore::new(&db);
+    chunk_store.init_schema()?;
+    let chunks = chunk_store.get_for_document(id)?;
+
+    // Without embeddings (or the model), fall back to matching query terms
+    let query_embedding = if chunks.iter().any(|c| c.embedding.is_some()) {
+        chunk_store.embed_text(query).ok()
+    } else {
+        None
+    };
+    let ranked = crate::search::rank_chunks(query, query_embedding.as_deref(), chunks);
+
+    if ranked.is_empty() {
+        println!(
+            "{} Nothing in {} matches '{}'",
+            "⊘".yellow(),
+            doc.filename,
+            query
+        );
+        return Ok(());
+    }
+
+    println!(
+        "\n{} {} for '{}'\n",
+        "Search in".bold(),
+        doc.filename.cyan(),
+        query.cyan()
+    );
+
+    let terms = crate::search::query_terms(query);
+    for (chunk, score) in ranked.iter().take(limit) {
+        let location = chunk.location();
+        let location = location.trim_start_matches([' ', '/', ',']);
+        println!(
+            "  {}{} {}",
+            format!("#{}", chunk.chunk_index).bold(),
+            if location.is_empty() {
+                String::new()
+            } else {
+                format!(" {}", location)
+            },
+            format!("({:.2})", score).dimmed()
+        );
+        let (excerpt, matches) = excerpt(&chunk.content, &terms);
+        println!("      {}\n", highlight(excerpt, &matches));
     }
 
     Ok(())
 }
 
+/// Show how a document is stored and used, to help decide what to prune or
+/// re-ingest
+pub async fn stats(id: i64) -> Result<()> {
+    let db = Database::open()?;
+    let Some(doc) = DocumentStore::new(&db).get(id)? else {
+        println!("{} Document not found: {}", "✗".red(), id);
+        return Ok(());
+    };
+    let chunk_store = ChunkStore::new(&db);
+    chunk_store.init_schema()?;
+    let chunks = chunk_store.stats_for_document(id)?;
+    let now = chrono::Utc::now();
+    let items: Vec<_> = StudyStore::new(&db)
+        .list()?
+        .into_iter()
+        .filter(|item| item.document_id == Some(id))
+        .collect();
+    let due = items.iter().filter(|i| i.next_review_date <= now).count();
+
+    println!(
+        "\n{} {} {}\n",
+        "Stats for".bold(),
+        doc.filename.cyan(),
+        format!("(doc {})", doc.id).dimmed()
+    );
+    println!(
+        "  {:<12} {} chars, ~{} tokens",
+        "Size",
+        doc.content.len(),
+        crate::llm::tokens::estimate(&doc.content)
+    );
+    println!(
+        "  {:<12} {} ({} embedded, {}%)",
+        "Chunks",
+        chunks.chunks,
+        chunks.embedded,
+        (chunks.embedded * 100)
+            .checked_div(chunks.chunks)
+            .unwrap_or(0)
+    );
+    println!(
+        "  {:<12} {} time(s) in chat answers ({} of {} chunks used)",
+        "Retrieved", chunks.retrievals, chunks.retrieved, chunks.chunks
+    );
+    println!("  {:<12} {} ({} due)", "Study items", items.len(), due);
+
+    if chunks.embedded < chunks.chunks {
+        println!(
+            "\n{} Some chunks have no embedding; {} fills them in",
+            "ℹ".blue(),
+            format!("librarian docs reindex {}", id).cyan()
+        );
+    }
+    println!();
+
+    Ok(())
+}
+
+/// Write a document's extracted text (e.g. a lecture transcript) to a file, or
+/// to stdout with `-`
+pub async fn export(id: i64, output: Option<PathBuf>) -> Result<()> {
+    let db = Database::open()?;
+    let Some(doc) = DocumentStore::new(&db).get(id)? else {
+        anyhow::bail!("Document not found: {}", id);
+    };
+
+    if output.as_deref() == Some(std::path::Path::new("-")) {
+        println!("{}", doc.content);
+        return Ok(());
+    }
+
+    let path = output.unwrap_or_else(|| {
+        let stem = std::path::Path::new(&doc.filename)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| doc.filename.clone());
+        PathBuf::from(format!("{}-{}.txt", doc.id, super::export::slugify(&stem)))
+    });
+    std::fs::write(&path, &doc.content)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    println!(
+        "{} Exported the text of {} to {}",
+        "✓".green(),
+        doc.filename,
+        path.display().to_string().cyan()
+    );
+
+    Ok(())
+}
+
+/// Give a document a clearer name for lists and citations
+pub async fn rename(id: i64, name: Option<String>) -> Result<()> {
+    let db = Database::open()?;
+    let store = DocumentStore::new(&db);
+    let Some(doc) = store.get(id)? else {
+        println!("{} Document not found: {}", "✗".red(), id);
+        return Ok(());
+    };
+
+    let name = match name {
+        Some(name) => name,
+        None => Text::new("New name:")
+            .with_initial_value(&doc.filename)
+            .prompt()?,
+    };
+    let name = name.trim();
+    if name.is_empty() || name == doc.filename {
+        println!("{}", "Name unchanged.".dimmed());
+        return Ok(());
+    }
+
+    store.rename(id, name)?;
+    println!(
+        "{} Renamed {} to {}",
+        "✓".green(),
+        doc.filename.dimmed(),
+        name.cyan()
+    );
+
+    Ok(())
+}
+
+/// List a document's chunks (index, length, position, whether embedded), or
+/// print the chunk at `index` in full
+pub async fn chunks(id: i64, index: Option<i64>) -> Result<()> {
+    let db = Database::open()?;
+    let Some(doc) = DocumentStore::new(&db).get(id)? else {
+        println!("{} Document not found: {}", "✗".red(), id);
+        return Ok(());
+    };
+    let chunk_store = ChunkStore::new(&db);
+    chunk_store.init_schema()?;
+    let chunks = chunk_store.get_for_document(id)?;
+
+    if let Some(index) = index {
+        let Some(chunk) = chunks.iter().find(|c| c.chunk_index == index) else {
+            println!(
+                "{} {} has no chunk #{} ({} chunks)",
+                "⊘".yellow(),
+                doc.filename,
+                index,
+                chunks.len()
+            );
+            return Ok(());
+        };
+        println!("\n{}", "─".repeat(50).dimmed());
+        println!(
+            "{}{} {}",
+            doc.filename.bold(),
+            chunk.location(),
+            format!("(chunk #{}, id {})", chunk.chunk_index, chunk.id).dimmed()
+        );
+        println!("{}", "─".repeat(50).dimmed());
+        println!("{}\n", chunk.content.trim());
+        return Ok(());
+    }
+
+    if chunks.is_empty() {
+        println!(
+            "{} {} has no chunks; {} creates them",
+            "⊘".yellow(),
+            doc.filename,
+            format!("librarian docs reindex {}", id).cyan()
+        );
+        return Ok(());
+    }
+
+    println!(
+        "\n{} {} {}\n",
+        "Chunks of".bold(),
+        doc.filename.cyan(),
+        format!("({})", chunks.len()).dimmed()
+    );
+    for chunk in &chunks {
+        let location = chunk.location();
+        let location = location.trim_start_matches([' ', '/', ',']);
+        let preview: String = chunk
+            .content
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .chars()
+            .take(60)
+            .collect();
+        println!(
+            "  {:>5} {} {:>6} chars  {}{}",
+            format!("#{}", chunk.chunk_index).bold(),
+            if chunk.embedding.is_some() {
+                "✓".green()
+            } else {
+                "·".dimmed()
+            },
+            chunk.content.len(),
+            if location.is_empty() {
+                String::new()
+            } else {
+                format!("{}  ", location.cyan())
+            },
+            preview.dimmed()
+        );
+    }
+    println!(
+        "\n{} ✓ = embedded. Print one with {}\n",
+        "ℹ".blue(),
+        format!("librarian docs chunks {} <index>", id).cyan()
+    );
+
+    Ok(())
+}
+
+/// Chunks of one document that must overlap another's (by word Jaccard) to
+/// count as repeated
+const DUPLICATE_CHUNK_OVERLAP: f64 = 0.8;
+
+/// Share of the smaller document's chunks repeated in the other for the pair
+/// to be reported as near-duplicates
+const NEAR_DUPLICATE_SHARE: f64 = 0.8;
+
+/// Something two documents must share to be compared as possible duplicates
+#[derive(PartialEq, Eq, Hash)]
+enum DuplicateBucket<'a> {
+    Hash(&'a str),
+    Content(&'a str),
+    /// A chunk's MinHash signature (seed, smallest hash)
+    MinHash(u64, u64),
+}
+
+/// Two documents with (nearly) the same content
+struct DuplicatePair<'a> {
+    /// The older document, kept by default
+    keep: &'a Document,
+    other: &'a Document,
+    /// Share of the smaller document's chunks found in the other (1.0 when
+    /// identical)
+    overlap: f64,
+    identical: bool,
+}
+
+/// Report documents with the same content (by hash or text) or mostly the same
+/// chunks, and offer to merge or delete them
+pub async fn dedupe() -> Result<()> {
+    let db = Database::open()?;
+    let store = DocumentStore::new(&db);
+    let chunk_store = ChunkStore::new(&db);
+    chunk_store.init_schema()?;
+
+    let mut documents = store.list()?;
+    documents.sort_by_key(|d| d.id);
+    let texts: Vec<Vec<String>> = documents
+        .iter()
+        .map(|d| {
+            let chunks: Vec<String> = chunk_store
+                .get_for_document(d.id)?
+                .into_iter()
+                .map(|c| c.content)
+                .collect();
+            anyhow::Ok(if chunks.is_empty() {
+                vec![d.content.clone()]
+            } else {
+                chunks
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    let words: Vec<Vec<HashSet<&str>>> = texts
+        .iter()
+        .map(|chunks| chunks.iter().map(|c| crate::search::word_set(c)).collect())
+        .collect();
+
+    // Only documents that share a content hash, their full text or a chunk's
+    // MinHash signature can be duplicates, so only those pairs get compared
+    let mut buckets: HashMap<DuplicateBucket, BTreeSet<usize>> = HashMap::new();
+    for (i, doc) in documents.iter().enumerate() {
+        if let Some(hash) = &doc.content_hash {
+            buckets
+                .entry(DuplicateBucket::Hash(hash))
+                .or_default()
+                .insert(i);
+        }
+        buckets
+            .entry(DuplicateBucket::Content(&doc.content))
+            .or_default()
+            .insert(i);
+        for (seed, min) in words[i].iter().flat_map(crate::search::minhashes) {
+            buckets
+                .entry(DuplicateBucket::MinHash(seed, min))
+                .or_default()
+                .insert(i);
+        }
+    }
+    let mut candidates = BTreeSet::new();
+    for bucket in buckets.values() {
+        for (n, i) in bucket.iter().enumerate() {
+            candidates.extend(bucket.iter().skip(n + 1).map(|j| (*i, *j)));
+        }
+    }
+
+    let mut pairs = Vec::new();
+    for (i, j) in candidates {
+        let (a, b) = (&documents[i], &documents[j]);
+        let same_hash = a.content_hash.is_some() && a.content_hash == b.content_hash;
+        if same_hash || a.content == b.content {
+            pairs.push(DuplicatePair {
+                keep: a,
+                other: b,
+                overlap: 1.0,
+                identical: true,
+            });
+            continue;
+        }
+
+        // Documents of very different sizes can't repeat each other
+        let (short, long) = if words[i].len() <= words[j].len() {
+            (&words[i], &words[j])
+        } else {
+            (&words[j], &words[i])
+        };
+        if short.len() * 2 < long.len() {
+            continue;
+        }
+        let overlap = crate::search::chunk_overlap_share(short, long, DUPLICATE_CHUNK_OVERLAP);
+        if overlap >= NEAR_DUPLICATE_SHARE {
+            pairs.push(DuplicatePair {
+                keep: a,
+                other: b,
+                overlap,
+                identical: false,
+            });
+        }
+    }
+
+    if pairs.is_empty() {
+        println!("{} No duplicate documents found.", "✓".green());
+        return Ok(());
+    }
+
+    println!(
+        "\n{} {} pair(s) of duplicate documents\n",
+        "Found".bold(),
+        pairs.len()
+    );
+    if !std::io::stdin().is_terminal() {
+        for pair in &pairs {
+            print_duplicate_pair(pair);
+        }
+        return Ok(());
+    }
+
+    let mut removed: Vec<i64> = Vec::new();
+    for pair in &pairs {
+        if removed.contains(&pair.keep.id) || removed.contains(&pair.other.id) {
+            continue;
+        }
+        print_duplicate_pair(pair);
+
+        let merge = format!(
+            "Merge [{}] into [{}] (keeps its tags, study items and notes)",
+            pair.other.id, pair.keep.id
+        );
+        let delete_other = format!("Delete [{}] {}", pair.other.id, pair.other.filename);
+        let delete_keep = format!("Delete [{}] {}", pair.keep.id, pair.keep.filename);
+        let options = vec![
+            "Keep both".to_string(),
+            merge.clone(),
+            delete_other.clone(),
+            delete_keep.clone(),
+            "Stop".to_string(),
+        ];
+        let choice = match Select::new("What should happen?", options).prompt() {
+            Ok(choice) => choice,
+            Err(inquire::InquireError::OperationCanceled)
+            | Err(inquire::InquireError::OperationInterrupted) => break,
+            Err(e) => return Err(e.into()),
+        };
+
+        if choice == merge {
+            store.merge_into(pair.other.id, pair.keep.id)?;
+            removed.push(pair.other.id);
+            println!("{} Merged into {}", "✓".green(), pair.keep.filename.cyan());
+        } else if choice == delete_other {
+            store.delete(pair.other.id)?;
+            removed.push(pair.other.id);
+            println!("{} Deleted {}", "✓".green(), pair.other.filename);
+        } else if choice == delete_keep {
+            store.delete(pair.keep.id)?;
+            removed.push(pair.keep.id);
+            println!("{} Deleted {}", "✓".green(), pair.keep.filename);
+        } else if choice == "Stop" {
+            break;
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+fn print_duplicate_pair(pair: &DuplicatePair) {
+    let how = if pair.identical {
+        "identical".yellow().to_string()
+    } else {
+        format!("{:.0}% of chunks repeated", pair.overlap * 100.0)
+    };
+    println!(
+        "  {} {}  ≈  {} {}  {}",
+        format!("[{}]", pair.keep.id).dimmed(),
+        pair.keep.filename.bold(),
+        format!("[{}]", pair.other.id).dimmed(),
+        pair.other.filename.bold(),
+        format!("({})", how).dimmed()
+    );
+}
+
+/// Add and remove a document's tags, or edit them at a prompt if neither is given
+pub async fn tag(id: i64, add: Vec<String>, remove: Vec<String>) -> Result<()> {
+    let db = Database::open()?;
+    let store = DocumentStore::new(&db);
+    let doc = match store.get(id)? {
+        Some(doc) => doc,
+        None => {
+            println!("{} Document not found: {}", "✗".red(), id);
+            return Ok(());
+        }
+    };
+
+    let tags = if add.is_empty() && remove.is_empty() {
+        let input = Text::new("Tags (comma-separated):")
+            .with_initial_value(doc.tags.as_deref().unwrap_or(""))
+            .with_help_message("Leave empty to remove every tag")
+            .prompt()?;
+        let tags = crate::storage::study::normalize_tags(&input);
+        store.set_tags(id, tags.as_deref())?;
+        tags
+    } else {
+        store.edit_tags(id, &add, &remove)?
+    };
+
+    println!(
+        "{} {} tags: {}",
+        "✓".green(),
+        doc.filename,
+        tags.as_deref().unwrap_or("none").cyan()
+    );
+    Ok(())
+}
+
+/// Around 300 characters of a chunk, starting a little before the first
+/// query term, with the ranges of every term in it
+fn excerpt<'t>(text: &'t str, terms: &[String]) -> (&'t str, Vec<Range<usize>>) {
+    let first = crate::search::term_ranges(text, terms)
+        .first()
+        .map_or(0, |r| r.start);
+
+    let mut start = first.saturating_sub(80);
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (start + 300).min(text.len());
+    while !text.is_char_boundary(end) {
+        end += 1;
+    }
+
+    let excerpt = &text[start..end];
+    (excerpt, crate::search::term_ranges(excerpt, terms))
+}
+
+/// Chunks shown when running a collection
+const COLLECTION_CHUNKS: usize = 5;
+
+/// Pick a saved search to run, or create/delete one
+async fn collections() -> Result<()> {
+    let db = Database::open()?;
+    let store = CollectionStore::new(&db);
+    let collections = store.list()?;
+
+    let mut options: Vec<String> = collections
+        .iter()
+        .map(|c| {
+            let filters = c.describe_filters();
+            if filters.is_empty() {
+                format!("{}  │ {}", c.name, c.query)
+            } else {
+                format!("{}  │ {} ({})", c.name, c.query, filters)
+            }
+        })
+        .collect();
+    options.push("➕  New collection".to_string());
+    if !collections.is_empty() {
+        options.push("🗑️   Delete a collection".to_string());
+    }
+    options.push("←   Back".to_string());
+
+    let choice = Select::new("Collections:", options.clone()).prompt()?;
+    let index = options.iter().position(|o| o == &choice).unwrap_or(0);
+
+    if let Some(collection) = collections.get(index) {
+        return run_collection(&db, collection);
+    }
+
+    match choice.as_str() {
+        c if c.contains("New collection") => {
+            let collection = create_collection(&store)?;
+            run_collection(&db, &collection)
+        }
+        c if c.contains("Delete a collection") => {
+            let names: Vec<&str> = collections.iter().map(|c| c.name.as_str()).collect();
+            let name = Select::new("Delete which collection?", names).prompt()?;
+            if let Some(collection) = collections.iter().find(|c| c.name == name) {
+                store.delete(collection.id)?;
+                println!("{} Deleted collection {}", "✓".green(), name.cyan());
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn create_collection(store: &CollectionStore) -> Result<Collection> {
+    let name = Text::new("Collection name:")
+        .with_help_message("e.g. Scheduling algorithms")
+        .prompt()?;
+    let query = Text::new("Search query:")
+        .with_help_message("Full-text query, re-run every time you open the collection")
+        .prompt()?;
+    if name.trim().is_empty() || query.trim().is_empty() {
+        anyhow::bail!("A collection needs a name and a query");
+    }
+
+    let content_type = Text::new("Only this type (optional):")
+        .with_help_message("e.g. pdf, markdown, video — leave empty for any")
+        .prompt()?;
+    let tag = Text::new("Only this tag (optional):")
+        .with_help_message("Leave empty for any")
+        .prompt()?;
+
+    let id = store.save(
+        name.trim(),
+        query.trim(),
+        optional(&content_type),
+        optional(&tag),
+    )?;
+    println!("{} Saved collection {}", "✓".green(), name.trim().cyan());
+
+    Ok(Collection {
+        id,
+        name: name.trim().to_string(),
+        query: query.trim().to_string(),
+        content_type: optional(&content_type).map(str::to_string),
+        tag: optional(&tag).map(str::to_string),
+    })
+}
+
+/// A prompt answer, or None if left empty
+fn optional(answer: &str) -> Option<&str> {
+    Some(answer.trim()).filter(|s| !s.is_empty())
+}
+
+/// Show the documents and chunks currently matching a collection
+fn run_collection(db: &Database, collection: &Collection) -> Result<()> {
+    let doc_store = DocumentStore::new(db);
+    let chunk_store = ChunkStore::new(db);
+    chunk_store.init_schema()?;
+
+    let hits: Vec<SearchHit> = doc_store
+        .search_hits(&collection.query)?
+        .into_iter()
+        .filter(|hit| collection.includes(&hit.document))
+        .collect();
+
+    println!(
+        "\n{} {} {}\n",
+        "Collection:".bold(),
+        collection.name.cyan(),
+        format!("({})", collection.query).dimmed()
+    );
+
+    if hits.is_empty() {
+        println!("{} No documents match yet", "⊘".yellow());
+        return Ok(());
+    }
+
+    println!("{} ({})", "Documents".bold(), hits.len());
+    for hit in &hits {
+        print_document_summary(&hit.document);
+        println!("      {}", highlight_snippet(hit));
+    }
+
+    // The best passages from those documents
+    let chunks = chunk_store
+        .search_content(&collection.query, 200)?
+        .into_iter()
+        .filter(|c| hits.iter().any(|h| h.document.id == c.document_id))
+        .collect();
+    let ranked = crate::search::rank_chunks(&collection.query, None, chunks);
+    if ranked.is_empty() {
+        return Ok(());
+    }
+
+    println!("\n{}", "Top passages".bold());
+    let terms = crate::search::query_terms(&collection.query);
+    for (chunk, _) in ranked.iter().take(COLLECTION_CHUNKS) {
+        let filename = hits
+            .iter()
+            .find(|h| h.document.id == chunk.document_id)
+            .map(|h| h.document.filename.as_str())
+            .unwrap_or("Unknown");
+        println!(
+            "  {} {}{}",
+            format!("[{}]", chunk.document_id).dimmed(),
+            filename.bold(),
+            chunk.location().dimmed()
+        );
+        let (excerpt, matches) = excerpt(&chunk.content, &terms);
+        println!("      {}", highlight(excerpt, &matches));
+    }
+
+    Ok(())
+}
+
+/// Whether a document came from a URL or a file that's still on disk
+fn can_open_source(doc: &Document) -> bool {
+    let target = doc.source_path.as_str();
+    target.starts_with("http://")
+        || target.starts_with("https://")
+        || std::path::Path::new(target).exists()
+}
+
+/// Open a document by ID with the system's default app
+pub async fn open(id: i64) -> Result<()> {
+    let db = Database::open()?;
+    let Some(doc) = DocumentStore::new(&db).get(id)? else {
+        println!("{} Document not found: {}", "✗".red(), id);
+        return Ok(());
+    };
+
+    open_source(&doc)?;
+    println!("{} Opened {}", "✓".green(), doc.filename.cyan());
+    Ok(())
+}
+
+/// Open a document's original file or URL with the system's default app
+pub fn open_source(doc: &Document) -> Result<()> {
+    let target = doc.source_path.as_str();
+    if !can_open_source(doc) {
+        anyhow::bail!("The original file is no longer at {}", target);
+    }
+
+    let mut command = if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+
+    command
+        .arg(target)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Couldn't open {}: {}", target, e))?;
+
+    Ok(())
+}
+
+/// Pick several documents, then delete, tag or move them all at once
+async fn bulk() -> Result<()> {
+    let db = Database::open()?;
+    let store = DocumentStore::new(&db);
+    let documents = store.list()?;
+    if documents.is_empty() {
+        println!("{}", "No documents in this bucket yet.".dimmed());
+        return Ok(());
+    }
+
+    let options: Vec<String> = documents
+        .iter()
+        .map(|d| match &d.tags {
+            Some(tags) => format!("{:>4}  {} [{}]", d.id, d.filename, tags),
+            None => format!("{:>4}  {}", d.id, d.filename),
+        })
+        .collect();
+    let picked = MultiSelect::new("Select documents:", options)
+        .with_help_message("Space to select, → all, ← none, type to filter, Enter when done")
+        .raw_prompt()?;
+    if picked.is_empty() {
+        println!("{}", "Nothing selected.".dimmed());
+        return Ok(());
+    }
+    let selected: Vec<&Document> = picked.iter().map(|o| &documents[o.index]).collect();
+
+    let action = Select::new(
+        &format!("{} document(s) selected:", selected.len()),
+        vec![
+            "🗑️   Delete",
+            "🏷️   Add tags",
+            "🏷️   Remove tags",
+            "📦  Move to another bucket",
+            "←   Back",
+        ],
+    )
+    .prompt()?;
+
+    match action {
+        a if a.contains("Delete") => {
+            let confirm = Select::new(
+                &format!("Delete {} document(s)?", selected.len()),
+                vec!["No", "Yes"],
+            )
+            .prompt()?;
+            if confirm != "Yes" {
+                println!("{}", "Cancelled.".dimmed());
+                return Ok(());
+            }
+            for doc in &selected {
+                store.delete(doc.id)?;
+            }
+            println!("{} Deleted {} document(s)", "✓".green(), selected.len());
+        }
+        a if a.contains("Add tags") || a.contains("Remove tags") => {
+            let adding = a.contains("Add tags");
+            let input = Text::new("Tags (comma-separated):").prompt()?;
+            let tags: Vec<String> = input
+                .split(',')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect();
+            if tags.is_empty() {
+                println!("{}", "No tags given, nothing changed.".dimmed());
+                return Ok(());
+            }
+            for doc in &selected {
+                if adding {
+                    store.edit_tags(doc.id, &tags, &[])?;
+                } else {
+                    store.edit_tags(doc.id, &[], &tags)?;
+                }
+            }
+            println!(
+                "{} {} {} {} {} document(s)",
+                "✓".green(),
+                if adding { "Added" } else { "Removed" },
+                tags.join(", ").cyan(),
+                if adding { "to" } else { "from" },
+                selected.len()
+            );
+        }
+        a if a.contains("Move") => move_documents(&store, &selected)?,
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Move documents (with their chunks) into another bucket. Notes attached to
+/// them stay behind and are removed.
+fn move_documents(store: &DocumentStore, documents: &[&Document]) -> Result<()> {
+    let current = crate::bucket::get_current_bucket()?.map(|b| b.name);
+    let mut targets: Vec<String> = crate::bucket::Bucket::list_active()?
+        .into_iter()
+        .filter(|name| Some(name) != current.as_ref())
+        .collect();
+    if current.is_some() {
+        targets.push(DEFAULT_LIBRARY.to_string());
+    }
+    if targets.is_empty() {
+        println!(
+            "{} No other buckets yet. Create one with {}",
+            "ℹ".blue(),
+            "librarian bucket create <name>".cyan()
+        );
+        return Ok(());
+    }
+
+    let target = Select::new("Move to:", targets).prompt()?;
+    let target_db = if target == DEFAULT_LIBRARY {
+        Database::open_at_path(Database::default_db_path()?)?
+    } else {
+        Database::open_for_bucket(&crate::bucket::Bucket::open(&target)?)?
+    };
+
+    let mut moved = 0;
+    for doc in documents {
+        match store.move_to(doc.id, &target_db) {
+            Ok(_) => moved += 1,
+            Err(e) => println!("  {} {}", "⊘".yellow(), e),
+        }
+    }
+    println!(
+        "{} Moved {} document(s) to {}",
+        "✓".green(),
+        moved,
+        target.cyan()
+    );
+
+    Ok(())
+}
+
+/// Rename a document chosen by ID
+async fn rename_document() -> Result<()> {
+    let id_str = Text::new("Document ID:")
+        .with_help_message("Enter the document ID to rename")
+        .prompt()?;
+
+    let id: i64 = id_str
+        .trim()
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid ID"))?;
+
+    rename(id, None).await
+}
+
+/// Edit the tags of a document chosen by ID
+async fn tag_document() -> Result<()> {
+    let id_str = Text::new("Document ID:")
+        .with_help_message("Enter the document ID to tag")
+        .prompt()?;
+
+    let id: i64 = id_str
+        .trim()
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid ID"))?;
+
+    tag(id, Vec::new(), Vec::new()).await
+}
+
 /// View a specific document
 async fn view_document() -> Result<()> {
     let db = Database::open()?;
@@ -180,6 +1372,21 @@ async fn view_document() -> Result<()> {
             println!("{} {} chars", "Length:".bold(), doc.content.len());
             println!("{}", "─".repeat(50).dimmed());
 
+            let notes = NoteStore::new(&db).for_document(doc.id)?;
+            if !notes.is_empty() {
+                println!("\n{} ({})", "Your notes:".bold(), notes.len());
+                for note in &notes {
+                    println!(
+                        "  {} {} {}",
+                        format!("[{}]", note.document_id).dimmed(),
+                        note.created_at.format("%Y-%m-%d").to_string().dimmed(),
+                        note.content
+                    );
+                }
+            }
+
+            print_backlinks(&db, doc.id)?;
+
             // Show content preview or full content
             let preview_len = doc.content.len().min(500);
             println!("\n{}", "Content preview:".bold());
@@ -190,6 +1397,24 @@ async fn view_document() -> Result<()> {
                     format!("... ({} more chars)", doc.content.len() - 500).dimmed()
                 );
             }
+            println!();
+            let mut actions = vec!["📖  Read full text"];
+            if can_open_source(&doc) {
+                actions.push("📂  Open original source");
+            }
+            actions.push("←   Back");
+            match Select::new("Next:", actions).prompt() {
+                Ok(a) if a.contains("Read full text") => {
+                    let markdown = doc.content_type == "markdown"
+                        || doc.content_type.starts_with("generated-");
+                    crate::render::page(&doc.content, markdown)?;
+                }
+                Ok(a) if a.contains("Open original") => match open_source(&doc) {
+                    Ok(()) => println!("{} Opened {}", "✓".green(), doc.filename.cyan()),
+                    Err(e) => println!("{} {}", "⊘".yellow(), e),
+                },
+                _ => {}
+            }
         }
         None => {
             println!("{} Document not found: {}", "✗".red(), id);
@@ -199,6 +1424,43 @@ async fn view_document() -> Result<()> {
     Ok(())
 }
 
+/// Show study items and notes that were created from this document
+fn print_backlinks(db: &Database, document_id: i64) -> Result<()> {
+    ChunkStore::new(db).init_schema()?;
+    let backlinks = LinkStore::new(db).referencing_document(document_id)?;
+    if backlinks.is_empty() {
+        return Ok(());
+    }
+
+    let doc_store = DocumentStore::new(db);
+    let study_store = StudyStore::new(db);
+
+    println!("\n{} ({})", "Referenced by:".bold(), backlinks.len());
+    for link in &backlinks {
+        let label = match link.from_kind.as_str() {
+            KIND_STUDY_ITEM => study_store.get(link.from_id)?.map(|item| {
+                format!(
+                    "{} {}",
+                    format!("({})", item.item_type).dimmed(),
+                    item.front
+                )
+            }),
+            KIND_NOTE => doc_store.get(link.from_id)?.map(|doc| doc.filename),
+            _ => None,
+        };
+
+        if let Some(label) = label {
+            println!(
+                "  {} {}",
+                format!("[chunk {}]", link.chunk_index).dimmed(),
+                label
+            );
+        }
+    }
+
+    Ok(())
+}
+
 /// Delete a document (public interface)
 pub async fn delete(id: Option<i64>) -> Result<()> {
     let db = Database::open()?;
@@ -254,6 +1516,10 @@ async fn delete_document() -> Result<()> {
 }
 
 fn print_document_summary(doc: &Document) {
+    println!("{}", document_summary(doc));
+}
+
+fn document_summary(doc: &Document) -> String {
     let tags = doc.tags.as_deref().unwrap_or("");
     let tags_display = if tags.is_empty() {
         String::new()
@@ -261,12 +1527,12 @@ fn print_document_summary(doc: &Document) {
         format!(" [{}]", tags.cyan())
     };
 
-    println!(
+    format!(
         "  {} {} {}{} ({} chars)",
         format!("[{}]", doc.id).dimmed(),
         doc.filename.bold(),
         doc.content_type.dimmed(),
         tags_display,
         doc.content.len()
-    );
+    )
 }
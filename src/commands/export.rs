@@ -0,0 +1,378 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::bucket::Bucket;
+use crate::storage::conversations::Conversation;
+use crate::storage::{
+    CitationStore, ConversationStore, Database, DocumentStore, ReadingStore, StudyStore,
+};
+
+/// Output format for library exports
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    /// A single JSON file
+    Json,
+    /// A folder of Markdown files (one per document / conversation)
+    Markdown,
+}
+
+#[derive(Serialize)]
+struct LibraryExport {
+    exported_at: DateTime<Utc>,
+    buckets: Vec<BucketExport>,
+}
+
+#[derive(Serialize)]
+struct BucketExport {
+    name: String,
+    documents: Vec<DocumentExport>,
+    study_items: Vec<StudyItemExport>,
+    conversations: Vec<ConversationExport>,
+}
+
+#[derive(Serialize)]
+struct DocumentExport {
+    id: i64,
+    title: String,
+    source: String,
+    content_type: String,
+    tags: Vec<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reading_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    citation: Option<CitationExport>,
+    content: String,
+}
+
+#[derive(Serialize)]
+struct CitationExport {
+    entry_type: String,
+    title: String,
+    authors: Vec<String>,
+    year: Option<i32>,
+    venue: Option<String>,
+    doi: Option<String>,
+    url: Option<String>,
+    arxiv_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct StudyItemExport {
+    pub(crate) id: i64,
+    pub(crate) document_id: Option<i64>,
+    pub(crate) item_type: String,
+    pub(crate) front: String,
+    pub(crate) back: String,
+    /// Tags and decks
+    #[serde(default)]
+    pub(crate) tags: Vec<String>,
+    pub(crate) next_review: DateTime<Utc>,
+    pub(crate) interval_days: f64,
+    pub(crate) ease_factor: f64,
+    pub(crate) review_count: i64,
+    pub(crate) created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ConversationExport {
+    id: i64,
+    title: Option<String>,
+    created_at: DateTime<Utc>,
+    messages: Vec<MessageExport>,
+}
+
+#[derive(Serialize)]
+struct MessageExport {
+    role: String,
+    content: String,
+}
+
+impl ConversationExport {
+    /// A conversation and all its messages
+    pub(crate) fn load(store: &ConversationStore, conv: Conversation) -> Result<Self> {
+        let messages = store
+            .get_messages(conv.id)?
+            .into_iter()
+            .map(|m| MessageExport {
+                role: m.role,
+                content: m.content,
+            })
+            .collect();
+
+        Ok(Self {
+            id: conv.id,
+            title: conv.title,
+            created_at: conv.created_at,
+            messages,
+        })
+    }
+
+    pub(crate) fn title(&self) -> &str {
+        self.title.as_deref().unwrap_or("Untitled conversation")
+    }
+
+    /// File name for the exported conversation (without extension)
+    pub(crate) fn file_stem(&self) -> String {
+        format!("{}-{}", self.id, slugify(self.title()))
+    }
+
+    pub(crate) fn to_markdown(&self) -> String {
+        let mut md = format!(
+            "# {}\n\n_{}_\n",
+            self.title(),
+            self.created_at.format("%Y-%m-%d %H:%M")
+        );
+        for message in &self.messages {
+            md.push_str(&format!("\n## {}\n\n{}\n", message.role, message.content));
+        }
+        md
+    }
+}
+
+/// Export every bucket that isn't archived to an open format
+pub async fn library(format: ExportFormat, output: Option<PathBuf>) -> Result<()> {
+    let mut buckets = Vec::new();
+
+    if Database::default_db_path()?.exists() {
+        let db = Database::open_at_path(Database::default_db_path()?)?;
+        buckets.push(collect_bucket("default", &db)?);
+    }
+    for name in Bucket::list_active()? {
+        let db = Database::open_for_bucket(&Bucket::open(&name)?)?;
+        buckets.push(collect_bucket(&name, &db)?);
+    }
+    let archived = Bucket::list_all()?.len() - Bucket::list_active()?.len();
+    if archived > 0 {
+        println!(
+            "{} Skipping {} archived bucket(s); unarchive them to include them",
+            "ℹ".blue(),
+            archived
+        );
+    }
+
+    if buckets.is_empty() {
+        println!("{} Nothing to export yet.", "ℹ".blue());
+        return Ok(());
+    }
+
+    let export = LibraryExport {
+        exported_at: Utc::now(),
+        buckets,
+    };
+
+    let stamp = export.exported_at.format("%Y%m%d-%H%M%S");
+    let output = match format {
+        ExportFormat::Json => {
+            let path =
+                output.unwrap_or_else(|| PathBuf::from(format!("librarian-export-{}.json", stamp)));
+            let json = serde_json::to_string_pretty(&export)?;
+            std::fs::write(&path, json)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            path
+        }
+        ExportFormat::Markdown => {
+            let dir =
+                output.unwrap_or_else(|| PathBuf::from(format!("librarian-export-{}", stamp)));
+            write_markdown(&export, &dir)?;
+            dir
+        }
+    };
+
+    let docs: usize = export.buckets.iter().map(|b| b.documents.len()).sum();
+    let items: usize = export.buckets.iter().map(|b| b.study_items.len()).sum();
+    let convs: usize = export.buckets.iter().map(|b| b.conversations.len()).sum();
+
+    println!(
+        "{} Exported {} buckets ({} documents, {} study items, {} conversations) to {}",
+        "✓".green(),
+        export.buckets.len(),
+        docs,
+        items,
+        convs,
+        output.display().to_string().cyan()
+    );
+
+    Ok(())
+}
+
+fn collect_bucket(name: &str, db: &Database) -> Result<BucketExport> {
+    let doc_store = DocumentStore::new(db);
+    let citation_store = CitationStore::new(db);
+    let reading_store = ReadingStore::new(db);
+    let conversation_store = ConversationStore::new(db);
+
+    let mut documents = Vec::new();
+    for doc in doc_store.list()?.into_iter().rev() {
+        let citation = citation_store.get(doc.id)?.map(|c| CitationExport {
+            entry_type: c.entry_type,
+            title: c.title,
+            authors: c.authors,
+            year: c.year,
+            venue: c.venue,
+            doi: c.doi,
+            url: c.url,
+            arxiv_id: c.arxiv_id,
+        });
+        let reading_status = reading_store
+            .get(doc.id)?
+            .map(|entry| entry.status.as_str().to_string());
+
+        documents.push(DocumentExport {
+            id: doc.id,
+            title: doc.filename,
+            source: doc.source_path,
+            content_type: doc.content_type,
+            tags: split_tags(doc.tags.as_deref()),
+            created_at: doc.created_at,
+            updated_at: doc.updated_at,
+            reading_status,
+            citation,
+            content: doc.content,
+        });
+    }
+
+    let study_items = StudyStore::new(db)
+        .list()?
+        .into_iter()
+        .map(|item| StudyItemExport {
+            id: item.id,
+            document_id: item.document_id,
+            item_type: item.item_type,
+            front: item.front,
+            back: item.back,
+            tags: split_tags(item.tags.as_deref()),
+            next_review: item.next_review_date,
+            interval_days: item.interval_days,
+            ease_factor: item.ease_factor,
+            review_count: item.review_count,
+            created_at: item.created_at,
+        })
+        .collect();
+
+    let mut conversations = Vec::new();
+    for conv in conversation_store.list()? {
+        conversations.push(ConversationExport::load(&conversation_store, conv)?);
+    }
+
+    Ok(BucketExport {
+        name: name.to_string(),
+        documents,
+        study_items,
+        conversations,
+    })
+}
+
+/// Write one folder per bucket with documents/, conversations/ and study-items.md
+fn write_markdown(export: &LibraryExport, dir: &Path) -> Result<()> {
+    for bucket in &export.buckets {
+        let bucket_dir = dir.join(&bucket.name);
+        let docs_dir = bucket_dir.join("documents");
+        let convs_dir = bucket_dir.join("conversations");
+        std::fs::create_dir_all(&docs_dir)
+            .with_context(|| format!("Failed to create {}", docs_dir.display()))?;
+        std::fs::create_dir_all(&convs_dir)?;
+
+        for doc in &bucket.documents {
+            let path = docs_dir.join(format!("{}-{}.md", doc.id, slugify(&doc.title)));
+            std::fs::write(path, document_markdown(doc))?;
+        }
+
+        for conv in &bucket.conversations {
+            let path = convs_dir.join(format!("{}.md", conv.file_stem()));
+            std::fs::write(path, conv.to_markdown())?;
+        }
+
+        if !bucket.study_items.is_empty() {
+            let mut md = String::from("# Study Items\n");
+            for item in &bucket.study_items {
+                md.push_str(&format!(
+                    "\n## {} ({})\n\n**Q:** {}\n\n**A:** {}\n",
+                    item.id, item.item_type, item.front, item.back
+                ));
+                if !item.tags.is_empty() {
+                    md.push_str(&format!("\n**Tags:** {}\n", item.tags.join(", ")));
+                }
+            }
+            std::fs::write(bucket_dir.join("study-items.md"), md)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Markdown with YAML front matter, readable by Obsidian and most static site tools
+fn document_markdown(doc: &DocumentExport) -> String {
+    let mut md = String::from("---\n");
+    md.push_str(&format!("title: {}\n", yaml_string(&doc.title)));
+    md.push_str(&format!("source: {}\n", yaml_string(&doc.source)));
+    md.push_str(&format!("type: {}\n", doc.content_type));
+    if !doc.tags.is_empty() {
+        md.push_str(&format!("tags: [{}]\n", doc.tags.join(", ")));
+    }
+    md.push_str(&format!("created: {}\n", doc.created_at.to_rfc3339()));
+    if let Some(status) = &doc.reading_status {
+        md.push_str(&format!("reading: {}\n", status));
+    }
+    if let Some(doi) = doc.citation.as_ref().and_then(|c| c.doi.as_ref()) {
+        md.push_str(&format!("doi: {}\n", doi));
+    }
+    md.push_str("---\n\n");
+    md.push_str(&doc.content);
+    md.push('\n');
+    md
+}
+
+fn split_tags(tags: Option<&str>) -> Vec<String> {
+    tags.unwrap_or("")
+        .split(',')
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+fn yaml_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Filesystem-safe name for an exported file
+pub(crate) fn slugify(title: &str) -> String {
+    let slug: String = title
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+
+    let slug = slug
+        .split('-')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    slug.chars().take(60).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(
+            slugify("Lecture 3: Graphs & Trees.pdf"),
+            "lecture-3-graphs-trees-pdf"
+        );
+        assert_eq!(slugify("  "), "");
+    }
+
+    #[test]
+    fn test_split_tags() {
+        assert_eq!(split_tags(Some("note, cs101,,")), vec!["note", "cs101"]);
+        assert!(split_tags(None).is_empty());
+    }
+}
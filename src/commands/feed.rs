@@ -0,0 +1,172 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::ingest::{self, feed};
+use crate::storage::{ChunkStore, Database, DocumentStore, FeedStore, Subscription};
+
+/// Most entries added from one feed per refresh, so subscribing to a long
+/// archive doesn't pull in years of posts at once
+const MAX_NEW_ENTRIES: usize = 20;
+
+/// Subscribe to an RSS or Atom feed and add its current entries
+pub async fn add(url: String) -> Result<()> {
+    let parsed = feed::fetch_feed(&url).await?;
+
+    let db = Database::open()?;
+    let Some(id) = FeedStore::new(&db).add(&url, &parsed.title)? else {
+        println!("{} Already subscribed to {}", "ℹ".blue(), url);
+        return Ok(());
+    };
+    println!(
+        "{} Subscribed to \"{}\" ({} entries)",
+        "✓".green(),
+        parsed.title,
+        parsed.entries.len()
+    );
+
+    let subscription = Subscription {
+        id,
+        url,
+        title: parsed.title.clone(),
+        last_checked: None,
+    };
+    refresh_one(&db, &subscription, parsed).await?;
+
+    Ok(())
+}
+
+/// List subscriptions
+pub async fn list() -> Result<()> {
+    let db = Database::open()?;
+    let feeds = FeedStore::new(&db).list()?;
+
+    if feeds.is_empty() {
+        println!("{}", "No feed subscriptions.".dimmed());
+        println!("Subscribe with {}", "librarian feed add <url>".cyan());
+        return Ok(());
+    }
+
+    println!("\n{}\n", "Feeds:".bold());
+    for feed in &feeds {
+        let checked = feed
+            .last_checked
+            .map(|t| {
+                format!(
+                    "checked {}",
+                    t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")
+                )
+            })
+            .unwrap_or_else(|| "never checked".to_string());
+        println!(
+            "  {} {}  {}",
+            format!("[{}]", feed.id).dimmed(),
+            feed.title.bold(),
+            format!("{} · {}", feed.url, checked).dimmed()
+        );
+    }
+    println!();
+
+    Ok(())
+}
+
+/// Unsubscribe from a feed, keeping the documents it already added
+pub async fn remove(id: i64) -> Result<()> {
+    let db = Database::open()?;
+    if FeedStore::new(&db).remove(id)? {
+        println!("{} Unsubscribed from feed {}", "✓".green(), id);
+    } else {
+        println!("{} Feed not found: {}", "✗".red(), id);
+    }
+
+    Ok(())
+}
+
+/// Add new entries from every subscription now
+pub async fn refresh() -> Result<()> {
+    let db = Database::open()?;
+    if FeedStore::new(&db).list()?.is_empty() {
+        println!("{}", "No feed subscriptions.".dimmed());
+        return Ok(());
+    }
+
+    let added = refresh_all(&db).await?;
+    println!("\n{} {} new document(s)", "Done:".bold(), added);
+
+    Ok(())
+}
+
+/// Check every subscription for new entries and add them. Returns how many
+/// documents were added.
+pub async fn refresh_all(db: &Database) -> Result<usize> {
+    let feeds = FeedStore::new(db).list()?;
+    let mut added = 0;
+
+    for subscription in &feeds {
+        match feed::fetch_feed(&subscription.url).await {
+            Ok(parsed) => added += refresh_one(db, subscription, parsed).await?,
+            Err(e) => println!(
+                "  {} {} {}",
+                "✗".red(),
+                subscription.title,
+                format!("({})", e).dimmed()
+            ),
+        }
+    }
+
+    Ok(added)
+}
+
+/// Add a feed's entries that haven't been seen before. Entries that fail to
+/// fetch stay unseen, so the next refresh tries them again.
+async fn refresh_one(
+    db: &Database,
+    subscription: &Subscription,
+    parsed: feed::Feed,
+) -> Result<usize> {
+    let feeds = FeedStore::new(db);
+    let doc_store = DocumentStore::new(db);
+    let chunk_store = ChunkStore::new(db);
+    chunk_store.init_schema()?;
+
+    let mut added = 0;
+    for entry in &parsed.entries {
+        if added >= MAX_NEW_ENTRIES {
+            break;
+        }
+        if feeds.is_seen(subscription.id, &entry.link)? {
+            continue;
+        }
+        if doc_store.exists_by_path(&entry.link)? {
+            feeds.mark_seen(subscription.id, &entry.link)?;
+            continue;
+        }
+
+        match ingest::fetch_url(&entry.link).await {
+            Ok(content) => {
+                let (doc_id, chunks) =
+                    super::add::store_url(&doc_store, &chunk_store, &entry.link, &content, None)?;
+                feeds.mark_seen(subscription.id, &entry.link)?;
+                println!(
+                    "  {} {} {}",
+                    "+".green(),
+                    content.title,
+                    format!(
+                        "(id: {}, {} chunks, from {})",
+                        doc_id, chunks, subscription.title
+                    )
+                    .dimmed()
+                );
+                added += 1;
+            }
+            Err(e) => println!(
+                "  {} {} {}",
+                "✗".red(),
+                entry.title,
+                format!("({})", e).dimmed()
+            ),
+        }
+    }
+
+    feeds.mark_checked(subscription.id)?;
+    Ok(added)
+}
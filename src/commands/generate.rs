@@ -1,20 +1,125 @@
 #![allow(clippy::collapsible_if)]
 
 use anyhow::Result;
+use chrono::{Local, NaiveDate};
+use clap::ValueEnum;
 use colored::Colorize;
-use inquire::{Select, Text};
+use inquire::{MultiSelect, Select, Text};
+use std::collections::HashSet;
 use std::io::Write;
 use std::path::PathBuf;
+use std::sync::Mutex;
 
 use crate::bucket;
 use crate::config::Config;
-use crate::embeddings;
-use crate::ingest::{ChunkConfig, chunk_text};
-use crate::llm::GroqClient;
-use crate::storage::{ChunkStore, Database, DocumentStore};
+use crate::ingest::{self, ChunkConfig, chunk_sections};
+use crate::llm::schema::{
+    self, ClozeSet, FlashcardSet, ProblemSet, Quiz, StudyTopic, StudyTopics, cloze_answers,
+};
+use crate::llm::{ChatProvider, tokens};
+use crate::storage::chunks::StoredChunk;
+use crate::storage::links::{KIND_CHUNK, KIND_STUDY_ITEM};
+use crate::storage::plan::PlanEntry;
+use crate::storage::study::normalize_tags;
+use crate::storage::{
+    ChunkStore, Database, Document, DocumentStore, LinkStore, PlanStore, StudyStore,
+};
+
+/// How hard practice problems should be
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Difficulty {
+    /// Direct applications of one idea
+    Easy,
+    #[default]
+    Medium,
+    /// Multi-step problems that combine ideas
+    Hard,
+}
+
+impl Difficulty {
+    fn describe(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy: each applies a single concept directly, in one or two steps",
+            Difficulty::Medium => "medium: each takes a few steps and some judgement",
+            Difficulty::Hard => {
+                "hard: each takes several steps, combines concepts or needs a non-obvious approach, like the hardest exam questions"
+            }
+        }
+    }
+}
+
+/// How much to generate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Length {
+    Short,
+    #[default]
+    Medium,
+    Long,
+}
+
+/// Who the material is pitched at
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Level {
+    /// New to the subject
+    Intro,
+    /// Preparing for the course's exam
+    #[default]
+    Exam,
+    /// Advanced, graduate-level depth
+    Grad,
+}
+
+/// Length and difficulty for study guides, flashcards, cloze cards and quizzes
+#[derive(Debug, Clone, Copy, Default, clap::Args)]
+pub struct GenerationOptions {
+    /// How much to generate
+    #[arg(long, value_enum, default_value = "medium")]
+    pub length: Length,
+    /// Who the material is pitched at
+    #[arg(long, value_enum, default_value = "exam")]
+    pub difficulty: Level,
+}
+
+impl GenerationOptions {
+    /// Instructions to add to the system prompt for the generator `name`
+    /// (None for generators these options don't apply to)
+    fn instructions(&self, name: &str) -> Option<String> {
+        let length = match (name, self.length) {
+            ("Study Guide", Length::Short) => {
+                "Keep it short (about 400 words): key concepts and the most important details only."
+            }
+            ("Study Guide", Length::Medium) => "Aim for about 800-1200 words.",
+            ("Study Guide", Length::Long) => {
+                "Make it in-depth (2000 words or more), with an example for every concept."
+            }
+            ("Flashcards", Length::Short) => "Create 6-8 flashcards.",
+            ("Flashcards", Length::Medium) => "Create 10-15 flashcards.",
+            ("Flashcards", Length::Long) => "Create 20-25 flashcards.",
+            ("Cloze Cards", Length::Short) => "Create 6-8 cards.",
+            ("Cloze Cards", Length::Medium) => "Create 10-15 cards.",
+            ("Cloze Cards", Length::Long) => "Create 20-25 cards.",
+            ("Quiz", Length::Short) => "Create 5 questions.",
+            ("Quiz", Length::Medium) => "Create 10 questions.",
+            ("Quiz", Length::Long) => "Create 20 questions.",
+            _ => return None,
+        };
+        let level = match self.difficulty {
+            Level::Intro => {
+                "The student is new to the subject: define every term, favour intuition and simple examples, and keep notation light."
+            }
+            Level::Exam => {
+                "Pitch it at the course's exam: focus on what is likely to be tested, mixing recall with application."
+            }
+            Level::Grad => {
+                "The student is advanced: take the basics as known and go into derivations, edge cases, trade-offs and how ideas connect."
+            }
+        };
+        Some(format!("Length: {}\nLevel: {}", length, level))
+    }
+}
 
 /// Prompts for different generation types
-mod prompts {
+pub(crate) mod prompts {
     pub const STUDY_GUIDE: &str = r#"You are creating a comprehensive study guide from the provided course materials.
 
 Create a well-organized study guide that includes:
@@ -29,43 +134,42 @@ Cite specific documents when referencing information: [Source: filename]"#;
 
     pub const FLASHCARDS: &str = r#"You are creating flashcards for studying from the provided course materials.
 
-Generate flashcards in this exact format:
----
-Q: [Question]
-A: [Answer]
----
+Reply with a single JSON object in exactly this shape:
+{"flashcards": [{"question": "...", "answer": "..."}]}
 
 Rules:
-- Create 10-15 flashcards covering key concepts
+- Create the number of flashcards asked for below, covering key concepts
 - Questions should test understanding, not just recall
 - Answers should be concise but complete
 - Cover the most important material first
 - Include a mix of definition, concept, and application questions"#;
 
-    pub const QUIZ: &str = r#"You are creating a practice quiz from the provided course materials.
+    pub const CLOZE: &str = r#"You are creating cloze (fill-in-the-blank) cards for studying from the provided course materials.
+
+Reply with a single JSON object in exactly this shape:
+{"cloze": [{"text": "The {{scheduler}} decides which process runs next."}]}
 
-Generate a quiz with mixed question types:
+Rules:
+- Create the number of cards asked for below, covering key concepts
+- Each card is one self-contained sentence taken from or closely based on the materials
+- Wrap the key term to recall in double braces; blank one term per card (two at most)
+- Blank the important word (a term, name, number or mechanism), never a filler word
+- The rest of the sentence must give enough context to recall the blank"#;
 
-## Multiple Choice
-1. Question text
-   a) Option A
-   b) Option B
-   c) Option C
-   d) Option D
-   **Answer: b)**
+    pub const QUIZ: &str = r#"You are creating a practice quiz from the provided course materials.
 
-## Fill in the Blank
-1. The process of _______ is essential for...
-   **Answer: [correct answer]**
+Reply with a single JSON object in exactly this shape:
+{"questions": [
+  {"type": "multiple_choice", "question": "...", "options": ["...", "...", "...", "..."], "answer": "b"},
+  {"type": "fill_in_blank", "question": "The process of _______ is essential for...", "answer": "..."},
+  {"type": "short_answer", "question": "Explain the concept of...", "answer": "brief expected answer"}
+]}
 
-## Short Answer
-1. Explain the concept of...
-   **Answer: [brief expected answer]**
+A multiple-choice "answer" is the letter of the correct option (a for the first).
 
 Rules:
-- Create 10 questions total (mix of types)
+- Create the number of questions asked for below (mix of types)
 - Base questions only on the provided materials
-- Include answers after each question
 - Progress from easier to harder questions"#;
 
     pub const SUMMARY: &str = r#"You are creating a concise summary of the provided course materials.
@@ -80,6 +184,47 @@ Create a summary that:
 Keep the summary focused and scannable. Use bullet points and headers.
 Target length: 300-500 words."#;
 
+    pub const LECTURE_NOTES: &str = r#"You are turning a raw lecture transcript into structured lecture notes.
+
+The transcript is marked with timestamps like [12:34] where each passage begins.
+
+Write the notes in clean Markdown:
+1. A `##` heading for each topic the lecture moves through, followed by the
+   timestamp where it starts, e.g. `## Virtual Memory [14:32]`
+2. Under each heading, bullet points with the key points, definitions and
+   examples the lecturer gives (leave out filler, jokes and logistics)
+3. **Bold** each technical term the first time it appears
+4. End with a `## Key Terms` section listing the terms referenced, each with
+   a one-line definition
+
+Don't add a title; start with the first heading. Keep the timestamps exactly
+as they appear in the transcript. Only include what the lecture says."#;
+
+    pub const PROBLEMS: &str = r#"You are writing a practice problem set with worked solutions from the provided course materials.
+
+Reply with a single JSON object in exactly this shape:
+{"problems": [{"question": "...", "solution": "...", "answer": "..."}]}
+
+Rules:
+- Each "question" is self-contained: give every value and condition needed to solve it
+- Problems should make the student apply the material (calculate, derive, trace, design, explain why), not recall definitions
+- Each "solution" works the problem step by step in Markdown, explaining why each step is taken
+- "answer" is the final result in a few words or a number (empty if the problem has no single answer)
+- Never put the answer in the question
+- Base problems only on the provided materials"#;
+
+    pub const STUDY_PLAN: &str = r#"You are planning a student's revision for an upcoming exam from an outline of their course materials.
+
+Reply with a single JSON object in exactly this shape:
+{"topics": [{"title": "...", "documents": ["filename"], "tasks": ["..."], "effort": 2}]}
+
+Rules:
+- List 5-20 topics covering the materials, in the order they should be studied (foundations first)
+- "documents" names the files that cover the topic, exactly as given
+- "tasks" are 1-3 concrete actions, e.g. "Reread section 2.3" or "Work the page replacement examples"
+- "effort" is how much work the topic needs: 1 (light), 2 (moderate) or 3 (heavy)
+- Only include topics found in the materials"#;
+
     pub const HOMEWORK_HELP: &str = r#"You are a tutor helping a student with their homework using their course materials.
 
 Guidelines:
@@ -116,32 +261,152 @@ pub async fn run() -> Result<()> {
     );
     println!();
 
-    let options = vec![
-        "📚  Study Guide    │ Comprehensive topic overview",
-        "🃏  Flashcards     │ Q&A cards for memorization",
-        "📋  Practice Quiz  │ Test your knowledge",
-        "📝  Summary        │ Quick topic recap",
-        "✏️   Homework Help  │ Interactive problem solving",
-        "←   Back",
-    ];
+    loop {
+        let scope = match documents_in_scope().len() {
+            0 => "Whole bucket".to_string(),
+            1 => "1 chosen document".to_string(),
+            n => format!("{} chosen documents", n),
+        };
+        let options = vec![
+            "📚  Study Guide    │ Comprehensive topic overview".to_string(),
+            "🃏  Flashcards     │ Q&A cards for memorization".to_string(),
+            "🕳️   Cloze Cards    │ Fill-in-the-blank sentences".to_string(),
+            "📋  Practice Quiz  │ Test your knowledge".to_string(),
+            "🧮  Problem Set    │ Practice problems, worked solutions kept apart".to_string(),
+            "📝  Summary        │ Quick topic recap".to_string(),
+            "🎙️   Lecture Notes  │ Timestamped notes from a recording".to_string(),
+            "🗓️   Study Plan     │ Day-by-day schedule up to your exam".to_string(),
+            "✏️   Homework Help  │ Interactive problem solving".to_string(),
+            format!("📑  Documents      │ {}", scope),
+            "←   Back".to_string(),
+        ];
+
+        let selection = Select::new("What would you like to generate?", options).prompt()?;
+
+        match selection {
+            s if s.contains("Study Guide") => {
+                study_guide(None, GenerationOptions::default()).await?
+            }
+            s if s.contains("Flashcards") => flashcards(None, GenerationOptions::default()).await?,
+            s if s.contains("Cloze Cards") => cloze(None, GenerationOptions::default()).await?,
+            s if s.contains("Practice Quiz") => quiz(None, GenerationOptions::default()).await?,
+            s if s.contains("Problem Set") => {
+                let difficulty = Select::new("Difficulty:", vec!["Easy", "Medium", "Hard"])
+                    .with_starting_cursor(1)
+                    .prompt()?;
+                let difficulty = Difficulty::from_str(difficulty, true).unwrap_or_default();
+                problems(None, 5, difficulty).await?
+            }
+            s if s.contains("Summary") => summary(None).await?,
+            s if s.contains("Lecture Notes") => lecture_notes(None).await?,
+            s if s.contains("Study Plan") => study_plan(None).await?,
+            s if s.contains("Homework Help") => homework_help().await?,
+            s if s.contains("Documents") => {
+                choose_documents()?;
+                continue;
+            }
+            _ => {}
+        }
+        break;
+    }
+
+    Ok(())
+}
+
+/// Documents generation is limited to (empty for the whole bucket)
+static SCOPE: Mutex<Vec<i64>> = Mutex::new(Vec::new());
+
+/// Limit generation to these documents (e.g. from `--docs 3,7,12`) plus any
+/// tagged `tag`, checking that each is in the current bucket
+pub fn set_documents(ids: &[i64], tag: Option<&str>) -> Result<()> {
+    let db = Database::open()?;
+    let doc_store = DocumentStore::new(&db);
+    for &id in ids {
+        if doc_store.get(id)?.is_none() {
+            anyhow::bail!("No document with ID {} in this bucket", id);
+        }
+    }
+    let mut scope = ids.to_vec();
+    if let Some(tag) = tag {
+        let tagged: Vec<i64> = doc_store
+            .list()?
+            .iter()
+            .filter(|d| d.has_tag(tag))
+            .map(|d| d.id)
+            .collect();
+        if tagged.is_empty() {
+            anyhow::bail!("No documents in this bucket are tagged '{}'", tag);
+        }
+        scope.extend(tagged.into_iter().filter(|id| !ids.contains(id)));
+    }
+    *SCOPE.lock().unwrap() = scope;
+    Ok(())
+}
+
+/// The documents generation is limited to, in the order given (empty for the
+/// whole bucket)
+fn documents_in_scope() -> Vec<i64> {
+    SCOPE.lock().unwrap().clone()
+}
+
+/// Pick the documents to generate from
+fn choose_documents() -> Result<()> {
+    let db = Database::open()?;
+    let documents = DocumentStore::new(&db).list()?;
+    if documents.is_empty() {
+        println!("{}", "No documents in this bucket yet.".dimmed());
+        return Ok(());
+    }
+
+    let current = documents_in_scope();
+    let options: Vec<String> = documents
+        .iter()
+        .map(|d| format!("{:>4}  {}", d.id, d.filename))
+        .collect();
+    let defaults: Vec<usize> = documents
+        .iter()
+        .enumerate()
+        .filter(|(_, d)| current.contains(&d.id))
+        .map(|(i, _)| i)
+        .collect();
 
-    let selection = Select::new("What would you like to generate?", options).prompt()?;
+    let selected = MultiSelect::new("Generate from:", options.clone())
+        .with_default(&defaults)
+        .with_help_message("Space to pick, Enter to confirm; pick none for the whole bucket")
+        .prompt()?;
+    let ids: Vec<i64> = selected
+        .iter()
+        .filter_map(|s| options.iter().position(|o| o == s))
+        .map(|i| documents[i].id)
+        .collect();
 
-    match selection {
-        s if s.contains("Study Guide") => study_guide(None).await?,
-        s if s.contains("Flashcards") => flashcards(None).await?,
-        s if s.contains("Practice Quiz") => quiz(None).await?,
-        s if s.contains("Summary") => summary(None).await?,
-        s if s.contains("Homework Help") => homework_help().await?,
-        s if s.contains("Back") => {}
-        _ => {}
+    if ids.is_empty() {
+        println!("{} Using the whole bucket", "✓".green());
+    } else {
+        println!("{} Using {} documents", "✓".green(), ids.len());
     }
+    *SCOPE.lock().unwrap() = ids;
+    Ok(())
+}
 
+/// Print which documents generation is limited to, if any
+fn print_scope(doc_store: &DocumentStore) -> Result<()> {
+    let scope = documents_in_scope();
+    if scope.is_empty() {
+        return Ok(());
+    }
+    let mut names = Vec::new();
+    for id in scope {
+        if let Some(doc) = doc_store.get(id)? {
+            names.push(doc.filename);
+        }
+    }
+    println!("{} {}", "Documents:".dimmed(), names.join(", "));
     Ok(())
 }
 
 /// Generate a study guide
-pub async fn study_guide(topic: Option<String>) -> Result<()> {
+pub async fn study_guide(topic: Option<String>, options: GenerationOptions) -> Result<()> {
     let topic = match topic {
         Some(t) => t,
         None => Text::new("Topic or focus area (or press Enter for all materials):")
@@ -149,11 +414,23 @@ pub async fn study_guide(topic: Option<String>) -> Result<()> {
             .unwrap_or_default(),
     };
 
-    generate_content("Study Guide", prompts::STUDY_GUIDE, &topic).await
+    generate_content("Study Guide", prompts::STUDY_GUIDE, &topic, options).await
 }
 
 /// Generate flashcards
-pub async fn flashcards(topic: Option<String>) -> Result<()> {
+pub async fn flashcards(topic: Option<String>, options: GenerationOptions) -> Result<()> {
+    let topic = match topic {
+        Some(t) => t,
+        None => Text::new("Topic or focus area (or press Enter for all materials):")
+            .prompt()
+            .unwrap_or_default(),
+    };
+
+    generate_content("Flashcards", prompts::FLASHCARDS, &topic, options).await
+}
+
+/// Generate cloze (fill-in-the-blank) cards
+pub async fn cloze(topic: Option<String>, options: GenerationOptions) -> Result<()> {
     let topic = match topic {
         Some(t) => t,
         None => Text::new("Topic or focus area (or press Enter for all materials):")
@@ -161,11 +438,11 @@ pub async fn flashcards(topic: Option<String>) -> Result<()> {
             .unwrap_or_default(),
     };
 
-    generate_content("Flashcards", prompts::FLASHCARDS, &topic).await
+    generate_content("Cloze Cards", prompts::CLOZE, &topic, options).await
 }
 
 /// Generate a quiz
-pub async fn quiz(topic: Option<String>) -> Result<()> {
+pub async fn quiz(topic: Option<String>, options: GenerationOptions) -> Result<()> {
     let topic = match topic {
         Some(t) => t,
         None => Text::new("Topic or focus area (or press Enter for all materials):")
@@ -173,7 +450,149 @@ pub async fn quiz(topic: Option<String>) -> Result<()> {
             .unwrap_or_default(),
     };
 
-    generate_content("Quiz", prompts::QUIZ, &topic).await
+    generate_content("Quiz", prompts::QUIZ, &topic, options).await
+}
+
+/// Generate practice problems, keeping their worked solutions out of sight
+/// until asked for and saving them to a separate file
+pub async fn problems(topic: Option<String>, count: u8, difficulty: Difficulty) -> Result<()> {
+    let topic = match topic {
+        Some(t) => t,
+        None => Text::new("Topic or focus area (or press Enter for all materials):")
+            .prompt()
+            .unwrap_or_default(),
+    };
+
+    let config = Config::load()?;
+    let client = match crate::llm::client(&config) {
+        Some(client) => client,
+        None => {
+            println!(
+                "{} No API key configured. Run {} to set up.",
+                "Error:".red().bold(),
+                "librarian config".cyan()
+            );
+            return Ok(());
+        }
+    };
+
+    let focus = if topic.is_empty() {
+        String::new()
+    } else {
+        format!(" focusing on: {}", topic)
+    };
+    let request = format!(
+        "Write {} practice problems{}. Make them {}.\n\nCOURSE MATERIALS:\n",
+        count,
+        focus,
+        difficulty.describe()
+    );
+    let (context, _) = get_document_context(&topic, &prompt_messages(prompts::PROBLEMS, &request))?;
+    if context.is_empty() {
+        println!(
+            "{} No documents found in current bucket. Add materials first with {}",
+            "Error:".red(),
+            "librarian add".cyan()
+        );
+        return Ok(());
+    }
+
+    let bucket_name = bucket::get_current_bucket()?
+        .map(|b| b.name)
+        .unwrap_or_else(|| "(default)".to_string());
+
+    println!("\n{} {}", "Bucket:".dimmed(), bucket_name.cyan());
+    println!(
+        "{} {} {} problems",
+        "Generating:".dimmed(),
+        count.to_string().yellow(),
+        format!("{:?}", difficulty).to_lowercase().yellow()
+    );
+    print_scope(&DocumentStore::new(&Database::open()?))?;
+    if !topic.is_empty() {
+        println!("{} {}", "Focus:".dimmed(), topic);
+    }
+    print!("{} ", "Working...".dimmed());
+    std::io::stdout().flush().ok();
+
+    let messages = prompt_messages(prompts::PROBLEMS, &format!("{}{}", request, context));
+
+    let set: ProblemSet = match client.chat_as(&messages).await {
+        Ok(set) => set,
+        Err(e) => {
+            println!("\n{} {}", "Error:".red(), e);
+            return Ok(());
+        }
+    };
+    print!("\r{}\r", " ".repeat(20));
+
+    let title = if topic.is_empty() {
+        "Practice Problems".to_string()
+    } else {
+        format!("Practice Problems: {}", topic)
+    };
+    let problems = set.problems_markdown(&title);
+    let solutions = set.solutions_markdown(&title);
+
+    println!("\n{}", "─".repeat(50).dimmed());
+    crate::render::print_markdown(&problems);
+    if config.show_usage {
+        crate::commands::chat::print_usage(&client);
+    }
+    println!("{}", "─".repeat(50).dimmed());
+
+    let reveal = Select::new("Show the worked solutions?", vec!["No", "Yes"]).prompt()?;
+    if reveal == "Yes" {
+        crate::render::print_markdown(&solutions);
+        println!("{}", "─".repeat(50).dimmed());
+    }
+
+    save_problem_set(&problems, &solutions)
+}
+
+/// Offer to save a problem set as problems.md and solutions.md in a folder
+/// of its own under the bucket's generated/ folder
+fn save_problem_set(problems: &str, solutions: &str) -> Result<()> {
+    let save_options = vec![
+        "📚  Save & add to library  │ Save both files and make them searchable",
+        "💾  Save files only        │ Just save to disk",
+        "❌  Don't save             │ Discard output",
+    ];
+    let save = Select::new("What would you like to do?", save_options).prompt()?;
+    if save.contains("Don't save") {
+        println!("{}", "Output not saved.".dimmed());
+        return Ok(());
+    }
+
+    let default_name = format!("problems-{}", Local::now().format("%Y%m%d-%H%M"));
+    let folder = Text::new("Folder name:")
+        .with_default(&default_name)
+        .prompt()?;
+    let dir = get_save_path(&folder)?;
+    std::fs::create_dir_all(&dir)?;
+
+    for (filename, content, content_type) in [
+        ("problems.md", problems, "Practice Problems"),
+        ("solutions.md", solutions, "Practice Solutions"),
+    ] {
+        let path = dir.join(filename);
+        std::fs::write(&path, content)?;
+        if save.contains("add to library") {
+            let name = format!("{}/{}", folder, filename);
+            ingest_generated_content(&path, &name, content_type, content)?;
+        }
+    }
+
+    println!(
+        "{} Saved problems.md and solutions.md to {}",
+        "✓".green(),
+        dir.display().to_string().cyan()
+    );
+    if save.contains("add to library") {
+        println!("{} Added to your library - now searchable!", "✓".green());
+    }
+
+    Ok(())
 }
 
 /// Generate a summary
@@ -185,14 +604,464 @@ pub async fn summary(topic: Option<String>) -> Result<()> {
             .unwrap_or_default(),
     };
 
-    generate_content("Summary", prompts::SUMMARY, &topic).await
+    generate_content(
+        "Summary",
+        prompts::SUMMARY,
+        &topic,
+        GenerationOptions::default(),
+    )
+    .await
+}
+
+/// Turn a recording's transcript into structured, timestamped lecture notes
+pub async fn lecture_notes(doc_id: Option<i64>) -> Result<()> {
+    let config = Config::load()?;
+    let client = match crate::llm::client(&config) {
+        Some(client) => client,
+        None => {
+            println!(
+                "{} No API key configured. Run {} to set up.",
+                "Error:".red().bold(),
+                "librarian config".cyan()
+            );
+            return Ok(());
+        }
+    };
+
+    let db = Database::open()?;
+    let doc_store = DocumentStore::new(&db);
+    let chunk_store = ChunkStore::new(&db);
+    chunk_store.init_schema()?;
+
+    let doc = match doc_id {
+        Some(id) => match doc_store.get(id)? {
+            Some(doc) => doc,
+            None => {
+                println!("{} No document with ID {}", "Error:".red(), id);
+                return Ok(());
+            }
+        },
+        None => {
+            let recordings: Vec<_> = doc_store
+                .list()?
+                .into_iter()
+                .filter(is_transcript)
+                .collect();
+            if recordings.is_empty() {
+                println!(
+                    "{} No recordings in this bucket. Add a lecture with {}",
+                    "Error:".red(),
+                    "librarian add lecture.mp3".cyan()
+                );
+                return Ok(());
+            }
+            let options: Vec<String> = recordings
+                .iter()
+                .map(|d| format!("{:>4}  {}", d.id, d.filename))
+                .collect();
+            let choice = Select::new("Which recording?", options.clone()).prompt()?;
+            let index = options.iter().position(|o| *o == choice).unwrap_or(0);
+            recordings[index].clone()
+        }
+    };
+
+    if !is_transcript(&doc) {
+        println!(
+            "{} {} is a {} document; lecture notes need an audio, video or YouTube transcript.",
+            "Error:".red(),
+            doc.filename.cyan(),
+            doc.content_type
+        );
+        return Ok(());
+    }
+
+    let chunks = chunk_store.get_for_document(doc.id)?;
+    let transcript = timestamped_transcript(&doc.content, &chunks);
+
+    // Long lectures are taken a part at a time, each within the context window
+    // left beside the instructions (counted in their longer, multi-part form)
+    let prompt = prompt_messages(
+        prompts::LECTURE_NOTES,
+        &lecture_request(&doc.filename, Some((99, 99))),
+    );
+    let budget = client.available_context_tokens(&prompt, 8192).max(1000);
+    let parts = transcript_parts(&transcript, budget);
+
+    println!("\n{} {}", "Recording:".dimmed(), doc.filename.cyan());
+    println!("{} {}", "Generating:".dimmed(), "Lecture Notes".yellow());
+    if parts.len() > 1 {
+        println!("{} {} parts", "Length:".dimmed(), parts.len());
+    }
+    println!("\n{}", "─".repeat(50).dimmed());
+
+    let title = format!("# Lecture Notes: {}\n\n", doc.filename);
+    crate::render::print_markdown(&title);
+    let mut notes = title;
+
+    for (i, part) in parts.iter().enumerate() {
+        let position = (parts.len() > 1).then_some((i + 1, parts.len()));
+        let request = lecture_request(&doc.filename, position);
+        let messages = prompt_messages(prompts::LECTURE_NOTES, &format!("{}{}", request, part));
+
+        match crate::render::stream_answer(&client, &messages).await {
+            Ok(response) => {
+                notes.push_str(response.trim());
+                notes.push_str("\n\n");
+            }
+            Err(e) => {
+                println!("{} {}", "Error:".red(), e);
+                return Ok(());
+            }
+        }
+    }
+
+    if config.show_usage {
+        crate::commands::chat::print_usage(&client);
+    }
+    println!("{}", "─".repeat(50).dimmed());
+
+    offer_save("Lecture Notes", &doc.filename, notes.trim_end())
+}
+
+/// Whether a document's content is a transcript (audio, video or YouTube)
+fn is_transcript(doc: &Document) -> bool {
+    matches!(doc.content_type.as_str(), "audio" | "video" | "youtube")
+}
+
+/// A transcript with a `[m:ss]` marker wherever one of its chunks starts,
+/// found by matching the chunk's opening words in the full text
+fn timestamped_transcript(content: &str, chunks: &[StoredChunk]) -> String {
+    let mut out = String::with_capacity(content.len() + chunks.len() * 10);
+    let mut copied = 0;
+
+    for chunk in chunks {
+        let Some(seconds) = chunk.start_time else {
+            continue;
+        };
+        let opening_len = chunk
+            .content
+            .char_indices()
+            .nth(60)
+            .map_or(chunk.content.len(), |(i, _)| i);
+        let opening = &chunk.content[..opening_len];
+        let Some(found) = content[copied..].find(opening.trim()) else {
+            continue;
+        };
+        let at = copied + found;
+        out.push_str(&content[copied..at]);
+        if !out.is_empty() && !out.ends_with(char::is_whitespace) {
+            out.push(' ');
+        }
+        out.push_str(&format!("[{}] ", crate::ingest::format_timestamp(seconds)));
+        copied = at;
+    }
+    out.push_str(&content[copied..]);
+    out
+}
+
+/// The request for notes on (part `n` of `total` of) a transcript, which the
+/// transcript text follows
+fn lecture_request(filename: &str, position: Option<(usize, usize)>) -> String {
+    let position = match position {
+        Some((n, total)) => format!(
+            " This is part {} of {}; continue the notes from where the last part ended.",
+            n, total
+        ),
+        None => String::new(),
+    };
+    format!(
+        "Write lecture notes for this transcript of {}.{}\n\n",
+        filename, position
+    )
+}
+
+/// Split text into parts of at most `max_tokens`, breaking at a line or
+/// word boundary
+fn transcript_parts(text: &str, max_tokens: usize) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = text.trim();
+
+    while !rest.is_empty() {
+        let mut end = tokens::fit(rest, max_tokens);
+        if end < rest.len() {
+            let head = &rest[..end];
+            end = head
+                .rfind('\n')
+                .or_else(|| head.rfind(' '))
+                .filter(|&i| i > 0)
+                .unwrap_or(end);
+        }
+        if end == 0 {
+            end = rest.len();
+        }
+        parts.push(rest[..end].trim());
+        rest = rest[end..].trim_start();
+    }
+    parts
+}
+
+/// Plan the days up to an exam: survey the bucket's documents for topics,
+/// spread them over the days left and offer to show each day's share in
+/// `review`
+pub async fn study_plan(exam_date: Option<NaiveDate>) -> Result<()> {
+    let exam_date = match exam_date {
+        Some(date) => date,
+        None => {
+            let input = Text::new("Exam date (YYYY-MM-DD):").prompt()?;
+            match input.trim().parse() {
+                Ok(date) => date,
+                Err(_) => {
+                    println!("{} Dates look like 2026-05-10", "Error:".red());
+                    return Ok(());
+                }
+            }
+        }
+    };
+    let today = Local::now().date_naive();
+    if exam_date <= today {
+        println!("{} The exam date has to be after today", "Error:".red());
+        return Ok(());
+    }
+
+    let config = Config::load()?;
+    let client = match crate::llm::client(&config) {
+        Some(client) => client,
+        None => {
+            println!(
+                "{} No API key configured. Run {} to set up.",
+                "Error:".red().bold(),
+                "librarian config".cyan()
+            );
+            return Ok(());
+        }
+    };
+
+    let db = Database::open()?;
+    let chunk_store = ChunkStore::new(&db);
+    chunk_store.init_schema()?;
+    let scope = documents_in_scope();
+    let documents: Vec<Document> = DocumentStore::new(&db)
+        .list()?
+        .into_iter()
+        .filter(|d| !d.content_type.starts_with("generated-"))
+        .filter(|d| scope.is_empty() || scope.contains(&d.id))
+        .collect();
+
+    if documents.is_empty() {
+        println!(
+            "{} No documents found in current bucket. Add materials first with {}",
+            "Error:".red(),
+            "librarian add".cyan()
+        );
+        return Ok(());
+    }
+
+    let bucket_name = bucket::get_current_bucket()?
+        .map(|b| b.name)
+        .unwrap_or_else(|| "(default)".to_string());
+
+    println!("\n{} {}", "Bucket:".dimmed(), bucket_name.cyan());
+    println!("{} {}", "Generating:".dimmed(), "Study Plan".yellow());
+    print_scope(&DocumentStore::new(&db))?;
+    println!(
+        "{} {} ({} days away)",
+        "Exam:".dimmed(),
+        exam_date.format("%a %b %-d"),
+        (exam_date - today).num_days()
+    );
+    print!("{} ", "Working...".dimmed());
+    std::io::stdout().flush().ok();
+
+    let request = format!(
+        "Plan revision for an exam on {} from these course materials:\n\n",
+        exam_date
+    );
+    let budget = client
+        .available_context_tokens(&prompt_messages(prompts::STUDY_PLAN, &request), 2048)
+        .max(500);
+    let outline = course_outline(&chunk_store, &documents, budget)?;
+    let messages = prompt_messages(prompts::STUDY_PLAN, &format!("{}{}", request, outline));
+
+    let topics: StudyTopics = match client.chat_as(&messages).await {
+        Ok(topics) => topics,
+        Err(e) => {
+            println!("\n{} {}", "Error:".red(), e);
+            return Ok(());
+        }
+    };
+    print!("\r{}\r", " ".repeat(20));
+
+    let entries = schedule_topics(&topics.topics, today, exam_date);
+    let markdown = plan_markdown(&bucket_name, exam_date, &entries);
+
+    println!("\n{}", "─".repeat(50).dimmed());
+    crate::render::print_markdown(&markdown);
+    if config.show_usage {
+        crate::commands::chat::print_usage(&client);
+    }
+    println!("{}", "─".repeat(50).dimmed());
+
+    offer_save("Study Plan", &bucket_name, &markdown)?;
+
+    let remind = Select::new(
+        "Show each day's plan when you run `librarian review`?",
+        vec!["No", "Yes"],
+    )
+    .prompt()?;
+    if remind == "Yes" {
+        let count = PlanStore::new(&db).replace(exam_date, &entries)?;
+        println!(
+            "{} Added {} plan entries; {} will show each day's",
+            "✓".green(),
+            count,
+            "librarian review".cyan()
+        );
+    }
+
+    Ok(())
+}
+
+/// Each document's name and type with its section titles (or, without
+/// sections, its opening), sharing `budget` tokens between them
+fn course_outline(
+    chunk_store: &ChunkStore,
+    documents: &[Document],
+    budget: usize,
+) -> Result<String> {
+    let per_document = (budget / documents.len()).max(60);
+    let mut outline = String::new();
+    let mut used = 0;
+
+    for doc in documents {
+        if used >= budget {
+            break;
+        }
+
+        let mut sections: Vec<String> = Vec::new();
+        for chunk in chunk_store.get_for_document(doc.id)? {
+            if let Some(section) = chunk.section {
+                if !sections.contains(&section) {
+                    sections.push(section);
+                }
+            }
+        }
+        let summary = if sections.is_empty() {
+            doc.content.clone()
+        } else {
+            format!("Sections: {}", sections.join("; "))
+        };
+        let summary = &summary[..tokens::fit(&summary, per_document)];
+
+        let block = format!(
+            "--- {} ({}) ---\n{}\n\n",
+            doc.filename, doc.content_type, summary
+        );
+        used += tokens::estimate(&block);
+        outline.push_str(&block);
+    }
+
+    Ok(outline)
+}
+
+/// Spread topics, in order, over the days from `today` to the day before the
+/// exam in proportion to their effort. The last day is kept for a final
+/// review when there are at least three, and days no topic lands on are
+/// left for catching up.
+fn schedule_topics(topics: &[StudyTopic], today: NaiveDate, exam: NaiveDate) -> Vec<PlanEntry> {
+    let days: Vec<NaiveDate> = today.iter_days().take_while(|d| *d < exam).collect();
+    let (study_days, review_day) = match days.split_last() {
+        Some((last, rest)) if days.len() >= 3 => (rest, Some(*last)),
+        _ => (&days[..], None),
+    };
+    let Some(last_study_day) = study_days.last() else {
+        return Vec::new();
+    };
+
+    let effort = |t: &StudyTopic| t.effort.clamp(1, 3);
+    let total: u32 = topics.iter().map(effort).sum::<u32>().max(1);
+    let mut entries = Vec::new();
+    let mut done = 0;
+
+    for topic in topics {
+        // Place each topic on the day its midpoint falls in
+        let middle = done as f64 + effort(topic) as f64 / 2.0;
+        let index = (middle / total as f64 * study_days.len() as f64) as usize;
+        done += effort(topic);
+
+        let mut details = topic.tasks.join("\n");
+        if !topic.documents.is_empty() {
+            if !details.is_empty() {
+                details.push('\n');
+            }
+            details.push_str(&format!("Sources: {}", topic.documents.join(", ")));
+        }
+        entries.push(PlanEntry {
+            day: study_days.get(index).copied().unwrap_or(*last_study_day),
+            title: topic.title.clone(),
+            details,
+        });
+    }
+
+    for day in study_days {
+        if !entries.iter().any(|e| e.day == *day) {
+            entries.push(PlanEntry {
+                day: *day,
+                title: "Catch up and review".to_string(),
+                details: "Revisit anything from earlier days that didn't stick\n\
+                          Run `librarian review`"
+                    .to_string(),
+            });
+        }
+    }
+    if let Some(day) = review_day {
+        entries.push(PlanEntry {
+            day,
+            title: "Final review".to_string(),
+            details: "Skim your notes on every topic\n\
+                      Take a practice quiz with `librarian quiz`\n\
+                      Run `librarian review`"
+                .to_string(),
+        });
+    }
+
+    // Stable, so topics keep their order within a day
+    entries.sort_by_key(|e| e.day);
+    entries
+}
+
+/// A study plan as markdown, a heading per day
+fn plan_markdown(bucket: &str, exam: NaiveDate, entries: &[PlanEntry]) -> String {
+    let mut out = format!(
+        "# Study Plan: {}\n\nExam on **{}**.\n",
+        bucket,
+        exam.format("%A, %B %-d, %Y")
+    );
+
+    let mut current = None;
+    for entry in entries {
+        if current != Some(entry.day) {
+            current = Some(entry.day);
+            let left = (exam - entry.day).num_days();
+            out.push_str(&format!(
+                "\n## {} ({} day{} left)\n",
+                entry.day.format("%a %b %-d"),
+                left,
+                if left == 1 { "" } else { "s" }
+            ));
+        }
+        out.push_str(&format!("\n### {}\n\n", entry.title));
+        for line in entry.details.lines() {
+            out.push_str(&format!("- {}\n", line));
+        }
+    }
+    out
 }
 
 /// Interactive homework help
 pub async fn homework_help() -> Result<()> {
     let config = Config::load()?;
-    let api_key = match config.get_api_key() {
-        Some(key) => key,
+    let client = match crate::llm::client(&config) {
+        Some(client) => client,
         None => {
             println!(
                 "{} No API key configured. Run {} to set up.",
@@ -203,10 +1072,14 @@ pub async fn homework_help() -> Result<()> {
         }
     };
 
-    let client = GroqClient::new(api_key, config.default_model);
-
     // Get context
-    let context = get_document_context("")?;
+    let (context, _) = get_document_context(
+        "",
+        &prompt_messages(
+            prompts::HOMEWORK_HELP,
+            "COURSE MATERIALS:\n\n\n---\n\nHOMEWORK PROBLEM: ",
+        ),
+    )?;
 
     if context.is_empty() {
         println!(
@@ -221,7 +1094,7 @@ pub async fn homework_help() -> Result<()> {
     println!("Type your homework question or problem.");
     println!("Type {} to exit.\n", "done".dimmed());
 
-    let mut conversation = vec![crate::llm::groq::Message {
+    let mut conversation = vec![crate::llm::Message {
         role: "system".to_string(),
         content: prompts::HOMEWORK_HELP.to_string(),
     }];
@@ -248,7 +1121,7 @@ pub async fn homework_help() -> Result<()> {
             context, input
         );
 
-        conversation.push(crate::llm::groq::Message {
+        conversation.push(crate::llm::Message {
             role: "user".to_string(),
             content: user_message,
         });
@@ -256,15 +1129,18 @@ pub async fn homework_help() -> Result<()> {
         print!("{} ", "Tutor:".magenta().bold());
         std::io::Write::flush(&mut std::io::stdout()).ok();
 
-        match client.chat_stream(&conversation).await {
+        match crate::render::stream_answer(&client, &conversation).await {
             Ok(response) => {
+                if config.show_usage {
+                    crate::commands::chat::print_usage(&client);
+                }
                 println!(); // Extra newline after streaming
 
                 // Store simplified version for history
                 if let Some(last_msg) = conversation.last_mut() {
                     last_msg.content = input.to_string();
                 }
-                conversation.push(crate::llm::groq::Message {
+                conversation.push(crate::llm::Message {
                     role: "assistant".to_string(),
                     content: response,
                 });
@@ -280,11 +1156,16 @@ pub async fn homework_help() -> Result<()> {
 }
 
 /// Core generation function
-async fn generate_content(name: &str, system_prompt: &str, topic: &str) -> Result<()> {
+async fn generate_content(
+    name: &str,
+    system_prompt: &str,
+    topic: &str,
+    options: GenerationOptions,
+) -> Result<()> {
     let config = Config::load()?;
 
-    let api_key = match config.get_api_key() {
-        Some(key) => key,
+    let client = match crate::llm::client(&config) {
+        Some(client) => client,
         None => {
             println!(
                 "{} No API key configured. Run {} to set up.",
@@ -295,10 +1176,26 @@ async fn generate_content(name: &str, system_prompt: &str, topic: &str) -> Resul
         }
     };
 
-    let client = GroqClient::new(api_key, config.default_model);
+    let system = match options.instructions(name) {
+        Some(instructions) => format!("{}\n\n{}", system_prompt, instructions),
+        None => system_prompt.to_string(),
+    };
+    let request = if topic.is_empty() {
+        format!(
+            "Create a {} from the following course materials:\n\n",
+            name.to_lowercase()
+        )
+    } else {
+        format!(
+            "Create a {} focused on '{}' from the following course materials:\n\n",
+            name.to_lowercase(),
+            topic
+        )
+    };
 
     // Get document context
-    let context = get_document_context(topic)?;
+    let (context, source_chunks) =
+        get_document_context(topic, &prompt_messages(&system, &request))?;
 
     if context.is_empty() {
         println!(
@@ -315,112 +1212,201 @@ async fn generate_content(name: &str, system_prompt: &str, topic: &str) -> Resul
 
     println!("\n{} {}", "Bucket:".dimmed(), bucket_name.cyan());
     println!("{} {}", "Generating:".dimmed(), name.yellow());
+    print_scope(&DocumentStore::new(&Database::open()?))?;
     if !topic.is_empty() {
         println!("{} {}", "Focus:".dimmed(), topic);
     }
+    if options.instructions(name).is_some() {
+        println!(
+            "{} {}, {}",
+            "Style:".dimmed(),
+            format!("{:?}", options.length).to_lowercase(),
+            format!("{:?}", options.difficulty).to_lowercase()
+        );
+    }
     print!("{} ", "Working...".dimmed());
 
     // Build the request
-    let user_message = if topic.is_empty() {
-        format!(
-            "Create a {} from the following course materials:\n\n{}",
-            name.to_lowercase(),
-            context
-        )
-    } else {
-        format!(
-            "Create a {} focused on '{}' from the following course materials:\n\n{}",
-            name.to_lowercase(),
-            topic,
-            context
-        )
+    let messages = prompt_messages(&system, &format!("{}{}", request, context));
+
+    // Flashcards, cloze cards and quizzes come back as JSON so they can be
+    // saved as study items; everything else streams as markdown
+    let generated = match name {
+        "Flashcards" | "Cloze Cards" | "Quiz" => {
+            let generated = generate_study_items(&client, name, &messages).await;
+            print!("\r{}\r", " ".repeat(20));
+            println!("\n{}", "─".repeat(50).dimmed());
+            if let Ok((markdown, _)) = &generated {
+                crate::render::print_markdown(markdown);
+            }
+            generated
+        }
+        _ => {
+            // Clear the "Working..." message and start streaming
+            print!("\r{}\r", " ".repeat(20));
+            println!("\n{}", "─".repeat(50).dimmed());
+            std::io::stdout().flush().ok();
+
+            crate::render::stream_answer(&client, &messages)
+                .await
+                .map(|response| (response, Vec::new()))
+        }
     };
 
-    let messages = vec![
-        crate::llm::groq::Message {
-            role: "system".to_string(),
-            content: system_prompt.to_string(),
-        },
-        crate::llm::groq::Message {
-            role: "user".to_string(),
-            content: user_message,
-        },
-    ];
-
-    // Clear the "Working..." message and start streaming
-    print!("\r{}\r", " ".repeat(20));
-    println!("\n{}", "─".repeat(50).dimmed());
-    std::io::stdout().flush().ok();
+    match generated {
+        Ok((response, items)) => {
+            if config.show_usage {
+                crate::commands::chat::print_usage(&client);
+            }
 
-    match client.chat_stream(&messages).await {
-        Ok(response) => {
-            // Render formatted markdown version
-            println!("\n{}", "─── Formatted Output ───".dimmed());
-            crate::render::render_markdown(&response);
             println!("{}", "─".repeat(50).dimmed());
 
-            // Offer to save
-            let save_options = vec![
-                "📚  Save & add to library  │ Save file and make it searchable",
-                "💾  Save file only         │ Just save to disk",
-                "❌  Don't save             │ Discard output",
-            ];
-            let save = Select::new("What would you like to do?", save_options).prompt()?;
-
-            if save.contains("Don't save") {
-                println!("{}", "Output not saved.".dimmed());
-            } else {
-                // Generate default filename
-                let default_name = format!(
-                    "{}-{}.md",
-                    name.to_lowercase().replace(' ', "-"),
-                    chrono::Local::now().format("%Y%m%d-%H%M")
-                );
+            offer_save(name, topic, &response)?;
 
-                let filename = Text::new("Filename:")
-                    .with_default(&default_name)
-                    .prompt()?;
+            // Offer to save as study items for spaced repetition
+            offer_save_study_items(&items, &source_chunks, topic)?;
+        }
+        Err(e) => {
+            println!("{} {}", "Error:".red(), e);
+        }
+    }
 
-                // Determine save path
-                let save_path = get_save_path(&filename)?;
+    Ok(())
+}
 
-                // Ensure directory exists
-                if let Some(parent) = save_path.parent() {
-                    std::fs::create_dir_all(parent)?;
-                }
+/// Offer to save generated markdown: as a file, optionally added to the
+/// library, or (for longer documents) as a PDF or HTML page
+fn offer_save(name: &str, topic: &str, response: &str) -> Result<()> {
+    let mut save_options = vec![
+        "📚  Save & add to library  │ Save file and make it searchable",
+        "💾  Save file only         │ Just save to disk",
+    ];
+    if matches!(name, "Study Guide" | "Lecture Notes") {
+        save_options.push("📄  Save as PDF           │ Print-ready copy");
+    }
+    if matches!(name, "Study Guide" | "Summary" | "Lecture Notes") {
+        save_options.push("🌐  Save as HTML          │ Styled page to open in a browser or share");
+    }
+    save_options.push("❌  Don't save             │ Discard output");
+    let save = Select::new("What would you like to do?", save_options).prompt()?;
+
+    if save.contains("Don't save") {
+        println!("{}", "Output not saved.".dimmed());
+    } else if save.contains("PDF") {
+        save_formatted(name, topic, response, "pdf")?;
+    } else if save.contains("HTML") {
+        save_formatted(name, topic, response, "html")?;
+    } else {
+        // Generate default filename
+        let default_name = format!(
+            "{}-{}.md",
+            name.to_lowercase().replace(' ', "-"),
+            chrono::Local::now().format("%Y%m%d-%H%M")
+        );
 
-                // Save the file
-                std::fs::write(&save_path, &response)?;
-                println!(
-                    "{} Saved to {}",
-                    "✓".green(),
-                    save_path.display().to_string().cyan()
-                );
+        let filename = Text::new("Filename:")
+            .with_default(&default_name)
+            .prompt()?;
 
-                // If user wants to add to library, ingest it
-                if save.contains("add to library") {
-                    ingest_generated_content(&save_path, &filename, name, &response)?;
-                    println!("{} Added to your library - now searchable!", "✓".green());
-                }
-            }
+        // Determine save path
+        let save_path = get_save_path(&filename)?;
 
-            // Offer to save as study items for spaced repetition
-            if name == "Flashcards" || name == "Quiz" {
-                offer_save_study_items(name, &response)?;
-            }
+        // Ensure directory exists
+        if let Some(parent) = save_path.parent() {
+            std::fs::create_dir_all(parent)?;
         }
-        Err(e) => {
-            println!("{} {}", "Error:".red(), e);
+
+        // Save the file
+        std::fs::write(&save_path, response)?;
+        println!(
+            "{} Saved to {}",
+            "✓".green(),
+            save_path.display().to_string().cyan()
+        );
+
+        // If user wants to add to library, ingest it
+        if save.contains("add to library") {
+            ingest_generated_content(&save_path, &filename, name, response)?;
+            println!("{} Added to your library - now searchable!", "✓".green());
         }
     }
 
     Ok(())
 }
 
-/// Parse generated flashcards/quiz output into study items and offer to save
-fn offer_save_study_items(content_type: &str, response: &str) -> Result<()> {
-    let items = parse_qa_pairs(content_type, response);
+/// Ask for flashcards, cloze cards or a quiz as JSON, returning it as
+/// markdown along with the `(item_type, front, back)` study items it holds.
+/// A cloze item keeps its `{{blanks}}` in the front; the back lists the
+/// hidden words.
+async fn generate_study_items(
+    client: &impl ChatProvider,
+    name: &str,
+    messages: &[crate::llm::Message],
+) -> Result<(String, Vec<(String, String, String)>)> {
+    if name == "Flashcards" {
+        // Models sometimes ignore the JSON shape and write Q:/A: cards anyway
+        let response = client.chat_json(messages).await?;
+        let set = match schema::parse::<FlashcardSet>(&response) {
+            Ok(set) => set,
+            Err(e) => {
+                let set = FlashcardSet::from_markdown(&response);
+                if set.flashcards.is_empty() {
+                    return Err(e);
+                }
+                set
+            }
+        };
+        let items = set
+            .flashcards
+            .iter()
+            .map(|c| {
+                (
+                    "flashcard".to_string(),
+                    c.question.clone(),
+                    c.answer.clone(),
+                )
+            })
+            .collect();
+        return Ok((set.to_markdown(), items));
+    }
+
+    if name == "Cloze Cards" {
+        let set: ClozeSet = client.chat_as(messages).await?;
+        let items = set
+            .cards()
+            .map(|c| {
+                (
+                    "cloze".to_string(),
+                    c.text.clone(),
+                    cloze_answers(&c.text).join(", "),
+                )
+            })
+            .collect();
+        return Ok((set.to_markdown(), items));
+    }
 
+    let quiz: Quiz = client.chat_as(messages).await?;
+    let items = quiz
+        .questions
+        .iter()
+        .map(|q| {
+            (
+                q.item_type().to_string(),
+                q.question().to_string(),
+                q.answer_text().to_string(),
+            )
+        })
+        .collect();
+    Ok((quiz.to_markdown(), items))
+}
+
+/// Offer to save generated flashcards/quiz questions as study items. Each
+/// saved item is linked back to the context chunk it most likely came from.
+fn offer_save_study_items(
+    items: &[(String, String, String)],
+    source_chunks: &[i64],
+    topic: &str,
+) -> Result<()> {
     if items.is_empty() {
         return Ok(());
     }
@@ -439,16 +1425,7 @@ fn offer_save_study_items(content_type: &str, response: &str) -> Result<()> {
     if let Ok(s) = choice {
         if s.contains("Save") {
             let db = Database::open()?;
-            let store = crate::storage::StudyStore::new(&db);
-
-            let bulk: Vec<(Option<i64>, &str, &str, &str)> = items
-                .iter()
-                .map(|(item_type, front, back)| {
-                    (None, item_type.as_str(), front.as_str(), back.as_str())
-                })
-                .collect();
-
-            let count = store.bulk_insert(&bulk)?;
+            let count = save_linked_study_items(&db, items, source_chunks, topic)?.len();
             println!(
                 "{} Saved {} items for spaced repetition!",
                 "✓".green(),
@@ -460,89 +1437,91 @@ fn offer_save_study_items(content_type: &str, response: &str) -> Result<()> {
     Ok(())
 }
 
-/// Parse Q/A pairs from generated output
-#[allow(clippy::needless_range_loop)]
-fn parse_qa_pairs(content_type: &str, text: &str) -> Vec<(String, String, String)> {
-    let mut items = Vec::new();
-    let item_type = if content_type == "Flashcards" {
-        "flashcard"
-    } else {
-        "quiz"
-    };
+/// Save study items, linking each to its best-matching source chunk and
+/// tagging it with the topic and that chunk's document, so `review --tag`
+/// can pick them out. Items are `(item_type, front, back)`; returns the new
+/// items' IDs.
+pub fn save_linked_study_items(
+    db: &Database,
+    items: &[(String, String, String)],
+    source_chunks: &[i64],
+    topic: &str,
+) -> Result<Vec<i64>> {
+    let study_store = StudyStore::new(db);
+    let chunk_store = ChunkStore::new(db);
+    let doc_store = DocumentStore::new(db);
+    let link_store = LinkStore::new(db);
 
-    let lines: Vec<&str> = text.lines().collect();
-    let mut i = 0;
-
-    while i < lines.len() {
-        let line = lines[i].trim();
-
-        // Match "Q: ..." / "A: ..." pattern (flashcards)
-        if let Some(q) = line.strip_prefix("Q:").or_else(|| line.strip_prefix("Q.")) {
-            let question = q.trim().to_string();
-            // Look for answer on next lines
-            for j in (i + 1)..lines.len().min(i + 4) {
-                let ans_line = lines[j].trim();
-                if let Some(a) = ans_line
-                    .strip_prefix("A:")
-                    .or_else(|| ans_line.strip_prefix("A."))
-                {
-                    items.push((
-                        item_type.to_string(),
-                        question.clone(),
-                        a.trim().to_string(),
-                    ));
-                    i = j + 1;
-                    break;
-                }
-            }
-        }
+    chunk_store.init_schema()?;
 
-        // Match numbered questions with **Answer:** pattern
-        if line.starts_with(|c: char| c.is_ascii_digit()) {
-            // Check for answer a few lines down
-            for j in (i + 1)..lines.len().min(i + 8) {
-                let ans_line = lines[j].trim().to_lowercase();
-                if ans_line.starts_with("**answer") || ans_line.starts_with("answer:") {
-                    let q_text = line
-                        .trim_start_matches(|c: char| {
-                            c.is_ascii_digit() || c == '.' || c == ')' || c == ' '
-                        })
-                        .to_string();
-                    let a_text = lines[j]
-                        .trim()
-                        .trim_start_matches("**")
-                        .trim_start_matches("Answer")
-                        .trim_start_matches("answer")
-                        .trim_start_matches("**")
-                        .trim_start_matches(':')
-                        .trim_start_matches("**")
-                        .trim()
-                        .trim_end_matches("**")
-                        .trim()
-                        .to_string();
-
-                    if !q_text.is_empty() && !a_text.is_empty() {
-                        items.push((item_type.to_string(), q_text, a_text));
-                    }
-                    i = j + 1;
-                    break;
-                }
-            }
-        }
+    let candidates: Vec<(i64, String)> = source_chunks
+        .iter()
+        .filter_map(|id| chunk_store.get(*id).ok().flatten())
+        .map(|c| (c.id, c.content))
+        .collect();
 
-        i += 1;
+    // All or nothing: a failure partway shouldn't leave unlinked cards behind
+    let tx = db.conn.unchecked_transaction()?;
+    let mut ids = Vec::new();
+    for (item_type, front, back) in items {
+        let source = crate::search::best_source(&format!("{} {}", front, back), &candidates);
+        let document_id = source
+            .and_then(|id| chunk_store.get(id).ok().flatten())
+            .map(|c| c.document_id);
+
+        let item_id = study_store.insert(document_id, item_type, front, back)?;
+        let filename = document_id
+            .and_then(|id| doc_store.get(id).ok().flatten())
+            .map(|d| d.filename);
+        // Commas separate tags, so keep them out of each one
+        let tags = [Some(topic), filename.as_deref()]
+            .into_iter()
+            .flatten()
+            .map(|t| t.replace(',', " "))
+            .collect::<Vec<_>>()
+            .join(",");
+        if let Some(tags) = normalize_tags(&tags) {
+            study_store.set_tags(item_id, Some(&tags))?;
+        }
+        if let Some(chunk_id) = source {
+            link_store.add(KIND_STUDY_ITEM, item_id, KIND_CHUNK, chunk_id)?;
+        }
+        ids.push(item_id);
     }
+    tx.commit()?;
 
-    items
+    Ok(ids)
 }
 
 /// Public wrapper for quiz module access
-pub fn get_document_context_pub(topic: &str) -> Result<String> {
-    get_document_context(topic)
+pub fn get_document_context_pub(
+    topic: &str,
+    prompt: &[crate::llm::Message],
+) -> Result<(String, Vec<i64>)> {
+    get_document_context(topic, prompt)
 }
 
-/// Get document context for generation
-fn get_document_context(topic: &str) -> Result<String> {
+/// A system message and a user request, e.g. to count what a prompt costs
+/// before the course materials are added to it
+pub fn prompt_messages(system: &str, request: &str) -> Vec<crate::llm::Message> {
+    vec![
+        crate::llm::Message {
+            role: "system".to_string(),
+            content: system.to_string(),
+        },
+        crate::llm::Message {
+            role: "user".to_string(),
+            content: request.to_string(),
+        },
+    ]
+}
+
+/// Get document context for generation, along with the IDs of the chunks
+/// it was built from (for whole documents, the chunks whose text made it into
+/// the context). Only the
+/// chosen documents are used when generation has been limited to some. The
+/// context fills whatever of the model's window `prompt` and the reply leave.
+fn get_document_context(topic: &str, prompt: &[crate::llm::Message]) -> Result<(String, Vec<i64>)> {
     let db = Database::open()?;
     let doc_store = DocumentStore::new(&db);
     let chunk_store = ChunkStore::new(&db);
@@ -551,18 +1530,34 @@ fn get_document_context(topic: &str) -> Result<String> {
     chunk_store.init_schema()?;
 
     let chunk_count = chunk_store.count().unwrap_or(0);
+    let scope = documents_in_scope();
+
+    // Dynamic context sizing based on model
+    let config = Config::load()?;
+    let max_context_tokens = match crate::llm::client(&config) {
+        Some(client) => client.available_context_tokens(prompt, 8192).max(500),
+        None => 2500,
+    };
 
     // If we have chunks and a topic, use semantic search
     if chunk_count > 0 && !topic.is_empty() {
-        if let Ok(context) = build_semantic_context(&chunk_store, &doc_store, topic) {
+        if let Ok((context, chunk_ids)) =
+            build_semantic_context(&chunk_store, &doc_store, topic, &scope, max_context_tokens)
+        {
             if !context.is_empty() {
-                return Ok(context);
+                return Ok((context, chunk_ids));
             }
         }
     }
 
     // Otherwise, use all documents (up to a limit)
-    let documents = if topic.is_empty() {
+    let documents = if !scope.is_empty() {
+        let mut documents = Vec::new();
+        for id in &scope {
+            documents.extend(doc_store.get(*id)?);
+        }
+        documents
+    } else if topic.is_empty() {
         doc_store.list()?
     } else {
         let results = doc_store.search(topic)?;
@@ -574,44 +1569,43 @@ fn get_document_context(topic: &str) -> Result<String> {
     };
 
     if documents.is_empty() {
-        return Ok(String::new());
+        return Ok((String::new(), Vec::new()));
     }
 
-    // Dynamic context sizing based on model
-    let config = Config::load()?;
-    let max_context_chars = if let Some(key) = config.get_api_key() {
-        let client = GroqClient::new(key, config.default_model);
-        client
-            .available_context_chars(500, 0, 8192)
-            .clamp(2000, 30000)
+    // Chosen documents share the budget, so the last chapter isn't crowded out
+    let per_document = if scope.is_empty() {
+        max_context_tokens
     } else {
-        10000
+        max_context_tokens / documents.len()
     };
 
     let mut context = String::new();
-    let mut total_chars = 0;
+    let mut total_tokens = 0;
+    let mut chunk_ids = Vec::new();
 
-    for doc in documents.iter().take(10) {
-        if total_chars >= max_context_chars {
+    for doc in documents.iter().take(10.max(scope.len())) {
+        if total_tokens >= max_context_tokens {
             break;
         }
 
-        let remaining = max_context_chars - total_chars;
-        let content = if doc.content.len() > remaining {
-            &doc.content[..remaining]
-        } else {
-            &doc.content
-        };
+        let remaining = (max_context_tokens - total_tokens).min(per_document);
+        let content = &doc.content[..tokens::fit(&doc.content, remaining)];
+        let block = format!("--- Document: {} ---\n{}\n\n", doc.filename, content);
 
-        context.push_str(&format!(
-            "--- Document: {} ---\n{}\n\n",
-            doc.filename, content
-        ));
+        total_tokens += tokens::estimate(&block);
+        context.push_str(&block);
 
-        total_chars += content.len() + doc.filename.len() + 30;
+        // The chunks the included text came from, so cards can be linked back
+        chunk_ids.extend(
+            chunk_store
+                .get_for_document(doc.id)?
+                .into_iter()
+                .filter(|c| content.contains(c.content.trim()))
+                .map(|c| c.id),
+        );
     }
 
-    Ok(context)
+    Ok((context, chunk_ids))
 }
 
 /// Build semantic context using embeddings
@@ -619,45 +1613,37 @@ fn build_semantic_context(
     chunk_store: &ChunkStore,
     doc_store: &DocumentStore,
     query: &str,
-) -> Result<String> {
+    scope: &[i64],
+    max_context_tokens: usize,
+) -> Result<(String, Vec<i64>)> {
     use crate::embeddings;
 
-    let query_embedding = embeddings::embed_text(query)?;
-    let chunks = chunk_store.get_all_with_embeddings()?;
-
-    if chunks.is_empty() {
-        return Ok(String::new());
-    }
-
-    let chunk_embeddings: Vec<(i64, Vec<f32>)> = chunks
-        .iter()
-        .filter_map(|c| c.embedding.as_ref().map(|e| (c.id, e.clone())))
-        .collect();
-
-    let similar = embeddings::find_similar(&query_embedding, &chunk_embeddings, 10);
-
-    // Dynamic context sizing
-    let config = Config::load()?;
-    let max_context_chars = if let Some(key) = config.get_api_key() {
-        let client = GroqClient::new(key, config.default_model);
-        client
-            .available_context_chars(500, 0, 8192)
-            .clamp(2000, 30000)
+    let candidates = if scope.is_empty() {
+        let query_embedding = embeddings::embed_text(query)?;
+        chunk_store.nearest(&query_embedding, crate::search::MMR_CANDIDATES)?
     } else {
-        10000
+        // Chosen documents are few enough to rank their chunks directly
+        let query_embedding = embeddings::embed_text(query).ok();
+        let mut scoped = Vec::new();
+        for document_id in scope.iter().collect::<HashSet<_>>() {
+            scoped.extend(chunk_store.get_for_document(*document_id)?);
+        }
+        let mut ranked = crate::search::rank_chunks(query, query_embedding.as_deref(), scoped);
+        ranked.truncate(crate::search::MMR_CANDIDATES);
+        ranked
     };
+    let similar = crate::search::mmr_select(candidates, 10, crate::search::MMR_LAMBDA);
 
-    let mut context = String::new();
-    let mut total_chars = 0;
-
-    let similar_ids: Vec<i64> = similar.iter().map(|(id, _)| *id).collect();
+    if similar.is_empty() {
+        return Ok((String::new(), Vec::new()));
+    }
 
-    for chunk in &chunks {
-        if !similar_ids.contains(&chunk.id) {
-            continue;
-        }
+    let mut context = String::new();
+    let mut total_tokens = 0;
+    let mut used_ids = Vec::new();
 
-        if total_chars >= max_context_chars {
+    for (chunk, _) in &similar {
+        if total_tokens >= max_context_tokens {
             break;
         }
 
@@ -665,13 +1651,53 @@ fn build_semantic_context(
         let filename = doc
             .map(|d| d.filename)
             .unwrap_or_else(|| "Unknown".to_string());
+        let filename = format!("{}{}", filename, chunk.location());
+
+        let block = format!("--- {} ---\n{}\n\n", filename, chunk.content);
+        used_ids.push(chunk.id);
+
+        total_tokens += tokens::estimate(&block);
+        context.push_str(&block);
+    }
+
+    Ok((context, used_ids))
+}
+
+/// Save generated markdown as a PDF or HTML page ("pdf" or "html") in the
+/// bucket's generated/ folder
+fn save_formatted(name: &str, topic: &str, markdown: &str, extension: &str) -> Result<()> {
+    let default_name = format!(
+        "{}-{}.{}",
+        name.to_lowercase().replace(' ', "-"),
+        chrono::Local::now().format("%Y%m%d-%H%M"),
+        extension
+    );
+    let filename = Text::new("Filename:")
+        .with_default(&default_name)
+        .prompt()?;
 
-        context.push_str(&format!("--- {} ---\n{}\n\n", filename, chunk.content));
+    let save_path = get_save_path(&filename)?;
+    if let Some(parent) = save_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
 
-        total_chars += chunk.content.len() + filename.len() + 20;
+    let title = if topic.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}: {}", name, topic)
+    };
+    if extension == "pdf" {
+        crate::pdf::write_markdown(markdown, &title, &save_path)?;
+    } else {
+        crate::html::write_markdown(markdown, &title, &save_path)?;
     }
+    println!(
+        "{} Saved to {}",
+        "✓".green(),
+        save_path.display().to_string().cyan()
+    );
 
-    Ok(context)
+    Ok(())
 }
 
 /// Get the save path for generated content (inside bucket's generated/ folder)
@@ -721,19 +1747,158 @@ fn ingest_generated_content(
         Some("generated,study-material"),
     )?;
 
-    // Chunk and embed
-    let config = ChunkConfig::default();
-    let chunks = chunk_text(content, &config);
+    // Chunk and embed (generated material is Markdown, so split at its headings)
+    let config = ChunkConfig::current();
+    let sections = ingest::text::markdown_sections(content);
+    let chunks = chunk_sections(content, &sections, &config);
 
     for chunk in &chunks {
-        let embedding = embeddings::embed_text(&chunk.text).ok();
-        chunk_store.insert(
-            doc_id,
-            chunk.index as i64,
-            &chunk.text,
-            embedding.as_deref(),
-        )?;
+        let embedding = chunk_store.embed_text(&chunk.text).ok();
+        chunk_store.insert_chunk(doc_id, chunk, embedding.as_deref())?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(content: &str, start_time: Option<f64>) -> StoredChunk {
+        StoredChunk {
+            id: 0,
+            document_id: 1,
+            chunk_index: 0,
+            content: content.to_string(),
+            embedding: None,
+            section: None,
+            page: None,
+            start_time,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_quiz_with_mock_provider() {
+        let messages = vec![
+            crate::llm::Message {
+                role: "system".to_string(),
+                content: prompts::QUIZ.to_string(),
+            },
+            crate::llm::Message {
+                role: "user".to_string(),
+                content: "Create an interactive quiz from these materials:\n\nATP...".to_string(),
+            },
+        ];
+        let client = crate::llm::MockProvider::new();
+
+        let (markdown, items) = generate_study_items(&client, "Quiz", &messages)
+            .await
+            .unwrap();
+        let types: Vec<&str> = items.iter().map(|(t, _, _)| t.as_str()).collect();
+        assert_eq!(types, vec!["quiz_mc", "quiz_fill", "quiz_short"]);
+        assert_eq!(items[0].2, "Mitochondria");
+        assert!(markdown.contains("Where is most ATP made?"));
+
+        let (_, cards) = generate_study_items(
+            &client,
+            "Flashcards",
+            &[crate::llm::Message {
+                role: "system".to_string(),
+                content: prompts::FLASHCARDS.to_string(),
+            }],
+        )
+        .await
+        .unwrap();
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0].0, "flashcard");
+    }
+
+    #[test]
+    fn test_timestamped_transcript() {
+        let content = "Welcome back. Today we cover paging. A page table maps pages to frames.";
+        let chunks = vec![
+            chunk("Welcome back. Today we cover paging.", Some(0.0)),
+            chunk("A page table maps pages to frames.", Some(872.0)),
+            chunk("Not in the transcript", Some(900.0)),
+        ];
+
+        assert_eq!(
+            timestamped_transcript(content, &chunks),
+            "[0:00] Welcome back. Today we cover paging. [14:32] A page table maps pages to frames."
+        );
+    }
+
+    #[test]
+    fn test_schedule_topics() {
+        let date = |s: &str| s.parse::<NaiveDate>().unwrap();
+        let topic = |title: &str, effort| StudyTopic {
+            title: title.to_string(),
+            documents: vec!["os.pdf".to_string()],
+            tasks: vec!["Reread the chapter".to_string()],
+            effort,
+        };
+        let topics = vec![
+            topic("Processes", 1),
+            topic("Scheduling", 1),
+            topic("Paging", 3),
+            topic("Files", 1),
+        ];
+
+        // A week before the exam: six days to study, one for the final review
+        let entries = schedule_topics(&topics, date("2026-05-05"), date("2026-05-12"));
+        let on = |day: &str| -> Vec<&str> {
+            entries
+                .iter()
+                .filter(|e| e.day == date(day))
+                .map(|e| e.title.as_str())
+                .collect()
+        };
+        assert_eq!(on("2026-05-05"), vec!["Processes"]);
+        assert_eq!(on("2026-05-06"), vec!["Scheduling"]);
+        assert_eq!(on("2026-05-07"), vec!["Catch up and review"]);
+        assert_eq!(on("2026-05-08"), vec!["Paging"]);
+        assert_eq!(on("2026-05-10"), vec!["Files"]);
+        assert_eq!(on("2026-05-11"), vec!["Final review"]);
+        assert_eq!(entries[0].details, "Reread the chapter\nSources: os.pdf");
+
+        // With one day left everything happens today
+        let entries = schedule_topics(&topics, date("2026-05-09"), date("2026-05-10"));
+        assert_eq!(entries.len(), 4);
+        assert!(entries.iter().all(|e| e.day == date("2026-05-09")));
+
+        let markdown = plan_markdown("OS", date("2026-05-10"), &entries);
+        assert!(markdown.contains("## Sat May 9 (1 day left)\n\n### Processes\n\n- Reread"));
+    }
+
+    #[test]
+    fn test_generation_options() {
+        let options = GenerationOptions {
+            length: Length::Long,
+            difficulty: Level::Intro,
+        };
+        let quiz = options.instructions("Quiz").unwrap();
+        assert!(quiz.starts_with("Length: Create 20 questions.\nLevel: The student is new"));
+        assert!(
+            GenerationOptions::default()
+                .instructions("Flashcards")
+                .unwrap()
+                .contains("10-15 flashcards")
+        );
+        assert_eq!(options.instructions("Summary"), None);
+    }
+
+    #[test]
+    fn test_transcript_parts() {
+        let text = "[0:00] one two three\n[1:00] four five six\n[2:00] seven eight nine";
+        let parts = transcript_parts(text, 12);
+
+        assert!(parts.len() > 1);
+        assert!(
+            parts
+                .iter()
+                .all(|p| !p.is_empty() && tokens::estimate(p) <= 12)
+        );
+        assert_eq!(parts.join(" ").split_whitespace().count(), 12);
+        assert_eq!(transcript_parts("short", 100), vec!["short"]);
+    }
+}
@@ -0,0 +1,648 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use rusqlite::Connection;
+use std::collections::{BTreeSet, HashMap};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::ingest::{self, ChunkConfig, chunk_sections};
+use crate::llm::schema::{FlashcardSet, cloze_answers};
+use crate::storage::links::KIND_DOCUMENT;
+use crate::storage::study::normalize_tags;
+use crate::storage::{ChunkStore, Database, DocumentStore, LinkStore, StudyStore};
+
+/// A note read from an Anki collection, with its first card's schedule
+#[derive(Debug, Clone)]
+struct AnkiNote {
+    fields: String,
+    /// Card type: 0 = new, 1 = learning, 2 = review, 3 = relearning
+    card_type: i64,
+    /// Review cards: days since collection creation
+    due: i64,
+    /// Interval in days (negative = seconds, for learning cards)
+    interval: i64,
+    /// Ease in permille (2500 = 2.5)
+    factor: i64,
+    reps: i64,
+}
+
+/// Import an Anki deck package (.apkg) into the current bucket's study items
+pub async fn anki(path: &Path, keep_schedule: bool) -> Result<()> {
+    let file =
+        std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut archive = zip::ZipArchive::new(file).context("Not a valid .apkg (zip) file")?;
+
+    // Newer exports put the real collection in anki21; anki2 is then a stub
+    let entry = ["collection.anki21", "collection.anki2"]
+        .into_iter()
+        .find(|name| archive.by_name(name).is_ok());
+    let Some(entry) = entry else {
+        if archive.by_name("collection.anki21b").is_ok() {
+            anyhow::bail!(
+                "This deck uses Anki's newest compressed format. Re-export it from Anki \
+                 with \"Support older Anki versions\" checked."
+            );
+        }
+        anyhow::bail!("No Anki collection found in {}", path.display());
+    };
+
+    let mut bytes = Vec::new();
+    archive.by_name(entry)?.read_to_end(&mut bytes)?;
+
+    // rusqlite needs a file to open
+    let temp_path = std::env::temp_dir().join(format!("librarian-anki-{}.db", std::process::id()));
+    std::fs::write(&temp_path, &bytes)?;
+    let result = Connection::open(&temp_path)
+        .context("Failed to open Anki collection")
+        .and_then(|conn| read_collection(&conn));
+    let _ = std::fs::remove_file(&temp_path);
+    let (created, notes) = result?;
+
+    let db = Database::open()?;
+    let store = StudyStore::new(&db);
+
+    let (mut imported, mut skipped) = (0, 0);
+    for note in &notes {
+        let Some((item_type, front, back)) = note_to_item(&note.fields) else {
+            skipped += 1;
+            continue;
+        };
+
+        if store.exists(&front, &back)? {
+            skipped += 1;
+            continue;
+        }
+
+        let id = store.insert(None, item_type, &front, &back)?;
+        if keep_schedule && let Some((next, interval, ease)) = schedule(note, created) {
+            store.set_schedule(id, next, interval, ease, note.reps)?;
+        }
+        imported += 1;
+    }
+
+    println!(
+        "{} Imported {} cards from {} ({} skipped as empty or already present)",
+        "✓".green(),
+        imported,
+        path.display().to_string().cyan(),
+        skipped
+    );
+    if !keep_schedule && imported > 0 {
+        println!(
+            "  {}",
+            "Cards start fresh; pass --keep-schedule to keep Anki's intervals.".dimmed()
+        );
+    }
+
+    Ok(())
+}
+
+/// A card read from a CSV/TSV or Markdown file
+#[derive(Debug, Clone, PartialEq)]
+struct CardRow {
+    item_type: &'static str,
+    front: String,
+    back: String,
+    tags: Option<String>,
+}
+
+/// Import question/answer cards from a CSV, TSV or Markdown (Q:/A:) file
+pub async fn cards(path: &Path, tag: Option<String>) -> Result<()> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let rows = match ext.as_str() {
+        "csv" => parse_card_table(&text, b',')?,
+        "tsv" | "tab" => parse_card_table(&text, b'\t')?,
+        "md" | "markdown" => parse_card_markdown(&text),
+        // Plain text: tab-separated if it looks like it, else Q:/A: blocks
+        _ if text.lines().any(|l| l.contains('\t')) => parse_card_table(&text, b'\t')?,
+        _ => parse_card_markdown(&text),
+    };
+    if rows.is_empty() {
+        anyhow::bail!(
+            "No cards found in {}. Expected question,answer rows (CSV/TSV) or Q:/A: lines (Markdown).",
+            path.display()
+        );
+    }
+
+    let db = Database::open()?;
+    let store = StudyStore::new(&db);
+
+    let (mut imported, mut skipped) = (0, 0);
+    for row in &rows {
+        if store.exists(&row.front, &row.back)? {
+            skipped += 1;
+            continue;
+        }
+
+        let id = store.insert(None, row.item_type, &row.front, &row.back)?;
+        let tags = [row.tags.as_deref(), tag.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(",");
+        if let Some(tags) = normalize_tags(&tags) {
+            store.set_tags(id, Some(&tags))?;
+        }
+        imported += 1;
+    }
+
+    println!(
+        "{} Imported {} cards from {} ({} already present)",
+        "✓".green(),
+        imported,
+        path.display().to_string().cyan(),
+        skipped
+    );
+    if imported > 0 {
+        println!(
+            "  {}",
+            "They're due now; start with `librarian review`.".dimmed()
+        );
+    }
+
+    Ok(())
+}
+
+/// Rows of `question,answer[,tags]`, skipping a header row and blank lines.
+/// A row whose only cell has {{blanks}} becomes a cloze card.
+fn parse_card_table(text: &str, delimiter: u8) -> Result<Vec<CardRow>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(text.as_bytes());
+
+    let mut rows = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let record = record.with_context(|| format!("Invalid row {}", i + 1))?;
+        let cell = |n: usize| record.get(n).map(str::trim).unwrap_or("");
+        let (front, back, tags) = (cell(0), cell(1), cell(2));
+
+        let header = ["question", "front", "q", "term", "prompt"];
+        if i == 0 && header.iter().any(|h| front.eq_ignore_ascii_case(h)) {
+            continue;
+        }
+        if let Some(row) = card_row(front, back, (!tags.is_empty()).then(|| tags.to_string())) {
+            rows.push(row);
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Q:/A: blocks, in the same shape flashcards are saved as
+fn parse_card_markdown(text: &str) -> Vec<CardRow> {
+    FlashcardSet::from_markdown(text)
+        .flashcards
+        .iter()
+        .filter_map(|card| card_row(&card.question, &card.answer, None))
+        .collect()
+}
+
+fn card_row(front: &str, back: &str, tags: Option<String>) -> Option<CardRow> {
+    let answers = cloze_answers(front);
+    let (item_type, back) = if back.is_empty() && !answers.is_empty() {
+        ("cloze", answers.join(", "))
+    } else {
+        ("flashcard", back.to_string())
+    };
+    if front.is_empty() || back.is_empty() {
+        return None;
+    }
+
+    Some(CardRow {
+        item_type,
+        front: front.to_string(),
+        back,
+        tags,
+    })
+}
+
+/// Read the collection creation time and every note with its first card
+fn read_collection(conn: &Connection) -> Result<(i64, Vec<AnkiNote>)> {
+    let created: i64 = conn
+        .query_row("SELECT crt FROM col", [], |row| row.get(0))
+        .context("Not an Anki collection")?;
+
+    let mut stmt = conn.prepare(
+        "SELECT n.flds, c.type, c.due, c.ivl, c.factor, c.reps
+         FROM notes n
+         JOIN cards c ON c.nid = n.id
+         WHERE c.ord = (SELECT MIN(ord) FROM cards WHERE nid = n.id)
+         ORDER BY n.id",
+    )?;
+
+    let notes = stmt
+        .query_map([], |row| {
+            Ok(AnkiNote {
+                fields: row.get(0)?,
+                card_type: row.get(1)?,
+                due: row.get(2)?,
+                interval: row.get(3)?,
+                factor: row.get(4)?,
+                reps: row.get(5)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok((created, notes))
+}
+
+/// Turn a note's fields into (item type, front, back). Cloze notes become
+/// "cloze" items with the deletions blanked out on the front.
+fn note_to_item(fields: &str) -> Option<(&'static str, String, String)> {
+    let fields: Vec<String> = fields.split('\x1f').map(strip_html).collect();
+    let first = fields.first().filter(|f| !f.is_empty())?;
+
+    if first.contains("{{c") {
+        let (front, back) = cloze_sides(first);
+        return Some(("cloze", front, back));
+    }
+
+    let back = fields.get(1).filter(|b| !b.is_empty())?;
+    Some(("flashcard", first.clone(), back.clone()))
+}
+
+/// Split `{{c1::answer::hint}}` text into a blanked front and a filled-in back
+fn cloze_sides(text: &str) -> (String, String) {
+    let mut front = String::new();
+    let mut back = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{c") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        let inner = &rest[start + 2..start + len];
+        front.push_str(&rest[..start]);
+        back.push_str(&rest[..start]);
+
+        // inner is "c1::answer" or "c1::answer::hint"
+        let mut parts = inner.splitn(3, "::").skip(1);
+        let answer = parts.next().unwrap_or("");
+        let hint = parts.next();
+        front.push_str(&format!("[{}]", hint.unwrap_or("...")));
+        back.push_str(answer);
+
+        rest = &rest[start + len + 2..];
+    }
+
+    front.push_str(rest);
+    back.push_str(rest);
+    (front, back)
+}
+
+/// Convert a field's HTML to plain text
+fn strip_html(field: &str) -> String {
+    let field = field
+        .replace("<br>", "\n")
+        .replace("<br/>", "\n")
+        .replace("<br />", "\n")
+        .replace("</div>", "\n");
+    let fragment = scraper::Html::parse_fragment(&field);
+    let text: String = fragment.root_element().text().collect();
+
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Map a review card's Anki schedule to (next review, interval days, ease).
+/// New and learning cards keep the default "due now" schedule.
+fn schedule(note: &AnkiNote, collection_created: i64) -> Option<(DateTime<Utc>, f64, f64)> {
+    if note.card_type != 2 || note.interval <= 0 {
+        return None;
+    }
+
+    let next = DateTime::from_timestamp(collection_created + note.due * 86400, 0)?;
+    let ease = if note.factor > 0 {
+        note.factor as f64 / 1000.0
+    } else {
+        2.5
+    };
+
+    Some((next, note.interval as f64, ease.max(1.3)))
+}
+
+/// A note read from an Obsidian vault
+struct VaultNote {
+    path: PathBuf,
+    name: String,
+    content: String,
+    /// Names of the notes it links to
+    links: Vec<String>,
+}
+
+/// Import every note in an Obsidian vault. `[[wiki links]]` between notes are
+/// recorded as document links, and each note is tagged with the notes that
+/// link to it (`backlink:<name>`). Re-running refreshes links and tags.
+pub async fn obsidian(vault: &Path) -> Result<()> {
+    if !vault.is_dir() {
+        anyhow::bail!("Not a folder: {}", vault.display());
+    }
+
+    let mut paths = Vec::new();
+    collect_markdown(vault, &mut paths)?;
+    if paths.is_empty() {
+        println!(
+            "{} No Markdown notes found in {}",
+            "⚠".yellow(),
+            vault.display()
+        );
+        return Ok(());
+    }
+
+    let mut notes = Vec::new();
+    for path in paths {
+        let raw = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        notes.push(VaultNote {
+            path: std::fs::canonicalize(&path)?,
+            name,
+            links: wiki_links(&raw),
+            content: resolve_wiki_links(strip_frontmatter(&raw)),
+        });
+    }
+
+    // Obsidian resolves links by note name, case-insensitively
+    let by_name: HashMap<String, usize> = notes
+        .iter()
+        .enumerate()
+        .map(|(i, note)| (note.name.to_lowercase(), i))
+        .collect();
+
+    let mut backlinks: Vec<BTreeSet<&str>> = vec![BTreeSet::new(); notes.len()];
+    for note in &notes {
+        for target in &note.links {
+            if let Some(&i) = by_name.get(&target.to_lowercase())
+                && notes[i].name != note.name
+            {
+                backlinks[i].insert(&note.name);
+            }
+        }
+    }
+
+    let db = Database::open()?;
+    let doc_store = DocumentStore::new(&db);
+    let chunk_store = ChunkStore::new(&db);
+    let link_store = LinkStore::new(&db);
+    chunk_store.init_schema()?;
+
+    println!("Found {} notes\n", notes.len());
+    let pb = indicatif::ProgressBar::new(notes.len() as u64);
+
+    let (mut added, mut existing, mut total_chunks) = (0, 0, 0);
+    let mut doc_ids = Vec::with_capacity(notes.len());
+
+    for (note, linked_from) in notes.iter().zip(&backlinks) {
+        pb.set_message(note.name.clone());
+
+        let tags = linked_from
+            .iter()
+            .map(|name| format!("backlink:{}", name.replace(',', " ")))
+            .collect::<Vec<_>>()
+            .join(",");
+        let tags = (!tags.is_empty()).then_some(tags);
+        let source_path = note.path.to_string_lossy().to_string();
+
+        if let Some(doc) = doc_store.find_by_path(&source_path)? {
+            doc_store.set_tags(doc.id, tags.as_deref())?;
+            doc_ids.push(doc.id);
+            existing += 1;
+            pb.inc(1);
+            continue;
+        }
+
+        let doc_id = doc_store.insert(
+            &source_path,
+            &note.name,
+            "markdown",
+            &note.content,
+            tags.as_deref(),
+        )?;
+        if let Ok(hash) = ingest::file_hash(&note.path) {
+            doc_store.set_content_hash(doc_id, &hash)?;
+        }
+
+        let sections = ingest::text::markdown_sections(&note.content);
+        let chunks = chunk_sections(&note.content, &sections, &ChunkConfig::current());
+        for chunk in &chunks {
+            let embedding = chunk_store.embed_text(&chunk.text).ok();
+            chunk_store.insert_chunk(doc_id, chunk, embedding.as_deref())?;
+        }
+
+        doc_ids.push(doc_id);
+        added += 1;
+        total_chunks += chunks.len();
+        pb.inc(1);
+    }
+
+    pb.finish_and_clear();
+
+    let mut link_count = 0;
+    for (note, &from) in notes.iter().zip(&doc_ids) {
+        for target in &note.links {
+            if let Some(&i) = by_name.get(&target.to_lowercase())
+                && doc_ids[i] != from
+            {
+                link_store.add(KIND_DOCUMENT, from, KIND_DOCUMENT, doc_ids[i])?;
+                link_count += 1;
+            }
+        }
+    }
+
+    println!(
+        "{} Imported {} notes ({} chunks), {} already imported, {} wiki links resolved",
+        "✓".green(),
+        added,
+        total_chunks,
+        existing,
+        link_count
+    );
+
+    Ok(())
+}
+
+/// Recursively collect .md files, skipping hidden folders like .obsidian and .trash
+fn collect_markdown(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)?.filter_map(|e| e.ok()).collect();
+    entries.sort_by_key(|e| e.path());
+
+    for entry in entries {
+        let path = entry.path();
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+
+        if path.is_dir() {
+            if !hidden {
+                collect_markdown(&path, paths)?;
+            }
+        } else if !hidden
+            && path
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("md"))
+        {
+            paths.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Drop a leading YAML frontmatter block
+fn strip_frontmatter(text: &str) -> &str {
+    if let Some(rest) = text.strip_prefix("---\n")
+        && let Some(end) = rest.find("\n---")
+    {
+        return rest[end + 4..].trim_start_matches(['-', '\n']);
+    }
+    text
+}
+
+/// Target note names of every `[[link]]` / `![[embed]]`, in order, without
+/// headings, block references or aliases
+fn wiki_links(text: &str) -> Vec<String> {
+    let mut links = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("[[") {
+        let Some(len) = rest[start + 2..].find("]]") else {
+            break;
+        };
+        let inner = &rest[start + 2..start + 2 + len];
+        let target = inner.split(['|', '#', '^']).next().unwrap_or("").trim();
+        // Links may include a folder path; Obsidian matches on the note name
+        let target = target.rsplit('/').next().unwrap_or(target);
+        let target = target.strip_suffix(".md").unwrap_or(target);
+
+        if !target.is_empty() && !links.iter().any(|l: &String| l == target) {
+            links.push(target.to_string());
+        }
+        rest = &rest[start + 2 + len + 2..];
+    }
+
+    links
+}
+
+/// Replace `[[Note|Alias]]` with its display text so notes read naturally
+fn resolve_wiki_links(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("[[") {
+        let Some(len) = rest[start + 2..].find("]]") else {
+            break;
+        };
+        let inner = &rest[start + 2..start + 2 + len];
+        let display = match inner.split_once('|') {
+            Some((_, alias)) => alias,
+            None => inner.split(['#', '^']).next().unwrap_or(inner),
+        };
+
+        out.push_str(rest[..start].strip_suffix('!').unwrap_or(&rest[..start]));
+        out.push_str(display.trim());
+        rest = &rest[start + 2 + len + 2..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_anki_collection() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE col (crt INTEGER);
+             CREATE TABLE notes (id INTEGER, flds TEXT);
+             CREATE TABLE cards (nid INTEGER, ord INTEGER, type INTEGER, due INTEGER,
+                                 ivl INTEGER, factor INTEGER, reps INTEGER);
+             INSERT INTO col VALUES (1700000000);
+             INSERT INTO notes VALUES (1, 'What is <b>ATP</b>?\x1fEnergy<br>currency');
+             INSERT INTO notes VALUES (2, 'The {{c1::mitochondria::organelle}} makes {{c2::ATP}}\x1f');
+             INSERT INTO cards VALUES (1, 0, 2, 10, 12, 2300, 5);
+             INSERT INTO cards VALUES (1, 1, 0, 0, 0, 0, 0);
+             INSERT INTO cards VALUES (2, 0, 0, 3, 0, 0, 0);",
+        )
+        .unwrap();
+
+        let (created, notes) = read_collection(&conn).unwrap();
+        assert_eq!(notes.len(), 2);
+
+        let (kind, front, back) = note_to_item(&notes[0].fields).unwrap();
+        assert_eq!(
+            (kind, front.as_str(), back.as_str()),
+            ("flashcard", "What is ATP?", "Energy\ncurrency")
+        );
+        let (next, interval, ease) = schedule(&notes[0], created).unwrap();
+        assert_eq!(next.timestamp(), 1700000000 + 10 * 86400);
+        assert_eq!((interval, ease), (12.0, 2.3));
+
+        let (kind, front, back) = note_to_item(&notes[1].fields).unwrap();
+        assert_eq!(kind, "cloze");
+        assert_eq!(front, "The [organelle] makes [...]");
+        assert_eq!(back, "The mitochondria makes ATP");
+        assert!(schedule(&notes[1], created).is_none());
+    }
+
+    #[test]
+    fn test_parse_card_files() {
+        let csv = "Question,Answer,Tags\n\
+                   What is a TLB?,\"A cache of page-table entries, per core\",paging\n\
+                   The {{scheduler}} picks the next process,,\n\
+                   No answer here,,\n";
+        let rows = parse_card_table(csv, b',').unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].front, "What is a TLB?");
+        assert_eq!(rows[0].back, "A cache of page-table entries, per core");
+        assert_eq!(rows[0].tags.as_deref(), Some("paging"));
+        assert_eq!(
+            (rows[1].item_type, rows[1].back.as_str()),
+            ("cloze", "scheduler")
+        );
+
+        let tsv = parse_card_table("ATP\tEnergy currency\n", b'\t').unwrap();
+        assert_eq!(
+            (tsv[0].item_type, tsv[0].back.as_str()),
+            ("flashcard", "Energy currency")
+        );
+
+        let md =
+            parse_card_markdown("**Q:** What is ATP?\n**A:** Energy currency\n---\nQ: Half a card");
+        assert_eq!(md.len(), 1);
+        assert_eq!(md[0].front, "What is ATP?");
+    }
+
+    #[test]
+    fn test_wiki_links() {
+        let text = "---\ntags: [bio]\n---\nSee [[Cell Biology#Mitosis|mitosis]] and \
+                    ![[diagrams/Krebs Cycle.png]] plus [[ATP]] and [[atp]].";
+
+        let body = strip_frontmatter(text);
+        assert!(body.starts_with("See"));
+        assert_eq!(
+            wiki_links(body),
+            vec!["Cell Biology", "Krebs Cycle.png", "ATP", "atp"]
+        );
+        assert_eq!(
+            resolve_wiki_links(body),
+            "See mitosis and diagrams/Krebs Cycle.png plus ATP and atp."
+        );
+    }
+}
@@ -1,8 +1,20 @@
 pub mod add;
 pub mod bucket;
+pub mod cards;
 pub mod chat;
+pub mod cite;
 pub mod config;
+pub mod conversations;
 pub mod docs;
+pub mod export;
+pub mod feed;
 pub mod generate;
+pub mod import;
+pub mod note;
+pub mod queue;
 pub mod quiz;
+pub mod reindex;
 pub mod review;
+pub mod stats;
+pub mod sync;
+pub mod watch;
@@ -0,0 +1,136 @@
+use anyhow::Result;
+use colored::Colorize;
+use inquire::Text;
+
+use crate::ingest::{ChunkConfig, chunk_text};
+use crate::storage::{ChunkStore, Database, DocumentStore, NoteStore};
+
+/// Add a quick note to the current bucket
+pub async fn run(text: Option<String>, doc: Option<i64>) -> Result<()> {
+    let text = match text {
+        Some(t) => t,
+        None => Text::new("Note:")
+            .with_help_message("Your own insight - it will show up in future chats")
+            .prompt()?,
+    };
+
+    if text.trim().is_empty() {
+        println!("{}", "Cancelled.".dimmed());
+        return Ok(());
+    }
+
+    let db = Database::open()?;
+
+    if let Some(doc_id) = doc
+        && DocumentStore::new(&db).get(doc_id)?.is_none()
+    {
+        println!("{} Document not found: {}", "✗".red(), doc_id);
+        return Ok(());
+    }
+
+    let note_doc_id = save_note(&db, &text, doc, None)?;
+
+    println!("{} Saved note (id: {})", "✓".green(), note_doc_id);
+
+    Ok(())
+}
+
+/// Store a note as a searchable mini-document and link it to its source.
+/// Returns the note's document ID.
+pub fn save_note(
+    db: &Database,
+    text: &str,
+    attached_document_id: Option<i64>,
+    conversation_id: Option<i64>,
+) -> Result<i64> {
+    let doc_store = DocumentStore::new(db);
+    let chunk_store = ChunkStore::new(db);
+    let note_store = NoteStore::new(db);
+
+    chunk_store.init_schema()?;
+
+    let text = text.trim();
+    let now = chrono::Utc::now();
+    let source_path = format!("note://{}", now.timestamp_nanos_opt().unwrap_or_default());
+    let filename = note_title(text);
+
+    let doc_id = doc_store.insert(&source_path, &filename, "note", text, Some("note"))?;
+
+    let chunks = chunk_text(text, &ChunkConfig::current());
+    for chunk in &chunks {
+        let embedding = chunk_store.embed_text(&chunk.text).ok();
+        chunk_store.insert(
+            doc_id,
+            chunk.index as i64,
+            &chunk.text,
+            embedding.as_deref(),
+        )?;
+    }
+
+    note_store.insert(doc_id, attached_document_id, conversation_id)?;
+
+    Ok(doc_id)
+}
+
+/// Build a short display title from the start of the note
+fn note_title(text: &str) -> String {
+    let first_line = text.lines().next().unwrap_or("");
+    let title: String = first_line.chars().take(50).collect();
+    if first_line.chars().count() > 50 {
+        format!("Note: {}...", title.trim_end())
+    } else {
+        format!("Note: {}", title)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embeddings;
+    use crate::storage::links::{KIND_CHUNK, KIND_STUDY_ITEM};
+    use crate::storage::{LinkStore, StudyStore};
+
+    #[test]
+    fn test_note_and_study_item_round_trip() {
+        embeddings::use_mock_backend();
+        let db = Database::open_in_memory().unwrap();
+        let docs = DocumentStore::new(&db);
+        let chunks = ChunkStore::new(&db);
+        chunks.init_schema().unwrap();
+
+        let doc_id = docs
+            .insert("/bio.md", "bio.md", "markdown", "Mitochondria", None)
+            .unwrap();
+        let chunk_id = chunks
+            .insert(doc_id, 0, "Mitochondria produce ATP for the cell", None)
+            .unwrap();
+
+        let note_id = save_note(&db, "ATP is the energy currency", Some(doc_id), None).unwrap();
+        let notes = NoteStore::new(&db).for_document(doc_id).unwrap();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].document_id, note_id);
+        assert!(
+            chunks.get_for_document(note_id).unwrap()[0]
+                .embedding
+                .is_some()
+        );
+
+        let items = vec![(
+            "flashcard".to_string(),
+            "What produces ATP?".to_string(),
+            "Mitochondria".to_string(),
+        )];
+        let saved =
+            crate::commands::generate::save_linked_study_items(&db, &items, &[chunk_id], "")
+                .unwrap();
+        assert_eq!(saved.len(), 1);
+
+        let item = &StudyStore::new(&db).list().unwrap()[0];
+        assert_eq!(item.document_id, Some(doc_id));
+        let links = LinkStore::new(&db)
+            .created_from(KIND_STUDY_ITEM, item.id)
+            .unwrap();
+        assert_eq!(links[0].to_kind, KIND_CHUNK);
+        assert_eq!(links[0].to_id, chunk_id);
+    }
+}
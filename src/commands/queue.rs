@@ -0,0 +1,142 @@
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::storage::reading::ReadingEntry;
+use crate::storage::{Database, DocumentStore, ReadingStatus, ReadingStore};
+
+/// Show the reading queue
+pub async fn list() -> Result<()> {
+    let db = Database::open()?;
+    let doc_store = DocumentStore::new(&db);
+    let reading_store = ReadingStore::new(&db);
+
+    let queue = reading_store.queue()?;
+    let counts = reading_store.counts()?;
+
+    if queue.is_empty() {
+        println!("{} Nothing left to read!", "✓".green());
+        return Ok(());
+    }
+
+    println!(
+        "\n{} ({} in progress, {} unread, {} done)\n",
+        "Reading Queue".bold(),
+        counts.in_progress.to_string().yellow(),
+        counts.unread.to_string().cyan(),
+        counts.done.to_string().green()
+    );
+
+    for entry in &queue {
+        if let Some(doc) = doc_store.get(entry.document_id)? {
+            println!(
+                "  {} {} {}",
+                format!("[{}]", doc.id).dimmed(),
+                doc.filename.bold(),
+                format_progress(entry)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Mark a document's reading status and/or progress
+pub async fn mark(
+    id: i64,
+    status: Option<ReadingStatus>,
+    progress: Option<u8>,
+    position: Option<String>,
+) -> Result<()> {
+    let db = Database::open()?;
+    let doc_store = DocumentStore::new(&db);
+    let reading_store = ReadingStore::new(&db);
+
+    let Some(doc) = doc_store.get(id)? else {
+        println!("{} Document not found: {}", "✗".red(), id);
+        return Ok(());
+    };
+
+    if progress.is_some() || position.is_some() {
+        reading_store.set_progress(id, progress, position.as_deref())?;
+    }
+    if let Some(status) = status {
+        reading_store.set_status(id, status)?;
+    }
+
+    let entry = reading_store.get(id)?;
+    println!(
+        "{} {} {}",
+        "✓".green(),
+        doc.filename.bold(),
+        entry.as_ref().map(format_progress).unwrap_or_default()
+    );
+
+    Ok(())
+}
+
+/// Open the next document in the reading queue
+pub async fn next() -> Result<()> {
+    let db = Database::open()?;
+    let doc_store = DocumentStore::new(&db);
+    let reading_store = ReadingStore::new(&db);
+
+    let Some(entry) = reading_store.queue()?.into_iter().next() else {
+        println!("{} Nothing left to read!", "✓".green());
+        return Ok(());
+    };
+
+    let Some(doc) = doc_store.get(entry.document_id)? else {
+        return Ok(());
+    };
+
+    if entry.status == ReadingStatus::Unread {
+        reading_store.set_status(doc.id, ReadingStatus::InProgress)?;
+    }
+
+    println!("\n{}", "─".repeat(50).dimmed());
+    println!("{} {}", "Next up:".bold(), doc.filename.cyan().bold());
+    println!("{} {}", "ID:".bold(), doc.id);
+    println!("{} {}", "Type:".bold(), doc.content_type);
+    println!("{} {}", "Source:".bold(), doc.source_path.dimmed());
+    if let Some(position) = &entry.position {
+        println!("{} {}", "Resume at:".bold(), position.yellow());
+    }
+    if let Some(progress) = entry.progress {
+        println!("{} {}%", "Progress:".bold(), progress);
+    }
+    println!("{}", "─".repeat(50).dimmed());
+
+    let preview: String = doc.content.chars().take(500).collect();
+    println!("\n{}", preview);
+    if doc.content.chars().count() > 500 {
+        println!("{}", "...".dimmed());
+    }
+
+    println!(
+        "\nTrack progress with {}",
+        format!(
+            "librarian queue mark {} --progress 50 --position \"p. 12\"",
+            doc.id
+        )
+        .cyan()
+    );
+
+    Ok(())
+}
+
+fn format_progress(entry: &ReadingEntry) -> String {
+    let mut parts = vec![entry.status.as_str().to_string()];
+    if let Some(progress) = entry.progress {
+        parts.push(format!("{}%", progress));
+    }
+    if let Some(position) = &entry.position {
+        parts.push(format!("at {}", position));
+    }
+
+    let text = format!("({})", parts.join(", "));
+    match entry.status {
+        ReadingStatus::Unread => text.dimmed().to_string(),
+        ReadingStatus::InProgress => text.yellow().to_string(),
+        ReadingStatus::Done => text.green().to_string(),
+    }
+}
@@ -1,29 +1,18 @@
 use anyhow::Result;
 use colored::Colorize;
-use inquire::Select;
+use inquire::{Select, Text};
 
+use crate::commands::review;
 use crate::config::Config;
-use crate::llm::{GroqClient, groq::Message};
-use crate::storage::{Database, StudyStore};
-
-/// Question types parsed from quiz output
-enum QuizQuestion {
-    MultipleChoice {
-        question: String,
-        options: Vec<(char, String)>,
-        correct: char,
-    },
-    FillInBlank {
-        question: String,
-        answer: String,
-    },
-    ShortAnswer {
-        question: String,
-        expected: String,
-    },
-}
-
-pub async fn run() -> Result<()> {
+use crate::llm::ChatProvider;
+use crate::llm::Message;
+use crate::llm::schema::{Quiz, QuizQuestion, hide_cloze, option_letter};
+use crate::storage::activity;
+use crate::storage::study::normalize_tags;
+use crate::storage::{Database, StudyItem, StudyStore};
+
+/// Start a quiz; `tag` limits due-item review to items with that tag
+pub async fn run(tag: Option<String>) -> Result<()> {
     println!();
     println!(
         "    {}",
@@ -50,7 +39,7 @@ pub async fn run() -> Result<()> {
     let db = Database::open()?;
     let store = StudyStore::new(&db);
 
-    let due_count = store.count_due()?;
+    let due_count = store.count_due(tag.as_deref())?;
 
     let mode_options = if due_count > 0 {
         vec![
@@ -72,15 +61,184 @@ pub async fn run() -> Result<()> {
     }
 
     if selection.contains("Review due") {
-        return run_due_quiz(&store).await;
+        return run_due_quiz(&db, tag.as_deref()).await;
     }
 
     // Generate fresh quiz
-    run_fresh_quiz(&store).await
+    run_fresh_quiz(&db).await
+}
+
+/// Study item types saved from quizzes
+const QUIZ_TYPES: [&str; 3] = ["quiz_mc", "quiz_fill", "quiz_short"];
+
+/// Browse saved quiz questions to view, correct, tag or delete them,
+/// optionally only those carrying `tag`
+pub async fn bank(tag: Option<String>) -> Result<()> {
+    let db = Database::open()?;
+    let store = StudyStore::new(&db);
+
+    loop {
+        let items: Vec<StudyItem> = store
+            .list()?
+            .into_iter()
+            .filter(|item| QUIZ_TYPES.contains(&item.item_type.as_str()))
+            .filter(|item| tag.as_deref().is_none_or(|tag| has_tag(item, tag)))
+            .collect();
+
+        if items.is_empty() {
+            match &tag {
+                Some(tag) => println!(
+                    "{}",
+                    format!("No quiz questions tagged '{}'.", tag).dimmed()
+                ),
+                None => {
+                    println!("{}", "No saved quiz questions yet.".dimmed());
+                    println!("Take one with {}", "librarian quiz".cyan());
+                }
+            }
+            return Ok(());
+        }
+
+        let mut labels: Vec<String> = items.iter().map(bank_label).collect();
+        labels.push("←   Back".to_string());
+
+        let choice = Select::new(
+            &format!("Question bank ({} questions):", items.len()),
+            labels,
+        )
+        .with_page_size(15)
+        .raw_prompt();
+        let item = match choice {
+            Ok(choice) if choice.index < items.len() => &items[choice.index],
+            Ok(_)
+            | Err(inquire::InquireError::OperationCanceled)
+            | Err(inquire::InquireError::OperationInterrupted) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+
+        manage_item(&store, item.id)?;
+    }
+}
+
+/// One list line: "#12  short  What is a TLB?  [paging, os]"
+fn bank_label(item: &StudyItem) -> String {
+    let kind = item.item_type.trim_start_matches("quiz_");
+    let mut question: String = item.front.lines().next().unwrap_or("").to_string();
+    if question.chars().count() > 60 {
+        question = question.chars().take(57).collect::<String>() + "...";
+    }
+    match item.tags.as_deref().filter(|t| !t.is_empty()) {
+        Some(tags) => format!(
+            "#{:<4} {:<5}  {}  [{}]",
+            item.id,
+            kind,
+            question,
+            tags.replace(',', ", ")
+        ),
+        None => format!("#{:<4} {:<5}  {}", item.id, kind, question),
+    }
+}
+
+fn has_tag(item: &StudyItem, tag: &str) -> bool {
+    item.tags
+        .as_deref()
+        .unwrap_or("")
+        .split(',')
+        .any(|t| t.trim().eq_ignore_ascii_case(tag))
+}
+
+/// Show one question with its actions until the user goes back or deletes it
+fn manage_item(store: &StudyStore<'_>, id: i64) -> Result<()> {
+    loop {
+        let Some(item) = store.get(id)? else {
+            return Ok(());
+        };
+
+        println!();
+        println!(
+            "{} {}",
+            format!("[{}]", item.id).dimmed(),
+            item.item_type.trim_start_matches("quiz_").cyan()
+        );
+        println!("  {} {}", "Q:".bold(), item.front);
+        println!("  {} {}", "A:".bold(), item.back);
+        if let Some(tags) = item.tags.as_deref() {
+            println!("  {} {}", "Tags:".dimmed(), tags.replace(',', ", "));
+        }
+        println!(
+            "  {}",
+            format!(
+                "Reviewed {} times · next review {}",
+                item.review_count,
+                item.next_review_date.format("%Y-%m-%d")
+            )
+            .dimmed()
+        );
+        println!();
+
+        let action = Select::new(
+            "What would you like to do?",
+            vec![
+                "✏️   Edit question",
+                "✏️   Edit answer",
+                "🏷️   Tags",
+                "🗑️   Delete",
+                "←   Back",
+            ],
+        )
+        .prompt();
+        let action = match action {
+            Ok(action) => action,
+            Err(inquire::InquireError::OperationCanceled) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+
+        match action {
+            a if a.contains("Edit question") => {
+                let front = Text::new("Question:")
+                    .with_initial_value(&item.front)
+                    .prompt()?;
+                let front = front.trim();
+                if !front.is_empty() && front != item.front {
+                    store.update_text(item.id, front, &item.back)?;
+                    println!("{} Question updated", "✓".green());
+                }
+            }
+            a if a.contains("Edit answer") => {
+                let back = Text::new("Answer:")
+                    .with_initial_value(&item.back)
+                    .prompt()?;
+                let back = back.trim();
+                if !back.is_empty() && back != item.back {
+                    store.update_text(item.id, &item.front, back)?;
+                    println!("{} Answer updated", "✓".green());
+                }
+            }
+            a if a.contains("Tags") => {
+                let current = item.tags.as_deref().unwrap_or("").replace(',', ", ");
+                let tags = Text::new("Tags:")
+                    .with_initial_value(&current)
+                    .with_help_message("Comma-separated; leave empty to clear")
+                    .prompt()?;
+                store.set_tags(item.id, normalize_tags(&tags).as_deref())?;
+                println!("{} Tags updated", "✓".green());
+            }
+            a if a.contains("Delete") => {
+                let confirm = Select::new("Delete this question?", vec!["No", "Yes"]).prompt()?;
+                if confirm == "Yes" {
+                    store.delete(item.id)?;
+                    println!("{} Deleted question {}", "✓".green(), item.id);
+                    return Ok(());
+                }
+            }
+            _ => return Ok(()),
+        }
+    }
 }
 
-async fn run_due_quiz(store: &StudyStore<'_>) -> Result<()> {
-    let items = store.get_due(20)?;
+async fn run_due_quiz(db: &Database, tag: Option<&str>) -> Result<()> {
+    let store = StudyStore::new(db);
+    let items = store.get_due(20, tag)?;
 
     if items.is_empty() {
         println!("{}", "No items due for review!".dimmed());
@@ -96,7 +254,11 @@ async fn run_due_quiz(store: &StudyStore<'_>) -> Result<()> {
 
     for (i, item) in items.iter().enumerate() {
         println!("\n{} [{}/{}]", "Question".bold().cyan(), i + 1, total);
-        println!("  {}", item.front);
+        if item.item_type == "cloze" {
+            println!("  {}", hide_cloze(&item.front, str::to_string));
+        } else {
+            println!("  {}", item.front);
+        }
         println!();
 
         let answer = inquire::Text::new("  Your answer:")
@@ -121,6 +283,16 @@ async fn run_due_quiz(store: &StudyStore<'_>) -> Result<()> {
             println!("  {} Incorrect", "✗".red().bold());
             println!("  {} {}", "Expected:".dimmed(), item.back);
             store.update_after_review(item.id, 1)?;
+
+            // Let the user read up on what they missed
+            if let Some((doc, chunk)) = review::find_source(db, item)? {
+                let next = Select::new("", vec!["→ Next question", review::SHOW_SOURCE])
+                    .prompt()
+                    .unwrap_or("→ Next question");
+                if next == review::SHOW_SOURCE {
+                    review::print_passage(&doc, &chunk);
+                }
+            }
         }
 
         if item.item_type == "quiz_mc" {
@@ -138,6 +310,7 @@ async fn run_due_quiz(store: &StudyStore<'_>) -> Result<()> {
         println!("{}", "─".repeat(50).dimmed());
     }
 
+    activity::record(activity::QUIZ, 1);
     print_quiz_summary(
         correct,
         total,
@@ -146,13 +319,21 @@ async fn run_due_quiz(store: &StudyStore<'_>) -> Result<()> {
         other_correct,
         other_total,
     );
+    // Misses were rescheduled as failed recalls above
+    if correct < total {
+        println!(
+            "{} {} missed items come back for review tomorrow",
+            "↻".yellow(),
+            total - correct
+        );
+    }
     Ok(())
 }
 
-async fn run_fresh_quiz(store: &StudyStore<'_>) -> Result<()> {
+async fn run_fresh_quiz(db: &Database) -> Result<()> {
     let config = Config::load()?;
-    let api_key = match config.get_api_key() {
-        Some(key) => key,
+    let client = match crate::llm::client(&config) {
+        Some(client) => client,
         None => {
             println!(
                 "{} No API key configured. Run {} to set up.",
@@ -163,14 +344,28 @@ async fn run_fresh_quiz(store: &StudyStore<'_>) -> Result<()> {
         }
     };
 
-    let client = GroqClient::new(api_key, config.default_model);
-
     let topic = inquire::Text::new("Topic (or Enter for all materials):")
         .prompt()
         .unwrap_or_default();
 
+    let request = if topic.is_empty() {
+        "Create an interactive quiz from these materials:\n\n\n\nCover the most important topics."
+            .to_string()
+    } else {
+        format!(
+            "Create an interactive quiz from these materials:\n\n\n\nFocus on: {}",
+            topic
+        )
+    };
+
     // Get context
-    let context = crate::commands::generate::get_document_context_pub(&topic)?;
+    let (context, source_chunks) = crate::commands::generate::get_document_context_pub(
+        &topic,
+        &crate::commands::generate::prompt_messages(
+            crate::commands::generate::prompts::QUIZ,
+            &request,
+        ),
+    )?;
 
     if context.is_empty() {
         println!(
@@ -185,7 +380,7 @@ async fn run_fresh_quiz(store: &StudyStore<'_>) -> Result<()> {
     let messages = vec![
         Message {
             role: "system".to_string(),
-            content: QUIZ_PROMPT.to_string(),
+            content: crate::commands::generate::prompts::QUIZ.to_string(),
         },
         Message {
             role: "user".to_string(),
@@ -203,14 +398,16 @@ async fn run_fresh_quiz(store: &StudyStore<'_>) -> Result<()> {
         },
     ];
 
-    let response = client.chat(&messages).await?;
-
-    // Parse questions from response
-    let questions = parse_quiz_questions(&response);
+    let questions = match client.chat_as::<Quiz>(&messages).await {
+        Ok(quiz) => quiz.questions,
+        Err(e) => {
+            println!("{} Could not generate a quiz: {:#}", "Error:".red(), e);
+            return Ok(());
+        }
+    };
 
     if questions.is_empty() {
-        println!("Could not parse quiz questions. Displaying raw quiz:\n");
-        println!("{}", response);
+        println!("{}", "The model returned no questions.".dimmed());
         return Ok(());
     }
 
@@ -222,42 +419,55 @@ async fn run_fresh_quiz(store: &StudyStore<'_>) -> Result<()> {
     let mut other_correct = 0;
     let mut other_total = 0;
 
-    // Items to save for spaced repetition
-    let mut items_to_save: Vec<(Option<i64>, &str, &str, &str)> = Vec::new();
+    // Questions answered wrong or skipped, by index
+    let mut missed = Vec::new();
 
     for (i, q) in questions.iter().enumerate() {
         println!("\n{} [{}/{}]", "Question".bold().cyan(), i + 1, total);
 
         match q {
             QuizQuestion::MultipleChoice {
-                question,
-                options,
-                correct: correct_answer,
+                question, options, ..
             } => {
                 mc_total += 1;
+                let correct_answer = q.correct_letter().unwrap_or('a');
                 println!("  {}", question);
-                for (letter, text) in options {
-                    println!("    {}) {}", letter, text);
-                }
                 println!();
 
-                let answer = inquire::Text::new("  Your answer (a/b/c/d):")
-                    .prompt()
-                    .unwrap_or_default();
-
-                let user_char = answer.trim().to_lowercase().chars().next().unwrap_or(' ');
-                let is_correct = user_char == *correct_answer;
-
-                if is_correct {
-                    println!("  {} Correct!", "✓".green().bold());
-                    correct += 1;
-                    mc_correct += 1;
-                } else {
-                    println!(
-                        "  {} Incorrect. Answer: {})",
-                        "✗".red().bold(),
-                        correct_answer
-                    );
+                let labels: Vec<String> = options
+                    .iter()
+                    .enumerate()
+                    .map(|(j, text)| format!("{}) {}", option_letter(j), text))
+                    .collect();
+                let choice = Select::new("  Your answer:", labels)
+                    .with_help_message("↑↓ to move, Enter to answer, Esc to skip")
+                    .raw_prompt();
+                let chosen = match choice {
+                    Ok(option) => Some(option_letter(option.index)),
+                    Err(inquire::InquireError::OperationCanceled) => None,
+                    Err(inquire::InquireError::OperationInterrupted) => {
+                        println!("\n{}", "Quiz ended early.".dimmed());
+                        mc_total -= 1;
+                        break;
+                    }
+                    Err(e) => return Err(e.into()),
+                };
+
+                let answer = format!("{}) {}", correct_answer, q.answer_text());
+                match chosen {
+                    Some(letter) if letter == correct_answer => {
+                        println!("  {} Correct!", "✓".green().bold());
+                        correct += 1;
+                        mc_correct += 1;
+                    }
+                    Some(_) => {
+                        println!("  {} Incorrect. Answer: {}", "✗".red().bold(), answer);
+                        missed.push(i);
+                    }
+                    None => {
+                        println!("  {} Skipped. Answer: {}", "→".yellow().bold(), answer);
+                        missed.push(i);
+                    }
                 }
             }
             QuizQuestion::FillInBlank { question, answer } => {
@@ -280,9 +490,13 @@ async fn run_fresh_quiz(store: &StudyStore<'_>) -> Result<()> {
                     other_correct += 1;
                 } else {
                     println!("  {} Incorrect. Answer: {}", "✗".red().bold(), answer);
+                    missed.push(i);
                 }
             }
-            QuizQuestion::ShortAnswer { question, expected } => {
+            QuizQuestion::ShortAnswer {
+                question,
+                answer: expected,
+            } => {
                 other_total += 1;
                 println!("  {}", question);
                 println!();
@@ -309,6 +523,7 @@ async fn run_fresh_quiz(store: &StudyStore<'_>) -> Result<()> {
                     other_correct += 1;
                 } else {
                     println!("  {} Expected: {}", "✗".red().bold(), expected);
+                    missed.push(i);
                 }
             }
         }
@@ -316,225 +531,114 @@ async fn run_fresh_quiz(store: &StudyStore<'_>) -> Result<()> {
         println!("{}", "─".repeat(50).dimmed());
     }
 
+    activity::record(activity::QUIZ, 1);
     print_quiz_summary(
         correct,
-        total,
+        mc_total + other_total,
         mc_correct,
         mc_total,
         other_correct,
         other_total,
     );
 
-    // Offer to save for spaced repetition
-    let save_opts = vec![
-        "💾  Save to spaced repetition │ Review these later",
-        "❌  Don't save",
-    ];
-    let save = Select::new("Save quiz items for spaced repetition?", save_opts).prompt();
+    save_questions(db, &config, &questions, &missed, &source_chunks, &topic)?;
 
-    if let Ok(s) = save
-        && s.contains("Save")
-    {
-        // Collect items to save
-        for q in &questions {
-            match q {
-                QuizQuestion::MultipleChoice {
-                    question,
-                    correct: c,
-                    options,
-                    ..
-                } => {
-                    let answer = options
-                        .iter()
-                        .find(|(l, _)| l == c)
-                        .map(|(_, t)| t.as_str())
-                        .unwrap_or("?");
-                    items_to_save.push((None, "quiz_mc", question, answer));
-                }
-                QuizQuestion::FillInBlank { question, answer } => {
-                    items_to_save.push((None, "quiz_fill", question, answer));
-                }
-                QuizQuestion::ShortAnswer { question, expected } => {
-                    items_to_save.push((None, "quiz_short", question, expected));
-                }
-            }
-        }
+    Ok(())
+}
+
+/// Save a fresh quiz's questions for spaced repetition: just the missed ones
+/// (due again tomorrow) without asking when the config says so, otherwise
+/// whichever the user picks
+fn save_questions(
+    db: &Database,
+    config: &Config,
+    questions: &[QuizQuestion],
+    missed: &[usize],
+    source_chunks: &[i64],
+    topic: &str,
+) -> Result<()> {
+    let as_item = |q: &QuizQuestion| {
+        (
+            q.item_type().to_string(),
+            q.question().to_string(),
+            q.answer_text().to_string(),
+        )
+    };
+    let missed_items: Vec<_> = missed.iter().map(|&i| as_item(&questions[i])).collect();
 
-        let saved = store.bulk_insert(&items_to_save)?;
+    if config.quiz_save_missed && !missed_items.is_empty() {
+        let saved = save_missed(db, &missed_items, source_chunks, topic)?;
         println!(
-            "{} Saved {} items for spaced repetition review!",
+            "{} Saved {} missed questions for review tomorrow",
             "✓".green(),
             saved
         );
+        return Ok(());
     }
 
-    Ok(())
-}
-
-fn parse_quiz_questions(text: &str) -> Vec<QuizQuestion> {
-    let mut questions = Vec::new();
-    let lines: Vec<&str> = text.lines().collect();
-    let mut i = 0;
-
-    while i < lines.len() {
-        let line = lines[i].trim();
-
-        // Try to detect a numbered question
-        if let Some(q_text) = extract_question_text(line) {
-            // Check if next lines have options (a-d)
-            let mut options = Vec::new();
-            let mut j = i + 1;
-
-            while j < lines.len() {
-                let opt_line = lines[j].trim();
-                if let Some((letter, text)) = extract_option(opt_line) {
-                    options.push((letter, text));
-                    j += 1;
-                } else {
-                    break;
-                }
-            }
-
-            if options.len() >= 2 {
-                // Multiple choice — find answer
-                let correct = find_answer_letter(&lines[j..]);
-                let skip = if correct.is_some() { j + 1 } else { j };
-
-                questions.push(QuizQuestion::MultipleChoice {
-                    question: q_text,
-                    options,
-                    correct: correct.unwrap_or('a'),
-                });
-                i = skip;
-                continue;
-            }
-
-            // Check for fill-in-blank (contains ___)
-            if q_text.contains("___")
-                && let Some(answer) = find_answer_text(&lines[j..])
-            {
-                questions.push(QuizQuestion::FillInBlank {
-                    question: q_text,
-                    answer,
-                });
-                i = j + 1;
-                continue;
-            }
-
-            // Default: short answer
-            if let Some(answer) = find_answer_text(&lines[(i + 1)..]) {
-                questions.push(QuizQuestion::ShortAnswer {
-                    question: q_text,
-                    expected: answer,
-                });
-                i = j + 1;
-                continue;
-            }
-        }
-
-        i += 1;
-    }
-
-    questions
-}
-
-fn extract_question_text(line: &str) -> Option<String> {
-    let line = line.trim();
-    // Match patterns like "1.", "1)", "Q:", or "**1.**"
-    if line.starts_with("Q:") || line.starts_with("Q.") {
-        return Some(line[2..].trim().to_string());
-    }
-
-    // Numbered question: strip leading number + punctuation
-    let mut chars = line.chars().peekable();
-    // Skip markdown bold
-    if line.starts_with("**") {
-        let inner = line.trim_start_matches("**");
-        if let Some(end) = inner.find("**") {
-            let num_part = &inner[..end];
-            if num_part.chars().any(|c| c.is_ascii_digit()) {
-                let rest = inner[end..].trim_start_matches("**").trim();
-                if !rest.is_empty() {
-                    return Some(rest.to_string());
-                }
-            }
-        }
+    let missed_option = format!(
+        "🎯  Save the {} I missed        │ Back for review tomorrow",
+        missed_items.len()
+    );
+    let mut save_opts = Vec::new();
+    if !missed_items.is_empty() {
+        save_opts.push(missed_option.as_str());
     }
+    save_opts.push("💾  Save every question        │ Review them all later");
+    save_opts.push("❌  Don't save");
+    let save = Select::new("Save quiz items for spaced repetition?", save_opts).prompt();
 
-    // Numbered: "1. question" or "1) question"
-    if chars.peek().is_some_and(|c| c.is_ascii_digit()) {
-        let num_end = line
-            .find(|c: char| !c.is_ascii_digit())
-            .unwrap_or(line.len());
-        let rest = line[num_end..].trim_start_matches(['.', ')', ':']).trim();
-        if !rest.is_empty() {
-            return Some(rest.to_string());
+    match save {
+        Ok(s) if s.contains("I missed") => {
+            let saved = save_missed(db, &missed_items, source_chunks, topic)?;
+            println!(
+                "{} Saved {} missed questions for review tomorrow",
+                "✓".green(),
+                saved
+            );
         }
-    }
-
-    None
-}
-
-fn extract_option(line: &str) -> Option<(char, String)> {
-    let line = line.trim();
-    if line.len() < 3 {
-        return None;
-    }
-
-    let first = line.chars().next()?;
-    if !first.is_ascii_lowercase() || !('a'..='d').contains(&first) {
-        return None;
-    }
-
-    let rest = &line[1..];
-    if rest.starts_with(')') || rest.starts_with('.') || rest.starts_with(':') {
-        let text = rest[1..].trim().to_string();
-        if !text.is_empty() {
-            return Some((first, text));
+        Ok(s) if s.contains("Save every") => {
+            let items: Vec<_> = questions.iter().map(as_item).collect();
+            let saved = crate::commands::generate::save_linked_study_items(
+                db,
+                &items,
+                source_chunks,
+                topic,
+            )?;
+            println!(
+                "{} Saved {} items for spaced repetition review!",
+                "✓".green(),
+                saved.len()
+            );
         }
+        _ => {}
     }
 
-    None
-}
-
-fn find_answer_letter(lines: &[&str]) -> Option<char> {
-    for line in lines.iter().take(3) {
-        let line = line.trim().to_lowercase();
-        // Match "**Answer: b)**" or "Answer: b" patterns
-        if line.contains("answer") {
-            for c in line.chars() {
-                if ('a'..='d').contains(&c) {
-                    return Some(c);
-                }
-            }
-        }
-    }
-    None
+    Ok(())
 }
 
-fn find_answer_text(lines: &[&str]) -> Option<String> {
-    for line in lines.iter().take(3) {
-        let line_trimmed = line.trim();
-        let lower = line_trimmed.to_lowercase();
-
-        if lower.starts_with("**answer") || lower.starts_with("answer") {
-            let text = line_trimmed
-                .trim_start_matches("**")
-                .trim_start_matches("Answer")
-                .trim_start_matches("answer")
-                .trim_start_matches("**")
-                .trim_start_matches(':')
-                .trim_start_matches("**")
-                .trim()
-                .trim_end_matches("**")
-                .trim();
-
-            if !text.is_empty() {
-                return Some(text.to_string());
-            }
-        }
+/// Add missed questions to the deck (or find them if already there) and
+/// schedule them as failed recalls, so they come back tomorrow
+fn save_missed(
+    db: &Database,
+    items: &[(String, String, String)],
+    source_chunks: &[i64],
+    topic: &str,
+) -> Result<usize> {
+    let store = StudyStore::new(db);
+    for item in items {
+        let id = match store.find(&item.1, &item.2)? {
+            Some(id) => id,
+            None => crate::commands::generate::save_linked_study_items(
+                db,
+                std::slice::from_ref(item),
+                source_chunks,
+                topic,
+            )?[0],
+        };
+        store.update_after_review(id, 1)?;
     }
-    None
+    Ok(items.len())
 }
 
 fn print_quiz_summary(
@@ -596,29 +700,3 @@ fn print_quiz_summary(
     );
     println!();
 }
-
-const QUIZ_PROMPT: &str = r#"You are creating a practice quiz from the provided course materials.
-
-Generate a quiz with mixed question types:
-
-## Multiple Choice
-1. Question text
-   a) Option A
-   b) Option B
-   c) Option C
-   d) Option D
-   **Answer: b)**
-
-## Fill in the Blank
-1. The process of _______ is essential for...
-   **Answer: [correct answer]**
-
-## Short Answer
-1. Explain the concept of...
-   **Answer: [brief expected answer]**
-
-Rules:
-- Create 10 questions total (mix of types)
-- Base questions only on the provided materials
-- Include answers after each question
-- Progress from easier to harder questions"#;
@@ -0,0 +1,329 @@
+use anyhow::Result;
+use colored::Colorize;
+use std::path::Path;
+
+use super::add::create_progress_bar;
+use super::sync::{self, ChunkPlan};
+use crate::bucket::{self, Bucket};
+use crate::ingest::{
+    self, ChunkConfig, ContentType, ExtractedContent, Section, Timestamp, code, text,
+};
+use crate::storage::chunks::StoredChunk;
+use crate::storage::{ChunkStore, Database, Document, DocumentStore};
+
+/// Chunks embedded per model call when filling in missing embeddings
+const EMBED_BATCH: usize = 64;
+
+/// Re-chunk and re-embed every document in a bucket, e.g. after the embedding
+/// model or chunk settings changed. With `missing_only`, just embed the chunks
+/// that have no embedding.
+pub async fn run(bucket_name: Option<String>, missing_only: bool) -> Result<()> {
+    let db = match &bucket_name {
+        Some(name) => {
+            let bucket = Bucket::open(name)?;
+            println!("{} {}", "Bucket:".dimmed(), bucket.name.cyan());
+            Database::open_for_bucket(&bucket)?
+        }
+        None => {
+            if let Some(bucket) = bucket::get_current_bucket()? {
+                println!("{} {}", "Bucket:".dimmed(), bucket.name.cyan());
+            } else {
+                println!("{} {}", "Bucket:".dimmed(), "(default)".dimmed());
+            }
+            Database::open()?
+        }
+    };
+    let doc_store = DocumentStore::new(&db);
+    let chunk_store = ChunkStore::new(&db);
+    chunk_store.init_schema()?;
+
+    if missing_only {
+        return embed_missing(&chunk_store);
+    }
+
+    let docs = doc_store.list()?;
+    if docs.is_empty() {
+        println!("{} No documents to reindex.", "ℹ".blue());
+        return Ok(());
+    }
+
+    println!("\n{} {} document(s)...\n", "Reindexing".bold(), docs.len());
+
+    let config = ChunkConfig::current();
+    let mut reindexed = 0;
+    let mut total_chunks = 0;
+    let mut errors = 0;
+
+    for doc in &docs {
+        match reindex_document(doc, &chunk_store, &config).await {
+            Ok(plan) => {
+                println!(
+                    "  {} {} {}",
+                    "✓".green(),
+                    doc.filename,
+                    sync::describe_plan(&plan).dimmed()
+                );
+                reindexed += 1;
+                total_chunks += plan.chunk_count();
+            }
+            Err(e) => {
+                println!(
+                    "  {} {} {}",
+                    "✗".red(),
+                    doc.filename,
+                    format!("({})", e).dimmed()
+                );
+                errors += 1;
+            }
+        }
+    }
+
+    println!(
+        "\n{} {} reindexed ({} chunks), {} failed",
+        "Done:".bold(),
+        reindexed.to_string().green(),
+        total_chunks,
+        errors
+    );
+
+    Ok(())
+}
+
+/// Re-chunk and re-embed one document with the current settings
+pub async fn document(id: i64) -> Result<()> {
+    let db = Database::open()?;
+    let chunk_store = ChunkStore::new(&db);
+    chunk_store.init_schema()?;
+
+    let Some(doc) = DocumentStore::new(&db).get(id)? else {
+        println!("{} Document not found: {}", "✗".red(), id);
+        return Ok(());
+    };
+
+    let plan = reindex_document(&doc, &chunk_store, &ChunkConfig::current()).await?;
+    println!(
+        "{} Reindexed {} {}",
+        "✓".green(),
+        doc.filename,
+        sync::describe_plan(&plan).dimmed()
+    );
+
+    Ok(())
+}
+
+/// Embed chunks stored without an embedding (e.g. the model failed to load
+/// when they were added)
+fn embed_missing(chunk_store: &ChunkStore) -> Result<()> {
+    let chunks = chunk_store.get_unembedded()?;
+    if chunks.is_empty() {
+        println!("{} Every chunk already has an embedding.", "✓".green());
+        return Ok(());
+    }
+
+    let pb = create_progress_bar(chunks.len() as u64, "Embedding chunks");
+    for batch in chunks.chunks(EMBED_BATCH) {
+        let texts: Vec<&str> = batch.iter().map(|c| c.content.as_str()).collect();
+        let vectors = chunk_store.embed_texts(&texts)?;
+        for (chunk, vector) in batch.iter().zip(&vectors) {
+            chunk_store.update_embedding(chunk.id, vector)?;
+        }
+        pb.inc(batch.len() as u64);
+    }
+    pb.finish_and_clear();
+
+    println!(
+        "{} Embedded {} chunk(s).",
+        "✓".green(),
+        chunks.len().to_string().bold()
+    );
+
+    Ok(())
+}
+
+async fn reindex_document(
+    doc: &Document,
+    chunk_store: &ChunkStore<'_>,
+    config: &ChunkConfig,
+) -> Result<ChunkPlan> {
+    let content = match extract_again(doc).await {
+        Some(content) => content,
+        None => rebuild_content(doc, &chunk_store.get_for_document(doc.id)?),
+    };
+
+    let chunks = content.chunks(config);
+    sync::reembed_chunks(chunk_store, doc.id, &chunks)
+}
+
+/// Extract the source file again when that's cheap and yields the stored text,
+/// which gives exact page and section boundaries
+async fn extract_again(doc: &Document) -> Option<ExtractedContent> {
+    let path = Path::new(&doc.source_path);
+    let content_type = ContentType::from_path(path);
+    // Transcription and OCR are slow (and may cost API calls); the stored text is enough
+    if content_type.is_media() || content_type.is_image() || !path.is_file() {
+        return None;
+    }
+
+    ingest::extract_from_file_async(path)
+        .await
+        .ok()
+        .filter(|content| content.text == doc.content)
+}
+
+/// Rebuild a document's content from its stored text, recovering sections,
+/// pages and timestamps from its current chunks
+fn rebuild_content(doc: &Document, old: &[StoredChunk]) -> ExtractedContent {
+    let content_type = ContentType::from_stored(&doc.content_type);
+    let (mut sections, pages, timestamps) = structure_from_chunks(&doc.content, old);
+
+    // Headings and definitions can be found again exactly
+    match content_type {
+        ContentType::Markdown => sections = text::markdown_sections(&doc.content),
+        ContentType::Code(language) => sections = code::sections(&doc.content, language),
+        _ => {}
+    }
+
+    ExtractedContent {
+        source: doc.source_path.clone(),
+        content_type,
+        text: doc.content.clone(),
+        sections,
+        pages,
+        timestamps,
+    }
+}
+
+/// Section, page and timestamp boundaries, at chunk granularity, from where
+/// each existing chunk sits in the text
+fn structure_from_chunks(
+    text: &str,
+    chunks: &[StoredChunk],
+) -> (Vec<Section>, Vec<usize>, Vec<Timestamp>) {
+    let mut sections: Vec<Section> = Vec::new();
+    let mut pages = Vec::new();
+    let mut timestamps = Vec::new();
+    let mut cursor = 0;
+
+    for chunk in chunks {
+        let content = chunk.content.trim();
+        let Some(found) = text.get(cursor..).and_then(|rest| rest.find(content)) else {
+            continue;
+        };
+        let start = cursor + found;
+        // Chunks overlap, so the next one may begin before this one ends
+        cursor = start + content.chars().next().map_or(0, char::len_utf8);
+
+        if let Some(title) = &chunk.section
+            && sections.last().is_none_or(|s| &s.title != title)
+        {
+            sections.push(Section {
+                title: title.clone(),
+                start,
+            });
+        }
+        if let Some(page) = chunk.page {
+            while (pages.len() as i64) < page {
+                pages.push(start);
+            }
+        }
+        if let Some(seconds) = chunk.start_time {
+            timestamps.push(Timestamp { start, seconds });
+        }
+    }
+
+    (sections, pages, timestamps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embeddings;
+    use crate::ingest::ChunkStrategy;
+
+    fn stored(content: &str, section: Option<&str>, page: Option<i64>) -> StoredChunk {
+        StoredChunk {
+            id: 0,
+            document_id: 1,
+            chunk_index: 0,
+            content: content.to_string(),
+            embedding: None,
+            section: section.map(str::to_string),
+            page,
+            start_time: None,
+        }
+    }
+
+    #[test]
+    fn test_structure_from_chunks() {
+        let text = "Intro text. More intro. Chapter two begins. The end.";
+        let chunks = vec![
+            stored("Intro text. More intro.", Some("Intro"), Some(1)),
+            stored("More intro. Chapter two", Some("Intro"), Some(1)),
+            stored("Chapter two begins.", Some("Two"), Some(3)),
+            stored("missing from the text", Some("Gone"), None),
+            stored(" The end. ", Some("Two"), Some(3)),
+        ];
+
+        let (sections, pages, timestamps) = structure_from_chunks(text, &chunks);
+        let sections: Vec<(&str, usize)> = sections
+            .iter()
+            .map(|s| (s.title.as_str(), s.start))
+            .collect();
+
+        assert_eq!(sections, vec![("Intro", 0), ("Two", 24)]);
+        assert_eq!(pages, vec![0, 24, 24]);
+        assert!(timestamps.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reindex_document_rechunks_stored_text() {
+        embeddings::use_mock_backend();
+        let db = Database::open_in_memory().unwrap();
+        let docs = DocumentStore::new(&db);
+        let chunks = ChunkStore::new(&db);
+        chunks.init_schema().unwrap();
+
+        let content = format!(
+            "# Glycolysis\n\n{}\n\n# Krebs cycle\n\n{}\n",
+            "Glucose is split into two pyruvate molecules. ".repeat(8),
+            "Acetyl-CoA is oxidised to carbon dioxide. ".repeat(8)
+        );
+        // The source file is gone, so the stored text is all there is
+        let id = docs
+            .insert(
+                "/nonexistent/librarian/respiration.md",
+                "respiration.md",
+                "markdown",
+                &content,
+                None,
+            )
+            .unwrap();
+        let old = chunks.insert(id, 0, &content, None).unwrap();
+
+        let config = ChunkConfig {
+            chunk_size: 200,
+            overlap: 0,
+            strategy: ChunkStrategy::Structured,
+        };
+        let doc = docs.get(id).unwrap().unwrap();
+        let plan = reindex_document(&doc, &chunks, &config).await.unwrap();
+        assert!(plan.chunk_count() > 2);
+
+        let stored = chunks.get_for_document(id).unwrap();
+        assert_eq!(stored.len(), plan.chunk_count());
+        assert!(stored.iter().all(|c| c.id != old && c.embedding.is_some()));
+        let sections: Vec<&str> = stored.iter().filter_map(|c| c.section.as_deref()).collect();
+        assert!(sections.contains(&"Glycolysis") && sections.contains(&"Krebs cycle"));
+
+        // Unchanged settings keep every chunk
+        let ids: Vec<i64> = stored.iter().map(|c| c.id).collect();
+        reindex_document(&doc, &chunks, &config).await.unwrap();
+        let kept: Vec<i64> = chunks
+            .get_for_document(id)
+            .unwrap()
+            .iter()
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(kept, ids);
+    }
+}
@@ -1,14 +1,33 @@
 use anyhow::Result;
 use colored::Colorize;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
 use inquire::Select;
+use std::io::IsTerminal;
 
-use crate::storage::{Database, StudyStore};
+use crate::llm::schema::{cloze_answers, hide_cloze, render_cloze};
+use crate::storage::activity;
+use crate::storage::chunks::StoredChunk;
+use crate::storage::links::{KIND_CHUNK, KIND_STUDY_ITEM};
+use crate::storage::{
+    ChunkStore, Database, Document, DocumentStore, LinkStore, PlanStore, StudyItem, StudyStore,
+};
 
-pub async fn run() -> Result<()> {
+/// Review due items; `tag` limits the session to items with that tag
+pub async fn run(tag: Option<String>) -> Result<()> {
     let db = Database::open()?;
     let store = StudyStore::new(&db);
 
-    let due_count = store.count_due()?;
+    print_todays_plan(&db)?;
+
+    let due_count = store.count_due(tag.as_deref())?;
+
+    if due_count == 0
+        && let Some(tag) = &tag
+    {
+        println!("\n{} No items tagged '{}' are due.", "✓".green(), tag);
+        return Ok(());
+    }
 
     if due_count == 0 {
         println!(
@@ -45,7 +64,7 @@ pub async fn run() -> Result<()> {
     );
     println!();
 
-    let items = store.get_due(50)?;
+    let items = store.get_due(50, tag.as_deref())?;
     let total = items.len();
     let mut correct = 0;
 
@@ -58,7 +77,7 @@ pub async fn run() -> Result<()> {
             format!("({})", item.item_type).dimmed()
         );
         println!();
-        println!("  {} {}", "Q:".bold().yellow(), item.front);
+        print_question(item);
         println!();
 
         // Wait for user to reveal answer
@@ -66,29 +85,19 @@ pub async fn run() -> Result<()> {
             .with_default("")
             .prompt();
 
-        println!("  {} {}", "A:".bold().green(), item.back);
+        print_answer(item);
+        let source = find_source(&db, item)?;
+        if let Some((doc, chunk)) = &source {
+            print_source_line(doc, chunk);
+        }
         println!();
 
         // Self-rate
-        let options = vec![
-            "1 - Did not remember at all",
-            "2 - Barely remembered, wrong",
-            "3 - Remembered with difficulty",
-            "4 - Remembered correctly",
-            "5 - Easy, perfect recall",
-        ];
-
-        let rating = Select::new("  How well did you recall this?", options).prompt();
-
-        let quality: u8 = match rating {
-            Ok(s) => s.chars().next().unwrap_or('3').to_digit(10).unwrap_or(3) as u8,
-            Err(inquire::InquireError::OperationCanceled)
-            | Err(inquire::InquireError::OperationInterrupted) => {
-                println!("\n{}", "Review session ended early.".dimmed());
-                print_summary(correct, i);
-                return Ok(());
-            }
-            Err(e) => return Err(e.into()),
+        let Some(quality) = rate_recall(source.as_ref())? else {
+            println!("\n{}", "Review session ended early.".dimmed());
+            activity::record(activity::REVIEW, i as i64);
+            print_summary(correct, i);
+            return Ok(());
         };
 
         if quality >= 3 {
@@ -100,11 +109,421 @@ pub async fn run() -> Result<()> {
         println!("{}", "─".repeat(50).dimmed());
     }
 
+    activity::record(activity::REVIEW, total as i64);
     print_summary(correct, total);
 
     Ok(())
 }
 
+/// Go through every item matching `topic` (a tag, or else text in the
+/// question or answer; everything when empty) regardless of due dates.
+/// Missed cards come back at the end of the round until each one is
+/// recalled. Nothing is rescheduled.
+pub async fn cram(topic: &str) -> Result<()> {
+    let db = Database::open()?;
+    let store = StudyStore::new(&db);
+
+    let topic = topic.trim();
+    let mut items = if topic.is_empty() {
+        store.list()?
+    } else {
+        store.list_tagged(topic)?
+    };
+    if items.is_empty() && !topic.is_empty() {
+        let needle = topic.to_lowercase();
+        items = store
+            .list()?
+            .into_iter()
+            .filter(|i| {
+                i.front.to_lowercase().contains(&needle) || i.back.to_lowercase().contains(&needle)
+            })
+            .collect();
+    }
+    if items.is_empty() {
+        println!("\n{} Nothing to cram for '{}'.", "⊘".yellow(), topic);
+        return Ok(());
+    }
+
+    println!(
+        "\n{} {} cards{} {}\n",
+        "📚 Cram mode:".bold().cyan(),
+        items.len(),
+        if topic.is_empty() {
+            String::new()
+        } else {
+            format!(" on '{}'", topic)
+        },
+        "(review schedule is left alone)".dimmed()
+    );
+
+    let total = items.len();
+    let mut queue: std::collections::VecDeque<StudyItem> = items.into();
+    let (mut round, mut round_left, mut first_try, mut answered) = (1, total, 0, 0);
+    let mut missed_once = std::collections::HashSet::new();
+
+    while let Some(item) = queue.pop_front() {
+        round_left -= 1;
+        println!(
+            "{} {} {}",
+            "Card".bold().cyan(),
+            format!("[{} left]", queue.len() + 1).dimmed(),
+            format!("({})", item.item_type).dimmed()
+        );
+        println!();
+        print_question(&item);
+        println!();
+
+        let _ = inquire::Text::new("  Press Enter to reveal answer...")
+            .with_default("")
+            .prompt();
+
+        print_answer(&item);
+        println!();
+
+        let choice = Select::new("  Did you get it?", vec!["✓ Got it", "↻ Again"]).prompt();
+        if choice.is_ok() {
+            answered += 1;
+        }
+        match choice {
+            Ok(c) if c.contains("Got it") => {
+                if !missed_once.contains(&item.id) {
+                    first_try += 1;
+                }
+            }
+            Ok(_) => {
+                missed_once.insert(item.id);
+                queue.push_back(item);
+            }
+            Err(inquire::InquireError::OperationCanceled)
+            | Err(inquire::InquireError::OperationInterrupted) => {
+                println!("\n{}", "Cram session ended early.".dimmed());
+                break;
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        if round_left == 0 && !queue.is_empty() {
+            round += 1;
+            round_left = queue.len();
+            println!(
+                "\n{} Round {}: {} cards to go again\n",
+                "↻".yellow(),
+                round,
+                queue.len()
+            );
+        }
+
+        println!("{}", "─".repeat(50).dimmed());
+    }
+
+    activity::record(activity::REVIEW, answered);
+    println!(
+        "\n{} {} of {} cards recalled on the first try",
+        "✓".green(),
+        first_try,
+        total
+    );
+
+    Ok(())
+}
+
+/// Menu entry that shows the passage a card came from
+pub(crate) const SHOW_SOURCE: &str = "📎 Show the source passage";
+
+/// Self-ratings, from quality 1 to 5
+const RATINGS: [&str; 5] = [
+    "1 - Did not remember at all",
+    "2 - Barely remembered, wrong",
+    "3 - Remembered with difficulty",
+    "4 - Remembered correctly",
+    "5 - Easy, perfect recall",
+];
+
+/// A key pressed while rating a card
+enum RatingKey {
+    Rating(u8),
+    ShowSource,
+    Stop,
+}
+
+/// Ask how well a card was recalled, None if the user wants to stop. In a
+/// terminal a single key does it (1-5, space/Enter for 4, s for the source
+/// passage); otherwise it falls back to a menu.
+fn rate_recall(source: Option<&(Document, StoredChunk)>) -> Result<Option<u8>> {
+    if !std::io::stdin().is_terminal() {
+        let mut options = RATINGS.to_vec();
+        if source.is_some() {
+            options.push(SHOW_SOURCE);
+        }
+        loop {
+            let choice =
+                Select::new("  How well did you recall this?", options.clone()).raw_prompt();
+            match choice {
+                Ok(choice) if choice.index < RATINGS.len() => {
+                    return Ok(Some(choice.index as u8 + 1));
+                }
+                Ok(_) => {
+                    if let Some((doc, chunk)) = source {
+                        print_passage(doc, chunk);
+                    }
+                }
+                Err(inquire::InquireError::OperationCanceled)
+                | Err(inquire::InquireError::OperationInterrupted) => return Ok(None),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    println!("  {}", "How well did you recall this?".bold());
+    println!(
+        "  {}",
+        format!(
+            "1 forgot · 2 wrong · 3 hard · 4 good · 5 easy   (space = good, {}Esc = stop)",
+            if source.is_some() { "s = source, " } else { "" }
+        )
+        .dimmed()
+    );
+
+    loop {
+        terminal::enable_raw_mode()?;
+        let key = read_rating_key();
+        terminal::disable_raw_mode()?;
+
+        match key? {
+            RatingKey::Rating(quality) => {
+                println!("  {} {}", "→".cyan(), RATINGS[quality as usize - 1]);
+                return Ok(Some(quality));
+            }
+            RatingKey::ShowSource => match source {
+                Some((doc, chunk)) => print_passage(doc, chunk),
+                None => println!("  {}", "No source passage recorded for this card.".dimmed()),
+            },
+            RatingKey::Stop => return Ok(None),
+        }
+    }
+}
+
+/// Wait for a rating key (the terminal must be in raw mode)
+fn read_rating_key() -> Result<RatingKey> {
+    loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Ok(RatingKey::Stop);
+            }
+            KeyCode::Char(c @ '1'..='5') => return Ok(RatingKey::Rating(c as u8 - b'0')),
+            KeyCode::Char(' ') | KeyCode::Enter => return Ok(RatingKey::Rating(4)),
+            KeyCode::Char('s') => return Ok(RatingKey::ShowSource),
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(RatingKey::Stop),
+            _ => {}
+        }
+    }
+}
+
+fn print_question(item: &StudyItem) {
+    if item.item_type == "cloze" {
+        let hidden = hide_cloze(&item.front, |blank| blank.yellow().to_string());
+        println!("  {} {}", "Q:".bold().yellow(), hidden);
+    } else {
+        println!("  {} {}", "Q:".bold().yellow(), item.front);
+    }
+}
+
+fn print_answer(item: &StudyItem) {
+    // Cards imported with their blanks already hidden carry the full text
+    // on the back instead
+    if item.item_type == "cloze" && !cloze_answers(&item.front).is_empty() {
+        let revealed = render_cloze(&item.front, |answer| {
+            answer.green().bold().underline().to_string()
+        });
+        println!("  {} {}", "A:".bold().green(), revealed);
+    } else {
+        println!("  {} {}", "A:".bold().green(), item.back);
+    }
+}
+
+/// Show the due forecast for the next `days` days, plus retention and
+/// reviews per day over the last `days` days
+pub fn stats(days: u32) -> Result<()> {
+    let db = Database::open()?;
+    let store = StudyStore::new(&db);
+    let days = days.max(1);
+
+    let (count, ease) = store.ease_summary()?;
+    if count == 0 {
+        println!("{}", "No study items yet.".dimmed());
+        println!(
+            "  Use {} to create some.",
+            "librarian generate flashcards".cyan()
+        );
+        return Ok(());
+    }
+
+    println!(
+        "\n{} ({} items, average ease {:.2})\n",
+        "Review stats".bold(),
+        count,
+        ease.unwrap_or(2.5)
+    );
+
+    let forecast = store.due_forecast(days)?;
+    let busiest = forecast.iter().map(|(_, n)| *n).max().unwrap_or(0).max(1);
+    println!("{} (next {} days)", "Due".bold(), days);
+    for (i, (day, due)) in forecast.iter().enumerate() {
+        let label = match i {
+            0 => "Today".to_string(),
+            1 => "Tomorrow".to_string(),
+            _ => day.format("%a %b %-d").to_string(),
+        };
+        let bar = "▪".repeat((due * 30).div_ceil(busiest));
+        if *due == 0 {
+            println!("  {:<12} {:>4}", label.dimmed(), "·".dimmed());
+        } else {
+            println!("  {:<12} {:>4} {}", label, due, bar.cyan());
+        }
+    }
+
+    let since = chrono::Local::now().date_naive() - chrono::Duration::days(i64::from(days) - 1);
+    let history = store.review_history(since)?;
+    println!("\n{} (last {} days)", "Reviews".bold(), days);
+    if history.is_empty() {
+        println!("  {}", "No reviews yet.".dimmed());
+        println!();
+        return Ok(());
+    }
+    for day in &history {
+        println!(
+            "  {:<12} {:>4} reviewed {}",
+            day.day.format("%a %b %-d").to_string(),
+            day.reviews,
+            format!("({:.0}% recalled)", percent(day.recalled, day.reviews)).dimmed()
+        );
+    }
+
+    let reviews: usize = history.iter().map(|d| d.reviews).sum();
+    let recalled: usize = history.iter().map(|d| d.recalled).sum();
+    println!(
+        "\n{} {}% ({} of {} reviews rated 3 or better)\n",
+        "Retention:".bold(),
+        format!("{:.0}", percent(recalled, reviews)).cyan(),
+        recalled,
+        reviews
+    );
+
+    Ok(())
+}
+
+fn percent(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64 * 100.0
+    }
+}
+
+/// List what today's study plan (from `generate study-plan`) calls for
+fn print_todays_plan(db: &Database) -> Result<()> {
+    let today = chrono::Local::now().date_naive();
+    let entries = PlanStore::new(db).for_day(today)?;
+    let Some((_, exam_date)) = entries.first() else {
+        return Ok(());
+    };
+
+    let days_left = (*exam_date - today).num_days();
+    println!(
+        "\n{} {}",
+        "📅 Today's plan".bold().cyan(),
+        format!(
+            "(exam on {}, {} day{} away)",
+            exam_date.format("%a %b %-d"),
+            days_left,
+            if days_left == 1 { "" } else { "s" }
+        )
+        .dimmed()
+    );
+    for (entry, _) in &entries {
+        println!("  {} {}", "•".cyan(), entry.title.bold());
+        for line in entry.details.lines() {
+            println!("      {}", line.dimmed());
+        }
+    }
+
+    Ok(())
+}
+
+/// The passage a card came from: the chunk it was linked to when it was
+/// generated, else the chunk of its document that best matches the card
+pub(crate) fn find_source(
+    db: &Database,
+    item: &StudyItem,
+) -> Result<Option<(Document, StoredChunk)>> {
+    let chunk_store = ChunkStore::new(db);
+    chunk_store.init_schema()?;
+
+    let linked = LinkStore::new(db)
+        .created_from(KIND_STUDY_ITEM, item.id)?
+        .into_iter()
+        .find(|l| l.to_kind == KIND_CHUNK)
+        .and_then(|link| chunk_store.get(link.to_id).ok().flatten());
+
+    let chunk = match (linked, item.document_id) {
+        (Some(chunk), _) => chunk,
+        (None, Some(document_id)) => {
+            let mut chunks = chunk_store.get_for_document(document_id)?;
+            let candidates: Vec<(i64, String)> =
+                chunks.iter().map(|c| (c.id, c.content.clone())).collect();
+            let text = format!("{} {}", item.front, item.back);
+            let best = crate::search::best_source(&text, &candidates)
+                .or_else(|| candidates.first().map(|(id, _)| *id));
+            let Some(index) = best.and_then(|id| chunks.iter().position(|c| c.id == id)) else {
+                return Ok(None);
+            };
+            chunks.swap_remove(index)
+        }
+        (None, None) => return Ok(None),
+    };
+
+    Ok(DocumentStore::new(db)
+        .get(chunk.document_id)?
+        .map(|doc| (doc, chunk)))
+}
+
+/// One line naming where a card came from, with the start of the passage
+fn print_source_line(doc: &Document, chunk: &StoredChunk) {
+    let snippet: String = chunk.content.chars().take(150).collect();
+    println!(
+        "  {} {}{} {}",
+        "📎 From:".dimmed(),
+        doc.filename.cyan(),
+        chunk.location().dimmed(),
+        format!("(chunk {})", chunk.chunk_index).dimmed()
+    );
+    println!(
+        "     {}",
+        format!("\"{}...\"", snippet.replace('\n', " ").trim()).dimmed()
+    );
+}
+
+/// The whole passage a card came from
+pub(crate) fn print_passage(doc: &Document, chunk: &StoredChunk) {
+    println!(
+        "\n  {} {}{}",
+        "📎".dimmed(),
+        doc.filename.cyan().bold(),
+        chunk.location().dimmed()
+    );
+    println!("  {}", "─".repeat(50).dimmed());
+    for line in chunk.content.trim().lines() {
+        println!("  {}", line);
+    }
+    println!("  {}\n", "─".repeat(50).dimmed());
+}
+
 fn print_summary(correct: usize, total: usize) {
     let pct = if total > 0 {
         (correct as f64 / total as f64) * 100.0
@@ -0,0 +1,84 @@
+use anyhow::Result;
+use chrono::{Duration, Local};
+use colored::Colorize;
+
+use crate::storage::UsageStore;
+use crate::storage::usage::{self, DailyUsage};
+
+/// Show tokens, requests and time spent over the last `days` days
+pub fn usage(days: u32) -> Result<()> {
+    let db = usage::open()?;
+    let store = UsageStore::new(&db);
+    let since = (Local::now() - Duration::days(i64::from(days.max(1)) - 1))
+        .format("%Y-%m-%d")
+        .to_string();
+
+    let by_day = store.by_day(&since)?;
+    if by_day.is_empty() {
+        println!(
+            "{}",
+            format!("No model calls in the last {} days.", days).dimmed()
+        );
+        return Ok(());
+    }
+
+    let label = if days == 1 {
+        "today".to_string()
+    } else {
+        format!("last {} days", days)
+    };
+    println!("\n{} ({})\n", "Usage".bold(), label);
+
+    println!("{}", "By day".bold());
+    for day in &by_day {
+        print_totals(&day.day, day);
+    }
+
+    println!("\n{}", "By model".bold());
+    for model in store.by_model(&since)? {
+        print_totals(&model.model, &model);
+    }
+
+    let by_command = store.by_command(&since)?;
+    if !by_command.is_empty() {
+        println!("\n{}", "By command".bold());
+        for command in by_command {
+            println!(
+                "  {:<28} {:>5} requests {:>10} tokens {}",
+                command.command,
+                command.requests,
+                command.total_tokens,
+                format!(
+                    "(avg {:.1}s)",
+                    command.total_ms as f64 / command.requests.max(1) as f64 / 1000.0
+                )
+                .dimmed()
+            );
+        }
+    }
+
+    let total: u64 = by_day.iter().map(DailyUsage::total_tokens).sum();
+    let requests: u64 = by_day.iter().map(|d| d.requests).sum();
+    println!(
+        "\n{} {} tokens over {} requests\n",
+        "Total:".bold(),
+        total.to_string().cyan(),
+        requests
+    );
+
+    Ok(())
+}
+
+fn print_totals(label: &str, usage: &DailyUsage) {
+    println!(
+        "  {:<28} {:>5} requests {:>10} tokens {}",
+        label,
+        usage.requests,
+        usage.total_tokens(),
+        format!(
+            "({} prompt + {} completion)",
+            usage.prompt_tokens, usage.completion_tokens
+        )
+        .dimmed()
+    );
+}
@@ -0,0 +1,433 @@
+use anyhow::Result;
+use colored::Colorize;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::ingest::{self, Chunk, ChunkConfig};
+use crate::storage::{ChunkStore, Database, Document, DocumentStore, FeedStore};
+
+/// How a document's chunks change when its content is refreshed
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct ChunkPlan {
+    /// Existing chunks to keep, with their new index
+    keep: Vec<(i64, usize)>,
+    /// Indexes of new chunks that need to be embedded and inserted
+    insert: Vec<usize>,
+    /// Existing chunks that no longer appear in the content
+    remove: Vec<i64>,
+}
+
+impl ChunkPlan {
+    /// How many chunks the document has once the plan is applied
+    pub(crate) fn chunk_count(&self) -> usize {
+        self.keep.len() + self.insert.len()
+    }
+}
+
+/// Outcome of refreshing a single document
+pub(crate) enum Refresh {
+    Unchanged,
+    Updated(ChunkPlan),
+}
+
+/// Re-fetch URL documents, re-ingest source files whose content changed and
+/// add new feed entries; with `every`, keep doing so every that many minutes
+pub async fn run(id: Option<i64>, every: Option<u64>) -> Result<()> {
+    let Some(minutes) = every else {
+        return sync_once(id).await;
+    };
+
+    loop {
+        if let Err(e) = sync_once(id).await {
+            eprintln!("{} {}", "Error:".red(), e);
+        }
+        println!(
+            "{}",
+            format!("Next sync in {} minute(s). Ctrl+C to stop.", minutes).dimmed()
+        );
+        tokio::time::sleep(std::time::Duration::from_secs(minutes.max(1) * 60)).await;
+    }
+}
+
+async fn sync_once(id: Option<i64>) -> Result<()> {
+    let db = Database::open()?;
+    let doc_store = DocumentStore::new(&db);
+    let chunk_store = ChunkStore::new(&db);
+    chunk_store.init_schema()?;
+
+    let docs: Vec<Document> = match id {
+        Some(id) => match doc_store.get(id)? {
+            Some(doc) if is_syncable(&doc) => vec![doc],
+            Some(doc) => {
+                println!(
+                    "{} \"{}\" has no URL or local file to sync from",
+                    "⚠".yellow(),
+                    doc.filename
+                );
+                return Ok(());
+            }
+            None => {
+                println!("{} Document not found: {}", "✗".red(), id);
+                return Ok(());
+            }
+        },
+        None => doc_store.list()?.into_iter().filter(is_syncable).collect(),
+    };
+
+    let has_feeds = id.is_none() && !FeedStore::new(&db).list()?.is_empty();
+    if docs.is_empty() && !has_feeds {
+        println!("{} No URLs, source files or feeds to sync.", "ℹ".blue());
+        return Ok(());
+    }
+
+    println!("\n{} {} document(s)...\n", "Syncing".bold(), docs.len());
+
+    let mut updated = 0;
+    let mut unchanged = 0;
+    let mut errors = 0;
+
+    for doc in &docs {
+        let result = if doc.content_type == "url" {
+            refresh_url(doc, &doc_store, &chunk_store).await
+        } else {
+            refresh_file(Path::new(&doc.source_path), doc, &doc_store, &chunk_store).await
+        };
+
+        match result {
+            Ok(Refresh::Unchanged) => {
+                println!("  {} {}", "·".dimmed(), doc.filename.dimmed());
+                unchanged += 1;
+            }
+            Ok(Refresh::Updated(plan)) => {
+                println!(
+                    "  {} {} {}",
+                    "✓".green(),
+                    doc.filename,
+                    describe_plan(&plan).dimmed()
+                );
+                updated += 1;
+            }
+            Err(e) => {
+                println!(
+                    "  {} {} {}",
+                    "✗".red(),
+                    doc.filename,
+                    format!("({})", e).dimmed()
+                );
+                errors += 1;
+            }
+        }
+    }
+
+    let mut summary = format!(
+        "{} updated, {} unchanged, {} failed",
+        updated.to_string().green(),
+        unchanged,
+        errors
+    );
+    if has_feeds {
+        println!("\n{}", "Checking feeds...".bold());
+        let added = super::feed::refresh_all(&db).await?;
+        summary.push_str(&format!(", {} new from feeds", added));
+    }
+
+    println!("\n{} {}", "Done:".bold(), summary);
+
+    Ok(())
+}
+
+/// URLs, and documents whose source file is still on disk
+fn is_syncable(doc: &Document) -> bool {
+    doc.content_type == "url" || Path::new(&doc.source_path).is_file()
+}
+
+pub(crate) fn describe_plan(plan: &ChunkPlan) -> String {
+    format!(
+        "(+{} -{} chunks, {} unchanged)",
+        plan.insert.len(),
+        plan.remove.len(),
+        plan.keep.len()
+    )
+}
+
+async fn refresh_url(
+    doc: &Document,
+    doc_store: &DocumentStore<'_>,
+    chunk_store: &ChunkStore<'_>,
+) -> Result<Refresh> {
+    let content = ingest::fetch_url(&doc.source_path).await?;
+    if content.text == doc.content {
+        return Ok(Refresh::Unchanged);
+    }
+
+    let chunks = content.chunks(&ChunkConfig::current());
+    let update = prepare_chunks(chunk_store, doc.id, &chunks)?;
+
+    let tx = chunk_store.transaction()?;
+    apply_chunks(chunk_store, doc.id, &chunks, &update)?;
+    doc_store.update_content(doc.id, &content.title, &content.text)?;
+    tx.commit()?;
+
+    Ok(Refresh::Updated(update.plan))
+}
+
+/// Re-extract a source file if its hash changed since it was added
+pub(crate) async fn refresh_file(
+    path: &Path,
+    doc: &Document,
+    doc_store: &DocumentStore<'_>,
+    chunk_store: &ChunkStore<'_>,
+) -> Result<Refresh> {
+    let hash = ingest::file_hash(path)?;
+
+    match &doc.content_hash {
+        Some(old) if *old == hash => return Ok(Refresh::Unchanged),
+        None => {
+            // Added before hashes were tracked - take the current file as the baseline
+            doc_store.set_content_hash(doc.id, &hash)?;
+            return Ok(Refresh::Unchanged);
+        }
+        Some(_) => {}
+    }
+
+    // Extract, chunk and embed before touching the stored document, so a
+    // file that fails to read leaves the old version in place
+    let content = ingest::extract_from_file_async(path).await?;
+    let chunks = content.chunks(&ChunkConfig::current());
+    let update = prepare_chunks(chunk_store, doc.id, &chunks)?;
+
+    let tx = chunk_store.transaction()?;
+    apply_chunks(chunk_store, doc.id, &chunks, &update)?;
+    doc_store.update_content(doc.id, &doc.filename, &content.text)?;
+    doc_store.set_content_hash(doc.id, &hash)?;
+    tx.commit()?;
+
+    Ok(Refresh::Updated(update.plan))
+}
+
+/// New chunks for a document matched against its current ones, with the
+/// added chunks already embedded
+struct ChunkUpdate {
+    plan: ChunkPlan,
+    /// Embedding for each chunk in `plan.insert`, in order (None if it failed)
+    embeddings: Vec<Option<Vec<f32>>>,
+}
+
+/// Work out how a document's chunks change, embedding only the sections that
+/// changed. Nothing is written yet.
+fn prepare_chunks(
+    chunk_store: &ChunkStore,
+    document_id: i64,
+    chunks: &[Chunk],
+) -> Result<ChunkUpdate> {
+    let old: Vec<(i64, String)> = chunk_store
+        .get_for_document(document_id)?
+        .into_iter()
+        .map(|c| (c.id, c.content))
+        .collect();
+    let new: Vec<String> = chunks.iter().map(|c| c.text.clone()).collect();
+
+    let plan = plan_chunks(&old, &new);
+    let embeddings = plan
+        .insert
+        .iter()
+        .map(|index| chunk_store.embed_text(&chunks[*index].text).ok())
+        .collect();
+
+    Ok(ChunkUpdate { plan, embeddings })
+}
+
+/// Write a prepared update. Unchanged chunks keep their IDs so links to them
+/// stay valid.
+fn apply_chunks(
+    chunk_store: &ChunkStore,
+    document_id: i64,
+    chunks: &[Chunk],
+    update: &ChunkUpdate,
+) -> Result<()> {
+    for id in &update.plan.remove {
+        chunk_store.delete(*id)?;
+    }
+    for (id, index) in &update.plan.keep {
+        chunk_store.set_position(*id, &chunks[*index])?;
+    }
+    for (index, embedding) in update.plan.insert.iter().zip(&update.embeddings) {
+        chunk_store.insert_chunk(document_id, &chunks[*index], embedding.as_deref())?;
+    }
+
+    Ok(())
+}
+
+/// Replace a document's chunks and embed every one of them again, e.g. after
+/// the embedding model changed. Chunks whose text is unchanged keep their IDs.
+/// Everything is embedded first and written in one transaction, so a failure
+/// leaves the old chunks as they were.
+pub(crate) fn reembed_chunks(
+    chunk_store: &ChunkStore,
+    document_id: i64,
+    chunks: &[Chunk],
+) -> Result<ChunkPlan> {
+    let texts: Vec<&str> = chunks.iter().map(|c| c.text.as_str()).collect();
+    let vectors = chunk_store.embed_texts(&texts)?;
+
+    let old: Vec<(i64, String)> = chunk_store
+        .get_for_document(document_id)?
+        .into_iter()
+        .map(|c| (c.id, c.content))
+        .collect();
+    let new: Vec<String> = chunks.iter().map(|c| c.text.clone()).collect();
+
+    let plan = plan_chunks(&old, &new);
+
+    let tx = chunk_store.transaction()?;
+    for id in &plan.remove {
+        chunk_store.delete(*id)?;
+    }
+    for (id, index) in &plan.keep {
+        chunk_store.set_position(*id, &chunks[*index])?;
+        chunk_store.update_embedding(*id, &vectors[*index])?;
+    }
+    for index in &plan.insert {
+        chunk_store.insert_chunk(document_id, &chunks[*index], Some(&vectors[*index]))?;
+    }
+    tx.commit()?;
+
+    Ok(plan)
+}
+
+/// Match new chunks against existing ones by exact content
+fn plan_chunks(old: &[(i64, String)], new: &[String]) -> ChunkPlan {
+    let mut available: HashMap<&str, Vec<i64>> = HashMap::new();
+    for (id, content) in old {
+        available.entry(content.as_str()).or_default().push(*id);
+    }
+
+    let mut plan = ChunkPlan::default();
+    for (index, content) in new.iter().enumerate() {
+        match available
+            .get_mut(content.as_str())
+            .filter(|ids| !ids.is_empty())
+            .map(|ids| ids.remove(0))
+        {
+            Some(id) => plan.keep.push((id, index)),
+            None => plan.insert.push(index),
+        }
+    }
+
+    plan.remove = available.into_values().flatten().collect();
+    plan.remove.sort();
+
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embeddings;
+
+    #[test]
+    fn test_plan_chunks() {
+        let old = vec![
+            (1, "intro".to_string()),
+            (2, "old middle".to_string()),
+            (3, "outro".to_string()),
+        ];
+        let new = vec![
+            "intro".to_string(),
+            "new middle".to_string(),
+            "extra".to_string(),
+            "outro".to_string(),
+        ];
+
+        let plan = plan_chunks(&old, &new);
+        assert_eq!(plan.keep, vec![(1, 0), (3, 3)]);
+        assert_eq!(plan.insert, vec![1, 2]);
+        assert_eq!(plan.remove, vec![2]);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_file_only_when_hash_changes() {
+        embeddings::use_mock_backend();
+        let db = Database::open_in_memory().unwrap();
+        let docs = DocumentStore::new(&db);
+        let chunks = ChunkStore::new(&db);
+        chunks.init_schema().unwrap();
+
+        let path = std::env::temp_dir().join(format!("librarian_sync_{}.txt", std::process::id()));
+        std::fs::write(&path, "First version.").unwrap();
+
+        let id = docs
+            .insert(
+                path.to_str().unwrap(),
+                "notes.txt",
+                "text",
+                "First version.",
+                None,
+            )
+            .unwrap();
+        docs.set_content_hash(id, &ingest::file_hash(&path).unwrap())
+            .unwrap();
+        chunks.insert(id, 0, "First version.", None).unwrap();
+
+        let doc = docs.get(id).unwrap().unwrap();
+        assert!(matches!(
+            refresh_file(&path, &doc, &docs, &chunks).await.unwrap(),
+            Refresh::Unchanged
+        ));
+
+        std::fs::write(&path, "Second version.").unwrap();
+        let result = refresh_file(&path, &doc, &docs, &chunks).await.unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let Refresh::Updated(plan) = result else {
+            panic!("expected the changed file to be re-ingested");
+        };
+        assert_eq!((plan.insert.len(), plan.remove.len()), (1, 1));
+        assert_eq!(docs.get(id).unwrap().unwrap().content, "Second version.");
+    }
+
+    #[tokio::test]
+    async fn test_failed_refresh_keeps_old_version() {
+        embeddings::use_mock_backend();
+        let db = Database::open_in_memory().unwrap();
+        let docs = DocumentStore::new(&db);
+        let chunks = ChunkStore::new(&db);
+        chunks.init_schema().unwrap();
+
+        let path =
+            std::env::temp_dir().join(format!("librarian_sync_broken_{}.pdf", std::process::id()));
+        std::fs::write(&path, "not really a pdf").unwrap();
+
+        let id = docs
+            .insert(
+                path.to_str().unwrap(),
+                "slides.pdf",
+                "pdf",
+                "Slide text.",
+                None,
+            )
+            .unwrap();
+        docs.set_content_hash(id, "hash of the readable version")
+            .unwrap();
+        let chunk = chunks.insert(id, 0, "Slide text.", None).unwrap();
+
+        let doc = docs.get(id).unwrap().unwrap();
+        let result = refresh_file(&path, &doc, &docs, &chunks).await;
+        let _ = std::fs::remove_file(&path);
+
+        assert!(result.is_err());
+        let doc = docs.get(id).unwrap().unwrap();
+        assert_eq!(doc.content, "Slide text.");
+        assert_eq!(
+            doc.content_hash.as_deref(),
+            Some("hash of the readable version")
+        );
+        let kept: Vec<i64> = chunks
+            .get_for_document(id)
+            .unwrap()
+            .iter()
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(kept, vec![chunk]);
+    }
+}
@@ -0,0 +1,90 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use notify_debouncer_mini::notify::RecursiveMode;
+use notify_debouncer_mini::{DebounceEventResult, new_debouncer};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::add::OnDuplicate;
+use crate::ingest::ContentType;
+use crate::storage::{ChunkStore, Database, DocumentStore};
+
+/// Default quiet period before a changed file is ingested
+pub const DEFAULT_DEBOUNCE_SECS: u64 = 2;
+
+/// Watch a folder and ingest files as they are added or modified
+pub async fn run(dir: String, debounce_secs: u64) -> Result<()> {
+    let dir = PathBuf::from(dir);
+    if !dir.is_dir() {
+        anyhow::bail!("Not a directory: {}", dir.display());
+    }
+
+    let db = Database::open()?;
+    let doc_store = DocumentStore::new(&db);
+    let chunk_store = ChunkStore::new(&db);
+    chunk_store.init_schema()?;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut debouncer = new_debouncer(
+        Duration::from_secs(debounce_secs),
+        move |result: DebounceEventResult| {
+            let _ = tx.send(result);
+        },
+    )
+    .context("Failed to start file watcher")?;
+    debouncer
+        .watcher()
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .with_context(|| format!("Failed to watch {}", dir.display()))?;
+
+    println!(
+        "{} Watching {} for new files {}",
+        "👀".cyan(),
+        dir.display().to_string().bold(),
+        "(Ctrl+C to stop)".dimmed()
+    );
+
+    while let Some(result) = rx.recv().await {
+        let events = match result {
+            Ok(events) => events,
+            Err(e) => {
+                println!("{} Watch error: {}", "⚠".yellow(), e);
+                continue;
+            }
+        };
+
+        let mut paths: Vec<PathBuf> = events.into_iter().map(|e| e.path).collect();
+        paths.sort();
+        paths.dedup();
+
+        // add::process_file re-ingests files that are already in the bucket
+        // only when their content hash changed, and links exact duplicates
+        for path in paths.iter().filter(|p| should_ingest(p)) {
+            println!("\n{} {}", "↻".cyan(), path.display());
+            if let Err(e) =
+                super::add::process_file(path, &doc_store, &chunk_store, OnDuplicate::Link).await
+            {
+                println!("{} {}: {}", "✗".red(), path.display(), e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Skip directories, hidden/temp files and anything we can't extract
+fn should_ingest(path: &Path) -> bool {
+    if !path.is_file() {
+        return false;
+    }
+
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if name.starts_with('.') || name.starts_with("~$") {
+        return false;
+    }
+
+    !matches!(ContentType::from_path(path), ContentType::Unknown)
+}
@@ -1,13 +1,193 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Where audio gets transcribed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptionBackend {
+    /// Groq's hosted Whisper API
+    #[default]
+    Groq,
+    /// whisper.cpp on this machine (no API key, audio never leaves the device)
+    Local,
+}
+
+/// How text is read out of images
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageReader {
+    /// Tesseract first, then the vision model if OCR finds nothing
+    #[default]
+    Auto,
+    /// Tesseract only (nothing leaves the machine)
+    Ocr,
+    /// Always describe and transcribe with a Groq vision model
+    Vision,
+}
+
+/// ONNX execution provider the local embedding model runs on. GPU providers
+/// only work when built with the matching cargo feature (`cuda`, `coreml`,
+/// `directml`); otherwise embedding falls back to the CPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingDevice {
+    #[default]
+    Cpu,
+    /// NVIDIA GPUs
+    Cuda,
+    /// Apple Neural Engine / GPU
+    CoreMl,
+    /// Any DirectX 12 GPU on Windows
+    DirectMl,
+}
+
+impl EmbeddingDevice {
+    pub fn name(self) -> &'static str {
+        match self {
+            EmbeddingDevice::Cpu => "CPU",
+            EmbeddingDevice::Cuda => "CUDA",
+            EmbeddingDevice::CoreMl => "CoreML",
+            EmbeddingDevice::DirectMl => "DirectML",
+        }
+    }
+}
+
+/// Cross-encoder that re-scores retrieved chunks against the question before
+/// they're sent to the LLM (downloaded on first use)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Reranker {
+    #[default]
+    Off,
+    /// BAAI bge-reranker-base (English and Chinese)
+    Bge,
+    /// Jina reranker v1 turbo (English, fastest)
+    Jina,
+    /// BAAI bge-reranker-v2-m3 (multilingual, largest)
+    BgeM3,
+}
+
+impl Reranker {
+    pub fn name(self) -> &'static str {
+        match self {
+            Reranker::Off => "Off",
+            Reranker::Bge => "bge-reranker-base",
+            Reranker::Jina => "jina-reranker-v1-turbo-en",
+            Reranker::BgeM3 => "bge-reranker-v2-m3",
+        }
+    }
+}
+
+/// Where chat and generation requests go
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    /// Groq's hosted API (needs an API key)
+    #[default]
+    Groq,
+    /// A local Ollama server (no key, works offline)
+    Ollama,
+    /// Anthropic's Claude models (needs an API key)
+    Anthropic,
+    /// Any OpenAI-compatible server (OpenRouter, vLLM, LM Studio, ...)
+    OpenAi,
+}
+
+impl Provider {
+    pub fn name(self) -> &'static str {
+        match self {
+            Provider::Groq => "Groq",
+            Provider::Ollama => "Ollama",
+            Provider::Anthropic => "Anthropic",
+            Provider::OpenAi => "OpenAI-compatible",
+        }
+    }
+}
+
+/// Credentials sent when fetching pages from a site that needs a login
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SiteAuth {
+    /// Netscape-format cookies.txt exported from a logged-in browser
+    pub cookies_file: Option<PathBuf>,
+    /// Extra request headers, e.g. `Authorization = "Bearer ..."`
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+/// Canvas LMS course pulled in by `librarian add canvas`
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CanvasConfig {
+    /// e.g. "https://canvas.myuni.edu"
+    pub base_url: String,
+    /// Access token from Account → Settings → New Access Token
+    pub token: String,
+    /// The number in the course's URL (/courses/<id>)
+    pub course_id: String,
+}
+
+/// Chat model given for this invocation (e.g. `chat --model`), taking
+/// precedence over the configured default
+static MODEL_OVERRIDE: OnceLock<String> = OnceLock::new();
 
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct Config {
     pub groq_api_key: Option<String>,
     pub default_model: Option<String>,
+    #[serde(default)]
+    pub provider: Provider,
+    /// Ollama server address (default http://localhost:11434)
+    pub ollama_url: Option<String>,
+    /// Ollama model for chat and generation
+    pub ollama_model: Option<String>,
+    pub anthropic_api_key: Option<String>,
+    /// Claude model for chat and generation
+    pub anthropic_model: Option<String>,
+    /// Base URL of an OpenAI-compatible server, e.g. https://openrouter.ai/api/v1
+    pub openai_base_url: Option<String>,
+    pub openai_api_key: Option<String>,
+    /// Model name as the OpenAI-compatible server knows it
+    pub openai_model: Option<String>,
     pub data_dir: Option<PathBuf>,
     pub current_bucket: Option<String>,
+    /// Tesseract languages for OCR, e.g. "eng" or "spa+eng"
+    pub ocr_languages: Option<String>,
+    #[serde(default)]
+    pub transcription_backend: TranscriptionBackend,
+    /// ggml Whisper model used by the local backend
+    pub whisper_model_path: Option<PathBuf>,
+    /// Spoken language of recordings as an ISO-639-1 code, e.g. "es" (unset
+    /// lets Whisper detect it)
+    pub whisper_language: Option<String>,
+    /// Translate recordings to English instead of transcribing them as spoken
+    #[serde(default)]
+    pub whisper_translate: bool,
+    #[serde(default)]
+    pub image_reader: ImageReader,
+    /// Target chunk size in characters
+    pub chunk_size: Option<usize>,
+    /// Characters shared between neighbouring chunks
+    pub chunk_overlap: Option<usize>,
+    #[serde(default)]
+    pub chunk_strategy: crate::ingest::ChunkStrategy,
+    /// Groq model used to describe images
+    pub vision_model: Option<String>,
+    #[serde(default)]
+    pub embedding_device: EmbeddingDevice,
+    #[serde(default)]
+    pub reranker: Reranker,
+    /// Print token counts after each chat answer
+    #[serde(default)]
+    pub show_usage: bool,
+    /// Save quiz questions answered wrong to the review deck without asking
+    #[serde(default)]
+    pub quiz_save_missed: bool,
+    /// Per-domain credentials for fetching URLs, keyed by host (subdomains match)
+    #[serde(default)]
+    pub sites: HashMap<String, SiteAuth>,
+    pub canvas: Option<CanvasConfig>,
 }
 
 impl Config {
@@ -101,6 +281,71 @@ impl Config {
             || std::env::var("GROQ_API_KEY").is_ok()
     }
 
+    /// OCR languages in Tesseract's `-l` format, defaulting to English
+    pub fn ocr_languages(&self) -> String {
+        self.ocr_languages
+            .clone()
+            .filter(|l| !l.trim().is_empty())
+            .unwrap_or_else(|| crate::ingest::ocr::DEFAULT_LANGUAGES.to_string())
+    }
+
+    /// Path of the local Whisper model, defaulting to `<data dir>/models/ggml-base.en.bin`
+    pub fn whisper_model_path(&self) -> Result<PathBuf> {
+        match &self.whisper_model_path {
+            Some(path) => Ok(path.clone()),
+            None => Ok(Self::data_dir()?.join("models").join("ggml-base.en.bin")),
+        }
+    }
+
+    /// Use this chat model for the rest of this run
+    pub fn set_model_override(model: &str) {
+        let _ = MODEL_OVERRIDE.set(model.to_string());
+    }
+
+    /// Model for chat and generation: the per-run override, then the
+    /// configured one for the provider (None leaves the choice to the client)
+    pub fn chat_model(&self) -> Option<String> {
+        let configured = match self.provider {
+            Provider::Groq => &self.default_model,
+            Provider::Ollama => &self.ollama_model,
+            Provider::Anthropic => &self.anthropic_model,
+            Provider::OpenAi => &self.openai_model,
+        };
+        MODEL_OVERRIDE
+            .get()
+            .cloned()
+            .or_else(|| configured.clone().filter(|m| !m.is_empty()))
+    }
+
+    /// Address of the Ollama server
+    pub fn ollama_url(&self) -> String {
+        self.ollama_url
+            .clone()
+            .filter(|u| !u.is_empty())
+            .unwrap_or_else(|| crate::llm::ollama::DEFAULT_URL.to_string())
+    }
+
+    /// Groq vision model for image descriptions
+    pub fn vision_model(&self) -> String {
+        self.vision_model
+            .clone()
+            .filter(|m| !m.is_empty())
+            .unwrap_or_else(|| crate::llm::GroqClient::VISION_MODEL.to_string())
+    }
+
+    /// Credentials configured for a host, matching the most specific domain
+    pub fn site_auth(&self, host: &str) -> Option<&SiteAuth> {
+        let host = host.to_lowercase();
+        self.sites
+            .iter()
+            .filter(|(domain, _)| {
+                let domain = domain.to_lowercase();
+                host == domain || host.ends_with(&format!(".{}", domain))
+            })
+            .max_by_key(|(domain, _)| domain.len())
+            .map(|(_, auth)| auth)
+    }
+
     /// Get the Groq API key, checking environment variable as fallback
     pub fn get_api_key(&self) -> Option<String> {
         self.groq_api_key
@@ -108,4 +353,49 @@ impl Config {
             .filter(|k| !k.is_empty())
             .or_else(|| std::env::var("GROQ_API_KEY").ok())
     }
+
+    /// Get the Anthropic API key, checking environment variable as fallback
+    pub fn anthropic_api_key(&self) -> Option<String> {
+        self.anthropic_api_key
+            .clone()
+            .filter(|k| !k.is_empty())
+            .or_else(|| std::env::var("ANTHROPIC_API_KEY").ok())
+    }
+
+    /// Key for the OpenAI-compatible server, checking environment variable
+    /// as fallback (None for servers that don't need one)
+    pub fn openai_api_key(&self) -> Option<String> {
+        self.openai_api_key
+            .clone()
+            .filter(|k| !k.is_empty())
+            .or_else(|| std::env::var("OPENAI_API_KEY").ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_site_auth_matches_subdomains() {
+        let config: Config = toml::from_str(
+            r#"
+            [sites."example.edu"]
+            headers = { Authorization = "Bearer campus" }
+
+            [sites."canvas.example.edu"]
+            cookies_file = "/tmp/cookies.txt"
+            "#,
+        )
+        .unwrap();
+
+        let canvas = config.site_auth("canvas.example.edu").unwrap();
+        assert!(canvas.cookies_file.is_some());
+        let wiki = config.site_auth("wiki.example.edu").unwrap();
+        assert_eq!(wiki.headers["Authorization"], "Bearer campus");
+        assert!(config.site_auth("notexample.edu").is_none());
+
+        // Tables must serialize after plain values
+        assert!(toml::to_string_pretty(&config).is_ok());
+    }
 }
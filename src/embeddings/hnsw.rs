@@ -0,0 +1,464 @@
+//! Hierarchical navigable small world graph for approximate nearest-neighbour
+//! search over chunk embeddings. Saved to disk next to a bucket's database so
+//! large buckets only add what changed instead of rebuilding on every query.
+
+use anyhow::{Context, Result};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 8] = b"LIBHNSW1";
+
+/// Neighbours linked per node when it is inserted (the bottom layer keeps up
+/// to twice as many)
+const M: usize = 16;
+/// Candidates considered while inserting
+const EF_CONSTRUCTION: usize = 100;
+/// Candidates considered while searching
+const EF_SEARCH: usize = 64;
+const MAX_LEVEL: usize = 16;
+
+pub struct Hnsw {
+    dimensions: usize,
+    /// Chunk ID of each node
+    ids: Vec<i64>,
+    /// Normalized vectors, `dimensions` floats per node
+    vectors: Vec<f32>,
+    /// Each node's neighbours on every layer it is on (index 0 is the bottom)
+    links: Vec<Vec<Vec<u32>>>,
+    /// Nodes whose chunk was removed; they still route searches
+    deleted: Vec<bool>,
+    /// Live node for each chunk ID
+    nodes: HashMap<i64, u32>,
+    entry: Option<u32>,
+    rng: u64,
+}
+
+/// A node and its distance from the query, ordered by distance
+#[derive(Clone, Copy, PartialEq)]
+struct Candidate(f32, u32);
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0).then(self.1.cmp(&other.1))
+    }
+}
+
+impl Hnsw {
+    pub fn new(dimensions: usize) -> Self {
+        Self {
+            dimensions,
+            ids: Vec::new(),
+            vectors: Vec::new(),
+            links: Vec::new(),
+            deleted: Vec::new(),
+            nodes: HashMap::new(),
+            entry: None,
+            rng: 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    /// Chunk IDs currently in the index
+    pub fn ids(&self) -> impl Iterator<Item = i64> + '_ {
+        self.nodes.keys().copied()
+    }
+
+    pub fn contains(&self, id: i64) -> bool {
+        self.nodes.contains_key(&id)
+    }
+
+    /// Share of nodes that only exist for routing (worth rebuilding when high)
+    pub fn deleted_fraction(&self) -> f32 {
+        if self.ids.is_empty() {
+            return 0.0;
+        }
+        (self.ids.len() - self.nodes.len()) as f32 / self.ids.len() as f32
+    }
+
+    /// Add a chunk's vector, replacing any earlier one for the same chunk
+    pub fn insert(&mut self, id: i64, vector: &[f32]) {
+        self.remove(id);
+
+        let node = self.ids.len() as u32;
+        let level = self.random_level();
+        self.ids.push(id);
+        self.vectors.extend(normalized(vector));
+        self.links.push(vec![Vec::new(); level + 1]);
+        self.deleted.push(false);
+        self.nodes.insert(id, node);
+
+        let Some(entry) = self.entry else {
+            self.entry = Some(node);
+            return;
+        };
+
+        let query = self.vector(node).to_vec();
+        let top = self.level_of(entry);
+        let mut entry_points = vec![entry];
+
+        for layer in (level + 1..=top).rev() {
+            let nearest = self.search_layer(&query, &entry_points, 1, layer);
+            entry_points = vec![nearest[0].1];
+        }
+
+        for layer in (0..=level.min(top)).rev() {
+            let found = self.search_layer(&query, &entry_points, EF_CONSTRUCTION, layer);
+            let neighbours: Vec<u32> = found.iter().take(M).map(|c| c.1).collect();
+
+            for &neighbour in &neighbours {
+                self.links[neighbour as usize][layer].push(node);
+                self.prune(neighbour, layer);
+            }
+            self.links[node as usize][layer] = neighbours;
+            entry_points = found.iter().map(|c| c.1).collect();
+        }
+
+        if level > top {
+            self.entry = Some(node);
+        }
+    }
+
+    /// Drop a chunk from search results
+    pub fn remove(&mut self, id: i64) {
+        if let Some(node) = self.nodes.remove(&id) {
+            self.deleted[node as usize] = true;
+        }
+    }
+
+    /// The `k` nearest chunks to the query, most similar first, with their
+    /// cosine similarity
+    pub fn search(&self, query: &[f32], k: usize) -> Vec<(i64, f32)> {
+        let Some(entry) = self.entry else {
+            return Vec::new();
+        };
+        if query.len() != self.dimensions || k == 0 {
+            return Vec::new();
+        }
+
+        let query = normalized(query);
+        let mut entry_points = vec![entry];
+        for layer in (1..=self.level_of(entry)).rev() {
+            let nearest = self.search_layer(&query, &entry_points, 1, layer);
+            entry_points = vec![nearest[0].1];
+        }
+
+        self.search_layer(&query, &entry_points, EF_SEARCH.max(k * 2), 0)
+            .into_iter()
+            .filter(|c| !self.deleted[c.1 as usize])
+            .take(k)
+            .map(|c| (self.ids[c.1 as usize], 1.0 - c.0))
+            .collect()
+    }
+
+    /// Write the index to `path` (via a temporary file, so a crash can't leave
+    /// a half-written index behind)
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("hnsw.tmp");
+        let file = std::fs::File::create(&tmp)
+            .with_context(|| format!("Failed to write vector index: {:?}", tmp))?;
+        let mut out = BufWriter::new(file);
+
+        out.write_all(MAGIC)?;
+        write_u32(&mut out, self.dimensions as u32)?;
+        write_u32(&mut out, self.ids.len() as u32)?;
+        out.write_all(&self.entry.map_or(-1, |e| e as i64).to_le_bytes())?;
+        out.write_all(&self.rng.to_le_bytes())?;
+
+        for node in 0..self.ids.len() {
+            out.write_all(&self.ids[node].to_le_bytes())?;
+            out.write_all(&[self.deleted[node] as u8])?;
+            write_u32(&mut out, self.links[node].len() as u32)?;
+            for neighbours in &self.links[node] {
+                write_u32(&mut out, neighbours.len() as u32)?;
+                for &n in neighbours {
+                    write_u32(&mut out, n)?;
+                }
+            }
+        }
+        for value in &self.vectors {
+            out.write_all(&value.to_le_bytes())?;
+        }
+
+        out.flush()?;
+        drop(out);
+        std::fs::rename(&tmp, path)?;
+
+        Ok(())
+    }
+
+    /// Read an index written by `save`
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("Failed to open vector index: {:?}", path))?;
+        let mut input = BufReader::new(file);
+
+        let mut magic = [0u8; 8];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            anyhow::bail!("Not a vector index: {:?}", path);
+        }
+
+        let dimensions = read_u32(&mut input)? as usize;
+        let count = read_u32(&mut input)? as usize;
+        let entry = read_i64(&mut input)?;
+        let rng = read_u64(&mut input)?;
+
+        let mut index = Self::new(dimensions);
+        index.rng = rng;
+        index.entry = u32::try_from(entry).ok();
+
+        for node in 0..count {
+            let id = read_i64(&mut input)?;
+            let mut deleted = [0u8];
+            input.read_exact(&mut deleted)?;
+
+            let layers = read_u32(&mut input)? as usize;
+            if layers == 0 || layers > MAX_LEVEL + 1 {
+                anyhow::bail!("Corrupt vector index: {:?}", path);
+            }
+            let mut links = Vec::with_capacity(layers);
+            for _ in 0..layers {
+                let len = read_u32(&mut input)? as usize;
+                let neighbours = (0..len)
+                    .map(|_| read_u32(&mut input))
+                    .collect::<Result<Vec<_>>>()?;
+                if neighbours.iter().any(|&n| n as usize >= count) {
+                    anyhow::bail!("Corrupt vector index: {:?}", path);
+                }
+                links.push(neighbours);
+            }
+
+            index.ids.push(id);
+            index.links.push(links);
+            index.deleted.push(deleted[0] != 0);
+            if deleted[0] == 0 {
+                index.nodes.insert(id, node as u32);
+            }
+        }
+
+        let mut bytes = vec![0u8; count * dimensions * 4];
+        input.read_exact(&mut bytes)?;
+        index.vectors = super::bytes_to_embedding(&bytes);
+
+        if index.entry.is_some_and(|e| e as usize >= count) {
+            anyhow::bail!("Corrupt vector index: {:?}", path);
+        }
+
+        Ok(index)
+    }
+
+    fn vector(&self, node: u32) -> &[f32] {
+        let start = node as usize * self.dimensions;
+        &self.vectors[start..start + self.dimensions]
+    }
+
+    fn level_of(&self, node: u32) -> usize {
+        self.links[node as usize].len() - 1
+    }
+
+    fn distance(&self, query: &[f32], node: u32) -> f32 {
+        let dot: f32 = query
+            .iter()
+            .zip(self.vector(node))
+            .map(|(a, b)| a * b)
+            .sum();
+        1.0 - dot
+    }
+
+    /// Best-first search of one layer, returning up to `ef` nodes nearest first
+    fn search_layer(
+        &self,
+        query: &[f32],
+        entry_points: &[u32],
+        ef: usize,
+        layer: usize,
+    ) -> Vec<Candidate> {
+        let mut visited: HashSet<u32> = entry_points.iter().copied().collect();
+        let mut candidates: BinaryHeap<Reverse<Candidate>> = BinaryHeap::new();
+        let mut found: BinaryHeap<Candidate> = BinaryHeap::new();
+
+        for &node in entry_points {
+            let candidate = Candidate(self.distance(query, node), node);
+            candidates.push(Reverse(candidate));
+            found.push(candidate);
+        }
+        while found.len() > ef {
+            found.pop();
+        }
+
+        while let Some(Reverse(closest)) = candidates.pop() {
+            if found.len() >= ef && found.peek().is_some_and(|worst| closest.0 > worst.0) {
+                break;
+            }
+
+            let Some(neighbours) = self.links[closest.1 as usize].get(layer) else {
+                continue;
+            };
+            for &neighbour in neighbours {
+                if !visited.insert(neighbour) {
+                    continue;
+                }
+                let candidate = Candidate(self.distance(query, neighbour), neighbour);
+                if found.len() < ef || found.peek().is_some_and(|worst| candidate.0 < worst.0) {
+                    candidates.push(Reverse(candidate));
+                    found.push(candidate);
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+
+        found.into_sorted_vec()
+    }
+
+    /// Keep only a node's closest neighbours once a layer's list overflows
+    fn prune(&mut self, node: u32, layer: usize) {
+        let max = if layer == 0 { M * 2 } else { M };
+        if self.links[node as usize][layer].len() <= max {
+            return;
+        }
+
+        let base = self.vector(node).to_vec();
+        let mut neighbours: Vec<Candidate> = self.links[node as usize][layer]
+            .iter()
+            .map(|&n| Candidate(self.distance(&base, n), n))
+            .collect();
+        neighbours.sort();
+        self.links[node as usize][layer] = neighbours.into_iter().take(max).map(|c| c.1).collect();
+    }
+
+    /// Layer for a new node: each layer up holds about 1/M as many nodes
+    fn random_level(&mut self) -> usize {
+        // xorshift64*, seeded the same way every time so builds are reproducible
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        let bits = self.rng.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11;
+        let uniform = (bits as f64 + 0.5) / (1u64 << 53) as f64;
+
+        let level = -uniform.ln() / (M as f64).ln();
+        (level as usize).min(MAX_LEVEL)
+    }
+}
+
+fn normalized(vector: &[f32]) -> Vec<f32> {
+    let norm: f32 = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return vector.to_vec();
+    }
+    vector.iter().map(|x| x / norm).collect()
+}
+
+fn write_u32(out: &mut impl Write, value: u32) -> std::io::Result<()> {
+    out.write_all(&value.to_le_bytes())
+}
+
+fn read_u32(input: &mut impl Read) -> Result<u32> {
+    let mut bytes = [0u8; 4];
+    input.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_i64(input: &mut impl Read) -> Result<i64> {
+    let mut bytes = [0u8; 8];
+    input.read_exact(&mut bytes)?;
+    Ok(i64::from_le_bytes(bytes))
+}
+
+fn read_u64(input: &mut impl Read) -> Result<u64> {
+    let mut bytes = [0u8; 8];
+    input.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embeddings::find_similar;
+
+    /// Deterministic pseudo-random vectors
+    fn vectors(count: usize, dimensions: usize) -> Vec<Vec<f32>> {
+        let mut state = 42u64;
+        (0..count)
+            .map(|_| {
+                (0..dimensions)
+                    .map(|_| {
+                        state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+                        ((state >> 33) as f32 / u32::MAX as f32) - 0.25
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_search_matches_brute_force() {
+        let data = vectors(600, 32);
+        let mut index = Hnsw::new(32);
+        for (i, v) in data.iter().enumerate() {
+            index.insert(i as i64, v);
+        }
+
+        let all: Vec<(i64, Vec<f32>)> = data
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i as i64, v.clone()))
+            .collect();
+
+        let mut hits = 0;
+        for query in data.iter().step_by(30) {
+            let exact: HashSet<i64> = find_similar(query, &all, 10)
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect();
+            hits += index
+                .search(query, 10)
+                .iter()
+                .filter(|(id, _)| exact.contains(id))
+                .count();
+        }
+        // 20 queries x 10 results
+        assert!(hits >= 190, "recall too low: {}/200", hits);
+    }
+
+    #[test]
+    fn test_remove_save_and_load() {
+        let data = vectors(200, 16);
+        let mut index = Hnsw::new(16);
+        for (i, v) in data.iter().enumerate() {
+            index.insert(i as i64, v);
+        }
+
+        assert_eq!(index.search(&data[7], 1)[0].0, 7);
+        index.remove(7);
+        assert!(index.search(&data[7], 5).iter().all(|(id, _)| *id != 7));
+
+        // Re-inserting replaces the vector
+        index.insert(8, &data[7]);
+        assert_eq!(index.search(&data[7], 1)[0].0, 8);
+
+        let path = std::env::temp_dir().join(format!("librarian_hnsw_{}.hnsw", std::process::id()));
+        index.save(&path).unwrap();
+        let loaded = Hnsw::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(loaded.dimensions(), 16);
+        assert!(!loaded.contains(7));
+        assert_eq!(loaded.search(&data[42], 5), index.search(&data[42], 5));
+        assert!(loaded.deleted_fraction() > 0.0);
+    }
+}
@@ -0,0 +1,49 @@
+//! Deterministic stand-in for the local embedding model.
+//!
+//! Each word is hashed into one of `DIMENSIONS` buckets, so texts that share
+//! words get a high cosine similarity - close enough to exercise retrieval
+//! without downloading the ONNX model.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use super::DIMENSIONS;
+
+pub fn embed(text: &str) -> Vec<f32> {
+    let mut vector = vec![0.0f32; DIMENSIONS];
+
+    for word in text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        let mut hasher = DefaultHasher::new();
+        word.to_lowercase().hash(&mut hasher);
+        vector[(hasher.finish() % DIMENSIONS as u64) as usize] += 1.0;
+    }
+
+    let norm: f32 = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for x in &mut vector {
+            *x /= norm;
+        }
+    }
+
+    vector
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embeddings::cosine_similarity;
+
+    #[test]
+    fn test_mock_embeddings_are_deterministic_and_similar() {
+        let a = embed("Photosynthesis happens in the chloroplast");
+        let b = embed("where does photosynthesis happen? the chloroplast");
+        let c = embed("The French Revolution began in 1789");
+
+        assert_eq!(a.len(), DIMENSIONS);
+        assert_eq!(a, embed("Photosynthesis happens in the chloroplast"));
+        assert!(cosine_similarity(&a, &b) > cosine_similarity(&a, &c));
+    }
+}
@@ -1,10 +1,47 @@
+pub mod hnsw;
+mod mock;
+pub mod rerank;
+
 use anyhow::{Context, Result};
-use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
+use fastembed::{EmbeddingModel, ExecutionProviderDispatch, InitOptions, TextEmbedding};
+use ort::execution_providers::{
+    CUDAExecutionProvider, CoreMLExecutionProvider, DirectMLExecutionProvider, ExecutionProvider,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Mutex, OnceLock};
+use tokenizers::Tokenizer;
+
+use crate::config::{Config, EmbeddingDevice};
+
+/// Size of the vectors the embedding model produces (AllMiniLML6V2)
+pub const DIMENSIONS: usize = 384;
 
 /// Global embedding model instance (loaded once)
 static EMBEDDING_MODEL: OnceLock<Mutex<TextEmbedding>> = OnceLock::new();
 
+/// The embedding model's tokenizer, set up for counting (None if the model
+/// couldn't be loaded)
+static TOKENIZER: OnceLock<Option<Tokenizer>> = OnceLock::new();
+
+/// When set, embeddings come from the deterministic mock backend
+static USE_MOCK: AtomicBool = AtomicBool::new(false);
+
+/// Switch to the mock embedding backend (no model download, deterministic
+/// output). Used by tests and when `LIBRARIAN_MOCK_LLM` is set.
+pub fn use_mock_backend() {
+    USE_MOCK.store(true, Ordering::Relaxed);
+}
+
+/// Name of the model embeddings currently come from, so stored vectors can
+/// tell which model produced them
+pub fn model_id() -> &'static str {
+    if USE_MOCK.load(Ordering::Relaxed) {
+        "mock"
+    } else {
+        "all-MiniLM-L6-v2"
+    }
+}
+
 /// Get or initialize the embedding model
 fn get_model() -> Result<&'static Mutex<TextEmbedding>> {
     if let Some(model) = EMBEDDING_MODEL.get() {
@@ -12,8 +49,9 @@ fn get_model() -> Result<&'static Mutex<TextEmbedding>> {
     }
 
     // Initialize the model
-    let model = TextEmbedding::try_new(InitOptions::new(EmbeddingModel::AllMiniLML6V2))
-        .context("Failed to initialize embedding model")?;
+    let options = InitOptions::new(EmbeddingModel::AllMiniLML6V2)
+        .with_execution_providers(execution_providers());
+    let model = TextEmbedding::try_new(options).context("Failed to initialize embedding model")?;
 
     // Try to set it (another thread might have beat us)
     let _ = EMBEDDING_MODEL.set(Mutex::new(model));
@@ -23,8 +61,64 @@ fn get_model() -> Result<&'static Mutex<TextEmbedding>> {
         .context("Failed to get embedding model")
 }
 
+/// The embedding model's subword tokenizer without its padding and 256-token
+/// truncation, for counting tokens in whole prompts. None with the mock
+/// backend or when the model can't be loaded.
+pub fn tokenizer() -> Option<&'static Tokenizer> {
+    if USE_MOCK.load(Ordering::Relaxed) {
+        return None;
+    }
+    TOKENIZER
+        .get_or_init(|| {
+            let model = get_model().ok()?.lock().ok()?;
+            let mut tokenizer = model.tokenizer.clone();
+            tokenizer.with_padding(None);
+            tokenizer.with_truncation(None).ok()?;
+            Some(tokenizer)
+        })
+        .as_ref()
+}
+
+/// The configured GPU provider, if this build and machine can use it. ONNX
+/// Runtime always appends the CPU provider, which runs on every core.
+fn execution_providers() -> Vec<ExecutionProviderDispatch> {
+    let device = Config::load()
+        .map(|c| c.embedding_device)
+        .unwrap_or_default();
+
+    let (available, provider) = match device {
+        EmbeddingDevice::Cpu => return Vec::new(),
+        EmbeddingDevice::Cuda => {
+            let ep = CUDAExecutionProvider::default();
+            (ep.is_available(), ep.build())
+        }
+        EmbeddingDevice::CoreMl => {
+            let ep = CoreMLExecutionProvider::default();
+            (ep.is_available(), ep.build())
+        }
+        EmbeddingDevice::DirectMl => {
+            let ep = DirectMLExecutionProvider::default();
+            (ep.is_available(), ep.build())
+        }
+    };
+
+    if available.unwrap_or(false) {
+        vec![provider]
+    } else {
+        eprintln!(
+            "Note: {} isn't available in this build or on this machine; embedding on the CPU",
+            device.name()
+        );
+        Vec::new()
+    }
+}
+
 /// Generate embeddings for a list of texts
 pub fn embed_texts(texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+    if USE_MOCK.load(Ordering::Relaxed) {
+        return Ok(texts.iter().map(|t| mock::embed(t)).collect());
+    }
+
     let model = get_model()?;
     let model = model
         .lock()
@@ -64,6 +158,7 @@ pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
 }
 
 /// Find the most similar texts given a query embedding
+#[allow(dead_code)]
 pub fn find_similar(
     query_embedding: &[f32],
     embeddings: &[(i64, Vec<f32>)], // (id, embedding)
@@ -0,0 +1,70 @@
+//! Cross-encoder reranking: score each passage against the query jointly,
+//! which ranks far better than comparing separately computed embeddings.
+
+use anyhow::{Context, Result};
+use fastembed::{RerankInitOptions, RerankerModel, TextRerank};
+use std::sync::atomic::Ordering;
+use std::sync::{Mutex, OnceLock};
+
+use super::{USE_MOCK, cosine_similarity, execution_providers, mock};
+use crate::config::{Config, Reranker};
+
+/// Reranker model instance (loaded once, for the reranker configured then)
+static RERANK_MODEL: OnceLock<Mutex<TextRerank>> = OnceLock::new();
+
+/// The reranker chosen in the config, if any
+pub fn configured() -> Option<Reranker> {
+    let reranker = Config::load().map(|c| c.reranker).unwrap_or_default();
+    (reranker != Reranker::Off).then_some(reranker)
+}
+
+fn get_model(reranker: Reranker) -> Result<&'static Mutex<TextRerank>> {
+    if let Some(model) = RERANK_MODEL.get() {
+        return Ok(model);
+    }
+
+    let model_name = match reranker {
+        Reranker::Jina => RerankerModel::JINARerankerV1TurboEn,
+        Reranker::BgeM3 => RerankerModel::BGERerankerV2M3,
+        Reranker::Bge | Reranker::Off => RerankerModel::BGERerankerBase,
+    };
+    let options =
+        RerankInitOptions::new(model_name).with_execution_providers(execution_providers());
+    let model = TextRerank::try_new(options).context("Failed to initialize reranker model")?;
+
+    let _ = RERANK_MODEL.set(Mutex::new(model));
+
+    RERANK_MODEL.get().context("Failed to get reranker model")
+}
+
+/// Relevance of each passage to the query, in the order given (higher is
+/// more relevant; the scale depends on the model)
+pub fn scores(reranker: Reranker, query: &str, passages: &[&str]) -> Result<Vec<f32>> {
+    if passages.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    if USE_MOCK.load(Ordering::Relaxed) {
+        let query = mock::embed(query);
+        return Ok(passages
+            .iter()
+            .map(|p| cosine_similarity(&query, &mock::embed(p)))
+            .collect());
+    }
+
+    let model = get_model(reranker)?;
+    let model = model
+        .lock()
+        .map_err(|_| anyhow::anyhow!("Failed to lock reranker model"))?;
+
+    let results = model
+        .rerank(query, passages.to_vec(), false, None)
+        .context("Failed to rerank passages")?;
+
+    let mut scores = vec![f32::MIN; passages.len()];
+    for result in results {
+        scores[result.index] = result.score;
+    }
+
+    Ok(scores)
+}
@@ -0,0 +1,349 @@
+//! Converts generated markdown into a standalone HTML page (inline styles and
+//! a table of contents) that opens in any browser and can be sent as a file.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use termimad::minimad::{self, Composite, CompositeStyle, Compound, Line};
+
+const STYLESHEET: &str = r#"
+body { font: 16px/1.6 -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; color: #222;
+       max-width: 46em; margin: 2em auto; padding: 0 1.2em; }
+h1, h2, h3, h4 { line-height: 1.25; margin: 1.6em 0 0.5em; }
+h1 { font-size: 2em; border-bottom: 2px solid #eee; padding-bottom: 0.3em; }
+h2 { font-size: 1.5em; border-bottom: 1px solid #eee; padding-bottom: 0.2em; }
+code { font: 0.9em/1.4 "SFMono-Regular", Menlo, Consolas, monospace; background: #f3f3f3;
+       padding: 0.1em 0.3em; border-radius: 3px; }
+pre { background: #f6f8fa; padding: 0.8em 1em; border-radius: 6px; overflow-x: auto; }
+pre code { background: none; padding: 0; }
+blockquote { margin: 1em 0; padding: 0.2em 1em; color: #555; border-left: 4px solid #ddd; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { border: 1px solid #ddd; padding: 0.4em 0.8em; text-align: left; }
+th { background: #f6f8fa; }
+nav.toc { background: #fafafa; border: 1px solid #eee; border-radius: 6px; padding: 0.6em 1.2em; }
+nav.toc ul { list-style: none; padding-left: 0; margin: 0.4em 0; }
+nav.toc li.level-3 { padding-left: 1.2em; }
+nav.toc a { color: #0366d6; text-decoration: none; }
+footer { margin-top: 3em; color: #999; font-size: 0.85em; }
+@media print { nav.toc { display: none; } }
+"#;
+
+/// Escape text for HTML
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Anchor id for a heading ("Key Terms & Ideas" -> "key-terms-ideas")
+fn slug(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "section".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+fn inline(compounds: &[Compound]) -> String {
+    let mut html = String::new();
+    for compound in compounds {
+        let mut text = escape(compound.src);
+        if compound.code {
+            text = format!("<code>{}</code>", text);
+        }
+        if compound.italic {
+            text = format!("<em>{}</em>", text);
+        }
+        if compound.bold {
+            text = format!("<strong>{}</strong>", text);
+        }
+        if compound.strikeout {
+            text = format!("<del>{}</del>", text);
+        }
+        html.push_str(&text);
+    }
+    html
+}
+
+fn plain(compounds: &[Compound]) -> String {
+    compounds.iter().map(|c| c.src).collect()
+}
+
+/// Numbered list prefix of a paragraph's first compound
+fn number_prefix(composite: &Composite) -> Option<usize> {
+    crate::render::number_prefix(composite.compounds.first()?.src)
+}
+
+/// The block being built, closed when a line of another kind arrives
+#[derive(Debug, PartialEq)]
+enum Block {
+    None,
+    Paragraph,
+    /// Open list tags, outermost first (true for `<ol>`)
+    List(Vec<bool>),
+    Code,
+    Quote,
+    Table,
+}
+
+struct Writer {
+    body: String,
+    block: Block,
+    /// (level, id, text) of each heading, for the table of contents
+    headings: Vec<(u8, String, String)>,
+}
+
+impl Writer {
+    fn close(&mut self) {
+        match std::mem::replace(&mut self.block, Block::None) {
+            Block::None => {}
+            Block::Paragraph => self.body.push_str("</p>\n"),
+            Block::List(open) => {
+                for ordered in open.into_iter().rev() {
+                    self.body.push_str(if ordered {
+                        "</li></ol>\n"
+                    } else {
+                        "</li></ul>\n"
+                    });
+                }
+            }
+            Block::Code => self.body.push_str("</code></pre>\n"),
+            Block::Quote => self.body.push_str("</blockquote>\n"),
+            Block::Table => self.body.push_str("</table>\n"),
+        }
+    }
+
+    /// Switch to `block` unless it's already open
+    fn enter(&mut self, block: Block, open_tag: &str) -> bool {
+        if self.block == block {
+            return false;
+        }
+        self.close();
+        self.body.push_str(open_tag);
+        self.block = block;
+        true
+    }
+
+    fn heading(&mut self, level: u8, compounds: &[Compound]) {
+        self.close();
+        let text = plain(compounds);
+        let base = slug(&text);
+        let mut id = base.clone();
+        let mut n = 1;
+        while self.headings.iter().any(|(_, i, _)| *i == id) {
+            n += 1;
+            id = format!("{}-{}", base, n);
+        }
+        self.body.push_str(&format!(
+            "<h{level} id=\"{id}\">{}</h{level}>\n",
+            inline(compounds)
+        ));
+        self.headings.push((level, id, text));
+    }
+
+    fn list_item(&mut self, depth: usize, ordered: bool, compounds: &[Compound]) {
+        let mut open = match std::mem::replace(&mut self.block, Block::None) {
+            Block::List(open) => open,
+            other => {
+                self.block = other;
+                self.close();
+                Vec::new()
+            }
+        };
+
+        // Close deeper lists, or a list of the other kind at this depth
+        while open.len() > depth + 1 || (open.len() == depth + 1 && open[depth] != ordered) {
+            let closed = open.pop().unwrap_or_default();
+            self.body.push_str(if closed {
+                "</li></ol>\n"
+            } else {
+                "</li></ul>\n"
+            });
+        }
+        if open.len() == depth + 1 {
+            self.body.push_str("</li>\n");
+        }
+        while open.len() < depth + 1 {
+            self.body
+                .push_str(if ordered { "<ol>\n" } else { "<ul>\n" });
+            open.push(ordered);
+        }
+
+        self.body.push_str(&format!("<li>{}", inline(compounds)));
+        self.block = Block::List(open);
+    }
+
+    fn table_row(&mut self, cells: &[Composite], header: bool) {
+        self.enter(Block::Table, "<table>\n");
+        let tag = if header { "th" } else { "td" };
+        self.body.push_str("<tr>");
+        for cell in cells {
+            self.body
+                .push_str(&format!("<{tag}>{}</{tag}>", inline(&cell.compounds)));
+        }
+        self.body.push_str("</tr>\n");
+    }
+}
+
+/// Render markdown as a standalone HTML page titled `title`
+pub fn markdown_to_html(markdown: &str, title: &str) -> String {
+    let markdown = crate::render::normalize_lists(markdown);
+    let text = minimad::parse_text(&markdown, minimad::Options::default());
+    let mut writer = Writer {
+        body: String::new(),
+        block: Block::None,
+        headings: Vec::new(),
+    };
+
+    for (i, line) in text.lines.iter().enumerate() {
+        match line {
+            Line::Normal(composite) => match composite.style {
+                CompositeStyle::Paragraph if composite.compounds.is_empty() => writer.close(),
+                CompositeStyle::Paragraph => match number_prefix(composite) {
+                    Some(prefix_len) => {
+                        let mut compounds = composite.compounds.clone();
+                        compounds[0].src = &compounds[0].src[prefix_len..];
+                        writer.list_item(0, true, &compounds);
+                    }
+                    None => {
+                        if !writer.enter(Block::Paragraph, "<p>") {
+                            writer.body.push('\n');
+                        }
+                        writer.body.push_str(&inline(&composite.compounds));
+                    }
+                },
+                CompositeStyle::Header(level) => writer.heading(level, &composite.compounds),
+                CompositeStyle::ListItem(depth) => {
+                    writer.list_item(usize::from(depth), false, &composite.compounds)
+                }
+                CompositeStyle::Quote => {
+                    if !writer.enter(Block::Quote, "<blockquote>") {
+                        writer.body.push_str("<br>\n");
+                    }
+                    writer.body.push_str(&inline(&composite.compounds));
+                }
+                CompositeStyle::Code => {
+                    if !writer.enter(Block::Code, "<pre><code>") {
+                        writer.body.push('\n');
+                    }
+                    writer.body.push_str(&escape(&plain(&composite.compounds)));
+                }
+            },
+            Line::TableRow(row) => {
+                // A row followed by a rule is the header
+                let header = matches!(text.lines.get(i + 1), Some(Line::TableRule(_)));
+                writer.table_row(&row.cells, header);
+            }
+            Line::TableRule(_) | Line::CodeFence(_) => {}
+            Line::HorizontalRule => {
+                writer.close();
+                writer.body.push_str("<hr>\n");
+            }
+        }
+    }
+    writer.close();
+
+    page(title, &writer.headings, &writer.body)
+}
+
+/// Wrap the body in a page, with a table of contents when there are enough
+/// sections to need one
+fn page(title: &str, headings: &[(u8, String, String)], body: &str) -> String {
+    // The title heading doesn't need a link to itself
+    let entries: Vec<&(u8, String, String)> = headings
+        .iter()
+        .filter(|(level, _, _)| matches!(level, 2 | 3))
+        .collect();
+
+    let mut toc = String::new();
+    if entries.len() >= 3 {
+        toc.push_str("<nav class=\"toc\">\n<strong>Contents</strong>\n<ul>\n");
+        for (level, id, text) in entries {
+            toc.push_str(&format!(
+                "<li class=\"level-{}\"><a href=\"#{}\">{}</a></li>\n",
+                level,
+                id,
+                escape(text)
+            ));
+        }
+        toc.push_str("</ul>\n</nav>\n");
+    }
+
+    // Put the contents after the title heading when the page starts with one
+    let (head, rest) = match body.strip_prefix("<h1") {
+        Some(_) => body.split_at(body.find('\n').map_or(0, |i| i + 1)),
+        None => ("", body),
+    };
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}{}{}\
+         <footer>Made with The Librarian</footer>\n</body>\n</html>\n",
+        escape(title),
+        STYLESHEET,
+        head,
+        toc,
+        rest
+    )
+}
+
+/// Write markdown to `path` as a standalone HTML page
+pub fn write_markdown(markdown: &str, title: &str, path: &Path) -> Result<()> {
+    std::fs::write(path, markdown_to_html(markdown, title))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_to_html() {
+        let markdown = "# Cell Biology\n\n\
+                        ## Energy\n\nATP is the **energy** currency.\nIt is made in <mitochondria>.\n\n\
+                        ## Key Terms\n\n- Ribosome\n  - Makes proteins\n- Nucleus\n\n\
+                        ## Steps\n\n1. Glycolysis\n2. Krebs cycle\n\n\
+                        ```\nlet x = a < b;\n```\n\n\
+                        | Term | Meaning |\n|---|---|\n| ATP | energy |\n";
+        let html = markdown_to_html(markdown, "Study Guide: cells");
+
+        assert!(html.contains("<title>Study Guide: cells</title>"));
+        assert!(html.contains("<h2 id=\"key-terms\">Key Terms</h2>"));
+        assert!(html.contains("<a href=\"#energy\">Energy</a>"));
+        // Contents come right after the title
+        assert!(html.find("<h1").unwrap() < html.find("<nav").unwrap());
+        assert!(html.contains(
+            "<p>ATP is the <strong>energy</strong> currency.\nIt is made in &lt;mitochondria&gt;.</p>"
+        ));
+        assert!(
+            html.contains("<li>Ribosome<ul>\n<li>Makes proteins</li></ul>\n</li>\n<li>Nucleus")
+        );
+        assert!(html.contains("<ol>\n<li>Glycolysis</li>\n<li>Krebs cycle</li></ol>"));
+        assert!(html.contains("<pre><code>let x = a &lt; b;</code></pre>"));
+        assert!(html.contains("<tr><th>Term</th><th>Meaning</th></tr>"));
+        assert!(html.contains("<tr><td>ATP</td><td>energy</td></tr>"));
+    }
+
+    #[test]
+    fn test_slug() {
+        assert_eq!(slug("Key Terms & Ideas"), "key-terms-ideas");
+        assert_eq!(slug("???"), "section");
+    }
+}
@@ -0,0 +1,264 @@
+//! arXiv papers by ID, using the arXiv export API for metadata
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use super::url::USER_AGENT;
+
+const API_URL: &str = "https://export.arxiv.org/api/query";
+
+const ATOM_NS: &str = "http://www.w3.org/2005/Atom";
+const ARXIV_NS: &str = "http://arxiv.org/schemas/atom";
+
+/// Metadata for a paper
+#[derive(Debug, Clone, Default)]
+pub struct ArxivPaper {
+    /// e.g. "2301.12345" or "hep-th/9901001" (with a version if one was asked for)
+    pub id: String,
+    pub title: String,
+    pub authors: Vec<String>,
+    pub summary: String,
+    pub year: Option<i32>,
+    pub doi: Option<String>,
+    pub journal_ref: Option<String>,
+}
+
+impl ArxivPaper {
+    /// Abstract page, used as the document's source
+    pub fn abs_url(&self) -> String {
+        format!("https://arxiv.org/abs/{}", self.id)
+    }
+
+    pub fn pdf_url(&self) -> String {
+        format!("https://arxiv.org/pdf/{}", self.id)
+    }
+
+    /// Title, authors and abstract, for when the PDF can't be read
+    pub fn abstract_text(&self) -> String {
+        format!(
+            "{}\n\n{}\n\nAbstract\n{}",
+            self.title,
+            self.authors.join(", "),
+            self.summary
+        )
+    }
+}
+
+/// Recognize `arxiv:2301.12345` or an arxiv.org abs/pdf link, returning the ID
+pub fn parse_id(source: &str) -> Option<String> {
+    let source = source.trim();
+
+    let id = if let Some((scheme, rest)) = source.split_once(':')
+        && scheme.eq_ignore_ascii_case("arxiv")
+    {
+        rest
+    } else {
+        let rest = source
+            .strip_prefix("https://")
+            .or_else(|| source.strip_prefix("http://"))?;
+        let rest = rest.strip_prefix("www.").unwrap_or(rest);
+        let path = rest.strip_prefix("arxiv.org/")?;
+        let id = path
+            .strip_prefix("abs/")
+            .or_else(|| path.strip_prefix("pdf/"))?;
+        let id = id.split(['?', '#']).next().unwrap_or(id);
+        id.strip_suffix(".pdf").unwrap_or(id)
+    };
+
+    let id = id.trim().trim_end_matches('/');
+    is_valid_id(id).then(|| id.to_string())
+}
+
+/// New-style `YYMM.NNNNN[vN]` or old-style `archive[.SUB]/YYMMNNN[vN]`
+fn is_valid_id(id: &str) -> bool {
+    let base = match id.rsplit_once('v') {
+        Some((base, version))
+            if !version.is_empty() && version.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            base
+        }
+        _ => id,
+    };
+
+    if let Some((archive, number)) = base.split_once('/') {
+        return !archive.is_empty()
+            && archive
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c == '-' || c == '.')
+            && number.len() == 7
+            && number.chars().all(|c| c.is_ascii_digit());
+    }
+
+    match base.split_once('.') {
+        Some((yymm, number)) => {
+            yymm.len() == 4
+                && yymm.chars().all(|c| c.is_ascii_digit())
+                && (4..=5).contains(&number.len())
+                && number.chars().all(|c| c.is_ascii_digit())
+        }
+        None => false,
+    }
+}
+
+/// Look up a paper's title, authors and abstract
+pub async fn fetch_metadata(id: &str) -> Result<ArxivPaper> {
+    let response = client()?
+        .get(API_URL)
+        .query(&[("id_list", id)])
+        .send()
+        .await
+        .context("Failed to reach arXiv")?;
+
+    if !response.status().is_success() {
+        anyhow::bail!("arXiv API returned HTTP {}", response.status());
+    }
+
+    let feed = response
+        .text()
+        .await
+        .context("Failed to read arXiv response")?;
+    let mut paper = parse_feed(&feed)?.with_context(|| format!("No arXiv paper with ID {}", id))?;
+    // Keep the ID as asked for, so a pinned version downloads that version
+    paper.id = id.to_string();
+
+    Ok(paper)
+}
+
+/// Download the paper's PDF into `dir`
+pub async fn download_pdf(paper: &ArxivPaper, dir: &Path) -> Result<PathBuf> {
+    let response = client()?
+        .get(paper.pdf_url())
+        .send()
+        .await
+        .context("Failed to download PDF from arXiv")?;
+
+    if !response.status().is_success() {
+        anyhow::bail!("arXiv returned HTTP {} for the PDF", response.status());
+    }
+
+    let bytes = response.bytes().await.context("Failed to download PDF")?;
+    if !bytes.starts_with(b"%PDF") {
+        anyhow::bail!("arXiv didn't return a PDF for {}", paper.id);
+    }
+
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}.pdf", paper.id.replace('/', "_")));
+    std::fs::write(&path, &bytes)?;
+
+    Ok(path)
+}
+
+fn client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(std::time::Duration::from_secs(60))
+        .build()?)
+}
+
+/// Read the first entry of an arXiv API Atom feed
+fn parse_feed(xml: &str) -> Result<Option<ArxivPaper>> {
+    let doc = roxmltree::Document::parse(xml).context("Invalid arXiv API response")?;
+
+    let Some(entry) = doc
+        .root_element()
+        .children()
+        .find(|n| n.has_tag_name((ATOM_NS, "entry")))
+    else {
+        return Ok(None);
+    };
+
+    let text = |ns: &str, name: &str| {
+        entry
+            .children()
+            .find(|n| n.has_tag_name((ns, name)))
+            .and_then(|n| n.text())
+            .map(|t| t.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|t| !t.is_empty())
+    };
+
+    // Unknown IDs come back as an entry titled "Error"
+    let Some(title) = text(ATOM_NS, "title").filter(|t| t != "Error") else {
+        return Ok(None);
+    };
+
+    let authors = entry
+        .children()
+        .filter(|n| n.has_tag_name((ATOM_NS, "author")))
+        .filter_map(|author| {
+            author
+                .children()
+                .find(|n| n.has_tag_name((ATOM_NS, "name")))
+                .and_then(|n| n.text())
+                .map(|name| name.trim().to_string())
+        })
+        .collect();
+
+    let year = text(ATOM_NS, "published").and_then(|date| date.get(..4)?.parse().ok());
+
+    Ok(Some(ArxivPaper {
+        id: String::new(),
+        title,
+        authors,
+        summary: text(ATOM_NS, "summary").unwrap_or_default(),
+        year,
+        doi: text(ARXIV_NS, "doi"),
+        journal_ref: text(ARXIV_NS, "journal_ref"),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_id() {
+        assert_eq!(parse_id("arxiv:2301.12345").as_deref(), Some("2301.12345"));
+        assert_eq!(
+            parse_id("arXiv:1706.03762v7").as_deref(),
+            Some("1706.03762v7")
+        );
+        assert_eq!(
+            parse_id("https://arxiv.org/abs/hep-th/9901001").as_deref(),
+            Some("hep-th/9901001")
+        );
+        assert_eq!(
+            parse_id("https://arxiv.org/pdf/2301.12345v2.pdf").as_deref(),
+            Some("2301.12345v2")
+        );
+        assert!(parse_id("arxiv:not-an-id").is_none());
+        assert!(parse_id("https://example.com/abs/2301.12345").is_none());
+        assert!(parse_id("notes/2301.12345.md").is_none());
+    }
+
+    #[test]
+    fn test_parse_feed() {
+        let feed = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom" xmlns:arxiv="http://arxiv.org/schemas/atom">
+  <title>ArXiv Query</title>
+  <entry>
+    <id>http://arxiv.org/abs/1706.03762v7</id>
+    <published>2017-06-12T17:57:34Z</published>
+    <title>Attention Is All
+      You Need</title>
+    <summary>  The dominant sequence transduction models...  </summary>
+    <author><name>Ashish Vaswani</name></author>
+    <author><name>Noam Shazeer</name></author>
+    <arxiv:doi>10.48550/arXiv.1706.03762</arxiv:doi>
+  </entry>
+</feed>"#;
+
+        let paper = parse_feed(feed).unwrap().unwrap();
+        assert_eq!(paper.title, "Attention Is All You Need");
+        assert_eq!(paper.authors, vec!["Ashish Vaswani", "Noam Shazeer"]);
+        assert_eq!(paper.year, Some(2017));
+        assert_eq!(
+            paper.summary,
+            "The dominant sequence transduction models..."
+        );
+        assert_eq!(paper.doi.as_deref(), Some("10.48550/arXiv.1706.03762"));
+        assert!(paper.journal_ref.is_none());
+
+        let error = r#"<feed xmlns="http://www.w3.org/2005/Atom"><entry><title>Error</title></entry></feed>"#;
+        assert!(parse_feed(error).unwrap().is_none());
+    }
+}
@@ -0,0 +1,269 @@
+//! Course content from Canvas LMS via its REST API
+
+use anyhow::{Context, Result};
+use scraper::Html;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::path::{Path, PathBuf};
+
+use super::url::{USER_AGENT, clean_text, extract_text_from_element};
+use crate::config::CanvasConfig;
+
+/// Largest page size Canvas allows
+const PER_PAGE: &str = "100";
+
+pub struct CanvasClient {
+    client: reqwest::Client,
+    base_url: String,
+    token: String,
+    pub course_id: String,
+}
+
+/// An announcement or wiki page, already converted to text
+#[derive(Debug, Clone)]
+pub struct CanvasDocument {
+    /// Link to the item in Canvas, used as the document's source
+    pub source: String,
+    pub title: String,
+    pub text: String,
+}
+
+/// A wiki page as listed (the body is fetched separately)
+#[derive(Debug, Clone, Deserialize)]
+pub struct CanvasPage {
+    /// Page slug
+    url: String,
+    pub title: String,
+    pub html_url: String,
+}
+
+/// An uploaded course file
+#[derive(Debug, Clone, Deserialize)]
+pub struct CanvasFile {
+    pub id: i64,
+    pub display_name: String,
+    /// Authenticated download link
+    url: String,
+    #[serde(default)]
+    pub locked_for_user: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct Announcement {
+    title: String,
+    message: Option<String>,
+    html_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PageBody {
+    body: Option<String>,
+}
+
+impl CanvasClient {
+    pub fn new(config: &CanvasConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(std::time::Duration::from_secs(60))
+            .build()?;
+
+        Ok(Self {
+            client,
+            base_url: config.base_url.trim_end_matches('/').to_string(),
+            token: config.token.clone(),
+            course_id: config.course_id.clone(),
+        })
+    }
+
+    /// Stable link for a file, used as its document source (the download URL
+    /// carries a short-lived token)
+    pub fn file_source(&self, file: &CanvasFile) -> String {
+        format!(
+            "{}/courses/{}/files/{}",
+            self.base_url, self.course_id, file.id
+        )
+    }
+
+    /// All announcements posted in the course
+    pub async fn announcements(&self) -> Result<Vec<CanvasDocument>> {
+        let context = format!("course_{}", self.course_id);
+        let announcements: Vec<Announcement> = self
+            .get_all(
+                "/api/v1/announcements",
+                &[
+                    ("context_codes[]", context.as_str()),
+                    ("start_date", "2000-01-01"),
+                ],
+            )
+            .await
+            .context("Failed to list announcements")?;
+
+        Ok(announcements
+            .into_iter()
+            .map(|a| CanvasDocument {
+                source: a.html_url,
+                text: html_to_text(a.message.as_deref().unwrap_or("")),
+                title: a.title,
+            })
+            .collect())
+    }
+
+    /// Published wiki pages in the course
+    pub async fn pages(&self) -> Result<Vec<CanvasPage>> {
+        let path = format!("/api/v1/courses/{}/pages", self.course_id);
+        self.get_all(&path, &[("published", "true")])
+            .await
+            .context("Failed to list pages")
+    }
+
+    /// Fetch a page's body as text
+    pub async fn page(&self, page: &CanvasPage) -> Result<CanvasDocument> {
+        let path = format!("/api/v1/courses/{}/pages/{}", self.course_id, page.url);
+        let body: PageBody = self
+            .request(&format!("{}{}", self.base_url, path), &[])
+            .await?
+            .json()
+            .await
+            .context("Unexpected page response from Canvas")?;
+
+        Ok(CanvasDocument {
+            source: page.html_url.clone(),
+            title: page.title.clone(),
+            text: html_to_text(body.body.as_deref().unwrap_or("")),
+        })
+    }
+
+    /// Files uploaded to the course
+    pub async fn files(&self) -> Result<Vec<CanvasFile>> {
+        let path = format!("/api/v1/courses/{}/files", self.course_id);
+        self.get_all(&path, &[])
+            .await
+            .context("Failed to list files (the course may hide its Files tab)")
+    }
+
+    /// Download a file into `dir`, returning where it was saved
+    pub async fn download(&self, file: &CanvasFile, dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+
+        let bytes = self
+            .request(&file.url, &[])
+            .await?
+            .bytes()
+            .await
+            .with_context(|| format!("Failed to download {}", file.display_name))?;
+
+        // Prefix with the id so files sharing a name don't overwrite each other
+        let name = file.display_name.replace(['/', '\\'], "_");
+        let path = dir.join(format!("{}-{}", file.id, name));
+        std::fs::write(&path, &bytes)?;
+
+        Ok(path)
+    }
+
+    /// GET every page of a paginated list endpoint
+    async fn get_all<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        let mut query = query.to_vec();
+        query.push(("per_page", PER_PAGE));
+
+        let mut next = Some(format!("{}{}", self.base_url, path));
+        let mut first = true;
+
+        while let Some(url) = next {
+            // Canvas's next links already carry the query string
+            let response = self.request(&url, if first { &query } else { &[] }).await?;
+            first = false;
+
+            next = response
+                .headers()
+                .get(reqwest::header::LINK)
+                .and_then(|v| v.to_str().ok())
+                .and_then(next_link);
+
+            let page: Vec<T> = response
+                .json()
+                .await
+                .context("Unexpected response from Canvas")?;
+            items.extend(page);
+        }
+
+        Ok(items)
+    }
+
+    async fn request(&self, url: &str, query: &[(&str, &str)]) -> Result<reqwest::Response> {
+        let response = self
+            .client
+            .get(url)
+            .query(query)
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .context("Failed to reach Canvas")?;
+
+        match response.status() {
+            s if s.is_success() => Ok(response),
+            reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => {
+                anyhow::bail!(
+                    "Canvas denied access. Check the access token (Account → Settings), \
+                     or the course may hide this section."
+                )
+            }
+            reqwest::StatusCode::NOT_FOUND => {
+                anyhow::bail!("Not found on Canvas (check the course ID): {}", url)
+            }
+            s => anyhow::bail!("Canvas returned HTTP {}", s),
+        }
+    }
+}
+
+/// The `rel="next"` URL from a Link header
+fn next_link(header: &str) -> Option<String> {
+    header.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        params
+            .split(';')
+            .any(|p| p.trim() == "rel=\"next\"")
+            .then(|| {
+                url.trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string()
+            })
+    })
+}
+
+/// Convert an HTML fragment (announcement message, page body) to plain text
+fn html_to_text(html: &str) -> String {
+    let fragment = Html::parse_fragment(html);
+    clean_text(&extract_text_from_element(&fragment.root_element()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_link() {
+        let header = "<https://canvas.example.edu/api/v1/courses/1/files?page=1&per_page=100>; rel=\"current\",\
+                      <https://canvas.example.edu/api/v1/courses/1/files?page=2&per_page=100>; rel=\"next\",\
+                      <https://canvas.example.edu/api/v1/courses/1/files?page=5&per_page=100>; rel=\"last\"";
+        assert_eq!(
+            next_link(header).as_deref(),
+            Some("https://canvas.example.edu/api/v1/courses/1/files?page=2&per_page=100")
+        );
+
+        let last = "<https://canvas.example.edu/api/v1/courses/1/files?page=5>; rel=\"current\"";
+        assert!(next_link(last).is_none());
+    }
+
+    #[test]
+    fn test_html_to_text() {
+        let text =
+            html_to_text("<p>Midterm moved to Friday.</p><ul><li>Bring a calculator.</li></ul>");
+        assert_eq!(text, "Midterm moved to Friday.\nBring a calculator.");
+    }
+}
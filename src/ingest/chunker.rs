@@ -1,6 +1,13 @@
 //! Text chunking strategies for RAG
 #![allow(clippy::collapsible_if)]
 
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+use super::{Section, Timestamp};
+use crate::config::Config;
+
 /// A chunk of text with metadata
 #[derive(Debug, Clone)]
 pub struct Chunk {
@@ -10,6 +17,23 @@ pub struct Chunk {
     pub start_char: usize,
     #[allow(dead_code)]
     pub end_char: usize,
+    /// Title of the section (e.g. chapter) the chunk belongs to
+    pub section: Option<String>,
+    /// 1-based page the chunk starts on, for paginated sources (PDF)
+    pub page: Option<usize>,
+    /// Seconds into the recording where the chunk starts (audio/video)
+    pub start_time: Option<f64>,
+}
+
+/// How documents are split into chunks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ChunkStrategy {
+    /// Follow the document's structure: chapters, table headers, code definitions
+    #[default]
+    Structured,
+    /// Plain character windows, ignoring structure
+    Fixed,
 }
 
 /// Configuration for chunking
@@ -19,6 +43,7 @@ pub struct ChunkConfig {
     pub chunk_size: usize,
     /// Overlap between chunks in characters
     pub overlap: usize,
+    pub strategy: ChunkStrategy,
 }
 
 impl Default for ChunkConfig {
@@ -26,10 +51,68 @@ impl Default for ChunkConfig {
         Self {
             chunk_size: 1000, // ~250 tokens
             overlap: 200,     // Some overlap for context continuity
+            strategy: ChunkStrategy::Structured,
         }
     }
 }
 
+/// Chunking settings given for this invocation (e.g. `add --chunk-size`),
+/// taking precedence over the configured ones
+static OVERRIDE: OnceLock<ChunkConfig> = OnceLock::new();
+
+impl ChunkConfig {
+    /// The chunking to use: the per-run override, then config, then defaults
+    pub fn current() -> Self {
+        if let Some(config) = OVERRIDE.get() {
+            return config.clone();
+        }
+
+        Config::load()
+            .map(|c| Self::from_config(&c))
+            .unwrap_or_default()
+    }
+
+    /// Settings from the config file, with defaults for anything unset
+    pub fn from_config(config: &Config) -> Self {
+        let defaults = Self::default();
+        Self {
+            chunk_size: config.chunk_size.unwrap_or(defaults.chunk_size),
+            overlap: config.chunk_overlap.unwrap_or(defaults.overlap),
+            strategy: config.chunk_strategy,
+        }
+        .validated()
+    }
+
+    /// Use these settings (with any unset ones from config) for the rest of this run
+    pub fn set_override(
+        chunk_size: Option<usize>,
+        overlap: Option<usize>,
+        strategy: Option<ChunkStrategy>,
+    ) {
+        let mut config = Self::current();
+        if let Some(size) = chunk_size {
+            config.chunk_size = size;
+        }
+        if let Some(overlap) = overlap {
+            config.overlap = overlap;
+        }
+        if let Some(strategy) = strategy {
+            config.strategy = strategy;
+        }
+        let _ = OVERRIDE.set(config.validated());
+    }
+
+    /// Keep the size sane and the overlap below it, so chunking always advances
+    fn validated(mut self) -> Self {
+        self.chunk_size = self.chunk_size.max(MIN_CHUNK_SIZE);
+        self.overlap = self.overlap.min(self.chunk_size / 2);
+        self
+    }
+}
+
+/// Smallest chunk size accepted from settings
+pub const MIN_CHUNK_SIZE: usize = 100;
+
 /// Split text into chunks with overlap
 pub fn chunk_text(text: &str, config: &ChunkConfig) -> Vec<Chunk> {
     let text = text.trim();
@@ -45,6 +128,9 @@ pub fn chunk_text(text: &str, config: &ChunkConfig) -> Vec<Chunk> {
             index: 0,
             start_char: 0,
             end_char: text.len(),
+            section: None,
+            page: None,
+            start_time: None,
         }];
     }
 
@@ -71,6 +157,9 @@ pub fn chunk_text(text: &str, config: &ChunkConfig) -> Vec<Chunk> {
                 index,
                 start_char: start,
                 end_char: end,
+                section: None,
+                page: None,
+                start_time: None,
             });
             index += 1;
         }
@@ -95,6 +184,237 @@ pub fn chunk_text(text: &str, config: &ChunkConfig) -> Vec<Chunk> {
     chunks
 }
 
+/// Split text into chunks that never cross a section boundary, tagging each
+/// chunk with its section title. Falls back to `chunk_text` with no sections
+/// or the fixed strategy.
+pub fn chunk_sections(text: &str, sections: &[Section], config: &ChunkConfig) -> Vec<Chunk> {
+    if sections.is_empty() || config.strategy == ChunkStrategy::Fixed {
+        return chunk_text(text, config);
+    }
+
+    let mut chunks = Vec::new();
+
+    // Any text before the first section is chunked untitled
+    let mut bounds: Vec<(Option<&str>, usize)> = vec![(None, 0)];
+    bounds.extend(sections.iter().map(|s| (Some(s.title.as_str()), s.start)));
+
+    for (i, (title, start)) in bounds.iter().enumerate() {
+        let end = bounds.get(i + 1).map(|(_, s)| *s).unwrap_or(text.len());
+        let start = find_char_boundary(text, *start);
+        let end = find_char_boundary(text, end);
+        if start >= end {
+            continue;
+        }
+
+        for mut chunk in chunk_text(&text[start..end], config) {
+            chunk.index = chunks.len();
+            chunk.start_char += start;
+            chunk.end_char += start;
+            chunk.section = title.map(str::to_string);
+            chunks.push(chunk);
+        }
+    }
+
+    chunks
+}
+
+/// Chunk source code along definition boundaries: consecutive small
+/// definitions are packed together up to the chunk size, and a definition too
+/// big for one chunk is split between lines. Each chunk is titled with the
+/// first definition in it. Indentation is kept.
+pub fn chunk_code(text: &str, sections: &[Section], config: &ChunkConfig) -> Vec<Chunk> {
+    let mut bounds: Vec<(Option<&str>, usize)> = vec![(None, 0)];
+    bounds.extend(sections.iter().map(|s| (Some(s.title.as_str()), s.start)));
+
+    let mut spans: Vec<(Option<&str>, usize, usize)> = Vec::new();
+    for (i, (title, start)) in bounds.iter().enumerate() {
+        let end = bounds.get(i + 1).map(|(_, s)| *s).unwrap_or(text.len());
+        let start = find_char_boundary(text, *start);
+        let end = find_char_boundary(text, end);
+        if start < end && !text[start..end].trim().is_empty() {
+            spans.push((*title, start, end));
+        }
+    }
+
+    let mut chunks: Vec<Chunk> = Vec::new();
+    let push = |chunks: &mut Vec<Chunk>, title: Option<&str>, start: usize, end: usize| {
+        let body = text[start..end].trim_matches(['\n', '\r']);
+        if !body.trim().is_empty() {
+            chunks.push(Chunk {
+                text: body.to_string(),
+                index: chunks.len(),
+                start_char: start,
+                end_char: end,
+                section: title.map(str::to_string),
+                page: None,
+                start_time: None,
+            });
+        }
+    };
+
+    // The chunk being packed: (title, start, end)
+    let mut current: Option<(Option<&str>, usize, usize)> = None;
+
+    for (title, start, end) in spans {
+        if let Some((cur_title, cur_start, cur_end)) = current {
+            if end - cur_start <= config.chunk_size {
+                current = Some((cur_title.or(title), cur_start, end));
+                continue;
+            }
+            push(&mut chunks, cur_title, cur_start, cur_end);
+        }
+
+        if end - start <= config.chunk_size {
+            current = Some((title, start, end));
+            continue;
+        }
+
+        // Too big for one chunk: split between lines
+        let mut piece_start = start;
+        let mut offset = start;
+        for line in text[start..end].split_inclusive('\n') {
+            if offset > piece_start && offset + line.len() - piece_start > config.chunk_size {
+                push(&mut chunks, title, piece_start, offset);
+                piece_start = offset;
+            }
+            offset += line.len();
+        }
+        current = Some((title, piece_start, end));
+    }
+
+    if let Some((title, start, end)) = current {
+        push(&mut chunks, title, start, end);
+    }
+
+    chunks
+}
+
+/// Chunk tabular text (one row per line, first line of each section is the
+/// header) so that every chunk starts with its table's header row
+pub fn chunk_table(text: &str, sections: &[Section], config: &ChunkConfig) -> Vec<Chunk> {
+    let mut bounds: Vec<(Option<&str>, usize)> = if sections.is_empty() {
+        vec![(None, 0)]
+    } else {
+        sections
+            .iter()
+            .map(|s| (Some(s.title.as_str()), s.start))
+            .collect()
+    };
+    bounds.sort_by_key(|(_, start)| *start);
+
+    let mut chunks: Vec<Chunk> = Vec::new();
+
+    for (i, (title, start)) in bounds.iter().enumerate() {
+        let end = bounds.get(i + 1).map(|(_, s)| *s).unwrap_or(text.len());
+        let start = find_char_boundary(text, *start);
+        let end = find_char_boundary(text, end);
+        if start >= end {
+            continue;
+        }
+
+        let mut lines = text[start..end].lines().filter(|l| !l.trim().is_empty());
+        let Some(header) = lines.next() else {
+            continue;
+        };
+
+        let mut current = header.to_string();
+        let mut has_rows = false;
+        let flush = |current: &mut String, chunks: &mut Vec<Chunk>| {
+            chunks.push(Chunk {
+                text: std::mem::replace(current, header.to_string()),
+                index: chunks.len(),
+                start_char: start,
+                end_char: end,
+                section: title.map(str::to_string),
+                page: None,
+                start_time: None,
+            });
+        };
+
+        // Rows too long to fit beside the header are split across chunks
+        let room = config
+            .chunk_size
+            .saturating_sub(header.len() + 1)
+            .max(config.chunk_size / 2);
+        for line in lines {
+            for piece in split_row(line, room) {
+                if has_rows && current.len() + piece.len() + 1 > config.chunk_size {
+                    flush(&mut current, &mut chunks);
+                }
+                current.push('\n');
+                current.push_str(piece);
+                has_rows = true;
+            }
+        }
+
+        flush(&mut current, &mut chunks);
+    }
+
+    chunks
+}
+
+/// Split a table row into pieces of at most `room` bytes, breaking between
+/// cells where possible, then between words
+fn split_row(row: &str, room: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = row;
+
+    while rest.len() > room {
+        let limit = find_char_boundary(rest, room);
+        // A separator may end just past `room`; the piece before it still fits
+        let before = |sep: &str| {
+            rest[..find_char_boundary(rest, room + sep.len())]
+                .rfind(sep)
+                .filter(|&p| p > 0)
+        };
+        let (end, skip) = if let Some(pos) = before(" | ") {
+            (pos, 3)
+        } else if let Some(pos) = before(" ") {
+            (pos, 1)
+        } else {
+            // One long word: cut it, keeping at least a character
+            let cut = if limit > 0 {
+                limit
+            } else {
+                rest.chars().next().map_or(rest.len(), char::len_utf8)
+            };
+            (cut, 0)
+        };
+        pieces.push(&rest[..end]);
+        rest = &rest[end + skip..];
+    }
+
+    if !rest.is_empty() {
+        pieces.push(rest);
+    }
+    pieces
+}
+
+/// Tag each chunk with the page it starts on, given the byte offset where
+/// each page starts (page N at `pages[N - 1]`)
+pub fn assign_pages(chunks: &mut [Chunk], pages: &[usize]) {
+    if pages.is_empty() {
+        return;
+    }
+
+    for chunk in chunks {
+        let page = pages.partition_point(|start| *start <= chunk.start_char);
+        chunk.page = Some(page.max(1));
+    }
+}
+
+/// Tag each chunk with the time of the transcript segment it starts in
+pub fn assign_timestamps(chunks: &mut [Chunk], timestamps: &[Timestamp]) {
+    if timestamps.is_empty() {
+        return;
+    }
+
+    for chunk in chunks {
+        let i = timestamps.partition_point(|t| t.start <= chunk.start_char);
+        chunk.start_time = Some(timestamps[i.saturating_sub(1)].seconds);
+    }
+}
+
 /// Find the nearest valid UTF-8 character boundary at or before the given position
 fn find_char_boundary(text: &str, pos: usize) -> usize {
     if pos >= text.len() {
@@ -172,9 +492,138 @@ mod tests {
         let config = ChunkConfig {
             chunk_size: 100,
             overlap: 20,
+            ..Default::default()
         };
         let text = "A".repeat(500);
         let chunks = chunk_text(&text, &config);
         assert!(chunks.len() > 1);
     }
+
+    #[test]
+    fn test_chunk_sections() {
+        let text = "Preface text.\n\nChapter one text.\n\nChapter two text.";
+        let sections = vec![
+            Section {
+                title: "One".to_string(),
+                start: text.find("Chapter one").unwrap(),
+            },
+            Section {
+                title: "Two".to_string(),
+                start: text.find("Chapter two").unwrap(),
+            },
+        ];
+
+        let chunks = chunk_sections(text, &sections, &ChunkConfig::default());
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].section, None);
+        assert_eq!(chunks[1].section.as_deref(), Some("One"));
+        assert_eq!(chunks[1].text, "Chapter one text.");
+        assert_eq!(chunks[2].index, 2);
+        assert_eq!(chunks[2].section.as_deref(), Some("Two"));
+    }
+
+    #[test]
+    fn test_assign_pages() {
+        let config = ChunkConfig {
+            chunk_size: 20,
+            overlap: 0,
+            ..Default::default()
+        };
+        let text = "Page one text here.\nPage two text here.\nPage three text.";
+        let pages = vec![0, 20, 20, 40];
+
+        let mut chunks = chunk_text(text, &config);
+        assign_pages(&mut chunks, &pages);
+        let numbers: Vec<_> = chunks.iter().map(|c| c.page).collect();
+        assert_eq!(numbers, vec![Some(1), Some(3), Some(4)]);
+    }
+
+    #[test]
+    fn test_chunk_config_from_config() {
+        let config: Config =
+            toml::from_str("chunk_size = 500\nchunk_overlap = 400\nchunk_strategy = \"fixed\"")
+                .unwrap();
+
+        let chunking = ChunkConfig::from_config(&config);
+        assert_eq!(chunking.chunk_size, 500);
+        assert_eq!(chunking.overlap, 250);
+        assert_eq!(chunking.strategy, ChunkStrategy::Fixed);
+
+        let defaults = ChunkConfig::from_config(&Config::default());
+        assert_eq!(defaults.chunk_size, 1000);
+        assert_eq!(defaults.strategy, ChunkStrategy::Structured);
+    }
+
+    #[test]
+    fn test_chunk_code() {
+        let config = ChunkConfig {
+            chunk_size: 60,
+            overlap: 0,
+            ..Default::default()
+        };
+        let text = "fn a() {}\n\nfn b() {}\n\nfn long() {\n    let x = 1;\n    let y = 2;\n    let z = 3;\n    x + y + z\n}\n";
+        let sections: Vec<Section> = ["fn a", "fn b", "fn long"]
+            .iter()
+            .map(|title| Section {
+                title: title.to_string(),
+                start: text.find(*title).unwrap(),
+            })
+            .collect();
+
+        let chunks = chunk_code(text, &sections, &config);
+        assert_eq!(chunks[0].text, "fn a() {}\n\nfn b() {}");
+        assert_eq!(chunks[0].section.as_deref(), Some("fn a"));
+        assert!(chunks.len() >= 3);
+        for chunk in &chunks[1..] {
+            assert_eq!(chunk.section.as_deref(), Some("fn long"));
+            assert!(chunk.text.len() <= 60);
+        }
+        assert!(chunks[2].text.starts_with("    "));
+    }
+
+    #[test]
+    fn test_chunk_table_repeats_header() {
+        let config = ChunkConfig {
+            chunk_size: 40,
+            overlap: 0,
+            ..Default::default()
+        };
+        let text = "name | score\nalice | 90\nbob | 85\ncarol | 77\ndave | 60";
+
+        let chunks = chunk_table(text, &[], &config);
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.text.starts_with("name | score\n"));
+        }
+        let rows: usize = chunks.iter().map(|c| c.text.lines().count() - 1).sum();
+        assert_eq!(rows, 4);
+    }
+
+    #[test]
+    fn test_chunk_table_splits_long_rows() {
+        let config = ChunkConfig {
+            chunk_size: 40,
+            overlap: 0,
+            ..Default::default()
+        };
+        let notes = "the quick brown fox jumps over the lazy dog again and again";
+        let text = format!("name | notes\nalice | {}\nbob | short", notes);
+
+        let chunks = chunk_table(&text, &[], &config);
+        assert!(chunks.len() > 2);
+        for chunk in &chunks {
+            assert!(chunk.text.starts_with("name | notes\n"));
+            assert!(chunk.text.len() <= config.chunk_size);
+        }
+        let body: Vec<&str> = chunks
+            .iter()
+            .flat_map(|c| c.text.lines().skip(1))
+            .flat_map(|line| line.split([' ', '|']))
+            .filter(|w| !w.is_empty())
+            .collect();
+        assert_eq!(body.join(" "), format!("alice {} bob short", notes));
+
+        assert_eq!(split_row("abcdef", 4), vec!["abcd", "ef"]);
+        assert_eq!(split_row("a | bb | c", 7), vec!["a | bb", "c"]);
+    }
 }
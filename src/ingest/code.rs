@@ -0,0 +1,301 @@
+//! Source code files, split at function/class boundaries with a simple
+//! brace (or, for Python, indentation) heuristic
+
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use super::Section;
+
+/// Source file extensions and the language recorded for them
+const LANGUAGES: &[(&str, &str)] = &[
+    ("rs", "rust"),
+    ("py", "python"),
+    ("c", "c"),
+    ("h", "c"),
+    ("cpp", "cpp"),
+    ("cc", "cpp"),
+    ("hpp", "cpp"),
+    ("cs", "csharp"),
+    ("java", "java"),
+    ("kt", "kotlin"),
+    ("scala", "scala"),
+    ("swift", "swift"),
+    ("go", "go"),
+    ("js", "javascript"),
+    ("jsx", "javascript"),
+    ("ts", "typescript"),
+    ("tsx", "typescript"),
+    ("php", "php"),
+];
+
+/// Blocks whose direct children are definitions worth their own section
+const CONTAINER_KEYWORDS: &[&str] = &[
+    "impl",
+    "trait",
+    "mod",
+    "class",
+    "interface",
+    "namespace",
+    "object",
+    "extern",
+];
+
+/// The language of a source file, judged by its extension
+pub fn language(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|(e, _)| *e == ext)
+        .map(|(_, language)| *language)
+}
+
+/// Whether a stored content type is one of the code languages
+pub fn is_code_type(content_type: &str) -> bool {
+    find_language(content_type).is_some()
+}
+
+/// The language name matching a stored content type
+pub fn find_language(name: &str) -> Option<&'static str> {
+    LANGUAGES
+        .iter()
+        .find(|(_, language)| *language == name)
+        .map(|(_, language)| *language)
+}
+
+/// Read a source file, returning its text and a section per top-level
+/// definition (and per method inside classes/impls)
+pub fn extract(path: &Path, language: &str) -> Result<(String, Vec<Section>)> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read source file: {:?}", path))?;
+    let sections = sections(&text, language);
+    Ok((text, sections))
+}
+
+/// Definition sections of already-read source text
+pub fn sections(text: &str, language: &str) -> Vec<Section> {
+    if language == "python" {
+        indent_sections(text)
+    } else {
+        brace_sections(text)
+    }
+}
+
+/// Sections for brace languages: a definition starts after a blank line (or a
+/// closed block) at the top level or directly inside a container block, and
+/// takes any comments/attributes right above it along
+fn brace_sections(text: &str) -> Vec<Section> {
+    let mut sections = Vec::new();
+    // One entry per open brace: whether that block is a container
+    let mut blocks: Vec<bool> = Vec::new();
+    let mut at_boundary = true;
+    let mut pending: Option<usize> = None;
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let trimmed = line.trim();
+
+        let in_container = blocks.iter().all(|&container| container);
+        if trimmed.is_empty() {
+            at_boundary = in_container;
+            pending = None;
+            continue;
+        }
+
+        if at_boundary && in_container && !trimmed.starts_with('}') {
+            if is_preamble(trimmed) {
+                pending.get_or_insert(start);
+            } else {
+                let title = definition_title(trimmed);
+                sections.push(Section {
+                    title,
+                    start: pending.take().unwrap_or(start),
+                });
+                at_boundary = false;
+            }
+        } else if !is_preamble(trimmed) {
+            at_boundary = false;
+        }
+
+        for c in code_chars(trimmed) {
+            match c {
+                '{' => blocks.push(opens_container(trimmed)),
+                '}' => {
+                    blocks.pop();
+                }
+                _ => {}
+            }
+        }
+
+        // The first member of a container, or a definition right after a
+        // closing brace, needs no blank line before it
+        let opened_container = trimmed.ends_with('{') && blocks.last() == Some(&true);
+        if (opened_container || trimmed.starts_with('}'))
+            && blocks.iter().all(|&container| container)
+        {
+            at_boundary = true;
+        }
+    }
+
+    sections
+}
+
+/// Sections for Python: `def`/`class` at the top level or one level in
+/// (methods), with decorators kept on the definition they decorate
+fn indent_sections(text: &str) -> Vec<Section> {
+    let mut sections = Vec::new();
+    let mut decorator_start: Option<usize> = None;
+    let mut in_class = false;
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        let trimmed = trimmed.trim_end();
+
+        if trimmed.is_empty() || indent > 4 {
+            continue;
+        }
+        // Only methods are split out, not functions nested in functions
+        if indent == 0 {
+            in_class = trimmed.starts_with("class ");
+        } else if !in_class {
+            continue;
+        }
+
+        if trimmed.starts_with('@') {
+            decorator_start.get_or_insert(start);
+            continue;
+        }
+
+        if ["def ", "async def ", "class "]
+            .iter()
+            .any(|k| trimmed.starts_with(k))
+        {
+            sections.push(Section {
+                title: definition_title(trimmed),
+                start: decorator_start.take().unwrap_or(start),
+            });
+        } else {
+            decorator_start = None;
+        }
+    }
+
+    sections
+}
+
+/// Comments and attributes that belong to the definition below them
+fn is_preamble(line: &str) -> bool {
+    ["//", "/*", "*", "#[", "#!", "@"]
+        .iter()
+        .any(|p| line.starts_with(p))
+}
+
+/// Whether the block a line opens holds definitions (impl, class, ...)
+fn opens_container(line: &str) -> bool {
+    !line.contains('(')
+        && line
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .any(|word| CONTAINER_KEYWORDS.contains(&word))
+}
+
+/// The line's characters outside string literals and `//` comments, so that
+/// braces inside them don't count
+fn code_chars(line: &str) -> impl Iterator<Item = char> + '_ {
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    let mut prev = '\0';
+    let mut done = false;
+
+    line.chars().filter(move |&c| {
+        if done {
+            return false;
+        }
+        let keep = match quote {
+            Some(q) => {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == q {
+                    quote = None;
+                }
+                false
+            }
+            None if c == '/' && prev == '/' => {
+                done = true;
+                false
+            }
+            None if c == '"' => {
+                quote = Some(c);
+                false
+            }
+            None => true,
+        };
+        prev = c;
+        keep
+    })
+}
+
+/// A short title for a definition: its signature up to the parameters or body
+fn definition_title(line: &str) -> String {
+    let line = line.trim_end_matches([':', ';']);
+    let end = line.find(['(', '{']).unwrap_or(line.len());
+    let title = line[..end].trim();
+    let title = if title.is_empty() { line.trim() } else { title };
+    title.chars().take(80).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles(text: &str, sections: &[Section]) -> Vec<(String, String)> {
+        sections
+            .iter()
+            .map(|s| {
+                let first_line = text[s.start..].lines().next().unwrap_or("").to_string();
+                (s.title.clone(), first_line)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_brace_sections() {
+        let text = "use std::fmt;\n\n/// Adds\n#[inline]\npub fn add(a: i32) -> i32 {\n    let s = \"}\";\n\n    a + 1\n}\n\nimpl Foo {\n    fn one(&self) {}\n\n    fn two(&self) {\n        if x {\n        }\n    }\n}\nfn tail() {}\n";
+        let sections = brace_sections(text);
+
+        assert_eq!(
+            titles(text, &sections),
+            vec![
+                ("use std::fmt".to_string(), "use std::fmt;".to_string()),
+                ("pub fn add".to_string(), "/// Adds".to_string()),
+                ("impl Foo".to_string(), "impl Foo {".to_string()),
+                ("fn one".to_string(), "    fn one(&self) {}".to_string()),
+                ("fn two".to_string(), "    fn two(&self) {".to_string()),
+                ("fn tail".to_string(), "fn tail() {}".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_indent_sections() {
+        let text = "import os\n\n@cache\ndef load(path):\n    def inner():\n        pass\n\nclass Model:\n    def fit(self, x):\n        pass\n";
+        let sections = indent_sections(text);
+
+        assert_eq!(
+            titles(text, &sections),
+            vec![
+                ("def load".to_string(), "@cache".to_string()),
+                ("class Model".to_string(), "class Model:".to_string()),
+                ("def fit".to_string(), "    def fit(self, x):".to_string()),
+            ]
+        );
+        assert_eq!(language(Path::new("src/main.RS")), Some("rust"));
+        assert!(is_code_type("python"));
+        assert!(!is_code_type("markdown"));
+    }
+}
@@ -0,0 +1,116 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use super::{Section, read_zip_entry};
+
+const WORD_NS: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+
+/// Extract paragraphs from a Word document. Headings become Markdown-style
+/// `#` lines and section boundaries for the chunker.
+pub fn extract(path: &Path) -> Result<(String, Vec<Section>)> {
+    let file =
+        std::fs::File::open(path).with_context(|| format!("Failed to open DOCX: {:?}", path))?;
+    let mut archive = zip::ZipArchive::new(file).context("Not a valid DOCX (zip) file")?;
+
+    let xml = read_zip_entry(&mut archive, "DOCX", "word/document.xml")?;
+
+    let (text, sections) = parse_document(&xml)?;
+
+    if text.is_empty() {
+        anyhow::bail!("No text could be extracted from DOCX: {:?}", path);
+    }
+
+    Ok((text, sections))
+}
+
+fn parse_document(xml: &str) -> Result<(String, Vec<Section>)> {
+    let doc = roxmltree::Document::parse(xml).context("Invalid DOCX document XML")?;
+
+    let mut text = String::new();
+    let mut sections = Vec::new();
+
+    for paragraph in doc.descendants().filter(|n| n.has_tag_name("p")) {
+        let content = paragraph_text(&paragraph);
+        let content = content.trim();
+        if content.is_empty() {
+            continue;
+        }
+
+        if !text.is_empty() {
+            text.push_str("\n\n");
+        }
+
+        match heading_level(&paragraph) {
+            Some(level) => {
+                sections.push(Section {
+                    title: content.to_string(),
+                    start: text.len(),
+                });
+                text.push_str(&"#".repeat(level));
+                text.push(' ');
+                text.push_str(content);
+            }
+            None => text.push_str(content),
+        }
+    }
+
+    Ok((text, sections))
+}
+
+/// Concatenate the text runs of a paragraph
+fn paragraph_text(paragraph: &roxmltree::Node) -> String {
+    let mut text = String::new();
+
+    for node in paragraph.descendants() {
+        match node.tag_name().name() {
+            "t" => text.push_str(node.text().unwrap_or("")),
+            "tab" => text.push('\t'),
+            "br" | "cr" => text.push('\n'),
+            _ => {}
+        }
+    }
+
+    text
+}
+
+/// Heading level from the paragraph style ("Title", "Heading1", "heading 2", ...)
+fn heading_level(paragraph: &roxmltree::Node) -> Option<usize> {
+    let style = paragraph
+        .children()
+        .find(|n| n.has_tag_name("pPr"))?
+        .children()
+        .find(|n| n.has_tag_name("pStyle"))?
+        .attribute((WORD_NS, "val"))?
+        .to_lowercase();
+
+    if style == "title" {
+        return Some(1);
+    }
+
+    let level = style.strip_prefix("heading")?.trim();
+    level.parse::<usize>().ok().map(|l| l.clamp(1, 6))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_document() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+              <w:body>
+                <w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>Week 1</w:t></w:r></w:p>
+                <w:p><w:r><w:t>Supply and </w:t></w:r><w:r><w:t>demand.</w:t></w:r></w:p>
+                <w:p/>
+                <w:p><w:pPr><w:pStyle w:val="Heading2"/></w:pPr><w:r><w:t>Elasticity</w:t></w:r></w:p>
+              </w:body>
+            </w:document>"#;
+
+        let (text, sections) = parse_document(xml).unwrap();
+        assert_eq!(text, "# Week 1\n\nSupply and demand.\n\n## Elasticity");
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[1].title, "Elasticity");
+        assert!(text[sections[1].start..].starts_with("## Elasticity"));
+    }
+}
@@ -0,0 +1,137 @@
+use anyhow::{Context, Result};
+use scraper::{Html, Selector};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::url::{clean_text, extract_text_from_element};
+use super::{Section, read_zip_entry};
+
+/// Extract chapter text from an EPUB, in reading (spine) order.
+/// Returns the full text plus the offset where each chapter starts.
+pub fn extract(path: &Path) -> Result<(String, Vec<Section>)> {
+    let file =
+        std::fs::File::open(path).with_context(|| format!("Failed to open EPUB: {:?}", path))?;
+    let mut archive = zip::ZipArchive::new(file).context("Not a valid EPUB (zip) file")?;
+
+    let container = read_zip_entry(&mut archive, "EPUB", "META-INF/container.xml")?;
+    let opf_path = rootfile_path(&container).context("EPUB has no package document")?;
+    let opf = read_zip_entry(&mut archive, "EPUB", &opf_path)?;
+
+    // Chapter hrefs are relative to the package document
+    let base = match opf_path.rfind('/') {
+        Some(i) => &opf_path[..=i],
+        None => "",
+    };
+
+    let mut text = String::new();
+    let mut sections = Vec::new();
+
+    for (number, href) in spine_hrefs(&opf)?.iter().enumerate() {
+        let Ok(xhtml) = read_zip_entry(&mut archive, "EPUB", &format!("{}{}", base, href)) else {
+            continue;
+        };
+
+        let (title, chapter_text) = extract_chapter(&xhtml);
+        if chapter_text.is_empty() {
+            continue;
+        }
+
+        if !text.is_empty() {
+            text.push_str("\n\n");
+        }
+        sections.push(Section {
+            title: title.unwrap_or_else(|| format!("Chapter {}", number + 1)),
+            start: text.len(),
+        });
+        text.push_str(&chapter_text);
+    }
+
+    if text.is_empty() {
+        anyhow::bail!("No text could be extracted from EPUB: {:?}", path);
+    }
+
+    Ok((text, sections))
+}
+
+/// Find the package document (.opf) path in META-INF/container.xml
+fn rootfile_path(container: &str) -> Option<String> {
+    let doc = roxmltree::Document::parse(container).ok()?;
+    doc.descendants()
+        .find(|n| n.has_tag_name("rootfile"))
+        .and_then(|n| n.attribute("full-path"))
+        .map(str::to_string)
+}
+
+/// Resolve the spine's itemrefs to manifest hrefs
+fn spine_hrefs(opf: &str) -> Result<Vec<String>> {
+    let doc = roxmltree::Document::parse(opf).context("Invalid EPUB package document")?;
+
+    let manifest: HashMap<&str, &str> = doc
+        .descendants()
+        .filter(|n| n.has_tag_name("item"))
+        .filter_map(|n| Some((n.attribute("id")?, n.attribute("href")?)))
+        .collect();
+
+    Ok(doc
+        .descendants()
+        .filter(|n| n.has_tag_name("itemref"))
+        .filter_map(|n| n.attribute("idref"))
+        .filter_map(|id| manifest.get(id))
+        .map(|href| href.split('#').next().unwrap_or(href).to_string())
+        .collect())
+}
+
+/// Get a chapter's title (first heading, else <title>) and body text
+fn extract_chapter(xhtml: &str) -> (Option<String>, String) {
+    let document = Html::parse_document(xhtml);
+
+    let title = ["h1", "h2", "h3", "title"].iter().find_map(|tag| {
+        let selector = Selector::parse(tag).ok()?;
+        let element = document.select(&selector).next()?;
+        let title = element.text().collect::<String>();
+        let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+        (!title.is_empty()).then_some(title)
+    });
+
+    let text = Selector::parse("body")
+        .ok()
+        .and_then(|selector| document.select(&selector).next())
+        .map(|body| clean_text(&extract_text_from_element(&body)))
+        .unwrap_or_default();
+
+    (title, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spine_order_and_titles() {
+        let container = r#"<?xml version="1.0"?>
+            <container xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+              <rootfiles><rootfile full-path="OEBPS/content.opf"/></rootfiles>
+            </container>"#;
+        assert_eq!(
+            rootfile_path(container).as_deref(),
+            Some("OEBPS/content.opf")
+        );
+
+        let opf = r#"<?xml version="1.0"?>
+            <package xmlns="http://www.idpf.org/2007/opf">
+              <manifest>
+                <item id="c2" href="ch2.xhtml" media-type="application/xhtml+xml"/>
+                <item id="c1" href="ch1.xhtml#start" media-type="application/xhtml+xml"/>
+              </manifest>
+              <spine><itemref idref="c1"/><itemref idref="c2"/></spine>
+            </package>"#;
+        assert_eq!(spine_hrefs(opf).unwrap(), vec!["ch1.xhtml", "ch2.xhtml"]);
+
+        let (title, text) = extract_chapter(
+            "<html><head><title>Book</title></head>\
+             <body><h1>Cell Biology</h1><p>Cells are the basic unit of life.</p></body></html>",
+        );
+        assert_eq!(title.as_deref(), Some("Cell Biology"));
+        assert!(text.contains("basic unit of life"));
+    }
+}
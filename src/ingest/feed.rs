@@ -0,0 +1,135 @@
+//! RSS and Atom feeds, for `librarian feed` subscriptions
+
+use anyhow::{Context, Result};
+
+/// A feed's title and entries, in the order the feed lists them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Feed {
+    pub title: String,
+    pub entries: Vec<FeedEntry>,
+}
+
+/// One post, episode or article in a feed
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedEntry {
+    pub title: String,
+    pub link: String,
+}
+
+/// Download and parse a feed
+pub async fn fetch_feed(url: &str) -> Result<Feed> {
+    let xml = super::url::fetch_text(url).await?;
+    parse_feed(&xml).with_context(|| format!("{} isn't an RSS or Atom feed", url))
+}
+
+/// Parse RSS 2.0, RSS 1.0 (RDF) or Atom. Entries without a link are skipped.
+pub fn parse_feed(xml: &str) -> Result<Feed> {
+    let doc = roxmltree::Document::parse(xml).context("Invalid XML")?;
+    let root = doc.root_element();
+
+    let child_text = |node: roxmltree::Node, name: &str| {
+        node.children()
+            .find(|n| n.has_tag_name(name))
+            .and_then(|n| n.text())
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+    };
+
+    let (title, entries) = match root.tag_name().name() {
+        "rss" | "RDF" => {
+            let title = root
+                .descendants()
+                .find(|n| n.has_tag_name("channel"))
+                .and_then(|channel| child_text(channel, "title"));
+            let entries = root
+                .descendants()
+                .filter(|n| n.has_tag_name("item"))
+                .filter_map(|item| {
+                    // A permalink guid stands in for a missing <link>
+                    let link = child_text(item, "link")
+                        .or_else(|| child_text(item, "guid").filter(|g| g.starts_with("http")))?;
+                    let title = child_text(item, "title").unwrap_or_else(|| link.clone());
+                    Some(FeedEntry { title, link })
+                })
+                .collect();
+            (title, entries)
+        }
+        "feed" => {
+            let entries = root
+                .children()
+                .filter(|n| n.has_tag_name("entry"))
+                .filter_map(|entry| {
+                    let link = entry
+                        .children()
+                        .filter(|n| n.has_tag_name("link"))
+                        .find(|n| n.attribute("rel").is_none_or(|rel| rel == "alternate"))
+                        .and_then(|n| n.attribute("href"))?
+                        .to_string();
+                    let title = child_text(entry, "title").unwrap_or_else(|| link.clone());
+                    Some(FeedEntry { title, link })
+                })
+                .collect();
+            (child_text(root, "title"), entries)
+        }
+        other => anyhow::bail!("Expected <rss> or <feed>, found <{}>", other),
+    };
+
+    Ok(Feed {
+        title: title.unwrap_or_else(|| "Untitled feed".to_string()),
+        entries,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rss() {
+        let feed = parse_feed(
+            r#"<?xml version="1.0"?>
+            <rss version="2.0"><channel>
+              <title>CS4414 Announcements</title>
+              <item><title>Lecture 5 notes</title><link>https://example.edu/l5</link></item>
+              <item><guid isPermaLink="true">https://example.edu/l6</guid></item>
+              <item><title>No link</title></item>
+            </channel></rss>"#,
+        )
+        .unwrap();
+
+        assert_eq!(feed.title, "CS4414 Announcements");
+        assert_eq!(
+            feed.entries,
+            vec![
+                FeedEntry {
+                    title: "Lecture 5 notes".to_string(),
+                    link: "https://example.edu/l5".to_string(),
+                },
+                FeedEntry {
+                    title: "https://example.edu/l6".to_string(),
+                    link: "https://example.edu/l6".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_atom() {
+        let feed = parse_feed(
+            r#"<feed xmlns="http://www.w3.org/2005/Atom">
+              <title>Lab blog</title>
+              <entry>
+                <title>Paging, explained</title>
+                <link rel="self" href="https://example.org/api/1"/>
+                <link href="https://example.org/paging"/>
+              </entry>
+            </feed>"#,
+        )
+        .unwrap();
+
+        assert_eq!(feed.title, "Lab blog");
+        assert_eq!(feed.entries.len(), 1);
+        assert_eq!(feed.entries[0].link, "https://example.org/paging");
+        assert!(parse_feed("<html><body/></html>").is_err());
+    }
+}
@@ -1,17 +1,32 @@
+pub mod arxiv;
+pub mod canvas;
 pub mod chunker;
+pub mod code;
+pub mod docx;
+pub mod epub;
+pub mod feed;
 pub mod ocr;
 pub mod pdf;
+pub mod table;
 pub mod text;
 pub mod url;
+pub mod vocabulary;
 
-pub use chunker::{ChunkConfig, chunk_text};
-pub use url::fetch_url;
+pub use chunker::{
+    Chunk, ChunkConfig, ChunkStrategy, assign_pages, assign_timestamps, chunk_code, chunk_sections,
+    chunk_table, chunk_text,
+};
+pub use url::{
+    CrawlOptions, fetch_page, fetch_robots, fetch_url, is_youtube_channel_url,
+    list_youtube_channel, same_host, youtube_video_id,
+};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::path::Path;
 
-use crate::config::Config;
-use crate::llm::whisper::{self, WhisperClient};
+use crate::config::{Config, TranscriptionBackend};
+use crate::llm::local_whisper::LocalWhisper;
+use crate::llm::whisper::{self, TranscribeOptions, Transcript, WhisperClient};
 
 /// Supported content types
 #[derive(Debug, Clone)]
@@ -22,7 +37,12 @@ pub enum ContentType {
     Audio,
     Video,
     Image,
-    #[allow(dead_code)]
+    Epub,
+    Docx,
+    Csv,
+    Xlsx,
+    /// Source code, with its language (e.g. "rust")
+    Code(&'static str),
     Url,
     Unknown,
 }
@@ -38,12 +58,39 @@ impl ContentType {
             Some("pdf") => ContentType::Pdf,
             Some("txt") => ContentType::Text,
             Some("md" | "markdown") => ContentType::Markdown,
+            Some("epub") => ContentType::Epub,
+            Some("docx") => ContentType::Docx,
+            Some("csv") => ContentType::Csv,
+            Some("xlsx") => ContentType::Xlsx,
             Some("mp3" | "wav" | "m4a" | "ogg" | "flac") => ContentType::Audio,
             Some("mp4" | "mkv" | "avi" | "mov" | "webm" | "flv") => ContentType::Video,
             Some("png" | "jpg" | "jpeg" | "gif" | "bmp" | "tiff" | "tif" | "webp") => {
                 ContentType::Image
             }
-            _ => ContentType::Unknown,
+            _ => code::language(path)
+                .map(ContentType::Code)
+                .unwrap_or(ContentType::Unknown),
+        }
+    }
+
+    /// The type recorded for a stored document (the inverse of the name it was
+    /// saved under)
+    pub fn from_stored(name: &str) -> Self {
+        match name {
+            "pdf" => ContentType::Pdf,
+            "text" => ContentType::Text,
+            "markdown" => ContentType::Markdown,
+            "audio" => ContentType::Audio,
+            "video" => ContentType::Video,
+            "image" => ContentType::Image,
+            "epub" => ContentType::Epub,
+            "docx" => ContentType::Docx,
+            "csv" => ContentType::Csv,
+            "xlsx" => ContentType::Xlsx,
+            "url" => ContentType::Url,
+            other => code::find_language(other)
+                .map(ContentType::Code)
+                .unwrap_or(ContentType::Unknown),
         }
     }
 
@@ -55,6 +102,27 @@ impl ContentType {
     pub fn is_image(&self) -> bool {
         matches!(self, ContentType::Image)
     }
+
+    pub fn is_table(&self) -> bool {
+        matches!(self, ContentType::Csv | ContentType::Xlsx)
+    }
+}
+
+/// A titled part of a document (e.g. an EPUB chapter)
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub title: String,
+    /// Byte offset into the extracted text where the section begins
+    pub start: usize,
+}
+
+/// Where a timed stretch of a transcript begins
+#[derive(Debug, Clone)]
+pub struct Timestamp {
+    /// Byte offset into the extracted text
+    pub start: usize,
+    /// Seconds from the start of the recording
+    pub seconds: f64,
 }
 
 /// Extracted content from a file
@@ -64,6 +132,30 @@ pub struct ExtractedContent {
     pub source: String,
     pub content_type: ContentType,
     pub text: String,
+    /// Section boundaries, if the format has them (empty otherwise)
+    pub sections: Vec<Section>,
+    /// Byte offset where each page starts, for paginated formats (empty otherwise)
+    pub pages: Vec<usize>,
+    /// Transcript segment start times, for audio and video (empty otherwise)
+    pub timestamps: Vec<Timestamp>,
+}
+
+impl ExtractedContent {
+    /// Chunk the content using the strategy that suits its type
+    pub fn chunks(&self, config: &ChunkConfig) -> Vec<Chunk> {
+        let mut chunks = if config.strategy == ChunkStrategy::Fixed {
+            chunk_text(&self.text, config)
+        } else if self.content_type.is_table() {
+            chunk_table(&self.text, &self.sections, config)
+        } else if matches!(self.content_type, ContentType::Code(_)) {
+            chunk_code(&self.text, &self.sections, config)
+        } else {
+            chunk_sections(&self.text, &self.sections, config)
+        };
+        assign_pages(&mut chunks, &self.pages);
+        assign_timestamps(&mut chunks, &self.timestamps);
+        chunks
+    }
 }
 
 /// Extract text content from a file based on its type (sync, for text-based files)
@@ -78,9 +170,41 @@ pub fn extract_from_file(path: &Path) -> Result<ExtractedContent> {
         );
     }
 
+    let mut sections = Vec::new();
+    let mut pages = Vec::new();
     let text = match content_type {
-        ContentType::Pdf => pdf::extract(path)?,
-        ContentType::Text | ContentType::Markdown => text::extract(path)?,
+        ContentType::Pdf => {
+            let (text, starts) = pdf::extract(path)?;
+            pages = starts;
+            text
+        }
+        ContentType::Text => text::extract(path)?,
+        ContentType::Markdown => {
+            let text = text::extract(path)?;
+            sections = text::markdown_sections(&text);
+            text
+        }
+        ContentType::Epub => {
+            let (text, chapters) = epub::extract(path)?;
+            sections = chapters;
+            text
+        }
+        ContentType::Docx => {
+            let (text, headings) = docx::extract(path)?;
+            sections = headings;
+            text
+        }
+        ContentType::Csv => table::extract_csv(path)?,
+        ContentType::Xlsx => {
+            let (text, sheets) = table::extract_xlsx(path)?;
+            sections = sheets;
+            text
+        }
+        ContentType::Code(language) => {
+            let (text, definitions) = code::extract(path, language)?;
+            sections = definitions;
+            text
+        }
         ContentType::Unknown => {
             // Try to read as text anyway
             text::extract(path)?
@@ -93,6 +217,9 @@ pub fn extract_from_file(path: &Path) -> Result<ExtractedContent> {
         source: path.display().to_string(),
         content_type,
         text,
+        sections,
+        pages,
+        timestamps: Vec::new(),
     })
 }
 
@@ -100,11 +227,52 @@ pub fn extract_from_file(path: &Path) -> Result<ExtractedContent> {
 pub async fn extract_from_file_async(path: &Path) -> Result<ExtractedContent> {
     let content_type = ContentType::from_path(path);
 
+    let mut sections = Vec::new();
+    let mut pages = Vec::new();
+    let mut timestamps = Vec::new();
     let text = match &content_type {
-        ContentType::Pdf => pdf::extract(path)?,
-        ContentType::Text | ContentType::Markdown => text::extract(path)?,
-        ContentType::Audio => transcribe_audio(path).await?,
-        ContentType::Video => transcribe_video(path).await?,
+        ContentType::Pdf => {
+            let (text, starts) = pdf::extract(path)?;
+            pages = starts;
+            text
+        }
+        ContentType::Text => text::extract(path)?,
+        ContentType::Markdown => {
+            let text = text::extract(path)?;
+            sections = text::markdown_sections(&text);
+            text
+        }
+        ContentType::Epub => {
+            let (text, chapters) = epub::extract(path)?;
+            sections = chapters;
+            text
+        }
+        ContentType::Docx => {
+            let (text, headings) = docx::extract(path)?;
+            sections = headings;
+            text
+        }
+        ContentType::Csv => table::extract_csv(path)?,
+        ContentType::Xlsx => {
+            let (text, sheets) = table::extract_xlsx(path)?;
+            sections = sheets;
+            text
+        }
+        ContentType::Code(language) => {
+            let (text, definitions) = code::extract(path, language)?;
+            sections = definitions;
+            text
+        }
+        ContentType::Audio | ContentType::Video => {
+            let transcript = if matches!(content_type, ContentType::Video) {
+                transcribe_video(path).await?
+            } else {
+                transcribe_audio(path).await?
+            };
+            let (text, times) = timed_text(&transcript);
+            timestamps = times;
+            text
+        }
         ContentType::Image => ocr::extract_text(path).await?,
         ContentType::Url => unreachable!("URLs should use fetch_url() directly"),
         ContentType::Unknown => {
@@ -117,22 +285,33 @@ pub async fn extract_from_file_async(path: &Path) -> Result<ExtractedContent> {
         source: path.display().to_string(),
         content_type,
         text,
+        sections,
+        pages,
+        timestamps,
     })
 }
 
-/// Transcribe an audio file using Groq Whisper
-async fn transcribe_audio(path: &Path) -> Result<String> {
+/// Transcribe an audio file with the configured backend (Groq Whisper or local)
+async fn transcribe_audio(path: &Path) -> Result<Transcript> {
     let config = Config::load()?;
+    let options = TranscribeOptions::current();
+
+    if config.transcription_backend == TranscriptionBackend::Local {
+        return LocalWhisper::new(config.whisper_model_path()?)
+            .transcribe(path, &options)
+            .await;
+    }
+
     let api_key = config
         .get_api_key()
         .ok_or_else(|| anyhow::anyhow!("No API key configured for transcription"))?;
 
     let client = WhisperClient::new(api_key, None);
-    client.transcribe(path).await
+    client.transcribe(path, &options).await
 }
 
 /// Transcribe a video file (extract audio first, then transcribe)
-async fn transcribe_video(path: &Path) -> Result<String> {
+async fn transcribe_video(path: &Path) -> Result<Transcript> {
     // Extract audio from video
     let audio_path = whisper::extract_audio_from_video(path).await?;
 
@@ -145,6 +324,74 @@ async fn transcribe_video(path: &Path) -> Result<String> {
     result
 }
 
+/// Join transcript segments into one text, recording where each begins.
+/// Falls back to the plain text when the backend returned no segments.
+fn timed_text(transcript: &Transcript) -> (String, Vec<Timestamp>) {
+    if transcript.segments.is_empty() {
+        return (transcript.text.trim().to_string(), Vec::new());
+    }
+
+    let mut text = String::new();
+    let mut timestamps = Vec::new();
+
+    for segment in &transcript.segments {
+        let segment_text = segment.text.trim();
+        if segment_text.is_empty() {
+            continue;
+        }
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        timestamps.push(Timestamp {
+            start: text.len(),
+            seconds: segment.start,
+        });
+        text.push_str(segment_text);
+    }
+
+    (text, timestamps)
+}
+
+/// Format seconds as `m:ss`, or `h:mm:ss` for long recordings
+pub fn format_timestamp(seconds: f64) -> String {
+    let total = seconds.max(0.0) as u64;
+    let (hours, minutes, secs) = (total / 3600, (total % 3600) / 60, total % 60);
+
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, secs)
+    } else {
+        format!("{}:{:02}", minutes, secs)
+    }
+}
+
+/// SHA-256 of a file's bytes, hex encoded
+pub fn file_hash(path: &Path) -> Result<String> {
+    use sha2::{Digest, Sha256};
+
+    let bytes = std::fs::read(path)
+        .with_context(|| format!("Failed to read file for hashing: {:?}", path))?;
+    let digest = Sha256::digest(&bytes);
+
+    Ok(digest.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Read a text entry out of a zip-based document (EPUB, XLSX, DOCX);
+/// `format` names the document type in the error when the entry is missing
+fn read_zip_entry(
+    archive: &mut zip::ZipArchive<std::fs::File>,
+    format: &str,
+    name: &str,
+) -> Result<String> {
+    use std::io::Read;
+
+    let mut entry = archive
+        .by_name(name)
+        .with_context(|| format!("{} is missing {}", format, name))?;
+    let mut content = String::new();
+    entry.read_to_string(&mut content)?;
+    Ok(content)
+}
+
 /// Check if a file requires transcription
 pub fn requires_transcription(path: &Path) -> bool {
     ContentType::from_path(path).is_media()
@@ -156,3 +403,32 @@ pub fn requires_async_processing(path: &Path) -> bool {
     let ct = ContentType::from_path(path);
     ct.is_media() || ct.is_image()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::whisper::TranscriptSegment;
+
+    #[test]
+    fn test_timed_text() {
+        let transcript = Transcript {
+            text: "Welcome back. Today: entropy.".to_string(),
+            segments: vec![
+                TranscriptSegment {
+                    start: 0.0,
+                    text: " Welcome back.".to_string(),
+                },
+                TranscriptSegment {
+                    start: 872.4,
+                    text: " Today: entropy.".to_string(),
+                },
+            ],
+        };
+
+        let (text, timestamps) = timed_text(&transcript);
+        assert_eq!(text, "Welcome back. Today: entropy.");
+        assert_eq!(timestamps[1].start, text.find("Today").unwrap());
+        assert_eq!(format_timestamp(timestamps[1].seconds), "14:32");
+        assert_eq!(format_timestamp(3723.0), "1:02:03");
+    }
+}
@@ -1,7 +1,78 @@
 use anyhow::{Context, Result};
 use std::path::Path;
+use std::sync::OnceLock;
 use tokio::process::Command;
 
+use crate::config::{Config, ImageReader};
+use crate::llm::GroqClient;
+
+/// Groq rejects inline images larger than this
+const MAX_VISION_BYTES: u64 = 4 * 1024 * 1024;
+
+const VISION_PROMPT: &str = "This image is from a student's study materials. \
+First transcribe all text in it exactly, including handwriting (write equations in LaTeX). \
+Then describe any diagrams, charts, tables or figures: what they show, their labels, \
+and how the parts relate. Reply in plain text with a \"Transcription:\" section and a \
+\"Description:\" section. Don't add commentary beyond what is in the image.";
+
+/// Languages used when none are configured
+pub const DEFAULT_LANGUAGES: &str = "eng";
+
+/// Languages requested for this invocation (e.g. `add --ocr-lang`), taking
+/// precedence over the configured ones
+static LANGUAGE_OVERRIDE: OnceLock<String> = OnceLock::new();
+
+/// Use these OCR languages for the rest of this run
+pub fn set_languages(languages: &str) {
+    let _ = LANGUAGE_OVERRIDE.set(languages.to_string());
+}
+
+/// The languages to OCR with: the per-run override, then config, then English
+fn languages() -> String {
+    if let Some(languages) = LANGUAGE_OVERRIDE.get() {
+        return languages.clone();
+    }
+
+    Config::load()
+        .map(|c| c.ocr_languages())
+        .unwrap_or_else(|_| DEFAULT_LANGUAGES.to_string())
+}
+
+/// List the language packs Tesseract has installed
+pub async fn installed_languages() -> Result<Vec<String>> {
+    let output = Command::new("tesseract")
+        .arg("--list-langs")
+        .output()
+        .await
+        .context("Failed to run tesseract")?;
+
+    // Older versions print the list to stderr
+    let mut listing = String::from_utf8_lossy(&output.stdout).to_string();
+    listing.push_str(&String::from_utf8_lossy(&output.stderr));
+
+    Ok(parse_language_list(&listing))
+}
+
+/// Parse `tesseract --list-langs` output, skipping its header line
+fn parse_language_list(listing: &str) -> Vec<String> {
+    listing
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.contains(' '))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Requested languages (joined with '+') that are not installed
+fn missing_languages(requested: &str, installed: &[String]) -> Vec<String> {
+    requested
+        .split('+')
+        .map(str::trim)
+        .filter(|lang| !lang.is_empty() && !installed.iter().any(|i| i == lang))
+        .map(str::to_string)
+        .collect()
+}
+
 /// Supported image formats for OCR
 #[allow(dead_code)]
 const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "bmp", "tiff", "tif", "webp"];
@@ -39,8 +110,70 @@ fn validate_path(path: &Path) -> Result<std::path::PathBuf> {
     Ok(canonical)
 }
 
-/// Extract text from an image using Tesseract OCR
+/// Extract text from an image with the configured reader: Tesseract, a
+/// vision model, or Tesseract falling back to the vision model when it finds
+/// nothing (and an API key is set)
 pub async fn extract_text(path: &Path) -> Result<String> {
+    let config = Config::load().unwrap_or_default();
+
+    match config.image_reader {
+        ImageReader::Ocr => tesseract_text(path).await,
+        ImageReader::Vision => describe_image(path, &config).await,
+        ImageReader::Auto => match tesseract_text(path).await {
+            Ok(text) => Ok(text),
+            Err(ocr_error) if config.has_api_key() => describe_image(path, &config)
+                .await
+                .with_context(|| format!("OCR failed ({}) and so did the vision model", ocr_error)),
+            Err(ocr_error) => Err(ocr_error),
+        },
+    }
+}
+
+/// Describe and transcribe an image with a Groq vision model
+async fn describe_image(path: &Path, config: &Config) -> Result<String> {
+    let canonical_path = validate_path(path)?;
+    let api_key = config.get_api_key().context(
+        "Reading images with a vision model needs a Groq API key. Set one with `librarian config`.",
+    )?;
+
+    let size = std::fs::metadata(&canonical_path)?.len();
+    if size > MAX_VISION_BYTES {
+        anyhow::bail!(
+            "Image is {:.1} MB; the vision model accepts up to {} MB",
+            size as f64 / 1_048_576.0,
+            MAX_VISION_BYTES / 1_048_576
+        );
+    }
+
+    let mime = image_mime(&canonical_path)
+        .with_context(|| format!("Unsupported image type for the vision model: {:?}", path))?;
+    let image = std::fs::read(&canonical_path)?;
+
+    let client = GroqClient::new(api_key, Some(config.vision_model()));
+    let text = client.describe_image(VISION_PROMPT, mime, &image).await?;
+    let text = text.trim().to_string();
+
+    if text.is_empty() {
+        anyhow::bail!("The vision model returned no description");
+    }
+
+    Ok(text)
+}
+
+/// MIME type for images the vision API accepts
+fn image_mime(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    match ext.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// Extract text from an image using Tesseract OCR
+async fn tesseract_text(path: &Path) -> Result<String> {
     // Validate input path
     let canonical_path = validate_path(path)?;
     let path_str = canonical_path
@@ -59,12 +192,34 @@ pub async fn extract_text(path: &Path) -> Result<String> {
         );
     }
 
+    let languages = languages();
+    if let Ok(installed) = installed_languages().await
+        && !installed.is_empty()
+    {
+        let missing = missing_languages(&languages, &installed);
+        if !missing.is_empty() {
+            anyhow::bail!(
+                "Tesseract language pack(s) not installed: {}\n  \
+                 Installed: {}\n  \
+                 - Ubuntu/Debian: sudo apt install {}\n  \
+                 - macOS: brew install tesseract-lang",
+                missing.join(", "),
+                installed.join(", "),
+                missing
+                    .iter()
+                    .map(|lang| format!("tesseract-ocr-{}", lang.replace('_', "-").to_lowercase()))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            );
+        }
+    }
+
     // Run tesseract with validated path
     let output = Command::new("tesseract")
         .arg(path_str)
         .arg("stdout") // Output to stdout
         .arg("-l")
-        .arg("eng") // English language
+        .arg(&languages)
         .arg("--psm")
         .arg("1") // Automatic page segmentation with OSD
         .output()
@@ -131,6 +286,23 @@ mod tests {
         assert!(!is_image_file(Path::new("test.txt")));
     }
 
+    #[test]
+    fn test_image_mime() {
+        assert_eq!(image_mime(Path::new("notes.JPG")), Some("image/jpeg"));
+        assert_eq!(image_mime(Path::new("diagram.png")), Some("image/png"));
+        assert_eq!(image_mime(Path::new("scan.tiff")), None);
+    }
+
+    #[test]
+    fn test_language_packs() {
+        let listing = "List of available languages in \"/usr/share/tesseract-ocr/5/tessdata/\" (3):\neng\nosd\nspa\n";
+        let installed = parse_language_list(listing);
+        assert_eq!(installed, vec!["eng", "osd", "spa"]);
+
+        assert!(missing_languages("spa+eng", &installed).is_empty());
+        assert_eq!(missing_languages("deu+eng", &installed), vec!["deu"]);
+    }
+
     #[test]
     fn test_clean_ocr_text() {
         let input = "  Hello World  \n\n\n  This is OCR text  \n | \n More text";
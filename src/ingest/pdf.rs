@@ -2,16 +2,19 @@ use anyhow::{Context, Result};
 use std::panic;
 use std::path::Path;
 
-/// Extract text content from a PDF file
-pub fn extract(path: &Path) -> Result<String> {
+/// Extract text content from a PDF file, page by page.
+/// Returns the full text plus the byte offset where each page starts
+/// (page N starts at `pages[N - 1]`).
+pub fn extract(path: &Path) -> Result<(String, Vec<usize>)> {
     let bytes =
         std::fs::read(path).with_context(|| format!("Failed to read PDF file: {:?}", path))?;
 
     // Try pdf_extract first, but catch panics (it can crash on complex PDFs)
-    let extract_result = panic::catch_unwind(|| pdf_extract::extract_text_from_mem(&bytes));
+    let extract_result =
+        panic::catch_unwind(|| pdf_extract::extract_text_from_mem_by_pages(&bytes));
 
-    let text = match extract_result {
-        Ok(Ok(text)) => text,
+    let pages = match extract_result {
+        Ok(Ok(pages)) => pages,
         Ok(Err(e)) => {
             // pdf_extract returned an error, try fallback
             eprintln!("Warning: pdf_extract failed, trying fallback: {}", e);
@@ -24,40 +27,73 @@ pub fn extract(path: &Path) -> Result<String> {
         }
     };
 
-    // Clean up the extracted text
-    let cleaned = text
-        .lines()
-        .map(|line| line.trim())
-        .filter(|line| !line.is_empty())
-        .collect::<Vec<_>>()
-        .join("\n");
+    let (text, starts) = join_pages(&pages);
 
-    if cleaned.is_empty() {
+    if text.is_empty() {
         anyhow::bail!("No text could be extracted from PDF: {:?}", path);
     }
 
-    Ok(cleaned)
+    Ok((text, starts))
 }
 
-/// Fallback PDF text extraction using lopdf
-fn extract_with_lopdf(bytes: &[u8]) -> Result<String> {
-    use lopdf::Document;
-
-    let doc = Document::load_mem(bytes).context("Failed to load PDF with lopdf")?;
-
+/// Clean up each page's text and join them, recording where each page starts.
+/// Empty pages still get an (empty) entry, so page numbers stay aligned.
+fn join_pages(pages: &[String]) -> (String, Vec<usize>) {
     let mut text = String::new();
+    let mut starts = Vec::with_capacity(pages.len());
 
-    let pages = doc.get_pages();
-    for (page_num, _) in pages {
-        if let Ok(page_text) = doc.extract_text(&[page_num]) {
-            text.push_str(&page_text);
+    for page in pages {
+        let cleaned = page
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if !text.is_empty() && !cleaned.is_empty() {
             text.push('\n');
         }
+        starts.push(text.len());
+        text.push_str(&cleaned);
     }
 
-    if text.trim().is_empty() {
+    (text, starts)
+}
+
+/// Fallback PDF text extraction using lopdf
+fn extract_with_lopdf(bytes: &[u8]) -> Result<Vec<String>> {
+    use lopdf::Document;
+
+    let doc = Document::load_mem(bytes).context("Failed to load PDF with lopdf")?;
+
+    let pages: Vec<String> = doc
+        .get_pages()
+        .keys()
+        .map(|page_num| doc.extract_text(&[*page_num]).unwrap_or_default())
+        .collect();
+
+    if pages.iter().all(|page| page.trim().is_empty()) {
         anyhow::bail!("Could not extract any text from PDF (may be scanned/image-based)");
     }
 
-    Ok(text)
+    Ok(pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_join_pages() {
+        let pages = vec![
+            "  Intro\n\n".to_string(),
+            String::new(),
+            "Chapter 1\n  Body ".to_string(),
+        ];
+
+        let (text, starts) = join_pages(&pages);
+        assert_eq!(text, "Intro\nChapter 1\nBody");
+        assert_eq!(starts, vec![0, 5, 6]);
+        assert!(text[starts[2]..].starts_with("Chapter 1"));
+    }
 }
@@ -0,0 +1,206 @@
+//! CSV and XLSX extraction. Tables become one line per row (`a | b | c`),
+//! headed by the column names, so they can be chunked with the header
+//! repeated (see `chunker::chunk_table`).
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::{Section, read_zip_entry};
+
+/// Extract a CSV file as a header line followed by one line per row
+pub fn extract_csv(path: &Path) -> Result<String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_path(path)
+        .with_context(|| format!("Failed to read CSV file: {:?}", path))?;
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.context("Invalid CSV row")?;
+        rows.push(record.iter().map(str::to_string).collect());
+    }
+
+    let text = format_rows(&rows);
+    if text.is_empty() {
+        anyhow::bail!("CSV file is empty: {:?}", path);
+    }
+
+    Ok(text)
+}
+
+/// Extract every worksheet of an XLSX workbook; each sheet is a section
+pub fn extract_xlsx(path: &Path) -> Result<(String, Vec<Section>)> {
+    let file =
+        std::fs::File::open(path).with_context(|| format!("Failed to open XLSX: {:?}", path))?;
+    let mut archive = zip::ZipArchive::new(file).context("Not a valid XLSX (zip) file")?;
+
+    let shared_strings = match read_zip_entry(&mut archive, "XLSX", "xl/sharedStrings.xml") {
+        Ok(xml) => parse_shared_strings(&xml)?,
+        Err(_) => Vec::new(),
+    };
+    let sheets = sheet_names(&mut archive)?;
+
+    let mut text = String::new();
+    let mut sections = Vec::new();
+
+    for (name, entry) in sheets {
+        let Ok(xml) = read_zip_entry(&mut archive, "XLSX", &entry) else {
+            continue;
+        };
+        let sheet_text = format_rows(&parse_sheet(&xml, &shared_strings)?);
+        if sheet_text.is_empty() {
+            continue;
+        }
+
+        if !text.is_empty() {
+            text.push_str("\n\n");
+        }
+        sections.push(Section {
+            title: name,
+            start: text.len(),
+        });
+        text.push_str(&sheet_text);
+    }
+
+    if text.is_empty() {
+        anyhow::bail!("No data could be extracted from XLSX: {:?}", path);
+    }
+
+    Ok((text, sections))
+}
+
+/// Join cells with " | ", dropping fully empty rows
+fn format_rows(rows: &[Vec<String>]) -> String {
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| cell.split_whitespace().collect::<Vec<_>>().join(" "))
+                .collect::<Vec<_>>()
+        })
+        .filter(|row| row.iter().any(|cell| !cell.is_empty()))
+        .map(|row| row.join(" | "))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Sheet names and their part paths, in workbook order
+fn sheet_names(archive: &mut zip::ZipArchive<std::fs::File>) -> Result<Vec<(String, String)>> {
+    let workbook = read_zip_entry(archive, "XLSX", "xl/workbook.xml")?;
+    let rels = read_zip_entry(archive, "XLSX", "xl/_rels/workbook.xml.rels")?;
+
+    let rels_doc = roxmltree::Document::parse(&rels).context("Invalid workbook relationships")?;
+    let targets: HashMap<&str, &str> = rels_doc
+        .descendants()
+        .filter(|n| n.has_tag_name("Relationship"))
+        .filter_map(|n| Some((n.attribute("Id")?, n.attribute("Target")?)))
+        .collect();
+
+    let doc = roxmltree::Document::parse(&workbook).context("Invalid workbook")?;
+    Ok(doc
+        .descendants()
+        .filter(|n| n.has_tag_name("sheet"))
+        .filter_map(|n| {
+            let name = n.attribute("name")?;
+            let rel_id = n
+                .attributes()
+                .find(|a| a.name() == "id")
+                .map(|a| a.value())?;
+            let target = targets.get(rel_id)?.trim_start_matches('/');
+            let entry = if target.starts_with("xl/") {
+                target.to_string()
+            } else {
+                format!("xl/{}", target)
+            };
+            Some((name.to_string(), entry))
+        })
+        .collect())
+}
+
+fn parse_shared_strings(xml: &str) -> Result<Vec<String>> {
+    let doc = roxmltree::Document::parse(xml).context("Invalid shared strings")?;
+    Ok(doc
+        .descendants()
+        .filter(|n| n.has_tag_name("si"))
+        .map(|si| {
+            si.descendants()
+                .filter(|n| n.has_tag_name("t"))
+                .filter_map(|t| t.text())
+                .collect()
+        })
+        .collect())
+}
+
+fn parse_sheet(xml: &str, shared_strings: &[String]) -> Result<Vec<Vec<String>>> {
+    let doc = roxmltree::Document::parse(xml).context("Invalid worksheet")?;
+
+    let mut rows = Vec::new();
+    for row in doc.descendants().filter(|n| n.has_tag_name("row")) {
+        let mut cells: Vec<String> = Vec::new();
+
+        for cell in row.children().filter(|n| n.has_tag_name("c")) {
+            // Respect the column letter so blank cells keep their place
+            if let Some(column) = cell.attribute("r").map(column_index) {
+                while cells.len() < column {
+                    cells.push(String::new());
+                }
+            }
+
+            let value = cell
+                .children()
+                .find(|n| n.has_tag_name("v"))
+                .and_then(|v| v.text())
+                .unwrap_or("");
+            let text = match cell.attribute("t") {
+                Some("s") => value
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|i| shared_strings.get(i).cloned())
+                    .unwrap_or_default(),
+                Some("inlineStr") => cell
+                    .descendants()
+                    .filter(|n| n.has_tag_name("t"))
+                    .filter_map(|t| t.text())
+                    .collect(),
+                _ => value.to_string(),
+            };
+            cells.push(text);
+        }
+
+        rows.push(cells);
+    }
+
+    Ok(rows)
+}
+
+/// Zero-based column index from a cell reference like "C7"
+fn column_index(reference: &str) -> usize {
+    reference
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .fold(0, |acc, c| {
+            acc * 26 + (c.to_ascii_uppercase() as usize - 'A' as usize + 1)
+        })
+        .saturating_sub(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sheet() {
+        let shared = vec!["Criterion".to_string(), "Points".to_string()];
+        let xml = r#"<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+            <sheetData>
+              <row r="1"><c r="A1" t="s"><v>0</v></c><c r="B1" t="s"><v>1</v></c></row>
+              <row r="2"><c r="A2" t="inlineStr"><is><t>Thesis</t></is></c><c r="C2"><v>10</v></c></row>
+            </sheetData>
+        </worksheet>"#;
+
+        let rows = parse_sheet(xml, &shared).unwrap();
+        assert_eq!(format_rows(&rows), "Criterion | Points\nThesis |  | 10");
+        assert_eq!(column_index("AA3"), 26);
+    }
+}
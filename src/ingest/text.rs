@@ -1,7 +1,104 @@
 use anyhow::{Context, Result};
 use std::path::Path;
 
+use super::Section;
+
 /// Extract text content from a text/markdown file
 pub fn extract(path: &Path) -> Result<String> {
     std::fs::read_to_string(path).with_context(|| format!("Failed to read text file: {:?}", path))
 }
+
+/// A section per Markdown `#` heading (outside code fences), titled with the heading text
+pub fn markdown_sections(text: &str) -> Vec<Section> {
+    let mut sections = Vec::new();
+    let mut in_fence = false;
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let trimmed = line.trim();
+
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let level = trimmed.chars().take_while(|&c| c == '#').count();
+        if !(1..=6).contains(&level) || !trimmed[level..].starts_with(' ') {
+            continue;
+        }
+
+        let title = trimmed[level..].trim().trim_end_matches('#').trim();
+        if !title.is_empty() {
+            sections.push(Section {
+                title: title.to_string(),
+                start,
+            });
+        }
+    }
+
+    sections
+}
+
+/// Locate known headings (e.g. from HTML `<h2>`s) in extracted text, in order,
+/// matching whole lines
+pub fn heading_sections(text: &str, headings: &[String]) -> Vec<Section> {
+    let mut sections = Vec::new();
+    let mut lines = text.split_inclusive('\n').scan(0, |offset, line| {
+        let start = *offset;
+        *offset += line.len();
+        Some((start, line.trim()))
+    });
+
+    for heading in headings {
+        let heading = heading.split_whitespace().collect::<Vec<_>>().join(" ");
+        if heading.is_empty() {
+            continue;
+        }
+        // Headings that never appear (e.g. in a skipped sidebar) are passed over
+        let mut rest = lines.clone();
+        if let Some((start, _)) = rest.find(|(_, line)| *line == heading) {
+            sections.push(Section {
+                title: heading,
+                start,
+            });
+            lines = rest;
+        }
+    }
+
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_sections() {
+        let text = "Intro\n# Cells\nText\n```\n# not a heading\n```\n## Mitosis ##\n#hashtag\n";
+        let sections = markdown_sections(text);
+
+        let titles: Vec<_> = sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, vec!["Cells", "Mitosis"]);
+        assert!(text[sections[1].start..].starts_with("## Mitosis"));
+    }
+
+    #[test]
+    fn test_heading_sections() {
+        let text = "Title\nIntro text\nSetup\nInstall it\nUsage\nRun it";
+        let headings = vec![
+            "Setup".to_string(),
+            "Sidebar".to_string(),
+            "Usage".to_string(),
+        ];
+        let sections = heading_sections(text, &headings);
+
+        let titles: Vec<_> = sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, vec!["Setup", "Usage"]);
+        assert!(text[sections[1].start..].starts_with("Usage"));
+    }
+}
@@ -5,6 +5,10 @@ use scraper::{Html, Selector};
 use std::net::IpAddr;
 use url::Url;
 
+use super::text::heading_sections;
+use super::{Chunk, ChunkConfig, Section, chunk_sections};
+use crate::config::{Config, SiteAuth};
+
 /// Extracted content from a URL
 #[derive(Debug, Clone)]
 pub struct UrlContent {
@@ -12,8 +16,22 @@ pub struct UrlContent {
     pub url: String,
     pub title: String,
     pub text: String,
+    /// Where the page's headings fall in `text`
+    pub sections: Vec<Section>,
 }
 
+impl UrlContent {
+    /// Chunk the page along its headings
+    pub fn chunks(&self, config: &ChunkConfig) -> Vec<Chunk> {
+        chunk_sections(&self.text, &self.sections, config)
+    }
+}
+
+pub(super) const USER_AGENT: &str = "Mozilla/5.0 (compatible; librarian/0.1)";
+
+/// Redirects followed before a fetch gives up
+const MAX_REDIRECTS: usize = 5;
+
 /// Validate URL for SSRF protection
 fn validate_url(url: &Url) -> Result<()> {
     // Only allow http/https schemes
@@ -77,31 +95,299 @@ pub async fn fetch_url(url_str: &str) -> Result<UrlContent> {
         return fetch_youtube_transcript(url_str).await;
     }
 
-    // Fetch the page with redirect policy to prevent SSRF via redirects
+    let (html, _) = fetch_html(&url).await?;
+
+    // Parse and extract content
+    extract_article(&html, url_str)
+}
+
+/// Download a URL's body as text (e.g. a feed's XML), with the same checks
+/// and site auth as a page fetch
+pub async fn fetch_text(url_str: &str) -> Result<String> {
+    let url = Url::parse(url_str).context("Invalid URL")?;
+    validate_url(&url)?;
+
+    let (body, _) = fetch_html(&url).await?;
+    Ok(body)
+}
+
+/// Download a page's HTML, returning it with the final URL after redirects
+async fn fetch_html(url: &Url) -> Result<(String, Url)> {
+    // Redirects are followed by hand, so each hop is checked for SSRF before
+    // it's requested and only gets the credentials configured for its own host
     let client = reqwest::Client::builder()
-        .user_agent("Mozilla/5.0 (compatible; librarian/0.1)")
+        .user_agent(USER_AGENT)
         .timeout(std::time::Duration::from_secs(30))
-        .redirect(reqwest::redirect::Policy::limited(5))
+        .redirect(reqwest::redirect::Policy::none())
         .build()?;
+    let config = Config::load().ok();
 
-    let response = client
-        .get(url_str)
-        .send()
-        .await
-        .context("Failed to fetch URL")?;
+    let mut url = url.clone();
+    for _ in 0..=MAX_REDIRECTS {
+        let mut request = client.get(url.as_str());
+        if let Some(host) = url.host_str()
+            && let Some(auth) = config.as_ref().and_then(|c| c.site_auth(host))
+        {
+            request = request.headers(auth_headers(auth, host, url.scheme() == "https")?);
+        }
+
+        let response = request.send().await.context("Failed to fetch URL")?;
 
-    // Validate final URL after redirects
-    let final_url = response.url();
-    validate_url(final_url).context("Redirect led to blocked URL")?;
+        let location = response
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .and_then(|value| value.to_str().ok());
+        if response.status().is_redirection()
+            && let Some(location) = location
+        {
+            let next = url.join(location).context("Invalid redirect URL")?;
+            validate_url(&next).context("Redirect led to blocked URL")?;
+            url = next;
+            continue;
+        }
 
-    if !response.status().is_success() {
-        anyhow::bail!("HTTP error: {}", response.status());
+        if !response.status().is_success() {
+            anyhow::bail!("HTTP error: {}", response.status());
+        }
+
+        let html = response.text().await.context("Failed to read response")?;
+        return Ok((html, url));
     }
 
-    let html = response.text().await.context("Failed to read response")?;
+    anyhow::bail!("Too many redirects (more than {})", MAX_REDIRECTS)
+}
 
-    // Parse and extract content
-    extract_article(&html, url_str)
+/// Build the headers (and Cookie header) configured for a site. `https`
+/// says whether the request is encrypted; secure-only cookies are left out
+/// when it isn't.
+fn auth_headers(auth: &SiteAuth, host: &str, https: bool) -> Result<reqwest::header::HeaderMap> {
+    use reqwest::header::{COOKIE, HeaderMap, HeaderName, HeaderValue};
+
+    let mut headers = HeaderMap::new();
+
+    for (name, value) in &auth.headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .with_context(|| format!("Invalid header name in config: {}", name))?;
+        let value = HeaderValue::from_str(value)
+            .with_context(|| format!("Invalid value for header {} in config", name))?;
+        headers.insert(name, value);
+    }
+
+    if let Some(path) = &auth.cookies_file {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read cookies file {:?}", path))?;
+        let now = chrono::Utc::now().timestamp();
+        let cookies = parse_cookies(&contents, host, https, now);
+
+        if !cookies.is_empty() {
+            let header = cookies
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect::<Vec<_>>()
+                .join("; ");
+            headers.insert(
+                COOKIE,
+                HeaderValue::from_str(&header).context("Invalid cookie in cookies file")?,
+            );
+        }
+    }
+
+    Ok(headers)
+}
+
+/// Unexpired cookies for a host from a Netscape-format cookies.txt. Cookies
+/// marked secure are only included for `https` requests.
+fn parse_cookies(contents: &str, host: &str, https: bool, now: i64) -> Vec<(String, String)> {
+    let host = host.to_lowercase();
+
+    contents
+        .lines()
+        .filter_map(|line| {
+            // curl and browser exporters prefix HttpOnly cookies this way
+            let line = line.strip_prefix("#HttpOnly_").unwrap_or(line);
+            if line.starts_with('#') || line.trim().is_empty() {
+                return None;
+            }
+
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [
+                domain,
+                include_subdomains,
+                _path,
+                secure,
+                expires,
+                name,
+                value,
+            ] = fields[..]
+            else {
+                return None;
+            };
+
+            let domain = domain.trim_start_matches('.').to_lowercase();
+            let matches = host == domain
+                || (include_subdomains.eq_ignore_ascii_case("TRUE")
+                    && host.ends_with(&format!(".{}", domain)));
+            let expires: i64 = expires.parse().unwrap_or(0);
+            let expired = expires != 0 && expires < now;
+            let allowed = https || !secure.eq_ignore_ascii_case("TRUE");
+
+            (matches && !expired && allowed)
+                .then(|| (name.to_string(), value.trim_end().to_string()))
+        })
+        .collect()
+}
+
+/// How far to follow links when crawling
+#[derive(Debug, Clone, Copy)]
+pub struct CrawlOptions {
+    /// Link hops from the starting page (0 = only the start page)
+    pub depth: usize,
+    /// Only follow links on the starting page's host
+    pub same_domain: bool,
+}
+
+/// A crawled page: its readable content and the links it points to
+#[derive(Debug, Clone)]
+pub struct CrawledPage {
+    pub content: UrlContent,
+    pub links: Vec<String>,
+}
+
+/// Fetch a page for crawling, keeping its outgoing links
+pub async fn fetch_page(url_str: &str) -> Result<CrawledPage> {
+    let url = Url::parse(url_str).context("Invalid URL")?;
+    validate_url(&url)?;
+
+    let (html, final_url) = fetch_html(&url).await?;
+    let links = extract_links(&html, &final_url);
+    let content = extract_article(&html, url_str)?;
+
+    Ok(CrawledPage { content, links })
+}
+
+/// Absolute http(s) links in a page, without fragments, that pass SSRF checks
+fn extract_links(html: &str, base: &Url) -> Vec<String> {
+    let document = Html::parse_document(html);
+    let Ok(selector) = Selector::parse("a[href]") else {
+        return Vec::new();
+    };
+
+    let mut links = Vec::new();
+    for element in document.select(&selector) {
+        let Some(href) = element.value().attr("href") else {
+            continue;
+        };
+        let Ok(mut link) = base.join(href) else {
+            continue;
+        };
+        link.set_fragment(None);
+
+        if validate_url(&link).is_err() {
+            continue;
+        }
+        let link = link.to_string();
+        if !links.contains(&link) {
+            links.push(link);
+        }
+    }
+
+    links
+}
+
+/// Whether two URLs are on the same host
+pub fn same_host(a: &str, b: &str) -> bool {
+    match (Url::parse(a), Url::parse(b)) {
+        (Ok(a), Ok(b)) => a.host_str().is_some() && a.host_str() == b.host_str(),
+        _ => false,
+    }
+}
+
+/// The rules from a site's robots.txt that apply to us
+#[derive(Debug, Clone, Default)]
+pub struct Robots {
+    allow: Vec<String>,
+    disallow: Vec<String>,
+}
+
+impl Robots {
+    /// Parse robots.txt, keeping the groups for `*` and for our own agent
+    fn parse(robots_txt: &str) -> Self {
+        let mut robots = Robots::default();
+        let mut group_applies = false;
+        let mut in_agent_lines = false;
+
+        for line in robots_txt.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((field, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+
+            match field.trim().to_lowercase().as_str() {
+                "user-agent" => {
+                    // Consecutive user-agent lines share one group
+                    if !in_agent_lines {
+                        group_applies = false;
+                    }
+                    in_agent_lines = true;
+                    let agent = value.to_lowercase();
+                    group_applies |= agent == "*" || agent.contains("librarian");
+                }
+                "allow" if group_applies && !value.is_empty() => {
+                    in_agent_lines = false;
+                    robots.allow.push(value.to_string());
+                }
+                "disallow" if group_applies && !value.is_empty() => {
+                    in_agent_lines = false;
+                    robots.disallow.push(value.to_string());
+                }
+                _ => in_agent_lines = false,
+            }
+        }
+
+        robots
+    }
+
+    /// Whether a URL may be fetched (the longest matching rule wins)
+    pub fn allows(&self, url_str: &str) -> bool {
+        let Ok(url) = Url::parse(url_str) else {
+            return false;
+        };
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+
+        let longest = |rules: &[String]| {
+            rules
+                .iter()
+                .filter(|rule| path.starts_with(rule.trim_end_matches('*')))
+                .map(|rule| rule.len())
+                .max()
+        };
+
+        match (longest(&self.allow), longest(&self.disallow)) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(allow), Some(disallow)) => allow >= disallow,
+        }
+    }
+}
+
+/// Fetch and parse robots.txt for a URL's site. A missing or unreadable
+/// robots.txt allows everything.
+pub async fn fetch_robots(url_str: &str) -> Robots {
+    let Ok(mut url) = Url::parse(url_str) else {
+        return Robots::default();
+    };
+    url.set_path("/robots.txt");
+    url.set_query(None);
+    url.set_fragment(None);
+
+    match fetch_html(&url).await {
+        Ok((text, _)) => Robots::parse(&text),
+        Err(_) => Robots::default(),
+    }
 }
 
 /// Check if URL is a YouTube video
@@ -110,6 +396,106 @@ fn is_youtube_url(url: &Url) -> bool {
     host.contains("youtube.com") || host.contains("youtu.be")
 }
 
+/// Check if URL points at a channel or playlist rather than a single video
+pub fn is_youtube_channel_url(url_str: &str) -> bool {
+    let Ok(url) = Url::parse(url_str) else {
+        return false;
+    };
+    if !is_youtube_url(&url) {
+        return false;
+    }
+
+    let path = url.path();
+    path.starts_with("/@")
+        || path.starts_with("/channel/")
+        || path.starts_with("/c/")
+        || path.starts_with("/user/")
+        || path.starts_with("/playlist")
+}
+
+/// Extract the video ID from a YouTube video URL
+pub fn youtube_video_id(url_str: &str) -> Option<String> {
+    let url = Url::parse(url_str).ok()?;
+    if !is_youtube_url(&url) {
+        return None;
+    }
+
+    if url.host_str().unwrap_or("").contains("youtu.be") {
+        return url.path_segments()?.next().map(str::to_string);
+    }
+
+    if let Some((_, id)) = url.query_pairs().find(|(k, _)| k == "v") {
+        return Some(id.into_owned());
+    }
+
+    let mut segments = url.path_segments()?;
+    match segments.next() {
+        Some("shorts" | "live" | "embed") => segments.next().map(str::to_string),
+        _ => None,
+    }
+}
+
+/// A video listed in a channel or playlist
+#[derive(Debug, Clone)]
+pub struct ChannelVideo {
+    pub id: String,
+    pub title: String,
+}
+
+impl ChannelVideo {
+    pub fn url(&self) -> String {
+        format!("https://www.youtube.com/watch?v={}", self.id)
+    }
+}
+
+/// List the videos in a YouTube channel or playlist using yt-dlp,
+/// optionally keeping only titles that contain `filter` (case-insensitive)
+pub async fn list_youtube_channel(url: &str, filter: Option<&str>) -> Result<Vec<ChannelVideo>> {
+    use tokio::process::Command;
+
+    let output = Command::new("yt-dlp")
+        .args(["--flat-playlist", "--print", "%(id)s\t%(title)s", url])
+        .output()
+        .await
+        .context("yt-dlp not found. Install it with: pip install yt-dlp")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "yt-dlp could not list videos: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(parse_channel_listing(
+        &String::from_utf8_lossy(&output.stdout),
+        filter,
+    ))
+}
+
+/// Parse `id<TAB>title` lines from yt-dlp
+fn parse_channel_listing(listing: &str, filter: Option<&str>) -> Vec<ChannelVideo> {
+    let filter = filter.map(|f| f.to_lowercase());
+
+    listing
+        .lines()
+        .filter_map(|line| {
+            let (id, title) = line.split_once('\t')?;
+            let id = id.trim();
+            if id.is_empty() {
+                return None;
+            }
+            Some(ChannelVideo {
+                id: id.to_string(),
+                title: title.trim().to_string(),
+            })
+        })
+        .filter(|video| match &filter {
+            Some(f) => video.title.to_lowercase().contains(f),
+            None => true,
+        })
+        .collect()
+}
+
 /// Extract article content from HTML
 fn extract_article(html: &str, url: &str) -> Result<UrlContent> {
     let document = Html::parse_document(html);
@@ -165,10 +551,22 @@ fn extract_article(html: &str, url: &str) -> Result<UrlContent> {
         anyhow::bail!("Could not extract content from URL");
     }
 
+    // Page headings, in document order, to split the text into sections
+    let headings: Vec<String> = Selector::parse("h1, h2, h3")
+        .map(|selector| {
+            document
+                .select(&selector)
+                .map(|h| h.text().collect::<String>())
+                .collect()
+        })
+        .unwrap_or_default();
+    let sections = heading_sections(&text, &headings);
+
     Ok(UrlContent {
         url: url.to_string(),
         title,
         text,
+        sections,
     })
 }
 
@@ -212,7 +610,7 @@ fn extract_title(document: &Html) -> Option<String> {
 }
 
 /// Extract text from an HTML element, filtering out scripts/styles
-fn extract_text_from_element(element: &scraper::ElementRef) -> String {
+pub(super) fn extract_text_from_element(element: &scraper::ElementRef) -> String {
     let mut text = String::new();
 
     // Tags to skip entirely
@@ -265,7 +663,7 @@ fn extract_text_from_element(element: &scraper::ElementRef) -> String {
 }
 
 /// Clean up extracted text
-fn clean_text(text: &str) -> String {
+pub(super) fn clean_text(text: &str) -> String {
     let mut result = String::new();
     let mut prev_was_newline = false;
     let mut prev_was_space = false;
@@ -409,6 +807,7 @@ async fn fetch_youtube_transcript(url: &str) -> Result<UrlContent> {
         url: url.to_string(),
         title,
         text,
+        sections: Vec::new(),
     })
 }
 
@@ -492,4 +891,97 @@ mod tests {
         ));
         assert!(!is_youtube_url(&Url::parse("https://example.com").unwrap()));
     }
+
+    #[test]
+    fn test_extract_links() {
+        let base = Url::parse("https://wiki.example.edu/course/week1").unwrap();
+        let html = r#"<body>
+            <a href="week2#notes">Week 2</a>
+            <a href="/course/syllabus">Syllabus</a>
+            <a href="week2">Week 2 again</a>
+            <a href="mailto:prof@example.edu">Email</a>
+            <a href="http://127.0.0.1/admin">Local</a>
+        </body>"#;
+
+        assert_eq!(
+            extract_links(html, &base),
+            vec![
+                "https://wiki.example.edu/course/week2",
+                "https://wiki.example.edu/course/syllabus",
+            ]
+        );
+        assert!(same_host(
+            "https://wiki.example.edu/a",
+            "https://wiki.example.edu/b"
+        ));
+        assert!(!same_host(
+            "https://wiki.example.edu/a",
+            "https://example.edu/a"
+        ));
+    }
+
+    #[test]
+    fn test_parse_cookies() {
+        let cookies = "# Netscape HTTP Cookie File\n\
+            .example.edu\tTRUE\t/\tTRUE\t0\tsso\tabc\n\
+            #HttpOnly_canvas.example.edu\tFALSE\t/\tTRUE\t4102444800\tsession\txyz\n\
+            canvas.example.edu\tFALSE\t/\tTRUE\t1000\told\tgone\n\
+            other.org\tTRUE\t/\tFALSE\t0\ttrack\tme\n";
+
+        let found = parse_cookies(cookies, "canvas.example.edu", true, 2_000_000_000);
+        assert_eq!(
+            found,
+            vec![
+                ("sso".to_string(), "abc".to_string()),
+                ("session".to_string(), "xyz".to_string()),
+            ]
+        );
+        assert_eq!(parse_cookies(cookies, "wiki.example.edu", true, 0).len(), 1);
+
+        // Secure cookies never go over plain http
+        assert!(parse_cookies(cookies, "canvas.example.edu", false, 2_000_000_000).is_empty());
+        assert_eq!(
+            parse_cookies(cookies, "www.other.org", false, 0),
+            vec![("track".to_string(), "me".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_robots_rules() {
+        let robots = Robots::parse(
+            "User-agent: googlebot\nDisallow: /\n\n\
+             User-agent: *\nDisallow: /private/\nAllow: /private/syllabus\n",
+        );
+
+        assert!(robots.allows("https://example.edu/course"));
+        assert!(!robots.allows("https://example.edu/private/grades"));
+        assert!(robots.allows("https://example.edu/private/syllabus"));
+    }
+
+    #[test]
+    fn test_youtube_channel_helpers() {
+        assert!(is_youtube_channel_url("https://www.youtube.com/@mitocw"));
+        assert!(is_youtube_channel_url(
+            "https://www.youtube.com/playlist?list=PL123"
+        ));
+        assert!(!is_youtube_channel_url(
+            "https://www.youtube.com/watch?v=abc123"
+        ));
+
+        assert_eq!(
+            youtube_video_id("https://www.youtube.com/watch?v=abc123&t=10"),
+            Some("abc123".to_string())
+        );
+        assert_eq!(
+            youtube_video_id("https://youtu.be/abc123"),
+            Some("abc123".to_string())
+        );
+
+        let videos = parse_channel_listing(
+            "a1\tLecture 1: Intro\nb2\tQ&A session\nc3\tlecture 2\n",
+            Some("Lecture"),
+        );
+        let ids: Vec<_> = videos.iter().map(|v| v.id.as_str()).collect();
+        assert_eq!(ids, vec!["a1", "c3"]);
+    }
 }
@@ -0,0 +1,131 @@
+//! Course vocabulary for transcription: Whisper spells jargon far better when
+//! its prompt already contains the terms a lecture is likely to use.
+
+use std::collections::HashMap;
+
+/// Whisper reads at most 224 tokens of prompt; stay well inside that
+const MAX_PROMPT_CHARS: usize = 600;
+
+/// Terms shorter than this are rarely what Whisper gets wrong
+const MIN_TERM_LEN: usize = 3;
+
+/// Words this long are almost always technical when they recur
+const LONG_WORD_LEN: usize = 11;
+
+/// The terms in `texts` that look like jargon (acronyms, mixed case,
+/// hyphenated compounds, names mid-sentence, long words), most frequent
+/// first. A term must appear at least twice to count.
+pub fn key_terms(texts: &[&str], limit: usize) -> Vec<String> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut first_seen: HashMap<&str, usize> = HashMap::new();
+    let mut position = 0;
+
+    for text in texts {
+        let mut sentence_start = true;
+        for raw in text.split_whitespace() {
+            let word = raw.trim_matches(|c: char| !c.is_alphanumeric());
+            if is_jargon(word, sentence_start) {
+                *counts.entry(word).or_default() += 1;
+                first_seen.entry(word).or_insert(position);
+                position += 1;
+            }
+            sentence_start = raw.ends_with(['.', '!', '?', ':']);
+        }
+    }
+
+    let mut terms: Vec<(&str, usize)> = counts.into_iter().filter(|(_, n)| *n >= 2).collect();
+    terms.sort_by(|a, b| b.1.cmp(&a.1).then(first_seen[a.0].cmp(&first_seen[b.0])));
+    terms
+        .into_iter()
+        .take(limit)
+        .map(|(term, _)| term.to_string())
+        .collect()
+}
+
+fn is_jargon(word: &str, sentence_start: bool) -> bool {
+    if word.len() < MIN_TERM_LEN || word.chars().all(|c| c.is_ascii_digit()) {
+        return false;
+    }
+    let mut chars = word.chars();
+    let first_upper = chars.next().is_some_and(char::is_uppercase);
+    let inner_upper = chars.any(char::is_uppercase);
+
+    // "TCP", "mRNA", "GraphQL"
+    inner_upper
+        // "beta-oxidation", "x86-64"
+        || word.contains('-')
+        // "Krebs", "Dijkstra" (but not "The" opening a sentence)
+        || (first_upper && !sentence_start)
+        // "phosphorylation"
+        || word.chars().count() >= LONG_WORD_LEN
+}
+
+/// A Whisper prompt naming the course and its vocabulary, kept short enough
+/// for the model (None when there's nothing to say)
+pub fn transcription_prompt(course: Option<&str>, terms: &[String]) -> Option<String> {
+    let mut prompt = match course.map(str::trim).filter(|c| !c.is_empty()) {
+        Some(course) => format!("A lecture from {}.", course),
+        None => String::new(),
+    };
+
+    let mut listed = 0;
+    for term in terms {
+        let separator = if listed == 0 {
+            if prompt.is_empty() {
+                "Terms: "
+            } else {
+                " Terms: "
+            }
+        } else {
+            ", "
+        };
+        if prompt.len() + separator.len() + term.len() + 1 > MAX_PROMPT_CHARS {
+            break;
+        }
+        prompt.push_str(separator);
+        prompt.push_str(term);
+        listed += 1;
+    }
+    if listed > 0 {
+        prompt.push('.');
+    }
+
+    (!prompt.is_empty()).then_some(prompt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_terms() {
+        let notes = "The Krebs cycle runs in mitochondria. ATP is made there. \
+                     The cycle feeds NADH to the electron transport chain. \
+                     Beta-oxidation also yields NADH and acetyl-CoA. The Krebs \
+                     cycle consumes acetyl-CoA and regenerates ATP. The end.";
+        let terms = key_terms(&[notes], 10);
+
+        assert_eq!(terms, vec!["Krebs", "ATP", "NADH", "acetyl-CoA"]);
+        // Sentence-initial capitals and one-off words are skipped
+        assert!(!terms.contains(&"The".to_string()));
+        assert!(!terms.contains(&"Beta-oxidation".to_string()));
+    }
+
+    #[test]
+    fn test_transcription_prompt() {
+        let terms = vec!["Krebs".to_string(), "NADH".to_string()];
+        assert_eq!(
+            transcription_prompt(Some("BIO 201"), &terms).as_deref(),
+            Some("A lecture from BIO 201. Terms: Krebs, NADH.")
+        );
+        assert_eq!(
+            transcription_prompt(None, &terms).as_deref(),
+            Some("Terms: Krebs, NADH.")
+        );
+        assert_eq!(transcription_prompt(Some(" "), &[]), None);
+
+        let many: Vec<String> = (0..200).map(|i| format!("term{}", i)).collect();
+        let long = transcription_prompt(None, &many).unwrap();
+        assert!(long.len() <= MAX_PROMPT_CHARS);
+    }
+}
@@ -0,0 +1,356 @@
+//! Claude models through Anthropic's Messages API. Requests use the same
+//! system/user/assistant `Message`s as every other provider; they're
+//! translated here.
+
+use anyhow::{Context, Result};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use super::ChatProvider;
+use super::models::{ModelInfo, ModelList};
+use super::openai::SseLines;
+use super::provider::{self, Message, Usage};
+
+const API_URL: &str = "https://api.anthropic.com/v1/messages";
+const MODELS_URL: &str = "https://api.anthropic.com/v1/models";
+const API_VERSION: &str = "2023-06-01";
+
+/// Claude models: (id, description, context_window_tokens)
+pub const MODELS: &[(&str, &str, usize)] = &[
+    (
+        "claude-sonnet-4-5",
+        "Claude Sonnet 4.5 - Best balance",
+        200_000,
+    ),
+    ("claude-haiku-4-5", "Claude Haiku 4.5 - Fastest", 200_000),
+    ("claude-opus-4-1", "Claude Opus 4.1 - Most capable", 200_000),
+];
+
+/// Model used when none is configured
+pub const DEFAULT_MODEL: &str = "claude-sonnet-4-5";
+
+#[derive(Debug, Serialize)]
+struct MessagesRequest<'a> {
+    model: &'a str,
+    max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    messages: Vec<Message>,
+    temperature: f32,
+    stream: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessagesResponse {
+    content: Vec<ContentBlock>,
+    usage: Option<AnthropicUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentBlock {
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AnthropicUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+}
+
+/// One server-sent event from a streamed response
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamEvent {
+    MessageStart {
+        message: StreamMessage,
+    },
+    ContentBlockDelta {
+        delta: Delta,
+    },
+    MessageDelta {
+        usage: AnthropicUsage,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamMessage {
+    usage: AnthropicUsage,
+}
+
+#[derive(Debug, Deserialize)]
+struct Delta {
+    #[serde(default)]
+    text: String,
+}
+
+/// A chat client for Claude
+#[derive(Debug, Clone)]
+pub struct AnthropicClient {
+    http: reqwest::Client,
+    api_key: String,
+    model: String,
+    /// Token counts for the most recent request
+    last_usage: Arc<Mutex<Option<Usage>>>,
+}
+
+impl AnthropicClient {
+    pub fn new(api_key: String, model: String) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            api_key,
+            model,
+            last_usage: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Remember a request's usage and log the call
+    fn record_usage(&self, usage: Option<Usage>, started: Instant) {
+        *self.last_usage.lock().unwrap() = usage;
+        provider::log_call("Anthropic", &self.model, usage, "chat", started);
+    }
+
+    async fn send(&self, messages: &[Message], stream: bool) -> Result<reqwest::Response> {
+        let (system, messages) = translate(messages);
+        let request = MessagesRequest {
+            model: &self.model,
+            max_tokens: 4096,
+            system,
+            messages,
+            temperature: 0.7,
+            stream,
+        };
+
+        let response = with_auth(self.http.post(API_URL), &self.api_key)
+            .json(&request)
+            .send()
+            .await
+            .context("Failed to send request to Anthropic")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            anyhow::bail!("Anthropic API error ({}): {}", status, text);
+        }
+
+        Ok(response)
+    }
+
+    /// Send a conversation and wait for the whole reply. With `json`, the
+    /// reply is started with `{` for Claude to continue, since the Messages
+    /// API has no JSON mode.
+    async fn complete(&self, messages: &[Message], json: bool) -> Result<String> {
+        let started = Instant::now();
+        let mut messages = messages.to_vec();
+        if json {
+            messages.push(Message {
+                role: "assistant".to_string(),
+                content: "{".to_string(),
+            });
+        }
+
+        let response: MessagesResponse = self
+            .send(&messages, false)
+            .await?
+            .json()
+            .await
+            .context("Failed to parse Anthropic response")?;
+
+        let mut text: String = response.content.iter().map(|b| b.text.as_str()).collect();
+        if text.is_empty() {
+            anyhow::bail!("No response from Anthropic");
+        }
+        if json {
+            text.insert(0, '{');
+        }
+        let usage = response.usage.map(|u| Usage {
+            prompt_tokens: u.input_tokens,
+            completion_tokens: u.output_tokens,
+        });
+        self.record_usage(usage, started);
+
+        Ok(text)
+    }
+}
+
+impl ChatProvider for AnthropicClient {
+    fn name(&self) -> &'static str {
+        "Anthropic"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn set_model(&mut self, model: String) {
+        self.model = model;
+    }
+
+    fn models(&self) -> &'static [(&'static str, &'static str, usize)] {
+        MODELS
+    }
+
+    async fn fetch_models(&self) -> Result<Vec<ModelInfo>> {
+        let list: ModelList = with_auth(self.http.get(MODELS_URL), &self.api_key)
+            .send()
+            .await
+            .context("Failed to reach Anthropic")?
+            .error_for_status()?
+            .json()
+            .await
+            .context("Failed to parse Anthropic's model list")?;
+
+        Ok(list.chat_models())
+    }
+
+    fn last_usage(&self) -> Option<Usage> {
+        *self.last_usage.lock().unwrap()
+    }
+
+    async fn chat(&self, messages: &[Message]) -> Result<String> {
+        self.complete(messages, false).await
+    }
+
+    async fn chat_json(&self, messages: &[Message]) -> Result<String> {
+        self.complete(messages, true).await
+    }
+
+    /// Hand each piece of the reply to `on_token` as it arrives
+    async fn chat_stream_with(
+        &self,
+        messages: &[Message],
+        mut on_token: impl FnMut(&str),
+    ) -> Result<String> {
+        let started = Instant::now();
+        let response = self.send(messages, true).await?;
+
+        let mut full_response = String::new();
+        let mut usage = Usage::default();
+        let mut stream = response.bytes_stream();
+        // Events can be split across network chunks
+        let mut lines = SseLines::default();
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.context("Failed to read stream chunk")?;
+
+            for line in lines.push(&chunk) {
+                let Some(data) = line.strip_prefix("data: ") else {
+                    continue;
+                };
+                match serde_json::from_str::<StreamEvent>(data) {
+                    Ok(StreamEvent::MessageStart { message }) => {
+                        usage.prompt_tokens = message.usage.input_tokens;
+                    }
+                    Ok(StreamEvent::ContentBlockDelta { delta }) => {
+                        on_token(&delta.text);
+                        full_response.push_str(&delta.text);
+                    }
+                    Ok(StreamEvent::MessageDelta { usage: delta }) => {
+                        usage.completion_tokens = delta.output_tokens;
+                    }
+                    Ok(StreamEvent::Other) | Err(_) => {}
+                }
+            }
+        }
+
+        self.record_usage(Some(usage), started);
+        Ok(full_response)
+    }
+}
+
+/// Split messages into Claude's separate system prompt and a conversation
+/// that starts with the user and alternates roles (consecutive messages from
+/// the same role are joined)
+fn translate(messages: &[Message]) -> (Option<String>, Vec<Message>) {
+    let mut system: Vec<&str> = Vec::new();
+    let mut turns: Vec<Message> = Vec::new();
+
+    for message in messages {
+        if message.role == "system" {
+            system.push(&message.content);
+            continue;
+        }
+        match turns.last_mut() {
+            Some(last) if last.role == message.role => {
+                last.content.push_str("\n\n");
+                last.content.push_str(&message.content);
+            }
+            _ => turns.push(message.clone()),
+        }
+    }
+
+    // The conversation has to open with a user turn
+    if turns.first().is_some_and(|m| m.role != "user") {
+        turns.insert(
+            0,
+            Message {
+                role: "user".to_string(),
+                content: "(conversation continues)".to_string(),
+            },
+        );
+    }
+
+    let system = (!system.is_empty()).then(|| system.join("\n\n"));
+    (system, turns)
+}
+
+/// Add the headers every Anthropic request needs
+fn with_auth(request: reqwest::RequestBuilder, api_key: &str) -> reqwest::RequestBuilder {
+    request
+        .header("x-api-key", api_key)
+        .header("anthropic-version", API_VERSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: &str) -> Message {
+        Message {
+            role: role.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_translate_messages() {
+        let (system, turns) = translate(&[
+            message("system", "You are The Librarian."),
+            message(
+                "system",
+                "Summary of the earlier part of this conversation: ATP.",
+            ),
+            message("user", "ATTACHED FILE: hw.pdf"),
+            message("user", "What is question 2 asking?"),
+            message("assistant", "It asks about ATP."),
+            message("user", "Thanks"),
+        ]);
+
+        assert_eq!(
+            system.as_deref(),
+            Some(
+                "You are The Librarian.\n\nSummary of the earlier part of this conversation: ATP."
+            )
+        );
+        let roles: Vec<&str> = turns.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, vec!["user", "assistant", "user"]);
+        assert_eq!(
+            turns[0].content,
+            "ATTACHED FILE: hw.pdf\n\nWhat is question 2 asking?"
+        );
+    }
+
+    #[test]
+    fn test_translate_opens_with_user() {
+        let (system, turns) = translate(&[message("assistant", "Hello")]);
+        assert!(system.is_none());
+        assert_eq!(turns[0].role, "user");
+        assert_eq!(turns[1].role, "assistant");
+    }
+}
@@ -0,0 +1,199 @@
+//! Offline transcription with whisper.cpp. The model only runs in builds with
+//! the `local-whisper` feature; other builds explain how to enable it.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use super::whisper::{TranscribeOptions, Transcript, check_ffmpeg, record_transcription};
+
+/// Sample rate whisper.cpp expects
+const SAMPLE_RATE: &str = "16000";
+
+const MODEL_URL: &str =
+    "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.en.bin";
+
+pub struct LocalWhisper {
+    model_path: PathBuf,
+}
+
+impl LocalWhisper {
+    pub fn new(model_path: PathBuf) -> Self {
+        Self { model_path }
+    }
+
+    /// Whether this build can run Whisper locally
+    pub fn is_available() -> bool {
+        cfg!(feature = "local-whisper")
+    }
+
+    /// Transcribe an audio or video file on this machine
+    pub async fn transcribe(
+        &self,
+        file_path: &Path,
+        options: &TranscribeOptions,
+    ) -> Result<Transcript> {
+        if !Self::is_available() {
+            anyhow::bail!(
+                "This build doesn't include local transcription. Reinstall with:\n  \
+                 cargo install --path . --features local-whisper\n\
+                 (building whisper.cpp needs cmake and clang)"
+            );
+        }
+
+        if !self.model_path.exists() {
+            anyhow::bail!(
+                "Whisper model not found at {:?}. Download one with:\n  \
+                 curl -L --create-dirs -o {:?} {}\n\
+                 or choose another model file under `librarian config`.",
+                self.model_path,
+                self.model_path,
+                MODEL_URL
+            );
+        }
+
+        let started = Instant::now();
+        let samples = decode_audio(file_path).await?;
+        let model_path = self.model_path.clone();
+        let options = options.clone();
+
+        let transcript =
+            tokio::task::spawn_blocking(move || run_model(&model_path, &samples, &options))
+                .await
+                .context("Transcription task failed")??;
+
+        let model = self
+            .model_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        record_transcription("Local", &model, started);
+        Ok(transcript)
+    }
+}
+
+/// Decode any audio/video file to 16 kHz mono f32 samples using ffmpeg
+async fn decode_audio(file_path: &Path) -> Result<Vec<f32>> {
+    if !check_ffmpeg().await {
+        anyhow::bail!(
+            "ffmpeg is required for local transcription. Install it with:\n\
+             - Arch: sudo pacman -S ffmpeg\n\
+             - Ubuntu: sudo apt install ffmpeg\n\
+             - macOS: brew install ffmpeg"
+        );
+    }
+
+    let canonical = std::fs::canonicalize(file_path)
+        .with_context(|| format!("Failed to resolve path: {:?}", file_path))?;
+    let input_str = canonical
+        .to_str()
+        .ok_or_else(|| anyhow::anyhow!("Invalid UTF-8 in audio path"))?;
+
+    let output = tokio::process::Command::new("ffmpeg")
+        .args([
+            "-nostdin",
+            "-i",
+            input_str,
+            "-vn", // No video
+            "-f",
+            "f32le", // Raw 32-bit float samples
+            "-ac",
+            "1", // Mono
+            "-ar",
+            SAMPLE_RATE,
+            "-",
+        ])
+        .stderr(std::process::Stdio::null())
+        .output()
+        .await
+        .context("Failed to run ffmpeg")?;
+
+    if !output.status.success() {
+        anyhow::bail!("ffmpeg failed to decode audio from {:?}", file_path);
+    }
+
+    Ok(output
+        .stdout
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+#[cfg(feature = "local-whisper")]
+fn run_model(
+    model_path: &Path,
+    samples: &[f32],
+    options: &TranscribeOptions,
+) -> Result<Transcript> {
+    use super::whisper::TranscriptSegment;
+    use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+    let model = model_path
+        .to_str()
+        .ok_or_else(|| anyhow::anyhow!("Invalid UTF-8 in model path"))?;
+    let context = WhisperContext::new_with_params(model, WhisperContextParameters::default())
+        .context("Failed to load Whisper model")?;
+    let mut state = context
+        .create_state()
+        .context("Failed to initialize Whisper")?;
+
+    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_special(false);
+    params.set_print_timestamps(false);
+    // English-only (.en) models ignore both
+    params.set_language(Some(options.language.as_deref().unwrap_or("auto")));
+    params.set_translate(options.translate);
+    if let Some(prompt) = &options.prompt {
+        params.set_initial_prompt(prompt);
+    }
+    let threads = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4);
+    params.set_n_threads(threads as i32);
+
+    state
+        .full(params, samples)
+        .context("Whisper transcription failed")?;
+
+    let segments = state
+        .full_n_segments()
+        .context("Whisper returned no output")?;
+    let mut transcript = Transcript::default();
+    for i in 0..segments {
+        let text = state
+            .full_get_segment_text_lossy(i)
+            .context("Failed to read transcript segment")?;
+        // Segment times are in centiseconds
+        let start = state
+            .full_get_segment_t0(i)
+            .context("Failed to read segment time")?;
+
+        let text = text.trim();
+        if !text.is_empty() {
+            transcript.segments.push(TranscriptSegment {
+                start: start as f64 / 100.0,
+                text: text.to_string(),
+            });
+        }
+    }
+
+    transcript.text = transcript
+        .segments
+        .iter()
+        .map(|s| s.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Ok(transcript)
+}
+
+#[cfg(not(feature = "local-whisper"))]
+fn run_model(
+    _model_path: &Path,
+    _samples: &[f32],
+    _options: &TranscribeOptions,
+) -> Result<Transcript> {
+    anyhow::bail!("Local transcription is not available in this build")
+}
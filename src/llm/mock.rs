@@ -0,0 +1,141 @@
+//! A stand-in chat model with canned, deterministic replies, for trying the
+//! commands offline (set `LIBRARIAN_MOCK_LLM=1`) and for tests.
+
+use anyhow::Result;
+
+use super::models::ModelInfo;
+use super::provider::{ChatProvider, Message, Usage};
+
+/// Set to anything but "" or "0" to use `MockProvider` whatever the config says
+pub const ENV_VAR: &str = "LIBRARIAN_MOCK_LLM";
+
+/// Whether `ENV_VAR` asks for the mock provider
+pub fn enabled() -> bool {
+    std::env::var(ENV_VAR).is_ok_and(|v| !v.is_empty() && v != "0")
+}
+
+#[derive(Debug, Clone)]
+pub struct MockProvider {
+    model: String,
+}
+
+impl MockProvider {
+    pub const MODELS: &'static [(&'static str, &'static str, usize)] =
+        &[("mock", "Canned replies - no model involved", 8192)];
+
+    pub fn new() -> Self {
+        Self {
+            model: "mock".to_string(),
+        }
+    }
+}
+
+impl Default for MockProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The JSON object a generation prompt asks for, picked by the shape its
+/// system message describes (a quiz when nothing else matches)
+fn canned_json(messages: &[Message]) -> serde_json::Value {
+    let system = messages
+        .iter()
+        .filter(|m| m.role == "system")
+        .map(|m| m.content.as_str())
+        .collect::<String>();
+
+    if system.contains("{\"flashcards\"") {
+        serde_json::json!({ "flashcards": [
+            { "question": "What does ATP stand for?", "answer": "Adenosine triphosphate" },
+            { "question": "Where is most ATP made?", "answer": "In the mitochondria" },
+        ]})
+    } else if system.contains("{\"cloze\"") {
+        serde_json::json!({ "cloze": [
+            { "text": "Most {{ATP}} is made in the {{mitochondria}}." },
+        ]})
+    } else if system.contains("{\"problems\"") {
+        serde_json::json!({ "problems": [
+            { "question": "A cell makes 30 ATP per glucose. How many from 4 glucose?",
+              "solution": "30 ATP per glucose x 4 glucose = 120 ATP.",
+              "answer": "120" },
+        ]})
+    } else if system.contains("{\"topics\"") {
+        serde_json::json!({ "topics": [
+            { "title": "Cellular respiration", "documents": [], "tasks": ["Review glycolysis"], "effort": 2 },
+        ]})
+    } else {
+        serde_json::json!({ "questions": [
+            { "type": "multiple_choice", "question": "Where is most ATP made?",
+              "options": ["Nucleus", "Mitochondria", "Ribosome", "Golgi apparatus"], "answer": "b" },
+            { "type": "fill_in_blank", "question": "ATP stands for adenosine _______.",
+              "answer": "triphosphate" },
+            { "type": "short_answer", "question": "What is ATP used for?",
+              "answer": "storing and moving energy in cells" },
+        ]})
+    }
+}
+
+impl ChatProvider for MockProvider {
+    fn name(&self) -> &'static str {
+        "Mock"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn set_model(&mut self, model: String) {
+        self.model = model;
+    }
+
+    fn models(&self) -> &'static [(&'static str, &'static str, usize)] {
+        Self::MODELS
+    }
+
+    async fn fetch_models(&self) -> Result<Vec<ModelInfo>> {
+        Ok(Self::MODELS
+            .iter()
+            .map(|(id, _, context_window)| ModelInfo {
+                id: id.to_string(),
+                context_window: Some(*context_window),
+            })
+            .collect())
+    }
+
+    fn last_usage(&self) -> Option<Usage> {
+        None
+    }
+
+    /// Repeats the last line it was asked (where the question sits after any
+    /// retrieved context), so replies are easy to tell apart
+    async fn chat(&self, messages: &[Message]) -> Result<String> {
+        let question = messages
+            .iter()
+            .rev()
+            .find(|m| m.role == "user")
+            .and_then(|m| m.content.lines().rev().find(|line| !line.trim().is_empty()))
+            .unwrap_or("")
+            .trim();
+        Ok(format!(
+            "This is a canned reply from the mock provider to: {}",
+            question
+        ))
+    }
+
+    async fn chat_json(&self, messages: &[Message]) -> Result<String> {
+        Ok(canned_json(messages).to_string())
+    }
+
+    async fn chat_stream_with(
+        &self,
+        messages: &[Message],
+        mut on_token: impl FnMut(&str),
+    ) -> Result<String> {
+        let reply = self.chat(messages).await?;
+        for word in reply.split_inclusive(' ') {
+            on_token(word);
+        }
+        Ok(reply)
+    }
+}
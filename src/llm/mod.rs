@@ -1,4 +1,130 @@
+pub mod anthropic;
 pub mod groq;
+pub mod local_whisper;
+pub mod mock;
+pub mod models;
+pub mod ollama;
+pub mod openai;
+pub mod provider;
+pub mod schema;
+pub mod tokens;
 pub mod whisper;
 
+pub use anthropic::AnthropicClient;
 pub use groq::GroqClient;
+pub use mock::MockProvider;
+pub use ollama::OllamaClient;
+pub use openai::OpenAiClient;
+pub use provider::{ChatProvider, Message, Usage};
+
+use anyhow::Result;
+
+use crate::config::{Config, Provider};
+use models::ModelInfo;
+
+/// The client for whichever provider is configured
+#[derive(Debug, Clone)]
+pub enum Client {
+    Groq(GroqClient),
+    Anthropic(AnthropicClient),
+    OpenAi(OpenAiClient),
+    Ollama(OllamaClient),
+    Mock(MockProvider),
+}
+
+/// A client for the configured provider (None when a hosted provider is
+/// selected but has no API key, or a custom server has no URL or model).
+/// `LIBRARIAN_MOCK_LLM=1` swaps in the canned `MockProvider`.
+pub fn client(config: &Config) -> Option<Client> {
+    if mock::enabled() {
+        return Some(Client::Mock(MockProvider::new()));
+    }
+
+    let model = config.chat_model();
+    match config.provider {
+        Provider::Groq => config
+            .get_api_key()
+            .map(|key| Client::Groq(GroqClient::new(key, model))),
+        Provider::Anthropic => config.anthropic_api_key().map(|key| {
+            Client::Anthropic(AnthropicClient::new(
+                key,
+                model.unwrap_or_else(|| anthropic::DEFAULT_MODEL.to_string()),
+            ))
+        }),
+        Provider::OpenAi => config
+            .openai_base_url
+            .as_deref()
+            .filter(|url| !url.is_empty())
+            .zip(model)
+            .map(|(url, model)| {
+                Client::OpenAi(OpenAiClient::new(url, config.openai_api_key(), model))
+            }),
+        Provider::Ollama => Some(Client::Ollama(OllamaClient::new(
+            &config.ollama_url(),
+            model.unwrap_or_else(|| ollama::DEFAULT_MODEL.to_string()),
+        ))),
+    }
+}
+
+/// Run the same expression against whichever client is inside
+macro_rules! each_client {
+    ($client:expr, $inner:ident => $body:expr) => {
+        match $client {
+            Client::Groq($inner) => $body,
+            Client::Anthropic($inner) => $body,
+            Client::OpenAi($inner) => $body,
+            Client::Ollama($inner) => $body,
+            Client::Mock($inner) => $body,
+        }
+    };
+}
+
+impl ChatProvider for Client {
+    fn name(&self) -> &'static str {
+        each_client!(self, c => c.name())
+    }
+
+    fn model(&self) -> &str {
+        each_client!(self, c => c.model())
+    }
+
+    fn set_model(&mut self, model: String) {
+        each_client!(self, c => c.set_model(model))
+    }
+
+    fn models(&self) -> &'static [(&'static str, &'static str, usize)] {
+        each_client!(self, c => c.models())
+    }
+
+    fn context_window(&self) -> usize {
+        each_client!(self, c => c.context_window())
+    }
+
+    async fn fetch_models(&self) -> Result<Vec<ModelInfo>> {
+        each_client!(self, c => c.fetch_models().await)
+    }
+
+    fn cache_key(&self) -> String {
+        each_client!(self, c => c.cache_key())
+    }
+
+    fn last_usage(&self) -> Option<Usage> {
+        each_client!(self, c => c.last_usage())
+    }
+
+    async fn chat(&self, messages: &[Message]) -> Result<String> {
+        each_client!(self, c => c.chat(messages).await)
+    }
+
+    async fn chat_json(&self, messages: &[Message]) -> Result<String> {
+        each_client!(self, c => c.chat_json(messages).await)
+    }
+
+    async fn chat_stream_with(
+        &self,
+        messages: &[Message],
+        on_token: impl FnMut(&str),
+    ) -> Result<String> {
+        each_client!(self, c => c.chat_stream_with(messages, on_token).await)
+    }
+}
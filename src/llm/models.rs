@@ -0,0 +1,155 @@
+//! Models a provider currently offers, fetched from its `/models` endpoint and
+//! cached so the list is still there offline.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use super::ChatProvider;
+use crate::config::Config;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelInfo {
+    pub id: String,
+    /// Context window in tokens, when the provider reports it
+    #[serde(default)]
+    pub context_window: Option<usize>,
+}
+
+/// Where a model list came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// Just fetched from the provider
+    Live,
+    /// Saved the last time the provider answered
+    Cache,
+    /// The list built into The Librarian
+    BuiltIn,
+}
+
+/// `/models` responses from OpenAI-compatible servers and Anthropic
+#[derive(Debug, Deserialize)]
+pub(super) struct ModelList {
+    pub data: Vec<ListedModel>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct ListedModel {
+    pub id: String,
+    /// Groq's extension
+    #[serde(default)]
+    pub context_window: Option<usize>,
+    /// Groq marks retired models inactive
+    #[serde(default)]
+    pub active: Option<bool>,
+}
+
+impl ModelList {
+    /// Chat models only (Groq also lists speech and moderation models)
+    pub(super) fn chat_models(self) -> Vec<ModelInfo> {
+        let mut models: Vec<ModelInfo> = self
+            .data
+            .into_iter()
+            .filter(|m| m.active != Some(false))
+            .filter(|m| !["whisper", "tts", "guard"].iter().any(|s| m.id.contains(s)))
+            .map(|m| ModelInfo {
+                id: m.id,
+                context_window: m.context_window,
+            })
+            .collect();
+        models.sort_by(|a, b| a.id.cmp(&b.id));
+        models
+    }
+}
+
+/// The provider's models: fetched live and cached, falling back to the last
+/// cached list and then the built-in one
+pub async fn list(client: &impl ChatProvider) -> (Vec<ModelInfo>, Source) {
+    match client.fetch_models().await {
+        Ok(models) if !models.is_empty() => {
+            if let Err(e) = save_cache(&client.cache_key(), &models) {
+                eprintln!("Note: Could not cache the model list: {}", e);
+            }
+            (models, Source::Live)
+        }
+        _ => known(client),
+    }
+}
+
+/// The provider's models without asking it: the last cached list, then the
+/// built-in one
+pub fn known(client: &impl ChatProvider) -> (Vec<ModelInfo>, Source) {
+    match load_cache().remove(&client.cache_key()) {
+        Some(models) if !models.is_empty() => (models, Source::Cache),
+        _ => (
+            client
+                .models()
+                .iter()
+                .map(|(id, _, ctx)| ModelInfo {
+                    id: id.to_string(),
+                    context_window: Some(*ctx),
+                })
+                .collect(),
+            Source::BuiltIn,
+        ),
+    }
+}
+
+/// Context window the provider last reported for `model` (`cache_key` is
+/// the provider's `ChatProvider::cache_key`)
+pub fn cached_context_window(cache_key: &str, model: &str) -> Option<usize> {
+    load_cache()
+        .remove(cache_key)?
+        .into_iter()
+        .find(|m| m.id == model)?
+        .context_window
+}
+
+fn cache_path() -> Result<PathBuf> {
+    Ok(Config::data_dir()?.join("models.json"))
+}
+
+/// Cached model lists by provider cache key
+fn load_cache() -> HashMap<String, Vec<ModelInfo>> {
+    cache_path()
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save_cache(cache_key: &str, models: &[ModelInfo]) -> Result<()> {
+    let mut cache = load_cache();
+    cache.insert(cache_key.to_string(), models.to_vec());
+    let path = cache_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(&cache)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chat_models() {
+        let list: ModelList = serde_json::from_str(
+            r#"{"object": "list", "data": [
+                {"id": "whisper-large-v3", "active": true, "context_window": 448},
+                {"id": "mixtral-8x7b-32768", "active": false, "context_window": 32768},
+                {"id": "llama-3.3-70b-versatile", "active": true, "context_window": 131072},
+                {"id": "claude-sonnet-4-5", "display_name": "Claude Sonnet 4.5"}
+            ]}"#,
+        )
+        .unwrap();
+
+        let models = list.chat_models();
+        let ids: Vec<&str> = models.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["claude-sonnet-4-5", "llama-3.3-70b-versatile"]);
+        assert_eq!(models[1].context_window, Some(131072));
+        assert_eq!(models[0].context_window, None);
+    }
+}
@@ -0,0 +1,112 @@
+//! Local models served by Ollama (https://ollama.com), reached through its
+//! OpenAI-compatible API.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use super::ChatProvider;
+use super::models::ModelInfo;
+use super::openai::OpenAiCompat;
+use super::provider::{Message, Usage};
+
+/// Where `ollama serve` listens by default
+pub const DEFAULT_URL: &str = "http://localhost:11434";
+
+/// Model used when none is configured
+pub const DEFAULT_MODEL: &str = "llama3.2";
+
+#[derive(Debug, Deserialize)]
+struct TagsResponse {
+    models: Vec<Tag>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Tag {
+    name: String,
+}
+
+/// A chat client for the Ollama server at `base_url`
+#[derive(Debug, Clone)]
+pub struct OllamaClient {
+    api: OpenAiCompat,
+}
+
+impl OllamaClient {
+    pub fn new(base_url: &str, model: String) -> Self {
+        Self {
+            api: OpenAiCompat::new(
+                "Ollama",
+                format!("{}/v1/chat/completions", base_url.trim_end_matches('/')),
+                None,
+                model,
+            ),
+        }
+    }
+}
+
+impl ChatProvider for OllamaClient {
+    fn name(&self) -> &'static str {
+        "Ollama"
+    }
+
+    fn model(&self) -> &str {
+        &self.api.model
+    }
+
+    fn set_model(&mut self, model: String) {
+        self.api.model = model;
+    }
+
+    /// Whatever has been pulled; see `installed_models`
+    fn models(&self) -> &'static [(&'static str, &'static str, usize)] {
+        &[]
+    }
+
+    async fn fetch_models(&self) -> Result<Vec<ModelInfo>> {
+        self.api.fetch_models().await
+    }
+
+    fn cache_key(&self) -> String {
+        self.api.cache_key()
+    }
+
+    fn last_usage(&self) -> Option<Usage> {
+        self.api.last_usage()
+    }
+
+    async fn chat(&self, messages: &[Message]) -> Result<String> {
+        self.api.complete(messages, false).await
+    }
+
+    async fn chat_json(&self, messages: &[Message]) -> Result<String> {
+        self.api.complete(messages, true).await
+    }
+
+    async fn chat_stream_with(
+        &self,
+        messages: &[Message],
+        on_token: impl FnMut(&str),
+    ) -> Result<String> {
+        self.api.complete_stream(messages, on_token).await
+    }
+}
+
+/// Models pulled into the Ollama server at `base_url`
+pub async fn installed_models(base_url: &str) -> Result<Vec<String>> {
+    let url = format!("{}/api/tags", base_url.trim_end_matches('/'));
+    let response = reqwest::get(&url)
+        .await
+        .with_context(|| {
+            format!(
+                "Could not reach Ollama at {} (is `ollama serve` running?)",
+                base_url
+            )
+        })?
+        .error_for_status()?;
+    let tags: TagsResponse = response
+        .json()
+        .await
+        .context("Failed to parse Ollama's model list")?;
+
+    Ok(tags.models.into_iter().map(|t| t.name).collect())
+}
@@ -0,0 +1,434 @@
+//! The OpenAI chat completions API. Groq, Ollama and any OpenAI-compatible
+//! server (OpenRouter, vLLM, LM Studio, llama.cpp's server, ...) speak it, so
+//! `OpenAiCompat` makes the requests for all of them; `OpenAiClient` is the
+//! provider for a custom server.
+
+#![allow(clippy::collapsible_if)]
+
+use anyhow::{Context, Result};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use super::models::{ModelInfo, ModelList};
+use super::provider::{self, ChatProvider, Message, Usage};
+
+#[derive(Debug, Serialize)]
+struct ChatRequest {
+    model: String,
+    messages: Vec<Message>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    /// `{"type": "json_object"}` for JSON mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<serde_json::Value>,
+    stream: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+    usage: Option<Usage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Choice {
+    message: Message,
+}
+
+/// Streaming response chunk
+#[derive(Debug, Deserialize)]
+struct StreamChunk {
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    /// Groq reports usage in its own field on the final chunk
+    x_groq: Option<GroqExtension>,
+    /// Other OpenAI-compatible servers report it on the chunk itself
+    usage: Option<Usage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GroqExtension {
+    usage: Option<Usage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamChoice {
+    delta: Delta,
+    #[allow(dead_code)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Delta {
+    content: Option<String>,
+}
+
+/// Splits a server-sent event stream into lines. Network chunks can end
+/// mid-line (or mid-character), so the unfinished tail is kept for the next one.
+#[derive(Debug, Default)]
+pub(super) struct SseLines {
+    buffer: Vec<u8>,
+}
+
+impl SseLines {
+    /// Add a chunk and return the lines it completed, without line endings
+    pub(super) fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+
+        let Some(last_newline) = self.buffer.iter().rposition(|b| *b == b'\n') else {
+            return Vec::new();
+        };
+        let complete: Vec<u8> = self.buffer.drain(..=last_newline).collect();
+        String::from_utf8_lossy(&complete)
+            .lines()
+            .map(|line| line.trim_end_matches('\r').to_string())
+            .collect()
+    }
+}
+
+/// Requests to one OpenAI-compatible chat completions endpoint
+#[derive(Debug, Clone)]
+pub(super) struct OpenAiCompat {
+    http: reqwest::Client,
+    /// Who serves the requests, for messages ("Groq", "Ollama", ...)
+    provider: &'static str,
+    /// Chat endpoint URL
+    endpoint: String,
+    api_key: Option<String>,
+    pub(super) model: String,
+    /// Token counts for the most recent request
+    last_usage: Arc<Mutex<Option<Usage>>>,
+}
+
+impl OpenAiCompat {
+    pub(super) fn new(
+        provider: &'static str,
+        endpoint: String,
+        api_key: Option<String>,
+        model: String,
+    ) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            provider,
+            endpoint,
+            api_key,
+            model,
+            last_usage: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Add the bearer token, when there is one
+    fn with_auth(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.api_key {
+            Some(key) => request.header("Authorization", format!("Bearer {}", key)),
+            None => request,
+        }
+    }
+
+    /// POST a JSON body to the chat endpoint, turning error statuses into errors
+    async fn send(&self, body: &impl Serialize) -> Result<reqwest::Response> {
+        let response = self
+            .with_auth(self.http.post(&self.endpoint))
+            .header("Content-Type", "application/json")
+            .json(body)
+            .send()
+            .await
+            .with_context(|| format!("Failed to send request to {}", self.provider))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            anyhow::bail!("{} API error ({}): {}", self.provider, status, text);
+        }
+
+        Ok(response)
+    }
+
+    /// Remember a request's usage and log the call ("chat" or "vision")
+    fn record_usage(&self, usage: Option<Usage>, kind: &str, started: Instant) {
+        *self.last_usage.lock().unwrap() = usage;
+        provider::log_call(self.provider, &self.model, usage, kind, started);
+    }
+
+    pub(super) fn last_usage(&self) -> Option<Usage> {
+        *self.last_usage.lock().unwrap()
+    }
+
+    /// Model cache key: every server of a kind shares a name, so add its URL
+    pub(super) fn cache_key(&self) -> String {
+        format!("{} {}", self.provider, self.endpoint)
+    }
+
+    /// Read a non-streamed reply and log its usage
+    async fn reply(
+        &self,
+        response: reqwest::Response,
+        kind: &str,
+        started: Instant,
+    ) -> Result<String> {
+        let chat_response: ChatResponse = response
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse {} response", self.provider))?;
+        self.record_usage(chat_response.usage, kind, started);
+
+        chat_response
+            .choices
+            .first()
+            .map(|c| c.message.content.clone())
+            .with_context(|| format!("No response from {}", self.provider))
+    }
+
+    /// Send a conversation and wait for the whole reply, in JSON mode if `json`
+    pub(super) async fn complete(&self, messages: &[Message], json: bool) -> Result<String> {
+        let started = Instant::now();
+        let request = ChatRequest {
+            model: self.model.clone(),
+            messages: messages.to_vec(),
+            temperature: Some(0.7),
+            max_tokens: Some(4096),
+            response_format: json.then(|| serde_json::json!({ "type": "json_object" })),
+            stream: false,
+        };
+
+        let response = self.send(&request).await?;
+        self.reply(response, "chat", started).await
+    }
+
+    /// Send a conversation, handing each token to `on_token` as it arrives
+    pub(super) async fn complete_stream(
+        &self,
+        messages: &[Message],
+        mut on_token: impl FnMut(&str),
+    ) -> Result<String> {
+        let started = Instant::now();
+        let request = ChatRequest {
+            model: self.model.clone(),
+            messages: messages.to_vec(),
+            temperature: Some(0.7),
+            max_tokens: Some(4096),
+            response_format: None,
+            stream: true,
+        };
+
+        let response = self.send(&request).await?;
+
+        let mut full_response = String::new();
+        let mut usage = None;
+        let mut stream = response.bytes_stream();
+        let mut lines = SseLines::default();
+
+        while let Some(chunk_result) = stream.next().await {
+            let chunk = chunk_result.context("Failed to read stream chunk")?;
+
+            // SSE format: "data: {...}\n\n"
+            for line in lines.push(&chunk) {
+                let Some(data) = line.strip_prefix("data: ") else {
+                    continue;
+                };
+                if data == "[DONE]" {
+                    break;
+                }
+
+                if let Ok(parsed) = serde_json::from_str::<StreamChunk>(data) {
+                    if let Some(reported) = parsed.x_groq.and_then(|x| x.usage).or(parsed.usage) {
+                        usage = Some(reported);
+                    }
+                    if let Some(choice) = parsed.choices.first() {
+                        if let Some(content) = &choice.delta.content {
+                            on_token(content);
+                            full_response.push_str(content);
+                        }
+                    }
+                }
+            }
+        }
+
+        self.record_usage(usage, "chat", started);
+
+        Ok(full_response)
+    }
+
+    /// Ask a vision-capable model about an image (sent inline as a data URL)
+    pub(super) async fn describe_image(
+        &self,
+        prompt: &str,
+        mime: &str,
+        image: &[u8],
+    ) -> Result<String> {
+        use base64::Engine;
+
+        let data_url = format!(
+            "data:{};base64,{}",
+            mime,
+            base64::engine::general_purpose::STANDARD.encode(image)
+        );
+        let request = serde_json::json!({
+            "model": self.model,
+            "messages": [{
+                "role": "user",
+                "content": [
+                    { "type": "text", "text": prompt },
+                    { "type": "image_url", "image_url": { "url": data_url } },
+                ],
+            }],
+            "temperature": 0.2,
+            "max_tokens": 4096,
+        });
+
+        let started = Instant::now();
+        let response = self.send(&request).await?;
+        self.reply(response, "vision", started).await
+    }
+
+    /// Models listed next to the chat endpoint
+    pub(super) async fn fetch_models(&self) -> Result<Vec<ModelInfo>> {
+        let url = format!(
+            "{}/models",
+            self.endpoint.trim_end_matches("/chat/completions")
+        );
+        let list: ModelList = self
+            .with_auth(self.http.get(&url))
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach {}", self.provider))?
+            .error_for_status()?
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse {}'s model list", self.provider))?;
+
+        Ok(list.chat_models())
+    }
+}
+
+/// Any server that speaks the OpenAI chat completions API, reached at its
+/// base URL
+#[derive(Debug, Clone)]
+pub struct OpenAiClient {
+    api: OpenAiCompat,
+}
+
+impl OpenAiClient {
+    pub fn new(base_url: &str, api_key: Option<String>, model: String) -> Self {
+        Self {
+            api: OpenAiCompat::new(
+                "OpenAI-compatible server",
+                chat_url(base_url),
+                api_key,
+                model,
+            ),
+        }
+    }
+}
+
+impl ChatProvider for OpenAiClient {
+    fn name(&self) -> &'static str {
+        self.api.provider
+    }
+
+    fn model(&self) -> &str {
+        &self.api.model
+    }
+
+    fn set_model(&mut self, model: String) {
+        self.api.model = model;
+    }
+
+    /// Custom servers run whatever they have
+    fn models(&self) -> &'static [(&'static str, &'static str, usize)] {
+        &[]
+    }
+
+    async fn fetch_models(&self) -> Result<Vec<ModelInfo>> {
+        self.api.fetch_models().await
+    }
+
+    fn cache_key(&self) -> String {
+        self.api.cache_key()
+    }
+
+    fn last_usage(&self) -> Option<Usage> {
+        self.api.last_usage()
+    }
+
+    async fn chat(&self, messages: &[Message]) -> Result<String> {
+        self.api.complete(messages, false).await
+    }
+
+    async fn chat_json(&self, messages: &[Message]) -> Result<String> {
+        self.api.complete(messages, true).await
+    }
+
+    async fn chat_stream_with(
+        &self,
+        messages: &[Message],
+        on_token: impl FnMut(&str),
+    ) -> Result<String> {
+        self.api.complete_stream(messages, on_token).await
+    }
+}
+
+/// Chat completions URL for a base URL like `https://openrouter.ai/api/v1`
+/// (a full `.../chat/completions` URL is used as given)
+fn chat_url(base_url: &str) -> String {
+    let base = base_url.trim().trim_end_matches('/');
+    if base.ends_with("/chat/completions") {
+        base.to_string()
+    } else {
+        format!("{}/chat/completions", base)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_lines_across_chunks() {
+        let mut lines = SseLines::default();
+        let event = "data: {\"choices\":[{\"delta\":{\"content\":\"é\"}}]}\n\n";
+        let bytes = event.as_bytes();
+        // Split inside the JSON and inside the two-byte "é"
+        let split = event.find('é').unwrap() + 1;
+
+        assert!(lines.push(&bytes[..split]).is_empty());
+        let completed = lines.push(&bytes[split..]);
+        assert_eq!(
+            completed,
+            vec![
+                "data: {\"choices\":[{\"delta\":{\"content\":\"é\"}}]}".to_string(),
+                String::new()
+            ]
+        );
+
+        let parsed: StreamChunk =
+            serde_json::from_str(completed[0].strip_prefix("data: ").unwrap()).unwrap();
+        assert_eq!(parsed.choices[0].delta.content.as_deref(), Some("é"));
+        assert!(lines.push(b"data: [DO").is_empty());
+        assert_eq!(lines.push(b"NE]\r\n"), vec!["data: [DONE]".to_string()]);
+    }
+
+    #[test]
+    fn test_chat_url() {
+        assert_eq!(
+            chat_url("https://openrouter.ai/api/v1/"),
+            "https://openrouter.ai/api/v1/chat/completions"
+        );
+        assert_eq!(
+            chat_url("http://localhost:8080/v1/chat/completions"),
+            "http://localhost:8080/v1/chat/completions"
+        );
+    }
+
+    #[test]
+    fn test_cache_key_includes_server() {
+        let here = OpenAiClient::new("http://localhost:8080/v1", None, "llama".to_string());
+        let there = OpenAiClient::new("https://openrouter.ai/api/v1", None, "llama".to_string());
+        assert_eq!(here.name(), there.name());
+        assert_ne!(here.cache_key(), there.cache_key());
+    }
+}
@@ -0,0 +1,210 @@
+//! What commands need from a chat model, whoever serves it.
+
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+use super::models::ModelInfo;
+
+/// One turn of a conversation: "system", "user" or "assistant"
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Message {
+    pub role: String,
+    pub content: String,
+}
+
+/// Tokens a provider counted for a request
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+/// A chat model that commands can talk to. Each provider has its own client
+/// (`GroqClient`, `AnthropicClient`, ...); commands should only rely on this.
+pub trait ChatProvider {
+    /// Who serves the requests, for messages ("Groq", "Ollama", ...)
+    fn name(&self) -> &'static str;
+
+    /// Model the requests go to
+    fn model(&self) -> &str;
+
+    /// Send later requests to another model
+    fn set_model(&mut self, model: String);
+
+    /// Models this provider is known to offer: (id, description,
+    /// context_window_tokens). Empty when any name the server knows is fine.
+    fn models(&self) -> &'static [(&'static str, &'static str, usize)];
+
+    /// Ask the provider which models it offers right now (see
+    /// `models::list` for the cached fallback)
+    async fn fetch_models(&self) -> Result<Vec<ModelInfo>>;
+
+    /// Which entry of the model cache is this provider's: its name, plus the
+    /// server's URL for providers that can run anywhere
+    fn cache_key(&self) -> String {
+        self.name().to_string()
+    }
+
+    /// Token counts for the most recent request, if the provider reported them
+    fn last_usage(&self) -> Option<Usage>;
+
+    /// Send a conversation and wait for the whole reply
+    async fn chat(&self, messages: &[Message]) -> Result<String>;
+
+    /// Send a conversation and ask for a single JSON object back (the
+    /// messages should describe its shape)
+    async fn chat_json(&self, messages: &[Message]) -> Result<String>;
+
+    /// Send a conversation, handing each token to `on_token` as it arrives.
+    /// Returns the complete response.
+    async fn chat_stream_with(
+        &self,
+        messages: &[Message],
+        on_token: impl FnMut(&str),
+    ) -> Result<String>;
+
+    /// Get the context window size (in tokens) for the current model: the
+    /// built-in list, then what the provider last reported
+    fn context_window(&self) -> usize {
+        self.models()
+            .iter()
+            .find(|(id, _, _)| *id == self.model())
+            .map(|(_, _, ctx)| *ctx)
+            .or_else(|| super::models::cached_context_window(&self.cache_key(), self.model()))
+            .unwrap_or(8192)
+    }
+
+    /// Tokens left for retrieved context once `messages` and the reply are
+    /// accounted for
+    fn available_context_tokens(
+        &self,
+        messages: &[Message],
+        reserved_response_tokens: usize,
+    ) -> usize {
+        let used_tokens = super::tokens::estimate_messages(messages);
+        self.context_window()
+            .saturating_sub(used_tokens + reserved_response_tokens)
+    }
+
+    /// Ask for JSON and parse the reply as `T`
+    async fn chat_as<T: DeserializeOwned>(&self, messages: &[Message]) -> Result<T> {
+        let response = self.chat_json(messages).await?;
+        super::schema::parse(&response)
+    }
+
+    /// Query with a system prompt
+    async fn query_with_system(&self, system: &str, user: &str) -> Result<String> {
+        let messages = vec![
+            Message {
+                role: "system".to_string(),
+                content: system.to_string(),
+            },
+            Message {
+                role: "user".to_string(),
+                content: user.to_string(),
+            },
+        ];
+        self.chat(&messages).await
+    }
+}
+
+/// Log a finished request ("chat" or "vision") for `librarian stats usage`
+pub(super) fn log_call(
+    provider: &str,
+    model: &str,
+    usage: Option<Usage>,
+    kind: &str,
+    started: Instant,
+) {
+    let call = crate::storage::usage::Call {
+        kind,
+        provider,
+        model,
+        usage,
+        duration_ms: started.elapsed().as_millis() as u64,
+    };
+    if let Err(e) = crate::storage::usage::record(&call) {
+        eprintln!("Note: Could not record token usage: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answers every request with the roles it was sent
+    struct Echo;
+
+    impl ChatProvider for Echo {
+        fn name(&self) -> &'static str {
+            "Echo"
+        }
+
+        fn model(&self) -> &str {
+            "echo-small"
+        }
+
+        fn set_model(&mut self, _model: String) {}
+
+        fn models(&self) -> &'static [(&'static str, &'static str, usize)] {
+            &[("echo-small", "Echo", 1000)]
+        }
+
+        async fn fetch_models(&self) -> Result<Vec<ModelInfo>> {
+            Ok(Vec::new())
+        }
+
+        fn last_usage(&self) -> Option<Usage> {
+            None
+        }
+
+        async fn chat(&self, messages: &[Message]) -> Result<String> {
+            Ok(messages
+                .iter()
+                .map(|m| m.role.as_str())
+                .collect::<Vec<_>>()
+                .join(","))
+        }
+
+        async fn chat_json(&self, messages: &[Message]) -> Result<String> {
+            Ok(format!("{{\"roles\": \"{}\"}}", self.chat(messages).await?))
+        }
+
+        async fn chat_stream_with(
+            &self,
+            messages: &[Message],
+            mut on_token: impl FnMut(&str),
+        ) -> Result<String> {
+            let reply = self.chat(messages).await?;
+            on_token(&reply);
+            Ok(reply)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_provided_methods() {
+        let echo = Echo;
+        assert_eq!(
+            echo.query_with_system("Be brief.", "Hi").await.unwrap(),
+            "system,user"
+        );
+        assert_eq!(echo.context_window(), 1000);
+
+        #[derive(serde::Deserialize)]
+        struct Roles {
+            roles: String,
+        }
+        let parsed: Roles = echo.chat_as(&[]).await.unwrap();
+        assert_eq!(parsed.roles, "");
+
+        crate::embeddings::use_mock_backend();
+        let prompt = [Message {
+            role: "system".to_string(),
+            content: "x".repeat(400),
+        }];
+        // 1000 tokens - 124 estimated for the prompt - 500 reserved
+        assert_eq!(echo.available_context_tokens(&prompt, 500), 376);
+    }
+}
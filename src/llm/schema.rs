@@ -0,0 +1,490 @@
+//! Typed shapes that flashcard, cloze, quiz, problem set and study plan generation ask the model to return as
+//! JSON, so the results can be used without scraping markdown.
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Reply shape for flashcard generation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlashcardSet {
+    pub flashcards: Vec<Flashcard>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Flashcard {
+    pub question: String,
+    pub answer: String,
+}
+
+/// Reply shape for quiz generation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Quiz {
+    pub questions: Vec<QuizQuestion>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QuizQuestion {
+    MultipleChoice {
+        question: String,
+        options: Vec<String>,
+        /// Letter of the correct option ("a", "b", ...)
+        answer: String,
+    },
+    FillInBlank {
+        question: String,
+        answer: String,
+    },
+    ShortAnswer {
+        question: String,
+        answer: String,
+    },
+}
+
+impl QuizQuestion {
+    pub fn question(&self) -> &str {
+        match self {
+            QuizQuestion::MultipleChoice { question, .. }
+            | QuizQuestion::FillInBlank { question, .. }
+            | QuizQuestion::ShortAnswer { question, .. } => question,
+        }
+    }
+
+    /// Letter of a multiple-choice question's correct option, accepting
+    /// "b", "b)", "B" or the option's text
+    pub fn correct_letter(&self) -> Option<char> {
+        let QuizQuestion::MultipleChoice {
+            options, answer, ..
+        } = self
+        else {
+            return None;
+        };
+        let answer = answer.trim();
+        if let Some(index) = options.iter().position(|o| o.trim() == answer) {
+            return Some(option_letter(index));
+        }
+        let letter = answer.chars().next()?.to_ascii_lowercase();
+        let index = (letter as usize).checked_sub('a' as usize)?;
+        (index < options.len()).then_some(letter)
+    }
+
+    /// The answer in words (a multiple-choice question's correct option)
+    pub fn answer_text(&self) -> &str {
+        match self {
+            QuizQuestion::MultipleChoice {
+                options, answer, ..
+            } => self
+                .correct_letter()
+                .and_then(|l| options.get(l as usize - 'a' as usize))
+                .unwrap_or(answer),
+            QuizQuestion::FillInBlank { answer, .. } | QuizQuestion::ShortAnswer { answer, .. } => {
+                answer
+            }
+        }
+    }
+
+    /// Study item type this question is saved as
+    pub fn item_type(&self) -> &'static str {
+        match self {
+            QuizQuestion::MultipleChoice { .. } => "quiz_mc",
+            QuizQuestion::FillInBlank { .. } => "quiz_fill",
+            QuizQuestion::ShortAnswer { .. } => "quiz_short",
+        }
+    }
+}
+
+/// Reply shape for cloze generation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClozeSet {
+    pub cloze: Vec<Cloze>,
+}
+
+/// A sentence with its blanks marked, e.g. "The {{scheduler}} decides which
+/// process runs next"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cloze {
+    pub text: String,
+}
+
+/// Placeholder shown where a cloze blank is hidden
+pub const CLOZE_BLANK: &str = "[_____]";
+
+/// Split cloze text into `(text, is_blank)` pieces, in order. An unclosed
+/// "{{" is kept as plain text.
+pub fn cloze_parts(text: &str) -> Vec<(&str, bool)> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while let Some(open) = rest.find("{{") {
+        let Some(close) = rest[open + 2..].find("}}") else {
+            break;
+        };
+        if open > 0 {
+            parts.push((&rest[..open], false));
+        }
+        parts.push((rest[open + 2..open + 2 + close].trim(), true));
+        rest = &rest[open + 2 + close + 2..];
+    }
+    if !rest.is_empty() {
+        parts.push((rest, false));
+    }
+    parts
+}
+
+/// A blank's answer and optional hint. Besides plain "answer", Anki's
+/// "c1::answer::hint" form is understood, so imported cards render too.
+pub fn cloze_blank(inner: &str) -> (&str, Option<&str>) {
+    let inner = match inner.split_once("::") {
+        Some((number, rest))
+            if number.len() > 1
+                && number.starts_with('c')
+                && number[1..].chars().all(|c| c.is_ascii_digit()) =>
+        {
+            rest
+        }
+        _ => inner,
+    };
+    match inner.split_once("::") {
+        Some((answer, hint)) if !hint.trim().is_empty() => (answer.trim(), Some(hint.trim())),
+        Some((answer, _)) => (answer.trim(), None),
+        None => (inner.trim(), None),
+    }
+}
+
+/// The words hidden by a cloze's blanks
+pub fn cloze_answers(text: &str) -> Vec<&str> {
+    cloze_parts(text)
+        .into_iter()
+        .filter(|(_, blank)| *blank)
+        .map(|(part, _)| cloze_blank(part).0)
+        .collect()
+}
+
+/// Cloze text with each blank replaced by `blank(answer)`
+pub fn render_cloze(text: &str, blank: impl Fn(&str) -> String) -> String {
+    cloze_parts(text)
+        .into_iter()
+        .map(|(part, is_blank)| {
+            if is_blank {
+                blank(cloze_blank(part).0)
+            } else {
+                part.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Cloze text as shown before it's answered: each blank becomes its hint in
+/// brackets, or `CLOZE_BLANK`, passed through `style`
+pub fn hide_cloze(text: &str, style: impl Fn(&str) -> String) -> String {
+    cloze_parts(text)
+        .into_iter()
+        .map(|(part, is_blank)| match (is_blank, cloze_blank(part).1) {
+            (false, _) => part.to_string(),
+            (true, Some(hint)) => style(&format!("[{}]", hint)),
+            (true, None) => style(CLOZE_BLANK),
+        })
+        .collect()
+}
+
+impl ClozeSet {
+    /// Cards that actually have a blank to fill in
+    pub fn cards(&self) -> impl Iterator<Item = &Cloze> {
+        self.cloze
+            .iter()
+            .filter(|c| !cloze_answers(&c.text).is_empty())
+    }
+
+    pub fn to_markdown(&self) -> String {
+        self.cards()
+            .enumerate()
+            .map(|(i, card)| {
+                format!(
+                    "{}. {}\n   **Answer: {}**\n",
+                    i + 1,
+                    hide_cloze(&card.text, str::to_string),
+                    cloze_answers(&card.text).join(", ")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Reply shape for practice problem generation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProblemSet {
+    pub problems: Vec<Problem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Problem {
+    pub question: String,
+    /// Worked solution, step by step
+    pub solution: String,
+    /// Final answer, when there is a single one
+    #[serde(default)]
+    pub answer: String,
+}
+
+impl ProblemSet {
+    /// The problems alone, numbered, with nothing giving the answers away
+    pub fn problems_markdown(&self, title: &str) -> String {
+        let mut out = format!("# {}\n\n", title);
+        for (i, problem) in self.problems.iter().enumerate() {
+            out.push_str(&format!(
+                "## Problem {}\n\n{}\n\n",
+                i + 1,
+                problem.question.trim()
+            ));
+        }
+        out
+    }
+
+    /// Each problem's worked solution and answer, numbered to match
+    pub fn solutions_markdown(&self, title: &str) -> String {
+        let mut out = format!("# {} (Solutions)\n\n", title);
+        for (i, problem) in self.problems.iter().enumerate() {
+            out.push_str(&format!(
+                "## Problem {}\n\n{}\n\n",
+                i + 1,
+                problem.solution.trim()
+            ));
+            if !problem.answer.trim().is_empty() {
+                out.push_str(&format!("**Answer:** {}\n\n", problem.answer.trim()));
+            }
+        }
+        out
+    }
+}
+
+/// Reply shape for study plan generation: the bucket's topics in the order
+/// they should be studied
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StudyTopics {
+    pub topics: Vec<StudyTopic>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StudyTopic {
+    pub title: String,
+    /// Filenames of the documents that cover the topic
+    #[serde(default)]
+    pub documents: Vec<String>,
+    #[serde(default)]
+    pub tasks: Vec<String>,
+    /// Relative effort, 1 (light) to 3 (heavy)
+    #[serde(default = "default_effort")]
+    pub effort: u32,
+}
+
+fn default_effort() -> u32 {
+    1
+}
+
+/// Letter shown before the option at `index`
+pub fn option_letter(index: usize) -> char {
+    (b'a' + index as u8) as char
+}
+
+impl FlashcardSet {
+    /// Read cards written as "Q: ..." / "A: ..." lines (bold or not), the way
+    /// `to_markdown` and older flashcard files write them. An answer runs
+    /// until the next question or a `---` separator.
+    pub fn from_markdown(text: &str) -> Self {
+        let mut flashcards = Vec::new();
+        let mut question: Option<String> = None;
+        let mut answer: Option<String> = None;
+
+        let mut finish = |question: &mut Option<String>, answer: &mut Option<String>| {
+            if let (Some(q), Some(a)) = (question.take(), answer.take())
+                && !q.trim().is_empty()
+                && !a.trim().is_empty()
+            {
+                flashcards.push(Flashcard {
+                    question: q.trim().to_string(),
+                    answer: a.trim().to_string(),
+                });
+            }
+        };
+
+        for line in text.lines() {
+            let plain = line.trim().replace("**", "");
+            if let Some(q) = plain.strip_prefix("Q:") {
+                finish(&mut question, &mut answer);
+                question = Some(q.to_string());
+            } else if let Some(a) = plain.strip_prefix("A:") {
+                answer = Some(a.to_string());
+            } else if plain == "---" {
+                finish(&mut question, &mut answer);
+            } else if let Some(text) = answer.as_mut().or(question.as_mut())
+                && !plain.is_empty()
+            {
+                text.push('\n');
+                text.push_str(&plain);
+            }
+        }
+        finish(&mut question, &mut answer);
+
+        Self { flashcards }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        self.flashcards
+            .iter()
+            .map(|card| format!("---\n**Q:** {}\n\n**A:** {}\n", card.question, card.answer))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Quiz {
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        for (i, q) in self.questions.iter().enumerate() {
+            out.push_str(&format!("{}. {}\n", i + 1, q.question()));
+            if let QuizQuestion::MultipleChoice { options, .. } = q {
+                for (j, option) in options.iter().enumerate() {
+                    out.push_str(&format!("   {}) {}\n", option_letter(j), option));
+                }
+            }
+            match q.correct_letter() {
+                Some(letter) => out.push_str(&format!("   **Answer: {})**\n\n", letter)),
+                None => out.push_str(&format!("   **Answer: {}**\n\n", q.answer_text())),
+            }
+        }
+        out
+    }
+}
+
+/// Parse a model's JSON reply, tolerating a code fence or a sentence around
+/// the object
+pub fn parse<T: DeserializeOwned>(response: &str) -> Result<T> {
+    let start = response
+        .find('{')
+        .context("The model's reply contained no JSON")?;
+    let end = response
+        .rfind('}')
+        .context("The model's reply contained no JSON")?;
+    serde_json::from_str(&response[start..=end.max(start)])
+        .context("The model's reply didn't match the expected JSON shape")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_quiz() {
+        let reply = r#"```json
+{"questions": [
+  {"type": "multiple_choice", "question": "Where is ATP made?",
+   "options": ["Nucleus", "Mitochondria", "Ribosome"], "answer": "b)"},
+  {"type": "fill_in_blank", "question": "ATP stands for adenosine ___.", "answer": "triphosphate"},
+  {"type": "short_answer", "question": "Why is ATP useful?", "answer": "It stores energy"}
+]}
+```"#;
+        let quiz: Quiz = parse(reply).unwrap();
+
+        assert_eq!(quiz.questions.len(), 3);
+        assert_eq!(quiz.questions[0].correct_letter(), Some('b'));
+        assert_eq!(quiz.questions[0].answer_text(), "Mitochondria");
+        assert_eq!(quiz.questions[1].item_type(), "quiz_fill");
+        assert!(
+            quiz.to_markdown()
+                .contains("   c) Ribosome\n   **Answer: b)**")
+        );
+    }
+
+    #[test]
+    fn test_correct_letter_from_option_text() {
+        let q = QuizQuestion::MultipleChoice {
+            question: "2 + 2?".to_string(),
+            options: vec!["3".to_string(), "4".to_string()],
+            answer: "4".to_string(),
+        };
+        assert_eq!(q.correct_letter(), Some('b'));
+
+        let q = QuizQuestion::MultipleChoice {
+            question: "2 + 2?".to_string(),
+            options: vec!["3".to_string(), "4".to_string()],
+            answer: "e".to_string(),
+        };
+        assert_eq!(q.correct_letter(), None);
+    }
+
+    #[test]
+    fn test_flashcards_from_markdown() {
+        let markdown = "# Cell biology\n\n---\n**Q:** What is ATP?\n\n**A:** The cell's\nenergy currency\n\n\
+                        ---\nQ: Where is it made?\nA: Mitochondria\nQ: Unanswered?\n";
+        let set = FlashcardSet::from_markdown(markdown);
+
+        assert_eq!(set.flashcards.len(), 2);
+        assert_eq!(set.flashcards[0].answer, "The cell's\nenergy currency");
+        assert_eq!(set.flashcards[1].question, "Where is it made?");
+
+        let round_trip = FlashcardSet::from_markdown(&set.to_markdown());
+        assert_eq!(round_trip.flashcards[0].answer, set.flashcards[0].answer);
+    }
+
+    #[test]
+    fn test_cloze() {
+        let reply = r#"{"cloze": [
+  {"text": "The {{scheduler}} decides which process runs next."},
+  {"text": "A {{ mutex }} guards a {{critical section}}."},
+  {"text": "No blanks here."}
+]}"#;
+        let set: ClozeSet = parse(reply).unwrap();
+
+        assert_eq!(set.cards().count(), 2);
+        assert_eq!(
+            cloze_answers(&set.cloze[1].text),
+            vec!["mutex", "critical section"]
+        );
+        assert_eq!(
+            render_cloze(&set.cloze[0].text, |_| CLOZE_BLANK.to_string()),
+            "The [_____] decides which process runs next."
+        );
+        assert_eq!(
+            render_cloze("{{Deadlock}} needs {{four}} conditions", |a| a
+                .to_uppercase()),
+            "DEADLOCK needs FOUR conditions"
+        );
+        assert!(set.to_markdown().contains("2. A [_____] guards"));
+        // Anki-style numbered blanks with hints
+        let anki = "The {{c1::mitochondria::organelle}} makes {{c2::ATP}}";
+        assert_eq!(cloze_answers(anki), vec!["mitochondria", "ATP"]);
+        assert_eq!(
+            hide_cloze(anki, str::to_string),
+            "The [organelle] makes [_____]"
+        );
+        assert_eq!(cloze_blank("C++::language"), ("C++", Some("language")));
+        // An unclosed marker is left as text
+        assert_eq!(
+            cloze_parts("Ends with {{oops"),
+            vec![("Ends with {{oops", false)]
+        );
+    }
+
+    #[test]
+    fn test_problem_set_keeps_solutions_apart() {
+        let reply = r#"{"problems": [
+  {"question": "A page is 4 KiB. How many offset bits?", "solution": "4 KiB = 2^12 bytes.", "answer": "12"},
+  {"question": "Explain thrashing.", "solution": "Too little memory per process..."}
+]}"#;
+        let set: ProblemSet = parse(reply).unwrap();
+        let problems = set.problems_markdown("Paging");
+        let solutions = set.solutions_markdown("Paging");
+
+        assert!(problems.contains("## Problem 2\n\nExplain thrashing."));
+        assert!(!problems.contains("2^12"));
+        assert!(solutions.starts_with("# Paging (Solutions)"));
+        assert!(solutions.contains("2^12 bytes.\n\n**Answer:** 12"));
+        assert!(!solutions.contains("**Answer:** \n"));
+    }
+
+    #[test]
+    fn test_parse_rejects_non_json() {
+        assert!(parse::<FlashcardSet>("Sorry, I can't help with that.").is_err());
+    }
+}
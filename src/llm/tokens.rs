@@ -0,0 +1,84 @@
+//! Token estimates for fitting prompts into a model's context window.
+//!
+//! The chat models' own tokenizers aren't available locally, so counts come
+//! from the embedding model's WordPiece tokenizer (or ~4 characters per token
+//! when it can't be loaded). Tokenizers split text differently - code, maths
+//! and non-English text especially - so every estimate carries a 20% margin
+//! to keep prompts from overrunning the real window.
+
+use super::provider::Message;
+
+/// Characters per token when no tokenizer is available
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Tokens each message costs beyond its text (role and separators)
+const MESSAGE_OVERHEAD: usize = 4;
+
+/// Estimates are the local count scaled by this much (percent)
+const SAFETY_MARGIN_PERCENT: usize = 120;
+
+/// Estimated number of tokens in `text`, margin included
+pub fn estimate(text: &str) -> usize {
+    let local = match crate::embeddings::tokenizer().and_then(|t| t.encode(text, false).ok()) {
+        Some(encoding) => encoding.len(),
+        None => text.len().div_ceil(CHARS_PER_TOKEN),
+    };
+    (local * SAFETY_MARGIN_PERCENT).div_ceil(100)
+}
+
+/// Estimated number of tokens a conversation takes up in a request
+pub fn estimate_messages<'a>(messages: impl IntoIterator<Item = &'a Message>) -> usize {
+    messages
+        .into_iter()
+        .map(|m| estimate(&m.content) + MESSAGE_OVERHEAD)
+        .sum()
+}
+
+/// Length in bytes of the longest prefix of `text` whose estimate fits in
+/// `max_tokens` (always on a char boundary)
+pub fn fit(text: &str, max_tokens: usize) -> usize {
+    let max_tokens = max_tokens * 100 / SAFETY_MARGIN_PERCENT;
+    let offsets = crate::embeddings::tokenizer()
+        .and_then(|t| t.encode(text, false).ok())
+        .map(|encoding| encoding.get_offsets().to_vec());
+
+    let end = match offsets {
+        Some(offsets) if offsets.len() <= max_tokens => text.len(),
+        Some(offsets) => offsets[..max_tokens]
+            .iter()
+            .map(|(_, end)| *end)
+            .max()
+            .unwrap_or(0),
+        None => (max_tokens * CHARS_PER_TOKEN).min(text.len()),
+    };
+
+    let mut end = end.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimates_without_tokenizer() {
+        crate::embeddings::use_mock_backend();
+
+        // 4 tokens locally, plus the margin
+        assert_eq!(estimate("The mitochondria"), 5);
+        assert_eq!(fit("The mitochondria", 3), 8);
+        assert_eq!(fit("The mitochondria", 100), 16);
+        assert!(estimate(&"The mitochondria"[..fit("The mitochondria", 3)]) <= 3);
+        // Never splits a character
+        assert_eq!(fit("café au lait", 2), 3);
+
+        let messages = [Message {
+            role: "user".to_string(),
+            content: "What is ATP?".to_string(),
+        }];
+        assert_eq!(estimate_messages(&messages), 4 + MESSAGE_OVERHEAD);
+    }
+}
@@ -2,9 +2,27 @@ use anyhow::{Context, Result};
 use reqwest::multipart;
 use serde::Deserialize;
 use std::path::Path;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use crate::config::Config;
 
 const GROQ_WHISPER_URL: &str = "https://api.groq.com/openai/v1/audio/transcriptions";
 
+const GROQ_TRANSLATION_URL: &str = "https://api.groq.com/openai/v1/audio/translations";
+
+/// Groq only translates with the full large-v3 model
+const TRANSLATION_MODEL: &str = "whisper-large-v3";
+
+/// Groq rejects uploads over 25 MB; stay a little under it
+const MAX_UPLOAD_BYTES: u64 = 24 * 1024 * 1024;
+
+/// Length of each piece when splitting long recordings
+const PIECE_SECS: f64 = 600.0;
+
+/// Extra audio at the end of each piece so words at the cut aren't lost
+const PIECE_OVERLAP_SECS: f64 = 5.0;
+
 #[derive(Debug, Clone)]
 pub struct WhisperClient {
     client: reqwest::Client,
@@ -15,6 +33,96 @@ pub struct WhisperClient {
 #[derive(Debug, Deserialize)]
 struct TranscriptionResponse {
     text: String,
+    #[serde(default)]
+    segments: Vec<TranscriptSegment>,
+}
+
+/// Which language to listen for, and whether to translate to English
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TranscribeOptions {
+    /// ISO-639-1 code of the spoken language; None lets Whisper detect it
+    pub language: Option<String>,
+    /// Translate the speech to English instead of transcribing it as spoken
+    pub translate: bool,
+    /// Text Whisper treats as what came before, to steer spelling of names
+    /// and jargon
+    pub prompt: Option<String>,
+}
+
+/// Options given for this invocation (e.g. `add --language`), taking
+/// precedence over the configured ones
+static OPTIONS_OVERRIDE: OnceLock<TranscribeOptions> = OnceLock::new();
+
+/// Vocabulary prompt the add flow built for this run's recordings
+static PROMPT: OnceLock<String> = OnceLock::new();
+
+impl TranscribeOptions {
+    /// The options to use: the per-run override, then config
+    pub fn current() -> Self {
+        let mut options = OPTIONS_OVERRIDE.get().cloned().unwrap_or_else(|| {
+            Config::load()
+                .map(|c| Self::from_config(&c))
+                .unwrap_or_default()
+        });
+        options.prompt = PROMPT.get().cloned();
+        options
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            language: normalize_language(config.whisper_language.as_deref()),
+            translate: config.whisper_translate,
+            prompt: None,
+        }
+    }
+
+    /// Use this language and/or translation (with config for the rest) for
+    /// the rest of this run
+    pub fn set_override(language: Option<&str>, translate: bool) {
+        let mut options = Self::current();
+        if language.is_some() {
+            options.language = normalize_language(language);
+        }
+        options.translate |= translate;
+        let _ = OPTIONS_OVERRIDE.set(options);
+    }
+
+    /// Prompt every transcription for the rest of this run with `prompt`
+    pub fn set_prompt(prompt: String) {
+        let _ = PROMPT.set(prompt);
+    }
+
+    /// How the options read in settings ("auto-detect", "es, translated to English")
+    pub fn describe(&self) -> String {
+        let language = self.language.as_deref().unwrap_or("auto-detect");
+        if self.translate {
+            format!("{}, translated to English", language)
+        } else {
+            language.to_string()
+        }
+    }
+}
+
+/// Lowercase a language code, treating blank and "auto" as unset
+pub fn normalize_language(language: Option<&str>) -> Option<String> {
+    language
+        .map(|l| l.trim().to_lowercase())
+        .filter(|l| !l.is_empty() && l != "auto")
+}
+
+/// A transcription along with when each segment was spoken
+#[derive(Debug, Clone, Default)]
+pub struct Transcript {
+    pub text: String,
+    pub segments: Vec<TranscriptSegment>,
+}
+
+/// A stretch of speech, timed from the start of the recording
+#[derive(Debug, Clone, Deserialize)]
+pub struct TranscriptSegment {
+    /// Seconds from the start of the recording
+    pub start: f64,
+    pub text: String,
 }
 
 impl WhisperClient {
@@ -36,8 +144,44 @@ impl WhisperClient {
         }
     }
 
-    /// Transcribe an audio file
-    pub async fn transcribe(&self, file_path: &Path) -> Result<String> {
+    /// Transcribe an audio file, keeping segment timestamps. Files too large
+    /// to upload are split into pieces and stitched back together.
+    pub async fn transcribe(
+        &self,
+        file_path: &Path,
+        options: &TranscribeOptions,
+    ) -> Result<Transcript> {
+        let size = std::fs::metadata(file_path)
+            .with_context(|| format!("Failed to read audio file: {:?}", file_path))?
+            .len();
+
+        if size <= MAX_UPLOAD_BYTES {
+            return self.transcribe_file(file_path, options).await;
+        }
+
+        let duration = audio_duration(file_path).await?;
+        let mut pieces = Vec::new();
+        let mut offset = 0.0;
+
+        while offset < duration {
+            let piece_path =
+                extract_audio_piece(file_path, offset, PIECE_SECS + PIECE_OVERLAP_SECS).await?;
+            let result = self.transcribe_file(&piece_path, options).await;
+            let _ = std::fs::remove_file(&piece_path);
+
+            pieces.push((offset, result?));
+            offset += PIECE_SECS;
+        }
+
+        Ok(stitch_pieces(pieces, PIECE_SECS))
+    }
+
+    /// Upload a single file for transcription (or translation)
+    async fn transcribe_file(
+        &self,
+        file_path: &Path,
+        options: &TranscribeOptions,
+    ) -> Result<Transcript> {
         let file_name = file_path
             .file_name()
             .and_then(|n| n.to_str())
@@ -51,14 +195,30 @@ impl WhisperClient {
             .file_name(file_name)
             .mime_str(Self::guess_mime_type(file_path))?;
 
-        let form = multipart::Form::new()
+        let (url, model) = if options.translate {
+            (GROQ_TRANSLATION_URL, TRANSLATION_MODEL)
+        } else {
+            (GROQ_WHISPER_URL, self.model.as_str())
+        };
+
+        let mut form = multipart::Form::new()
             .part("file", file_part)
-            .text("model", self.model.clone())
-            .text("response_format", "json");
+            .text("model", model.to_string())
+            .text("response_format", "verbose_json");
+        // Translations always come out in English
+        if let Some(language) = &options.language
+            && !options.translate
+        {
+            form = form.text("language", language.clone());
+        }
+        if let Some(prompt) = &options.prompt {
+            form = form.text("prompt", prompt.clone());
+        }
 
+        let started = Instant::now();
         let response = self
             .client
-            .post(GROQ_WHISPER_URL)
+            .post(url)
             .header("Authorization", format!("Bearer {}", self.api_key))
             .multipart(form)
             .send()
@@ -75,8 +235,12 @@ impl WhisperClient {
             .json()
             .await
             .context("Failed to parse Whisper response")?;
+        record_transcription("Groq", model, started);
 
-        Ok(transcription.text)
+        Ok(Transcript {
+            text: transcription.text,
+            segments: transcription.segments,
+        })
     }
 
     fn guess_mime_type(path: &Path) -> &'static str {
@@ -93,6 +257,139 @@ impl WhisperClient {
     }
 }
 
+/// Log a transcription call (Whisper reports no token counts)
+pub(super) fn record_transcription(provider: &str, model: &str, started: Instant) {
+    let call = crate::storage::usage::Call {
+        kind: "transcription",
+        provider,
+        model,
+        usage: None,
+        duration_ms: started.elapsed().as_millis() as u64,
+    };
+    if let Err(e) = crate::storage::usage::record(&call) {
+        eprintln!("Note: Could not record usage: {}", e);
+    }
+}
+
+/// Combine the transcripts of consecutive pieces. Each piece was cut with some
+/// overlap, so segments that begin past the piece's own span are dropped (the
+/// next piece has them), and segment times are shifted to the full recording.
+fn stitch_pieces(pieces: Vec<(f64, Transcript)>, piece_secs: f64) -> Transcript {
+    let count = pieces.len();
+    let mut stitched = Transcript::default();
+    let mut texts = Vec::new();
+
+    for (i, (offset, transcript)) in pieces.into_iter().enumerate() {
+        let is_last = i + 1 == count;
+
+        if transcript.segments.is_empty() {
+            texts.push(transcript.text.trim().to_string());
+            continue;
+        }
+
+        for segment in transcript.segments {
+            if !is_last && segment.start >= piece_secs {
+                continue;
+            }
+            texts.push(segment.text.trim().to_string());
+            stitched.segments.push(TranscriptSegment {
+                start: offset + segment.start,
+                text: segment.text,
+            });
+        }
+    }
+
+    texts.retain(|t| !t.is_empty());
+    stitched.text = texts.join(" ");
+    stitched
+}
+
+/// Length of an audio/video file in seconds, via ffprobe
+async fn audio_duration(path: &Path) -> Result<f64> {
+    validate_path(path)?;
+    let canonical = std::fs::canonicalize(path)?;
+    let input_str = canonical
+        .to_str()
+        .ok_or_else(|| anyhow::anyhow!("Invalid UTF-8 in audio path"))?;
+
+    let output = tokio::process::Command::new("ffprobe")
+        .args([
+            "-v",
+            "error",
+            "-show_entries",
+            "format=duration",
+            "-of",
+            "default=noprint_wrappers=1:nokey=1",
+            input_str,
+        ])
+        .output()
+        .await
+        .context("Failed to run ffprobe (install ffmpeg to transcribe long recordings)")?;
+
+    if !output.status.success() {
+        anyhow::bail!("ffprobe could not read the length of {:?}", path);
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse::<f64>()
+        .with_context(|| format!("Could not determine the length of {:?}", path))
+}
+
+/// Cut `length` seconds of audio starting at `start` into a small mono MP3
+async fn extract_audio_piece(path: &Path, start: f64, length: f64) -> Result<std::path::PathBuf> {
+    let canonical = std::fs::canonicalize(path)?;
+    let input_str = canonical
+        .to_str()
+        .ok_or_else(|| anyhow::anyhow!("Invalid UTF-8 in audio path"))?;
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or(std::time::Duration::from_secs(0))
+        .as_millis();
+    let output_path = std::env::temp_dir().join(format!(
+        "librarian-piece-{}-{}-{}.mp3",
+        std::process::id(),
+        timestamp,
+        start as u64
+    ));
+    let output_str = output_path
+        .to_str()
+        .ok_or_else(|| anyhow::anyhow!("Invalid UTF-8 in output path"))?;
+
+    let status = tokio::process::Command::new("ffmpeg")
+        .args([
+            "-ss",
+            &start.to_string(),
+            "-t",
+            &length.to_string(),
+            "-i",
+            input_str,
+            "-vn", // No video
+            "-acodec",
+            "libmp3lame",
+            "-ar",
+            "16000", // 16kHz is plenty for speech
+            "-ac",
+            "1", // Mono
+            "-b:a",
+            "64k", // ~5 MB per 10 minutes, well under the upload limit
+            "-y",
+            output_str,
+        ])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .await
+        .context("Failed to run ffmpeg")?;
+
+    if !status.success() {
+        anyhow::bail!("ffmpeg failed to split {:?} at {}s", path, start);
+    }
+
+    Ok(output_path)
+}
+
 /// Check if ffmpeg is available for video processing
 pub async fn check_ffmpeg() -> bool {
     tokio::process::Command::new("ffmpeg")
@@ -205,3 +502,50 @@ pub fn is_video_file(path: &Path) -> bool {
         Some("mp4" | "mkv" | "avi" | "mov" | "webm" | "flv")
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start: f64, text: &str) -> TranscriptSegment {
+        TranscriptSegment {
+            start,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_stitch_pieces() {
+        let first = Transcript {
+            text: String::new(),
+            segments: vec![segment(0.0, " Intro."), segment(602.0, " Overlap.")],
+        };
+        let second = Transcript {
+            text: String::new(),
+            segments: vec![segment(1.5, " Overlap."), segment(30.0, " The end.")],
+        };
+
+        let stitched = stitch_pieces(vec![(0.0, first), (600.0, second)], 600.0);
+        assert_eq!(stitched.text, "Intro. Overlap. The end.");
+        let starts: Vec<f64> = stitched.segments.iter().map(|s| s.start).collect();
+        assert_eq!(starts, vec![0.0, 601.5, 630.0]);
+    }
+
+    #[test]
+    fn test_options_from_config() {
+        let mut config = Config::default();
+        assert_eq!(
+            TranscribeOptions::from_config(&config).describe(),
+            "auto-detect"
+        );
+
+        config.whisper_language = Some(" ES ".to_string());
+        config.whisper_translate = true;
+        let options = TranscribeOptions::from_config(&config);
+        assert_eq!(options.language.as_deref(), Some("es"));
+        assert_eq!(options.describe(), "es, translated to English");
+
+        assert_eq!(normalize_language(Some("auto")), None);
+        assert_eq!(normalize_language(Some("")), None);
+    }
+}
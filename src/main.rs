@@ -1,15 +1,18 @@
 use anyhow::Result;
-use clap::{CommandFactory, Parser, Subcommand};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
 use clap_complete::{Shell, generate};
 use colored::Colorize;
+use commands::docs::OutputFormat;
 use std::io;
 
 mod bucket;
 mod commands;
 mod config;
 mod embeddings;
+mod html;
 mod ingest;
 mod llm;
+mod pdf;
 mod render;
 mod search;
 mod storage;
@@ -70,19 +73,129 @@ enum Commands {
     /// Add knowledge (files, directories, URLs, videos)
     Add {
         /// Path or URL to add (skips interactive prompt if provided)
+        #[arg(conflicts_with = "stdin")]
         path: Option<String>,
+        /// Read the document's text from stdin instead
+        #[arg(long)]
+        stdin: bool,
+        /// With --stdin: title for the document
+        #[arg(long, requires = "stdin")]
+        name: Option<String>,
+        /// For YouTube channels/playlists: only add videos whose title contains this
+        #[arg(long = "match")]
+        filter: Option<String>,
+        /// For YouTube channels/playlists and crawls: seconds to wait between requests
+        #[arg(long, default_value_t = commands::add::DEFAULT_CHANNEL_DELAY_SECS)]
+        delay: u64,
+        /// For URLs: follow links and add each linked page too
+        #[arg(long)]
+        crawl: bool,
+        /// With --crawl: how many links deep to follow
+        #[arg(long, default_value_t = 1, requires = "crawl")]
+        depth: usize,
+        /// With --crawl: only follow links on the starting page's site
+        #[arg(long, requires = "crawl")]
+        same_domain: bool,
+        /// OCR languages for images, e.g. "spa" or "deu+eng" (overrides config)
+        #[arg(long)]
+        ocr_lang: Option<String>,
+        /// Spoken language of audio and video, e.g. "es" (overrides config)
+        #[arg(long, value_name = "CODE")]
+        language: Option<String>,
+        /// Translate audio and video to English while transcribing
+        #[arg(long)]
+        translate: bool,
+        /// Course name or terms to help transcription, e.g. "CHEM 241 organic chemistry"
+        /// (defaults to the bucket name)
+        #[arg(long)]
+        hint: Option<String>,
+        /// Target chunk size in characters (overrides config)
+        #[arg(long)]
+        chunk_size: Option<usize>,
+        /// Characters shared between neighbouring chunks (overrides config)
+        #[arg(long)]
+        overlap: Option<usize>,
+        /// Split along document structure, or in fixed-size windows (overrides config)
+        #[arg(long, value_enum)]
+        chunking: Option<ingest::ChunkStrategy>,
+        /// Tag every added document, e.g. --tag exam,week3
+        #[arg(long, value_delimiter = ',')]
+        tag: Vec<String>,
+    },
+    /// Watch a folder and add new or changed files automatically
+    Watch {
+        /// Folder to watch
+        dir: String,
+        /// Seconds to wait for a file to settle before adding it
+        #[arg(long, default_value_t = commands::watch::DEFAULT_DEBOUNCE_SECS)]
+        debounce: u64,
+        /// OCR languages for images, e.g. "spa" or "deu+eng" (overrides config)
+        #[arg(long)]
+        ocr_lang: Option<String>,
+    },
+    /// Ask the Librarian - chat with your materials (pipe questions in to use it from scripts)
+    Chat {
+        /// Read questions from stdin even if it looks like a terminal
+        /// (this happens automatically when input is piped in)
+        #[arg(long)]
+        no_tty: bool,
+        /// Read questions from a file, one per paragraph
+        #[arg(long, short, value_name = "PATH")]
+        file: Option<std::path::PathBuf>,
+        /// Model to use instead of the configured default
+        #[arg(long, value_name = "ID")]
+        model: Option<String>,
+        /// Print the chunks retrieved for each question, with their scores
+        #[arg(long)]
+        show_context: bool,
+        /// Only draw on documents with this tag
+        #[arg(long)]
+        tag: Option<String>,
+    },
+    /// Answer one question from your materials and exit
+    Ask {
+        /// The question
+        question: String,
+        /// Print the answer and its sources as JSON
+        #[arg(long)]
+        json: bool,
+        /// Print the chunks retrieved for the question, with their scores
+        #[arg(long, conflicts_with = "json")]
+        show_context: bool,
+        /// Model to use instead of the configured default
+        #[arg(long, value_name = "ID")]
+        model: Option<String>,
     },
-    /// Ask the Librarian - chat with your materials
-    Chat,
     /// Browse your collection
-    List,
+    List {
+        /// Output format
+        #[arg(long, value_enum, default_value = "text")]
+        format: commands::docs::OutputFormat,
+        /// Shorthand for --format json
+        #[arg(long, conflicts_with = "format")]
+        json: bool,
+        #[command(flatten)]
+        options: commands::docs::ListOptions,
+    },
     /// Search your materials
     Search {
         /// Search query
         query: Option<String>,
+        /// Output format (json and tsv include each match's score and snippet)
+        #[arg(long, value_enum, default_value = "text")]
+        format: commands::docs::OutputFormat,
+        /// Shorthand for --format json
+        #[arg(long, conflicts_with = "format")]
+        json: bool,
+        /// Only search documents with this tag
+        #[arg(long)]
+        tag: Option<String>,
     },
     /// Manage documents
-    Docs,
+    Docs {
+        #[command(subcommand)]
+        action: Option<DocsAction>,
+    },
     /// Remove a document from your collection
     Delete {
         /// Document ID to delete
@@ -100,11 +213,109 @@ enum Commands {
     Generate {
         #[command(subcommand)]
         action: Option<GenerateAction>,
+        /// Model to use instead of the configured default
+        #[arg(long, value_name = "ID", global = true)]
+        model: Option<String>,
+        /// Only use these documents (IDs from `librarian docs`), e.g. 3,7,12
+        #[arg(long, value_name = "IDS", value_delimiter = ',', global = true)]
+        docs: Vec<i64>,
+        /// Only use documents with this tag (added to any --docs)
+        #[arg(long, global = true)]
+        tag: Option<String>,
     },
     /// Spaced repetition study session
-    Review,
+    Review {
+        #[command(subcommand)]
+        action: Option<ReviewAction>,
+        /// Only review items with this tag (topic or source document), e.g. "OS: scheduling"
+        #[arg(long, alias = "deck")]
+        tag: Option<String>,
+        /// Cram every item with this tag or topic (all items if omitted), ignoring due dates and leaving the schedule alone
+        #[arg(long, value_name = "TAG", num_args = 0..=1, default_missing_value = "")]
+        cram: Option<String>,
+    },
     /// Test your knowledge interactively
-    Quiz,
+    Quiz {
+        #[command(subcommand)]
+        action: Option<QuizAction>,
+        /// Only quiz on (or list) saved items with this tag
+        #[arg(long, alias = "deck", global = true)]
+        tag: Option<String>,
+    },
+    /// Jot down a quick note (searchable in future chats)
+    Note {
+        /// Note text (prompts if omitted)
+        text: Option<String>,
+        /// Attach the note to a document
+        #[arg(long = "doc")]
+        doc: Option<i64>,
+    },
+    /// Review, rename, delete or export chat conversations
+    #[command(alias = "conversation")]
+    Conversations {
+        #[command(subcommand)]
+        action: Option<ConversationAction>,
+    },
+    /// Track what you've read
+    Queue {
+        #[command(subcommand)]
+        action: Option<QueueAction>,
+    },
+    /// Open the next document in your reading queue
+    Next,
+    /// Re-fetch web pages, re-ingest source files that changed and add new feed entries
+    Sync {
+        /// Only sync this document
+        id: Option<i64>,
+        /// Keep running, syncing again every this many minutes
+        #[arg(long, value_name = "MINUTES")]
+        every: Option<u64>,
+    },
+    /// Subscribe to RSS/Atom feeds; `sync` adds their new entries
+    Feed {
+        #[command(subcommand)]
+        action: FeedAction,
+    },
+    /// Re-chunk and re-embed documents after changing the embedding model or chunk settings
+    Reindex {
+        /// Bucket to reindex (defaults to the current one)
+        #[arg(long)]
+        bucket: Option<String>,
+        /// Only embed chunks that have no embedding yet
+        #[arg(long)]
+        missing: bool,
+    },
+    /// Back up, move or share study items with their review schedules
+    Cards {
+        #[command(subcommand)]
+        action: CardsAction,
+    },
+    /// Export citations (BibTeX/APA) for your documents
+    Cite {
+        /// Document ID to cite
+        id: Option<i64>,
+        /// Cite every document in the current bucket
+        #[arg(long, conflicts_with = "id")]
+        all: bool,
+        /// Citation format
+        #[arg(long, value_enum, default_value = "bibtex")]
+        format: commands::cite::CiteFormat,
+    },
+    /// Export your data to open formats
+    Export {
+        #[command(subcommand)]
+        action: ExportAction,
+    },
+    /// Import study material from other apps
+    Import {
+        #[command(subcommand)]
+        action: ImportAction,
+    },
+    /// Show what your model calls have cost
+    Stats {
+        #[command(subcommand)]
+        action: StatsAction,
+    },
     /// Generate shell completions
     Completions {
         /// Shell to generate completions for
@@ -113,12 +324,82 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum DocsAction {
+    /// Open a document's original file or URL with the system's default app
+    Open {
+        /// Document ID
+        id: i64,
+    },
+    /// Find duplicate and near-duplicate documents, and merge or delete them
+    Dedupe,
+    /// List a document's chunks, or print one, to see what retrieval works with
+    Chunks {
+        /// Document ID
+        id: i64,
+        /// Print this chunk (its index from the list) in full
+        index: Option<i64>,
+    },
+    /// Save a document's extracted text, e.g. a lecture's transcript
+    Export {
+        /// Document ID
+        id: i64,
+        /// Output file ("-" for stdout; defaults to <id>-<name>.txt)
+        #[arg(short, long = "out", alias = "output")]
+        out: Option<std::path::PathBuf>,
+    },
+    /// Show a document's chunks, embedding coverage, size, chat use and study items
+    Stats {
+        /// Document ID
+        id: i64,
+    },
+    /// Re-chunk and re-embed one document with the current settings
+    Reindex {
+        /// Document ID
+        id: i64,
+    },
+    /// Give a document a clearer name (prompts for it if omitted)
+    Rename {
+        /// Document ID
+        id: i64,
+        /// New name
+        name: Option<String>,
+    },
+    /// Add or remove a document's tags (prompts for them if neither flag is given)
+    Tag {
+        /// Document ID
+        id: i64,
+        /// Tags to add, e.g. --add exam,week3
+        #[arg(long, value_delimiter = ',')]
+        add: Vec<String>,
+        /// Tags to remove
+        #[arg(long, value_delimiter = ',')]
+        remove: Vec<String>,
+    },
+    /// Find where one document covers a topic, ranked by chunk
+    SearchIn {
+        /// Document ID to search
+        id: i64,
+        /// What to look for
+        query: String,
+        /// Number of chunks to show
+        #[arg(long, default_value_t = 5)]
+        limit: usize,
+    },
+}
+
 #[derive(Subcommand)]
 enum BucketAction {
     /// Create a new bucket
     Create {
         /// Bucket name
         name: Option<String>,
+        /// Short description shown on the shelf (e.g. "CS4414 Fall")
+        #[arg(short, long)]
+        description: Option<String>,
+        /// Emoji shown on the shelf instead of 📕
+        #[arg(long)]
+        icon: Option<String>,
     },
     /// List all buckets
     List,
@@ -127,6 +408,23 @@ enum BucketAction {
         /// Bucket name
         name: Option<String>,
     },
+    /// Show documents by type, chunks, size on disk, study items due,
+    /// conversations and last activity for a bucket (the current one by default)
+    Stats {
+        /// Bucket name
+        name: Option<String>,
+    },
+    /// Archive a bucket (e.g. a finished class): hidden from the shelf and
+    /// pickers, but its data is kept
+    Archive {
+        /// Bucket name
+        name: Option<String>,
+    },
+    /// Bring an archived bucket back
+    Unarchive {
+        /// Bucket name
+        name: Option<String>,
+    },
     /// Delete a bucket
     Delete {
         /// Bucket name
@@ -134,64 +432,375 @@ enum BucketAction {
     },
 }
 
+#[derive(Subcommand)]
+enum ImportAction {
+    /// Import an Anki deck package (.apkg) as flashcards
+    Anki {
+        /// Path to the .apkg file
+        file: std::path::PathBuf,
+        /// Keep Anki's review intervals instead of starting cards fresh
+        #[arg(long)]
+        keep_schedule: bool,
+    },
+    /// Import an Obsidian vault, keeping [[wiki links]] between notes
+    Obsidian {
+        /// Path to the vault folder
+        vault: std::path::PathBuf,
+    },
+    /// Import question/answer cards from a CSV, TSV or Markdown (Q:/A:) file
+    Cards {
+        /// Path to the file
+        file: std::path::PathBuf,
+        /// Tag every imported card, e.g. "midterm-2023"
+        #[arg(long)]
+        tag: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum CardsAction {
+    /// Write the bucket's study items and their schedules to a file
+    Export {
+        /// Output format
+        #[arg(long, value_enum, default_value = "csv")]
+        format: commands::cards::CardsFormat,
+        /// Output file (`-` for stdout)
+        #[arg(long, short)]
+        output: Option<std::path::PathBuf>,
+        /// Only export items with this tag
+        #[arg(long)]
+        tag: Option<String>,
+    },
+    /// Load study items from a `cards export` or `export library` file, keeping their schedules and tags
+    Import {
+        /// Path to the CSV or JSON file
+        file: std::path::PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExportAction {
+    /// Export every bucket that isn't archived (documents, study items, conversations)
+    Library {
+        /// Output format
+        #[arg(long, value_enum, default_value = "json")]
+        format: commands::export::ExportFormat,
+        /// Output file (JSON) or directory (Markdown)
+        #[arg(long, short)]
+        output: Option<std::path::PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConversationAction {
+    /// List conversations, most recently active first
+    List,
+    /// Give a conversation a new title
+    Rename {
+        /// Conversation ID
+        id: i64,
+        /// New title (prompted for if omitted)
+        title: Option<String>,
+    },
+    /// Delete a conversation and its messages
+    Delete {
+        /// Conversation ID
+        #[arg(conflicts_with = "older_than")]
+        id: Option<i64>,
+        /// Instead delete every conversation idle for this many days
+        #[arg(long)]
+        older_than: Option<u32>,
+    },
+    /// Save a conversation as Markdown or JSON
+    Export {
+        /// Conversation ID
+        id: i64,
+        /// Output format
+        #[arg(long, value_enum, default_value = "markdown")]
+        format: commands::export::ExportFormat,
+        /// Output file ("-" for stdout; defaults to <id>-<title>.md/.json)
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReviewAction {
+    /// Cards due per day, retention and reviews done per day
+    Stats {
+        /// How many days ahead (forecast) and back (history) to include
+        #[arg(long, default_value_t = 30)]
+        days: u32,
+    },
+}
+
+#[derive(Subcommand)]
+enum QuizAction {
+    /// Browse, edit, tag or delete saved quiz questions
+    Bank,
+}
+
+#[derive(Subcommand)]
+enum QueueAction {
+    /// Show the reading queue
+    List,
+    /// Update a document's reading status or progress
+    Mark {
+        /// Document ID
+        id: i64,
+        /// New reading status
+        #[arg(value_enum)]
+        status: Option<storage::ReadingStatus>,
+        /// Percent read (0-100)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=100))]
+        progress: Option<u8>,
+        /// Where you left off (e.g. "p. 42" or "12:30")
+        #[arg(long)]
+        position: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum FeedAction {
+    /// Subscribe to a feed and add its current entries
+    Add {
+        /// Feed URL
+        url: String,
+    },
+    /// List subscriptions
+    List,
+    /// Unsubscribe (documents already added are kept)
+    Remove {
+        /// Feed ID (see `feed list`)
+        id: i64,
+    },
+    /// Add new entries from every feed now
+    Refresh,
+}
+
+#[derive(Subcommand)]
+enum StatsAction {
+    /// Tokens, requests and time spent per day, model and command
+    Usage {
+        /// How many days back to include
+        #[arg(long, default_value_t = 7)]
+        days: u32,
+    },
+}
+
 #[derive(Subcommand)]
 enum GenerateAction {
     /// Generate a comprehensive study guide
     StudyGuide {
         /// Topic or focus area
         topic: Option<String>,
+        #[command(flatten)]
+        options: commands::generate::GenerationOptions,
     },
     /// Generate flashcards for review
     Flashcards {
         /// Topic or focus area
         topic: Option<String>,
+        #[command(flatten)]
+        options: commands::generate::GenerationOptions,
+    },
+    /// Generate cloze (fill-in-the-blank) cards for review
+    Cloze {
+        /// Topic or focus area
+        topic: Option<String>,
+        #[command(flatten)]
+        options: commands::generate::GenerationOptions,
     },
     /// Generate a practice quiz
     Quiz {
         /// Topic or focus area
         topic: Option<String>,
+        #[command(flatten)]
+        options: commands::generate::GenerationOptions,
+    },
+    /// Generate practice problems with worked solutions (saved separately)
+    Problems {
+        /// Topic or focus area
+        topic: Option<String>,
+        /// Number of problems
+        #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u8).range(1..=20))]
+        count: u8,
+        /// How hard the problems should be
+        #[arg(long, value_enum, default_value = "medium")]
+        difficulty: commands::generate::Difficulty,
     },
     /// Generate a summary of materials
     Summary {
         /// Topic or document to summarize
         topic: Option<String>,
     },
+    /// Turn a lecture recording's transcript into timestamped notes
+    Notes {
+        /// Document ID of an audio, video or YouTube transcript
+        id: Option<i64>,
+    },
+    /// Plan revision day by day up to an exam
+    StudyPlan {
+        /// Date of the exam
+        #[arg(long, value_name = "YYYY-MM-DD")]
+        exam_date: Option<chrono::NaiveDate>,
+    },
     /// Interactive homework help mode
     Homework,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    storage::usage::set_command(&command_name(&matches));
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    // The offline mock covers embeddings too, so nothing needs downloading
+    if llm::mock::enabled() {
+        embeddings::use_mock_backend();
+    }
 
     match cli.command {
-        Some(Commands::Add { path }) => {
+        Some(Commands::Add {
+            path,
+            stdin,
+            name,
+            filter,
+            delay,
+            crawl,
+            depth,
+            same_domain,
+            ocr_lang,
+            language,
+            translate,
+            hint,
+            chunk_size,
+            overlap,
+            chunking,
+            tag,
+        }) => {
+            if let Some(languages) = ocr_lang {
+                ingest::ocr::set_languages(&languages);
+            }
+            if language.is_some() || translate {
+                llm::whisper::TranscribeOptions::set_override(language.as_deref(), translate);
+            }
+            if chunk_size.is_some() || overlap.is_some() || chunking.is_some() {
+                ingest::ChunkConfig::set_override(chunk_size, overlap, chunking);
+            }
             commands::bucket::print_bucket_context();
-            commands::add::run(path).await?;
+            if stdin {
+                commands::add::run_stdin(name, tag).await?;
+            } else {
+                let crawl = crawl.then_some(ingest::CrawlOptions { depth, same_domain });
+                commands::add::run(path, filter, delay, crawl, hint, tag).await?;
+            }
         }
-        Some(Commands::Chat) => {
+        Some(Commands::Watch {
+            dir,
+            debounce,
+            ocr_lang,
+        }) => {
+            if let Some(languages) = ocr_lang {
+                ingest::ocr::set_languages(&languages);
+            }
             commands::bucket::print_bucket_context();
-            commands::chat::run().await?;
+            commands::watch::run(dir, debounce).await?;
         }
-        Some(Commands::List) => {
-            commands::bucket::print_bucket_context();
-            commands::docs::list().await?;
+        Some(Commands::Chat {
+            no_tty,
+            file,
+            model,
+            show_context,
+            tag,
+        }) => {
+            override_model(model);
+            let questions = commands::chat::scripted_questions(no_tty, file.as_deref())?;
+            if questions.is_none() {
+                commands::bucket::print_bucket_context();
+            }
+            commands::chat::run(questions, show_context, tag).await?;
         }
-        Some(Commands::Search { query }) => {
-            commands::bucket::print_bucket_context();
-            commands::docs::search(query).await?;
+        Some(Commands::Ask {
+            question,
+            json,
+            show_context,
+            model,
+        }) => {
+            override_model(model);
+            commands::chat::ask(&question, json, show_context).await?;
         }
-        Some(Commands::Docs) => {
-            commands::bucket::print_bucket_context();
-            commands::docs::run().await?;
+        Some(Commands::List {
+            format,
+            json,
+            options,
+        }) => {
+            let format = if json { OutputFormat::Json } else { format };
+            if format == OutputFormat::Text {
+                commands::bucket::print_bucket_context();
+            }
+            commands::docs::list(format, &options).await?;
+        }
+        Some(Commands::Search {
+            query,
+            format,
+            json,
+            tag,
+        }) => {
+            let format = if json { OutputFormat::Json } else { format };
+            if format == OutputFormat::Text {
+                commands::bucket::print_bucket_context();
+            }
+            commands::docs::search(query, format, tag).await?;
+        }
+        Some(Commands::Docs { action }) => {
+            // Keep stdout clean when exporting to it
+            let to_stdout = matches!(
+                &action,
+                Some(DocsAction::Export { out: Some(path), .. }) if path.as_os_str() == "-"
+            );
+            if !to_stdout {
+                commands::bucket::print_bucket_context();
+            }
+            match action {
+                Some(DocsAction::Dedupe) => commands::docs::dedupe().await?,
+                Some(DocsAction::Chunks { id, index }) => {
+                    commands::docs::chunks(id, index).await?;
+                }
+                Some(DocsAction::Export { id, out }) => {
+                    commands::docs::export(id, out).await?;
+                }
+                Some(DocsAction::Open { id }) => {
+                    commands::docs::open(id).await?;
+                }
+                Some(DocsAction::Stats { id }) => {
+                    commands::docs::stats(id).await?;
+                }
+                Some(DocsAction::Reindex { id }) => {
+                    commands::reindex::document(id).await?;
+                }
+                Some(DocsAction::Rename { id, name }) => {
+                    commands::docs::rename(id, name).await?;
+                }
+                Some(DocsAction::Tag { id, add, remove }) => {
+                    commands::docs::tag(id, add, remove).await?;
+                }
+                Some(DocsAction::SearchIn { id, query, limit }) => {
+                    commands::docs::search_in(id, &query, limit).await?;
+                }
+                None => commands::docs::run().await?,
+            }
         }
         Some(Commands::Delete { id }) => {
             commands::bucket::print_bucket_context();
             commands::docs::delete(id).await?;
         }
         Some(Commands::Bucket { action }) => match action {
-            Some(BucketAction::Create { name }) => {
-                commands::bucket::create(name).await?;
+            Some(BucketAction::Create {
+                name,
+                description,
+                icon,
+            }) => {
+                commands::bucket::create(name, description, icon).await?;
             }
             Some(BucketAction::List) => {
                 commands::bucket::list().await?;
@@ -199,6 +808,15 @@ async fn main() -> Result<()> {
             Some(BucketAction::Use { name }) => {
                 commands::bucket::switch(name).await?;
             }
+            Some(BucketAction::Stats { name }) => {
+                commands::bucket::stats(name).await?;
+            }
+            Some(BucketAction::Archive { name }) => {
+                commands::bucket::archive(name).await?;
+            }
+            Some(BucketAction::Unarchive { name }) => {
+                commands::bucket::unarchive(name).await?;
+            }
             Some(BucketAction::Delete { name: _ }) => {
                 // Interactive delete
                 commands::bucket::run().await?;
@@ -210,21 +828,44 @@ async fn main() -> Result<()> {
         Some(Commands::Config) => {
             commands::config::run().await?;
         }
-        Some(Commands::Generate { action }) => {
+        Some(Commands::Generate {
+            action,
+            model,
+            docs,
+            tag,
+        }) => {
+            override_model(model);
             commands::bucket::print_bucket_context();
+            commands::generate::set_documents(&docs, tag.as_deref())?;
             match action {
-                Some(GenerateAction::StudyGuide { topic }) => {
-                    commands::generate::study_guide(topic).await?;
+                Some(GenerateAction::StudyGuide { topic, options }) => {
+                    commands::generate::study_guide(topic, options).await?;
+                }
+                Some(GenerateAction::Flashcards { topic, options }) => {
+                    commands::generate::flashcards(topic, options).await?;
+                }
+                Some(GenerateAction::Cloze { topic, options }) => {
+                    commands::generate::cloze(topic, options).await?;
                 }
-                Some(GenerateAction::Flashcards { topic }) => {
-                    commands::generate::flashcards(topic).await?;
+                Some(GenerateAction::Quiz { topic, options }) => {
+                    commands::generate::quiz(topic, options).await?;
                 }
-                Some(GenerateAction::Quiz { topic }) => {
-                    commands::generate::quiz(topic).await?;
+                Some(GenerateAction::Problems {
+                    topic,
+                    count,
+                    difficulty,
+                }) => {
+                    commands::generate::problems(topic, count, difficulty).await?;
                 }
                 Some(GenerateAction::Summary { topic }) => {
                     commands::generate::summary(topic).await?;
                 }
+                Some(GenerateAction::Notes { id }) => {
+                    commands::generate::lecture_notes(id).await?;
+                }
+                Some(GenerateAction::StudyPlan { exam_date }) => {
+                    commands::generate::study_plan(exam_date).await?;
+                }
                 Some(GenerateAction::Homework) => {
                     commands::generate::homework_help().await?;
                 }
@@ -233,14 +874,132 @@ async fn main() -> Result<()> {
                 }
             }
         }
-        Some(Commands::Review) => {
+        Some(Commands::Review { action, tag, cram }) => {
+            commands::bucket::print_bucket_context();
+            match (action, cram) {
+                (Some(ReviewAction::Stats { days }), _) => commands::review::stats(days)?,
+                (None, Some(topic)) => {
+                    let topic = if topic.is_empty() { tag } else { Some(topic) };
+                    commands::review::cram(topic.as_deref().unwrap_or("")).await?
+                }
+                (None, None) => commands::review::run(tag).await?,
+            }
+        }
+        Some(Commands::Quiz { action, tag }) => {
+            commands::bucket::print_bucket_context();
+            match action {
+                Some(QuizAction::Bank) => commands::quiz::bank(tag).await?,
+                None => commands::quiz::run(tag).await?,
+            }
+        }
+        Some(Commands::Note { text, doc }) => {
+            commands::bucket::print_bucket_context();
+            commands::note::run(text, doc).await?;
+        }
+        Some(Commands::Conversations { action }) => {
+            // Keep stdout clean when exporting to it
+            let to_stdout = matches!(
+                &action,
+                Some(ConversationAction::Export { output: Some(path), .. }) if path.as_os_str() == "-"
+            );
+            if !to_stdout {
+                commands::bucket::print_bucket_context();
+            }
+            match action {
+                Some(ConversationAction::Rename { id, title }) => {
+                    commands::conversations::rename(id, title).await?;
+                }
+                Some(ConversationAction::Delete { id, older_than }) => {
+                    commands::conversations::delete(id, older_than).await?;
+                }
+                Some(ConversationAction::Export { id, format, output }) => {
+                    commands::conversations::export(id, format, output).await?;
+                }
+                Some(ConversationAction::List) | None => {
+                    commands::conversations::list().await?;
+                }
+            }
+        }
+        Some(Commands::Queue { action }) => {
+            commands::bucket::print_bucket_context();
+            match action {
+                Some(QueueAction::Mark {
+                    id,
+                    status,
+                    progress,
+                    position,
+                }) => {
+                    commands::queue::mark(id, status, progress, position).await?;
+                }
+                Some(QueueAction::List) | None => {
+                    commands::queue::list().await?;
+                }
+            }
+        }
+        Some(Commands::Next) => {
             commands::bucket::print_bucket_context();
-            commands::review::run().await?;
+            commands::queue::next().await?;
         }
-        Some(Commands::Quiz) => {
+        Some(Commands::Sync { id, every }) => {
             commands::bucket::print_bucket_context();
-            commands::quiz::run().await?;
+            commands::sync::run(id, every).await?;
         }
+        Some(Commands::Feed { action }) => {
+            commands::bucket::print_bucket_context();
+            match action {
+                FeedAction::Add { url } => commands::feed::add(url).await?,
+                FeedAction::List => commands::feed::list().await?,
+                FeedAction::Remove { id } => commands::feed::remove(id).await?,
+                FeedAction::Refresh => commands::feed::refresh().await?,
+            }
+        }
+        Some(Commands::Reindex { bucket, missing }) => {
+            commands::reindex::run(bucket, missing).await?;
+        }
+        Some(Commands::Cite { id, all, format }) => {
+            commands::cite::run(id, all, format).await?;
+        }
+        Some(Commands::Cards { action }) => match action {
+            CardsAction::Export {
+                format,
+                output,
+                tag,
+            } => {
+                if output.as_deref() != Some(std::path::Path::new("-")) {
+                    commands::bucket::print_bucket_context();
+                }
+                commands::cards::export(format, output, tag).await?;
+            }
+            CardsAction::Import { file } => {
+                commands::bucket::print_bucket_context();
+                commands::cards::import(&file).await?;
+            }
+        },
+        Some(Commands::Export { action }) => match action {
+            ExportAction::Library { format, output } => {
+                commands::export::library(format, output).await?;
+            }
+        },
+        Some(Commands::Import { action }) => match action {
+            ImportAction::Anki {
+                file,
+                keep_schedule,
+            } => {
+                commands::bucket::print_bucket_context();
+                commands::import::anki(&file, keep_schedule).await?;
+            }
+            ImportAction::Obsidian { vault } => {
+                commands::bucket::print_bucket_context();
+                commands::import::obsidian(&vault).await?;
+            }
+            ImportAction::Cards { file, tag } => {
+                commands::bucket::print_bucket_context();
+                commands::import::cards(&file, tag).await?;
+            }
+        },
+        Some(Commands::Stats { action }) => match action {
+            StatsAction::Usage { days } => commands::stats::usage(days)?,
+        },
         Some(Commands::Completions { shell }) => {
             let mut cmd = Cli::command();
             let name = cmd.get_name().to_string();
@@ -255,9 +1014,48 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// The subcommand being run, e.g. "generate quiz" ("menu" for none), to
+/// attribute model calls to
+fn command_name(matches: &clap::ArgMatches) -> String {
+    let mut names = Vec::new();
+    let mut current = matches;
+    while let Some((name, sub)) = current.subcommand() {
+        names.push(name);
+        current = sub;
+    }
+    if names.is_empty() {
+        "menu".to_string()
+    } else {
+        names.join(" ")
+    }
+}
+
+/// Apply a `--model` flag for this run, warning about ids the provider
+/// wasn't last seen offering (its cached model list, or the built-in one)
+fn override_model(model: Option<String>) {
+    use llm::ChatProvider;
+
+    let Some(model) = model else {
+        return;
+    };
+    if let Some(client) = config::Config::load().ok().as_ref().and_then(llm::client) {
+        let (known, _) = llm::models::known(&client);
+        // Local and custom servers nobody has listed run whatever they have
+        if !known.is_empty() && !known.iter().any(|m| m.id == model) {
+            eprintln!(
+                "{} {} isn't a model {} is known to offer; sending it as-is",
+                "Note:".yellow(),
+                model,
+                client.name()
+            );
+        }
+    }
+    config::Config::set_model_override(&model);
+}
+
 /// Display the library shelf with buckets as books
 fn print_library_shelf() {
-    let buckets = bucket::Bucket::list_all().unwrap_or_default();
+    let buckets = bucket::Bucket::list_active().unwrap_or_default();
     let current = bucket::get_current_bucket().ok().flatten().map(|b| b.name);
 
     if buckets.is_empty() {
@@ -298,28 +1096,31 @@ fn print_library_shelf() {
         "├─────────────────────────────────────────────┤".cyan()
     );
 
-    // Draw books on shelf
-    let mut book_row = String::from("    │ ");
+    // Draw books on shelf, one per row so descriptions fit
     for bucket_name in &buckets {
         let is_current = current.as_ref() == Some(bucket_name);
+        let label = bucket::Bucket::open(bucket_name)
+            .map(|b| b.label())
+            .unwrap_or_else(|_| format!("{} {}", bucket::DEFAULT_ICON, bucket_name));
+        // Emoji take two columns; keep the label inside the 45-column box
+        let width = |text: &str| {
+            text.chars().count() + text.chars().filter(|c| *c as u32 >= 0x1F000).count()
+        };
+        let mut label = format!(" {} ", label);
+        if width(&label) > 43 {
+            while width(&label) > 41 {
+                label.pop();
+            }
+            label.push_str("… ");
+        }
+        let padding = " ".repeat(44 - width(&label));
         let book = if is_current {
-            format!(" 📖 {} ", bucket_name)
-                .on_cyan()
-                .black()
-                .to_string()
+            label.on_cyan().black().to_string()
         } else {
-            format!(" 📕 {} ", bucket_name).to_string()
+            label
         };
-        book_row.push_str(&book);
-        book_row.push_str("  ");
+        println!("    {} {}{}{}", "│".cyan(), book, padding, "│".cyan());
     }
-    // Pad to fit box
-    let display_len = book_row.chars().count();
-    if display_len < 50 {
-        book_row.push_str(&" ".repeat(50 - display_len));
-    }
-    book_row.push('│');
-    println!("{}", book_row.cyan());
 
     println!(
         "    {}",
@@ -329,6 +1130,9 @@ fn print_library_shelf() {
         "    {}",
         "▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀▀".yellow()
     );
+    if let Some(rollup) = commands::bucket::library_rollup(&buckets) {
+        println!("    {}", rollup.dimmed());
+    }
 }
 
 /// Print the status dashboard
@@ -356,10 +1160,50 @@ fn print_dashboard() {
         })
         .unwrap_or(0);
 
-    // Check API key status
-    let has_api_key = config::Config::load()
-        .map(|c| c.has_api_key())
-        .unwrap_or(false);
+    // Get reading queue status
+    let queue = storage::Database::open()
+        .and_then(|db| {
+            let store = storage::ReadingStore::new(&db);
+            store.counts()
+        })
+        .unwrap_or_default();
+
+    // Study streak and this week's activity (across all buckets)
+    let streak_status = match storage::activity::summary() {
+        Ok((0, _)) | Err(_) => "Review or take a quiz to start one".dimmed().to_string(),
+        Ok((days, week)) => format!(
+            "{} {} │ {}",
+            days.to_string().yellow().bold(),
+            if days == 1 { "day" } else { "days" },
+            format!(
+                "this week: {} reviews, {} quizzes, {} documents",
+                week.reviews, week.quizzes, week.documents
+            )
+            .dimmed()
+        ),
+    };
+
+    // Check API key status (Ollama runs locally without one)
+    let config = config::Config::load().unwrap_or_default();
+    let llm_status = if config.provider == config::Provider::Ollama {
+        "Not needed (Ollama)".green().to_string()
+    } else if config.provider == config::Provider::OpenAi {
+        if llm::client(&config).is_some() {
+            "Ready".green().to_string()
+        } else {
+            "Not configured".red().to_string()
+        }
+    } else if config.provider == config::Provider::Anthropic {
+        if config.anthropic_api_key().is_some() {
+            "Ready".green().to_string()
+        } else {
+            "Not configured".red().to_string()
+        }
+    } else if config.has_api_key() {
+        "Ready".green().to_string()
+    } else {
+        "Not configured".red().to_string()
+    };
 
     println!();
     println!(
@@ -383,15 +1227,24 @@ fn print_dashboard() {
         doc_count.to_string().green(),
         chunk_count.to_string().green()
     );
+    println!(
+        "    {}  {} {} in progress, {} unread",
+        "│".bright_black(),
+        "📑 Reading:".bold(),
+        queue.in_progress.to_string().yellow(),
+        queue.unread.to_string().cyan()
+    );
     println!(
         "    {}  {} {}",
         "│".bright_black(),
         "🔑 API Key:".bold(),
-        if has_api_key {
-            "Ready".green().to_string()
-        } else {
-            "Not configured".red().to_string()
-        }
+        llm_status
+    );
+    println!(
+        "    {}  {} {}",
+        "│".bright_black(),
+        "🔥 Streak:".bold(),
+        streak_status
     );
     println!(
         "    {}",
@@ -467,13 +1320,28 @@ async fn run_interactive() -> Result<()> {
 
         // Execute the selected action, catching errors gracefully
         let result = match selection {
-            s if s.contains("Add Knowledge") => commands::add::run(None).await,
-            s if s.contains("Ask the Librarian") => commands::chat::run().await,
+            s if s.contains("Add Knowledge") => {
+                commands::add::run(
+                    None,
+                    None,
+                    commands::add::DEFAULT_CHANNEL_DELAY_SECS,
+                    None,
+                    None,
+                    Vec::new(),
+                )
+                .await
+            }
+            s if s.contains("Ask the Librarian") => commands::chat::run(None, false, None).await,
             s if s.contains("Study Tools") => commands::generate::run().await,
-            s if s.contains("Review") => commands::review::run().await,
-            s if s.contains("Quiz") => commands::quiz::run().await,
-            s if s.contains("Browse Collection") => commands::docs::list().await,
-            s if s.contains("Search") => commands::docs::search(None).await,
+            s if s.contains("Review") => commands::review::run(None).await,
+            s if s.contains("Quiz") => commands::quiz::run(None).await,
+            s if s.contains("Browse Collection") => {
+                commands::docs::list(OutputFormat::Text, &commands::docs::ListOptions::default())
+                    .await
+            }
+            s if s.contains("Search") => {
+                commands::docs::search(None, OutputFormat::Text, None).await
+            }
             s if s.contains("Manage Documents") => commands::docs::run().await,
             s if s.contains("Manage Library") => commands::bucket::run().await,
             s if s.contains("Settings") => commands::config::run().await,
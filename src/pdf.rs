@@ -0,0 +1,650 @@
+//! Typesets generated markdown (headings, lists, quotes, code) into a PDF
+//! using the standard PDF fonts, so nothing needs to be embedded.
+
+use anyhow::{Context, Result};
+use lopdf::content::{Content, Operation};
+use lopdf::{Document, Object, Stream, StringFormat, dictionary};
+use std::path::Path;
+use termimad::minimad::{self, Composite, CompositeStyle, Compound, Line};
+
+/// A4, in points
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 56.0;
+const TEXT_WIDTH: f32 = PAGE_WIDTH - 2.0 * MARGIN;
+
+const BODY_SIZE: f32 = 11.0;
+const CODE_SIZE: f32 = 9.5;
+/// Space between lines as a multiple of the font size
+const LEADING: f32 = 1.35;
+/// Indent per list level and for quotes
+const INDENT: f32 = 16.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Font {
+    Regular,
+    Bold,
+    Italic,
+    BoldItalic,
+    Mono,
+}
+
+impl Font {
+    const ALL: [Font; 5] = [
+        Font::Regular,
+        Font::Bold,
+        Font::Italic,
+        Font::BoldItalic,
+        Font::Mono,
+    ];
+
+    /// Name the page resources refer to the font by
+    fn resource(self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+            Font::Italic => "F3",
+            Font::BoldItalic => "F4",
+            Font::Mono => "F5",
+        }
+    }
+
+    fn base_font(self) -> &'static str {
+        match self {
+            Font::Regular => "Helvetica",
+            Font::Bold => "Helvetica-Bold",
+            Font::Italic => "Helvetica-Oblique",
+            Font::BoldItalic => "Helvetica-BoldOblique",
+            Font::Mono => "Courier",
+        }
+    }
+
+    fn of(compound: &Compound, bold: bool) -> Font {
+        match (compound.code, compound.bold || bold, compound.italic) {
+            (true, _, _) => Font::Mono,
+            (false, true, true) => Font::BoldItalic,
+            (false, true, false) => Font::Bold,
+            (false, false, true) => Font::Italic,
+            (false, false, false) => Font::Regular,
+        }
+    }
+
+    /// Width of `text` at `size` points
+    fn width(self, text: &str, size: f32) -> f32 {
+        let units: f32 = text
+            .chars()
+            .map(|c| match self {
+                Font::Mono => 600.0,
+                // Bold glyphs run roughly a tenth wider; erring wide only
+                // wraps a little early
+                Font::Bold | Font::BoldItalic => helvetica_width(c) * 1.1,
+                Font::Regular | Font::Italic => helvetica_width(c),
+            })
+            .sum();
+        units * size / 1000.0
+    }
+}
+
+/// Helvetica's advance width for a character, in thousandths of the font size
+fn helvetica_width(c: char) -> f32 {
+    // Space through '~', from the font's metrics
+    #[rustfmt::skip]
+    const ASCII: [u16; 95] = [
+        278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+        556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+        1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+        667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+        333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+        556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+    ];
+    match c {
+        ' '..='~' => f32::from(ASCII[c as usize - 32]),
+        '—' | '…' => 1000.0,
+        _ => 556.0,
+    }
+}
+
+/// Encode text for the standard fonts (WinAnsiEncoding); characters they
+/// can't show become '?'
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' => c as u8,
+            '\u{a0}'..='\u{ff}' => c as u32 as u8,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '…' => 0x85,
+            '€' => 0x80,
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// A PDF text string for metadata, as UTF-16BE with a byte order mark
+/// written in hex (`<FEFF...>`), so any title survives, not just WinAnsi
+fn text_string(text: &str) -> Object {
+    let bytes = [0xFE, 0xFF]
+        .into_iter()
+        .chain(text.encode_utf16().flat_map(u16::to_be_bytes))
+        .collect();
+    Object::String(bytes, StringFormat::Hexadecimal)
+}
+
+/// A stretch of text in one font
+#[derive(Debug, Clone, PartialEq)]
+struct Run {
+    font: Font,
+    text: String,
+}
+
+/// Text between spaces, possibly in several fonts ("**ATP**,")
+type Word = Vec<Run>;
+
+fn word_width(word: &[Run], size: f32) -> f32 {
+    word.iter().map(|r| r.font.width(&r.text, size)).sum()
+}
+
+/// Split styled text into words
+fn words(compounds: &[Compound], bold: bool) -> Vec<Word> {
+    let mut words = Vec::new();
+    let mut word: Word = Vec::new();
+
+    for compound in compounds {
+        let font = Font::of(compound, bold);
+        for c in compound.src.chars() {
+            if c.is_whitespace() {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                continue;
+            }
+            match word.last_mut() {
+                Some(run) if run.font == font => run.text.push(c),
+                _ => word.push(Run {
+                    font,
+                    text: c.to_string(),
+                }),
+            }
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// Break words into lines no wider than `width`, splitting words that are
+/// wider than a whole line
+fn wrap(words: Vec<Word>, size: f32, width: f32) -> Vec<Vec<Run>> {
+    let space = Font::Regular.width(" ", size);
+    let mut lines = Vec::new();
+    let mut line: Vec<Run> = Vec::new();
+    let mut line_width = 0.0;
+
+    for word in words
+        .into_iter()
+        .flat_map(|w| split_long_word(w, size, width))
+    {
+        let w = word_width(&word, size);
+        if !line.is_empty() && line_width + space + w > width {
+            lines.push(std::mem::take(&mut line));
+            line_width = 0.0;
+        }
+        if !line.is_empty() {
+            push_text(&mut line, Font::Regular, " ");
+            line_width += space;
+        }
+        for run in word {
+            push_text(&mut line, run.font, &run.text);
+        }
+        line_width += w;
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Append text, merging it into the last run when the font matches
+fn push_text(line: &mut Vec<Run>, font: Font, text: &str) {
+    match line.last_mut() {
+        Some(run) if run.font == font => run.text.push_str(text),
+        _ => line.push(Run {
+            font,
+            text: text.to_string(),
+        }),
+    }
+}
+
+fn split_long_word(word: Word, size: f32, width: f32) -> Vec<Word> {
+    if word_width(&word, size) <= width {
+        return vec![word];
+    }
+    let mut pieces = Vec::new();
+    let mut piece: Word = Vec::new();
+    let mut piece_width = 0.0;
+    for run in word {
+        for c in run.text.chars() {
+            let w = run.font.width(c.encode_utf8(&mut [0; 4]), size);
+            if piece_width + w > width && !piece.is_empty() {
+                pieces.push(std::mem::take(&mut piece));
+                piece_width = 0.0;
+            }
+            push_text(&mut piece, run.font, c.encode_utf8(&mut [0; 4]));
+            piece_width += w;
+        }
+    }
+    if !piece.is_empty() {
+        pieces.push(piece);
+    }
+    pieces
+}
+
+/// Pages of drawing operations, filled top to bottom
+struct Pages {
+    done: Vec<Vec<Operation>>,
+    current: Vec<Operation>,
+    /// Baseline of the next line
+    y: f32,
+}
+
+impl Pages {
+    fn new() -> Self {
+        Self {
+            done: Vec::new(),
+            current: Vec::new(),
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    fn new_page(&mut self) {
+        self.done.push(std::mem::take(&mut self.current));
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    /// Start a new page unless `height` more points fit on this one
+    fn reserve(&mut self, height: f32) {
+        if self.y - height < MARGIN && !self.current.is_empty() {
+            self.new_page();
+        }
+    }
+
+    fn skip(&mut self, height: f32) {
+        if self.y < PAGE_HEIGHT - MARGIN {
+            self.y -= height;
+        }
+    }
+
+    /// Draw one line of runs with its left edge at `x`
+    fn line(&mut self, x: f32, runs: &[Run], size: f32) {
+        let height = size * LEADING;
+        self.reserve(height);
+        self.y -= size;
+
+        self.current.push(Operation::new("BT", vec![]));
+        self.current
+            .push(Operation::new("Td", vec![x.into(), self.y.into()]));
+        for run in runs {
+            self.current.push(Operation::new(
+                "Tf",
+                vec![Object::Name(run.font.resource().into()), size.into()],
+            ));
+            self.current.push(Operation::new(
+                "Tj",
+                vec![Object::string_literal(encode(&run.text))],
+            ));
+        }
+        self.current.push(Operation::new("ET", vec![]));
+
+        self.y -= height - size;
+    }
+
+    /// Wrap words into the space from `x` to the right margin
+    fn paragraph(&mut self, x: f32, words: Vec<Word>, size: f32) {
+        for line in wrap(words, size, PAGE_WIDTH - MARGIN - x) {
+            self.line(x, &line, size);
+        }
+    }
+
+    /// A light grey band behind the next `height` points (for code)
+    fn shade(&mut self, height: f32) {
+        self.current.extend([
+            Operation::new("q", vec![]),
+            Operation::new("rg", vec![0.94.into(), 0.94.into(), 0.94.into()]),
+            Operation::new(
+                "re",
+                vec![
+                    (MARGIN - 4.0).into(),
+                    (self.y - height).into(),
+                    (TEXT_WIDTH + 8.0).into(),
+                    height.into(),
+                ],
+            ),
+            Operation::new("f", vec![]),
+            Operation::new("Q", vec![]),
+        ]);
+    }
+
+    fn rule(&mut self) {
+        self.reserve(BODY_SIZE);
+        self.y -= BODY_SIZE / 2.0;
+        self.current.extend([
+            Operation::new("w", vec![0.5.into()]),
+            Operation::new("m", vec![MARGIN.into(), self.y.into()]),
+            Operation::new("l", vec![(PAGE_WIDTH - MARGIN).into(), self.y.into()]),
+            Operation::new("S", vec![]),
+        ]);
+        self.y -= BODY_SIZE / 2.0;
+    }
+
+    fn finish(mut self) -> Vec<Vec<Operation>> {
+        if !self.current.is_empty() || self.done.is_empty() {
+            self.done.push(self.current);
+        }
+        self.done
+    }
+}
+
+/// Numbered list prefix of a paragraph's first compound
+fn number_prefix(composite: &Composite) -> Option<usize> {
+    crate::render::number_prefix(composite.compounds.first()?.src)
+}
+
+/// Lay out markdown as pages of drawing operations
+fn layout(markdown: &str) -> Vec<Vec<Operation>> {
+    let markdown = crate::render::normalize_lists(markdown);
+    let text = minimad::parse_text(&markdown, minimad::Options::default());
+    let mut pages = Pages::new();
+    // Source lines of the paragraph being gathered (markdown soft-wraps)
+    let mut paragraph: Vec<Word> = Vec::new();
+    let mut in_code = false;
+
+    let flush = |pages: &mut Pages, paragraph: &mut Vec<Word>| {
+        if !paragraph.is_empty() {
+            pages.paragraph(MARGIN, std::mem::take(paragraph), BODY_SIZE);
+            pages.skip(BODY_SIZE * 0.6);
+        }
+    };
+
+    for line in &text.lines {
+        let code_line = matches!(line, Line::Normal(c) if c.style == CompositeStyle::Code);
+        if in_code && !code_line {
+            pages.skip(BODY_SIZE * 0.6);
+        }
+        in_code = code_line;
+
+        match line {
+            Line::Normal(composite) => match composite.style {
+                CompositeStyle::Paragraph if composite.compounds.is_empty() => {
+                    flush(&mut pages, &mut paragraph);
+                }
+                CompositeStyle::Paragraph => match number_prefix(composite) {
+                    Some(prefix_len) => {
+                        flush(&mut pages, &mut paragraph);
+                        let mut compounds = composite.compounds.clone();
+                        let number = compounds[0].src[..prefix_len].trim().to_string();
+                        compounds[0].src = &compounds[0].src[prefix_len..];
+                        list_item(&mut pages, &number, 0, &compounds);
+                    }
+                    None => paragraph.extend(words(&composite.compounds, false)),
+                },
+                CompositeStyle::Header(level) => {
+                    flush(&mut pages, &mut paragraph);
+                    let size = match level {
+                        1 => 20.0,
+                        2 => 16.0,
+                        3 => 13.0,
+                        _ => BODY_SIZE,
+                    };
+                    // Keep a heading with the line after it
+                    pages.reserve(size * LEADING + BODY_SIZE * LEADING * 2.0);
+                    pages.skip(size * 0.5);
+                    pages.paragraph(MARGIN, words(&composite.compounds, true), size);
+                    pages.skip(size * 0.3);
+                }
+                CompositeStyle::ListItem(depth) => {
+                    flush(&mut pages, &mut paragraph);
+                    list_item(&mut pages, "•", depth, &composite.compounds);
+                }
+                CompositeStyle::Quote => {
+                    flush(&mut pages, &mut paragraph);
+                    let quoted: Vec<Compound> = composite
+                        .compounds
+                        .iter()
+                        .map(|c| {
+                            let mut c = c.clone();
+                            c.italic = true;
+                            c
+                        })
+                        .collect();
+                    pages.paragraph(MARGIN + INDENT, words(&quoted, false), BODY_SIZE);
+                }
+                CompositeStyle::Code => {
+                    flush(&mut pages, &mut paragraph);
+                    let source: String = composite.compounds.iter().map(|c| c.src).collect();
+                    code_line_out(&mut pages, &source);
+                }
+            },
+            Line::TableRow(row) => {
+                flush(&mut pages, &mut paragraph);
+                let mut cells: Vec<Word> = Vec::new();
+                for (i, cell) in row.cells.iter().enumerate() {
+                    if i > 0 {
+                        cells.push(vec![Run {
+                            font: Font::Regular,
+                            text: "|".to_string(),
+                        }]);
+                    }
+                    cells.extend(words(&cell.compounds, false));
+                }
+                pages.paragraph(MARGIN, cells, BODY_SIZE);
+            }
+            Line::TableRule(_) => {}
+            Line::HorizontalRule => {
+                flush(&mut pages, &mut paragraph);
+                pages.rule();
+            }
+            Line::CodeFence(_) => {}
+        }
+    }
+    flush(&mut pages, &mut paragraph);
+
+    pages.finish()
+}
+
+/// A bullet or number hanging left of its wrapped text
+fn list_item(pages: &mut Pages, marker: &str, depth: u8, compounds: &[Compound]) {
+    let x = MARGIN + INDENT * f32::from(depth);
+    let lines = wrap(
+        words(compounds, false),
+        BODY_SIZE,
+        PAGE_WIDTH - MARGIN - x - INDENT,
+    );
+    for (i, mut line) in lines.into_iter().enumerate() {
+        if i == 0 {
+            // The marker and the text share a line; pad the marker out to
+            // the text column
+            let marker_width = Font::Regular.width(marker, BODY_SIZE);
+            let space = Font::Regular.width(" ", BODY_SIZE);
+            let pad = ((INDENT - marker_width) / space).max(1.0) as usize;
+            line.insert(
+                0,
+                Run {
+                    font: Font::Regular,
+                    text: format!("{}{}", marker, " ".repeat(pad)),
+                },
+            );
+            pages.line(x, &line, BODY_SIZE);
+        } else {
+            pages.line(x + INDENT, &line, BODY_SIZE);
+        }
+    }
+    pages.skip(BODY_SIZE * 0.2);
+}
+
+/// A line of code, hard-wrapped at the right margin
+fn code_line_out(pages: &mut Pages, source: &str) {
+    let columns = (TEXT_WIDTH / Font::Mono.width(" ", CODE_SIZE)) as usize;
+    let chars: Vec<char> = source.chars().collect();
+    let pieces: Vec<String> = if chars.is_empty() {
+        vec![String::new()]
+    } else {
+        chars
+            .chunks(columns.max(1))
+            .map(|c| c.iter().collect())
+            .collect()
+    };
+
+    for piece in pieces {
+        let height = CODE_SIZE * LEADING;
+        pages.reserve(height);
+        pages.shade(height);
+        pages.line(
+            MARGIN,
+            &[Run {
+                font: Font::Mono,
+                text: piece,
+            }],
+            CODE_SIZE,
+        );
+    }
+}
+
+/// Render markdown to a PDF file titled `title`
+pub fn write_markdown(markdown: &str, title: &str, path: &Path) -> Result<()> {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+
+    let mut fonts = lopdf::Dictionary::new();
+    for font in Font::ALL {
+        let id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => font.base_font(),
+            "Encoding" => "WinAnsiEncoding",
+        });
+        fonts.set(font.resource(), id);
+    }
+    let resources_id = doc.add_object(dictionary! { "Font" => fonts });
+
+    let laid_out = layout(markdown);
+    let total = laid_out.len();
+    let mut kids = Vec::new();
+    for (i, mut operations) in laid_out.into_iter().enumerate() {
+        let footer = format!("{} of {}", i + 1, total);
+        let x = (PAGE_WIDTH - Font::Regular.width(&footer, 9.0)) / 2.0;
+        operations.extend([
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec![Object::Name(b"F1".to_vec()), 9.into()]),
+            Operation::new("Td", vec![x.into(), (MARGIN / 2.0).into()]),
+            Operation::new("Tj", vec![Object::string_literal(footer)]),
+            Operation::new("ET", vec![]),
+        ]);
+
+        let content = Content { operations }
+            .encode()
+            .context("Failed to encode PDF page")?;
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+        });
+        kids.push(Object::from(page_id));
+    }
+
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => kids,
+            "Count" => total as i64,
+            "Resources" => resources_id,
+            "MediaBox" => vec![0.into(), 0.into(), PAGE_WIDTH.into(), PAGE_HEIGHT.into()],
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    let info_id = doc.add_object(dictionary! {
+        "Title" => text_string(title),
+        "Producer" => Object::string_literal("The Librarian"),
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.trailer.set("Info", info_id);
+    doc.compress();
+
+    doc.save(path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_keeps_lines_inside_width() {
+        let text =
+            "The **mitochondria** produce `ATP` through oxidative phosphorylation. ".repeat(8);
+        let composite = minimad::parse_inline(&text);
+        let lines = wrap(words(&composite.compounds, false), BODY_SIZE, 200.0);
+
+        assert!(lines.len() > 5);
+        for line in &lines {
+            assert!(word_width(line, BODY_SIZE) <= 200.0);
+        }
+        let fonts: Vec<Font> = lines.iter().flatten().map(|r| r.font).collect();
+        assert!(fonts.contains(&Font::Bold) && fonts.contains(&Font::Mono));
+    }
+
+    #[test]
+    fn test_write_markdown() {
+        let markdown = "# Cell Biology\n\nATP is the cell's **energy** currency.\n\n\
+                        ## Key terms\n\n- Mitochondria\n- Ribosome\n\n1. First\n2. Second\n\n\
+                        ```\nfn main() {}\n```\n\n> Remember this\n";
+        let pages = layout(markdown);
+        assert_eq!(pages.len(), 1);
+
+        let long = "A paragraph of study notes.\n\n".repeat(200);
+        assert!(layout(&long).len() > 1);
+
+        let path = std::env::temp_dir().join(format!("librarian_pdf_{}.pdf", std::process::id()));
+        write_markdown(markdown, "Cell Biology – ATP ✓", &path).unwrap();
+        let written = Document::load(&path).unwrap();
+        let info = written
+            .trailer
+            .get(b"Info")
+            .and_then(Object::as_reference)
+            .and_then(|id| written.get_dictionary(id))
+            .unwrap();
+        let title = info.get(b"Title").unwrap();
+        assert!(matches!(
+            title,
+            Object::String(_, StringFormat::Hexadecimal)
+        ));
+        let title = title.as_str().unwrap();
+        assert_eq!(title[..2], [0xFE, 0xFF]);
+        let units: Vec<u16> = title[2..]
+            .chunks(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        assert_eq!(String::from_utf16(&units).unwrap(), "Cell Biology – ATP ✓");
+        let text = pdf_extract::extract_text(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(written.get_pages().len(), 1);
+        assert!(text.contains("Cell Biology"));
+        assert!(text.contains("Mitochondria"));
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode("a–b “é” ✓"), b"a\x96b \x93\xe9\x94 ?".to_vec());
+    }
+}
@@ -1,5 +1,261 @@
-/// Render markdown text to the terminal using termimad
-pub fn render_markdown(text: &str) {
-    let skin = termimad::MadSkin::default();
-    skin.print_text(text);
+use anyhow::Result;
+use colored::Colorize;
+use std::io::{IsTerminal, Write};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::Notify;
+
+use crate::llm::{ChatProvider, Message};
+
+/// Set while an answer streams, so Ctrl+C stops the answer instead of the app
+static STREAMING: AtomicBool = AtomicBool::new(false);
+
+/// Stream a response, rendering its markdown as it arrives when stdout is a
+/// terminal (and printing it raw otherwise, so pipes get plain markdown).
+/// Ctrl+C stops the request and returns what had arrived so far.
+pub async fn stream_answer(client: &impl ChatProvider, messages: &[Message]) -> Result<String> {
+    let terminal = std::io::stdout().is_terminal();
+    let mut markdown = MarkdownStream::new();
+    let mut partial = String::new();
+
+    // Created before streaming starts so an early Ctrl+C isn't missed
+    let interrupted = interrupts().notified();
+    STREAMING.store(true, Ordering::SeqCst);
+    let result = tokio::select! {
+        result = client.chat_stream_with(messages, |token| {
+            partial.push_str(token);
+            if terminal {
+                markdown.push(token);
+            } else {
+                print!("{}", token);
+                std::io::stdout().flush().ok();
+            }
+        }) => Some(result),
+        _ = interrupted => None,
+    };
+    STREAMING.store(false, Ordering::SeqCst);
+
+    if terminal {
+        markdown.finish();
+    } else {
+        println!();
+    }
+
+    match result {
+        Some(response) => response,
+        None => {
+            println!("{}", "⏹ Stopped".dimmed());
+            Ok(partial)
+        }
+    }
+}
+
+/// Notified on Ctrl+C while an answer is streaming. Any other time Ctrl+C
+/// exits as usual.
+fn interrupts() -> &'static Notify {
+    static INTERRUPTS: OnceLock<Notify> = OnceLock::new();
+    INTERRUPTS.get_or_init(|| {
+        tokio::spawn(async {
+            while tokio::signal::ctrl_c().await.is_ok() {
+                if STREAMING.load(Ordering::SeqCst) {
+                    interrupts().notify_waiters();
+                } else {
+                    std::process::exit(130);
+                }
+            }
+        });
+        Notify::new()
+    })
+}
+
+/// Print a complete markdown document, rendered when stdout is a terminal
+pub fn print_markdown(text: &str) {
+    if std::io::stdout().is_terminal() {
+        termimad::MadSkin::default().print_text(text);
+    } else {
+        println!("{}", text);
+    }
+}
+
+/// Show long text a screen at a time in `$PAGER` (or `less`), rendering it as
+/// markdown if asked. Without a terminal, or if the pager won't start, the
+/// text is just printed.
+pub fn page(text: &str, markdown: bool) -> Result<()> {
+    if !std::io::stdout().is_terminal() {
+        println!("{}", text);
+        return Ok(());
+    }
+
+    let rendered = if markdown {
+        termimad::MadSkin::default().term_text(text).to_string()
+    } else {
+        text.to_string()
+    };
+
+    let pager = std::env::var("PAGER")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| "less -R".to_string());
+    let mut args = pager.split_whitespace();
+    let program = args.next().unwrap_or("less");
+    let child = std::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(_) => {
+            println!("{}", rendered);
+            return Ok(());
+        }
+    };
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // Quitting the pager early closes the pipe, which isn't an error
+        let _ = stdin.write_all(rendered.as_bytes());
+    }
+    child.wait()?;
+    Ok(())
+}
+
+/// Renders streamed markdown a block at a time: text is held back until a
+/// paragraph, heading or fenced code block is complete, then printed with
+/// termimad
+pub struct MarkdownStream {
+    skin: termimad::MadSkin,
+    pending: String,
+}
+
+impl MarkdownStream {
+    pub fn new() -> Self {
+        Self {
+            skin: termimad::MadSkin::default(),
+            pending: String::new(),
+        }
+    }
+
+    /// Add streamed text, printing whatever blocks it completes
+    pub fn push(&mut self, text: &str) {
+        self.pending.push_str(text);
+        let complete = complete_blocks_len(&self.pending);
+        if complete > 0 {
+            let blocks: String = self.pending.drain(..complete).collect();
+            self.skin.print_text(&blocks);
+        }
+    }
+
+    /// Print whatever is left once the stream has ended
+    pub fn finish(&mut self) {
+        if !self.pending.trim().is_empty() {
+            self.skin.print_text(&self.pending);
+        }
+        self.pending.clear();
+    }
+}
+
+/// Length of the complete markdown blocks at the start of `text`: up to the
+/// last blank line, heading or closing code fence that isn't inside a code
+/// block (0 if no block is complete yet)
+fn complete_blocks_len(text: &str) -> usize {
+    let mut complete = 0;
+    let mut offset = 0;
+    let mut in_fence = false;
+
+    // Only whole lines count; the last one may still be arriving
+    for line in text.split_inclusive('\n') {
+        offset += line.len();
+        if !line.ends_with('\n') {
+            break;
+        }
+
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            if !in_fence {
+                complete = offset;
+            }
+        } else if !in_fence && (trimmed.is_empty() || trimmed.starts_with('#')) {
+            complete = offset;
+        }
+    }
+
+    complete
+}
+
+/// Bullets as minimad reads them. It only knows "* item", with one space of
+/// indent per level, so "- item" and nested bullets (which models indent by
+/// two or four spaces) are rewritten; code blocks are left alone.
+pub fn normalize_lists(markdown: &str) -> String {
+    let mut out = String::with_capacity(markdown.len());
+    let mut in_fence = false;
+
+    for line in markdown.lines() {
+        let rest = line.trim_start();
+        if rest.starts_with("```") || rest.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        let indent: usize = line[..line.len() - rest.len()]
+            .chars()
+            .map(|c| if c == '\t' { 4 } else { 1 })
+            .sum();
+        let is_rule = rest.chars().all(|c| matches!(c, '-' | '*' | '_' | ' '));
+
+        if !in_fence && !is_rule && ["- ", "+ ", "* "].iter().any(|m| rest.starts_with(m)) {
+            let depth = (indent / 2).min(3);
+            out.push_str(&" ".repeat(depth));
+            out.push_str("* ");
+            out.push_str(&rest[2..]);
+        } else if !in_fence && indent > 0 && number_prefix(rest).is_some() {
+            // Indented numbered items would otherwise read as code
+            out.push_str(rest);
+        } else {
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Length of the "1. " or "2) " opening a numbered list item
+pub fn number_prefix(text: &str) -> Option<usize> {
+    let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let rest = &text[digits..];
+    (digits > 0 && digits < 4 && (rest.starts_with(". ") || rest.starts_with(") ")))
+        .then_some(digits + 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_lists() {
+        let markdown = "- Cells\n  - Mitochondria\n    + ATP\n---\n   2. Step\n```\n- code\n```\n";
+        assert_eq!(
+            normalize_lists(markdown),
+            "* Cells\n * Mitochondria\n  * ATP\n---\n2. Step\n```\n- code\n```\n"
+        );
+        assert_eq!(number_prefix("12) Twelve"), Some(4));
+        assert_eq!(number_prefix("2024 was"), None);
+    }
+
+    #[test]
+    fn test_complete_blocks_len() {
+        // Nothing complete until the paragraph ends
+        assert_eq!(complete_blocks_len("Mitochondria are"), 0);
+        assert_eq!(complete_blocks_len("Mitochondria are\nthe"), 0);
+
+        let text = "Mitochondria.\n\nATP is";
+        assert_eq!(complete_blocks_len(text), "Mitochondria.\n\n".len());
+
+        // Headings are complete at the end of their line
+        let text = "## Summary\nCells";
+        assert_eq!(complete_blocks_len(text), "## Summary\n".len());
+
+        // Blank lines and # comments inside a code block don't end it
+        let text = "```python\n# energy\n\nx = 1\n";
+        assert_eq!(complete_blocks_len(text), 0);
+        let text = "```python\n# energy\n\nx = 1\n```\nDone";
+        assert_eq!(complete_blocks_len(text), text.len() - "Done".len());
+    }
 }
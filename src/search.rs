@@ -1,4 +1,9 @@
 use std::collections::HashSet;
+use std::ops::Range;
+
+use crate::config::Reranker;
+use crate::embeddings::{cosine_similarity, rerank};
+use crate::storage::chunks::StoredChunk;
 
 /// Filler words/phrases to strip from queries for better embedding search
 const FILLER_PREFIXES: &[&str] = &[
@@ -138,31 +143,102 @@ fn extract_references(query: &str) -> Vec<String> {
     refs
 }
 
-/// Check if two text chunks have significant word-level overlap (Jaccard similarity)
-pub fn chunks_overlap(a: &str, b: &str, threshold: f64) -> bool {
-    let words_a: HashSet<&str> = a
-        .split_whitespace()
-        .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()))
-        .filter(|w| !w.is_empty())
-        .collect();
-    let words_b: HashSet<&str> = b
-        .split_whitespace()
+/// Distinct words of a chunk, as `chunks_overlap` compares them
+pub fn word_set(text: &str) -> HashSet<&str> {
+    text.split_whitespace()
         .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()))
         .filter(|w| !w.is_empty())
-        .collect();
+        .collect()
+}
 
-    if words_a.is_empty() || words_b.is_empty() {
-        return false;
+/// Jaccard similarity of two word sets (0.0 when either is empty)
+fn jaccard(a: &HashSet<&str>, b: &HashSet<&str>) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
     }
+    let intersection = a.intersection(b).count();
+    let union = a.len() + b.len() - intersection;
+    intersection as f64 / union as f64
+}
 
-    let intersection = words_a.intersection(&words_b).count();
-    let union = words_a.union(&words_b).count();
+/// Check if two text chunks have significant word-level overlap (Jaccard similarity)
+pub fn chunks_overlap(a: &str, b: &str, threshold: f64) -> bool {
+    let (words_a, words_b) = (word_set(a), word_set(b));
+    !words_a.is_empty() && !words_b.is_empty() && jaccard(&words_a, &words_b) >= threshold
+}
 
-    if union == 0 {
-        return false;
+/// MinHash signatures taken of each chunk
+const MINHASH_SEEDS: u64 = 4;
+
+/// MinHash signatures of a chunk's words as (seed, smallest hash) - two
+/// chunks with a word Jaccard of J share each one with probability J, so
+/// chunks that overlap at 0.8 almost always share at least one of the four
+pub fn minhashes(words: &HashSet<&str>) -> Vec<(u64, u64)> {
+    use std::hash::{DefaultHasher, Hash, Hasher};
+
+    (0..MINHASH_SEEDS)
+        .filter_map(|seed| {
+            words
+                .iter()
+                .map(|word| {
+                    let mut hasher = DefaultHasher::new();
+                    seed.hash(&mut hasher);
+                    word.hash(&mut hasher);
+                    hasher.finish()
+                })
+                .min()
+                .map(|min| (seed, min))
+        })
+        .collect()
+}
+
+/// Share of the chunks in `a` that overlap some chunk of `b` (0.0 if `a` has
+/// none), to spot a document that repeats most of another. Takes each chunk's
+/// `word_set` so they're built once per document, not once per comparison.
+pub fn chunk_overlap_share(a: &[HashSet<&str>], b: &[HashSet<&str>], threshold: f64) -> f64 {
+    if a.is_empty() {
+        return 0.0;
     }
+    let shared = a
+        .iter()
+        .filter(|x| {
+            !x.is_empty()
+                && b.iter()
+                    .any(|y| !y.is_empty() && jaccard(x, y) >= threshold)
+        })
+        .count();
+    shared as f64 / a.len() as f64
+}
 
-    (intersection as f64 / union as f64) >= threshold
+/// Pick the candidate passage that shares the most words with `text`
+/// (used to link a generated card back to the chunk it came from)
+pub fn best_source(text: &str, candidates: &[(i64, String)]) -> Option<i64> {
+    let words: HashSet<String> = text
+        .split_whitespace()
+        .map(|w| {
+            w.trim_matches(|c: char| !c.is_alphanumeric())
+                .to_lowercase()
+        })
+        .filter(|w| w.len() > 3)
+        .collect();
+
+    if words.is_empty() {
+        return None;
+    }
+
+    candidates
+        .iter()
+        .map(|(id, content)| {
+            let content = content.to_lowercase();
+            let hits = words
+                .iter()
+                .filter(|w| content.contains(w.as_str()))
+                .count();
+            (*id, hits)
+        })
+        .filter(|(_, hits)| *hits > 0)
+        .max_by_key(|(_, hits)| *hits)
+        .map(|(id, _)| id)
 }
 
 /// Remove chunks with >80% word overlap, keeping the first occurrence
@@ -181,6 +257,167 @@ pub fn deduplicate_chunks(chunks: Vec<(i64, String)>) -> Vec<(i64, String)> {
     result
 }
 
+/// Nearest chunks fetched for MMR to choose from
+pub const MMR_CANDIDATES: usize = 30;
+
+/// Weight of relevance against diversity when re-selecting with MMR (1.0
+/// ranks by relevance alone)
+pub const MMR_LAMBDA: f32 = 0.7;
+
+/// Extra redundancy counted against a chunk for each already-picked chunk from
+/// the same document, so context spreads across sources
+const SAME_DOCUMENT_PENALTY: f32 = 0.15;
+
+/// Maximal Marginal Relevance: pick `k` of the candidates (chunk, similarity to
+/// the query), each time taking the one most relevant to the query and least
+/// like the chunks already picked. Returned in pick order.
+pub fn mmr_select(
+    mut candidates: Vec<(StoredChunk, f32)>,
+    k: usize,
+    lambda: f32,
+) -> Vec<(StoredChunk, f32)> {
+    let mut selected: Vec<(StoredChunk, f32)> = Vec::new();
+
+    while selected.len() < k && !candidates.is_empty() {
+        let scores = candidates.iter().map(|(chunk, relevance)| {
+            let redundancy = selected
+                .iter()
+                .map(|(picked, _)| redundancy(chunk, picked))
+                .fold(0.0, f32::max);
+            lambda * relevance - (1.0 - lambda) * redundancy
+        });
+
+        let best = scores
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+        selected.push(candidates.remove(best));
+    }
+
+    selected
+}
+
+/// How much a candidate repeats a chunk already picked
+fn redundancy(candidate: &StoredChunk, picked: &StoredChunk) -> f32 {
+    let similarity = match (&candidate.embedding, &picked.embedding) {
+        (Some(a), Some(b)) => cosine_similarity(a, b),
+        _ if chunks_overlap(&candidate.content, &picked.content, 0.5) => 1.0,
+        _ => 0.0,
+    };
+
+    if candidate.document_id == picked.document_id {
+        similarity + SAME_DOCUMENT_PENALTY
+    } else {
+        similarity
+    }
+}
+
+/// Weight of the share of query terms a chunk contains, added to its semantic
+/// similarity when ranking chunks within one document
+const KEYWORD_WEIGHT: f32 = 0.3;
+
+/// Query words worth matching literally (lowercase)
+pub fn query_terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for word in query.split_whitespace() {
+        let word = word
+            .trim_matches(|c: char| !c.is_alphanumeric())
+            .to_lowercase();
+        if (word.len() > 2 || word.chars().any(|c| c.is_ascii_digit())) && !terms.contains(&word) {
+            terms.push(word);
+        }
+    }
+    terms
+}
+
+/// Byte ranges in `text` where any of the (lowercase) terms appear, in order
+pub fn term_ranges(text: &str, terms: &[String]) -> Vec<Range<usize>> {
+    // ASCII lowercasing keeps byte offsets the same as the original text
+    let lower = text.to_ascii_lowercase();
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+
+    for term in terms {
+        let mut from = 0;
+        while let Some(found) = lower[from..].find(term.as_str()) {
+            let start = from + found;
+            ranges.push(start..start + term.len());
+            from = start + term.len();
+        }
+    }
+
+    ranges.sort_by_key(|r| r.start);
+    // Drop matches inside earlier ones
+    let mut merged: Vec<Range<usize>> = Vec::new();
+    for range in ranges {
+        if merged.last().is_none_or(|last| range.start >= last.end) {
+            merged.push(range);
+        }
+    }
+    merged
+}
+
+/// Rank one document's chunks against a query: semantic similarity when the
+/// query and chunk have embeddings, plus a bonus for containing the query's
+/// terms. Best first; chunks matching neither are left out.
+pub fn rank_chunks(
+    query: &str,
+    query_embedding: Option<&[f32]>,
+    chunks: Vec<StoredChunk>,
+) -> Vec<(StoredChunk, f32)> {
+    let terms = query_terms(query);
+
+    let mut ranked: Vec<(StoredChunk, f32)> = chunks
+        .into_iter()
+        .filter_map(|chunk| {
+            let similarity = match (query_embedding, &chunk.embedding) {
+                (Some(query), Some(embedding)) => Some(cosine_similarity(query, embedding)),
+                _ => None,
+            };
+            let lower = chunk.content.to_lowercase();
+            let hits = terms.iter().filter(|t| lower.contains(t.as_str())).count();
+            let keyword = if terms.is_empty() {
+                0.0
+            } else {
+                hits as f32 / terms.len() as f32
+            };
+
+            if similarity.is_none() && hits == 0 {
+                return None;
+            }
+            let score = similarity.unwrap_or(0.0) + KEYWORD_WEIGHT * keyword;
+            Some((chunk, score))
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked
+}
+
+/// Chunks kept after reranking
+pub const RERANK_KEEP: usize = 10;
+
+/// Re-order candidate chunks (id, content) by a cross-encoder's judgement of
+/// how well each answers the query, keeping the best `keep` with their scores
+pub fn rerank_chunks(
+    reranker: Reranker,
+    query: &str,
+    chunks: &[(i64, String)],
+    keep: usize,
+) -> anyhow::Result<Vec<(i64, String, f32)>> {
+    let passages: Vec<&str> = chunks.iter().map(|(_, content)| content.as_str()).collect();
+    let scores = rerank::scores(reranker, query, &passages)?;
+
+    let mut scored: Vec<(f32, &(i64, String))> = scores.into_iter().zip(chunks).collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    Ok(scored
+        .into_iter()
+        .take(keep)
+        .map(|(score, (id, content))| (*id, content.clone(), score))
+        .collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -237,6 +474,52 @@ mod tests {
         assert!(!chunks_overlap(a, b, 0.5));
     }
 
+    #[test]
+    fn test_chunk_overlap_share() {
+        let slides = [
+            "paging maps virtual pages to frames",
+            "tlb caches recent translations",
+        ];
+        let revised = [
+            "paging maps virtual pages to physical frames",
+            "tlb caches recent translations",
+            "segmentation splits memory by purpose",
+        ];
+        let slides: Vec<_> = slides.iter().map(|c| word_set(c)).collect();
+        let revised: Vec<_> = revised.iter().map(|c| word_set(c)).collect();
+        assert_eq!(chunk_overlap_share(&slides, &revised, 0.7), 1.0);
+        assert!((chunk_overlap_share(&revised, &slides, 0.7) - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(chunk_overlap_share(&[], &slides, 0.7), 0.0);
+
+        // Overlapping chunks share a MinHash signature; unrelated ones don't
+        let shares = |a: &HashSet<&str>, b: &HashSet<&str>| {
+            let b = minhashes(b);
+            minhashes(a).iter().any(|m| b.contains(m))
+        };
+        assert!(shares(&slides[1], &revised[1]));
+        assert!(!shares(&slides[1], &revised[2]));
+        assert!(minhashes(&HashSet::new()).is_empty());
+    }
+
+    #[test]
+    fn test_best_source() {
+        let candidates = vec![
+            (
+                1,
+                "Photosynthesis converts light energy into glucose".to_string(),
+            ),
+            (
+                2,
+                "Mitochondria produce ATP through cellular respiration".to_string(),
+            ),
+        ];
+        assert_eq!(
+            best_source("What organelle produces ATP? Mitochondria", &candidates),
+            Some(2)
+        );
+        assert_eq!(best_source("unrelated words here", &candidates), None);
+    }
+
     #[test]
     fn test_deduplicate_chunks() {
         let chunks = vec![
@@ -258,4 +541,105 @@ mod tests {
         assert_eq!(deduped[0].0, 1);
         assert_eq!(deduped[1].0, 3);
     }
+
+    fn candidate(
+        id: i64,
+        document_id: i64,
+        embedding: Vec<f32>,
+        relevance: f32,
+    ) -> (StoredChunk, f32) {
+        let chunk = StoredChunk {
+            id,
+            document_id,
+            chunk_index: id,
+            content: format!("chunk {}", id),
+            embedding: Some(embedding),
+            section: None,
+            page: None,
+            start_time: None,
+        };
+        (chunk, relevance)
+    }
+
+    #[test]
+    fn test_mmr_prefers_other_sources() {
+        let candidates = vec![
+            candidate(1, 1, vec![1.0, 0.0, 0.0], 0.95),
+            // Near-duplicate of the best match, same page of the same document
+            candidate(2, 1, vec![0.99, 0.1, 0.0], 0.94),
+            candidate(3, 2, vec![0.6, 0.8, 0.0], 0.85),
+            candidate(4, 3, vec![0.6, 0.0, 0.8], 0.80),
+        ];
+
+        let picked: Vec<i64> = mmr_select(candidates.clone(), 3, MMR_LAMBDA)
+            .iter()
+            .map(|(c, _)| c.id)
+            .collect();
+        assert_eq!(picked, vec![1, 3, 4]);
+
+        // Relevance alone keeps the near-duplicate
+        let picked: Vec<i64> = mmr_select(candidates, 2, 1.0)
+            .iter()
+            .map(|(c, _)| c.id)
+            .collect();
+        assert_eq!(picked, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_rerank_chunks() {
+        crate::embeddings::use_mock_backend();
+        let chunks = vec![
+            (1, "the french revolution began in 1789".to_string()),
+            (2, "mitochondria produce atp for the cell".to_string()),
+            (3, "photosynthesis turns light into sugar".to_string()),
+        ];
+
+        let reranked = rerank_chunks(
+            Reranker::Bge,
+            "photosynthesis turns light into sugar",
+            &chunks,
+            2,
+        )
+        .unwrap();
+        assert_eq!(reranked.len(), 2);
+        assert_eq!(reranked[0].0, 3);
+    }
+
+    #[test]
+    fn test_term_ranges() {
+        let terms = query_terms("Where is ATP defined?");
+        assert_eq!(terms, vec!["where", "atp", "defined"]);
+
+        let text = "ATP is defined here; atp again";
+        let found: Vec<&str> = term_ranges(text, &terms)
+            .into_iter()
+            .map(|r| &text[r])
+            .collect();
+        assert_eq!(found, vec!["ATP", "defined", "atp"]);
+    }
+
+    #[test]
+    fn test_rank_chunks_by_keywords_without_embeddings() {
+        let chunk = |id: i64, content: &str| StoredChunk {
+            id,
+            document_id: 1,
+            chunk_index: id,
+            content: content.to_string(),
+            embedding: None,
+            section: None,
+            page: None,
+            start_time: None,
+        };
+        let chunks = vec![
+            chunk(0, "Cells and organelles"),
+            chunk(1, "Mitochondria produce ATP"),
+            chunk(2, "ATP is the energy currency"),
+        ];
+
+        let ranked: Vec<i64> = rank_chunks("mitochondria ATP", None, chunks)
+            .iter()
+            .map(|(c, _)| c.id)
+            .collect();
+        assert_eq!(ranked, vec![1, 2]);
+    }
 }
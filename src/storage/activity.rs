@@ -0,0 +1,163 @@
+use anyhow::Result;
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use rusqlite::params;
+
+use super::Database;
+
+/// Study items reviewed (in `review`, including cram sessions)
+pub const REVIEW: &str = "review";
+/// Quizzes finished
+pub const QUIZ: &str = "quiz";
+/// Documents added to any bucket
+pub const DOCUMENT: &str = "document";
+
+/// Activity totals over a stretch of days
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ActivityTotals {
+    pub reviews: i64,
+    pub quizzes: i64,
+    pub documents: i64,
+}
+
+pub struct ActivityStore<'a> {
+    db: &'a Database,
+}
+
+impl<'a> ActivityStore<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Add `count` to a kind of activity for `day` (YYYY-MM-DD)
+    pub fn add(&self, day: &str, kind: &str, count: i64) -> Result<()> {
+        self.db.conn.execute(
+            "INSERT INTO study_activity (day, kind, count) VALUES (?1, ?2, ?3)
+             ON CONFLICT (day, kind) DO UPDATE SET count = count + excluded.count",
+            params![day, kind, count],
+        )?;
+
+        Ok(())
+    }
+
+    /// Consecutive days with any activity, counting back from today. A
+    /// streak isn't broken until a whole day passes without studying, so
+    /// one that ran through yesterday still counts.
+    pub fn streak(&self, today: NaiveDate) -> Result<u32> {
+        let mut stmt = self
+            .db
+            .conn
+            .prepare("SELECT DISTINCT day FROM study_activity WHERE count > 0 ORDER BY day DESC")?;
+        let days: Vec<String> = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let mut expected = today;
+        let mut streak = 0;
+        for day in days {
+            let Ok(day) = NaiveDate::parse_from_str(&day, "%Y-%m-%d") else {
+                continue;
+            };
+            if day > today {
+                continue;
+            }
+            // Nothing yet today: the streak may still be running from yesterday
+            if streak == 0 && day == today - Duration::days(1) {
+                expected = day;
+            }
+            if day != expected {
+                break;
+            }
+            streak += 1;
+            expected -= Duration::days(1);
+        }
+
+        Ok(streak)
+    }
+
+    /// Totals from `since` (YYYY-MM-DD) on
+    pub fn totals_since(&self, since: &str) -> Result<ActivityTotals> {
+        let mut stmt = self
+            .db
+            .conn
+            .prepare("SELECT kind, SUM(count) FROM study_activity WHERE day >= ?1 GROUP BY kind")?;
+        let rows = stmt.query_map(params![since], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?;
+
+        let mut totals = ActivityTotals::default();
+        for row in rows {
+            let (kind, count) = row?;
+            match kind.as_str() {
+                REVIEW => totals.reviews = count,
+                QUIZ => totals.quizzes = count,
+                DOCUMENT => totals.documents = count,
+                _ => {}
+            }
+        }
+
+        Ok(totals)
+    }
+}
+
+/// Record activity for today in the default database, where it's kept so
+/// streaks carry across buckets. Failures are reported, not fatal.
+pub fn record(kind: &str, count: i64) {
+    if count <= 0 {
+        return;
+    }
+    let result = super::usage::open()
+        .and_then(|db| ActivityStore::new(&db).add(&super::usage::today(), kind, count));
+    if let Err(e) = result {
+        eprintln!("Note: Could not record study activity: {}", e);
+    }
+}
+
+/// Current streak and this week's totals (Monday on), for the dashboard
+pub fn summary() -> Result<(u32, ActivityTotals)> {
+    let db = super::usage::open()?;
+    let store = ActivityStore::new(&db);
+    let today = Local::now().date_naive();
+    let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+
+    Ok((
+        store.streak(today)?,
+        store.totals_since(&monday.format("%Y-%m-%d").to_string())?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_streak_and_totals() {
+        let db = Database::open_in_memory().unwrap();
+        let store = ActivityStore::new(&db);
+        let today = NaiveDate::from_ymd_opt(2026, 3, 12).unwrap();
+
+        assert_eq!(store.streak(today).unwrap(), 0);
+
+        for day in ["2026-03-08", "2026-03-10", "2026-03-11"] {
+            store.add(day, REVIEW, 5).unwrap();
+        }
+        store.add("2026-03-11", QUIZ, 1).unwrap();
+        store.add("2026-03-11", REVIEW, 3).unwrap();
+
+        // Ran through yesterday, so it's still alive today
+        assert_eq!(store.streak(today).unwrap(), 2);
+        store.add("2026-03-12", DOCUMENT, 2).unwrap();
+        assert_eq!(store.streak(today).unwrap(), 3);
+        // Two days without studying breaks it
+        let later = NaiveDate::from_ymd_opt(2026, 3, 14).unwrap();
+        assert_eq!(store.streak(later).unwrap(), 0);
+
+        assert_eq!(
+            store.totals_since("2026-03-09").unwrap(),
+            ActivityTotals {
+                reviews: 13,
+                quizzes: 1,
+                documents: 2
+            }
+        );
+    }
+}
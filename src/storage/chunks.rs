@@ -1,8 +1,13 @@
 use anyhow::{Context, Result};
 use rusqlite::params;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
 
-use super::Database;
-use crate::embeddings;
+use super::{Database, EmbeddingCache};
+use crate::embeddings::{self, hnsw::Hnsw};
+use crate::ingest::Chunk;
 
 /// A stored chunk with its embedding
 #[derive(Debug, Clone)]
@@ -12,6 +17,66 @@ pub struct StoredChunk {
     pub chunk_index: i64,
     pub content: String,
     pub embedding: Option<Vec<f32>>,
+    /// Section (e.g. chapter) title, if the source had sections
+    pub section: Option<String>,
+    /// Page the chunk starts on, if the source was paginated
+    pub page: Option<i64>,
+    /// Seconds into the recording where the chunk starts, for transcripts
+    pub start_time: Option<f64>,
+}
+
+impl StoredChunk {
+    /// Where the chunk sits in its document, to append to the filename when
+    /// citing it: " / Chapter 2, p. 14" or " @ 14:32" (empty if unknown)
+    pub fn location(&self) -> String {
+        let mut location = String::new();
+        if let Some(section) = &self.section {
+            location.push_str(&format!(" / {}", section));
+        }
+        if let Some(page) = self.page {
+            location.push_str(&format!(", p. {}", page));
+        }
+        if let Some(seconds) = self.start_time {
+            location.push_str(&format!(" @ {}", crate::ingest::format_timestamp(seconds)));
+        }
+        location
+    }
+}
+
+/// How a document's chunks are stored and used
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChunkStats {
+    pub chunks: i64,
+    /// Chunks that have an embedding
+    pub embedded: i64,
+    /// Times any of the chunks was put in a chat answer's context
+    pub retrievals: i64,
+    /// Chunks that were retrieved at least once
+    pub retrieved: i64,
+}
+
+/// Embedded chunks in a bucket before retrieval switches from exact search to
+/// the approximate HNSW index
+const HNSW_MIN_CHUNKS: i64 = 20_000;
+
+/// Share of removed nodes at which the HNSW index is rebuilt from scratch
+const HNSW_MAX_DELETED: f32 = 0.25;
+
+/// HNSW indexes this process has loaded, by file, so queries only apply
+/// what changed since the last one
+static HNSW_CACHE: OnceLock<Mutex<HashMap<PathBuf, CachedHnsw>>> = OnceLock::new();
+
+struct CachedHnsw {
+    index: Hnsw,
+    /// Highest chunk id the index has caught up with; later chunks are new
+    high_water: i64,
+    /// The file's modification time when last loaded or saved, to notice
+    /// another process saving a newer index
+    modified: Option<SystemTime>,
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
 }
 
 pub struct ChunkStore<'a> {
@@ -32,6 +97,9 @@ impl<'a> ChunkStore<'a> {
                 chunk_index INTEGER NOT NULL,
                 content TEXT NOT NULL,
                 embedding BLOB,
+                section TEXT,
+                page INTEGER,
+                start_time REAL,
                 FOREIGN KEY (document_id) REFERENCES documents(id) ON DELETE CASCADE
             )",
             [],
@@ -42,9 +110,147 @@ impl<'a> ChunkStore<'a> {
             [],
         )?;
 
+        self.db.add_column_if_missing("chunks", "section", "TEXT")?;
+        self.db.add_column_if_missing("chunks", "page", "INTEGER")?;
+        self.db
+            .add_column_if_missing("chunks", "start_time", "REAL")?;
+        self.db
+            .add_column_if_missing("chunks", "retrievals", "INTEGER NOT NULL DEFAULT 0")?;
+
+        EmbeddingCache::new(self.db).init_schema()?;
+        self.init_vector_index()?;
+        self.init_link_cleanup()?;
+
         Ok(())
     }
 
+    /// Drop links to or from a chunk when it's deleted (re-chunking, reindex,
+    /// document deletes and merges), and clear out any left by older versions
+    fn init_link_cleanup(&self) -> Result<()> {
+        let exists: bool = self.db.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE name = 'chunks_links_ad')",
+            [],
+            |row| row.get(0),
+        )?;
+        if exists {
+            return Ok(());
+        }
+
+        self.db.conn.execute_batch(
+            "
+            CREATE TRIGGER IF NOT EXISTS chunks_links_ad AFTER DELETE ON chunks BEGIN
+                DELETE FROM links
+                WHERE (to_kind = 'chunk' AND to_id = old.id)
+                   OR (from_kind = 'chunk' AND from_id = old.id);
+            END;
+
+            DELETE FROM links
+            WHERE (to_kind = 'chunk' AND to_id NOT IN (SELECT id FROM chunks))
+               OR (from_kind = 'chunk' AND from_id NOT IN (SELECT id FROM chunks));
+            ",
+        )?;
+
+        Ok(())
+    }
+
+    /// Create the sqlite-vec index over chunk embeddings, with triggers that
+    /// keep it in step with the chunks table, and fill it from existing chunks
+    fn init_vector_index(&self) -> Result<()> {
+        let exists: bool = self.db.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE name = 'chunk_vectors')",
+            [],
+            |row| row.get(0),
+        )?;
+
+        // Embeddings of another size (e.g. from a different model) stay out of the index
+        let bytes = embeddings::DIMENSIONS * 4;
+        self.db.conn.execute_batch(&format!(
+            "
+            CREATE VIRTUAL TABLE IF NOT EXISTS chunk_vectors USING vec0(
+                embedding float[{dimensions}] distance_metric=cosine
+            );
+
+            CREATE TRIGGER IF NOT EXISTS chunks_vec_ai AFTER INSERT ON chunks
+            WHEN length(new.embedding) = {bytes} BEGIN
+                INSERT INTO chunk_vectors(rowid, embedding) VALUES (new.id, new.embedding);
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS chunks_vec_ad AFTER DELETE ON chunks BEGIN
+                DELETE FROM chunk_vectors WHERE rowid = old.id;
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS chunks_vec_au AFTER UPDATE OF embedding ON chunks BEGIN
+                DELETE FROM chunk_vectors WHERE rowid = old.id;
+                INSERT INTO chunk_vectors(rowid, embedding)
+                SELECT new.id, new.embedding WHERE length(new.embedding) = {bytes};
+            END;
+
+            -- Re-embedded and deleted chunks the HNSW index still has the old vector for
+            CREATE TABLE IF NOT EXISTS stale_vectors (
+                chunk_id INTEGER PRIMARY KEY
+            );
+
+            CREATE TRIGGER IF NOT EXISTS chunks_hnsw_au AFTER UPDATE OF embedding ON chunks BEGIN
+                INSERT OR IGNORE INTO stale_vectors(chunk_id) VALUES (old.id);
+            END;
+            ",
+            dimensions = embeddings::DIMENSIONS,
+        ))?;
+
+        let logs_deletes: bool = self.db.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE name = 'chunks_hnsw_ad')",
+            [],
+            |row| row.get(0),
+        )?;
+        if !logs_deletes {
+            self.db.conn.execute_batch(
+                "CREATE TRIGGER IF NOT EXISTS chunks_hnsw_ad AFTER DELETE ON chunks BEGIN
+                    INSERT OR IGNORE INTO stale_vectors(chunk_id) VALUES (old.id);
+                END;",
+            )?;
+            // An index saved before deletions were logged may hold deleted
+            // chunks; rebuild it on the next search
+            if let Some(path) = self.hnsw_path() {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+
+        if !exists {
+            self.db.conn.execute(
+                "INSERT INTO chunk_vectors(rowid, embedding)
+                 SELECT id, embedding FROM chunks WHERE length(embedding) = ?1",
+                params![bytes as i64],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Start a transaction on this database, so several writes (chunks and
+    /// their document) land together. Dropping it without committing rolls
+    /// them back.
+    pub fn transaction(&self) -> Result<rusqlite::Transaction<'a>> {
+        Ok(self.db.conn.unchecked_transaction()?)
+    }
+
+    /// Cache of embeddings for chunk text in this database
+    pub fn embedding_cache(&self) -> EmbeddingCache<'a> {
+        EmbeddingCache::new(self.db)
+    }
+
+    /// Embed chunk texts, reusing embeddings cached for identical text
+    pub fn embed_texts(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        self.embedding_cache().embed(texts)
+    }
+
+    /// Embed a single chunk's text, reusing a cached embedding if there is one
+    pub fn embed_text(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed_texts(&[text])?
+            .into_iter()
+            .next()
+            .context("No embedding generated")
+    }
+
     /// Insert a chunk
     pub fn insert(
         &self,
@@ -67,11 +273,95 @@ impl<'a> ChunkStore<'a> {
         Ok(self.db.conn.last_insert_rowid())
     }
 
+    /// Insert an ingested chunk along with its section and page
+    pub fn insert_chunk(
+        &self,
+        document_id: i64,
+        chunk: &Chunk,
+        embedding: Option<&[f32]>,
+    ) -> Result<i64> {
+        let embedding_bytes = embedding.map(embeddings::embedding_to_bytes);
+
+        self.db
+            .conn
+            .execute(
+                "INSERT INTO chunks
+                    (document_id, chunk_index, content, embedding, section, page, start_time)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    document_id,
+                    chunk.index as i64,
+                    chunk.text,
+                    embedding_bytes,
+                    chunk.section,
+                    chunk.page.map(|p| p as i64),
+                    chunk.start_time
+                ],
+            )
+            .context("Failed to insert chunk")?;
+
+        Ok(self.db.conn.last_insert_rowid())
+    }
+
+    /// Insert a copy of a stored chunk (e.g. from another bucket), keeping its
+    /// embedding and position
+    pub fn insert_stored(&self, document_id: i64, chunk: &StoredChunk) -> Result<i64> {
+        let embedding_bytes = chunk
+            .embedding
+            .as_deref()
+            .map(embeddings::embedding_to_bytes);
+
+        self.db
+            .conn
+            .execute(
+                "INSERT INTO chunks
+                    (document_id, chunk_index, content, embedding, section, page, start_time)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    document_id,
+                    chunk.chunk_index,
+                    chunk.content,
+                    embedding_bytes,
+                    chunk.section,
+                    chunk.page,
+                    chunk.start_time
+                ],
+            )
+            .context("Failed to insert chunk")?;
+
+        Ok(self.db.conn.last_insert_rowid())
+    }
+
+    /// Get a chunk by ID
+    pub fn get(&self, id: i64) -> Result<Option<StoredChunk>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT id, document_id, chunk_index, content, embedding, section, page, start_time
+             FROM chunks WHERE id = ?1",
+        )?;
+
+        let mut rows = stmt.query(params![id])?;
+
+        if let Some(row) = rows.next()? {
+            let embedding_bytes: Option<Vec<u8>> = row.get(4)?;
+            Ok(Some(StoredChunk {
+                id: row.get(0)?,
+                document_id: row.get(1)?,
+                chunk_index: row.get(2)?,
+                content: row.get(3)?,
+                embedding: embedding_bytes.map(|b| embeddings::bytes_to_embedding(&b)),
+                section: row.get(5)?,
+                page: row.get(6)?,
+                start_time: row.get(7)?,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
     /// Get all chunks for a document
-    #[allow(dead_code)]
     pub fn get_for_document(&self, document_id: i64) -> Result<Vec<StoredChunk>> {
         let mut stmt = self.db.conn.prepare(
-            "SELECT id, document_id, chunk_index, content, embedding
+            "SELECT id, document_id, chunk_index, content, embedding, section, page, start_time
              FROM chunks WHERE document_id = ?1 ORDER BY chunk_index",
         )?;
 
@@ -85,6 +375,9 @@ impl<'a> ChunkStore<'a> {
                 chunk_index: row.get(2)?,
                 content: row.get(3)?,
                 embedding,
+                section: row.get(5)?,
+                page: row.get(6)?,
+                start_time: row.get(7)?,
             })
         })?;
 
@@ -96,10 +389,210 @@ impl<'a> ChunkStore<'a> {
         Ok(chunks)
     }
 
+    /// The `k` chunks closest to a query embedding, most similar first, with
+    /// their cosine similarity. Large buckets use the approximate HNSW index
+    /// saved next to the database; smaller ones an exact sqlite-vec lookup.
+    pub fn nearest(&self, query: &[f32], k: usize) -> Result<Vec<(StoredChunk, f32)>> {
+        if self.hnsw_path().is_some() && self.embedded_count()? >= HNSW_MIN_CHUNKS {
+            return self.nearest_approximate(query, k);
+        }
+        self.nearest_exact(query, k)
+    }
+
+    /// Where this database's HNSW index lives (none for in-memory databases)
+    fn hnsw_path(&self) -> Option<PathBuf> {
+        let path = &self.db.path;
+        (path.as_os_str() != ":memory:").then(|| path.with_extension("hnsw"))
+    }
+
+    fn embedded_count(&self) -> Result<i64> {
+        let count = self.db.conn.query_row(
+            "SELECT COUNT(*) FROM chunks WHERE length(embedding) = ?1",
+            params![(embeddings::DIMENSIONS * 4) as i64],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
+    fn nearest_approximate(&self, query: &[f32], k: usize) -> Result<Vec<(StoredChunk, f32)>> {
+        let Some(path) = self.hnsw_path() else {
+            return self.nearest_exact(query, k);
+        };
+        let found = self.with_synced_hnsw(&path, |index| index.search(query, k))?;
+
+        let mut chunks = Vec::new();
+        for (id, similarity) in found {
+            if let Some(chunk) = self.get(id)? {
+                chunks.push((chunk, similarity));
+            }
+        }
+
+        Ok(chunks)
+    }
+
+    /// Run `f` on the HNSW index at `path`, brought up to date with the chunks
+    /// table first. The index stays loaded between calls; each one applies the
+    /// chunks logged in `stale_vectors` (deleted or re-embedded) and those
+    /// added past its high-water mark, and saves the result if that changed it.
+    fn with_synced_hnsw<R>(&self, path: &Path, f: impl FnOnce(&Hnsw) -> R) -> Result<R> {
+        let mut cache = HNSW_CACHE
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+
+        // Not loaded yet, or saved since by another process: read the file
+        let fresh = cache
+            .get(path)
+            .is_some_and(|cached| cached.modified.is_some() && cached.modified == modified(path));
+        if !fresh {
+            cache.insert(path.to_path_buf(), self.load_hnsw(path)?);
+        }
+        let cached = cache
+            .get_mut(path)
+            .context("HNSW index missing from cache")?;
+
+        let bytes = (embeddings::DIMENSIONS * 4) as i64;
+        let mut changed = false;
+
+        let mut stmt = self.db.conn.prepare(
+            "SELECT s.chunk_id, c.embedding FROM stale_vectors s
+             LEFT JOIN chunks c ON c.id = s.chunk_id AND length(c.embedding) = ?1",
+        )?;
+        let stale = stmt
+            .query_map(params![bytes], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, Option<Vec<u8>>>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        for (id, embedding) in stale {
+            changed |= cached.index.contains(id);
+            cached.index.remove(id);
+            // Chunks past the high-water mark are added below
+            if let Some(embedding) = embedding
+                && id <= cached.high_water
+            {
+                cached
+                    .index
+                    .insert(id, &embeddings::bytes_to_embedding(&embedding));
+                changed = true;
+            }
+        }
+
+        let mut stmt = self.db.conn.prepare(
+            "SELECT id, embedding FROM chunks WHERE id > ?1 AND length(embedding) = ?2
+             ORDER BY id",
+        )?;
+        let added = stmt
+            .query_map(params![cached.high_water, bytes], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        for (id, embedding) in added {
+            cached
+                .index
+                .insert(id, &embeddings::bytes_to_embedding(&embedding));
+            changed = true;
+        }
+
+        // Chunks without an embedding yet are logged when they get one
+        let latest: Option<i64> =
+            self.db
+                .conn
+                .query_row("SELECT MAX(id) FROM chunks", [], |row| row.get(0))?;
+        cached.high_water = cached.high_water.max(latest.unwrap_or(0));
+
+        self.db.conn.execute("DELETE FROM stale_vectors", [])?;
+        if changed {
+            cached.index.save(path)?;
+            cached.modified = modified(path);
+        }
+
+        Ok(f(&cached.index))
+    }
+
+    /// Read the HNSW index saved at `path`, or start an empty one when it's
+    /// missing, unreadable or mostly tombstones
+    fn load_hnsw(&self, path: &Path) -> Result<CachedHnsw> {
+        let loaded = Hnsw::load(path).ok().filter(|index| {
+            index.dimensions() == embeddings::DIMENSIONS
+                && index.deleted_fraction() <= HNSW_MAX_DELETED
+        });
+
+        Ok(match loaded {
+            Some(index) => CachedHnsw {
+                high_water: index.ids().max().unwrap_or(0),
+                modified: modified(path),
+                index,
+            },
+            None => {
+                self.db.conn.execute("DELETE FROM stale_vectors", [])?;
+                CachedHnsw {
+                    index: Hnsw::new(embeddings::DIMENSIONS),
+                    high_water: 0,
+                    modified: None,
+                }
+            }
+        })
+    }
+
+    /// Exact nearest neighbours from the sqlite-vec index
+    fn nearest_exact(&self, query: &[f32], k: usize) -> Result<Vec<(StoredChunk, f32)>> {
+        let mut stmt = self.db.conn.prepare(
+            "WITH knn AS (
+                SELECT rowid, distance FROM chunk_vectors
+                WHERE embedding MATCH ?1 AND k = ?2
+             )
+             SELECT c.id, c.document_id, c.chunk_index, c.content, c.embedding, c.section,
+                    c.page, c.start_time, knn.distance
+             FROM knn JOIN chunks c ON c.id = knn.rowid
+             ORDER BY knn.distance",
+        )?;
+
+        let rows = stmt.query_map(
+            params![embeddings::embedding_to_bytes(query), k as i64],
+            |row| {
+                let embedding_bytes: Option<Vec<u8>> = row.get(4)?;
+                let distance: f64 = row.get(8)?;
+
+                Ok((
+                    StoredChunk {
+                        id: row.get(0)?,
+                        document_id: row.get(1)?,
+                        chunk_index: row.get(2)?,
+                        content: row.get(3)?,
+                        embedding: embedding_bytes.map(|b| embeddings::bytes_to_embedding(&b)),
+                        section: row.get(5)?,
+                        page: row.get(6)?,
+                        start_time: row.get(7)?,
+                    },
+                    1.0 - distance as f32,
+                ))
+            },
+        )?;
+
+        let mut chunks = Vec::new();
+        for chunk in rows {
+            chunks.push(chunk?);
+        }
+
+        Ok(chunks)
+    }
+
+    /// Whether any chunk has an embedding (i.e. semantic search can work)
+    pub fn has_embeddings(&self) -> Result<bool> {
+        let exists = self.db.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM chunks WHERE embedding IS NOT NULL)",
+            [],
+            |row| row.get(0),
+        )?;
+
+        Ok(exists)
+    }
+
     /// Get all chunks with embeddings (for semantic search)
+    #[allow(dead_code)]
     pub fn get_all_with_embeddings(&self) -> Result<Vec<StoredChunk>> {
         let mut stmt = self.db.conn.prepare(
-            "SELECT id, document_id, chunk_index, content, embedding
+            "SELECT id, document_id, chunk_index, content, embedding, section, page, start_time
              FROM chunks WHERE embedding IS NOT NULL",
         )?;
 
@@ -113,6 +606,9 @@ impl<'a> ChunkStore<'a> {
                 chunk_index: row.get(2)?,
                 content: row.get(3)?,
                 embedding,
+                section: row.get(5)?,
+                page: row.get(6)?,
+                start_time: row.get(7)?,
             })
         })?;
 
@@ -124,15 +620,76 @@ impl<'a> ChunkStore<'a> {
         Ok(chunks)
     }
 
-    /// Delete chunks for a document
-    #[allow(dead_code)]
-    pub fn delete_for_document(&self, document_id: i64) -> Result<usize> {
-        let affected = self.db.conn.execute(
-            "DELETE FROM chunks WHERE document_id = ?1",
+    /// Count one more retrieval for each of these chunks
+    pub fn record_retrievals(&self, ids: &[i64]) -> Result<()> {
+        for id in ids {
+            self.db.conn.execute(
+                "UPDATE chunks SET retrievals = retrievals + 1 WHERE id = ?1",
+                params![id],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Chunk, embedding and retrieval counts for a document
+    pub fn stats_for_document(&self, document_id: i64) -> Result<ChunkStats> {
+        let stats = self.db.conn.query_row(
+            "SELECT COUNT(*), COUNT(embedding), COALESCE(SUM(retrievals), 0),
+                    COUNT(CASE WHEN retrievals > 0 THEN 1 END)
+             FROM chunks WHERE document_id = ?1",
             params![document_id],
+            |row| {
+                Ok(ChunkStats {
+                    chunks: row.get(0)?,
+                    embedded: row.get(1)?,
+                    retrievals: row.get(2)?,
+                    retrieved: row.get(3)?,
+                })
+            },
+        )?;
+
+        Ok(stats)
+    }
+
+    /// Delete a single chunk
+    pub fn delete(&self, id: i64) -> Result<()> {
+        self.db
+            .conn
+            .execute("DELETE FROM chunks WHERE id = ?1", params![id])?;
+
+        Ok(())
+    }
+
+    /// Move a chunk to a new position within its document, updating the
+    /// section, page and time it now falls under
+    pub fn set_position(&self, id: i64, chunk: &Chunk) -> Result<()> {
+        self.db.conn.execute(
+            "UPDATE chunks SET chunk_index = ?1, section = ?2, page = ?3, start_time = ?4
+             WHERE id = ?5",
+            params![
+                chunk.index as i64,
+                chunk.section,
+                chunk.page.map(|p| p as i64),
+                chunk.start_time,
+                id
+            ],
         )?;
 
-        Ok(affected)
+        Ok(())
+    }
+
+    /// Number of chunks in each document that has any
+    pub fn counts_by_document(&self) -> Result<HashMap<i64, i64>> {
+        let mut stmt = self
+            .db
+            .conn
+            .prepare("SELECT document_id, COUNT(*) FROM chunks GROUP BY document_id")?;
+        let counts = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(counts)
     }
 
     /// Count chunks for a document
@@ -164,7 +721,7 @@ impl<'a> ChunkStore<'a> {
         let where_clause = conditions.join(" OR ");
 
         let sql = format!(
-            "SELECT id, document_id, chunk_index, content, embedding
+            "SELECT id, document_id, chunk_index, content, embedding, section, page, start_time
              FROM chunks WHERE {} LIMIT ?",
             where_clause
         );
@@ -191,6 +748,9 @@ impl<'a> ChunkStore<'a> {
                 chunk_index: row.get(2)?,
                 content: row.get(3)?,
                 embedding,
+                section: row.get(5)?,
+                page: row.get(6)?,
+                start_time: row.get(7)?,
             });
         }
 
@@ -208,10 +768,9 @@ impl<'a> ChunkStore<'a> {
     }
 
     /// Get chunks with embeddings that haven't been embedded yet
-    #[allow(dead_code)]
     pub fn get_unembedded(&self) -> Result<Vec<StoredChunk>> {
         let mut stmt = self.db.conn.prepare(
-            "SELECT id, document_id, chunk_index, content, embedding
+            "SELECT id, document_id, chunk_index, content, embedding, section, page, start_time
              FROM chunks WHERE embedding IS NULL",
         )?;
 
@@ -222,6 +781,9 @@ impl<'a> ChunkStore<'a> {
                 chunk_index: row.get(2)?,
                 content: row.get(3)?,
                 embedding: None,
+                section: row.get(5)?,
+                page: row.get(6)?,
+                start_time: row.get(7)?,
             })
         })?;
 
@@ -234,7 +796,6 @@ impl<'a> ChunkStore<'a> {
     }
 
     /// Update chunk embedding
-    #[allow(dead_code)]
     pub fn update_embedding(&self, chunk_id: i64, embedding: &[f32]) -> Result<()> {
         let embedding_bytes = embeddings::embedding_to_bytes(embedding);
 
@@ -246,3 +807,195 @@ impl<'a> ChunkStore<'a> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DocumentStore;
+
+    #[test]
+    fn test_nearest_uses_vector_index() {
+        embeddings::use_mock_backend();
+        let db = Database::open_in_memory().unwrap();
+        let chunks = ChunkStore::new(&db);
+        chunks.init_schema().unwrap();
+        let doc = DocumentStore::new(&db)
+            .insert("notes.txt", "notes.txt", "text", "", None)
+            .unwrap();
+
+        let texts = [
+            "photosynthesis turns light into sugar",
+            "the french revolution began in 1789",
+            "chlorophyll absorbs light for photosynthesis",
+        ];
+        let mut ids = Vec::new();
+        for (i, text) in texts.iter().enumerate() {
+            let embedding = embeddings::embed_text(text).unwrap();
+            ids.push(
+                chunks
+                    .insert(doc, i as i64, text, Some(&embedding))
+                    .unwrap(),
+            );
+        }
+        // No embedding: never returned
+        chunks
+            .insert(1, 3, "light sugar photosynthesis", None)
+            .unwrap();
+
+        let query = embeddings::embed_text("photosynthesis light").unwrap();
+        let found: Vec<i64> = chunks
+            .nearest(&query, 2)
+            .unwrap()
+            .iter()
+            .map(|(c, _)| c.id)
+            .collect();
+        assert_eq!(found.len(), 2);
+        assert!(found.contains(&ids[0]) && found.contains(&ids[2]));
+
+        // Deleting or re-embedding a chunk updates the index
+        chunks.delete(ids[0]).unwrap();
+        chunks.update_embedding(ids[1], &query).unwrap();
+        let (best, similarity) = chunks.nearest(&query, 1).unwrap().remove(0);
+        assert_eq!(best.id, ids[1]);
+        assert!(similarity > 0.99);
+    }
+
+    #[test]
+    fn test_hnsw_index_follows_chunk_changes() {
+        embeddings::use_mock_backend();
+        let dir = std::env::temp_dir().join(format!("librarian_hnsw_sync_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let db = Database::open_at_path(dir.join("documents.db")).unwrap();
+        let chunks = ChunkStore::new(&db);
+        chunks.init_schema().unwrap();
+        let doc = DocumentStore::new(&db)
+            .insert("notes.txt", "notes.txt", "text", "", None)
+            .unwrap();
+        let path = chunks.hnsw_path().unwrap();
+        assert_eq!(path, dir.join("documents.hnsw"));
+
+        let texts = [
+            "photosynthesis turns light into sugar",
+            "the french revolution began in 1789",
+            "mitochondria are the powerhouse of the cell",
+        ];
+        let mut ids = Vec::new();
+        for (i, text) in texts.iter().enumerate() {
+            let embedding = embeddings::embed_text(text).unwrap();
+            ids.push(
+                chunks
+                    .insert(doc, i as i64, text, Some(&embedding))
+                    .unwrap(),
+            );
+        }
+
+        let query = embeddings::embed_text("photosynthesis turns light into sugar").unwrap();
+        let best = chunks
+            .with_synced_hnsw(&path, |index| index.search(&query, 1)[0].0)
+            .unwrap();
+        assert!(path.exists());
+        assert_eq!(best, ids[0]);
+
+        // Deletions, re-embeddings and inserts are applied to the loaded index
+        chunks.delete(ids[0]).unwrap();
+        chunks.update_embedding(ids[2], &query).unwrap();
+        let added = embeddings::embed_text("chlorophyll absorbs light").unwrap();
+        let new_id = chunks
+            .insert(doc, 3, "chlorophyll absorbs light", Some(&added))
+            .unwrap();
+
+        let found = chunks
+            .with_synced_hnsw(&path, |index| index.search(&query, 3))
+            .unwrap();
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0].0, ids[2]);
+        assert!(found.iter().all(|(id, _)| *id != ids[0]));
+        assert!(found.iter().any(|(id, _)| *id == new_id));
+        let high_water = HNSW_CACHE.get().unwrap().lock().unwrap()[&path].high_water;
+        assert_eq!(high_water, new_id);
+
+        // ...and saved, so a fresh load finds the same
+        HNSW_CACHE.get().unwrap().lock().unwrap().remove(&path);
+        let reloaded = chunks
+            .with_synced_hnsw(&path, |index| index.search(&query, 3))
+            .unwrap();
+        assert_eq!(reloaded, found);
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_stats_for_document() {
+        embeddings::use_mock_backend();
+        let db = Database::open_in_memory().unwrap();
+        let chunks = ChunkStore::new(&db);
+        chunks.init_schema().unwrap();
+        let doc = DocumentStore::new(&db)
+            .insert("notes.txt", "notes.txt", "text", "", None)
+            .unwrap();
+
+        let embedding = embeddings::embed_text("paging").unwrap();
+        let first = chunks.insert(doc, 0, "paging", Some(&embedding)).unwrap();
+        let second = chunks.insert(doc, 1, "segmentation", None).unwrap();
+        chunks.insert(doc, 2, "swapping", None).unwrap();
+
+        chunks.record_retrievals(&[first, second]).unwrap();
+        assert_eq!(
+            chunks.counts_by_document().unwrap(),
+            HashMap::from([(doc, 3)])
+        );
+        chunks.record_retrievals(&[first]).unwrap();
+
+        let stats = chunks.stats_for_document(doc).unwrap();
+        assert_eq!(
+            stats,
+            ChunkStats {
+                chunks: 3,
+                embedded: 1,
+                retrievals: 3,
+                retrieved: 2,
+            }
+        );
+        assert_eq!(
+            chunks.stats_for_document(doc + 1).unwrap(),
+            ChunkStats::default()
+        );
+    }
+
+    #[test]
+    fn test_deleting_chunks_drops_their_links() {
+        use crate::storage::StudyStore;
+        use crate::storage::links::{KIND_CHUNK, KIND_STUDY_ITEM, LinkStore};
+
+        let db = Database::open_in_memory().unwrap();
+        let chunks = ChunkStore::new(&db);
+        chunks.init_schema().unwrap();
+        let docs = DocumentStore::new(&db);
+        let doc = docs.insert("a.txt", "a.txt", "text", "", None).unwrap();
+        let chunk = chunks.insert(doc, 0, "mitochondria", None).unwrap();
+        let study = StudyStore::new(&db);
+        let card = study.insert(Some(doc), "flashcard", "Q", "A").unwrap();
+        let links = LinkStore::new(&db);
+        links.add(KIND_STUDY_ITEM, card, KIND_CHUNK, chunk).unwrap();
+
+        chunks.delete(chunk).unwrap();
+        assert!(
+            links
+                .created_from(KIND_STUDY_ITEM, card)
+                .unwrap()
+                .is_empty()
+        );
+
+        // Deleting the study item itself clears its links too
+        let chunk = chunks.insert(doc, 0, "mitochondria", None).unwrap();
+        links.add(KIND_STUDY_ITEM, card, KIND_CHUNK, chunk).unwrap();
+        study.delete(card).unwrap();
+        assert!(
+            links
+                .created_from(KIND_STUDY_ITEM, card)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::{Context, Result};
+use rusqlite::params;
+
+use super::Database;
+
+/// Bibliographic metadata attached to a document
+#[derive(Debug, Clone, Default)]
+pub struct Citation {
+    pub document_id: i64,
+    /// BibTeX entry type (article, misc, online, ...)
+    pub entry_type: String,
+    pub title: String,
+    pub authors: Vec<String>,
+    pub year: Option<i32>,
+    pub venue: Option<String>,
+    pub doi: Option<String>,
+    pub url: Option<String>,
+    pub arxiv_id: Option<String>,
+}
+
+pub struct CitationStore<'a> {
+    db: &'a Database,
+}
+
+impl<'a> CitationStore<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Insert or replace the citation for a document
+    pub fn upsert(&self, citation: &Citation) -> Result<()> {
+        let authors = citation.authors.join(" and ");
+
+        self.db
+            .conn
+            .execute(
+                "INSERT OR REPLACE INTO citations (document_id, entry_type, title, authors, year, venue, doi, url, arxiv_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    citation.document_id,
+                    citation.entry_type,
+                    citation.title,
+                    authors,
+                    citation.year,
+                    citation.venue,
+                    citation.doi,
+                    citation.url,
+                    citation.arxiv_id
+                ],
+            )
+            .context("Failed to save citation")?;
+
+        Ok(())
+    }
+
+    /// Get the citation for a document
+    pub fn get(&self, document_id: i64) -> Result<Option<Citation>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT document_id, entry_type, title, authors, year, venue, doi, url, arxiv_id
+             FROM citations WHERE document_id = ?1",
+        )?;
+
+        let mut rows = stmt.query(params![document_id])?;
+
+        if let Some(row) = rows.next()? {
+            Ok(Some(Self::row_to_citation(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn row_to_citation(row: &rusqlite::Row) -> Result<Citation> {
+        let authors: String = row.get(3)?;
+
+        Ok(Citation {
+            document_id: row.get(0)?,
+            entry_type: row.get(1)?,
+            title: row.get(2)?,
+            authors: authors
+                .split(" and ")
+                .map(|a| a.trim().to_string())
+                .filter(|a| !a.is_empty())
+                .collect(),
+            year: row.get(4)?,
+            venue: row.get(5)?,
+            doi: row.get(6)?,
+            url: row.get(7)?,
+            arxiv_id: row.get(8)?,
+        })
+    }
+}
@@ -0,0 +1,158 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rusqlite::params;
+
+use super::{Database, Document};
+
+/// A saved search: a query plus optional filters, re-run on demand so it
+/// always reflects the current documents
+#[derive(Debug, Clone)]
+pub struct Collection {
+    pub id: i64,
+    pub name: String,
+    pub query: String,
+    /// Only documents of this content type (e.g. "pdf")
+    pub content_type: Option<String>,
+    /// Only documents with this tag
+    pub tag: Option<String>,
+}
+
+impl Collection {
+    /// Whether a document passes the collection's filters
+    pub fn includes(&self, doc: &Document) -> bool {
+        let type_ok = self
+            .content_type
+            .as_deref()
+            .is_none_or(|t| doc.content_type.eq_ignore_ascii_case(t));
+        let tag_ok = self.tag.as_deref().is_none_or(|tag| doc.has_tag(tag));
+        type_ok && tag_ok
+    }
+
+    /// The filters in words, e.g. "pdf, tagged exam" (empty if none)
+    pub fn describe_filters(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(content_type) = &self.content_type {
+            parts.push(content_type.clone());
+        }
+        if let Some(tag) = &self.tag {
+            parts.push(format!("tagged {}", tag));
+        }
+        parts.join(", ")
+    }
+}
+
+pub struct CollectionStore<'a> {
+    db: &'a Database,
+}
+
+impl<'a> CollectionStore<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Save a collection, replacing any existing one with the same name
+    pub fn save(
+        &self,
+        name: &str,
+        query: &str,
+        content_type: Option<&str>,
+        tag: Option<&str>,
+    ) -> Result<i64> {
+        let now = Utc::now().to_rfc3339();
+
+        self.db
+            .conn
+            .execute(
+                "INSERT INTO collections (name, query, content_type, tag, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT(name) DO UPDATE SET
+                    query = excluded.query,
+                    content_type = excluded.content_type,
+                    tag = excluded.tag",
+                params![name, query, content_type, tag, now],
+            )
+            .context("Failed to save collection")?;
+
+        let id = self.db.conn.query_row(
+            "SELECT id FROM collections WHERE name = ?1",
+            params![name],
+            |row| row.get(0),
+        )?;
+
+        Ok(id)
+    }
+
+    /// All collections, by name
+    pub fn list(&self) -> Result<Vec<Collection>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT id, name, query, content_type, tag FROM collections ORDER BY name COLLATE NOCASE",
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            Ok(Collection {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                query: row.get(2)?,
+                content_type: row.get(3)?,
+                tag: row.get(4)?,
+            })
+        })?;
+
+        let mut collections = Vec::new();
+        for collection in rows {
+            collections.push(collection?);
+        }
+
+        Ok(collections)
+    }
+
+    /// Delete a collection (the documents it matched are untouched)
+    pub fn delete(&self, id: i64) -> Result<()> {
+        self.db
+            .conn
+            .execute("DELETE FROM collections WHERE id = ?1", params![id])
+            .context("Failed to delete collection")?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::DocumentStore;
+
+    #[test]
+    fn test_save_replace_and_filter() {
+        let db = Database::open_in_memory().unwrap();
+        let store = CollectionStore::new(&db);
+
+        let id = store.save("Scheduling", "round robin", None, None).unwrap();
+        let replaced = store
+            .save(
+                "Scheduling",
+                "scheduling algorithms",
+                Some("pdf"),
+                Some("exam"),
+            )
+            .unwrap();
+        assert_eq!(id, replaced);
+
+        let collections = store.list().unwrap();
+        assert_eq!(collections.len(), 1);
+        let collection = &collections[0];
+        assert_eq!(collection.query, "scheduling algorithms");
+        assert_eq!(collection.describe_filters(), "pdf, tagged exam");
+
+        let docs = DocumentStore::new(&db);
+        let tagged = docs
+            .insert("a.pdf", "a.pdf", "pdf", "text", Some("os, exam"))
+            .unwrap();
+        let untagged = docs.insert("b.pdf", "b.pdf", "pdf", "text", None).unwrap();
+        assert!(collection.includes(&docs.get(tagged).unwrap().unwrap()));
+        assert!(!collection.includes(&docs.get(untagged).unwrap().unwrap()));
+
+        store.delete(id).unwrap();
+        assert!(store.list().unwrap().is_empty());
+    }
+}
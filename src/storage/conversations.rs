@@ -8,14 +8,12 @@ use super::Database;
 pub struct Conversation {
     pub id: i64,
     pub title: Option<String>,
-    #[allow(dead_code)]
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone)]
 pub struct StoredMessage {
-    #[allow(dead_code)]
     pub id: i64,
     #[allow(dead_code)]
     pub conversation_id: i64,
@@ -98,12 +96,50 @@ impl<'a> ConversationStore<'a> {
 
     /// List recent conversations
     pub fn list_recent(&self, limit: usize) -> Result<Vec<Conversation>> {
-        let mut stmt = self.db.conn.prepare(
+        self.query_conversations(
             "SELECT id, title, created_at, updated_at
              FROM conversations ORDER BY updated_at DESC LIMIT ?1",
+            params![limit as i64],
+        )
+    }
+
+    /// Get a conversation by ID
+    pub fn get(&self, id: i64) -> Result<Option<Conversation>> {
+        Ok(self
+            .query_conversations(
+                "SELECT id, title, created_at, updated_at FROM conversations WHERE id = ?1",
+                params![id],
+            )?
+            .pop())
+    }
+
+    /// Number of messages in a conversation
+    pub fn message_count(&self, conversation_id: i64) -> Result<i64> {
+        let count = self.db.conn.query_row(
+            "SELECT COUNT(*) FROM messages WHERE conversation_id = ?1",
+            params![conversation_id],
+            |row| row.get(0),
         )?;
+        Ok(count)
+    }
+
+    /// List every conversation, oldest first
+    pub fn list(&self) -> Result<Vec<Conversation>> {
+        self.query_conversations(
+            "SELECT id, title, created_at, updated_at
+             FROM conversations ORDER BY created_at ASC",
+            [],
+        )
+    }
 
-        let mut rows = stmt.query(params![limit as i64])?;
+    fn query_conversations(
+        &self,
+        sql: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<Conversation>> {
+        let mut stmt = self.db.conn.prepare(sql)?;
+
+        let mut rows = stmt.query(params)?;
         let mut conversations = Vec::new();
 
         while let Some(row) = rows.next()? {
@@ -133,8 +169,35 @@ impl<'a> ConversationStore<'a> {
         Ok(())
     }
 
+    /// The running summary of a conversation's older messages, and the ID of
+    /// the last message it covers
+    pub fn summary(&self, id: i64) -> Result<Option<(String, i64)>> {
+        let summary = self.db.conn.query_row(
+            "SELECT summary, summarized_through FROM conversations WHERE id = ?1",
+            params![id],
+            |row| {
+                let summary: Option<String> = row.get(0)?;
+                let through: Option<i64> = row.get(1)?;
+                Ok(summary.zip(through))
+            },
+        )?;
+        Ok(summary)
+    }
+
+    /// Replace the running summary, which now covers every message up to and
+    /// including `through_message_id`
+    pub fn set_summary(&self, id: i64, summary: &str, through_message_id: i64) -> Result<()> {
+        self.db
+            .conn
+            .execute(
+                "UPDATE conversations SET summary = ?1, summarized_through = ?2 WHERE id = ?3",
+                params![summary, through_message_id, id],
+            )
+            .context("Failed to save conversation summary")?;
+        Ok(())
+    }
+
     /// Delete a conversation and its messages
-    #[allow(dead_code)]
     pub fn delete(&self, id: i64) -> Result<bool> {
         self.db.conn.execute(
             "DELETE FROM messages WHERE conversation_id = ?1",
@@ -147,3 +210,26 @@ impl<'a> ConversationStore<'a> {
         Ok(affected > 0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_roundtrip() {
+        let db = Database::open_in_memory().unwrap();
+        let store = ConversationStore::new(&db);
+
+        let id = store.create(Some("ATP")).unwrap();
+        store.add_message(id, "user", "what is atp").unwrap();
+        let answer = store.add_message(id, "assistant", "energy").unwrap();
+        assert!(store.summary(id).unwrap().is_none());
+
+        store.set_summary(id, "Asked about ATP.", answer).unwrap();
+        assert_eq!(
+            store.summary(id).unwrap(),
+            Some(("Asked about ATP.".to_string(), answer))
+        );
+        assert_eq!(store.message_count(id).unwrap(), 2);
+    }
+}
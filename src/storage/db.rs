@@ -1,13 +1,14 @@
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use rusqlite::Connection;
 use std::path::PathBuf;
+use std::sync::Once;
 
 use crate::bucket::{self, Bucket};
 use crate::config::Config;
 
 pub struct Database {
     pub conn: Connection,
-    #[allow(dead_code)]
     pub path: PathBuf,
 }
 
@@ -29,6 +30,8 @@ impl Database {
 
     /// Open or create a database at a specific path
     pub fn open_at_path(path: PathBuf) -> Result<Self> {
+        register_vector_extension();
+
         // Ensure parent directory exists
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)
@@ -44,11 +47,70 @@ impl Database {
         Ok(db)
     }
 
+    /// Open a fresh in-memory database (for tests)
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self> {
+        register_vector_extension();
+        let conn = Connection::open_in_memory().context("Failed to open in-memory database")?;
+
+        let db = Self {
+            conn,
+            path: PathBuf::from(":memory:"),
+        };
+        db.init_schema()?;
+
+        Ok(db)
+    }
+
+    /// Add a column to an existing table if it isn't there yet (for
+    /// databases created by older versions)
+    pub fn add_column_if_missing(&self, table: &str, column: &str, definition: &str) -> Result<()> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("SELECT name FROM pragma_table_info('{}')", table))?;
+        let exists = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .filter_map(|name| name.ok())
+            .any(|name| name == column);
+
+        if !exists {
+            self.conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+                [],
+            )?;
+        }
+
+        Ok(())
+    }
+
     /// Get the default database file path (when no bucket is selected)
-    fn default_db_path() -> Result<PathBuf> {
+    pub fn default_db_path() -> Result<PathBuf> {
         Ok(Config::data_dir()?.join("default.db"))
     }
 
+    /// Size of the database file on disk (0 for in-memory databases)
+    pub fn size_on_disk(&self) -> u64 {
+        std::fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0)
+    }
+
+    /// When this database last saw activity: a document added or updated, a
+    /// chat message, or a review
+    pub fn last_activity(&self) -> Result<Option<DateTime<Utc>>> {
+        let latest: Option<String> = self.conn.query_row(
+            "SELECT MAX(t) FROM (
+                SELECT MAX(updated_at) AS t FROM documents
+                UNION ALL SELECT MAX(created_at) FROM messages
+                UNION ALL SELECT MAX(reviewed_at) FROM review_log
+            )",
+            [],
+            |row| row.get(0),
+        )?;
+
+        Ok(latest
+            .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+            .map(|t| t.with_timezone(&Utc)))
+    }
+
     /// Initialize database schema
     fn init_schema(&self) -> Result<()> {
         // Documents table
@@ -66,6 +128,9 @@ impl Database {
             [],
         )?;
 
+        // Hash of the source file, for detecting changes on re-add/sync
+        self.add_column_if_missing("documents", "content_hash", "TEXT")?;
+
         // Full-text search virtual table
         self.conn.execute(
             "CREATE VIRTUAL TABLE IF NOT EXISTS documents_fts USING fts5(
@@ -100,6 +165,17 @@ impl Database {
             ",
         )?;
 
+        // Other paths whose file is identical to an existing document
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS document_aliases (
+                source_path TEXT PRIMARY KEY,
+                document_id INTEGER NOT NULL,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (document_id) REFERENCES documents(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
         // Conversations table
         self.conn.execute(
             "CREATE TABLE IF NOT EXISTS conversations (
@@ -124,6 +200,10 @@ impl Database {
             [],
         )?;
 
+        // Running summary of turns folded out of long conversations
+        self.add_column_if_missing("conversations", "summary", "TEXT")?;
+        self.add_column_if_missing("conversations", "summarized_through", "INTEGER")?;
+
         // Study items table (spaced repetition)
         self.conn.execute(
             "CREATE TABLE IF NOT EXISTS study_items (
@@ -142,7 +222,224 @@ impl Database {
             )",
             [],
         )?;
+        self.add_column_if_missing("study_items", "tags", "TEXT")?;
+
+        // One row per review, for `librarian review stats`
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS review_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                item_id INTEGER NOT NULL,
+                quality INTEGER NOT NULL,
+                interval_days REAL NOT NULL,
+                ease_factor REAL NOT NULL,
+                reviewed_at TEXT NOT NULL,
+                FOREIGN KEY (item_id) REFERENCES study_items(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        // Bibliographic metadata for citation export
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS citations (
+                document_id INTEGER PRIMARY KEY,
+                entry_type TEXT NOT NULL,
+                title TEXT NOT NULL,
+                authors TEXT NOT NULL DEFAULT '',
+                year INTEGER,
+                venue TEXT,
+                doi TEXT,
+                url TEXT,
+                arxiv_id TEXT,
+                FOREIGN KEY (document_id) REFERENCES documents(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        // Reading queue (documents without a row are unread)
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS reading_queue (
+                document_id INTEGER PRIMARY KEY,
+                status TEXT NOT NULL DEFAULT 'unread',
+                progress INTEGER,
+                position TEXT,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (document_id) REFERENCES documents(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        // Personal notes (note text is stored as a "note" document)
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS notes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                document_id INTEGER NOT NULL,
+                attached_document_id INTEGER,
+                conversation_id INTEGER,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (document_id) REFERENCES documents(id) ON DELETE CASCADE,
+                FOREIGN KEY (attached_document_id) REFERENCES documents(id) ON DELETE SET NULL,
+                FOREIGN KEY (conversation_id) REFERENCES conversations(id) ON DELETE SET NULL
+            )",
+            [],
+        )?;
+
+        // Links between study items / notes and the chunks they came from
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS links (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                from_kind TEXT NOT NULL,
+                from_id INTEGER NOT NULL,
+                to_kind TEXT NOT NULL,
+                to_id INTEGER NOT NULL,
+                created_at TEXT NOT NULL,
+                UNIQUE (from_kind, from_id, to_kind, to_id)
+            )",
+            [],
+        )?;
+
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_links_to ON links(to_kind, to_id)",
+            [],
+        )?;
+
+        // Links can point at several kinds of rows, so they're cleaned up by
+        // triggers rather than foreign keys (chunks do the same in ChunkStore)
+        self.conn.execute_batch(
+            "
+            CREATE TRIGGER IF NOT EXISTS documents_links_ad AFTER DELETE ON documents BEGIN
+                DELETE FROM links
+                WHERE (to_kind = 'document' AND to_id = old.id)
+                   OR (from_kind = 'document' AND from_id = old.id);
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS study_items_links_ad AFTER DELETE ON study_items BEGIN
+                DELETE FROM links
+                WHERE (to_kind = 'study_item' AND to_id = old.id)
+                   OR (from_kind = 'study_item' AND from_id = old.id);
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS notes_links_ad AFTER DELETE ON notes BEGIN
+                DELETE FROM links
+                WHERE (to_kind = 'note' AND to_id = old.id)
+                   OR (from_kind = 'note' AND from_id = old.id);
+            END;
+            ",
+        )?;
+
+        // RSS/Atom feeds whose new entries `librarian sync` adds
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS feeds (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                url TEXT NOT NULL UNIQUE,
+                title TEXT NOT NULL,
+                last_checked TEXT,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // Entry links already handled for each feed, so a refresh only adds new ones
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS feed_entries (
+                feed_id INTEGER NOT NULL,
+                link TEXT NOT NULL,
+                seen_at TEXT NOT NULL,
+                PRIMARY KEY (feed_id, link),
+                FOREIGN KEY (feed_id) REFERENCES feeds(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        // Saved searches ("collections") re-run from the docs menu
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS collections (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE,
+                query TEXT NOT NULL,
+                content_type TEXT,
+                tag TEXT,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // Tokens used per day and model (kept in the default database, since
+        // rate limits apply across buckets)
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS usage_daily (
+                day TEXT NOT NULL,
+                model TEXT NOT NULL,
+                requests INTEGER NOT NULL DEFAULT 0,
+                prompt_tokens INTEGER NOT NULL DEFAULT 0,
+                completion_tokens INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (day, model)
+            )",
+            [],
+        )?;
+
+        // Dated study plan entries, shown by `review` on their day
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS study_plan (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                day TEXT NOT NULL,
+                exam_date TEXT NOT NULL,
+                title TEXT NOT NULL,
+                details TEXT NOT NULL DEFAULT '',
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // Reviews, quizzes and documents added per day, for the dashboard's
+        // streak (kept in the default database, like usage)
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS study_activity (
+                day TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                count INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (day, kind)
+            )",
+            [],
+        )?;
+
+        // Every model call, for `librarian stats usage`
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS usage (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                provider TEXT NOT NULL,
+                model TEXT NOT NULL,
+                command TEXT NOT NULL,
+                prompt_tokens INTEGER NOT NULL DEFAULT 0,
+                completion_tokens INTEGER NOT NULL DEFAULT 0,
+                duration_ms INTEGER NOT NULL,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
 
         Ok(())
     }
 }
+
+/// Load sqlite-vec into every connection opened from here on
+fn register_vector_extension() {
+    static REGISTER: Once = Once::new();
+
+    REGISTER.call_once(|| {
+        // SAFETY: sqlite3_vec_init has the signature SQLite expects of an
+        // extension entry point; auto-extensions take it as a bare fn pointer
+        unsafe {
+            rusqlite::ffi::sqlite3_auto_extension(Some(std::mem::transmute::<
+                *const (),
+                unsafe extern "C" fn(
+                    *mut rusqlite::ffi::sqlite3,
+                    *mut *const std::os::raw::c_char,
+                    *const rusqlite::ffi::sqlite3_api_routines,
+                ) -> std::os::raw::c_int,
+            >(
+                sqlite_vec::sqlite3_vec_init as *const (),
+            )));
+        }
+    });
+}
@@ -1,8 +1,13 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use rusqlite::params;
+use rusqlite::types::Value;
+use std::collections::HashMap;
+use std::ops::Range;
 
-use super::Database;
+use super::links::{KIND_CHUNK, KIND_DOCUMENT, KIND_NOTE, KIND_STUDY_ITEM};
+use super::study::normalize_tags;
+use super::{ChunkStore, Database, NoteStore};
 
 #[derive(Debug, Clone)]
 pub struct Document {
@@ -13,8 +18,58 @@ pub struct Document {
     pub content: String,
     pub tags: Option<String>,
     pub created_at: DateTime<Utc>,
-    #[allow(dead_code)]
     pub updated_at: DateTime<Utc>,
+    /// SHA-256 of the source file (None for URLs and older documents)
+    pub content_hash: Option<String>,
+}
+
+impl Document {
+    /// Whether the document carries this tag (ignoring case and a leading '#')
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag.trim().trim_start_matches('#');
+        self.tags
+            .as_deref()
+            .unwrap_or("")
+            .split(',')
+            .any(|t| t.trim().eq_ignore_ascii_case(tag))
+    }
+}
+
+/// A full-text search match
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub document: Document,
+    /// BM25 relevance (higher is better)
+    pub score: f64,
+    /// Text around the best match in the document's content
+    pub snippet: String,
+    /// Byte ranges of `snippet` that matched query terms
+    pub matches: Vec<Range<usize>>,
+}
+
+/// Wrap matched terms in FTS5 snippets so they can be found again
+const MATCH_START: char = '\u{2}';
+const MATCH_END: char = '\u{3}';
+
+/// Split a marked-up FTS5 snippet into its text and the ranges that matched
+fn parse_snippet(marked: &str) -> (String, Vec<Range<usize>>) {
+    let mut snippet = String::with_capacity(marked.len());
+    let mut matches = Vec::new();
+    let mut start = None;
+
+    for c in marked.chars() {
+        match c {
+            MATCH_START => start = Some(snippet.len()),
+            MATCH_END => {
+                if let Some(start) = start.take() {
+                    matches.push(start..snippet.len());
+                }
+            }
+            _ => snippet.push(c),
+        }
+    }
+
+    (snippet, matches)
 }
 
 pub struct DocumentStore<'a> {
@@ -49,7 +104,7 @@ impl<'a> DocumentStore<'a> {
     /// Get a document by ID
     pub fn get(&self, id: i64) -> Result<Option<Document>> {
         let mut stmt = self.db.conn.prepare(
-            "SELECT id, source_path, filename, content_type, content, tags, created_at, updated_at
+            "SELECT id, source_path, filename, content_type, content, tags, created_at, updated_at, content_hash
              FROM documents WHERE id = ?1",
         )?;
 
@@ -65,7 +120,7 @@ impl<'a> DocumentStore<'a> {
     /// List all documents
     pub fn list(&self) -> Result<Vec<Document>> {
         let mut stmt = self.db.conn.prepare(
-            "SELECT id, source_path, filename, content_type, content, tags, created_at, updated_at
+            "SELECT id, source_path, filename, content_type, content, tags, created_at, updated_at, content_hash
              FROM documents ORDER BY created_at DESC",
         )?;
 
@@ -82,7 +137,7 @@ impl<'a> DocumentStore<'a> {
     /// Search documents using full-text search
     pub fn search(&self, query: &str) -> Result<Vec<Document>> {
         let mut stmt = self.db.conn.prepare(
-            "SELECT d.id, d.source_path, d.filename, d.content_type, d.content, d.tags, d.created_at, d.updated_at
+            "SELECT d.id, d.source_path, d.filename, d.content_type, d.content, d.tags, d.created_at, d.updated_at, d.content_hash
              FROM documents d
              JOIN documents_fts fts ON d.id = fts.rowid
              WHERE documents_fts MATCH ?1
@@ -99,6 +154,150 @@ impl<'a> DocumentStore<'a> {
         Ok(documents)
     }
 
+    /// Search documents using full-text search, with each match's score and a
+    /// snippet of the content around it
+    pub fn search_hits(&self, query: &str) -> Result<Vec<SearchHit>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT d.id, d.source_path, d.filename, d.content_type, d.content, d.tags, d.created_at, d.updated_at, d.content_hash,
+                    bm25(documents_fts), snippet(documents_fts, 1, char(2), char(3), '…', 16)
+             FROM documents d
+             JOIN documents_fts fts ON d.id = fts.rowid
+             WHERE documents_fts MATCH ?1
+             ORDER BY rank"
+        )?;
+
+        let mut rows = stmt.query(params![query])?;
+        let mut hits = Vec::new();
+
+        while let Some(row) = rows.next()? {
+            let rank: f64 = row.get(9)?;
+            let (snippet, matches) = parse_snippet(&row.get::<_, String>(10)?);
+            hits.push(SearchHit {
+                document: Self::row_to_document(row)?,
+                // bm25() is lower for better matches
+                score: -rank,
+                snippet,
+                matches,
+            });
+        }
+
+        Ok(hits)
+    }
+
+    /// Replace a document's title and content (e.g. after re-fetching a URL)
+    pub fn update_content(&self, id: i64, filename: &str, content: &str) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+
+        self.db
+            .conn
+            .execute(
+                "UPDATE documents SET filename = ?1, content = ?2, updated_at = ?3 WHERE id = ?4",
+                params![filename, content, now, id],
+            )
+            .context("Failed to update document")?;
+
+        Ok(())
+    }
+
+    /// Give a document a new display name
+    pub fn rename(&self, id: i64, filename: &str) -> Result<bool> {
+        let now = Utc::now().to_rfc3339();
+
+        let count = self
+            .db
+            .conn
+            .execute(
+                "UPDATE documents SET filename = ?1, updated_at = ?2 WHERE id = ?3",
+                params![filename, now, id],
+            )
+            .context("Failed to rename document")?;
+
+        Ok(count > 0)
+    }
+
+    /// Replace a document's comma-separated tags
+    pub fn set_tags(&self, id: i64, tags: Option<&str>) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+
+        self.db
+            .conn
+            .execute(
+                "UPDATE documents SET tags = ?1, updated_at = ?2 WHERE id = ?3",
+                params![tags, now, id],
+            )
+            .context("Failed to update tags")?;
+
+        Ok(())
+    }
+
+    /// Add and remove tags on a document, returning its new tags
+    pub fn edit_tags(&self, id: i64, add: &[String], remove: &[String]) -> Result<Option<String>> {
+        let doc = self
+            .get(id)?
+            .with_context(|| format!("No document with ID {}", id))?;
+
+        let kept: Vec<&str> = doc
+            .tags
+            .as_deref()
+            .unwrap_or("")
+            .split(',')
+            .filter(|t| {
+                !remove.iter().any(|r| {
+                    r.trim()
+                        .trim_start_matches('#')
+                        .eq_ignore_ascii_case(t.trim())
+                })
+            })
+            .collect();
+        let added = add.iter().map(|t| t.trim().trim_start_matches('#'));
+        let tags = normalize_tags(&kept.into_iter().chain(added).collect::<Vec<_>>().join(","));
+
+        self.set_tags(id, tags.as_deref())?;
+        Ok(tags)
+    }
+
+    /// Find a document whose source file has the given hash
+    pub fn find_by_hash(&self, hash: &str) -> Result<Option<Document>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT id, source_path, filename, content_type, content, tags, created_at, updated_at, content_hash
+             FROM documents WHERE content_hash = ?1 ORDER BY id ASC LIMIT 1",
+        )?;
+
+        let mut rows = stmt.query(params![hash])?;
+
+        if let Some(row) = rows.next()? {
+            Ok(Some(Self::row_to_document(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Record another path holding the same file as an existing document
+    pub fn add_alias(&self, document_id: i64, source_path: &str) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+
+        self.db
+            .conn
+            .execute(
+                "INSERT OR REPLACE INTO document_aliases (source_path, document_id, created_at)
+                 VALUES (?1, ?2, ?3)",
+                params![source_path, document_id, now],
+            )
+            .context("Failed to link duplicate path")?;
+
+        Ok(())
+    }
+
+    /// Record the hash of a document's source file
+    pub fn set_content_hash(&self, id: i64, hash: &str) -> Result<()> {
+        self.db.conn.execute(
+            "UPDATE documents SET content_hash = ?1 WHERE id = ?2",
+            params![hash, id],
+        )?;
+
+        Ok(())
+    }
+
     /// Delete a document by ID
     pub fn delete(&self, id: i64) -> Result<bool> {
         let affected = self
@@ -109,6 +308,282 @@ impl<'a> DocumentStore<'a> {
         Ok(affected > 0)
     }
 
+    /// Move a document into another bucket's database with everything that
+    /// belongs to it: chunks, source aliases, citation, reading progress,
+    /// study items (with their review history), attached notes, and the links
+    /// between them. Returns its ID there. Fails if that bucket already has the
+    /// source.
+    pub fn move_to(&self, id: i64, target: &Database) -> Result<i64> {
+        let doc = self
+            .get(id)?
+            .with_context(|| format!("No document with ID {}", id))?;
+
+        let target_docs = DocumentStore::new(target);
+        if target_docs.exists_by_path(&doc.source_path)? {
+            anyhow::bail!("{} is already in that bucket", doc.filename);
+        }
+        ChunkStore::new(target).init_schema()?;
+
+        // All of it lands in the target or none does, and the source is only
+        // deleted once it has
+        let tx = target.conn.unchecked_transaction()?;
+        let moved = self.copy_to(&doc, target)?;
+        tx.commit()?;
+
+        let tx = self.db.conn.unchecked_transaction()?;
+        for (kind, old_id) in moved.keys() {
+            match *kind {
+                KIND_STUDY_ITEM => {
+                    self.db
+                        .conn
+                        .execute("DELETE FROM study_items WHERE id = ?1", params![old_id])?;
+                }
+                // The document itself and its notes' documents (chunks,
+                // aliases, citation, queue entry and note rows cascade)
+                KIND_DOCUMENT => {
+                    self.delete(*old_id)?;
+                }
+                _ => {}
+            }
+        }
+        tx.commit()?;
+
+        Ok(moved[&(KIND_DOCUMENT, id)])
+    }
+
+    /// Copy a document and what belongs to it into `target` (see `move_to`).
+    /// Returns the new ID of every copied row, by link kind and old ID.
+    fn copy_to(
+        &self,
+        doc: &Document,
+        target: &Database,
+    ) -> Result<HashMap<(&'static str, i64), i64>> {
+        let target_docs = DocumentStore::new(target);
+        let target_chunks = ChunkStore::new(target);
+        let mut moved = HashMap::new();
+
+        // The document, and the note documents written about it, with chunks
+        let notes = NoteStore::new(self.db).for_document(doc.id)?;
+        let mut documents = vec![doc.clone()];
+        for note in &notes {
+            documents.extend(self.get(note.document_id)?);
+        }
+        for d in &documents {
+            let new_id = target_docs.insert(
+                &d.source_path,
+                &d.filename,
+                &d.content_type,
+                &d.content,
+                d.tags.as_deref(),
+            )?;
+            if let Some(hash) = &d.content_hash {
+                target_docs.set_content_hash(new_id, hash)?;
+            }
+            moved.insert((KIND_DOCUMENT, d.id), new_id);
+
+            for chunk in ChunkStore::new(self.db).get_for_document(d.id)? {
+                moved.insert(
+                    (KIND_CHUNK, chunk.id),
+                    target_chunks.insert_stored(new_id, &chunk)?,
+                );
+            }
+        }
+        let new_id = moved[&(KIND_DOCUMENT, doc.id)];
+
+        // Conversations stay behind, so moved notes lose theirs
+        for note in &notes {
+            target.conn.execute(
+                "INSERT INTO notes (document_id, attached_document_id, conversation_id, created_at)
+                 VALUES (?1, ?2, NULL, ?3)",
+                params![
+                    moved[&(KIND_DOCUMENT, note.document_id)],
+                    new_id,
+                    note.created_at.to_rfc3339()
+                ],
+            )?;
+            moved.insert((KIND_NOTE, note.id), target.conn.last_insert_rowid());
+        }
+
+        self.copy_rows(
+            target,
+            "SELECT rowid, source_path, created_at FROM document_aliases WHERE document_id = ?1",
+            doc.id,
+            "INSERT OR IGNORE INTO document_aliases (document_id, source_path, created_at)
+             VALUES (?1, ?2, ?3)",
+            new_id,
+        )?;
+        self.copy_rows(
+            target,
+            "SELECT document_id, entry_type, title, authors, year, venue, doi, url, arxiv_id
+             FROM citations WHERE document_id = ?1",
+            doc.id,
+            "INSERT INTO citations
+                (document_id, entry_type, title, authors, year, venue, doi, url, arxiv_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            new_id,
+        )?;
+        self.copy_rows(
+            target,
+            "SELECT document_id, status, progress, position, updated_at
+             FROM reading_queue WHERE document_id = ?1",
+            doc.id,
+            "INSERT INTO reading_queue (document_id, status, progress, position, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            new_id,
+        )?;
+
+        let items = self.copy_rows(
+            target,
+            "SELECT id, item_type, front, back, next_review_date, interval_days, ease_factor,
+                    review_count, created_at, updated_at, tags
+             FROM study_items WHERE document_id = ?1",
+            doc.id,
+            "INSERT INTO study_items
+                (document_id, item_type, front, back, next_review_date, interval_days,
+                 ease_factor, review_count, created_at, updated_at, tags)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            new_id,
+        )?;
+        for (old_item, new_item) in items {
+            self.copy_rows(
+                target,
+                "SELECT id, quality, interval_days, ease_factor, reviewed_at
+                 FROM review_log WHERE item_id = ?1",
+                old_item,
+                "INSERT INTO review_log (item_id, quality, interval_days, ease_factor, reviewed_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                new_item,
+            )?;
+            moved.insert((KIND_STUDY_ITEM, old_item), new_item);
+        }
+
+        // Links between moved rows come along; links to rows left behind don't
+        let mut stmt = self.db.conn.prepare(
+            "SELECT to_kind, to_id, created_at FROM links WHERE from_kind = ?1 AND from_id = ?2",
+        )?;
+        for ((from_kind, from_id), new_from) in &moved {
+            let links = stmt
+                .query_map(params![from_kind, from_id], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            for (to_kind, to_id, created_at) in links {
+                let Some(new_to) = moved.get(&(to_kind.as_str(), to_id)) else {
+                    continue;
+                };
+                target.conn.execute(
+                    "INSERT OR IGNORE INTO links (from_kind, from_id, to_kind, to_id, created_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![from_kind, new_from, to_kind, new_to, created_at],
+                )?;
+            }
+        }
+
+        Ok(moved)
+    }
+
+    /// Copy the rows `select` finds for `id` into `target` with `insert`. The
+    /// first selected column is the row's ID and is replaced by `new_id`; the
+    /// rest are passed through in order. Returns (old ID, new row ID) pairs.
+    fn copy_rows(
+        &self,
+        target: &Database,
+        select: &str,
+        id: i64,
+        insert: &str,
+        new_id: i64,
+    ) -> Result<Vec<(i64, i64)>> {
+        let mut stmt = self.db.conn.prepare(select)?;
+        let columns = stmt.column_count();
+        let rows = stmt
+            .query_map(params![id], |row| {
+                let values = (1..columns)
+                    .map(|i| row.get::<_, Value>(i))
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                Ok((row.get::<_, i64>(0)?, values))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut copied = Vec::new();
+        for (old_id, values) in rows {
+            let params = std::iter::once(Value::Integer(new_id)).chain(values);
+            target
+                .conn
+                .execute(insert, rusqlite::params_from_iter(params))?;
+            copied.push((old_id, target.conn.last_insert_rowid()));
+        }
+
+        Ok(copied)
+    }
+
+    /// Fold a duplicate document into another: its tags, study items, attached
+    /// notes and source paths move over (so re-adding it is caught as a
+    /// duplicate), links to it or to its chunks are re-pointed where the kept
+    /// document has the same text, then it's deleted. All in one transaction.
+    pub fn merge_into(&self, from: i64, into: i64) -> Result<()> {
+        let doc = self
+            .get(from)?
+            .with_context(|| format!("No document with ID {}", from))?;
+
+        let tx = self.db.conn.unchecked_transaction()?;
+        let tags: Vec<String> = doc
+            .tags
+            .as_deref()
+            .unwrap_or("")
+            .split(',')
+            .map(str::to_string)
+            .collect();
+        self.edit_tags(into, &tags, &[])?;
+
+        let conn = &self.db.conn;
+        conn.execute(
+            "UPDATE study_items SET document_id = ?1 WHERE document_id = ?2",
+            params![into, from],
+        )?;
+        conn.execute(
+            "UPDATE notes SET attached_document_id = ?1 WHERE attached_document_id = ?2",
+            params![into, from],
+        )?;
+        conn.execute(
+            "UPDATE document_aliases SET document_id = ?1 WHERE document_id = ?2",
+            params![into, from],
+        )?;
+        self.add_alias(into, &doc.source_path)?;
+
+        // OR IGNORE: a link the kept document already has stays as it is, and
+        // the duplicate's copy is dropped with it below
+        conn.execute(
+            "UPDATE OR IGNORE links SET to_id = ?1 WHERE to_kind = ?3 AND to_id = ?2",
+            params![into, from, KIND_DOCUMENT],
+        )?;
+        conn.execute(
+            "UPDATE OR IGNORE links SET from_id = ?1 WHERE from_kind = ?3 AND from_id = ?2",
+            params![into, from, KIND_DOCUMENT],
+        )?;
+        conn.execute(
+            "UPDATE OR IGNORE links SET to_id = (
+                SELECT kept.id FROM chunks kept JOIN chunks dup ON dup.content = kept.content
+                WHERE dup.id = links.to_id AND kept.document_id = ?1
+                ORDER BY kept.chunk_index LIMIT 1
+             )
+             WHERE to_kind = ?3 AND to_id IN (SELECT id FROM chunks WHERE document_id = ?2)
+               AND EXISTS (
+                SELECT 1 FROM chunks kept JOIN chunks dup ON dup.content = kept.content
+                WHERE dup.id = links.to_id AND kept.document_id = ?1
+             )",
+            params![into, from, KIND_CHUNK],
+        )?;
+
+        // Links to chunks with no match go with the chunks (see ChunkStore)
+        self.delete(from)?;
+        tx.commit()?;
+        Ok(())
+    }
+
     /// Get document count
     pub fn count(&self) -> Result<i64> {
         let count: i64 = self
@@ -119,6 +594,38 @@ impl<'a> DocumentStore<'a> {
         Ok(count)
     }
 
+    /// Document counts per content type, most common first
+    pub fn count_by_type(&self) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT content_type, COUNT(*) FROM documents
+             GROUP BY content_type ORDER BY COUNT(*) DESC, content_type",
+        )?;
+        let counts = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(counts)
+    }
+
+    /// Find a document by its source path (or a path linked to it as a duplicate)
+    pub fn find_by_path(&self, source_path: &str) -> Result<Option<Document>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT id, source_path, filename, content_type, content, tags, created_at, updated_at, content_hash
+             FROM documents
+             WHERE source_path = ?1
+                OR id IN (SELECT document_id FROM document_aliases WHERE source_path = ?1)
+             LIMIT 1",
+        )?;
+
+        let mut rows = stmt.query(params![source_path])?;
+
+        if let Some(row) = rows.next()? {
+            Ok(Some(Self::row_to_document(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
     /// Check if a document with the given source path already exists
     pub fn exists_by_path(&self, source_path: &str) -> Result<bool> {
         let count: i64 = self.db.conn.query_row(
@@ -147,6 +654,335 @@ impl<'a> DocumentStore<'a> {
             updated_at: DateTime::parse_from_rfc3339(&updated_str)
                 .context("Invalid updated_at timestamp")?
                 .with_timezone(&Utc),
+            content_hash: row.get(8)?,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_by_hash_and_alias() {
+        let db = Database::open_in_memory().unwrap();
+        let store = DocumentStore::new(&db);
+
+        let id = store
+            .insert("/notes/a.pdf", "a.pdf", "pdf", "A", None)
+            .unwrap();
+        store.set_content_hash(id, "abc123").unwrap();
+
+        assert_eq!(store.find_by_hash("abc123").unwrap().unwrap().id, id);
+        assert!(store.find_by_hash("other").unwrap().is_none());
+
+        store.add_alias(id, "/downloads/a copy.pdf").unwrap();
+        let found = store
+            .find_by_path("/downloads/a copy.pdf")
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.id, id);
+        assert_eq!(found.source_path, "/notes/a.pdf");
+    }
+
+    #[test]
+    fn test_edit_tags() {
+        let db = Database::open_in_memory().unwrap();
+        let store = DocumentStore::new(&db);
+
+        let id = store
+            .insert("a.pdf", "a.pdf", "pdf", "A", Some("Lecture,midterm"))
+            .unwrap();
+
+        let tags = store
+            .edit_tags(id, &["#exam".into(), "lecture".into()], &["MIDTERM".into()])
+            .unwrap();
+        assert_eq!(tags.as_deref(), Some("Lecture,exam"));
+
+        let doc = store.get(id).unwrap().unwrap();
+        assert!(doc.has_tag("#Exam"));
+        assert!(!doc.has_tag("midterm"));
+
+        let tags = store
+            .edit_tags(id, &[], &["lecture".into(), "exam".into()])
+            .unwrap();
+        assert_eq!(tags, None);
+        assert!(store.edit_tags(id + 1, &[], &[]).is_err());
+    }
+
+    #[test]
+    fn test_rename_updates_search() {
+        let db = Database::open_in_memory().unwrap();
+        let store = DocumentStore::new(&db);
+
+        let id = store
+            .insert(
+                "slides.pdf",
+                "c3-final-slides-v2(1).pdf",
+                "pdf",
+                "Paging",
+                None,
+            )
+            .unwrap();
+
+        assert!(store.rename(id, "Memory management").unwrap());
+        assert!(!store.rename(id + 1, "Nothing").unwrap());
+
+        assert_eq!(
+            store.get(id).unwrap().unwrap().filename,
+            "Memory management"
+        );
+        assert_eq!(store.search_hits("management").unwrap().len(), 1);
+        assert!(store.search_hits("slides").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_move_to_other_database() {
+        let db = Database::open_in_memory().unwrap();
+        let other = Database::open_in_memory().unwrap();
+        let store = DocumentStore::new(&db);
+        let chunks = ChunkStore::new(&db);
+        chunks.init_schema().unwrap();
+
+        let id = store
+            .insert("os.pdf", "os.pdf", "pdf", "Paging. Scheduling.", Some("os"))
+            .unwrap();
+        chunks.insert(id, 0, "Paging.", None).unwrap();
+        chunks.insert(id, 1, "Scheduling.", None).unwrap();
+
+        let moved = store.move_to(id, &other).unwrap();
+        assert!(store.get(id).unwrap().is_none());
+
+        let doc = DocumentStore::new(&other).get(moved).unwrap().unwrap();
+        assert_eq!(doc.filename, "os.pdf");
+        assert_eq!(doc.tags.as_deref(), Some("os"));
+        let copied = ChunkStore::new(&other).get_for_document(moved).unwrap();
+        assert_eq!(copied.len(), 2);
+        assert_eq!(copied[1].content, "Scheduling.");
+
+        // A second copy of the same source is refused
+        let again = store.insert("os.pdf", "os.pdf", "pdf", "", None).unwrap();
+        assert!(store.move_to(again, &other).is_err());
+        assert!(store.get(again).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_move_to_takes_related_rows() {
+        use crate::storage::links::LinkStore;
+        use crate::storage::{Citation, CitationStore, ReadingStore, StudyStore};
+
+        let db = Database::open_in_memory().unwrap();
+        let other = Database::open_in_memory().unwrap();
+        let store = DocumentStore::new(&db);
+        let chunks = ChunkStore::new(&db);
+        chunks.init_schema().unwrap();
+
+        let id = store
+            .insert("/notes/os.pdf", "os.pdf", "pdf", "Paging.", None)
+            .unwrap();
+        let chunk = chunks.insert(id, 0, "Paging.", None).unwrap();
+        store.add_alias(id, "/downloads/os.pdf").unwrap();
+        CitationStore::new(&db)
+            .upsert(&Citation {
+                document_id: id,
+                entry_type: "book".to_string(),
+                title: "Operating Systems".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        ReadingStore::new(&db)
+            .set_progress(id, Some(40), Some("p. 12"))
+            .unwrap();
+        let study = StudyStore::new(&db);
+        let item = study
+            .insert(Some(id), "flashcard", "What is paging?", "Mapping pages")
+            .unwrap();
+        study.update_after_review(item, 4).unwrap();
+        let note_doc = store
+            .insert("note://1", "Note", "note", "Ask about TLBs", Some("note"))
+            .unwrap();
+        let note_chunk = chunks.insert(note_doc, 0, "Ask about TLBs", None).unwrap();
+        let note = NoteStore::new(&db)
+            .insert(note_doc, Some(id), None)
+            .unwrap();
+        let links = LinkStore::new(&db);
+        links.add(KIND_STUDY_ITEM, item, KIND_CHUNK, chunk).unwrap();
+        links.add(KIND_NOTE, note, KIND_CHUNK, chunk).unwrap();
+
+        let moved = store.move_to(id, &other).unwrap();
+
+        // Nothing is left behind in the source
+        assert!(store.get(id).unwrap().is_none());
+        assert!(store.get(note_doc).unwrap().is_none());
+        assert!(study.get(item).unwrap().is_none());
+        assert!(chunks.get(note_chunk).unwrap().is_none());
+
+        let target_docs = DocumentStore::new(&other);
+        assert_eq!(
+            target_docs
+                .find_by_path("/downloads/os.pdf")
+                .unwrap()
+                .unwrap()
+                .id,
+            moved
+        );
+        let citation = CitationStore::new(&other).get(moved).unwrap().unwrap();
+        assert_eq!(citation.title, "Operating Systems");
+        let entry = ReadingStore::new(&other).get(moved).unwrap().unwrap();
+        assert_eq!(
+            (entry.progress, entry.position.as_deref()),
+            (Some(40), Some("p. 12"))
+        );
+
+        let target_study = StudyStore::new(&other);
+        let items: Vec<_> = target_study
+            .list()
+            .unwrap()
+            .into_iter()
+            .filter(|i| i.document_id == Some(moved))
+            .collect();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].review_count, 1);
+        let history = target_study
+            .review_history(chrono::NaiveDate::from_ymd_opt(2000, 1, 1).unwrap())
+            .unwrap();
+        assert_eq!(history.iter().map(|d| d.reviews).sum::<usize>(), 1);
+
+        let notes = NoteStore::new(&other).for_document(moved).unwrap();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].content, "Ask about TLBs");
+        assert_eq!(
+            ChunkStore::new(&other)
+                .get_for_document(notes[0].document_id)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Links are re-pointed at the copied rows
+        let new_chunk = ChunkStore::new(&other).get_for_document(moved).unwrap()[0].id;
+        let target_links = LinkStore::new(&other);
+        let from_item = target_links
+            .created_from(KIND_STUDY_ITEM, items[0].id)
+            .unwrap();
+        assert_eq!(from_item.len(), 1);
+        assert_eq!(from_item[0].to_id, new_chunk);
+        let from_note = target_links.created_from(KIND_NOTE, notes[0].id).unwrap();
+        assert_eq!(from_note[0].to_id, new_chunk);
+    }
+
+    #[test]
+    fn test_merge_into() {
+        let db = Database::open_in_memory().unwrap();
+        let store = DocumentStore::new(&db);
+        let study = crate::storage::StudyStore::new(&db);
+
+        let keep = store
+            .insert("/notes/os.pdf", "os.pdf", "pdf", "Paging", Some("os"))
+            .unwrap();
+        let copy = store
+            .insert(
+                "/downloads/os(1).pdf",
+                "os(1).pdf",
+                "pdf",
+                "Paging",
+                Some("exam"),
+            )
+            .unwrap();
+        let item = study
+            .insert(Some(copy), "flashcard", "What is paging?", "Mapping pages")
+            .unwrap();
+
+        let chunks = ChunkStore::new(&db);
+        chunks.init_schema().unwrap();
+        let kept_chunk = chunks.insert(keep, 0, "Paging", None).unwrap();
+        let dup_chunk = chunks.insert(copy, 0, "Paging", None).unwrap();
+        let extra_chunk = chunks.insert(copy, 1, "Only in the copy", None).unwrap();
+        let links = crate::storage::LinkStore::new(&db);
+        links
+            .add(KIND_STUDY_ITEM, item, KIND_CHUNK, dup_chunk)
+            .unwrap();
+        links
+            .add(KIND_STUDY_ITEM, item, KIND_CHUNK, extra_chunk)
+            .unwrap();
+        links.add(KIND_NOTE, 7, KIND_DOCUMENT, copy).unwrap();
+
+        store.merge_into(copy, keep).unwrap();
+
+        // Links follow the text into the kept document, or go with it
+        let from_item = links.created_from(KIND_STUDY_ITEM, item).unwrap();
+        assert_eq!(from_item.len(), 1);
+        assert_eq!(from_item[0].to_id, kept_chunk);
+        assert_eq!(links.created_from(KIND_NOTE, 7).unwrap()[0].to_id, keep);
+
+        assert!(store.get(copy).unwrap().is_none());
+        assert_eq!(
+            store.get(keep).unwrap().unwrap().tags.as_deref(),
+            Some("os,exam")
+        );
+        assert_eq!(study.get(item).unwrap().unwrap().document_id, Some(keep));
+        assert_eq!(
+            store
+                .find_by_path("/downloads/os(1).pdf")
+                .unwrap()
+                .unwrap()
+                .id,
+            keep
+        );
+    }
+
+    #[test]
+    fn test_search_hits() {
+        let db = Database::open_in_memory().unwrap();
+        let store = DocumentStore::new(&db);
+
+        let id = store
+            .insert(
+                "bio.txt",
+                "bio.txt",
+                "text",
+                "Cells need energy. Mitochondria produce ATP through respiration.",
+                None,
+            )
+            .unwrap();
+        store
+            .insert(
+                "history.txt",
+                "history.txt",
+                "text",
+                "The revolution of 1789.",
+                None,
+            )
+            .unwrap();
+
+        let hits = store.search_hits("mitochondria").unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].document.id, id);
+        assert!(hits[0].score > 0.0);
+        assert!(hits[0].snippet.contains("Mitochondria produce ATP"));
+        let matched: Vec<&str> = hits[0]
+            .matches
+            .iter()
+            .map(|m| &hits[0].snippet[m.clone()])
+            .collect();
+        assert_eq!(matched, vec!["Mitochondria"]);
+    }
+
+    #[test]
+    fn test_count_by_type_and_last_activity() {
+        let db = Database::open_in_memory().unwrap();
+        let store = DocumentStore::new(&db);
+        assert!(db.last_activity().unwrap().is_none());
+
+        for (path, kind) in [("a.pdf", "pdf"), ("b.pdf", "pdf"), ("c.txt", "text")] {
+            store.insert(path, path, kind, "content", None).unwrap();
+        }
+
+        assert_eq!(
+            store.count_by_type().unwrap(),
+            vec![("pdf".to_string(), 2), ("text".to_string(), 1)]
+        );
+        let latest = db.last_activity().unwrap().unwrap();
+        assert!((Utc::now() - latest).num_seconds() < 60);
+    }
+}
@@ -0,0 +1,127 @@
+use anyhow::Result;
+use rusqlite::{OptionalExtension, params};
+use sha2::{Digest, Sha256};
+
+use super::Database;
+use crate::embeddings;
+
+/// Embeddings keyed by a hash of the text they were computed from, so text
+/// that was embedded before (re-added documents, sync, reindex) isn't
+/// embedded again. Entries outlive the chunks they came from.
+pub struct EmbeddingCache<'a> {
+    db: &'a Database,
+}
+
+impl<'a> EmbeddingCache<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Initialize the cache table if not exists
+    pub fn init_schema(&self) -> Result<()> {
+        self.db.conn.execute(
+            "CREATE TABLE IF NOT EXISTS embedding_cache (
+                model TEXT NOT NULL,
+                content_hash TEXT NOT NULL,
+                embedding BLOB NOT NULL,
+                PRIMARY KEY (model, content_hash)
+            )",
+            [],
+        )?;
+
+        Ok(())
+    }
+
+    /// Cached embeddings for each text, from the current model (None where
+    /// the text hasn't been embedded yet)
+    pub fn lookup(&self, texts: &[&str]) -> Result<Vec<Option<Vec<f32>>>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT embedding FROM embedding_cache WHERE model = ?1 AND content_hash = ?2",
+        )?;
+
+        texts
+            .iter()
+            .map(|text| {
+                let bytes: Option<Vec<u8>> = stmt
+                    .query_row(params![embeddings::model_id(), text_hash(text)], |row| {
+                        row.get(0)
+                    })
+                    .optional()?;
+                Ok(bytes.map(|b| embeddings::bytes_to_embedding(&b)))
+            })
+            .collect()
+    }
+
+    /// Remember the embedding computed for a text
+    pub fn store(&self, text: &str, embedding: &[f32]) -> Result<()> {
+        self.db.conn.execute(
+            "INSERT OR REPLACE INTO embedding_cache (model, content_hash, embedding)
+             VALUES (?1, ?2, ?3)",
+            params![
+                embeddings::model_id(),
+                text_hash(text),
+                embeddings::embedding_to_bytes(embedding)
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Embed texts, computing (in one batch) only those not already cached
+    pub fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let mut cached = self.lookup(texts)?;
+
+        let missing: Vec<&str> = texts
+            .iter()
+            .zip(&cached)
+            .filter(|(_, hit)| hit.is_none())
+            .map(|(text, _)| *text)
+            .collect();
+
+        if !missing.is_empty() {
+            let mut computed = embeddings::embed_texts(&missing)?.into_iter();
+            for (text, slot) in texts.iter().zip(cached.iter_mut()) {
+                if slot.is_none()
+                    && let Some(embedding) = computed.next()
+                {
+                    self.store(text, &embedding)?;
+                    *slot = Some(embedding);
+                }
+            }
+        }
+
+        Ok(cached.into_iter().flatten().collect())
+    }
+}
+
+/// Cache key for a chunk's text
+fn text_hash(text: &str) -> String {
+    Sha256::digest(text.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embed_reuses_cached_text() {
+        embeddings::use_mock_backend();
+        let db = Database::open_in_memory().unwrap();
+        let cache = EmbeddingCache::new(&db);
+        cache.init_schema().unwrap();
+
+        // A stand-in vector proves the cached value is returned, not recomputed
+        cache.store("seen before", &[1.0, 2.0]).unwrap();
+
+        let vectors = cache.embed(&["seen before", "new text"]).unwrap();
+        assert_eq!(vectors[0], vec![1.0, 2.0]);
+        assert_eq!(vectors[1], embeddings::embed_text("new text").unwrap());
+
+        let hits = cache.lookup(&["new text", "never seen"]).unwrap();
+        assert!(hits[0].is_some());
+        assert!(hits[1].is_none());
+    }
+}
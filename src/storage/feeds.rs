@@ -0,0 +1,141 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::params;
+
+use super::Database;
+
+/// A subscribed RSS or Atom feed
+#[derive(Debug, Clone)]
+pub struct Subscription {
+    pub id: i64,
+    pub url: String,
+    pub title: String,
+    pub last_checked: Option<DateTime<Utc>>,
+}
+
+pub struct FeedStore<'a> {
+    db: &'a Database,
+}
+
+impl<'a> FeedStore<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Subscribe to a feed. Returns None if it's already subscribed.
+    pub fn add(&self, url: &str, title: &str) -> Result<Option<i64>> {
+        let now = Utc::now().to_rfc3339();
+
+        let affected = self
+            .db
+            .conn
+            .execute(
+                "INSERT OR IGNORE INTO feeds (url, title, created_at) VALUES (?1, ?2, ?3)",
+                params![url, title, now],
+            )
+            .context("Failed to add feed")?;
+
+        Ok((affected > 0).then(|| self.db.conn.last_insert_rowid()))
+    }
+
+    /// All subscriptions, oldest first
+    pub fn list(&self) -> Result<Vec<Subscription>> {
+        let mut stmt = self
+            .db
+            .conn
+            .prepare("SELECT id, url, title, last_checked FROM feeds ORDER BY id ASC")?;
+
+        let feeds = stmt
+            .query_map([], |row| {
+                let last_checked: Option<String> = row.get(3)?;
+                Ok(Subscription {
+                    id: row.get(0)?,
+                    url: row.get(1)?,
+                    title: row.get(2)?,
+                    last_checked: last_checked
+                        .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+                        .map(|t| t.with_timezone(&Utc)),
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(feeds)
+    }
+
+    /// Unsubscribe. Documents already added from the feed are kept.
+    pub fn remove(&self, id: i64) -> Result<bool> {
+        let affected = self
+            .db
+            .conn
+            .execute("DELETE FROM feeds WHERE id = ?1", params![id])?;
+
+        Ok(affected > 0)
+    }
+
+    /// Record that the feed was just refreshed
+    pub fn mark_checked(&self, id: i64) -> Result<()> {
+        self.db.conn.execute(
+            "UPDATE feeds SET last_checked = ?1 WHERE id = ?2",
+            params![Utc::now().to_rfc3339(), id],
+        )?;
+
+        Ok(())
+    }
+
+    /// Whether an entry link was already handled for this feed
+    pub fn is_seen(&self, feed_id: i64, link: &str) -> Result<bool> {
+        let seen: bool = self.db.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM feed_entries WHERE feed_id = ?1 AND link = ?2)",
+            params![feed_id, link],
+            |row| row.get(0),
+        )?;
+
+        Ok(seen)
+    }
+
+    /// Remember an entry link so later refreshes skip it
+    pub fn mark_seen(&self, feed_id: i64, link: &str) -> Result<()> {
+        self.db.conn.execute(
+            "INSERT OR IGNORE INTO feed_entries (feed_id, link, seen_at) VALUES (?1, ?2, ?3)",
+            params![feed_id, link, Utc::now().to_rfc3339()],
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscriptions_and_seen_entries() {
+        let db = Database::open_in_memory().unwrap();
+        let store = FeedStore::new(&db);
+
+        let id = store
+            .add("https://example.edu/cs/feed.xml", "CS news")
+            .unwrap()
+            .unwrap();
+        assert!(
+            store
+                .add("https://example.edu/cs/feed.xml", "CS news")
+                .unwrap()
+                .is_none()
+        );
+        assert!(store.list().unwrap()[0].last_checked.is_none());
+
+        assert!(!store.is_seen(id, "https://example.edu/cs/1").unwrap());
+        store.mark_seen(id, "https://example.edu/cs/1").unwrap();
+        store.mark_seen(id, "https://example.edu/cs/1").unwrap();
+        assert!(store.is_seen(id, "https://example.edu/cs/1").unwrap());
+
+        store.mark_checked(id).unwrap();
+        assert!(store.list().unwrap()[0].last_checked.is_some());
+
+        // Unsubscribing forgets the feed's entries too
+        assert!(store.remove(id).unwrap());
+        assert!(store.list().unwrap().is_empty());
+        assert!(!store.is_seen(id, "https://example.edu/cs/1").unwrap());
+    }
+}
@@ -0,0 +1,105 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rusqlite::params;
+
+use super::Database;
+
+/// Kinds of things that can be linked together
+pub const KIND_CHUNK: &str = "chunk";
+pub const KIND_NOTE: &str = "note";
+pub const KIND_STUDY_ITEM: &str = "study_item";
+pub const KIND_DOCUMENT: &str = "document";
+
+/// A directed "created from" link, e.g. a flashcard created from a chunk
+#[derive(Debug, Clone)]
+pub struct Link {
+    #[allow(dead_code)]
+    pub from_kind: String,
+    #[allow(dead_code)]
+    pub from_id: i64,
+    pub to_kind: String,
+    pub to_id: i64,
+}
+
+/// Something that references a document, via one of its chunks
+#[derive(Debug, Clone)]
+pub struct Backlink {
+    pub from_kind: String,
+    pub from_id: i64,
+    pub chunk_index: i64,
+}
+
+pub struct LinkStore<'a> {
+    db: &'a Database,
+}
+
+impl<'a> LinkStore<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Record that `from` was created from `to`
+    pub fn add(&self, from_kind: &str, from_id: i64, to_kind: &str, to_id: i64) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+
+        self.db
+            .conn
+            .execute(
+                "INSERT OR IGNORE INTO links (from_kind, from_id, to_kind, to_id, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![from_kind, from_id, to_kind, to_id, now],
+            )
+            .context("Failed to insert link")?;
+
+        Ok(())
+    }
+
+    /// What an item was created from
+    pub fn created_from(&self, from_kind: &str, from_id: i64) -> Result<Vec<Link>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT from_kind, from_id, to_kind, to_id FROM links
+             WHERE from_kind = ?1 AND from_id = ?2 ORDER BY id ASC",
+        )?;
+
+        let rows = stmt.query_map(params![from_kind, from_id], |row| {
+            Ok(Link {
+                from_kind: row.get(0)?,
+                from_id: row.get(1)?,
+                to_kind: row.get(2)?,
+                to_id: row.get(3)?,
+            })
+        })?;
+
+        let mut links = Vec::new();
+        for link in rows {
+            links.push(link?);
+        }
+
+        Ok(links)
+    }
+
+    /// Everything created from any chunk of a document
+    pub fn referencing_document(&self, document_id: i64) -> Result<Vec<Backlink>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT l.from_kind, l.from_id, c.chunk_index FROM links l
+             JOIN chunks c ON l.to_kind = 'chunk' AND c.id = l.to_id
+             WHERE c.document_id = ?1
+             ORDER BY c.chunk_index ASC, l.id ASC",
+        )?;
+
+        let rows = stmt.query_map(params![document_id], |row| {
+            Ok(Backlink {
+                from_kind: row.get(0)?,
+                from_id: row.get(1)?,
+                chunk_index: row.get(2)?,
+            })
+        })?;
+
+        let mut backlinks = Vec::new();
+        for backlink in rows {
+            backlinks.push(backlink?);
+        }
+
+        Ok(backlinks)
+    }
+}
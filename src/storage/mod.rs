@@ -1,11 +1,30 @@
+pub mod activity;
 pub mod chunks;
+pub mod citations;
+pub mod collections;
 pub mod conversations;
 pub mod db;
 pub mod documents;
+pub mod embedding_cache;
+pub mod feeds;
+pub mod links;
+pub mod notes;
+pub mod plan;
+pub mod reading;
 pub mod study;
+pub mod usage;
 
 pub use chunks::ChunkStore;
+pub use citations::{Citation, CitationStore};
+pub use collections::{Collection, CollectionStore};
 pub use conversations::ConversationStore;
 pub use db::Database;
-pub use documents::{Document, DocumentStore};
-pub use study::StudyStore;
+pub use documents::{Document, DocumentStore, SearchHit};
+pub use embedding_cache::EmbeddingCache;
+pub use feeds::{FeedStore, Subscription};
+pub use links::LinkStore;
+pub use notes::NoteStore;
+pub use plan::PlanStore;
+pub use reading::{ReadingStatus, ReadingStore};
+pub use study::{StudyItem, StudyStore};
+pub use usage::UsageStore;
@@ -0,0 +1,84 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::params;
+
+use super::Database;
+
+/// A personal note. The note text itself lives in `documents` (content_type
+/// "note") so it is searchable and embedded like any other material.
+#[derive(Debug, Clone)]
+pub struct Note {
+    #[allow(dead_code)]
+    pub id: i64,
+    pub document_id: i64,
+    #[allow(dead_code)]
+    pub attached_document_id: Option<i64>,
+    #[allow(dead_code)]
+    pub conversation_id: Option<i64>,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct NoteStore<'a> {
+    db: &'a Database,
+}
+
+impl<'a> NoteStore<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Link a note document to the document or conversation it was written about
+    pub fn insert(
+        &self,
+        document_id: i64,
+        attached_document_id: Option<i64>,
+        conversation_id: Option<i64>,
+    ) -> Result<i64> {
+        let now = Utc::now().to_rfc3339();
+
+        self.db
+            .conn
+            .execute(
+                "INSERT INTO notes (document_id, attached_document_id, conversation_id, created_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![document_id, attached_document_id, conversation_id, now],
+            )
+            .context("Failed to insert note")?;
+
+        Ok(self.db.conn.last_insert_rowid())
+    }
+
+    /// Get notes attached to a document
+    pub fn for_document(&self, attached_document_id: i64) -> Result<Vec<Note>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT n.id, n.document_id, n.attached_document_id, n.conversation_id, d.content, n.created_at
+             FROM notes n JOIN documents d ON d.id = n.document_id
+             WHERE n.attached_document_id = ?1 ORDER BY n.created_at ASC",
+        )?;
+
+        let mut rows = stmt.query(params![attached_document_id])?;
+        let mut notes = Vec::new();
+
+        while let Some(row) = rows.next()? {
+            notes.push(Self::row_to_note(row)?);
+        }
+
+        Ok(notes)
+    }
+
+    fn row_to_note(row: &rusqlite::Row) -> Result<Note> {
+        let created_str: String = row.get(5)?;
+
+        Ok(Note {
+            id: row.get(0)?,
+            document_id: row.get(1)?,
+            attached_document_id: row.get(2)?,
+            conversation_id: row.get(3)?,
+            content: row.get(4)?,
+            created_at: DateTime::parse_from_rfc3339(&created_str)
+                .context("Invalid timestamp")?
+                .with_timezone(&Utc),
+        })
+    }
+}
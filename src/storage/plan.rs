@@ -0,0 +1,116 @@
+use anyhow::Result;
+use chrono::{NaiveDate, Utc};
+use rusqlite::params;
+
+use super::Database;
+
+/// One day's entry in a study plan
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanEntry {
+    pub day: NaiveDate,
+    pub title: String,
+    /// What to do, one task per line
+    pub details: String,
+}
+
+pub struct PlanStore<'a> {
+    db: &'a Database,
+}
+
+impl<'a> PlanStore<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Save a plan for the exam on `exam_date`, replacing any earlier plan
+    /// for the same exam. Returns the number of entries saved.
+    pub fn replace(&self, exam_date: NaiveDate, entries: &[PlanEntry]) -> Result<usize> {
+        let exam_date = exam_date.to_string();
+        let now = Utc::now().to_rfc3339();
+        let tx = self.db.conn.unchecked_transaction()?;
+
+        tx.execute(
+            "DELETE FROM study_plan WHERE exam_date = ?1",
+            params![exam_date],
+        )?;
+        for entry in entries {
+            tx.execute(
+                "INSERT INTO study_plan (day, exam_date, title, details, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    entry.day.to_string(),
+                    exam_date,
+                    entry.title,
+                    entry.details,
+                    now
+                ],
+            )?;
+        }
+        tx.commit()?;
+
+        Ok(entries.len())
+    }
+
+    /// The entries planned for `day`, with the date of the exam each is for
+    pub fn for_day(&self, day: NaiveDate) -> Result<Vec<(PlanEntry, NaiveDate)>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT title, details, exam_date FROM study_plan
+             WHERE day = ?1 ORDER BY exam_date, id",
+        )?;
+
+        let rows = stmt.query_map(params![day.to_string()], |row| {
+            let exam_date: String = row.get(2)?;
+            Ok((
+                PlanEntry {
+                    day,
+                    title: row.get(0)?,
+                    details: row.get(1)?,
+                },
+                exam_date.parse().unwrap_or(day),
+            ))
+        })?;
+
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_replace_plan() {
+        let db = Database::open_in_memory().unwrap();
+        let store = PlanStore::new(&db);
+        let entry = |day: &str, title: &str| PlanEntry {
+            day: date(day),
+            title: title.to_string(),
+            details: "Read chapter 3".to_string(),
+        };
+
+        store
+            .replace(
+                date("2026-05-10"),
+                &[entry("2026-05-01", "Paging"), entry("2026-05-02", "TLBs")],
+            )
+            .unwrap();
+        store
+            .replace(date("2026-06-01"), &[entry("2026-05-01", "Enzymes")])
+            .unwrap();
+        // Planning the same exam again replaces its entries
+        store
+            .replace(date("2026-05-10"), &[entry("2026-05-01", "Scheduling")])
+            .unwrap();
+
+        let today = store.for_day(date("2026-05-01")).unwrap();
+        assert_eq!(today.len(), 2);
+        assert_eq!(today[0].0.title, "Scheduling");
+        assert_eq!(today[0].1, date("2026-05-10"));
+        assert_eq!(today[1].0.title, "Enzymes");
+        assert!(store.for_day(date("2026-05-02")).unwrap().is_empty());
+    }
+}
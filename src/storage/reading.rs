@@ -0,0 +1,216 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use clap::ValueEnum;
+use rusqlite::params;
+
+use super::Database;
+
+/// Where a document sits in the reading queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReadingStatus {
+    Unread,
+    InProgress,
+    Done,
+}
+
+impl ReadingStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReadingStatus::Unread => "unread",
+            ReadingStatus::InProgress => "in-progress",
+            ReadingStatus::Done => "done",
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "in-progress" => ReadingStatus::InProgress,
+            "done" => ReadingStatus::Done,
+            _ => ReadingStatus::Unread,
+        }
+    }
+}
+
+/// A document's reading progress (documents with no entry are unread)
+#[derive(Debug, Clone)]
+pub struct ReadingEntry {
+    pub document_id: i64,
+    pub status: ReadingStatus,
+    /// Percent read (0-100)
+    pub progress: Option<i64>,
+    /// Free-form last position marker, e.g. "p. 42" or "12:30"
+    pub position: Option<String>,
+}
+
+/// Queue totals for the dashboard
+#[derive(Debug, Clone, Default)]
+pub struct QueueCounts {
+    pub unread: i64,
+    pub in_progress: i64,
+    pub done: i64,
+}
+
+pub struct ReadingStore<'a> {
+    db: &'a Database,
+}
+
+impl<'a> ReadingStore<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Set a document's status, keeping any recorded progress
+    pub fn set_status(&self, document_id: i64, status: ReadingStatus) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+
+        self.db
+            .conn
+            .execute(
+                "INSERT INTO reading_queue (document_id, status, updated_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(document_id) DO UPDATE SET status = ?2, updated_at = ?3",
+                params![document_id, status.as_str(), now],
+            )
+            .context("Failed to update reading status")?;
+
+        Ok(())
+    }
+
+    /// Record progress for a document (marks it in-progress, or done at 100%)
+    pub fn set_progress(
+        &self,
+        document_id: i64,
+        progress: Option<u8>,
+        position: Option<&str>,
+    ) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+        let progress = progress.map(|p| p.min(100) as i64);
+        let status = if progress == Some(100) {
+            ReadingStatus::Done
+        } else {
+            ReadingStatus::InProgress
+        };
+
+        self.db
+            .conn
+            .execute(
+                "INSERT INTO reading_queue (document_id, status, progress, position, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT(document_id) DO UPDATE SET
+                    status = ?2,
+                    progress = COALESCE(?3, progress),
+                    position = COALESCE(?4, position),
+                    updated_at = ?5",
+                params![document_id, status.as_str(), progress, position, now],
+            )
+            .context("Failed to update reading progress")?;
+
+        Ok(())
+    }
+
+    /// Get the reading entry for a document
+    pub fn get(&self, document_id: i64) -> Result<Option<ReadingEntry>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT document_id, status, progress, position FROM reading_queue WHERE document_id = ?1",
+        )?;
+
+        let mut rows = stmt.query(params![document_id])?;
+
+        if let Some(row) = rows.next()? {
+            let status: String = row.get(1)?;
+            Ok(Some(ReadingEntry {
+                document_id: row.get(0)?,
+                status: ReadingStatus::parse(&status),
+                progress: row.get(2)?,
+                position: row.get(3)?,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// List queued documents in reading order: in-progress first (most recently
+    /// touched), then unread (oldest first). Notes and generated material are skipped.
+    pub fn queue(&self) -> Result<Vec<ReadingEntry>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT d.id, COALESCE(r.status, 'unread'), r.progress, r.position
+             FROM documents d
+             LEFT JOIN reading_queue r ON r.document_id = d.id
+             WHERE COALESCE(r.status, 'unread') != 'done'
+               AND d.content_type NOT LIKE 'generated-%' AND d.content_type != 'note'
+             ORDER BY CASE WHEN r.status = 'in-progress' THEN 0 ELSE 1 END,
+                      CASE WHEN r.status = 'in-progress' THEN r.updated_at END DESC,
+                      d.created_at ASC",
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            let status: String = row.get(1)?;
+            Ok(ReadingEntry {
+                document_id: row.get(0)?,
+                status: ReadingStatus::parse(&status),
+                progress: row.get(2)?,
+                position: row.get(3)?,
+            })
+        })?;
+
+        let mut entries = Vec::new();
+        for entry in rows {
+            entries.push(entry?);
+        }
+
+        Ok(entries)
+    }
+
+    /// Count documents by reading status
+    pub fn counts(&self) -> Result<QueueCounts> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT COALESCE(r.status, 'unread'), COUNT(*)
+             FROM documents d
+             LEFT JOIN reading_queue r ON r.document_id = d.id
+             WHERE d.content_type NOT LIKE 'generated-%' AND d.content_type != 'note'
+             GROUP BY COALESCE(r.status, 'unread')",
+        )?;
+
+        let mut rows = stmt.query([])?;
+        let mut counts = QueueCounts::default();
+
+        while let Some(row) = rows.next()? {
+            let status: String = row.get(0)?;
+            let count: i64 = row.get(1)?;
+            match ReadingStatus::parse(&status) {
+                ReadingStatus::Unread => counts.unread += count,
+                ReadingStatus::InProgress => counts.in_progress += count,
+                ReadingStatus::Done => counts.done += count,
+            }
+        }
+
+        Ok(counts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{Database, DocumentStore};
+
+    #[test]
+    fn test_queue_order_and_counts() {
+        let db = Database::open_in_memory().unwrap();
+        let docs = DocumentStore::new(&db);
+        let store = ReadingStore::new(&db);
+
+        let a = docs.insert("/a.pdf", "a.pdf", "pdf", "A", None).unwrap();
+        let b = docs.insert("/b.pdf", "b.pdf", "pdf", "B", None).unwrap();
+        let c = docs.insert("/c.pdf", "c.pdf", "pdf", "C", None).unwrap();
+
+        store.set_progress(b, Some(40), Some("p. 12")).unwrap();
+        store.set_status(c, ReadingStatus::Done).unwrap();
+
+        let queue = store.queue().unwrap();
+        let ids: Vec<i64> = queue.iter().map(|e| e.document_id).collect();
+        assert_eq!(ids, vec![b, a]);
+        assert_eq!(queue[0].position.as_deref(), Some("p. 12"));
+
+        let counts = store.counts().unwrap();
+        assert_eq!((counts.unread, counts.in_progress, counts.done), (1, 1, 1));
+    }
+}
@@ -1,29 +1,45 @@
 use anyhow::{Context, Result};
-use chrono::{DateTime, Utc};
-use rusqlite::params;
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use rusqlite::{OptionalExtension, params};
 
 use super::Database;
 
 #[derive(Debug, Clone)]
 pub struct StudyItem {
     pub id: i64,
-    #[allow(dead_code)]
     pub document_id: Option<i64>,
     pub item_type: String,
     pub front: String,
     pub back: String,
-    #[allow(dead_code)]
     pub next_review_date: DateTime<Utc>,
-    #[allow(dead_code)]
     pub interval_days: f64,
-    #[allow(dead_code)]
     pub ease_factor: f64,
-    #[allow(dead_code)]
     pub review_count: i64,
-    #[allow(dead_code)]
     pub created_at: DateTime<Utc>,
     #[allow(dead_code)]
     pub updated_at: DateTime<Utc>,
+    /// Comma-separated tags
+    pub tags: Option<String>,
+}
+
+/// Reviews done on one (local) day
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReviewDay {
+    pub day: NaiveDate,
+    pub reviews: usize,
+    /// Reviews rated 3 or better
+    pub recalled: usize,
+}
+
+/// SQL condition matching every item when `param` is NULL, else the items
+/// carrying exactly that tag, ignoring case (tags are stored comma-joined,
+/// see `normalize_tags`). `instr` rather than LIKE, so `%` and `_` in a tag
+/// aren't wildcards.
+fn tag_filter(param: &str) -> String {
+    format!(
+        "({p} IS NULL OR instr(',' || LOWER(tags) || ',', ',' || LOWER({p}) || ',') > 0)",
+        p = param
+    )
 }
 
 pub struct StudyStore<'a> {
@@ -36,7 +52,6 @@ impl<'a> StudyStore<'a> {
     }
 
     /// Insert a new study item
-    #[allow(dead_code)]
     pub fn insert(
         &self,
         document_id: Option<i64>,
@@ -58,16 +73,68 @@ impl<'a> StudyStore<'a> {
         Ok(self.db.conn.last_insert_rowid())
     }
 
-    /// Get items due for review
-    pub fn get_due(&self, limit: usize) -> Result<Vec<StudyItem>> {
-        let now = Utc::now().to_rfc3339();
+    /// List every study item, oldest first
+    pub fn list(&self) -> Result<Vec<StudyItem>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT id, document_id, item_type, front, back, next_review_date, interval_days, ease_factor, review_count, created_at, updated_at, tags
+             FROM study_items ORDER BY id ASC",
+        )?;
+
+        let mut rows = stmt.query([])?;
+        let mut items = Vec::new();
 
+        while let Some(row) = rows.next()? {
+            items.push(Self::row_to_item(row)?);
+        }
+
+        Ok(items)
+    }
+
+    /// List every item carrying `tag`, oldest first
+    pub fn list_tagged(&self, tag: &str) -> Result<Vec<StudyItem>> {
+        let mut stmt = self.db.conn.prepare(&format!(
+            "SELECT id, document_id, item_type, front, back, next_review_date, interval_days, ease_factor, review_count, created_at, updated_at, tags
+             FROM study_items WHERE {} ORDER BY id ASC",
+            tag_filter("?1")
+        ))?;
+
+        let mut rows = stmt.query(params![tag])?;
+        let mut items = Vec::new();
+
+        while let Some(row) = rows.next()? {
+            items.push(Self::row_to_item(row)?);
+        }
+
+        Ok(items)
+    }
+
+    /// Get a study item by ID
+    pub fn get(&self, id: i64) -> Result<Option<StudyItem>> {
         let mut stmt = self.db.conn.prepare(
-            "SELECT id, document_id, item_type, front, back, next_review_date, interval_days, ease_factor, review_count, created_at, updated_at
-             FROM study_items WHERE next_review_date <= ?1 ORDER BY next_review_date ASC LIMIT ?2",
+            "SELECT id, document_id, item_type, front, back, next_review_date, interval_days, ease_factor, review_count, created_at, updated_at, tags
+             FROM study_items WHERE id = ?1",
         )?;
 
-        let mut rows = stmt.query(params![now, limit as i64])?;
+        let mut rows = stmt.query(params![id])?;
+
+        if let Some(row) = rows.next()? {
+            Ok(Some(Self::row_to_item(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Get items due for review, optionally only those tagged `tag`
+    pub fn get_due(&self, limit: usize, tag: Option<&str>) -> Result<Vec<StudyItem>> {
+        let now = Utc::now().to_rfc3339();
+
+        let mut stmt = self.db.conn.prepare(&format!(
+            "SELECT id, document_id, item_type, front, back, next_review_date, interval_days, ease_factor, review_count, created_at, updated_at, tags
+             FROM study_items WHERE next_review_date <= ?1 AND {} ORDER BY next_review_date ASC LIMIT ?3",
+            tag_filter("?2")
+        ))?;
+
+        let mut rows = stmt.query(params![now, tag, limit as i64])?;
         let mut items = Vec::new();
 
         while let Some(row) = rows.next()? {
@@ -77,17 +144,34 @@ impl<'a> StudyStore<'a> {
         Ok(items)
     }
 
-    /// Count items due for review
-    pub fn count_due(&self) -> Result<i64> {
+    /// Count items due for review, optionally only those tagged `tag`
+    pub fn count_due(&self, tag: Option<&str>) -> Result<i64> {
         let now = Utc::now().to_rfc3339();
         let count: i64 = self.db.conn.query_row(
-            "SELECT COUNT(*) FROM study_items WHERE next_review_date <= ?1",
-            params![now],
+            &format!(
+                "SELECT COUNT(*) FROM study_items WHERE next_review_date <= ?1 AND {}",
+                tag_filter("?2")
+            ),
+            params![now, tag],
             |row| row.get(0),
         )?;
         Ok(count)
     }
 
+    /// When the next item that isn't due yet comes due (None when none are
+    /// waiting)
+    pub fn next_due(&self) -> Result<Option<DateTime<Utc>>> {
+        let next: Option<String> = self.db.conn.query_row(
+            "SELECT MIN(next_review_date) FROM study_items WHERE next_review_date > ?1",
+            params![Utc::now().to_rfc3339()],
+            |row| row.get(0),
+        )?;
+
+        Ok(next
+            .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+            .map(|t| t.with_timezone(&Utc)))
+    }
+
     /// Update item after review using SM-2 algorithm
     /// quality: 0-5 (0-2 = fail/hard, 3 = okay, 4 = good, 5 = easy)
     pub fn update_after_review(&self, id: i64, quality: u8) -> Result<()> {
@@ -125,11 +209,159 @@ impl<'a> StudyStore<'a> {
             "UPDATE study_items SET interval_days = ?1, ease_factor = ?2, review_count = ?3, next_review_date = ?4, updated_at = ?5 WHERE id = ?6",
             params![new_interval, new_ease, new_count, next_str, now, id],
         )?;
+        self.db.conn.execute(
+            "INSERT INTO review_log (item_id, quality, interval_days, ease_factor, reviewed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![id, quality as i64, new_interval, new_ease, now],
+        )?;
 
         Ok(())
     }
 
+    /// Cards coming due on each of the next `days` local days, starting
+    /// today; overdue cards count towards today
+    pub fn due_forecast(&self, days: u32) -> Result<Vec<(NaiveDate, usize)>> {
+        let today = Local::now().date_naive();
+        let mut forecast: Vec<(NaiveDate, usize)> = (0..days)
+            .map(|n| (today + chrono::Duration::days(n as i64), 0))
+            .collect();
+
+        let mut stmt = self
+            .db
+            .conn
+            .prepare("SELECT next_review_date FROM study_items")?;
+        let dates = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        for date in dates {
+            let due = DateTime::parse_from_rfc3339(&date?)
+                .context("Invalid timestamp")?
+                .with_timezone(&Local)
+                .date_naive();
+            let offset = (due - today).num_days().max(0) as usize;
+            if let Some((_, count)) = forecast.get_mut(offset) {
+                *count += 1;
+            }
+        }
+
+        Ok(forecast)
+    }
+
+    /// Reviews per local day since `since`, oldest first (days without
+    /// reviews are left out)
+    pub fn review_history(&self, since: NaiveDate) -> Result<Vec<ReviewDay>> {
+        let mut stmt = self
+            .db
+            .conn
+            .prepare("SELECT quality, reviewed_at FROM review_log ORDER BY reviewed_at ASC")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut history: Vec<ReviewDay> = Vec::new();
+        for row in rows {
+            let (quality, reviewed_at) = row?;
+            let day = DateTime::parse_from_rfc3339(&reviewed_at)
+                .context("Invalid timestamp")?
+                .with_timezone(&Local)
+                .date_naive();
+            if day < since {
+                continue;
+            }
+            if history.last().is_none_or(|d| d.day != day) {
+                history.push(ReviewDay {
+                    day,
+                    reviews: 0,
+                    recalled: 0,
+                });
+            }
+            let entry = history.last_mut().expect("pushed above");
+            entry.reviews += 1;
+            if quality >= 3 {
+                entry.recalled += 1;
+            }
+        }
+
+        Ok(history)
+    }
+
+    /// Number of items and their average ease factor (None with no items)
+    pub fn ease_summary(&self) -> Result<(i64, Option<f64>)> {
+        Ok(self.db.conn.query_row(
+            "SELECT COUNT(*), AVG(ease_factor) FROM study_items",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?)
+    }
+
+    /// Whether an item with exactly this front and back already exists
+    pub fn exists(&self, front: &str, back: &str) -> Result<bool> {
+        Ok(self.find(front, back)?.is_some())
+    }
+
+    /// ID of the item with exactly this front and back, if there is one
+    pub fn find(&self, front: &str, back: &str) -> Result<Option<i64>> {
+        Ok(self
+            .db
+            .conn
+            .query_row(
+                "SELECT id FROM study_items WHERE front = ?1 AND back = ?2 ORDER BY id LIMIT 1",
+                params![front, back],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Overwrite an item's scheduling state (e.g. when importing review history)
+    pub fn set_schedule(
+        &self,
+        id: i64,
+        next_review: DateTime<Utc>,
+        interval_days: f64,
+        ease_factor: f64,
+        review_count: i64,
+    ) -> Result<()> {
+        self.db.conn.execute(
+            "UPDATE study_items SET next_review_date = ?1, interval_days = ?2, ease_factor = ?3, review_count = ?4, updated_at = ?5 WHERE id = ?6",
+            params![
+                next_review.to_rfc3339(),
+                interval_days,
+                ease_factor,
+                review_count,
+                Utc::now().to_rfc3339(),
+                id
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Correct an item's question and answer, keeping its schedule
+    pub fn update_text(&self, id: i64, front: &str, back: &str) -> Result<()> {
+        self.db.conn.execute(
+            "UPDATE study_items SET front = ?1, back = ?2, updated_at = ?3 WHERE id = ?4",
+            params![front, back, Utc::now().to_rfc3339(), id],
+        )?;
+        Ok(())
+    }
+
+    /// Replace an item's comma-separated tags
+    pub fn set_tags(&self, id: i64, tags: Option<&str>) -> Result<()> {
+        self.db.conn.execute(
+            "UPDATE study_items SET tags = ?1, updated_at = ?2 WHERE id = ?3",
+            params![tags, Utc::now().to_rfc3339(), id],
+        )?;
+        Ok(())
+    }
+
+    /// Delete a study item
+    pub fn delete(&self, id: i64) -> Result<()> {
+        self.db
+            .conn
+            .execute("DELETE FROM study_items WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
     /// Bulk insert study items, returns count inserted
+    #[allow(dead_code)]
     pub fn bulk_insert(
         &self,
         items: &[(Option<i64>, &str, &str, &str)], // (document_id, item_type, front, back)
@@ -172,21 +404,29 @@ impl<'a> StudyStore<'a> {
             updated_at: DateTime::parse_from_rfc3339(&updated_str)
                 .context("Invalid timestamp")?
                 .with_timezone(&Utc),
+            tags: row.get(11)?,
         })
     }
 }
 
+/// Normalize typed tags: trimmed, no empties or duplicates, comma-joined
+pub fn normalize_tags(input: &str) -> Option<String> {
+    let mut tags: Vec<&str> = Vec::new();
+    for tag in input.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag);
+        }
+    }
+    (!tags.is_empty()).then(|| tags.join(","))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::storage::Database;
-    use std::path::PathBuf;
 
     fn test_db() -> Database {
-        let path = PathBuf::from(format!("/tmp/librarian_test_{}.db", std::process::id()));
-        // Clean up any previous test DB
-        let _ = std::fs::remove_file(&path);
-        Database::open_at_path(path).unwrap()
+        Database::open_in_memory().unwrap()
     }
 
     #[test]
@@ -217,19 +457,11 @@ mod tests {
         assert_eq!(count, 1);
         assert!(interval >= 1.0);
         assert!(ease >= 2.5);
-
-        // Clean up
-        let _ = std::fs::remove_file(db.path.as_path());
     }
 
     #[test]
     fn test_sm2_fail_resets() {
-        let path = PathBuf::from(format!(
-            "/tmp/librarian_test_fail_{}.db",
-            std::process::id()
-        ));
-        let _ = std::fs::remove_file(&path);
-        let db = Database::open_at_path(path).unwrap();
+        let db = test_db();
         let store = StudyStore::new(&db);
         let id = store.insert(None, "flashcard", "Q", "A").unwrap();
 
@@ -250,8 +482,109 @@ mod tests {
 
         assert_eq!(count, 0);
         assert!((interval - 1.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_edit_tag_and_delete() {
+        let db = test_db();
+        let store = StudyStore::new(&db);
+        let id = store
+            .insert(None, "quiz_short", "Waht is a TLB?", "A cache")
+            .unwrap();
+        store.update_after_review(id, 4).unwrap();
+
+        store
+            .update_text(id, "What is a TLB?", "A page-table cache")
+            .unwrap();
+        store.set_tags(id, Some("paging,os")).unwrap();
+
+        let item = store.get(id).unwrap().unwrap();
+        assert_eq!(item.front, "What is a TLB?");
+        assert_eq!(item.back, "A page-table cache");
+        assert_eq!(item.tags.as_deref(), Some("paging,os"));
+        // Editing keeps the review schedule
+        assert_eq!(item.review_count, 1);
 
-        // Clean up
-        let _ = std::fs::remove_file(db.path.as_path());
+        store.set_tags(id, None).unwrap();
+        assert!(store.get(id).unwrap().unwrap().tags.is_none());
+
+        store.delete(id).unwrap();
+        assert!(store.get(id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_review_log_and_forecast() {
+        let db = test_db();
+        let store = StudyStore::new(&db);
+        let a = store.insert(None, "flashcard", "Q1", "A1").unwrap();
+        let b = store.insert(None, "flashcard", "Q2", "A2").unwrap();
+        store.insert(None, "flashcard", "Q3", "A3").unwrap();
+
+        store.update_after_review(a, 5).unwrap();
+        store.update_after_review(b, 1).unwrap();
+
+        let today = Local::now().date_naive();
+        let history = store.review_history(today).unwrap();
+        assert_eq!(
+            history,
+            vec![ReviewDay {
+                day: today,
+                reviews: 2,
+                recalled: 1
+            }]
+        );
+        assert!(
+            store
+                .review_history(today + chrono::Duration::days(1))
+                .unwrap()
+                .is_empty()
+        );
+
+        // Q3 is due now, Q1 and Q2 come back in a day
+        let forecast = store.due_forecast(30).unwrap();
+        assert_eq!(forecast.len(), 30);
+        assert_eq!(forecast[0], (today, 1));
+        assert_eq!(forecast.iter().map(|(_, n)| n).sum::<usize>(), 3);
+
+        let (count, ease) = store.ease_summary().unwrap();
+        assert_eq!(count, 3);
+        assert!(ease.unwrap() > 1.3);
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        assert_eq!(
+            normalize_tags(" paging, os ,, Paging").as_deref(),
+            Some("paging,os")
+        );
+        assert_eq!(normalize_tags(" , "), None);
+    }
+
+    #[test]
+    fn test_filter_by_tag() {
+        let db = test_db();
+        let store = StudyStore::new(&db);
+        let a = store.insert(None, "flashcard", "Q1", "A1").unwrap();
+        let b = store.insert(None, "flashcard", "Q2", "A2").unwrap();
+        store.insert(None, "flashcard", "Q3", "A3").unwrap();
+        store
+            .set_tags(a, normalize_tags("OS: scheduling, lecture3.pdf").as_deref())
+            .unwrap();
+        store.set_tags(b, Some("OS: scheduling extras")).unwrap();
+
+        assert_eq!(store.count_due(None).unwrap(), 3);
+        assert_eq!(store.count_due(Some("os: Scheduling")).unwrap(), 1);
+        let due = store.get_due(10, Some("lecture3.pdf")).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id, a);
+        assert!(store.get_due(10, Some("missing")).unwrap().is_empty());
+        assert_eq!(store.list_tagged("LECTURE3.pdf").unwrap().len(), 1);
+
+        // LIKE wildcards in a tag are matched literally
+        store.set_tags(b, Some("100%,week_1")).unwrap();
+        assert!(store.list_tagged("1%").unwrap().is_empty());
+        assert!(store.list_tagged("week11").unwrap().is_empty());
+        assert_eq!(store.count_due(Some("week_1")).unwrap(), 1);
+        assert_eq!(store.count_due(Some("100%")).unwrap(), 1);
     }
 }
@@ -0,0 +1,304 @@
+use anyhow::Result;
+use chrono::{Local, Utc};
+use rusqlite::{OptionalExtension, params};
+use std::sync::OnceLock;
+
+use super::Database;
+use crate::llm::Usage;
+
+/// Tokens used with one model on one day
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DailyUsage {
+    pub day: String,
+    pub model: String,
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+/// One call to a model, as logged in the `usage` table
+#[derive(Debug, Clone, Copy)]
+pub struct Call<'a> {
+    /// "chat", "vision" or "transcription"
+    pub kind: &'a str,
+    pub provider: &'a str,
+    pub model: &'a str,
+    /// Token counts, when the provider reported them
+    pub usage: Option<Usage>,
+    pub duration_ms: u64,
+}
+
+/// Totals for one command (`chat`, `generate quiz`, ...)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandUsage {
+    pub command: String,
+    pub requests: u64,
+    pub total_tokens: u64,
+    pub total_ms: u64,
+}
+
+/// Name of the command this run is serving, logged with each call
+static COMMAND: OnceLock<String> = OnceLock::new();
+
+impl DailyUsage {
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+pub struct UsageStore<'a> {
+    db: &'a Database,
+}
+
+impl<'a> UsageStore<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Add a request's tokens to the model's total for `day` (YYYY-MM-DD)
+    pub fn add(&self, day: &str, model: &str, usage: &Usage) -> Result<()> {
+        self.db.conn.execute(
+            "INSERT INTO usage_daily (day, model, requests, prompt_tokens, completion_tokens)
+             VALUES (?1, ?2, 1, ?3, ?4)
+             ON CONFLICT (day, model) DO UPDATE SET
+                requests = requests + 1,
+                prompt_tokens = prompt_tokens + excluded.prompt_tokens,
+                completion_tokens = completion_tokens + excluded.completion_tokens",
+            params![day, model, usage.prompt_tokens, usage.completion_tokens],
+        )?;
+
+        Ok(())
+    }
+
+    /// Log a call and add it to its model's total for today
+    pub fn log(&self, command: &str, call: &Call) -> Result<()> {
+        let usage = call.usage.unwrap_or_default();
+        self.db.conn.execute(
+            "INSERT INTO usage (kind, provider, model, command, prompt_tokens,
+                                completion_tokens, duration_ms, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                call.kind,
+                call.provider,
+                call.model,
+                command,
+                usage.prompt_tokens,
+                usage.completion_tokens,
+                call.duration_ms,
+                Utc::now().to_rfc3339()
+            ],
+        )?;
+        self.add(&today(), call.model, &usage)
+    }
+
+    /// A model's totals for `day` (zero if it wasn't used)
+    pub fn day(&self, day: &str, model: &str) -> Result<DailyUsage> {
+        let usage = self
+            .db
+            .conn
+            .query_row(
+                "SELECT requests, prompt_tokens, completion_tokens FROM usage_daily
+                 WHERE day = ?1 AND model = ?2",
+                params![day, model],
+                |row| {
+                    Ok(DailyUsage {
+                        day: day.to_string(),
+                        model: model.to_string(),
+                        requests: row.get(0)?,
+                        prompt_tokens: row.get(1)?,
+                        completion_tokens: row.get(2)?,
+                    })
+                },
+            )
+            .optional()?;
+
+        Ok(usage.unwrap_or_else(|| DailyUsage {
+            day: day.to_string(),
+            model: model.to_string(),
+            ..Default::default()
+        }))
+    }
+
+    /// Every model's totals for `day`, busiest first
+    pub fn for_day(&self, day: &str) -> Result<Vec<DailyUsage>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT model, requests, prompt_tokens, completion_tokens FROM usage_daily
+             WHERE day = ?1 ORDER BY prompt_tokens + completion_tokens DESC",
+        )?;
+
+        let rows = stmt.query_map(params![day], |row| {
+            Ok(DailyUsage {
+                day: day.to_string(),
+                model: row.get(0)?,
+                requests: row.get(1)?,
+                prompt_tokens: row.get(2)?,
+                completion_tokens: row.get(3)?,
+            })
+        })?;
+
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Totals for each day from `since` (YYYY-MM-DD) on, newest first, with
+    /// every model added together
+    pub fn by_day(&self, since: &str) -> Result<Vec<DailyUsage>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT day, SUM(requests), SUM(prompt_tokens), SUM(completion_tokens)
+             FROM usage_daily WHERE day >= ?1 GROUP BY day ORDER BY day DESC",
+        )?;
+
+        let rows = stmt.query_map(params![since], |row| {
+            Ok(DailyUsage {
+                day: row.get(0)?,
+                model: String::new(),
+                requests: row.get(1)?,
+                prompt_tokens: row.get(2)?,
+                completion_tokens: row.get(3)?,
+            })
+        })?;
+
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Totals for each model from `since` on, busiest first
+    pub fn by_model(&self, since: &str) -> Result<Vec<DailyUsage>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT model, SUM(requests), SUM(prompt_tokens), SUM(completion_tokens)
+             FROM usage_daily WHERE day >= ?1 GROUP BY model
+             ORDER BY SUM(prompt_tokens + completion_tokens) DESC, SUM(requests) DESC",
+        )?;
+
+        let rows = stmt.query_map(params![since], |row| {
+            Ok(DailyUsage {
+                day: since.to_string(),
+                model: row.get(0)?,
+                requests: row.get(1)?,
+                prompt_tokens: row.get(2)?,
+                completion_tokens: row.get(3)?,
+            })
+        })?;
+
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Totals for each command from `since` on (only calls logged since the
+    /// call log was added), busiest first
+    pub fn by_command(&self, since: &str) -> Result<Vec<CommandUsage>> {
+        let mut stmt = self.db.conn.prepare(
+            "SELECT command, COUNT(*), SUM(prompt_tokens + completion_tokens), SUM(duration_ms)
+             FROM usage WHERE date(created_at, 'localtime') >= ?1 GROUP BY command
+             ORDER BY COUNT(*) DESC",
+        )?;
+
+        let rows = stmt.query_map(params![since], |row| {
+            Ok(CommandUsage {
+                command: row.get(0)?,
+                requests: row.get(1)?,
+                total_tokens: row.get(2)?,
+                total_ms: row.get(3)?,
+            })
+        })?;
+
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+/// Today's date as usage is keyed, in local time
+pub fn today() -> String {
+    Local::now().format("%Y-%m-%d").to_string()
+}
+
+/// Open the database usage is kept in (the default one, whatever the bucket)
+pub fn open() -> Result<Database> {
+    Database::open_at_path(Database::default_db_path()?)
+}
+
+/// Name the command this run is serving (e.g. "generate quiz")
+pub fn set_command(command: &str) {
+    let _ = COMMAND.set(command.to_string());
+}
+
+/// Log a call made during this run
+pub fn record(call: &Call) -> Result<()> {
+    let db = open()?;
+    let command = COMMAND.get().map_or("menu", String::as_str);
+    UsageStore::new(&db).log(command, call)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_accumulates_per_day_and_model() {
+        let db = Database::open_in_memory().unwrap();
+        let store = UsageStore::new(&db);
+        let usage = Usage {
+            prompt_tokens: 1000,
+            completion_tokens: 200,
+        };
+
+        store
+            .add("2026-03-01", "llama-3.1-8b-instant", &usage)
+            .unwrap();
+        store
+            .add("2026-03-01", "llama-3.1-8b-instant", &usage)
+            .unwrap();
+        store
+            .add("2026-03-01", "openai/gpt-oss-120b", &usage)
+            .unwrap();
+        store
+            .add("2026-03-02", "llama-3.1-8b-instant", &usage)
+            .unwrap();
+
+        let day = store.day("2026-03-01", "llama-3.1-8b-instant").unwrap();
+        assert_eq!(day.requests, 2);
+        assert_eq!(day.total_tokens(), 2400);
+        assert_eq!(store.for_day("2026-03-01").unwrap().len(), 2);
+        assert_eq!(store.day("2026-03-03", "gemma2-9b-it").unwrap().requests, 0);
+
+        let days = store.by_day("2026-03-01").unwrap();
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].day, "2026-03-02");
+        assert_eq!(days[1].requests, 3);
+        let models = store.by_model("2026-03-02").unwrap();
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].total_tokens(), 1200);
+    }
+
+    #[test]
+    fn test_log_calls_by_command() {
+        let db = Database::open_in_memory().unwrap();
+        let store = UsageStore::new(&db);
+        let chat = Call {
+            kind: "chat",
+            provider: "Groq",
+            model: "llama-3.1-8b-instant",
+            usage: Some(Usage {
+                prompt_tokens: 100,
+                completion_tokens: 50,
+            }),
+            duration_ms: 800,
+        };
+        let transcription = Call {
+            kind: "transcription",
+            provider: "Groq",
+            model: "whisper-large-v3-turbo",
+            usage: None,
+            duration_ms: 3000,
+        };
+
+        store.log("chat", &chat).unwrap();
+        store.log("chat", &chat).unwrap();
+        store.log("add", &transcription).unwrap();
+
+        let commands = store.by_command(&today()).unwrap();
+        assert_eq!(commands[0].command, "chat");
+        assert_eq!(commands[0].requests, 2);
+        assert_eq!(commands[0].total_tokens, 300);
+        assert_eq!(commands[1].total_ms, 3000);
+        // Calls without token counts still count as requests for the day
+        let whisper = store.day(&today(), "whisper-large-v3-turbo").unwrap();
+        assert_eq!(whisper.requests, 1);
+    }
+}